@@ -0,0 +1,85 @@
+use std::path::Path;
+
+use gix::{
+    hash::ObjectId,
+    odb::pack::{self, bitmap},
+    prelude::Find,
+};
+
+/// Write a bitmap for all objects in `index` to `path`, with a bitmap for each commit that isn't the parent of another one.
+pub(crate) fn write(
+    path: &Path,
+    index: &dyn bitmap::Index,
+    ids: impl Iterator<Item = ObjectId>,
+    order: &bitmap::Order,
+    objects: &(impl gix::objs::Find + gix::objs::FindHeader),
+) -> anyhow::Result<()> {
+    let mut commits = gix::hashtable::HashSet::default();
+    let mut parents = gix::hashtable::HashSet::default();
+    let mut buf = Vec::new();
+    for id in ids {
+        let Some(commit) = objects
+            .try_find(&id, &mut buf)
+            .map_err(|err| anyhow::anyhow!(err))?
+            .and_then(gix::objs::Data::try_into_commit_iter)
+        else {
+            continue;
+        };
+        parents.extend(commit.parent_ids());
+        commits.insert(id);
+    }
+    let tips = commits.into_iter().filter(|id| !parents.contains(id));
+
+    let mut out = std::io::BufWriter::new(gix::lock::File::acquire_to_update_resource(
+        path,
+        gix::lock::acquire::Fail::Immediately,
+        None,
+    )?);
+    bitmap::File::write_to(&mut out, tips, index, order, objects, Default::default())?;
+    out.into_inner()?.commit()?;
+    Ok(())
+}
+
+/// Provide access to the objects of a single pack, which isn't necessarily part of an object database.
+pub(crate) struct BundleObjects {
+    bundle: pack::Bundle,
+    inflate: std::cell::RefCell<gix::features::zlib::Inflate>,
+}
+
+impl From<pack::Bundle> for BundleObjects {
+    fn from(bundle: pack::Bundle) -> Self {
+        BundleObjects {
+            bundle,
+            inflate: Default::default(),
+        }
+    }
+}
+
+impl BundleObjects {
+    pub(crate) fn bundle(&self) -> &pack::Bundle {
+        &self.bundle
+    }
+}
+
+impl gix::objs::Find for BundleObjects {
+    fn try_find<'a>(
+        &self,
+        id: &gix::hash::oid,
+        buffer: &'a mut Vec<u8>,
+    ) -> Result<Option<gix::objs::Data<'a>>, gix::objs::find::Error> {
+        self.bundle
+            .find(id, buffer, &mut self.inflate.borrow_mut(), &mut pack::cache::Never)
+            .map(|obj| obj.map(|(data, _location)| data))
+            .map_err(|err| Box::new(err) as _)
+    }
+}
+
+impl gix::objs::FindHeader for BundleObjects {
+    fn try_header(&self, id: &gix::hash::oid) -> Result<Option<gix::objs::Header>, gix::objs::find::Error> {
+        let mut buf = Vec::new();
+        Ok(self.try_find(id, &mut buf)?.map(|data| gix::objs::Header {
+            kind: data.kind,
+            size: data.data.len() as u64,
+        }))
+    }
+}
@@ -62,6 +62,9 @@ pub struct Context<W> {
     /// If true, delta objects may refer to their base as reference, allowing it not to be included in the created back.
     /// Otherwise these have to be recompressed in order to make the pack self-contained.
     pub thin: bool,
+    /// If set, objects which aren't yet in a pack are delta-compressed against the given amount of similar objects, with
+    /// delta chains no longer than the given depth.
+    pub delta_window_and_depth: Option<(usize, usize)>,
     /// If set, don't use more than this amount of threads.
     /// Otherwise, usually use as many threads as there are logical cores.
     /// A value of 0 is interpreted as no-limit
@@ -94,6 +97,7 @@ pub fn create<W, P>(
         expansion,
         nondeterministic_thread_count,
         thin,
+        delta_window_and_depth,
         thread_limit,
         statistics,
         pack_cache_size_in_bytes,
@@ -229,7 +233,12 @@ where
             Box::new(progress),
             pack::data::output::entry::iter_from_counts::Options {
                 thread_limit,
-                mode: pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                mode: match delta_window_and_depth {
+                    Some((window, depth)) => {
+                        pack::data::output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression { window, depth }
+                    }
+                    None => pack::data::output::entry::iter_from_counts::Mode::PackCopyAndBaseObjects,
+                },
                 allow_thin_pack: thin,
                 chunk_size,
                 version: Default::default(),
@@ -319,6 +328,7 @@ fn human_output(
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                delta_compressed_objects,
             },
     }: Statistics,
     mut out: impl std::io::Write,
@@ -339,10 +349,11 @@ fn human_output(
     #[rustfmt::skip]
     writeln!(
         out,
-        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
+        "\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}\n\t{:<width$} {}",
         "decoded and recompressed", decoded_and_recompressed_objects,
         "pack-to-pack copies", objects_copied_from_pack,
         "ref-delta-objects", ref_delta_objects,
+        "delta-compressed objects", delta_compressed_objects,
         "missing objects", missing_objects,
         width = width
     )?;
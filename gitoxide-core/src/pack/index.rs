@@ -1,4 +1,9 @@
-use std::{fs, io, path::PathBuf, str::FromStr, sync::atomic::AtomicBool};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::atomic::AtomicBool,
+};
 
 use gix::{odb::pack, NestedProgress};
 
@@ -51,6 +56,8 @@ pub struct Context<'a, W: io::Write> {
     pub should_interrupt: &'a AtomicBool,
     pub out: W,
     pub object_hash: gix::hash::Kind,
+    /// If `true`, also write a reachability bitmap for the pack, which requires an output directory.
+    pub write_bitmap: bool,
 }
 
 pub fn stream_len(mut s: impl io::Seek) -> io::Result<u64> {
@@ -83,8 +90,12 @@ pub fn from_pack(
         index_version: pack::index::Version::default(),
         object_hash: ctx.object_hash,
     };
+    if ctx.write_bitmap && directory.is_none() {
+        anyhow::bail!("A bitmap can only be written along with the pack and index, which requires an output directory")
+    }
     let out = ctx.out;
     let format = ctx.format;
+    let write_bitmap = ctx.write_bitmap;
     let res = match pack {
         PathOrRead::Path(pack) => {
             let pack_len = pack.metadata()?.len();
@@ -110,6 +121,10 @@ pub fn from_pack(
         ),
     }
     .with_context(|| "Failed to write pack and index")?;
+    if write_bitmap {
+        let index_path = res.index_path.as_deref().expect("written as directory is set");
+        write_pack_bitmap(index_path, res.object_hash).with_context(|| "Failed to write the bitmap")?;
+    }
     match format {
         OutputFormat::Human => drop(human_output(out, res)),
         #[cfg(feature = "serde")]
@@ -118,6 +133,22 @@ pub fn from_pack(
     Ok(())
 }
 
+/// Write a bitmap for the pack of the index at `index_path` with a bitmap for each commit that isn't the parent of another one.
+///
+/// All objects reachable from these commits must be contained in the pack.
+fn write_pack_bitmap(index_path: &Path, object_hash: gix::hash::Kind) -> anyhow::Result<()> {
+    let objects = super::bitmap::BundleObjects::from(pack::Bundle::at(index_path, object_hash)?);
+    let index = &objects.bundle().index;
+    let order = pack::bitmap::Order::from_index(index);
+    super::bitmap::write(
+        &index_path.with_extension("bitmap"),
+        index,
+        index.iter().map(|entry| entry.oid),
+        &order,
+        &objects,
+    )
+}
+
 fn human_output(mut out: impl io::Write, res: pack::bundle::write::Outcome) -> io::Result<()> {
     writeln!(&mut out, "index: {}", res.index.index_hash)?;
     writeln!(&mut out, "pack: {}", res.index.data_hash)
@@ -1,3 +1,4 @@
+mod bitmap;
 pub mod explode;
 pub mod index;
 pub mod multi_index;
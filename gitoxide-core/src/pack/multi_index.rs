@@ -1,6 +1,10 @@
-use std::{io::BufWriter, path::PathBuf, sync::atomic::AtomicBool};
+use std::{
+    io::BufWriter,
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
 
-use anyhow::bail;
+use anyhow::{bail, Context};
 use gix::NestedProgress;
 
 use crate::OutputFormat;
@@ -22,9 +26,10 @@ pub fn create(
     mut progress: impl NestedProgress + 'static,
     should_interrupt: &AtomicBool,
     object_hash: gix::hash::Kind,
+    write_bitmap: bool,
 ) -> anyhow::Result<()> {
     let mut out = BufWriter::new(gix::lock::File::acquire_to_update_resource(
-        output_path,
+        &output_path,
         gix::lock::acquire::Fail::Immediately,
         None,
     )?);
@@ -33,12 +38,41 @@ pub fn create(
         &mut out,
         &mut progress,
         should_interrupt,
-        gix::odb::pack::multi_index::write::Options { object_hash },
+        gix::odb::pack::multi_index::write::Options {
+            object_hash,
+            write_reverse_index: write_bitmap,
+        },
     )?;
     out.into_inner()?.commit()?;
+
+    if write_bitmap {
+        write_multi_index_bitmap(&output_path, object_hash)?;
+    }
     Ok(())
 }
 
+/// Write a bitmap for the multi-pack index at `path` with a bitmap for each commit that isn't the parent of another one.
+fn write_multi_index_bitmap(path: &Path, object_hash: gix::hash::Kind) -> anyhow::Result<()> {
+    let index = gix::odb::pack::multi_index::File::at(path)?;
+    let pack_dir = path.parent().context("multi-pack index must be in a directory")?;
+    let objects = gix::odb::at_opts(
+        pack_dir.parent().unwrap_or(pack_dir),
+        Vec::new(),
+        gix::odb::store::init::Options {
+            object_hash,
+            ..Default::default()
+        },
+    )?;
+    let order = gix::odb::pack::bitmap::Order::from_multi_index(&index)?;
+    super::bitmap::write(
+        &pack_dir.join(format!("multi-pack-index-{}.bitmap", index.checksum())),
+        &index,
+        index.iter().map(|entry| entry.oid),
+        &order,
+        &objects,
+    )
+}
+
 #[cfg(feature = "serde")]
 mod info {
     use std::path::PathBuf;
@@ -1,4 +1,3 @@
-use anyhow::bail;
 use gix::bstr::{BString, ByteSlice};
 
 pub fn log(mut repo: gix::Repository, out: &mut dyn std::io::Write, path: Option<BString>) -> anyhow::Result<()> {
@@ -19,22 +18,38 @@ fn log_all(repo: gix::Repository, out: &mut dyn std::io::Write) -> Result<(), an
     for info in topo {
         let info = info?;
 
-        write_info(&repo, &mut *out, &info)?;
+        write_info(&repo, &mut *out, &info.id)?;
     }
 
     Ok(())
 }
 
-fn log_file(_repo: gix::Repository, _out: &mut dyn std::io::Write, _path: BString) -> anyhow::Result<()> {
-    bail!("File-based lookup isn't yet implemented in a way that is competitively fast");
+fn log_file(repo: gix::Repository, out: &mut dyn std::io::Write, path: BString) -> anyhow::Result<()> {
+    let head = repo.head()?.peel_to_commit()?;
+    let pathspec = repo.pathspec(
+        true,
+        Some(path),
+        false,
+        &gix::index::State::new(repo.object_hash()),
+        gix::worktree::stack::state::attributes::Source::IdMapping,
+    )?;
+    let walk = repo
+        .rev_walk(Some(head.id))
+        .sorting(gix::revision::walk::Sorting::ByCommitTime(Default::default()))
+        .with_pathspec(pathspec)
+        .all()?;
+
+    for info in walk {
+        let info = info?;
+
+        write_info(&repo, &mut *out, &info.id)?;
+    }
+
+    Ok(())
 }
 
-fn write_info(
-    repo: &gix::Repository,
-    mut out: impl std::io::Write,
-    info: &gix::traverse::commit::Info,
-) -> Result<(), std::io::Error> {
-    let commit = repo.find_commit(info.id).unwrap();
+fn write_info(repo: &gix::Repository, mut out: impl std::io::Write, id: &gix::oid) -> Result<(), std::io::Error> {
+    let commit = repo.find_commit(id).unwrap();
 
     let message = commit.message_raw_sloppy();
     let title = message.lines().next();
@@ -42,7 +57,7 @@ fn write_info(
     writeln!(
         out,
         "{} {}",
-        info.id.to_hex_with_len(8),
+        id.to_hex_with_len(8),
         title.map_or_else(|| "<no message>".into(), BString::from)
     )?;
 
@@ -1,4 +1,10 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
 use anyhow::bail;
+use gix::bstr::BStr;
 
 use crate::OutputFormat;
 
@@ -27,3 +33,115 @@ pub fn list(repo: gix::Repository, out: &mut dyn std::io::Write, format: OutputF
     }
     Ok(())
 }
+
+pub mod add {
+    use gix::bstr::BString;
+
+    pub struct Options {
+        pub branch: Option<BString>,
+        pub detach: bool,
+        pub force: bool,
+        pub lock: bool,
+        pub reason: Option<BString>,
+        pub no_checkout: bool,
+    }
+}
+
+pub fn add(
+    repo: gix::Repository,
+    out: &mut dyn std::io::Write,
+    path: &Path,
+    commitish: Option<&BStr>,
+    should_interrupt: &AtomicBool,
+    add::Options {
+        branch,
+        detach,
+        force,
+        lock,
+        reason,
+        no_checkout,
+    }: add::Options,
+) -> anyhow::Result<()> {
+    let proxy = repo.worktree_add(
+        path,
+        commitish,
+        gix::worktree::add::Options {
+            new_branch: branch,
+            detach,
+            force,
+            lock_reason: lock.then(|| reason.unwrap_or_else(|| "added with --lock".into())),
+            no_checkout,
+        },
+        should_interrupt,
+    )?;
+    let worktree_repo = proxy.into_repo_with_possibly_inaccessible_worktree()?;
+    let head = worktree_repo.head()?;
+    match head.referent_name() {
+        Some(name) => writeln!(out, "Preparing worktree (checking out '{}')", name.shorten())?,
+        None => writeln!(
+            out,
+            "Preparing worktree (detached HEAD {})",
+            head.id().map(|id| id.shorten_or_id().to_string()).unwrap_or_default()
+        )?,
+    }
+    Ok(())
+}
+
+pub fn lock(repo: gix::Repository, worktree: &Path, reason: Option<&BStr>) -> anyhow::Result<()> {
+    Ok(find(&repo, worktree)?.lock(reason.unwrap_or_default())?)
+}
+
+pub fn unlock(repo: gix::Repository, worktree: &Path) -> anyhow::Result<()> {
+    Ok(find(&repo, worktree)?.unlock()?)
+}
+
+pub fn move_to(repo: gix::Repository, worktree: &Path, new_path: &Path, force: bool) -> anyhow::Result<()> {
+    find(&repo, worktree)?.move_to(new_path, force)?;
+    Ok(())
+}
+
+pub fn remove(repo: gix::Repository, worktree: &Path, force: u8) -> anyhow::Result<()> {
+    Ok(find(&repo, worktree)?.remove(gix::worktree::proxy::remove::Options {
+        force: force > 0,
+        force_locked: force > 1,
+    })?)
+}
+
+pub fn prune(repo: gix::Repository, out: &mut dyn std::io::Write, dry_run: bool) -> anyhow::Result<()> {
+    for pruned in repo.worktrees_prune(gix::worktree::prune::Options { dry_run, expire: None })? {
+        writeln!(
+            out,
+            "{}Removing worktrees/{}: {}",
+            if dry_run { "WOULD " } else { "" },
+            pruned.id,
+            pruned.reason
+        )?;
+    }
+    Ok(())
+}
+
+pub fn repair(repo: gix::Repository, out: &mut dyn std::io::Write, paths: &[PathBuf]) -> anyhow::Result<()> {
+    for repaired in repo.worktrees_repair(paths)? {
+        let what = match repaired.kind {
+            gix::worktree::repair::Kind::DotGitFile => ".git file",
+            gix::worktree::repair::Kind::GitDirFile => "gitdir",
+        };
+        writeln!(out, "repair: {}: {what} repaired", repaired.path.display())?;
+    }
+    Ok(())
+}
+
+/// Find the linked worktree at `location`, or with `location` as id.
+fn find<'repo>(repo: &'repo gix::Repository, location: &Path) -> anyhow::Result<gix::worktree::Proxy<'repo>> {
+    let location_real = gix::path::realpath(location).ok();
+    for proxy in repo.worktrees()? {
+        if location_real.is_some() && proxy.base().ok().and_then(|base| gix::path::realpath(base).ok()) == location_real
+        {
+            return Ok(proxy);
+        }
+    }
+    gix::path::os_str_into_bstr(location.as_os_str())
+        .ok()
+        .and_then(|id| repo.worktree_proxy_by_id(id))
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not a linked worktree", location.display()))
+}
@@ -51,8 +51,51 @@ pub fn decode(data: &[u8]) -> Result<(Vec, &[u8]), decode::Error> {
     ))
 }
 
+/// Compress `bits` into an EWAH bitmap with `num_bits` bits, where bit `n` is set if `bits[n / 64] & (1 << (n % 64)) != 0`.
+///
+/// Bits at or past `num_bits` must not be set.
+pub fn encode(bits: &[u64], num_bits: u32) -> Vec {
+    let mut out = std::vec::Vec::with_capacity(bits.len() / 2 + 1);
+    let mut rlw;
+    let mut words = bits.iter().copied().peekable();
+    loop {
+        let run_word = words.peek().copied().filter(|w| *w == 0 || *w == u64::MAX);
+        let mut running_len = 0;
+        if let Some(run_word) = run_word {
+            while running_len < RLW_LARGEST_RUNNING_COUNT && words.next_if_eq(&run_word).is_some() {
+                running_len += 1;
+            }
+        }
+        rlw = out.len();
+        out.push(0);
+        let mut literal_words = 0;
+        while literal_words < RLW_LARGEST_LITERAL_COUNT {
+            match words.next_if(|w| *w != 0 && *w != u64::MAX) {
+                Some(word) => {
+                    out.push(word);
+                    literal_words += 1;
+                }
+                None => break,
+            }
+        }
+        out[rlw] = u64::from(run_word == Some(u64::MAX)) | running_len << 1 | literal_words << (1 + RLW_RUNNING_BITS);
+        if words.peek().is_none() {
+            break;
+        }
+    }
+    Vec {
+        num_bits,
+        bits: out,
+        rlw: rlw as u64,
+    }
+}
+
+const RLW_RUNNING_BITS: u64 = 4 * 8;
+const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
+const RLW_LARGEST_LITERAL_COUNT: u64 = (1 << (64 - RLW_RUNNING_BITS - 1)) - 1;
+
 mod access {
-    use super::Vec;
+    use super::{Vec, RLW_LARGEST_RUNNING_COUNT, RLW_RUNNING_BITS};
 
     impl Vec {
         /// Call `f(index)` for each bit that is true, given the index of the bit that identifies it uniquely within the bit array.
@@ -92,6 +135,29 @@ mod access {
         pub fn num_bits(&self) -> usize {
             self.num_bits.try_into().expect("we are not on 16 bit systems")
         }
+
+        /// Decompress all bits, the inverse of [`encode()`](super::encode()).
+        pub fn to_bits(&self) -> std::vec::Vec<u64> {
+            let mut out = std::vec::Vec::with_capacity(self.num_bits().div_ceil(64));
+            let mut iter = self.bits.iter();
+            while let Some(word) = iter.next() {
+                let run = if rlw_runbit_is_set(word) { u64::MAX } else { 0 };
+                out.extend(std::iter::repeat_n(run, rlw_running_len(word) as usize));
+                out.extend(iter.by_ref().take(rlw_literal_words(word) as usize));
+            }
+            out.resize(self.num_bits().div_ceil(64), 0);
+            out
+        }
+
+        /// Write this bitmap to `out` in the format understood by [`decode()`](super::decode()).
+        pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+            out.write_all(&self.num_bits.to_be_bytes())?;
+            out.write_all(&(self.bits.len() as u32).to_be_bytes())?;
+            for word in &self.bits {
+                out.write_all(&word.to_be_bytes())?;
+            }
+            out.write_all(&(self.rlw as u32).to_be_bytes())
+        }
     }
 
     #[inline]
@@ -113,14 +179,11 @@ mod access {
     fn rlw_runbit_is_set(w: &u64) -> bool {
         w & 1 == 1
     }
-
-    const RLW_RUNNING_BITS: u64 = 4 * 8;
-    const RLW_LARGEST_RUNNING_COUNT: u64 = (1 << RLW_RUNNING_BITS) - 1;
 }
 
 /// A growable collection of u64 that are seen as stream of individual bits.
 #[allow(dead_code)]
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Vec {
     num_bits: u32,
     bits: std::vec::Vec<u64>,
@@ -108,6 +108,7 @@ pub fn file(
     let mut out = Vec::new();
     let mut diff_state = gix_diff::tree::State::default();
     let mut previous_entry: Option<(ObjectId, ObjectId)> = None;
+    let mut path_keys = None;
     let mut blame_path = if options.debug_track_path {
         Some(Vec::new())
     } else {
@@ -234,6 +235,19 @@ pub fn file(
             }
         }
 
+        // The changed-path Bloom filter is computed against the first parent, so if it rules out a change,
+        // the first parent has the same entry and takes all the blame, just like it would below.
+        if let Some((parent_id, parent_commit_time)) = parent_ids.first() {
+            if unchanged_according_to_bloom_filter(cache.as_ref(), &suspect, current_file_path.as_ref(), &mut path_keys)
+            {
+                stats.commits_skipped_with_bloom_filter += 1;
+                previous_entry = Some((*parent_id, entry_id));
+                pass_blame_from_to(suspect, *parent_id, &mut hunks_to_blame);
+                queue.insert(*parent_commit_time, *parent_id);
+                continue 'outer;
+            }
+        }
+
         for (pid, (parent_id, parent_commit_time)) in parent_ids.iter().enumerate() {
             if let Some(parent_entry_id) = find_path_entry_in_commit(
                 &odb,
@@ -943,6 +957,36 @@ fn find_path_entry_in_commit(
     Ok(res.map(|e| e.oid))
 }
 
+/// Return `true` if the changed-path Bloom filter of `commit` in `cache` shows that `file_path` definitely wasn't
+/// changed compared to its first parent.
+///
+/// `path_keys` caches the keys to query the filter with, and is updated if `file_path` or the filter settings change.
+fn unchanged_according_to_bloom_filter(
+    cache: Option<&gix_commitgraph::Graph>,
+    commit: &gix_hash::oid,
+    file_path: &BStr,
+    path_keys: &mut Option<(BString, gix_commitgraph::bloom::PathKeys)>,
+) -> bool {
+    let Some(filter) = cache
+        .and_then(|cache| cache.commit_by_id(commit))
+        .and_then(|commit| commit.bloom_filter())
+    else {
+        return false;
+    };
+    let keys = match path_keys {
+        Some((path, keys)) if path == file_path && keys.settings() == filter.settings() => keys,
+        _ => {
+            &path_keys
+                .insert((
+                    file_path.to_owned(),
+                    gix_commitgraph::bloom::PathKeys::new(file_path, filter.settings()),
+                ))
+                .1
+        }
+    };
+    !filter.may_contain_path(keys)
+}
+
 type ParentIds = SmallVec<[(gix_hash::ObjectId, i64); 2]>;
 
 fn collect_parents(
@@ -222,6 +222,9 @@ pub struct Statistics {
     /// The amount of blobs there were compared to each other to learn what changed between commits.
     /// Note that in order to diff a blob, one needs to load both versions from the database.
     pub blobs_diffed: usize,
+    /// The amount of commits that were skipped without decoding any tree as their changed-path Bloom filter
+    /// showed that they didn't change the blamed file.
+    pub commits_skipped_with_bloom_filter: usize,
 }
 
 impl Outcome {
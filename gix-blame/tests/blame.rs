@@ -432,6 +432,78 @@ fn since() -> gix_testtools::Result {
     Ok(())
 }
 
+#[test]
+fn changed_path_bloom_filters_skip_commits() -> gix_testtools::Result {
+    let worktree = gix_testtools::scripted_fixture_writable("make_blame_repo.sh")?;
+    let status = std::process::Command::new("git")
+        .args([
+            "commit-graph",
+            "write",
+            "--no-progress",
+            "--reachable",
+            "--changed-paths",
+        ])
+        .current_dir(worktree.path())
+        .status()?;
+    assert!(status.success());
+    let info_dir = worktree.path().join(".git/objects/info");
+
+    let Fixture {
+        odb,
+        mut resource_cache,
+        suspect,
+    } = Fixture::for_worktree_path(worktree.path().to_path_buf())?;
+    let options = || gix_blame::Options {
+        diff_algorithm: gix_diff::blob::Algorithm::Histogram,
+        ranges: BlameRanges::default(),
+        since: None,
+        rewrites: Some(gix_diff::Rewrites::default()),
+        debug_track_path: false,
+    };
+    for case in [
+        "simple",
+        "sub-directory/sub-directory",
+        "after-rename",
+        "after-rewrite",
+        "resolved-conflict",
+        "file-in-one-chain-of-ancestors",
+        "file-changed-in-two-branches",
+    ] {
+        let source_file_name: gix_object::bstr::BString = format!("{case}.txt").into();
+        let cache = gix_commitgraph::Graph::from_info_dir(&info_dir).map_err(gix_error::Error::from)?;
+        assert!(cache.bloom_filter_settings().is_some());
+        let with_bloom_filters = gix_blame::file(
+            &odb,
+            suspect,
+            Some(cache),
+            &mut resource_cache,
+            source_file_name.as_ref(),
+            options(),
+        )?;
+        let without_bloom_filters = gix_blame::file(
+            &odb,
+            suspect,
+            None,
+            &mut resource_cache,
+            source_file_name.as_ref(),
+            options(),
+        )?;
+
+        let baseline = Baseline::collect(
+            worktree.path().join(".git").join(format!("{case}.baseline")),
+            source_file_name,
+        )?;
+        pretty_assertions::assert_eq!(with_bloom_filters.entries, baseline, "{case}");
+
+        let (with, without) = (with_bloom_filters.statistics, without_bloom_filters.statistics);
+        assert_eq!(without.commits_skipped_with_bloom_filter, 0);
+        assert!(with.commits_skipped_with_bloom_filter > 0, "{case}");
+        assert!(with.trees_decoded < without.trees_decoded, "{case}");
+        assert_eq!(with.commits_traversed, without.commits_traversed, "{case}");
+    }
+    Ok(())
+}
+
 mod blame_ranges {
     use crate::{fixture_path, Baseline, Fixture};
     use gix_blame::BlameRanges;
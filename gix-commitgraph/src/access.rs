@@ -1,4 +1,4 @@
-use crate::{file, file::Commit, File, Graph, Position};
+use crate::{bloom, file, file::Commit, File, Graph, Position};
 
 /// Access
 impl Graph {
@@ -17,6 +17,12 @@ impl Graph {
         Some(r.file.commit_at(r.file_pos))
     }
 
+    /// Returns the settings of the changed-path Bloom filters of the first file that has them, for use
+    /// in creating [keys](bloom::PathKeys) to query [Bloom filters](Commit::bloom_filter()) with.
+    pub fn bloom_filter_settings(&self) -> Option<bloom::Settings> {
+        self.files.iter().find_map(File::bloom_filter_settings)
+    }
+
     /// Returns the `hash` at the given position `pos`.
     ///
     /// # Panics
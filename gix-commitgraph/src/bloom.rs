@@ -0,0 +1,194 @@
+//! Changed-path Bloom filters, stored in the Bloom Filter Index (`BIDX`) and Bloom Filter Data (`BDAT`) chunks.
+//!
+//! Each commit may have a filter containing all paths that changed compared to its first parent, including
+//! all leading directories of these paths. Querying a filter may yield false positives, but never false negatives,
+//! so it's possible to skip diffing commits which certainly didn't change a path.
+use bstr::BStr;
+
+/// The amount of bits in a single unit of a filter, which is a byte.
+const BITS_PER_WORD: u64 = 8;
+const SEED0: u32 = 0x293a_e76f;
+const SEED1: u32 = 0x7e64_6e2c;
+
+/// The settings used to create all Bloom filters in a commit-graph file, as stored in the header of its `BDAT` chunk.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Settings {
+    /// The version of the hash function used to hash paths into keys, either `1` or `2`.
+    ///
+    /// Version `1` sign-extends bytes larger than `0x7f` when hashing, as `git` did on most platforms before the
+    /// issue was corrected in version `2`.
+    pub hash_version: u32,
+    /// The amount of hashes, and thus bits, that are set for each key.
+    pub num_hashes: u32,
+    /// The minimal amount of bits per key that were used to size the filters.
+    pub bits_per_entry: u32,
+}
+
+/// The hash values of some data, for lookup in a [`Filter`] created with the same [`Settings`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    hashes: Vec<u32>,
+}
+
+impl Key {
+    /// Hash `data` into a key according to `settings`.
+    pub fn new(data: &[u8], settings: &Settings) -> Self {
+        let signed = settings.hash_version == 1;
+        let hash0 = murmur3_seeded(SEED0, data, signed);
+        let hash1 = murmur3_seeded(SEED1, data, signed);
+        Key {
+            hashes: (0..settings.num_hashes)
+                .map(|idx| hash0.wrapping_add(idx.wrapping_mul(hash1)))
+                .collect(),
+        }
+    }
+}
+
+/// The keys of a path and all of its leading directories, which is what's needed to learn if a path was changed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathKeys {
+    settings: Settings,
+    keys: Vec<Key>,
+}
+
+impl PathKeys {
+    /// Create the keys for the *slash-separated* `path` according to `settings`.
+    ///
+    /// Trailing slashes are ignored, and an empty `path` leads to no keys, which can't rule out any change.
+    pub fn new(path: &BStr, settings: Settings) -> Self {
+        let mut path: &[u8] = path.as_ref();
+        while let Some(stripped) = path.strip_suffix(b"/") {
+            path = stripped;
+        }
+        let mut keys = Vec::new();
+        if !path.is_empty() {
+            keys.push(Key::new(path, &settings));
+            keys.extend(
+                path.iter()
+                    .enumerate()
+                    .rev()
+                    .filter(|(pos, b)| **b == b'/' && *pos > 0)
+                    .map(|(pos, _)| Key::new(&path[..pos], &settings)),
+            );
+        }
+        PathKeys { settings, keys }
+    }
+
+    /// Return the settings these keys were created with.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Return all keys, the one of the full path first, followed by the ones of its leading directories.
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+}
+
+/// The changed-path Bloom filter of a single commit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Filter<'a> {
+    data: &'a [u8],
+    settings: Settings,
+}
+
+impl<'a> Filter<'a> {
+    pub(crate) fn new(data: &'a [u8], settings: Settings) -> Self {
+        Filter { data, settings }
+    }
+
+    /// Return the raw bits of this filter.
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return the settings this filter was created with.
+    pub fn settings(&self) -> Settings {
+        self.settings
+    }
+
+    /// Return `false` if `key` is definitely not contained in this filter, or `true` if it may be contained.
+    ///
+    /// Note that `key` must have been created with the [settings](Self::settings()) of this filter.
+    pub fn contains(&self, key: &Key) -> bool {
+        let num_bits = self.data.len() as u64 * BITS_PER_WORD;
+        if num_bits == 0 {
+            return true;
+        }
+        key.hashes.iter().all(|hash| {
+            let bit = u64::from(*hash) % num_bits;
+            self.data[(bit / BITS_PER_WORD) as usize] & (1 << (bit % BITS_PER_WORD)) != 0
+        })
+    }
+
+    /// Return `false` if the path that `keys` were created for was definitely not changed in the commit this filter
+    /// belongs to, or `true` if it may have been changed.
+    ///
+    /// If `keys` were created with different settings than this filter, `true` is returned as nothing can be ruled out.
+    pub fn may_contain_path(&self, keys: &PathKeys) -> bool {
+        keys.settings != self.settings || keys.keys.iter().all(|key| self.contains(key))
+    }
+}
+
+/// The seeded murmur3 hash of `data`, as implemented by `git`.
+///
+/// If `signed` is `true`, bytes are sign-extended like in version 1 of `git`s implementation.
+fn murmur3_seeded(mut seed: u32, data: &[u8], signed: bool) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+    const R1: u32 = 15;
+    const R2: u32 = 13;
+    const M: u32 = 5;
+    const N: u32 = 0xe654_6b64;
+
+    let byte = |b: u8| if signed { b as i8 as u32 } else { u32::from(b) };
+    let chunks = data.chunks_exact(4);
+    let tail = chunks.remainder();
+    for chunk in chunks {
+        let k = byte(chunk[0]) | byte(chunk[1]) << 8 | byte(chunk[2]) << 16 | byte(chunk[3]) << 24;
+        let k = k.wrapping_mul(C1).rotate_left(R1).wrapping_mul(C2);
+        seed ^= k;
+        seed = seed.rotate_left(R2).wrapping_mul(M).wrapping_add(N);
+    }
+
+    if !tail.is_empty() {
+        let mut k1 = 0u32;
+        for (idx, b) in tail.iter().enumerate().rev() {
+            k1 ^= byte(*b) << (idx * 8);
+        }
+        k1 = k1.wrapping_mul(C1).rotate_left(R1).wrapping_mul(C2);
+        seed ^= k1;
+    }
+
+    seed ^= data.len() as u32;
+    seed ^= seed >> 16;
+    seed = seed.wrapping_mul(0x85eb_ca6b);
+    seed ^= seed >> 13;
+    seed = seed.wrapping_mul(0xc2b2_ae35);
+    seed ^= seed >> 16;
+    seed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::murmur3_seeded;
+
+    #[test]
+    fn murmur3_matches_reference_values() {
+        for signed in [false, true] {
+            assert_eq!(murmur3_seeded(0, b"", signed), 0);
+            assert_eq!(murmur3_seeded(0, b"Hello world!", signed), 0x627b_0c2c);
+            assert_eq!(
+                murmur3_seeded(0, b"The quick brown fox jumps over the lazy dog", signed),
+                0x2e4f_f723
+            );
+        }
+        let high_bits = b"\x99\xaa\xbb\xcc\xdd\xee\xff";
+        assert_ne!(
+            murmur3_seeded(0, high_bits, false),
+            murmur3_seeded(0, high_bits, true),
+            "version 1 sign-extends bytes with the high bit set"
+        );
+    }
+}
@@ -4,7 +4,8 @@ use std::{
 };
 
 use crate::{
-    file::{self, commit::Commit, COMMIT_DATA_ENTRY_SIZE_SANS_HASH},
+    bloom,
+    file::{self, commit::Commit, COMMIT_DATA_ENTRY_SIZE_SANS_HASH, GENERATION_DATA_OVERFLOW_MASK},
     File,
 };
 
@@ -15,6 +16,39 @@ impl File {
         self.base_graph_count
     }
 
+    /// Return `true` if this file stores corrected commit dates, also known as generation data.
+    pub fn has_generation_data(&self) -> bool {
+        self.generation_data_offset.is_some()
+    }
+
+    /// Return the settings of the changed-path Bloom filters stored in this file, or `None` if there are none.
+    pub fn bloom_filter_settings(&self) -> Option<bloom::Settings> {
+        self.bloom_filter_settings
+    }
+
+    /// Return the changed-path Bloom filter of the commit at the given lexicographical position, or `None`
+    /// if this file has no Bloom filters or the filter wasn't computed for this commit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `pos` is out of bounds.
+    pub fn bloom_filter_at(&self, pos: file::Position) -> Option<bloom::Filter<'_>> {
+        assert!(
+            pos.0 < self.num_commits(),
+            "expected lexicographical position less than {}, got {}",
+            self.num_commits(),
+            pos.0
+        );
+        let settings = self.bloom_filter_settings?;
+        let index = &self.data[self.bloom_filter_index_offset?..];
+        let end_at = |pos: usize| u32::from_be_bytes(index[pos * 4..][..4].try_into().unwrap()) as usize;
+        let pos = pos.0 as usize;
+        let start = if pos == 0 { 0 } else { end_at(pos - 1) };
+        let end = end_at(pos);
+        let filter_data = self.data[self.bloom_filter_data_range.clone()?].get(start..end)?;
+        (!filter_data.is_empty()).then(|| bloom::Filter::new(filter_data, settings))
+    }
+
     /// Returns the commit data for the commit located at the given lexicographical position.
     ///
     /// `pos` must range from 0 to `self.num_commits()`.
@@ -127,6 +161,20 @@ impl File {
         &self.data[start..][..entry_size]
     }
 
+    /// Returns the offset of the corrected commit date for the commit at `pos` from its commit timestamp,
+    /// or `None` if this file has no Generation Data (GDA2) chunk or the offset overflows into a missing
+    /// Generation Data Overflow (GDO2) chunk.
+    pub(crate) fn corrected_commit_date_offset(&self, pos: file::Position) -> Option<u64> {
+        let start = self.generation_data_offset? + pos.0 as usize * 4;
+        let offset = u32::from_be_bytes(self.data[start..][..4].try_into().unwrap());
+        if offset & GENERATION_DATA_OVERFLOW_MASK == 0 {
+            return Some(offset.into());
+        }
+        let overflow = &self.data[self.generation_data_overflow_range.clone()?];
+        let start = (offset & !GENERATION_DATA_OVERFLOW_MASK) as usize * 8;
+        Some(u64::from_be_bytes(overflow.get(start..start + 8)?.try_into().unwrap()))
+    }
+
     /// Returns the byte slice for this file's entire Extra Edge List (EDGE) chunk.
     pub(crate) fn extra_edges_data(&self) -> Option<&[u8]> {
         Some(&self.data[self.extra_edges_list_range.clone()?])
@@ -3,9 +3,12 @@ use std::path::{Path, PathBuf};
 use gix_error::{message, ErrorExt, Exn, Message, ResultExt};
 
 use crate::{
+    bloom,
     file::{
-        BASE_GRAPHS_LIST_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
-        EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, HEADER_LEN, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
+        BASE_GRAPHS_LIST_CHUNK_ID, BLOOM_FILTER_DATA_CHUNK_ID, BLOOM_FILTER_DATA_HEADER_LEN,
+        BLOOM_FILTER_INDEX_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
+        EXTENDED_EDGES_LIST_CHUNK_ID, FAN_LEN, GENERATION_DATA_CHUNK_ID, GENERATION_DATA_OVERFLOW_CHUNK_ID, HEADER_LEN,
+        OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
     },
     File,
 };
@@ -129,6 +132,62 @@ impl File {
             .or_raise(|| message("Error getting offset for OID lookup chunk"))?;
 
         let extra_edges_list_range = chunks.usize_offset_by_id(EXTENDED_EDGES_LIST_CHUNK_ID).ok();
+        let generation_data_offset = chunks
+            .validated_usize_offset_by_id(GENERATION_DATA_CHUNK_ID, |chunk_range| {
+                let chunk_size = chunk_range.len();
+                let expected_size = commit_data_count as usize * 4;
+                if chunk_size != expected_size {
+                    return Err(message!("Commit-graph chunk {GENERATION_DATA_CHUNK_ID:?} has invalid size: expected chunk length {expected_size}, got {chunk_size}").raise())
+                }
+                Ok(chunk_range.start)
+            })
+            .ok()
+            .transpose()?;
+        let generation_data_overflow_range = chunks.usize_offset_by_id(GENERATION_DATA_OVERFLOW_CHUNK_ID).ok();
+
+        let bloom_filter_index_offset = chunks
+            .validated_usize_offset_by_id(BLOOM_FILTER_INDEX_CHUNK_ID, |chunk_range| {
+                let chunk_size = chunk_range.len();
+                let expected_size = commit_data_count as usize * 4;
+                if chunk_size != expected_size {
+                    return Err(message!("Commit-graph chunk {BLOOM_FILTER_INDEX_CHUNK_ID:?} has invalid size: expected chunk length {expected_size}, got {chunk_size}").raise())
+                }
+                Ok(chunk_range.start)
+            })
+            .ok()
+            .transpose()?;
+        let bloom_filter_data_range = chunks
+            .validated_usize_offset_by_id(BLOOM_FILTER_DATA_CHUNK_ID, |chunk_range| {
+                let chunk_size = chunk_range.len();
+                if chunk_size < BLOOM_FILTER_DATA_HEADER_LEN {
+                    return Err(message!("Commit-graph chunk {BLOOM_FILTER_DATA_CHUNK_ID:?} has invalid size: expected at least {BLOOM_FILTER_DATA_HEADER_LEN} bytes, got {chunk_size}").raise())
+                }
+                Ok(chunk_range)
+            })
+            .ok()
+            .transpose()?;
+        // Like `git`, ignore Bloom filters if one of the chunks is missing or if they were created with an unknown hash.
+        let (bloom_filter_index_offset, bloom_filter_data_range, bloom_filter_settings) =
+            match (bloom_filter_index_offset, bloom_filter_data_range) {
+                (Some(index_offset), Some(data_range)) => {
+                    let header = &data[data_range.start..][..BLOOM_FILTER_DATA_HEADER_LEN];
+                    let settings = bloom::Settings {
+                        hash_version: u32::from_be_bytes(header[..4].try_into().unwrap()),
+                        num_hashes: u32::from_be_bytes(header[4..8].try_into().unwrap()),
+                        bits_per_entry: u32::from_be_bytes(header[8..].try_into().unwrap()),
+                    };
+                    if matches!(settings.hash_version, 1 | 2) {
+                        (
+                            Some(index_offset),
+                            Some(data_range.start + BLOOM_FILTER_DATA_HEADER_LEN..data_range.end),
+                            Some(settings),
+                        )
+                    } else {
+                        (None, None, None)
+                    }
+                }
+                _ => (None, None, None),
+            };
 
         let trailer = &data[chunks.highest_offset() as usize..];
         if trailer.len() != object_hash.len_in_bytes() {
@@ -162,9 +221,14 @@ impl File {
         Ok(File {
             base_graph_count,
             base_graphs_list_offset,
+            bloom_filter_index_offset,
+            bloom_filter_data_range,
+            bloom_filter_settings,
             commit_data_offset,
             data,
             extra_edges_list_range,
+            generation_data_offset,
+            generation_data_overflow_range,
             fan,
             oid_lookup_offset,
             path,
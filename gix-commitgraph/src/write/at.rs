@@ -0,0 +1,197 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use gix_error::{message, Exn, Message, ResultExt};
+use gix_lock::tempfile::{create_dir::Retries, AutoRemove, ContainingDirectory};
+
+use crate::{
+    write::{Commit, Options, Outcome, Split},
+    File, Graph,
+};
+
+/// Add `commits` to the commit-graph in the `.git/objects/info` directory at `info_dir`, creating it if needed.
+///
+/// `commits` must contain all ancestors of the given commits that are not yet part of the commit-graph, and those that
+/// are in the commit-graph already are ignored. All commits use hashes of kind `object_hash`.
+/// Commits stored in the commit-graph are retained, and `options` control in which layout the commit-graph is written.
+/// Files that aren't used anymore after the update are removed.
+pub fn at(
+    info_dir: &Path,
+    commits: impl IntoIterator<Item = Commit>,
+    object_hash: gix_hash::Kind,
+    Options { split, generation_data }: Options,
+) -> Result<Outcome, Exn<Message>> {
+    let single_path = info_dir.join("commit-graph");
+    let graphs_dir = info_dir.join("commit-graphs");
+    let chain_path = graphs_dir.join("commit-graph-chain");
+
+    let has_single_file = single_path.is_file();
+    let has_chain = !has_single_file && chain_path.is_file();
+    let mut existing = if has_single_file {
+        vec![File::at(&single_path)?]
+    } else if has_chain {
+        Graph::from_commit_graphs_dir(&graphs_dir)?.files
+    } else {
+        Vec::new()
+    };
+    let mut commits: Vec<_> = commits
+        .into_iter()
+        .filter(|c| !existing.iter().any(|f| f.lookup(c.id).is_some()))
+        .collect();
+
+    let Some(split) = split else {
+        if commits.is_empty() && has_single_file {
+            return Ok(Outcome {
+                checksum: None,
+                num_commits: 0,
+                num_files: 1,
+            });
+        }
+        commits.extend(commits_of(&existing, 0)?);
+        drop(existing);
+        let num_commits = commits.len() as u32;
+        let mut lock =
+            gix_lock::File::acquire_to_update_resource(&single_path, gix_lock::acquire::Fail::Immediately, None)
+                .or_raise(|| message!("Could not lock '{}'", single_path.display()))?;
+        let checksum = {
+            let mut out = std::io::BufWriter::new(&mut lock);
+            let checksum = super::write_to(&mut out, commits, &[], object_hash, generation_data)?;
+            out.flush()
+                .or_raise(|| message!("Could not write '{}'", single_path.display()))?;
+            checksum
+        };
+        lock.commit()
+            .or_raise(|| message!("Could not commit '{}'", single_path.display()))?;
+        if has_chain {
+            remove_chain(&graphs_dir, &chain_path);
+        }
+        return Ok(Outcome {
+            checksum: Some(checksum),
+            num_commits,
+            num_files: 1,
+        });
+    };
+
+    if has_single_file {
+        commits.extend(commits_of(&existing, 0)?);
+        existing.clear();
+    }
+    let num_kept = match split {
+        Split::Replace => 0,
+        Split::NoMerge => existing.len(),
+        Split::Merge {
+            size_multiple,
+            max_commits,
+        } => {
+            let mut num_commits = commits.len() as u64;
+            let mut num_kept = existing.len();
+            while let Some(file) = num_kept.checked_sub(1).map(|idx| &existing[idx]) {
+                let file_commits = u64::from(file.num_commits());
+                if file_commits <= u64::from(size_multiple) * num_commits
+                    || max_commits.is_some_and(|max| num_commits > u64::from(max))
+                {
+                    num_commits += file_commits;
+                    num_kept -= 1;
+                } else {
+                    break;
+                }
+            }
+            num_kept
+        }
+    };
+    if commits.is_empty() && num_kept == existing.len() && !has_single_file {
+        return Ok(Outcome {
+            checksum: None,
+            num_commits: 0,
+            num_files: existing.len(),
+        });
+    }
+    commits.extend(commits_of(&existing, num_kept)?);
+    let merged: Vec<PathBuf> = existing.drain(num_kept..).map(|f| f.path().to_owned()).collect();
+    let base = existing;
+
+    let mut file = gix_lock::tempfile::new(
+        &graphs_dir,
+        ContainingDirectory::CreateAllRaceProof(Retries::default()),
+        AutoRemove::Tempfile,
+    )
+    .or_raise(|| message!("Could not create temporary file in '{}'", graphs_dir.display()))?;
+    let num_commits = commits.len() as u32;
+    let checksum = {
+        let mut out = std::io::BufWriter::new(&mut file);
+        let checksum = super::write_to(&mut out, commits, &base, object_hash, generation_data)?;
+        out.flush()
+            .or_raise(|| message!("Could not write commit-graph file into '{}'", graphs_dir.display()))?;
+        checksum
+    };
+    let new_path = graphs_dir.join(format!("graph-{checksum}.graph"));
+    file.persist(&new_path)
+        .map_err(|err| err.error)
+        .or_raise(|| message!("Could not write '{}'", new_path.display()))?;
+
+    let mut lock = gix_lock::File::acquire_to_update_resource(&chain_path, gix_lock::acquire::Fail::Immediately, None)
+        .or_raise(|| message!("Could not lock '{}'", chain_path.display()))?;
+    for id in base.iter().map(File::checksum).chain(Some(checksum.as_ref())) {
+        writeln!(lock, "{id}").or_raise(|| message!("Could not write '{}'", chain_path.display()))?;
+    }
+    lock.commit()
+        .or_raise(|| message!("Could not commit '{}'", chain_path.display()))?;
+
+    let num_files = base.len() + 1;
+    drop(base);
+    for path in merged.iter().filter(|p| **p != new_path) {
+        std::fs::remove_file(path).ok();
+    }
+    if has_single_file {
+        std::fs::remove_file(&single_path).ok();
+    }
+    Ok(Outcome {
+        checksum: Some(checksum),
+        num_commits,
+        num_files,
+    })
+}
+
+/// Extract all commits in `files[start..]`, whose parents are stored in any of `files`.
+fn commits_of(files: &[File], start: usize) -> Result<Vec<Commit>, Exn<Message>> {
+    let id_at = |mut pos: u32| {
+        for file in files {
+            match pos.checked_sub(file.num_commits()) {
+                Some(remaining) => pos = remaining,
+                None => return Some(file.id_at(crate::file::Position(pos))),
+            }
+        }
+        None
+    };
+    let mut out = Vec::new();
+    for commit in files[start..].iter().flat_map(File::iter_commits) {
+        let parents = commit
+            .iter_parents()
+            .map(|pos| {
+                let pos = pos.map_err(Exn::from)?;
+                id_at(pos.0).map(ToOwned::to_owned).ok_or_else(|| {
+                    message!("Parent of commit {} is out of bounds at position {pos}", commit.id()).into()
+                })
+            })
+            .collect::<Result<_, Exn<Message>>>()?;
+        out.push(Commit {
+            id: commit.id().to_owned(),
+            tree: commit.root_tree_id().to_owned(),
+            parents,
+            commit_time: commit.committer_timestamp(),
+        });
+    }
+    Ok(out)
+}
+
+/// Remove the commit-graph chain file at `chain_path` along with all files it lists in `graphs_dir`.
+fn remove_chain(graphs_dir: &Path, chain_path: &Path) {
+    if let Ok(chain) = std::fs::read_to_string(chain_path) {
+        for hash in chain.lines() {
+            std::fs::remove_file(graphs_dir.join(format!("graph-{hash}.graph"))).ok();
+        }
+    }
+    std::fs::remove_file(chain_path).ok();
+}
@@ -0,0 +1,283 @@
+use std::io::Write;
+
+use gix_error::{message, ErrorExt, Exn, Message, ResultExt};
+use gix_hash::ObjectId;
+
+use crate::{
+    file::{
+        BASE_GRAPHS_LIST_CHUNK_ID, COMMIT_DATA_CHUNK_ID, COMMIT_DATA_ENTRY_SIZE_SANS_HASH,
+        EXTENDED_EDGES_LIST_CHUNK_ID, EXTENDED_EDGES_MASK, FAN_LEN, GENERATION_DATA_CHUNK_ID,
+        GENERATION_DATA_OVERFLOW_CHUNK_ID, GENERATION_DATA_OVERFLOW_MASK, HEADER_LEN, LAST_EXTENDED_EDGE_MASK,
+        NO_PARENT, OID_FAN_CHUNK_ID, OID_LOOKUP_CHUNK_ID, SIGNATURE,
+    },
+    write::Commit,
+    File, GENERATION_NUMBER_MAX, MAX_COMMITS,
+};
+
+/// The largest corrected commit date offset that can be stored without using the overflow chunk.
+const GENERATION_DATA_OFFSET_MAX: u64 = (1 << 31) - 1;
+
+/// Write a commit-graph file with `commits` to `out` and return its checksum.
+///
+/// `base` are the files of the chain the new file is put on top of, with the bottom-most file first, or empty
+/// to write a standalone file. All parents of `commits` must either be contained in `commits` or in `base`, and
+/// `commits` that are contained in `base` already are ignored.
+/// If `generation_data` is `true`, corrected commit dates will be written, but only if all `base` files have them as well.
+/// `object_hash` is the kind of hash used by all `commits`.
+pub fn write_to(
+    out: &mut dyn std::io::Write,
+    mut commits: Vec<Commit>,
+    base: &[File],
+    object_hash: gix_hash::Kind,
+    generation_data: bool,
+) -> Result<ObjectId, Exn<Message>> {
+    commits.retain(|c| !base.iter().any(|f| f.lookup(c.id).is_some()));
+    commits.sort_by_key(|c| c.id);
+    commits.dedup_by(|a, b| a.id == b.id);
+
+    let num_base_commits: u64 = base.iter().map(|f| u64::from(f.num_commits())).sum();
+    if num_base_commits + commits.len() as u64 > u64::from(MAX_COMMITS) {
+        return Err(message!(
+            "Cannot write more than {MAX_COMMITS} commits into a commit-graph, got {}",
+            num_base_commits + commits.len() as u64
+        )
+        .raise());
+    }
+    let base_graph_count: u8 = base
+        .len()
+        .try_into()
+        .map_err(|_| message!("Cannot write a commit-graph with more than 255 base graphs").raise())?;
+    let num_base_commits = num_base_commits as u32;
+    let generation_data = generation_data && base.iter().all(File::has_generation_data);
+
+    let parents = commits
+        .iter()
+        .map(|commit| {
+            commit
+                .parents
+                .iter()
+                .map(|parent| {
+                    lookup(parent, &commits, base, num_base_commits).ok_or_else(|| {
+                        message!(
+                            "Parent {parent} of commit {} is not contained in the commit-graph",
+                            commit.id
+                        )
+                        .raise()
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let generations = compute_generations(&commits, &parents, base, num_base_commits);
+
+    let num_extra_edges: usize = parents.iter().filter(|p| p.len() > 2).map(|p| p.len() - 1).sum();
+    let num_generation_data_overflows = generations
+        .iter()
+        .zip(&commits)
+        .filter(|(g, c)| g.corrected_commit_date - c.commit_time > GENERATION_DATA_OFFSET_MAX)
+        .count();
+
+    let hash_len = object_hash.len_in_bytes();
+    let mut cf = gix_chunk::file::Index::for_writing();
+    cf.plan_chunk(OID_FAN_CHUNK_ID, (FAN_LEN * 4) as u64);
+    cf.plan_chunk(OID_LOOKUP_CHUNK_ID, (commits.len() * hash_len) as u64);
+    cf.plan_chunk(
+        COMMIT_DATA_CHUNK_ID,
+        (commits.len() * (hash_len + COMMIT_DATA_ENTRY_SIZE_SANS_HASH)) as u64,
+    );
+    if generation_data {
+        cf.plan_chunk(GENERATION_DATA_CHUNK_ID, (commits.len() * 4) as u64);
+        if num_generation_data_overflows > 0 {
+            cf.plan_chunk(
+                GENERATION_DATA_OVERFLOW_CHUNK_ID,
+                (num_generation_data_overflows * 8) as u64,
+            );
+        }
+    }
+    if num_extra_edges > 0 {
+        cf.plan_chunk(EXTENDED_EDGES_LIST_CHUNK_ID, (num_extra_edges * 4) as u64);
+    }
+    if !base.is_empty() {
+        cf.plan_chunk(BASE_GRAPHS_LIST_CHUNK_ID, (base.len() * hash_len) as u64);
+    }
+
+    let mut out = gix_hash::io::Write::new(out, object_hash);
+    let write = || -> std::io::Result<()> {
+        out.write_all(SIGNATURE)?;
+        out.write_all(&[1 /* version */, object_hash as u8])?;
+        out.write_all(&[cf.num_chunks() as u8, base_graph_count])?;
+        let mut chunk_write = cf.into_write(&mut out, HEADER_LEN)?;
+        while let Some(chunk_to_write) = chunk_write.next_chunk() {
+            match chunk_to_write {
+                OID_FAN_CHUNK_ID => {
+                    let mut count = 0u32;
+                    let mut ids = commits.iter().peekable();
+                    for byte in 0..FAN_LEN {
+                        while ids.next_if(|c| usize::from(c.id.first_byte()) == byte).is_some() {
+                            count += 1;
+                        }
+                        chunk_write.write_all(&count.to_be_bytes())?;
+                    }
+                }
+                OID_LOOKUP_CHUNK_ID => {
+                    for commit in &commits {
+                        chunk_write.write_all(commit.id.as_slice())?;
+                    }
+                }
+                COMMIT_DATA_CHUNK_ID => {
+                    let mut num_extra_edges = 0u32;
+                    for ((commit, parents), generation) in commits.iter().zip(&parents).zip(&generations) {
+                        chunk_write.write_all(commit.tree.as_slice())?;
+                        let parent1 = parents.first().copied().unwrap_or(NO_PARENT);
+                        let parent2 = match parents.len() {
+                            0 | 1 => NO_PARENT,
+                            2 => parents[1],
+                            num_parents => {
+                                let edge = EXTENDED_EDGES_MASK | num_extra_edges;
+                                num_extra_edges += num_parents as u32 - 1;
+                                edge
+                            }
+                        };
+                        chunk_write.write_all(&parent1.to_be_bytes())?;
+                        chunk_write.write_all(&parent2.to_be_bytes())?;
+                        let time_high = ((commit.commit_time >> 32) & 0x3) as u32;
+                        chunk_write.write_all(&((generation.topological_level << 2) | time_high).to_be_bytes())?;
+                        chunk_write.write_all(&(commit.commit_time as u32).to_be_bytes())?;
+                    }
+                }
+                GENERATION_DATA_CHUNK_ID => {
+                    let mut num_overflows = 0u32;
+                    for (commit, generation) in commits.iter().zip(&generations) {
+                        let mut offset = generation.corrected_commit_date - commit.commit_time;
+                        if offset > GENERATION_DATA_OFFSET_MAX {
+                            offset = u64::from(GENERATION_DATA_OVERFLOW_MASK | num_overflows);
+                            num_overflows += 1;
+                        }
+                        chunk_write.write_all(&(offset as u32).to_be_bytes())?;
+                    }
+                }
+                GENERATION_DATA_OVERFLOW_CHUNK_ID => {
+                    for (commit, generation) in commits.iter().zip(&generations) {
+                        let offset = generation.corrected_commit_date - commit.commit_time;
+                        if offset > GENERATION_DATA_OFFSET_MAX {
+                            chunk_write.write_all(&offset.to_be_bytes())?;
+                        }
+                    }
+                }
+                EXTENDED_EDGES_LIST_CHUNK_ID => {
+                    for parents in parents.iter().filter(|p| p.len() > 2) {
+                        let (last, rest) = parents[1..].split_last().expect("at least two extra parents");
+                        for parent in rest {
+                            chunk_write.write_all(&parent.to_be_bytes())?;
+                        }
+                        chunk_write.write_all(&(LAST_EXTENDED_EDGE_MASK | last).to_be_bytes())?;
+                    }
+                }
+                BASE_GRAPHS_LIST_CHUNK_ID => {
+                    for file in base {
+                        chunk_write.write_all(file.checksum().as_bytes())?;
+                    }
+                }
+                unknown => unreachable!("BUG: forgot to implement chunk {:?}", std::str::from_utf8(&unknown)),
+            }
+        }
+        Ok(())
+    };
+    write().or_raise(|| message("Could not write commit-graph"))?;
+    let checksum = out
+        .hash
+        .try_finalize()
+        .or_raise(|| message("Could not compute commit-graph checksum"))?;
+    out.inner
+        .write_all(checksum.as_slice())
+        .or_raise(|| message("Could not write commit-graph checksum"))?;
+    Ok(checksum)
+}
+
+/// Return the graph position of `id`, which is either contained in `commits` (placed after all `base` commits) or in `base`.
+fn lookup(id: &gix_hash::oid, commits: &[Commit], base: &[File], num_base_commits: u32) -> Option<u32> {
+    if let Ok(idx) = commits.binary_search_by(|c| c.id.as_ref().cmp(id)) {
+        return Some(num_base_commits + idx as u32);
+    }
+    let mut file_start = 0;
+    for file in base {
+        if let Some(pos) = file.lookup(id) {
+            return Some(file_start + pos.0);
+        }
+        file_start += file.num_commits();
+    }
+    None
+}
+
+#[derive(Clone, Copy)]
+struct Generation {
+    topological_level: u32,
+    corrected_commit_date: u64,
+}
+
+/// Compute the generation numbers of all `commits`, whose graph positions of `parents` are known.
+fn compute_generations(
+    commits: &[Commit],
+    parents: &[Vec<u32>],
+    base: &[File],
+    num_base_commits: u32,
+) -> Vec<Generation> {
+    let mut out: Vec<Option<Generation>> = vec![None; commits.len()];
+    let mut stack = Vec::new();
+    for idx in 0..commits.len() {
+        if out[idx].is_some() {
+            continue;
+        }
+        stack.push(idx);
+        'next_commit: while let Some(&idx) = stack.last() {
+            if out[idx].is_some() {
+                stack.pop();
+                continue;
+            }
+            let mut max_level = 0;
+            let mut max_corrected_commit_date = 0;
+            for &parent in &parents[idx] {
+                let parent_generation = match parent.checked_sub(num_base_commits) {
+                    Some(parent_idx) => match out[parent_idx as usize] {
+                        Some(generation) => generation,
+                        None => {
+                            stack.push(parent_idx as usize);
+                            continue 'next_commit;
+                        }
+                    },
+                    None => base_generation(base, parent),
+                };
+                max_level = max_level.max(parent_generation.topological_level);
+                max_corrected_commit_date = max_corrected_commit_date.max(parent_generation.corrected_commit_date);
+            }
+            stack.pop();
+            // Mirror git, which never assigns a corrected commit date of 0.
+            let commit_time = commits[idx].commit_time;
+            if commit_time > max_corrected_commit_date {
+                max_corrected_commit_date = commit_time - 1;
+            }
+            out[idx] = Some(Generation {
+                topological_level: (max_level + 1).min(GENERATION_NUMBER_MAX),
+                corrected_commit_date: max_corrected_commit_date + 1,
+            });
+        }
+    }
+    out.into_iter().map(|g| g.expect("all computed")).collect()
+}
+
+/// Return the generation of the commit at graph position `pos` in `base`.
+fn base_generation(base: &[File], mut pos: u32) -> Generation {
+    for file in base {
+        match pos.checked_sub(file.num_commits()) {
+            Some(remaining) => pos = remaining,
+            None => {
+                let commit = file.commit_at(crate::file::Position(pos));
+                return Generation {
+                    topological_level: commit.generation(),
+                    corrected_commit_date: commit.corrected_commit_date().unwrap_or_default(),
+                };
+            }
+        }
+    }
+    unreachable!("BUG: base positions are always valid")
+}
@@ -0,0 +1,90 @@
+//! Write commit-graph files, either as single file or as chain of files that can be updated incrementally.
+use gix_hash::ObjectId;
+
+mod at;
+pub use at::at;
+
+mod file;
+pub use file::write_to;
+
+/// The data of a commit necessary to store it in a commit-graph file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Commit {
+    /// The id of the commit.
+    pub id: ObjectId,
+    /// The id of the tree the commit points to.
+    pub tree: ObjectId,
+    /// The ids of all parents of the commit, in order.
+    pub parents: Vec<ObjectId>,
+    /// The committer timestamp in seconds since the unix epoch.
+    pub commit_time: u64,
+}
+
+/// Determine how new commits are added to an existing commit-graph chain in [`at()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Split {
+    /// Write the new commits into a new file on top of the chain, and merge it with the files below
+    /// as long as these aren't larger than `size_multiple` times the amount of commits of the merged files.
+    ///
+    /// This is the equivalent of `git commit-graph write --split`.
+    Merge {
+        /// Merge a file into the new one if it has at most this many times the amount of commits of the new file.
+        size_multiple: u32,
+        /// If set, also merge as long as the new file would have more than this amount of commits.
+        max_commits: Option<u32>,
+    },
+    /// Write the new commits into a new file on top of the chain, without merging any files.
+    ///
+    /// This is the equivalent of `git commit-graph write --split=no-merge`.
+    NoMerge,
+    /// Write all commits, old and new, into a chain with a single file.
+    ///
+    /// This is the equivalent of `git commit-graph write --split=replace`.
+    Replace,
+}
+
+impl Default for Split {
+    fn default() -> Self {
+        Split::Merge {
+            size_multiple: 2,
+            max_commits: None,
+        }
+    }
+}
+
+/// Options for use in [`at()`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Options {
+    /// If `None`, all commits are written into the single `commit-graph` file, removing the chain of files if present.
+    /// Otherwise, write commits into a chain of files in the `commit-graphs` directory as configured, removing the
+    /// single `commit-graph` file if present.
+    pub split: Option<Split>,
+    /// If `true`, write corrected commit dates, which are also known as generation data or generation numbers v2.
+    ///
+    /// Note that these are only written if all files below a new file in a chain have them as well.
+    /// This corresponds to `commitGraph.generationVersion = 2`, the default in `git`.
+    pub generation_data: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            split: None,
+            generation_data: true,
+        }
+    }
+}
+
+/// The result of [`at()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The checksum of the newly written file, or `None` if there was nothing to write.
+    pub checksum: Option<ObjectId>,
+    /// The amount of commits in the newly written file.
+    pub num_commits: u32,
+    /// The amount of files in the commit-graph after writing, which is 1 for the single `commit-graph` file.
+    pub num_files: usize,
+}
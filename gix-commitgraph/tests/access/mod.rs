@@ -1,3 +1,5 @@
+use gix_commitgraph::bloom::{PathKeys, Settings};
+
 use crate::{check_common, graph_and_expected, graph_and_expected_named};
 
 #[test]
@@ -50,6 +52,11 @@ fn single_commit_future_64bit_dates_work() {
         "this is close the highest representable value in the graph, like year 2500, so we are good for longer than I should care about"
     );
     assert_eq!(actual.generation(), 1);
+    assert_eq!(
+        actual.corrected_commit_date(),
+        None,
+        "generation data isn't written in V1"
+    );
 }
 
 #[test]
@@ -69,6 +76,21 @@ fn generation_numbers_overflow_is_handled_in_chained_graph() {
     }
 }
 
+#[test]
+fn corrected_commit_dates_overflow_is_handled_in_chained_graph() {
+    let names = ["future-1", "old-1", "future-2", "old-2", "extra"];
+    let (cg, refs) = graph_and_expected("generation_number_overflow.sh", &names);
+    let future_date = 4147483646;
+    for (offset, name) in names.iter().enumerate() {
+        let commit = cg.commit_by_id(refs[*name].id()).expect("present");
+        assert_eq!(
+            commit.corrected_commit_date(),
+            Some(future_date + offset as u64),
+            "{name}: commits with dates in the past are corrected to be younger than their parents"
+        );
+    }
+}
+
 #[test]
 fn octopus_merges() {
     let (cg, refs) = graph_and_expected(
@@ -111,3 +133,80 @@ fn two_parents() {
     assert_eq!(cg.commit_at(refs["parent2"].pos()).generation(), 1);
     assert_eq!(cg.commit_at(refs["child"].pos()).generation(), 2);
 }
+
+#[test]
+fn changed_path_bloom_filters() {
+    let (cg, refs) = graph_and_expected(
+        "changed_paths.sh",
+        &[
+            "initial",
+            "change-a",
+            "change-c",
+            "change-umlaut",
+            "no-change",
+            "too-many-changes",
+        ],
+    );
+    check_common(&cg, &refs);
+
+    let settings = cg.bloom_filter_settings().expect("written with --changed-paths");
+    assert_eq!(
+        settings,
+        Settings {
+            hash_version: 1,
+            num_hashes: 7,
+            bits_per_entry: 10
+        },
+        "the defaults of git"
+    );
+    let filter = |name: &str| {
+        cg.commit_at(refs[name].pos())
+            .bloom_filter()
+            .expect("filters are computed for all commits")
+    };
+    let may_contain = |name: &str, path: &str| filter(name).may_contain_path(&PathKeys::new(path.into(), settings));
+
+    for (name, changed, unchanged) in [
+        (
+            "initial",
+            &["a", "dir", "dir/", "dir/b", "dir/sub", "dir/sub/c", "dir/ümlaut"][..],
+            &["b", "many", "dir/c"][..],
+        ),
+        ("change-a", &["a"], &["dir", "dir/b", "dir/sub/c", "dir/ümlaut"]),
+        (
+            "change-c",
+            &["dir", "dir/sub", "dir/sub/c"],
+            &["a", "dir/b", "dir/ümlaut", "dir/sub/d"],
+        ),
+        (
+            "change-umlaut",
+            &["dir", "dir/ümlaut"],
+            &["a", "dir/sub", "dir/sub/c", "dir/umlaut"],
+        ),
+        ("no-change", &[], &["a", "dir", "dir/ümlaut"]),
+    ] {
+        for path in changed {
+            assert!(may_contain(name, path), "{name}: {path} was changed");
+        }
+        for path in unchanged {
+            assert!(!may_contain(name, path), "{name}: {path} was not changed");
+        }
+    }
+    assert_eq!(filter("no-change").data(), [0], "empty filters have a single byte");
+    assert_eq!(
+        filter("too-many-changes").data(),
+        [0xff],
+        "filters with too many changes contain everything"
+    );
+    assert!(may_contain("too-many-changes", "a"));
+    assert!(
+        filter("change-a").may_contain_path(&PathKeys::new(
+            "dir".into(),
+            Settings {
+                hash_version: 2,
+                ..settings
+            }
+        )),
+        "keys with different settings can't rule anything out"
+    );
+}
@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git config core.quotePath false
+
+mkdir -p dir/sub
+echo a >a && echo b >dir/b && echo c >dir/sub/c && echo u >"dir/ümlaut"
+git add . && git commit -q -m initial && git tag initial
+
+echo a2 >a && git commit -q -am "change a" && git tag change-a
+echo c2 >dir/sub/c && git commit -q -am "change dir/sub/c" && git tag change-c
+echo u2 >"dir/ümlaut" && git commit -q -am "change dir/ümlaut" && git tag change-umlaut
+git commit -q --allow-empty -m "no change" && git tag no-change
+
+mkdir many
+for i in $(seq 600); do echo $i >many/$i; done
+git add many && git commit -q -m "too many changes" && git tag too-many-changes
+
+git commit-graph write --no-progress --reachable --changed-paths
+git repack -adq
@@ -0,0 +1,181 @@
+use std::path::{Path, PathBuf};
+
+use gix_commitgraph::{write, File, Graph};
+use gix_testtools::scripted_fixture_read_only;
+
+use gix_testtools::Result;
+
+/// Return the paths of all commit-graph files in `info_dir`, with the bottom-most file of a chain first.
+fn graph_files(info_dir: &Path) -> Result<Vec<PathBuf>> {
+    let single = info_dir.join("commit-graph");
+    if single.is_file() {
+        return Ok(vec![single]);
+    }
+    let graphs_dir = info_dir.join("commit-graphs");
+    Ok(std::fs::read_to_string(graphs_dir.join("commit-graph-chain"))?
+        .lines()
+        .map(|hash| graphs_dir.join(format!("graph-{hash}.graph")))
+        .collect())
+}
+
+fn commits_of(graph: &Graph) -> Vec<write::Commit> {
+    let mut out: Vec<_> = graph
+        .iter_commits()
+        .map(|commit| write::Commit {
+            id: commit.id().to_owned(),
+            tree: commit.root_tree_id().to_owned(),
+            parents: commit
+                .iter_parents()
+                .map(|pos| graph.id_at(pos.expect("valid parent")).to_owned())
+                .collect(),
+            commit_time: commit.committer_timestamp(),
+        })
+        .collect();
+    out.sort_by_key(|c| graph.commit_by_id(c.id).expect("present").generation());
+    out
+}
+
+fn assert_files_reproduced(info_dir: &Path) -> Result {
+    let paths = graph_files(info_dir)?;
+    let graph = Graph::from_info_dir(info_dir).map_err(gix_error::Error::from)?;
+    let commits = commits_of(&graph);
+    for (layer, path) in paths.iter().enumerate() {
+        let file = File::at(path).map_err(gix_error::Error::from)?;
+        let base = paths[..layer]
+            .iter()
+            .map(File::at)
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(gix_error::Error::from)?;
+        let mut buf = Vec::new();
+        let checksum = write::write_to(
+            &mut buf,
+            commits
+                .iter()
+                .filter(|c| file.lookup(c.id).is_some())
+                .cloned()
+                .collect(),
+            &base,
+            gix_hash::Kind::Sha1,
+            file.has_generation_data(),
+        )
+        .map_err(gix_error::Error::from)?;
+        assert_eq!(checksum, file.checksum(), "{}", path.display());
+        assert!(
+            buf == std::fs::read(path)?,
+            "{}: the file is byte-for-byte the same",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn files_written_by_git_are_reproduced() -> Result {
+    for (script, name) in [
+        ("single_commit.sh", ""),
+        ("single_parent.sh", ""),
+        ("two_parents.sh", ""),
+        ("octopus_merges.sh", ""),
+        ("single_commit_huge_dates.sh", "max-date"),
+        ("split_chain.sh", ""),
+        ("generation_number_overflow.sh", ""),
+    ] {
+        let info_dir = scripted_fixture_read_only(script)?.join(name).join(".git/objects/info");
+        assert_files_reproduced(&info_dir)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn missing_parents_are_an_error() -> Result {
+    let info_dir = scripted_fixture_read_only("single_parent.sh")?.join(".git/objects/info");
+    let commits = commits_of(&Graph::from_info_dir(&info_dir).map_err(gix_error::Error::from)?);
+    let err = write::write_to(&mut Vec::new(), commits[1..].to_vec(), &[], gix_hash::Kind::Sha1, true).unwrap_err();
+    assert!(err.to_string().contains("is not contained in the commit-graph"));
+    Ok(())
+}
+
+#[test]
+fn incremental_updates_with_all_layouts() -> Result {
+    let source_dir = scripted_fixture_read_only("generation_number_overflow.sh")?.join(".git/objects/info");
+    let source = Graph::from_info_dir(&source_dir).map_err(gix_error::Error::from)?;
+    let commits = commits_of(&source);
+    assert_eq!(commits.len(), 5);
+
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let info_dir = tmp.path();
+    let write = |num_commits: usize, split: Option<write::Split>| -> Result<write::Outcome> {
+        let outcome = write::at(
+            info_dir,
+            commits[..num_commits].iter().cloned(),
+            gix_hash::Kind::Sha1,
+            write::Options {
+                split,
+                generation_data: true,
+            },
+        )
+        .map_err(gix_error::Error::from)?;
+        let graph = Graph::from_info_dir(info_dir).map_err(gix_error::Error::from)?;
+        graph
+            .verify_integrity(|_| Ok::<_, gix_error::Message>(()))
+            .map_err(gix_error::Error::from)?;
+        assert_eq!(graph.num_commits() as usize, num_commits);
+        for commit in graph.iter_commits() {
+            let expected = source.commit_by_id(commit.id()).expect("present");
+            assert_eq!(commit.generation(), expected.generation());
+            assert_eq!(commit.corrected_commit_date(), expected.corrected_commit_date());
+        }
+        assert_eq!(graph_files(info_dir)?.len(), outcome.num_files);
+        Ok(outcome)
+    };
+
+    let outcome = write(1, Some(write::Split::NoMerge))?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (1, 1));
+    let outcome = write(2, Some(write::Split::NoMerge))?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (1, 2),
+        "only new commits are written"
+    );
+
+    let outcome = write(3, Some(write::Split::default()))?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (3, 1),
+        "both small layers are merged into the new one"
+    );
+    let outcome = write(3, Some(write::Split::default()))?;
+    assert_eq!(outcome.checksum, None, "nothing to do");
+    assert_eq!(outcome.num_files, 1);
+
+    let outcome = write(4, Some(write::Split::NoMerge))?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (1, 2));
+    let outcome = write(
+        5,
+        Some(write::Split::Merge {
+            size_multiple: 1,
+            max_commits: None,
+        }),
+    )?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (2, 2),
+        "only the layer that isn't larger than the new one is merged"
+    );
+
+    let outcome = write(5, None)?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (5, 1));
+    assert!(info_dir.join("commit-graph").is_file());
+    assert_eq!(
+        std::fs::read_dir(info_dir.join("commit-graphs"))?.count(),
+        0,
+        "the chain was removed"
+    );
+    let outcome = write(5, None)?;
+    assert_eq!(outcome.checksum, None, "nothing to do");
+
+    let outcome = write(5, Some(write::Split::Replace))?;
+    assert_eq!((outcome.num_commits, outcome.num_files), (5, 1));
+    assert!(!info_dir.join("commit-graph").exists(), "the single file was removed");
+    Ok(())
+}
@@ -20,6 +20,14 @@ impl State {
         self.version
     }
 
+    /// Set the `version` to use when writing this state.
+    ///
+    /// Note that V2 and V3 are chosen automatically depending on the need for extended flags,
+    /// so only V4 with its path-prefix compression is retained as is.
+    pub fn set_version(&mut self, version: Version) {
+        self.version = version;
+    }
+
     /// Returns time at which the state was created, indicating its freshness compared to other files on disk.
     pub fn timestamp(&self) -> FileTime {
         self.timestamp
@@ -521,6 +529,9 @@ impl State {
     ///
     /// Alternatively, make sure to call [`sort_entries()`][State::sort_entries()] before entry lookup by path to restore
     /// the invariant.
+    ///
+    /// The `tree` extension is [invalidated](Self::invalidate_tree_at()) at `path`, along with the other extensions that
+    /// depend on the set of entries.
     pub fn dangerously_push_entry(
         &mut self,
         stat: entry::Stat,
@@ -529,6 +540,7 @@ impl State {
         mode: entry::Mode,
         path: &BStr,
     ) {
+        self.entry_added_at(path);
         let path = {
             let path_start = self.path_backing.len();
             self.path_backing.push_str(path);
@@ -568,6 +580,9 @@ impl State {
     ///
     /// Note that the memory used for the removed entries paths is not freed, as it's append-only, and
     /// that some extensions might refer to paths which are now deleted.
+    /// The `tree` extension is [invalidated](Self::invalidate_tree_at()) at the paths of all removed entries, along with
+    /// the other extensions that depend on the set of entries. Removed conflicting entries are recorded in the
+    /// [resolve-undo](Self::resolve_undo()) extension.
     ///
     /// ### Performance
     ///
@@ -576,12 +591,124 @@ impl State {
     pub fn remove_entries(&mut self, mut should_remove: impl FnMut(usize, &BStr, &mut Entry) -> bool) {
         let mut index = 0;
         let paths = &self.path_backing;
+        let mut removed = Vec::new();
         self.entries.retain_mut(|e| {
             let path = e.path_in(paths);
             let res = !should_remove(index, path, e);
+            if !res {
+                removed.push(e.clone());
+            }
             index += 1;
             res
         });
+        for entry in &removed {
+            self.entry_removed(entry);
+        }
+    }
+
+    /// Insert a new entry containing `stat`, `id`, `flags`, `mode` and `path` at its sorted position, or update the existing
+    /// entry at `path` with the same stage as indicated by `flags`. Return the index of the inserted or updated entry.
+    ///
+    /// Entries that conflict with the new one are removed, just like `git` does it:
+    ///
+    /// * entries in other stages at `path` if the new entry is [unconflicted](Stage::Unconflicted),
+    /// * entries *inside* of `path`, as `path` isn't a directory anymore,
+    /// * entries *at* the leading directories of `path`, as these are now directories.
+    ///
+    /// The `tree` extension is [invalidated](Self::invalidate_tree_at()) at `path`, along with the other extensions that
+    /// depend on the set of entries. Removed conflicting entries are recorded in the [resolve-undo](Self::resolve_undo())
+    /// extension.
+    ///
+    /// Note that entries must be sorted, as is the case after [sorting](Self::sort_entries()) or after reading an index from disk.
+    pub fn upsert_entry(
+        &mut self,
+        stat: entry::Stat,
+        id: gix_hash::ObjectId,
+        flags: entry::Flags,
+        mode: entry::Mode,
+        path: &BStr,
+    ) -> usize {
+        let stage = flags.stage();
+        self.invalidate_tree_at(path);
+        if let Some(idx) = self.entry_index_by_path_and_stage(path, stage) {
+            let entry = &mut self.entries[idx];
+            entry.stat = stat;
+            entry.id = id;
+            entry.flags = flags;
+            entry.mode = mode;
+            if stage == Stage::Unconflicted {
+                // The unconflicted entry sorts first, so all other stages follow it.
+                if let Some(range) = self.entry_range(path) {
+                    let removed: Vec<_> = self.entries.drain(idx + 1..range.end).collect();
+                    for entry in &removed {
+                        self.entry_removed(entry);
+                    }
+                }
+            }
+            return idx;
+        }
+
+        let paths = &self.path_backing;
+        let mut removed = Vec::new();
+        self.entries.retain(|e| {
+            let existing = e.path_in(paths);
+            let keep = if existing == path {
+                stage != Stage::Unconflicted
+            } else {
+                let is_inside =
+                    existing.len() > path.len() && existing.starts_with(path) && existing[path.len()] == b'/';
+                let is_leading_dir =
+                    path.len() > existing.len() && path.starts_with(existing) && path[existing.len()] == b'/';
+                !(is_inside || is_leading_dir)
+            };
+            if !keep {
+                removed.push(e.clone());
+            }
+            keep
+        });
+        for entry in &removed {
+            self.entry_removed(entry);
+        }
+        self.entry_set_changed_at(path);
+
+        let path_range = {
+            let path_start = self.path_backing.len();
+            self.path_backing.push_str(path);
+            path_start..self.path_backing.len()
+        };
+        let paths = &self.path_backing;
+        let idx = self.entries.partition_point(|e| {
+            Entry::cmp_filepaths(e.path_in(paths), path)
+                .then_with(|| e.stage().cmp(&stage))
+                .is_lt()
+        });
+        self.entries.insert(
+            idx,
+            Entry {
+                stat,
+                id,
+                flags,
+                mode,
+                path: path_range,
+            },
+        );
+        idx
+    }
+
+    /// Physically remove all entries at `path` in any stage, and return the amount of removed entries.
+    ///
+    /// Note that the memory used for the removed entries paths is not freed, and that the `tree` extension
+    /// is [invalidated](Self::invalidate_tree_at()) at `path`, along with the other extensions that depend on the set of entries.
+    /// Removed conflicting entries are recorded in the [resolve-undo](Self::resolve_undo()) extension.
+    pub fn remove_entries_by_path(&mut self, path: &BStr) -> usize {
+        let Some(range) = self.entry_range(path) else {
+            return 0;
+        };
+        let removed: Vec<_> = self.entries.drain(range).collect();
+        for entry in &removed {
+            self.entry_removed(entry);
+        }
+        removed.len()
     }
 
     /// Physically remove the entry at `index`, or panic if the entry didn't exist.
@@ -590,8 +717,41 @@ impl State {
     ///
     /// Note that the memory used for the removed entries paths is not freed, as it's append-only, and
     /// that some extensions might refer to paths which are now deleted.
+    /// The `tree` extension is [invalidated](Self::invalidate_tree_at()) at the path of the removed entry, along with
+    /// the other extensions that depend on the set of entries. A removed conflicting entry is recorded in the
+    /// [resolve-undo](Self::resolve_undo()) extension.
     pub fn remove_entry_at_index(&mut self, index: usize) -> Entry {
-        self.entries.remove(index)
+        let entry = self.entries.remove(index);
+        self.entry_removed(&entry);
+        entry
+    }
+
+    /// Update all extensions that depend on the set of entries after an entry at `path` was added.
+    fn entry_added_at(&mut self, path: &BStr) {
+        self.invalidate_tree_at(path);
+        self.entry_set_changed_at(path);
+    }
+
+    /// Update all extensions that depend on the set of entries after `entry` was removed, and remember it for
+    /// resolving it again if it was conflicting.
+    fn entry_removed(&mut self, entry: &Entry) {
+        let path = entry.path_in(&self.path_backing).to_owned();
+        self.invalidate_tree_at(path.as_ref());
+        self.entry_set_changed_at(path.as_ref());
+        if entry.stage() != Stage::Unconflicted {
+            extension::resolve_undo::record(self.resolve_undo.get_or_insert_with(Vec::new), path.as_ref(), entry);
+        }
+    }
+
+    /// Invalidate the extensions that refer to entries by position or that list files which aren't entries,
+    /// after an entry at `path` was added or removed.
+    fn entry_set_changed_at(&mut self, path: &BStr) {
+        if let Some(untracked) = self.untracked.as_mut() {
+            untracked.invalidate_path(path);
+        }
+        if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+            fs_monitor.mark_all_entries_dirty();
+        }
     }
 }
 
@@ -28,7 +28,7 @@ pub use flags::Flags;
 
 ///
 pub mod stat;
-mod write;
+pub(crate) mod write;
 
 use bitflags::bitflags;
 
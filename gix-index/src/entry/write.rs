@@ -1,21 +1,49 @@
-use crate::{entry, Entry, State};
+use bstr::BStr;
+
+use crate::{entry, util::encode_var_int, Entry, State};
 
 impl Entry {
     /// Serialize ourselves to `out` with path access via `state`, without padding.
     pub fn write_to(&self, mut out: impl std::io::Write, state: &State) -> std::io::Result<()> {
-        let stat = self.stat;
-        out.write_all(&stat.ctime.secs.to_be_bytes())?;
-        out.write_all(&stat.ctime.nsecs.to_be_bytes())?;
-        out.write_all(&stat.mtime.secs.to_be_bytes())?;
-        out.write_all(&stat.mtime.nsecs.to_be_bytes())?;
-        out.write_all(&stat.dev.to_be_bytes())?;
-        out.write_all(&stat.ino.to_be_bytes())?;
+        let path = self.path(state);
+        self.write_fields_before_path(&mut out, path)?;
+        out.write_all(path)?;
+        out.write_all(b"\0")
+    }
+
+    /// Serialize ourselves to `out` with path access via `state` as done in index V4, where the path is
+    /// stored in relation to the path of the `previous` entry, or `None` if this is the first one.
+    pub(crate) fn write_to_v4(
+        &self,
+        mut out: impl std::io::Write,
+        state: &State,
+        previous: Option<&Entry>,
+    ) -> std::io::Result<()> {
+        let path = self.path(state);
+        self.write_fields_before_path(&mut out, path)?;
+        let previous_path = previous.map_or(BStr::new(""), |e| e.path(state));
+        let common_prefix_len = previous_path
+            .iter()
+            .zip(path.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let mut buf = [0; 10];
+        out.write_all(encode_var_int(
+            (previous_path.len() - common_prefix_len) as u64,
+            &mut buf,
+        ))?;
+        out.write_all(&path[common_prefix_len..])?;
+        out.write_all(b"\0")
+    }
+
+    fn write_fields_before_path(&self, out: &mut impl std::io::Write, path: &BStr) -> std::io::Result<()> {
+        write_stat(out, &self.stat)?;
         out.write_all(&self.mode.bits().to_be_bytes())?;
+        let stat = self.stat;
         out.write_all(&stat.uid.to_be_bytes())?;
         out.write_all(&stat.gid.to_be_bytes())?;
         out.write_all(&stat.size.to_be_bytes())?;
         out.write_all(self.id.as_bytes())?;
-        let path = self.path(state);
         let path_len: u16 = if path.len() >= entry::Flags::PATH_LEN.bits() as usize {
             entry::Flags::PATH_LEN.bits() as u16
         } else {
@@ -31,7 +59,24 @@ impl Entry {
                     .to_be_bytes(),
             )?;
         }
-        out.write_all(path)?;
-        out.write_all(b"\0")
+        Ok(())
     }
 }
+
+/// Write the time, device and inode portions of `stat` to `out`, which precede the mode in an entry.
+fn write_stat(out: &mut impl std::io::Write, stat: &entry::Stat) -> std::io::Result<()> {
+    out.write_all(&stat.ctime.secs.to_be_bytes())?;
+    out.write_all(&stat.ctime.nsecs.to_be_bytes())?;
+    out.write_all(&stat.mtime.secs.to_be_bytes())?;
+    out.write_all(&stat.mtime.nsecs.to_be_bytes())?;
+    out.write_all(&stat.dev.to_be_bytes())?;
+    out.write_all(&stat.ino.to_be_bytes())
+}
+
+/// Write all of `stat` to `out` in the order used by extensions, which is the order of entries without the mode.
+pub(crate) fn write_full_stat(out: &mut impl std::io::Write, stat: &entry::Stat) -> std::io::Result<()> {
+    write_stat(out, stat)?;
+    out.write_all(&stat.uid.to_be_bytes())?;
+    out.write_all(&stat.gid.to_be_bytes())?;
+    out.write_all(&stat.size.to_be_bytes())
+}
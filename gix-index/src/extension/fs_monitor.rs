@@ -35,5 +35,131 @@ pub fn decode(data: &[u8]) -> Option<FsMonitor> {
         return None;
     }
 
-    FsMonitor { token, entry_dirty }.into()
+    FsMonitor {
+        token,
+        entry_dirty,
+        entries_changed: false,
+    }
+    .into()
+}
+
+impl FsMonitor {
+    /// Mark all entries dirty as entries were added or removed, which changes the positions the dirty-bitmap refers to.
+    ///
+    /// This way the filesystem monitor is asked about all entries, but can keep its token.
+    pub(crate) fn mark_all_entries_dirty(&mut self) {
+        self.entries_changed = true;
+    }
+
+    /// Serialize this instance as filesystem-monitor extension to `out`, for an index with `num_entries` entries.
+    ///
+    /// If [entries were added or removed](Self::mark_all_entries_dirty()), or if the dirty-bitmap has more entries than the index,
+    /// all entries are marked dirty as it can't apply anymore.
+    pub fn write_to(&self, mut out: impl std::io::Write, num_entries: u32) -> Result<(), std::io::Error> {
+        let mut data = Vec::new();
+        match &self.token {
+            Token::V1 { nanos_since_1970 } => {
+                data.extend_from_slice(&1_u32.to_be_bytes());
+                data.extend_from_slice(&nanos_since_1970.to_be_bytes());
+            }
+            Token::V2 { token } => {
+                data.extend_from_slice(&2_u32.to_be_bytes());
+                data.extend_from_slice(token);
+                data.push(0);
+            }
+        }
+
+        let mut bitmap = Vec::new();
+        if !self.entries_changed && self.entry_dirty.num_bits() <= num_entries as usize {
+            self.entry_dirty.write_to(&mut bitmap)?;
+        } else {
+            let mut bits = vec![u64::MAX; (num_entries as usize).div_ceil(64)];
+            if let Some(last) = bits.last_mut().filter(|_| num_entries % 64 != 0) {
+                *last = (1 << (num_entries % 64)) - 1;
+            }
+            gix_bitmap::ewah::encode(&bits, num_entries).write_to(&mut bitmap)?;
+        }
+        data.extend_from_slice(&(u32::try_from(bitmap.len()).expect("less than 4GB bitmap")).to_be_bytes());
+        data.extend_from_slice(&bitmap);
+
+        out.write_all(&SIGNATURE)?;
+        out.write_all(&(u32::try_from(data.len()).expect("less than 4GB fs-monitor extension")).to_be_bytes())?;
+        out.write_all(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{entry, State};
+
+    /// Return the fixture with all entries marked clean.
+    fn fsmn_fixture() -> crate::File {
+        let mut file = crate::File::at(
+            "tests/fixtures/loose_index/FSMN.git-index",
+            gix_hash::Kind::Sha1,
+            false,
+            Default::default(),
+        )
+        .expect("valid file");
+        let num_entries = file.entries().len();
+        file.fs_monitor.as_mut().expect("present").entry_dirty = gix_bitmap::ewah::encode(&[0], num_entries as u32);
+        file
+    }
+
+    fn num_dirty_entries_after_write(file: &crate::File) -> (usize, usize) {
+        let mut buf = Vec::new();
+        file.write_to(&mut buf, Default::default())
+            .expect("in-memory write succeeds");
+        let (state, _) = State::from_bytes(
+            &buf,
+            filetime::FileTime::now(),
+            gix_hash::Kind::Sha1,
+            Default::default(),
+        )
+        .expect("valid index");
+        let mut num_dirty = 0;
+        state
+            .fs_monitor()
+            .expect("still present")
+            .entry_dirty
+            .for_each_set_bit(|_| {
+                num_dirty += 1;
+                Some(())
+            });
+        (num_dirty, state.entries().len())
+    }
+
+    #[test]
+    fn all_entries_are_dirty_once_entries_are_added_or_removed() {
+        let file = fsmn_fixture();
+        let (num_dirty, num_entries) = num_dirty_entries_after_write(&file);
+        assert_eq!((num_dirty, num_entries), (0, 6), "precondition: all entries are clean");
+
+        let mut file = fsmn_fixture();
+        file.remove_entries_by_path("modified".into());
+        let (num_dirty, num_entries) = num_dirty_entries_after_write(&file);
+        assert_eq!(
+            num_dirty, num_entries,
+            "positions changed, so no entry is known to be clean"
+        );
+
+        let mut file = fsmn_fixture();
+        file.upsert_entry(
+            Default::default(),
+            gix_hash::Kind::Sha1.null(),
+            entry::Flags::empty(),
+            entry::Mode::FILE,
+            "added".into(),
+        );
+        let (num_dirty, num_entries) = num_dirty_entries_after_write(&file);
+        assert_eq!(num_dirty, num_entries);
+
+        let mut file = fsmn_fixture();
+        file.entries_mut()[0].flags.insert(entry::Flags::REMOVE);
+        let (num_dirty, num_entries) = num_dirty_entries_after_write(&file);
+        assert_eq!(
+            num_dirty, num_entries,
+            "entries that aren't written change positions as well"
+        );
+    }
 }
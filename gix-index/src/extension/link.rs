@@ -66,6 +66,21 @@ pub(crate) fn decode(data: &[u8], object_hash: gix_hash::Kind) -> Result<Link, d
 }
 
 impl Link {
+    /// Serialize this instance as link extension to `out`, including its bitmaps if present.
+    pub fn write_to(&self, mut out: impl std::io::Write) -> Result<(), std::io::Error> {
+        let mut data = Vec::new();
+        data.extend_from_slice(self.shared_index_checksum.as_slice());
+        if let Some(bitmaps) = &self.bitmaps {
+            bitmaps.delete.write_to(&mut data)?;
+            bitmaps.replace.write_to(&mut data)?;
+        }
+
+        out.write_all(&SIGNATURE)?;
+        out.write_all(&(u32::try_from(data.len()).expect("less than 4GB link extension")).to_be_bytes())?;
+        out.write_all(&data)
+    }
+
+    /// Merge the shared index we link to into `split_index`, which keeps a link without bitmaps to remember its shared index.
     pub(crate) fn dissolve_into(
         self,
         split_index: &mut crate::File,
@@ -73,6 +88,10 @@ impl Link {
         skip_hash: bool,
         options: crate::decode::Options,
     ) -> Result<(), crate::file::init::Error> {
+        // A null id means there is no shared index, which is how `git` writes split indices that don't have one yet.
+        if self.shared_index_checksum.is_null() {
+            return Ok(());
+        }
         let shared_index_path = split_index
             .path
             .parent()
@@ -88,10 +107,9 @@ impl Link {
             },
         )?;
 
-        if let Some(bitmaps) = self.bitmaps {
-            let mut split_entry_index = 0;
-
-            let mut err = None;
+        let mut split_entry_index = 0;
+        let mut err = None;
+        if let Some(bitmaps) = &self.bitmaps {
             bitmaps.replace.for_each_set_bit(|replace_index| {
                 let shared_entry = match shared_index.entries.get_mut(replace_index) {
                     Some(e) => e,
@@ -129,23 +147,25 @@ impl Link {
                 split_entry_index += 1;
                 Some(())
             });
-            if let Some(err) = err {
+            if let Some(err) = err.take() {
                 return Err(err.into());
             }
+        }
 
-            let split_index_path_backing = std::mem::take(&mut split_index.path_backing);
-            for mut split_entry in split_index.entries.drain(split_entry_index..) {
-                let start = shared_index.path_backing.len();
-                let split_index_path = split_entry.path.clone();
+        let split_index_path_backing = std::mem::take(&mut split_index.path_backing);
+        for mut split_entry in split_index.entries.drain(split_entry_index..) {
+            let start = shared_index.path_backing.len();
+            let split_index_path = split_entry.path.clone();
 
-                split_entry.path = start..start + split_entry.path.len();
-                shared_index.entries.push(split_entry);
+            split_entry.path = start..start + split_entry.path.len();
+            shared_index.entries.push(split_entry);
 
-                shared_index
-                    .path_backing
-                    .extend_from_slice(&split_index_path_backing[split_index_path]);
-            }
+            shared_index
+                .path_backing
+                .extend_from_slice(&split_index_path_backing[split_index_path]);
+        }
 
+        if let Some(bitmaps) = &self.bitmaps {
             bitmaps.delete.for_each_set_bit(|delete_index| {
                 let shared_entry = match shared_index.entries.get_mut(delete_index) {
                     Some(e) => e,
@@ -160,17 +180,21 @@ impl Link {
             if let Some(err) = err {
                 return Err(err.into());
             }
+        }
 
-            shared_index
-                .entries
-                .retain(|e| !e.flags.contains(crate::entry::Flags::REMOVE));
+        shared_index
+            .entries
+            .retain(|e| !e.flags.contains(crate::entry::Flags::REMOVE));
 
-            let mut shared_entries = std::mem::take(&mut shared_index.entries);
-            shared_entries.sort_by(|a, b| a.cmp(b, &shared_index.state));
+        let mut shared_entries = std::mem::take(&mut shared_index.entries);
+        shared_entries.sort_by(|a, b| a.cmp(b, &shared_index.state));
 
-            split_index.entries = shared_entries;
-            split_index.path_backing = std::mem::take(&mut shared_index.path_backing);
-        }
+        split_index.entries = shared_entries;
+        split_index.path_backing = std::mem::take(&mut shared_index.path_backing);
+        split_index.link = Some(Link {
+            shared_index_checksum: self.shared_index_checksum,
+            bitmaps: None,
+        });
 
         Ok(())
     }
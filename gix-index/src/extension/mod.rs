@@ -38,6 +38,9 @@ pub struct Link {
     /// The checksum of the shared index as last seen.
     pub shared_index_checksum: gix_hash::ObjectId,
     /// Bitmaps to tell us which entries to delete or replace.
+    ///
+    /// They are `None` once they have been applied while [reading an index file](crate::File::at()), which merges the
+    /// shared index into the split one and keeps this extension only to know which shared index it was based on.
     pub bitmaps: Option<link::Bitmaps>,
 }
 
@@ -67,6 +70,8 @@ pub struct FsMonitor {
     token: fs_monitor::Token,
     /// if a bit is true, the respective entry is NOT valid as per the fs monitor.
     entry_dirty: gix_bitmap::ewah::Vec,
+    /// If `true`, entries were added or removed so `entry_dirty` doesn't apply anymore and all entries are considered dirty.
+    entries_changed: bool,
 }
 
 mod iter;
@@ -1,7 +1,9 @@
-use bstr::BString;
+use std::io::Write;
+
+use bstr::{BStr, BString};
 use gix_hash::ObjectId;
 
-use crate::{extension::Signature, util::split_at_byte_exclusive};
+use crate::{entry, extension::Signature, util::split_at_byte_exclusive, Entry};
 
 pub type Paths = Vec<ResolvePath>;
 
@@ -24,6 +26,30 @@ pub struct Stage {
 
 pub const SIGNATURE: Signature = *b"REUC";
 
+/// Remember the conflicting `entry` at `path` which is about to be removed, so the conflict can be recreated later.
+pub fn record(paths: &mut Paths, path: &BStr, entry: &Entry) {
+    let stage = match entry.stage() {
+        entry::Stage::Unconflicted => return,
+        entry::Stage::Base => 0,
+        entry::Stage::Ours => 1,
+        entry::Stage::Theirs => 2,
+    };
+    let idx = match paths.iter().position(|p| p.name == path) {
+        Some(idx) => idx,
+        None => {
+            paths.push(ResolvePath {
+                name: path.to_owned(),
+                stages: [None; 3],
+            });
+            paths.len() - 1
+        }
+    };
+    paths[idx].stages[stage] = Some(Stage {
+        mode: entry.mode.bits(),
+        id: entry.id,
+    });
+}
+
 pub fn decode(mut data: &[u8], object_hash: gix_hash::Kind) -> Option<Paths> {
     let hash_len = object_hash.len_in_bytes();
     let mut out = Vec::new();
@@ -59,3 +85,60 @@ pub fn decode(mut data: &[u8], object_hash: gix_hash::Kind) -> Option<Paths> {
     }
     out.into()
 }
+
+/// Serialize `paths` as resolve-undo extension to `out`.
+pub fn write_to(paths: &Paths, mut out: impl std::io::Write) -> Result<(), std::io::Error> {
+    let mut data = Vec::new();
+    for path in paths {
+        data.extend_from_slice(&path.name);
+        data.push(0);
+        for stage in &path.stages {
+            write!(data, "{:o}", stage.map_or(0, |s| s.mode))?;
+            data.push(0);
+        }
+        for stage in path.stages.iter().flatten() {
+            data.extend_from_slice(stage.id.as_bytes());
+        }
+    }
+
+    out.write_all(&SIGNATURE)?;
+    out.write_all(&(u32::try_from(data.len()).expect("less than 4GB resolve-undo extension")).to_be_bytes())?;
+    out.write_all(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn removed_conflicting_entries_are_recorded() {
+        let mut file = crate::File::at(
+            "tests/fixtures/loose_index/conflicting-file.git-index",
+            gix_hash::Kind::Sha1,
+            false,
+            Default::default(),
+        )
+        .expect("valid file");
+        assert!(file.resolve_undo().is_none(), "precondition");
+        let conflicts: Vec<_> = file.entries().iter().map(|e| (e.mode.bits(), e.id)).collect();
+
+        file.upsert_entry(
+            Default::default(),
+            gix_hash::Kind::Sha1.null(),
+            crate::entry::Flags::empty(),
+            crate::entry::Mode::FILE,
+            "file".into(),
+        );
+        assert_eq!(file.entries().len(), 1, "the conflict was resolved");
+        let paths = file.resolve_undo().expect("created");
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].name, "file");
+        assert_eq!(
+            paths[0]
+                .stages
+                .iter()
+                .map(|s| s.map(|s| (s.mode, s.id)).expect("all stages present"))
+                .collect::<Vec<_>>(),
+            conflicts,
+            "all stages can be restored"
+        );
+    }
+}
@@ -1,8 +1,34 @@
-use crate::extension::Signature;
+use bstr::{BStr, ByteSlice};
+
+use crate::extension::{Signature, Tree};
 
 /// The signature for tree extensions
 pub const SIGNATURE: Signature = *b"TREE";
 
+impl Tree {
+    /// Invalidate this tree and all trees leading up to `path`, as the entries within them changed, and remove the tree
+    /// at `path` itself if there is one. `path` is relative to this tree and may be the path of a file or a directory.
+    pub fn invalidate_path(&mut self, path: &BStr) {
+        let mut tree = self;
+        let mut components = path.split_str("/").peekable();
+        while let Some(component) = components.next() {
+            tree.num_entries = None;
+            let Some(pos) = tree
+                .children
+                .iter()
+                .position(|child| child.name.as_slice() == component)
+            else {
+                return;
+            };
+            if components.peek().is_none() {
+                tree.children.remove(pos);
+                return;
+            }
+            tree = &mut tree.children[pos];
+        }
+    }
+}
+
 ///
 pub mod verify;
 
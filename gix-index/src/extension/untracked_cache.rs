@@ -1,10 +1,11 @@
-use bstr::BString;
+use bstr::{BStr, BString, ByteSlice};
 use gix_hash::ObjectId;
 
 use crate::{
     entry,
+    entry::write::write_full_stat,
     extension::{Signature, UntrackedCache},
-    util::{read_u32, split_at_byte_exclusive, var_int},
+    util::{encode_var_int, read_u32, split_at_byte_exclusive, var_int},
 };
 
 /// A structure to track filesystem stat information along with an object id, linking a worktree file with what's in our ODB.
@@ -34,6 +35,14 @@ pub struct Directory {
     pub check_only: bool,
 }
 
+impl Directory {
+    /// Forget what we know about this directory so it will be read again, without affecting its sub-directories.
+    fn invalidate(&mut self) {
+        self.stat = None;
+        self.untracked_entries.clear();
+    }
+}
+
 /// Only used as an indicator
 pub const SIGNATURE: Signature = *b"UNTR";
 
@@ -47,9 +56,19 @@ pub fn decode(data: &[u8], object_hash: gix_hash::Kind) -> Option<UntrackedCache
     let (identifier, data) = data.split_at_checked(identifier_len.try_into().ok()?)?;
 
     let hash_len = object_hash.len_in_bytes();
-    let (info_exclude, data) = decode_oid_stat(data, hash_len)?;
-    let (excludes_file, data) = decode_oid_stat(data, hash_len)?;
+    let (info_exclude_stat, data) = crate::decode::stat(data)?;
+    let (excludes_file_stat, data) = crate::decode::stat(data)?;
     let (dir_flags, data) = read_u32(data)?;
+    let (info_exclude_id, data) = data.split_at_checked(hash_len)?;
+    let (excludes_file_id, data) = data.split_at_checked(hash_len)?;
+    let info_exclude = OidStat {
+        stat: info_exclude_stat,
+        id: ObjectId::from_bytes_or_panic(info_exclude_id),
+    };
+    let excludes_file = OidStat {
+        stat: excludes_file_stat,
+        id: ObjectId::from_bytes_or_panic(excludes_file_id),
+    };
     let (exclude_filename_per_dir, data) = split_at_byte_exclusive(data, 0)?;
 
     let (num_directory_blocks, data) = var_int(data)?;
@@ -141,14 +160,200 @@ fn decode_directory_block<'a>(data: &'a [u8], directories: &mut Vec<Directory>)
     data.into()
 }
 
-fn decode_oid_stat(data: &[u8], hash_len: usize) -> Option<(OidStat, &[u8])> {
-    let (stat, data) = crate::decode::stat(data)?;
-    let (hash, data) = data.split_at_checked(hash_len)?;
-    Some((
-        OidStat {
-            stat,
-            id: ObjectId::from_bytes_or_panic(hash),
-        },
-        data,
-    ))
+/// The `dir_flags` bit that indicates that untracked directories are listed instead of the untracked files within them.
+const DIR_SHOW_OTHER_DIRECTORIES: u32 = 1 << 1;
+
+impl UntrackedCache {
+    /// Invalidate the directory that contains the entry at `path` as it was added or removed, like `git` does.
+    ///
+    /// As untracked directories are listed in their parent directory if they contain no tracked file, all leading
+    /// directories are invalidated as well in that case.
+    pub(crate) fn invalidate_path(&mut self, path: &BStr) {
+        if self.directories.is_empty() {
+            return;
+        }
+        let show_other_directories = self.dir_flags & DIR_SHOW_OTHER_DIRECTORIES != 0;
+        let mut leading_dirs = vec![0];
+        let mut components = path.split_str("/").collect::<Vec<_>>();
+        components.pop();
+        let mut found_parent_dir = true;
+        for component in components {
+            let current = &self.directories[*leading_dirs.last().expect("at least root")];
+            match current
+                .sub_directories
+                .iter()
+                .find(|idx| self.directories[**idx].name == component)
+            {
+                Some(idx) => leading_dirs.push(*idx),
+                None => {
+                    found_parent_dir = false;
+                    break;
+                }
+            }
+        }
+
+        let parent_dir = leading_dirs.pop().expect("at least root");
+        if found_parent_dir || show_other_directories {
+            self.directories[parent_dir].invalidate();
+        }
+        if show_other_directories {
+            for idx in leading_dirs {
+                self.directories[idx].invalidate();
+            }
+        }
+    }
+
+    /// Serialize this instance as untracked-cache extension to `out`, using hashes of kind `object_hash`.
+    pub fn write_to(&self, mut out: impl std::io::Write, object_hash: gix_hash::Kind) -> Result<(), std::io::Error> {
+        let mut data = Vec::new();
+        let mut buf = [0; 10];
+        data.extend_from_slice(encode_var_int(self.identifier.len() as u64, &mut buf));
+        data.extend_from_slice(&self.identifier);
+
+        let null_stat = entry::Stat::default();
+        for oid_stat in [&self.info_exclude, &self.excludes_file] {
+            write_full_stat(&mut data, oid_stat.as_ref().map_or(&null_stat, |s| &s.stat))?;
+        }
+        data.extend_from_slice(&self.dir_flags.to_be_bytes());
+        for oid_stat in [&self.info_exclude, &self.excludes_file] {
+            match oid_stat {
+                Some(oid_stat) => data.extend_from_slice(oid_stat.id.as_bytes()),
+                None => data.extend_from_slice(object_hash.null().as_bytes()),
+            }
+        }
+        data.extend_from_slice(&self.exclude_filename_per_dir);
+        data.push(0);
+
+        data.extend_from_slice(encode_var_int(self.directories.len() as u64, &mut buf));
+        if !self.directories.is_empty() {
+            let mut order = Vec::with_capacity(self.directories.len());
+            write_directory_block(&mut data, &self.directories, 0, &mut order);
+
+            let bitmap = |is_set: &dyn Fn(&Directory) -> bool| {
+                let mut bits = Vec::<u64>::new();
+                let mut num_bits = 0;
+                for (pos, dir) in order.iter().map(|idx| &self.directories[*idx]).enumerate() {
+                    if is_set(dir) {
+                        bits.resize(pos / 64 + 1, 0);
+                        bits[pos / 64] |= 1 << (pos % 64);
+                        num_bits = pos as u32 + 1;
+                    }
+                }
+                gix_bitmap::ewah::encode(&bits, num_bits)
+            };
+            bitmap(&|dir| dir.stat.is_some()).write_to(&mut data)?;
+            bitmap(&|dir| dir.check_only).write_to(&mut data)?;
+            bitmap(&|dir| dir.exclude_file_oid.is_some()).write_to(&mut data)?;
+            for dir in order.iter().map(|idx| &self.directories[*idx]) {
+                if let Some(stat) = &dir.stat {
+                    write_full_stat(&mut data, stat)?;
+                }
+            }
+            for dir in order.iter().map(|idx| &self.directories[*idx]) {
+                if let Some(id) = &dir.exclude_file_oid {
+                    data.extend_from_slice(id.as_bytes());
+                }
+            }
+        }
+        data.push(0);
+
+        out.write_all(&SIGNATURE)?;
+        out.write_all(&(u32::try_from(data.len()).expect("less than 4GB untracked extension")).to_be_bytes())?;
+        out.write_all(&data)
+    }
+}
+
+/// Write the directory at `idx` in `directories` and all of its sub-directories to `out`, recording the order
+/// in which they were written in `order`.
+fn write_directory_block(out: &mut Vec<u8>, directories: &[Directory], idx: usize, order: &mut Vec<usize>) {
+    order.push(idx);
+    let dir = &directories[idx];
+    let mut buf = [0; 10];
+    out.extend_from_slice(encode_var_int(dir.untracked_entries.len() as u64, &mut buf));
+    out.extend_from_slice(encode_var_int(dir.sub_directories.len() as u64, &mut buf));
+    out.extend_from_slice(&dir.name);
+    out.push(0);
+    for name in &dir.untracked_entries {
+        out.extend_from_slice(name);
+        out.push(0);
+    }
+    for sub_directory in &dir.sub_directories {
+        write_directory_block(out, directories, *sub_directory, order);
+    }
+}
+#[cfg(test)]
+mod tests {
+    use crate::{entry, State};
+
+    fn untr_fixture() -> crate::File {
+        crate::File::at(
+            "tests/fixtures/loose_index/UNTR.git-index",
+            gix_hash::Kind::Sha1,
+            false,
+            Default::default(),
+        )
+        .expect("valid file")
+    }
+
+    fn is_valid(state: &State, name: &str) -> bool {
+        let directories = &state.untracked().expect("present").directories;
+        let dir = directories.iter().find(|d| d.name == name).expect("exists");
+        if dir.stat.is_none() {
+            assert!(
+                dir.untracked_entries.is_empty(),
+                "untracked files are forgotten along with the stat"
+            );
+        }
+        dir.stat.is_some()
+    }
+
+    #[test]
+    fn adding_or_removing_entries_invalidates_their_directories() {
+        let mut file = untr_fixture();
+        file.upsert_entry(
+            Default::default(),
+            gix_hash::Kind::Sha1.null(),
+            entry::Flags::empty(),
+            entry::Mode::FILE,
+            "dthree/three".into(),
+        );
+        assert!(!is_valid(&file, "dthree"), "the directory of the new entry…");
+        assert!(
+            !is_valid(&file, ""),
+            "…and its parents are invalidated as untracked directories are listed"
+        );
+        assert!(is_valid(&file, "dtwo"));
+        assert!(is_valid(&file, "done"));
+
+        let mut file = untr_fixture();
+        file.untracked.as_mut().expect("present").dir_flags &= !super::DIR_SHOW_OTHER_DIRECTORIES;
+        file.remove_entries_by_path("done/one".into());
+        assert!(!is_valid(&file, "done"));
+        assert!(
+            is_valid(&file, ""),
+            "parent directories stay valid if untracked directories aren't listed"
+        );
+    }
+
+    #[test]
+    fn entries_marked_for_removal_invalidate_their_directories_when_written() {
+        let mut file = untr_fixture();
+        let idx = file.entry_index_by_path("done/one".into()).expect("present");
+        file.entries_mut()[idx].flags.insert(entry::Flags::REMOVE);
+
+        let mut buf = Vec::new();
+        file.write_to(&mut buf, Default::default())
+            .expect("in-memory write succeeds");
+        let (state, _) = State::from_bytes(
+            &buf,
+            filetime::FileTime::now(),
+            gix_hash::Kind::Sha1,
+            Default::default(),
+        )
+        .expect("valid index");
+        assert!(!is_valid(&state, "done"));
+        assert!(!is_valid(&state, ""));
+        assert!(is_valid(&state, "dtwo"));
+        assert!(is_valid(&file, "done"), "the in-memory state isn't affected");
+    }
 }
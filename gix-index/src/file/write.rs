@@ -1,4 +1,9 @@
-use crate::{write, File, Version};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{decode, entry, extension, write, Entry, File, PathStorage, State, Version};
 
 /// The error produced by [`File::write()`].
 #[derive(Debug, thiserror::Error)]
@@ -10,47 +15,293 @@ pub enum Error {
     AcquireLock(#[from] gix_lock::acquire::Error),
     #[error("Could not commit lock for index file")]
     CommitLock(#[from] gix_lock::commit::Error<gix_lock::File>),
+    #[error("Could not write shared index into '{}'", dir.display())]
+    SharedIndex { dir: PathBuf, source: std::io::Error },
 }
 
 impl File {
     /// Write the index to `out` with `options`, to be readable by [`File::at()`], returning the version that was actually written
     /// to retain all information of this index.
+    ///
+    /// Note that [split indices](write::Options::split_index) can only be written with [`File::write()`].
     pub fn write_to(
         &self,
-        mut out: impl std::io::Write,
+        out: impl std::io::Write,
         options: write::Options,
     ) -> Result<(Version, gix_hash::ObjectId), gix_hash::io::Error> {
         let _span = gix_features::trace::detail!("gix_index::File::write_to()", skip_hash = options.skip_hash);
-        let (version, hash) = if options.skip_hash {
-            let out: &mut dyn std::io::Write = &mut out;
-            let version = self.state.write_to(out, options)?;
-            (version, self.state.object_hash.null())
-        } else {
-            let mut hasher = gix_hash::io::Write::new(&mut out, self.state.object_hash);
-            let out: &mut dyn std::io::Write = &mut hasher;
-            let version = self.state.write_to(out, options)?;
-            (version, hasher.hash.try_finalize()?)
-        };
-        out.write_all(hash.as_slice())?;
-        Ok((version, hash))
+        write_state_to(&self.state, out, options)
     }
 
     /// Write ourselves to the path we were read from after acquiring a lock, using `options`.
     ///
+    /// If [`options.split_index`](write::Options::split_index) is set, a split index is written that only stores the
+    /// changes compared to its shared index, which is written next to it if there is none yet or if too many entries changed.
+    /// Otherwise, a split index we were read from is replaced with one that contains all entries.
+    ///
     /// Note that the hash produced will be stored which is why we need to be mutable.
     pub fn write(&mut self, options: write::Options) -> Result<(), Error> {
         let _span = gix_features::trace::detail!("gix_index::File::write()", path = ?self.path);
-        let mut lock = std::io::BufWriter::with_capacity(
-            64 * 1024,
-            gix_lock::File::acquire_to_update_resource(&self.path, gix_lock::acquire::Fail::Immediately, None)?,
-        );
-        let (version, digest) = self.write_to(&mut lock, options)?;
-        match lock.into_inner() {
-            Ok(lock) => lock.commit()?,
-            Err(err) => return Err(Error::Io(err.into_error().into())),
+        if let Some(split_index) = options.split_index {
+            return self.write_split(options, split_index);
+        }
+        let (version, digest) = write_locked(&self.path, &self.state, options)?;
+        self.state.version = version;
+        self.checksum = Some(digest);
+        self.state.link.take_if(|link| link.bitmaps.is_none());
+        Ok(())
+    }
+
+    fn write_split(&mut self, options: write::Options, split_index: write::SplitIndex) -> Result<(), Error> {
+        let dir = self.path.parent().expect("index file in .git folder");
+        let shared = self
+            .state
+            .link()
+            .map(|link| link.shared_index_checksum)
+            .filter(|checksum| !checksum.is_null())
+            .and_then(|checksum| {
+                let path = shared_index_path(dir, &checksum);
+                let shared = File::at(
+                    &path,
+                    self.state.object_hash,
+                    false,
+                    decode::Options {
+                        expected_checksum: Some(checksum),
+                        ..Default::default()
+                    },
+                )
+                .ok()?;
+                Some((shared, path, checksum))
+            });
+
+        let split = shared.and_then(|(shared, path, checksum)| {
+            let split = Split::new(&self.state, &shared.state);
+            (!split.has_too_many_unshared_entries(split_index.max_percent_change)).then(|| {
+                // Like `git`, mark the shared index as used so it doesn't expire.
+                filetime::set_file_mtime(&path, filetime::FileTime::now()).ok();
+                (split, checksum)
+            })
+        });
+        let (split, shared_index_checksum) = match split {
+            Some(split) => split,
+            None => {
+                let checksum = write_shared_index(dir, &self.state)?;
+                if let Some(expire) = split_index.shared_index_expire {
+                    remove_expired_shared_indices(dir, &checksum, expire);
+                }
+                (Split::default(), checksum)
+            }
         };
+
+        let state = split.into_state(&self.state, shared_index_checksum);
+        let (version, digest) = write_locked(&self.path, &state, options)?;
         self.state.version = version;
+        self.state.link = Some(extension::Link {
+            shared_index_checksum,
+            bitmaps: None,
+        });
         self.checksum = Some(digest);
         Ok(())
     }
 }
+
+fn write_state_to(
+    state: &State,
+    mut out: impl std::io::Write,
+    options: write::Options,
+) -> Result<(Version, gix_hash::ObjectId), gix_hash::io::Error> {
+    let (version, hash) = if options.skip_hash {
+        let out: &mut dyn std::io::Write = &mut out;
+        let version = state.write_to(out, options)?;
+        (version, state.object_hash.null())
+    } else {
+        let mut hasher = gix_hash::io::Write::new(&mut out, state.object_hash);
+        let out: &mut dyn std::io::Write = &mut hasher;
+        let version = state.write_to(out, options)?;
+        (version, hasher.hash.try_finalize()?)
+    };
+    out.write_all(hash.as_slice())?;
+    Ok((version, hash))
+}
+
+fn write_locked(path: &Path, state: &State, options: write::Options) -> Result<(Version, gix_hash::ObjectId), Error> {
+    let mut lock = std::io::BufWriter::with_capacity(
+        64 * 1024,
+        gix_lock::File::acquire_to_update_resource(path, gix_lock::acquire::Fail::Immediately, None)?,
+    );
+    let (version, digest) = write_state_to(state, &mut lock, options)?;
+    match lock.into_inner() {
+        Ok(lock) => lock.commit()?,
+        Err(err) => return Err(Error::Io(err.into_error().into())),
+    };
+    Ok((version, digest))
+}
+
+fn shared_index_path(dir: &Path, checksum: &gix_hash::oid) -> PathBuf {
+    dir.join(format!("sharedindex.{checksum}"))
+}
+
+/// Write all entries of `state` into a new shared index in `dir`, without any extension, and return its checksum.
+fn write_shared_index(dir: &Path, state: &State) -> Result<gix_hash::ObjectId, Error> {
+    use gix_lock::tempfile::{AutoRemove, ContainingDirectory};
+    let err = |source| Error::SharedIndex {
+        dir: dir.to_owned(),
+        source,
+    };
+    let mut file = gix_lock::tempfile::new(dir, ContainingDirectory::Exists, AutoRemove::Tempfile).map_err(err)?;
+    let mut out = std::io::BufWriter::with_capacity(64 * 1024, &mut file);
+    let (_version, checksum) = write_state_to(
+        state,
+        &mut out,
+        write::Options {
+            extensions: write::Extensions::None,
+            skip_hash: false,
+            split_index: None,
+        },
+    )?;
+    out.flush().map_err(err)?;
+    drop(out);
+    file.persist(shared_index_path(dir, &checksum))
+        .map_err(|e| err(e.error))?;
+    Ok(checksum)
+}
+
+/// Remove all shared indices in `dir` other than the one with `checksum` which weren't modified since `expire`.
+fn remove_expired_shared_indices(dir: &Path, checksum: &gix_hash::oid, expire: std::time::SystemTime) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let current = format!("sharedindex.{checksum}");
+    for entry in entries.filter_map(Result::ok) {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else { continue };
+        if !name.starts_with("sharedindex.") || name == current {
+            continue;
+        }
+        if entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified <= expire)
+        {
+            std::fs::remove_file(entry.path()).ok();
+        }
+    }
+}
+
+/// The difference of an index to its shared index, as stored in a split index.
+#[derive(Default)]
+struct Split {
+    /// Entries that replace the shared entry at the same position in `replace`, followed by all entries that were added.
+    entries: Vec<Entry>,
+    path_backing: PathStorage,
+    /// The ascending positions of shared entries that were deleted.
+    delete: Vec<usize>,
+    /// The ascending positions of shared entries that were replaced.
+    replace: Vec<usize>,
+    /// The amount of entries that aren't stored in the shared index at all.
+    num_unshared: usize,
+    /// The total amount of entries in the index.
+    num_entries: usize,
+}
+
+impl Split {
+    /// Compute the changes of `state` compared to `shared`, assuming both are sorted.
+    fn new(state: &State, shared: &State) -> Self {
+        let mut out = Split::default();
+        let mut added = Vec::new();
+        let mut shared_entries = shared.entries.iter().enumerate().peekable();
+        for entry in state.entries.iter().filter(|e| !e.flags.contains(entry::Flags::REMOVE)) {
+            out.num_entries += 1;
+            let cmp = |shared_entry: &Entry| {
+                Entry::cmp_filepaths(shared_entry.path(shared), entry.path(state))
+                    .then_with(|| shared_entry.stage().cmp(&entry.stage()))
+            };
+            while let Some((idx, _)) = shared_entries.next_if(|(_, e)| cmp(e).is_lt()) {
+                out.delete.push(idx);
+            }
+            match shared_entries.next_if(|(_, e)| cmp(e).is_eq()) {
+                Some((idx, shared_entry)) => {
+                    if content_differs(entry, shared_entry) {
+                        out.replace.push(idx);
+                        let start = out.path_backing.len();
+                        out.entries.push(Entry {
+                            path: start..start,
+                            ..entry.clone()
+                        });
+                    }
+                }
+                None => added.push(entry),
+            }
+        }
+        out.delete.extend(shared_entries.map(|(idx, _)| idx));
+
+        out.num_unshared = added.len();
+        for entry in added {
+            let start = out.path_backing.len();
+            out.path_backing.extend_from_slice(entry.path(state));
+            out.entries.push(Entry {
+                path: start..out.path_backing.len(),
+                ..entry.clone()
+            });
+        }
+        out
+    }
+
+    /// Return `true` if so many entries aren't stored in the shared index that a new one should be written,
+    /// according to `max_percent_change`.
+    fn has_too_many_unshared_entries(&self, max_percent_change: u8) -> bool {
+        match max_percent_change {
+            0 => true,
+            max => (self.num_entries as u64) * u64::from(max) < (self.num_unshared as u64) * 100,
+        }
+    }
+
+    /// Turn ourselves into the state of a split index with all extensions of `state` but the file-system monitor,
+    /// linked to the shared index with `shared_index_checksum`.
+    ///
+    /// The file-system monitor extension is dropped as its bitmap refers to positions of entries that aren't stored
+    /// in the split index, so the monitor has to rebuild its state like it would after it was invalidated.
+    fn into_state(self, state: &State, shared_index_checksum: gix_hash::ObjectId) -> State {
+        State {
+            object_hash: state.object_hash,
+            timestamp: state.timestamp,
+            version: state.version,
+            entries: self.entries,
+            path_backing: self.path_backing,
+            is_sparse: state.is_sparse,
+            end_of_index_at_decode_time: state.end_of_index_at_decode_time,
+            offset_table_at_decode_time: state.offset_table_at_decode_time,
+            tree: state.tree.clone(),
+            link: Some(extension::Link {
+                shared_index_checksum,
+                bitmaps: Some(extension::link::Bitmaps {
+                    delete: to_bitmap(&self.delete),
+                    replace: to_bitmap(&self.replace),
+                }),
+            }),
+            resolve_undo: state.resolve_undo.clone(),
+            untracked: state.untracked.clone(),
+            fs_monitor: None,
+        }
+    }
+}
+
+/// Return `true` if the parts of `a` and `b` that are stored on disk differ, ignoring their paths.
+fn content_differs(a: &Entry, b: &Entry) -> bool {
+    let on_disk_flags = entry::Flags::STAGE_MASK
+        | entry::Flags::EXTENDED
+        | entry::Flags::ASSUME_VALID
+        | entry::Flags::INTENT_TO_ADD
+        | entry::Flags::SKIP_WORKTREE;
+    a.id != b.id || a.mode != b.mode || a.stat != b.stat || (a.flags & on_disk_flags) != (b.flags & on_disk_flags)
+}
+
+/// Create a bitmap with the bits at all ascending `positions` set.
+fn to_bitmap(positions: &[usize]) -> gix_bitmap::ewah::Vec {
+    let num_bits = positions.last().map_or(0, |pos| pos + 1);
+    let mut bits = vec![0u64; num_bits.div_ceil(64)];
+    for pos in positions {
+        bits[pos / 64] |= 1 << (pos % 64);
+    }
+    gix_bitmap::ewah::encode(&bits, num_bits.try_into().expect("less than 4 billion entries"))
+}
@@ -58,31 +58,7 @@ pub mod from_tree {
             Find: gix_object::Find,
         {
             let _span = gix_features::trace::coarse!("gix_index::State::from_tree()");
-            let mut delegate = CollectEntries::new(validate);
-            match depthfirst(tree.to_owned(), depthfirst::State::default(), &objects, &mut delegate) {
-                Ok(()) => {}
-                Err(gix_traverse::tree::breadthfirst::Error::Cancelled) => {
-                    let (path, err) = delegate
-                        .invalid_path
-                        .take()
-                        .expect("cancellation only happens on validation error");
-                    return Err(Error::InvalidComponent { path, source: err });
-                }
-                Err(err) => return Err(err.into()),
-            }
-
-            let CollectEntries {
-                entries,
-                path_backing,
-                path: _,
-                path_deque: _,
-                validate: _,
-                invalid_path,
-            } = delegate;
-
-            if let Some((path, err)) = invalid_path {
-                return Err(Error::InvalidComponent { path, source: err });
-            }
+            let (entries, path_backing) = collect_entries(tree, objects, validate)?;
 
             Ok(State {
                 object_hash: tree.kind(),
@@ -102,6 +78,41 @@ pub mod from_tree {
         }
     }
 
+    /// Traverse `tree` recursively with sub-trees accessed through `objects`, and return all non-tree entries
+    /// with paths relative to `tree`, validated with `validate`.
+    pub(crate) fn collect_entries(
+        tree: &gix_hash::oid,
+        objects: impl gix_object::Find,
+        validate: gix_validate::path::component::Options,
+    ) -> Result<(Vec<Entry>, PathStorage), Error> {
+        let mut delegate = CollectEntries::new(validate);
+        match depthfirst(tree.to_owned(), depthfirst::State::default(), &objects, &mut delegate) {
+            Ok(()) => {}
+            Err(gix_traverse::tree::breadthfirst::Error::Cancelled) => {
+                let (path, err) = delegate
+                    .invalid_path
+                    .take()
+                    .expect("cancellation only happens on validation error");
+                return Err(Error::InvalidComponent { path, source: err });
+            }
+            Err(err) => return Err(err.into()),
+        }
+
+        let CollectEntries {
+            entries,
+            path_backing,
+            path: _,
+            path_deque: _,
+            validate: _,
+            invalid_path,
+        } = delegate;
+
+        if let Some((path, err)) = invalid_path {
+            return Err(Error::InvalidComponent { path, source: err });
+        }
+        Ok((entries, path_backing))
+    }
+
     struct CollectEntries {
         entries: Vec<Entry>,
         path_backing: PathStorage,
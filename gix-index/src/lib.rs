@@ -29,6 +29,9 @@ mod access;
 ///
 pub mod init;
 
+///
+pub mod sparse;
+
 ///
 pub mod decode;
 
@@ -38,6 +41,9 @@ pub mod verify;
 ///
 pub mod write;
 
+///
+pub mod write_tree;
+
 pub mod fs;
 
 /// All known versions of a git index file.
@@ -196,6 +202,19 @@ pub(crate) mod util {
         (num, data).into()
     }
 
+    /// Encode `n` into `buf` so that it can be decoded with [`var_int()`], and return the encoded bytes.
+    #[inline]
+    pub fn encode_var_int(mut n: u64, buf: &mut [u8; 10]) -> &[u8] {
+        let mut idx = buf.len() - 1;
+        buf[idx] = (n & 0x7f) as u8;
+        while n > 0x7f {
+            n = (n >> 7) - 1;
+            idx -= 1;
+            buf[idx] = 0x80 | (n & 0x7f) as u8;
+        }
+        &buf[idx..]
+    }
+
     #[inline]
     pub fn read_u32(data: &[u8]) -> Option<(u32, &[u8])> {
         data.split_at_checked(4)
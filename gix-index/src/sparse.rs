@@ -0,0 +1,223 @@
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+
+use crate::{
+    entry::{Flags, Mode, Stage, Stat},
+    extension, init, Entry, PathStorage, State,
+};
+
+///
+pub mod expand {
+    use bstr::BString;
+
+    /// The error returned by [State::expand_sparse_directories()](crate::State::expand_sparse_directories()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not expand the sparse directory at '{path}'")]
+        Traverse {
+            path: BString,
+            source: crate::init::from_tree::Error,
+        },
+    }
+}
+
+///
+pub mod collapse {
+    /// The error returned by [State::collapse_sparse_directories()](crate::State::collapse_sparse_directories()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not write the tree of a sparse directory")]
+        WriteTree(#[from] crate::write_tree::Error),
+    }
+}
+
+/// Sparse indices
+impl State {
+    /// Replace all [sparse directory entries](Mode::DIR) for which `should_expand(directory)` returns `true` with all the
+    /// entries of their tree, accessing the trees with `objects`, and return the amount of expanded directories.
+    ///
+    /// `directory` is the path of the directory without trailing slash.
+    /// Like the sparse directories they replace, all new entries are marked with [`SKIP_WORKTREE`](Flags::SKIP_WORKTREE).
+    /// Note that the index remains [sparse](State::is_sparse()), use [`expand_all_sparse_directories()`](Self::expand_all_sparse_directories())
+    /// to turn it into a regular index.
+    pub fn expand_sparse_directories(
+        &mut self,
+        objects: impl gix_object::Find,
+        mut should_expand: impl FnMut(&BStr) -> bool,
+    ) -> Result<usize, expand::Error> {
+        if !self.entries.iter().any(|e| e.mode.is_sparse()) {
+            return Ok(0);
+        }
+        let _span = gix_features::trace::coarse!("gix_index::State::expand_sparse_directories()");
+        let mut entries = Vec::with_capacity(self.entries.len());
+        let mut path_backing = PathStorage::with_capacity(self.path_backing.len());
+        let mut expanded = Vec::new();
+        for entry in &self.entries {
+            let path = entry.path(self);
+            let dir = path.strip_suffix(b"/").unwrap_or(path).as_bstr();
+            if !entry.mode.is_sparse() || !should_expand(dir) {
+                push_entry(&mut entries, &mut path_backing, entry, path);
+                continue;
+            }
+            let (tree_entries, tree_paths) = init::from_tree::collect_entries(&entry.id, &objects, Default::default())
+                .map_err(|source| expand::Error::Traverse {
+                    path: dir.to_owned(),
+                    source,
+                })?;
+            for tree_entry in tree_entries {
+                let start = path_backing.len();
+                path_backing.extend_from_slice(dir);
+                path_backing.push(b'/');
+                path_backing.extend_from_slice(&tree_paths[tree_entry.path.clone()]);
+                entries.push(Entry {
+                    flags: entry.flags | Flags::SKIP_WORKTREE | Flags::EXTENDED,
+                    path: start..path_backing.len(),
+                    ..tree_entry
+                });
+            }
+            expanded.push(dir.to_owned());
+        }
+
+        if !expanded.is_empty() {
+            self.entries = entries;
+            self.path_backing = path_backing;
+            self.sort_entries();
+            if let Some(tree) = self.tree.as_mut() {
+                for dir in &expanded {
+                    tree.invalidate_path(dir.as_bstr());
+                }
+            }
+            if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+                fs_monitor.mark_all_entries_dirty();
+            }
+        }
+        Ok(expanded.len())
+    }
+
+    /// Replace all [sparse directory entries](Mode::DIR) with the entries of their tree, accessing the trees with `objects`,
+    /// and return the amount of expanded directories.
+    ///
+    /// Afterward, the index isn't [sparse](State::is_sparse()) anymore.
+    pub fn expand_all_sparse_directories(&mut self, objects: impl gix_object::Find) -> Result<usize, expand::Error> {
+        let num_expanded = self.expand_sparse_directories(objects, |_| true)?;
+        self.is_sparse = false;
+        Ok(num_expanded)
+    }
+
+    /// Replace the entries of each outermost directory for which `is_excluded(directory)` returns `true` with a single
+    /// [sparse directory entry](Mode::DIR), which makes this index [sparse](State::is_sparse()), and return the amount of
+    /// created sparse directories.
+    ///
+    /// `directory` is the path of the directory without trailing slash, and it should be `true` if the directory and everything
+    /// in it is outside of the sparse checkout in *cone mode*.
+    /// Like `git`, directories are only collapsed if all their entries are marked with [`SKIP_WORKTREE`](Flags::SKIP_WORKTREE),
+    /// unconflicted and not submodules.
+    ///
+    /// The tree of each collapsed directory is taken from the `TREE` extension if it is valid there, or written to `objects` otherwise.
+    pub fn collapse_sparse_directories(
+        &mut self,
+        objects: impl gix_object::Write,
+        mut is_excluded: impl FnMut(&BStr) -> bool,
+    ) -> Result<usize, collapse::Error> {
+        let _span = gix_features::trace::coarse!("gix_index::State::collapse_sparse_directories()");
+        let mut entries = Vec::with_capacity(self.entries.len());
+        let mut path_backing = PathStorage::with_capacity(self.path_backing.len());
+        let mut collapsed = Vec::<BString>::new();
+        // The length of directories that contain the current entry but can't be collapsed, along with the position of their
+        // first entry past the directory.
+        let mut rejected = Vec::<(usize, usize)>::new();
+        let mut idx = 0;
+        'entries: while idx < self.entries.len() {
+            rejected.retain(|(_, end)| *end > idx);
+            let entry = &self.entries[idx];
+            let path = entry.path(self);
+            let dir_path = path.strip_suffix(b"/").unwrap_or(path).as_bstr();
+            for slash in dir_path.find_iter("/") {
+                if rejected.iter().any(|(len, _)| *len == slash) {
+                    continue;
+                }
+                let dir = dir_path[..slash].as_bstr();
+                let prefix = &path[..=slash];
+                let end =
+                    idx + self.entries[idx..].partition_point(|e| e.path_in(&self.path_backing).starts_with(prefix));
+                let dir_entries = &self.entries[idx..end];
+                if !is_excluded(dir) || !dir_entries.iter().all(is_collapsible) {
+                    rejected.push((slash, end));
+                    continue;
+                }
+
+                let id = match valid_tree_id(self.tree.as_ref(), dir) {
+                    Some(id) => id,
+                    None => {
+                        self.write_tree_recursive(
+                            dir_entries,
+                            prefix.len(),
+                            Default::default(),
+                            None,
+                            &objects,
+                            &|_| false,
+                        )?
+                        .id
+                    }
+                };
+                let start = path_backing.len();
+                path_backing.extend_from_slice(prefix);
+                entries.push(Entry {
+                    stat: Stat::default(),
+                    id,
+                    flags: Flags::SKIP_WORKTREE | Flags::EXTENDED,
+                    mode: Mode::DIR,
+                    path: start..path_backing.len(),
+                });
+                collapsed.push(dir.to_owned());
+                idx = end;
+                continue 'entries;
+            }
+            push_entry(&mut entries, &mut path_backing, entry, path);
+            idx += 1;
+        }
+
+        if !collapsed.is_empty() {
+            self.entries = entries;
+            self.path_backing = path_backing;
+            if let Some(tree) = self.tree.as_mut() {
+                for dir in &collapsed {
+                    tree.invalidate_path(dir.as_bstr());
+                }
+            }
+            if let Some(fs_monitor) = self.fs_monitor.as_mut() {
+                fs_monitor.mark_all_entries_dirty();
+            }
+        }
+        self.is_sparse = true;
+        Ok(collapsed.len())
+    }
+}
+
+/// Return `true` if `entry` may be part of a sparse directory.
+fn is_collapsible(entry: &Entry) -> bool {
+    entry.stage() == Stage::Unconflicted
+        && entry.flags.contains(Flags::SKIP_WORKTREE)
+        && !entry.flags.intersects(Flags::INTENT_TO_ADD | Flags::REMOVE)
+        && !entry.mode.is_submodule()
+        && entry.mode.to_tree_entry_mode().is_some()
+}
+
+fn push_entry(entries: &mut Vec<Entry>, path_backing: &mut PathStorage, entry: &Entry, path: &BStr) {
+    let start = path_backing.len();
+    path_backing.push_str(path);
+    entries.push(Entry {
+        path: start..path_backing.len(),
+        ..entry.clone()
+    });
+}
+
+/// Return the id of the tree at `dir` in the `TREE` extension `tree`, if it's still valid.
+fn valid_tree_id(tree: Option<&extension::Tree>, dir: &BStr) -> Option<gix_hash::ObjectId> {
+    let mut tree = tree?;
+    for component in dir.split_str("/") {
+        tree = tree.children.iter().find(|child| child.name.as_slice() == component)?;
+    }
+    tree.num_entries.is_some().then_some(tree.id)
+}
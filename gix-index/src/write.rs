@@ -1,4 +1,4 @@
-use std::io::Write;
+use std::{borrow::Cow, io::Write};
 
 use crate::{entry, extension, write::util::CountBytes, State, Version};
 
@@ -17,6 +17,12 @@ pub enum Extensions {
     Given {
         /// Write the tree-cache extension, if present.
         tree_cache: bool,
+        /// Write the resolve-undo extension, if present.
+        resolve_undo: bool,
+        /// Write the untracked-cache extension, if present.
+        untracked_cache: bool,
+        /// Write the filesystem-monitor extension, if present.
+        fs_monitor: bool,
         /// Write the end-of-index-entry extension.
         end_of_index_entry: bool,
     },
@@ -32,9 +38,15 @@ impl Extensions {
             Extensions::All => Some(signature),
             Extensions::Given {
                 tree_cache,
+                resolve_undo,
+                untracked_cache,
+                fs_monitor,
                 end_of_index_entry,
             } => match signature {
                 extension::tree::SIGNATURE => tree_cache,
+                extension::resolve_undo::SIGNATURE => resolve_undo,
+                extension::untracked_cache::SIGNATURE => untracked_cache,
+                extension::fs_monitor::SIGNATURE => fs_monitor,
                 extension::end_of_index_entry::SIGNATURE => end_of_index_entry,
                 _ => &false,
             }
@@ -45,7 +57,8 @@ impl Extensions {
 
 /// The options for use when [writing an index][State::write_to()].
 ///
-/// Note that default options write either index V2 or V3 depending on the content of the entries.
+/// Note that default options write either index V2 or V3 depending on the content of the entries,
+/// unless the [version](State::version()) is V4, which is retained.
 #[derive(Debug, Default, Clone, Copy)]
 pub struct Options {
     /// Configures which extensions to write.
@@ -56,6 +69,38 @@ pub struct Options {
     /// via [`File::write()`](crate::File::write()) and [`File::write_to()`](crate::File::write_to()).
     /// Note that
     pub skip_hash: bool,
+    /// If set, write a split index that only stores the changes compared to a shared index, which is created as needed.
+    ///
+    /// This value is typically controlled by `core.splitIndex` and only respected when the index is written
+    /// via [`File::write()`](crate::File::write()), as the shared index is written next to it.
+    pub split_index: Option<SplitIndex>,
+}
+
+/// Configure how a split index is written by [`File::write()`](crate::File::write()).
+#[derive(Debug, Clone, Copy)]
+pub struct SplitIndex {
+    /// The percentage of entries that may be stored only in the split index before a new shared index with all entries
+    /// is written. `0` writes a new shared index each time, and `100` or more never writes a new one unless there is none.
+    ///
+    /// This value is typically controlled by `splitIndex.maxPercentChange`.
+    pub max_percent_change: u8,
+    /// Shared indices that weren't used since this time are removed when a new shared index is written,
+    /// or `None` to never remove them.
+    ///
+    /// This value is typically controlled by `splitIndex.sharedIndexExpire`.
+    pub shared_index_expire: Option<std::time::SystemTime>,
+}
+
+impl Default for SplitIndex {
+    /// Use the same defaults as `git`, with a new shared index being written after 20% of the entries changed and unused
+    /// shared indices expiring after two weeks.
+    fn default() -> Self {
+        SplitIndex {
+            max_percent_change: 20,
+            shared_index_expire: std::time::SystemTime::now()
+                .checked_sub(std::time::Duration::from_secs(14 * 24 * 60 * 60)),
+        }
+    }
 }
 
 impl State {
@@ -66,6 +111,7 @@ impl State {
         Options {
             extensions,
             skip_hash: _,
+            split_index: _,
         }: Options,
     ) -> Result<Version, gix_hash::io::Error> {
         let _span = gix_features::trace::detail!("gix_index::State::write()");
@@ -85,9 +131,18 @@ impl State {
             .try_into()
             .expect("definitely not too many entries");
 
-        let offset_to_entries = header(&mut write, version, num_entries - removed_entries)?;
-        let offset_to_extensions = entries(&mut write, self, offset_to_entries)?;
-        let (extension_toc, out) = self.write_extensions(write, offset_to_extensions, extensions)?;
+        let num_written_entries = num_entries - removed_entries;
+        let offset_to_entries = header(&mut write, version, num_written_entries)?;
+        let offset_to_extensions = entries(&mut write, self, version, offset_to_entries)?;
+        let (untracked, fs_monitor) = self.extensions_without_removed_entries(removed_entries);
+        let (extension_toc, out) = self.write_extensions(
+            write,
+            offset_to_extensions,
+            extensions,
+            num_written_entries,
+            untracked.as_deref(),
+            fs_monitor.as_deref(),
+        )?;
 
         if num_entries > 0
             && extensions
@@ -106,17 +161,47 @@ impl State {
         mut write: CountBytes<T>,
         offset_to_extensions: u32,
         extensions: Extensions,
+        num_written_entries: u32,
+        untracked: Option<&extension::UntrackedCache>,
+        fs_monitor: Option<&extension::FsMonitor>,
     ) -> std::io::Result<(Vec<(extension::Signature, u32)>, T)>
     where
         T: std::io::Write,
     {
         type WriteExtFn<'a> = &'a dyn Fn(&mut dyn std::io::Write) -> Option<std::io::Result<extension::Signature>>;
         let extensions: &[WriteExtFn<'_>] = &[
+            &|write| {
+                self.link()
+                    .filter(|link| link.bitmaps.is_some())
+                    .map(|link| link.write_to(write).map(|_| extension::link::SIGNATURE))
+            },
             &|write| {
                 extensions
                     .should_write(extension::tree::SIGNATURE)
                     .and_then(|signature| self.tree().map(|tree| tree.write_to(write).map(|_| signature)))
             },
+            &|write| {
+                extensions
+                    .should_write(extension::resolve_undo::SIGNATURE)
+                    .and_then(|signature| {
+                        self.resolve_undo()
+                            .map(|paths| extension::resolve_undo::write_to(paths, write).map(|_| signature))
+                    })
+            },
+            &|write| {
+                extensions
+                    .should_write(extension::untracked_cache::SIGNATURE)
+                    .and_then(|signature| {
+                        untracked.map(|cache| cache.write_to(write, self.object_hash).map(|_| signature))
+                    })
+            },
+            &|write| {
+                extensions
+                    .should_write(extension::fs_monitor::SIGNATURE)
+                    .and_then(|signature| {
+                        fs_monitor.map(|fs_monitor| fs_monitor.write_to(write, num_written_entries).map(|_| signature))
+                    })
+            },
             &|write| {
                 self.is_sparse()
                     .then(|| extension::sparse::write_to(write).map(|_| extension::sparse::SIGNATURE))
@@ -124,7 +209,7 @@ impl State {
         ];
 
         let mut offset_to_previous_ext = offset_to_extensions;
-        let mut out = Vec::with_capacity(5);
+        let mut out = Vec::with_capacity(6);
         for write_ext in extensions {
             if let Some(signature) = write_ext(&mut write).transpose()? {
                 let offset_past_ext = write.count;
@@ -138,7 +223,41 @@ impl State {
 }
 
 impl State {
+    /// Return the untracked-cache and filesystem-monitor extensions as they have to be written if `removed_entries`
+    /// entries are marked for removal, as these aren't written and thus change the set of entries.
+    fn extensions_without_removed_entries(
+        &self,
+        removed_entries: u32,
+    ) -> (
+        Option<Cow<'_, extension::UntrackedCache>>,
+        Option<Cow<'_, extension::FsMonitor>>,
+    ) {
+        if removed_entries == 0 {
+            return (
+                self.untracked().map(Cow::Borrowed),
+                self.fs_monitor().map(Cow::Borrowed),
+            );
+        }
+        let untracked = self.untracked().map(|untracked| {
+            let mut untracked = untracked.clone();
+            for entry in self.entries.iter().filter(|e| e.flags.contains(entry::Flags::REMOVE)) {
+                untracked.invalidate_path(entry.path(self));
+            }
+            Cow::Owned(untracked)
+        });
+        let fs_monitor = self.fs_monitor().map(|fs_monitor| {
+            let mut fs_monitor = fs_monitor.clone();
+            fs_monitor.mark_all_entries_dirty();
+            Cow::Owned(fs_monitor)
+        });
+        (untracked, fs_monitor)
+    }
+
+    /// Like `git`, keep V4 as it can represent all entries, and otherwise use the lowest version that can.
     fn detect_required_version(&self) -> Version {
+        if self.version == Version::V4 {
+            return Version::V4;
+        }
         self.entries
             .iter()
             .find_map(|e| e.flags.contains(entry::Flags::EXTENDED).then_some(Version::V3))
@@ -164,11 +283,22 @@ fn header<T: std::io::Write>(
     Ok(out.count)
 }
 
-fn entries<T: std::io::Write>(out: &mut CountBytes<T>, state: &State, header_size: u32) -> Result<u32, std::io::Error> {
+fn entries<T: std::io::Write>(
+    out: &mut CountBytes<T>,
+    state: &State,
+    version: Version,
+    header_size: u32,
+) -> Result<u32, std::io::Error> {
+    let mut previous = None;
     for entry in state.entries() {
         if entry.flags.contains(entry::Flags::REMOVE) {
             continue;
         }
+        if version == Version::V4 {
+            entry.write_to_v4(&mut *out, state, previous)?;
+            previous = Some(entry);
+            continue;
+        }
         entry.write_to(&mut *out, state)?;
         match (out.count - header_size) % 8 {
             0 => {}
@@ -0,0 +1,157 @@
+use bstr::{BStr, BString, ByteSlice};
+use smallvec::SmallVec;
+
+use crate::{
+    entry::{Flags, Stage},
+    extension, Entry, State,
+};
+
+/// The error returned by [State::write_tree()].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Cannot write a tree as '{path}' has conflicts")]
+    Unmerged { path: BString },
+    #[error("Could not write the tree at '{path}'")]
+    WriteTree {
+        path: BString,
+        source: gix_object::write::Error,
+    },
+}
+
+/// Trees
+impl State {
+    /// Write the trees of all entries to `objects` and return the id of the root tree, like `git write-tree` does.
+    ///
+    /// Trees that are still valid in the `TREE` extension and exist in `objects` are reused instead of being written again.
+    /// Afterward, the `TREE` extension contains all written trees, so it can be written back along with the index.
+    /// Entries that are only [intended to be added](Flags::INTENT_TO_ADD) or [to be removed](Flags::REMOVE) are left out,
+    /// but the trees containing them are kept invalid.
+    ///
+    /// Note that entries must be sorted and unconflicted. Changes to entries made through mutable access, like with
+    /// [`entries_mut()`](Self::entries_mut()), must be followed by a call to [`invalidate_tree_at()`](Self::invalidate_tree_at())
+    /// or the `TREE` extension will be outdated. Methods that add or remove entries do this automatically.
+    pub fn write_tree(
+        &mut self,
+        objects: impl gix_object::Write + gix_object::Exists,
+    ) -> Result<gix_hash::ObjectId, Error> {
+        let _span = gix_features::trace::coarse!("gix_index::State::write_tree()");
+        let tree = self.write_tree_recursive(
+            &self.entries,
+            0,
+            Default::default(),
+            self.tree.as_ref(),
+            &objects,
+            &|id| objects.exists(id),
+        )?;
+        let id = tree.id;
+        self.tree = Some(tree);
+        Ok(id)
+    }
+
+    /// Invalidate the trees leading up to the entry at `path` in the `TREE` extension, and remove the tree at `path` if
+    /// there is one. This must be done whenever an entry changes, so [trees can be written](Self::write_tree()) correctly.
+    pub fn invalidate_tree_at(&mut self, path: &BStr) {
+        if let Some(tree) = self.tree.as_mut() {
+            tree.invalidate_path(path);
+        }
+    }
+
+    /// Write the tree named `name` made of `entries`, whose paths all start with the same directory of `prefix_len` bytes,
+    /// to `objects`, unless `cached` is the still valid tree of these entries and it `exists`.
+    pub(crate) fn write_tree_recursive(
+        &self,
+        entries: &[Entry],
+        prefix_len: usize,
+        name: SmallVec<[u8; 23]>,
+        cached: Option<&extension::Tree>,
+        objects: &impl gix_object::Write,
+        exists: &dyn Fn(&gix_hash::oid) -> bool,
+    ) -> Result<extension::Tree, Error> {
+        use gix_object::tree::EntryKind;
+
+        let num_entries = u32::try_from(entries.len()).ok();
+        if let Some(cached) = cached.filter(|tree| tree.num_entries.is_some() && tree.num_entries == num_entries) {
+            if exists(&cached.id) {
+                return Ok(extension::Tree { name, ..cached.clone() });
+            }
+        }
+
+        let mut tree = gix_object::Tree::empty();
+        let mut children = Vec::new();
+        let mut is_valid = true;
+        let mut idx = 0;
+        while idx < entries.len() {
+            let entry = &entries[idx];
+            let path = entry.path(self);
+            if entry.stage() != Stage::Unconflicted {
+                return Err(Error::Unmerged { path: path.to_owned() });
+            }
+            let rela_path = &path[prefix_len..];
+            let (filename, mode, oid) = match rela_path.find_byte(b'/') {
+                Some(slash) if slash + 1 == rela_path.len() => {
+                    idx += 1;
+                    (&rela_path[..slash], EntryKind::Tree.into(), entry.id)
+                }
+                Some(slash) => {
+                    let dir = &rela_path[..=slash];
+                    let end = idx
+                        + entries[idx..].partition_point(|e| e.path(self)[prefix_len..].starts_with(dir.as_bytes()));
+                    let dir_name = &rela_path[..slash];
+                    let child = self.write_tree_recursive(
+                        &entries[idx..end],
+                        prefix_len + dir.len(),
+                        dir_name.as_bytes().into(),
+                        cached.and_then(|tree| tree.children.iter().find(|child| child.name.as_slice() == dir_name)),
+                        objects,
+                        exists,
+                    )?;
+                    idx = end;
+                    is_valid &= child.num_entries.is_some();
+                    let id = child.id;
+                    children.push(child);
+                    // Directories with only left-out entries don't exist in the tree.
+                    if id.is_empty_tree() {
+                        continue;
+                    }
+                    (dir_name, EntryKind::Tree.into(), id)
+                }
+                None => {
+                    idx += 1;
+                    if entry.flags.intersects(Flags::INTENT_TO_ADD | Flags::REMOVE) {
+                        is_valid = false;
+                        continue;
+                    }
+                    let Some(mode) = entry.mode.to_tree_entry_mode() else {
+                        is_valid = false;
+                        continue;
+                    };
+                    (rela_path, mode, entry.id)
+                }
+            };
+            tree.entries.push(gix_object::tree::Entry {
+                mode,
+                filename: filename.into(),
+                oid,
+            });
+        }
+        tree.entries.sort();
+        let id = objects.write(&tree).map_err(|source| Error::WriteTree {
+            path: path_of_directory(entries.first().map(|e| e.path(self)), prefix_len),
+            source,
+        })?;
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(extension::Tree {
+            name,
+            id,
+            num_entries: num_entries.filter(|_| is_valid),
+            children,
+        })
+    }
+}
+
+fn path_of_directory(entry_path: Option<&BStr>, prefix_len: usize) -> BString {
+    entry_path
+        .map(|path| path[..prefix_len.saturating_sub(1)].into())
+        .unwrap_or_default()
+}
@@ -0,0 +1,21 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+
+touch a b
+mkdir c1
+(cd c1 && touch a b && mkdir c2 && cd c2 && touch a b)
+(cd c1 && mkdir c3 && cd c3 && echo c3 > a && touch b)
+mkdir d
+(cd d && touch a b && mkdir c4 && cd c4 && touch a b && echo c5 > c5)
+
+git add .
+git commit -m "init"
+
+git sparse-checkout set c1/c2 --sparse-index
+cp .git/index .git/sparse-index
+
+git sparse-checkout reapply --no-sparse-index
+mv .git/index .git/full-index
+mv .git/sparse-index .git/index
@@ -289,6 +289,64 @@ fn remove_entry_at_index() {
     assert_eq!(file.entries().len(), 0);
 }
 
+#[test]
+fn upsert_entry() {
+    let mut file = Fixture::Generated("v4_more_files_IEOT").open();
+    let num_entries = file.entries().len();
+    let entry = file.entry(0).clone();
+    let id = gix_hash::ObjectId::empty_blob(gix_hash::Kind::Sha1);
+
+    let idx = file.upsert_entry(entry.stat, id, entry.flags, entry.mode, "d/b".into());
+    assert_eq!(file.entries().len(), num_entries, "existing entries are updated");
+    assert_eq!(file.entry(idx).path(&file), "d/b");
+    assert_eq!(file.entry(idx).id, id);
+
+    let idx = file.upsert_entry(entry.stat, id, entry.flags, entry.mode, "d/bb".into());
+    assert_eq!(file.entries().len(), num_entries + 1, "new entries are inserted");
+    assert_eq!(file.entry(idx).path(&file), "d/bb");
+    assert!(file.verify_entries().is_ok(), "the sort order is maintained");
+    assert!(
+        file.tree().expect("present").num_entries.is_none(),
+        "the tree extension is invalidated"
+    );
+
+    file.upsert_entry(entry.stat, id, entry.flags, entry.mode, "d/last".into());
+    check_prefix(&file, "d/", &["d/a", "d/b", "d/bb", "d/c", "d/last"]);
+    file.upsert_entry(entry.stat, id, entry.flags, entry.mode, "d/last/new".into());
+    check_prefix(&file, "d/", &["d/a", "d/b", "d/bb", "d/c", "d/last/new"]);
+    assert!(file.verify_entries().is_ok());
+}
+
+#[test]
+fn upsert_entry_resolves_conflicts() {
+    let mut file = Fixture::Loose("conflicting-file").open();
+    let entry = file.entry(0).clone();
+
+    let theirs = gix_index::entry::Flags::from_stage(Stage::Theirs);
+    let idx = file.upsert_entry(entry.stat, entry.id, theirs, entry.mode, "file".into());
+    assert_eq!(file.entries().len(), 3, "the existing stage is updated");
+    assert_eq!(file.entry(idx).stage(), Stage::Theirs);
+
+    let idx = file.upsert_entry(
+        entry.stat,
+        entry.id,
+        gix_index::entry::Flags::empty(),
+        entry.mode,
+        "file".into(),
+    );
+    assert_eq!(idx, 0);
+    assert_eq!(file.entries().len(), 1, "all other stages are removed");
+    assert_eq!(file.entry(0).stage(), Stage::Unconflicted);
+}
+
+#[test]
+fn remove_entries_by_path() {
+    let mut file = Fixture::Loose("conflicting-file").open();
+    assert_eq!(file.remove_entries_by_path("foo".into()), 0);
+    assert_eq!(file.remove_entries_by_path("file".into()), 3, "all stages are removed");
+    assert!(file.entries().is_empty());
+}
+
 #[test]
 fn sort_entries() {
     let mut file = Fixture::Generated("v4_more_files_IEOT").open();
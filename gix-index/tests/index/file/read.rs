@@ -348,9 +348,17 @@ fn split_index_and_regular_index_of_same_content_are_indeed_the_same() {
         .unwrap(),
     );
 
+    let link = split.link().expect("the link to the shared index is retained");
     assert!(
-        split.link().is_none(),
-        "link extension is dissolved, merging the shared index permanently into the split one (for now)"
+        link.bitmaps.is_none(),
+        "the shared index is merged into the split one, which consumes the bitmaps"
+    );
+    assert!(
+        split
+            .path()
+            .with_file_name(format!("sharedindex.{}", link.shared_index_checksum))
+            .is_file(),
+        "it points to the shared index it was merged with"
     );
 
     let regular = verify(
@@ -14,9 +14,17 @@ fn roundtrips() -> crate::Result {
             Generated("v2"),
             options_with(write::Extensions::Given {
                 tree_cache: true,
+                resolve_undo: false,
+                untracked_cache: false,
+                fs_monitor: false,
                 end_of_index_entry: true,
             }),
         ),
+        (Loose("REUC"), all_ext_but_eoie()),
+        (Loose("UNTR"), all_ext_but_eoie()),
+        (Loose("UNTR-with-oids"), all_ext_but_eoie()),
+        (Loose("FSMN"), all_ext_but_eoie()),
+        (Generated("v4_more_files"), all_ext_but_eoie()),
         (Generated("v2_empty"), only_tree_ext()),
         (Generated("v2_more_files"), only_tree_ext()),
         (Generated("v2_all_file_kinds"), only_tree_ext()),
@@ -48,6 +56,7 @@ fn skip_hash() -> crate::Result {
     expected.write(Options {
         extensions: Default::default(),
         skip_hash: false,
+        split_index: None,
     })?;
 
     let actual = gix_index::File::at(
@@ -65,6 +74,7 @@ fn skip_hash() -> crate::Result {
     expected.write(Options {
         extensions: Default::default(),
         skip_hash: true,
+        split_index: None,
     })?;
 
     let actual = gix_index::File::at(
@@ -133,9 +143,8 @@ fn state_comparisons_with_various_extension_configurations() {
         Generated("v2_more_files"),
         Generated("v2_all_file_kinds"),
         Generated("v2_split_index"),
-        // TODO: this fails because git allows to configure the index version while gitoxide doesn't
-        //       the fixture artificially sets the version to V4 and gitoxide writes it back out as the lowest required version, V2
-        // Generated("v4_more_files_IEOT"),
+        Generated("v4_more_files_IEOT"),
+        Generated("v4_more_files"),
         Generated("v3_skip_worktree"),
         Generated("v3_added_files"),
         Generated("v3_sparse_index_non_cone"),
@@ -148,10 +157,16 @@ fn state_comparisons_with_various_extension_configurations() {
             options_with(write::Extensions::All),
             options_with(write::Extensions::Given {
                 tree_cache: true,
+                resolve_undo: true,
+                untracked_cache: true,
+                fs_monitor: true,
                 end_of_index_entry: false,
             }),
             options_with(write::Extensions::Given {
                 tree_cache: false,
+                resolve_undo: false,
+                untracked_cache: false,
+                fs_monitor: false,
                 end_of_index_entry: true,
             }),
         ] {
@@ -210,6 +225,130 @@ fn remove_flag_is_respected() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn split_index() -> crate::Result {
+    let tmp = gix_testtools::scripted_fixture_writable_standalone(
+        std::path::Path::new("make_index").join("v2_split_vs_regular_index.sh"),
+    )?;
+    let git_dir = tmp.path().join("split/.git");
+    let open = || gix_index::File::at(git_dir.join("index"), gix_hash::Kind::Sha1, false, Default::default());
+    let read_state = || -> crate::Result<State> {
+        let data = std::fs::read(git_dir.join("index"))?;
+        Ok(State::from_bytes(&data, FileTime::now(), gix_hash::Kind::Sha1, Default::default())?.0)
+    };
+    fn entries(index: &State) -> Vec<(bstr::BString, gix_hash::ObjectId, entry::Stat, entry::Flags)> {
+        index
+            .entries()
+            .iter()
+            .map(|e| (e.path(index).to_owned(), e.id, e.stat, e.flags))
+            .collect()
+    }
+    let shared_indices = || -> std::io::Result<usize> {
+        Ok(std::fs::read_dir(&git_dir)?
+            .filter_map(Result::ok)
+            .filter(|e| e.file_name().to_string_lossy().starts_with("sharedindex."))
+            .count())
+    };
+    let git_ls_files = |repo: &str| -> crate::Result<Vec<u8>> {
+        let out = std::process::Command::new("git")
+            .args(["ls-files", "--stage"])
+            .current_dir(tmp.path().join(repo))
+            .output()?;
+        assert!(out.status.success(), "git can read the index");
+        Ok(out.stdout)
+    };
+
+    let mut index = open()?;
+    let shared_index_checksum = index.link().expect("split index").shared_index_checksum;
+    index.entries_mut()[0].stat.size += 1;
+    index.write(Options {
+        split_index: Some(write::SplitIndex {
+            max_percent_change: 100,
+            shared_index_expire: None,
+        }),
+        ..Default::default()
+    })?;
+    assert_eq!(
+        index.link().map(|link| link.shared_index_checksum),
+        Some(shared_index_checksum),
+        "the shared index is reused as it may contain any amount of changes"
+    );
+    let split = read_state()?;
+    let bitmaps = split
+        .link()
+        .and_then(|link| link.bitmaps.as_ref())
+        .expect("bitmaps are written");
+    assert_eq!(
+        split.entries().len(),
+        bitmaps
+            .replace
+            .to_bits()
+            .iter()
+            .map(|w| w.count_ones() as usize)
+            .sum::<usize>()
+            + 2,
+        "only replaced entries and the two added ones are stored"
+    );
+    assert_eq!(
+        entries(&*open()?),
+        entries(&index),
+        "the split index is read back the same"
+    );
+    assert_eq!(git_ls_files("split")?, git_ls_files("regular")?);
+
+    index.write(Options {
+        split_index: Some(write::SplitIndex {
+            max_percent_change: 0,
+            shared_index_expire: Some(std::time::SystemTime::now() + std::time::Duration::from_secs(60)),
+        }),
+        ..Default::default()
+    })?;
+    let new_shared_index_checksum = index.link().expect("still split").shared_index_checksum;
+    assert_ne!(
+        new_shared_index_checksum, shared_index_checksum,
+        "a new shared index is written if there are too many changes"
+    );
+    assert_eq!(shared_indices()?, 1, "the previous shared index has expired");
+    let split = read_state()?;
+    assert_eq!(split.entries().len(), 0, "all entries are in the shared index");
+    assert_eq!(entries(&*open()?), entries(&index));
+    assert_eq!(git_ls_files("split")?, git_ls_files("regular")?);
+
+    index.write(Default::default())?;
+    assert!(
+        index.link().is_none(),
+        "without split index options, all entries are written"
+    );
+    assert!(read_state()?.link().is_none());
+    assert_eq!(entries(&*open()?), entries(&index));
+    Ok(())
+}
+
+#[test]
+fn split_index_drops_fs_monitor_extension() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let path = tmp.path().join("index");
+    std::fs::copy(crate::loose_file_path("FSMN"), &path)?;
+    let mut index = gix_index::File::at(&path, gix_hash::Kind::Sha1, false, Default::default())?;
+    assert!(index.fs_monitor().is_some(), "precondition");
+
+    index.write(Options {
+        extensions: write::Extensions::All,
+        split_index: Some(write::SplitIndex {
+            max_percent_change: 20,
+            shared_index_expire: None,
+        }),
+        ..Default::default()
+    })?;
+    let split = gix_index::File::at(&path, gix_hash::Kind::Sha1, false, Default::default())?;
+    assert!(split.link().is_some(), "a split index was written");
+    assert!(
+        split.fs_monitor().is_none(),
+        "its positions would refer to entries that aren't stored in the split index"
+    );
+    Ok(())
+}
+
 fn compare_states_against_baseline(
     actual: &State,
     actual_version: Version,
@@ -293,14 +432,29 @@ fn only_tree_ext() -> Options {
         extensions: write::Extensions::Given {
             end_of_index_entry: false,
             tree_cache: true,
+            resolve_undo: false,
+            untracked_cache: false,
+            fs_monitor: false,
         },
         skip_hash: false,
+        split_index: None,
     }
 }
 
+fn all_ext_but_eoie() -> Options {
+    options_with(write::Extensions::Given {
+        tree_cache: true,
+        resolve_undo: true,
+        untracked_cache: true,
+        fs_monitor: true,
+        end_of_index_entry: false,
+    })
+}
+
 fn options_with(extensions: write::Extensions) -> Options {
     Options {
         extensions,
         skip_hash: false,
+        split_index: None,
     }
 }
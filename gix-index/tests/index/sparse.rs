@@ -0,0 +1,155 @@
+use gix_index::{
+    entry::{Flags, Mode},
+    State,
+};
+use gix_object::Exists;
+
+use crate::index::fixture_index_path;
+
+fn sparse_and_full_index() -> crate::Result<(gix_index::File, gix_index::File, gix_odb::Handle)> {
+    let sparse_path = fixture_index_path("v3_sparse_index_and_full_index");
+    let git_dir = sparse_path.parent().expect("index in .git dir");
+    let sparse = gix_index::File::at(&sparse_path, gix_hash::Kind::Sha1, false, Default::default())?;
+    let full = gix_index::File::at(
+        git_dir.join("full-index"),
+        gix_hash::Kind::Sha1,
+        false,
+        Default::default(),
+    )?;
+    let odb = gix_odb::at(git_dir.join("objects"))?;
+    Ok((sparse, full, odb))
+}
+
+fn assert_entries_eq(actual: &State, expected: &State) {
+    actual.verify_entries().expect("entries are sorted");
+    let entries = |state: &State| {
+        state
+            .entries()
+            .iter()
+            .map(|e| (e.path(state).to_owned(), e.id, e.mode, e.flags & !Flags::UPTODATE))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(entries(actual), entries(expected));
+}
+
+#[test]
+fn expand_all_sparse_directories_matches_full_index() -> crate::Result {
+    let (mut sparse, full, odb) = sparse_and_full_index()?;
+    assert!(sparse.is_sparse());
+    assert_eq!(sparse.entries().iter().filter(|e| e.mode == Mode::DIR).count(), 2);
+
+    assert_eq!(sparse.expand_all_sparse_directories(&odb)?, 2);
+    assert!(!sparse.is_sparse());
+    assert_entries_eq(&sparse, &full);
+    sparse.verify_extensions(false, gix_object::find::Never)?;
+
+    assert_eq!(
+        sparse.expand_all_sparse_directories(&odb)?,
+        0,
+        "there is nothing left to expand"
+    );
+    Ok(())
+}
+
+#[test]
+fn expand_selected_sparse_directories() -> crate::Result {
+    let (mut sparse, _full, odb) = sparse_and_full_index()?;
+    assert_eq!(sparse.expand_sparse_directories(&odb, |dir| dir == "d")?, 1);
+    assert!(sparse.is_sparse(), "there is still the sparse directory c1/c3");
+
+    let paths: Vec<_> = sparse
+        .entries()
+        .iter()
+        .filter(|e| e.flags.contains(Flags::SKIP_WORKTREE))
+        .map(|e| (e.path(&sparse).to_string(), e.mode))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("c1/c3/".into(), Mode::DIR),
+            ("d/a".into(), Mode::FILE),
+            ("d/b".into(), Mode::FILE),
+            ("d/c4/a".into(), Mode::FILE),
+            ("d/c4/b".into(), Mode::FILE),
+            ("d/c4/c5".into(), Mode::FILE),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn collapse_sparse_directories_matches_sparse_index() -> crate::Result {
+    let (sparse, mut full, _odb) = sparse_and_full_index()?;
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let objects = gix_odb::at(tmp.path())?;
+
+    assert!(!full.is_sparse());
+    let num_collapsed = full.collapse_sparse_directories(&objects, |_dir| true)?;
+    assert_eq!(
+        num_collapsed, 2,
+        "only directories whose entries are all skipped can be collapsed, which excludes `c1`"
+    );
+    assert!(full.is_sparse());
+    assert_entries_eq(&full, &sparse);
+    full.verify_extensions(false, gix_object::find::Never)?;
+
+    assert_eq!(
+        full.collapse_sparse_directories(&objects, |_dir| true)?,
+        0,
+        "everything that can be collapsed was collapsed"
+    );
+    Ok(())
+}
+
+#[test]
+fn expanded_directories_can_be_collapsed_by_writing_their_trees() -> crate::Result {
+    let (mut sparse, _full, odb) = sparse_and_full_index()?;
+    let expected = sparse.clone();
+    sparse.expand_all_sparse_directories(&odb)?;
+
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let objects = gix_odb::at(tmp.path())?;
+    assert_eq!(
+        sparse.collapse_sparse_directories(&objects, |dir| dir == "d" || dir == "c1/c3")?,
+        2
+    );
+    assert_entries_eq(&sparse, &expected);
+    assert!(
+        objects.exists(
+            &sparse
+                .entries()
+                .iter()
+                .find(|e| e.mode == Mode::DIR)
+                .expect("present")
+                .id
+        ),
+        "trees of the collapsed directories were written as they are not in the TREE extension anymore"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicting_entries_prevent_collapsing() -> crate::Result {
+    let (_sparse, mut full, _odb) = sparse_and_full_index()?;
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let objects = gix_odb::at(tmp.path())?;
+
+    let entry = full
+        .entry_mut_by_path_and_stage("d/c4/a".into(), gix_index::entry::Stage::Unconflicted)
+        .expect("present");
+    entry.flags.insert(Flags::from_stage(gix_index::entry::Stage::Ours));
+    assert_eq!(full.collapse_sparse_directories(&objects, |_dir| true)?, 1);
+
+    let dirs: Vec<_> = full
+        .entries()
+        .iter()
+        .filter(|e| e.mode == Mode::DIR)
+        .map(|e| e.path(&full).to_string())
+        .collect();
+    assert_eq!(
+        dirs,
+        ["c1/c3/"],
+        "d/c4 contains a conflict, so neither it nor d can be collapsed"
+    );
+    Ok(())
+}
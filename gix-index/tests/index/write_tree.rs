@@ -0,0 +1,120 @@
+use gix_index::entry::{Flags, Stage};
+
+use crate::index::{fixture_index_path, hex_to_id};
+
+fn index_and_odb() -> crate::Result<(gix_index::File, gix_odb::memory::Proxy<gix_odb::Handle>)> {
+    let path = fixture_index_path("v2_deeper_tree");
+    let git_dir = path.parent().expect("index in .git dir");
+    let index = gix_index::File::at(&path, gix_hash::Kind::Sha1, false, Default::default())?;
+    let odb = gix_odb::memory::Proxy::new(gix_odb::at(git_dir.join("objects"))?, gix_hash::Kind::Sha1);
+    Ok((index, odb))
+}
+
+#[test]
+fn without_tree_extension_all_trees_are_written() -> crate::Result {
+    let (mut index, odb) = index_and_odb()?;
+    let expected = index.remove_tree().expect("written by git");
+
+    let id = index.write_tree(&odb)?;
+    assert_eq!(id, expected.id, "the tree is the same as the one written by git");
+    assert_eq!(
+        odb.num_objects_in_memory(),
+        7,
+        "all 8 trees, with `d/nested` and `sub/a` being the same"
+    );
+    assert_eq!(
+        index.tree(),
+        Some(&expected),
+        "the TREE extension is the same as the one written by git"
+    );
+    index.verify_extensions(true, &odb)?;
+    Ok(())
+}
+
+#[test]
+fn valid_trees_are_reused() -> crate::Result {
+    let (mut index, odb) = index_and_odb()?;
+    let expected = index.tree().expect("written by git").id;
+    assert_eq!(index.write_tree(&odb)?, expected);
+    assert_eq!(
+        odb.num_objects_in_memory(),
+        0,
+        "the TREE extension is valid, so nothing is written"
+    );
+
+    let entry = index.entry_by_path("d/a".into()).expect("present").clone();
+    index.upsert_entry(
+        entry.stat,
+        hex_to_id("d95f3ad14dee633a758d2e331151e950dd13e4ed"),
+        entry.flags,
+        entry.mode,
+        "d/a".into(),
+    );
+    let tree = index.tree().expect("still present");
+    assert_eq!(tree.num_entries, None, "the root is invalidated");
+    assert_eq!(tree.children[0].num_entries, None, "d is invalidated");
+    assert!(
+        tree.children[0].children[0].num_entries.is_some(),
+        "d/nested is unaffected"
+    );
+
+    let id = index.write_tree(&odb)?;
+    assert_ne!(id, expected);
+    assert_eq!(odb.num_objects_in_memory(), 2, "only the root and d were written");
+    assert_eq!(
+        index.tree().expect("present").num_entries,
+        Some(index.entries().len() as u32),
+        "the TREE extension is valid again"
+    );
+
+    let mut fresh = index.clone();
+    fresh.remove_tree();
+    assert_eq!(fresh.write_tree(&odb)?, id, "reused trees are the same as written ones");
+    Ok(())
+}
+
+#[test]
+fn entries_intended_to_be_added_are_left_out() -> crate::Result {
+    let (mut index, odb) = index_and_odb()?;
+    let expected = index.tree().expect("written by git").id;
+    let entry = index.entry_by_path("d/a".into()).expect("present").clone();
+    index.upsert_entry(
+        entry.stat,
+        entry.id,
+        Flags::INTENT_TO_ADD | Flags::EXTENDED,
+        entry.mode,
+        "d/new/file".into(),
+    );
+
+    assert_eq!(
+        index.write_tree(&odb)?,
+        expected,
+        "the new entry isn't part of the tree"
+    );
+    assert_eq!(
+        index.tree().expect("present").num_entries,
+        None,
+        "trees with entries that are left out stay invalid"
+    );
+    Ok(())
+}
+
+#[test]
+fn conflicts_cannot_be_written() -> crate::Result {
+    let (mut index, odb) = index_and_odb()?;
+    let entry = index.entry_by_path("d/a".into()).expect("present").clone();
+    index.upsert_entry(
+        entry.stat,
+        entry.id,
+        Flags::from_stage(Stage::Ours),
+        entry.mode,
+        "d/a".into(),
+    );
+
+    let err = index.write_tree(&odb).unwrap_err();
+    assert!(
+        matches!(&err, gix_index::write_tree::Error::Unmerged { path } if path == "d/a"),
+        "{err:?}"
+    );
+    Ok(())
+}
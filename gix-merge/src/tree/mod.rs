@@ -496,6 +496,9 @@ pub mod apply_index_entries {
         /// brought about `conflicts`, or else this function will fail if it cannot find the path matching
         /// the conflicting entries.
         ///
+        /// If `index` is [sparse](gix_index::State::is_sparse()), the sparse directories that contain conflicting paths must
+        /// be expanded beforehand, or else this function refuses to change `index` and returns `false`.
+        ///
         /// Note that in practice, whenever there is a single [conflict](Conflict), this function will return `true`.
         /// Errors can only occour if `index` isn't the one created from the merged tree that produced the `conflicts`.
         pub fn apply_index_entries(
@@ -504,8 +507,23 @@ pub mod apply_index_entries {
             index: &mut gix_index::State,
             removal_mode: RemovalMode,
         ) -> bool {
-            if index.is_sparse() {
-                gix_trace::error!("Refusing to apply index entries to sparse index - it's not tested yet");
+            let sparse_dirs: Vec<_> = index
+                .entries()
+                .iter()
+                .filter(|e| e.mode.is_sparse())
+                .map(|e| e.path(index))
+                .collect();
+            if !sparse_dirs.is_empty()
+                && conflicts.iter().filter(|c| c.is_unresolved(how)).any(|c| {
+                    [&c.ours, &c.theirs]
+                        .into_iter()
+                        .flat_map(|change| [change.location(), change.source_location()])
+                        .any(|path| sparse_dirs.iter().any(|dir| path.starts_with(dir)))
+                })
+            {
+                gix_trace::error!(
+                    "Refusing to apply index entries to sparse directories - they must be expanded with `gix_index::State::expand_sparse_directories()` first"
+                );
                 return false;
             }
             let len = index.entries().len();
@@ -171,6 +171,17 @@ mod from_bytes {
         Ok(())
     }
 
+    #[test]
+    fn signed_with_ssh() -> crate::Result {
+        let fixture = fixture_name("tag", "signed-ssh.txt");
+        let tag = TagRef::from_bytes(&fixture)?;
+        assert_eq!(tag.message, "signed with ssh");
+        let signature = tag.pgp_signature.expect("present");
+        assert!(signature.starts_with(b"-----BEGIN SSH SIGNATURE-----\n"));
+        assert!(signature.ends_with(b"-----END SSH SIGNATURE-----\n"));
+        Ok(())
+    }
+
     #[test]
     fn empty() -> crate::Result {
         let fixture = fixture_name("tag", "empty.txt");
@@ -74,6 +74,9 @@ mod sink;
 ///
 pub mod find;
 
+/// Fetching of missing objects from a promisor remote in partial clones.
+pub mod promisor;
+
 /// An object database equivalent to `/dev/null`, dropping all objects stored into it.
 mod traits;
 
@@ -0,0 +1,15 @@
+use gix_hash::ObjectId;
+
+/// The error returned by [`Fetch::fetch()`].
+pub type Error = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+/// A way to obtain objects that are missing locally from a promisor remote, as needed in partial clones.
+///
+/// It's installed into a [store handle](crate::store::Handle::promisor) to be called whenever an object to find is missing.
+pub trait Fetch: Send + Sync {
+    /// Fetch all objects with `ids` from the promisor remote and write them into the object database,
+    /// which is where the handle will find them after refreshing its view of the object database.
+    ///
+    /// Note that it's not an error if some of the objects can't be provided by the remote.
+    fn fetch(&self, ids: &[ObjectId]) -> Result<(), Error>;
+}
@@ -44,6 +44,12 @@ pub(crate) mod error {
             /// The original object to lookup
             id: gix_hash::ObjectId,
         },
+        #[error("Could not fetch missing object {id} from the promisor remote")]
+        PromisorFetch {
+            /// The object that was missing
+            id: gix_hash::ObjectId,
+            source: crate::promisor::Error,
+        },
     }
 
     #[derive(Copy, Clone)]
@@ -309,6 +315,24 @@ where
     pub(crate) fn clear_cache(&self) {
         self.packed_object_count.borrow_mut().take();
     }
+
+    /// If we have a promisor, fetch `id` from it as it couldn't be found locally.
+    ///
+    /// Return `true` if a fetch was performed, in which case the lookup should be repeated.
+    pub(crate) fn fetch_missing(&self, id: &gix_hash::oid) -> Result<bool, Error> {
+        match self.promisor.as_ref() {
+            Some(promisor) => {
+                promisor
+                    .fetch(&[id.to_owned()])
+                    .map_err(|source| Error::PromisorFetch {
+                        id: id.to_owned(),
+                        source,
+                    })?;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
 }
 
 impl<S> gix_pack::Find for super::Handle<S>
@@ -353,8 +377,27 @@ where
     ) -> Result<Option<(gix_object::Data<'a>, Option<gix_pack::data::entry::Location>)>, gix_object::find::Error> {
         let mut snapshot = self.snapshot.borrow_mut();
         let mut inflate = self.inflate.borrow_mut();
-        self.try_find_cached_inner(id, buffer, &mut inflate, pack_cache, &mut snapshot, None)
-            .map_err(|err| Box::new(err) as _)
+        let mut find = |buffer: &mut Vec<u8>| {
+            // Objects are always decoded into `buffer` in full, which allows to retry after fetching without holding on to it.
+            self.try_find_cached_inner(id, buffer, &mut inflate, pack_cache, &mut snapshot, None)
+                .map(|obj| obj.map(|(data, location)| (data.kind, location)))
+        };
+        let mut res = find(buffer);
+        if matches!(res, Ok(None)) && self.fetch_missing(id)? {
+            res = find(buffer);
+        }
+        res.map(|obj| {
+            obj.map(|(kind, location)| {
+                (
+                    gix_object::Data {
+                        kind,
+                        data: buffer.as_slice(),
+                    },
+                    location,
+                )
+            })
+        })
+        .map_err(|err| Box::new(err) as _)
     }
 
     fn location_by_oid(&self, id: &gix_hash::oid, buf: &mut Vec<u8>) -> Option<gix_pack::data::entry::Location> {
@@ -523,14 +566,17 @@ where
     fn try_header(&self, id: &gix_hash::oid) -> Result<Option<gix_object::Header>, gix_object::find::Error> {
         let mut snapshot = self.snapshot.borrow_mut();
         let mut inflate = self.inflate.borrow_mut();
-        self.try_header_inner(id, &mut inflate, &mut snapshot, None)
-            .map(|maybe_header| {
-                maybe_header.map(|hdr| gix_object::Header {
-                    kind: hdr.kind(),
-                    size: hdr.size(),
-                })
+        let mut res = self.try_header_inner(id, &mut inflate, &mut snapshot, None);
+        if matches!(res, Ok(None)) && self.fetch_missing(id)? {
+            res = self.try_header_inner(id, &mut inflate, &mut snapshot, None);
+        }
+        res.map(|maybe_header| {
+            maybe_header.map(|hdr| gix_object::Header {
+                kind: hdr.kind(),
+                size: hdr.size(),
             })
-            .map_err(|err| Box::new(err) as _)
+        })
+        .map_err(|err| Box::new(err) as _)
     }
 }
 
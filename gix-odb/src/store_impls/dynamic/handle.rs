@@ -255,6 +255,7 @@ impl super::Store {
             store: self.clone(),
             refresh: RefreshMode::default(),
             ignore_replacements: false,
+            promisor: None,
             token: Some(token),
             inflate: RefCell::new(Default::default()),
             snapshot: RefCell::new(self.collect_snapshot()),
@@ -272,6 +273,7 @@ impl super::Store {
             store: self.clone(),
             refresh: Default::default(),
             ignore_replacements: false,
+            promisor: None,
             token: Some(token),
             inflate: RefCell::new(Default::default()),
             snapshot: RefCell::new(self.collect_snapshot()),
@@ -327,6 +329,34 @@ where
     pub fn refresh_mode(&mut self) -> RefreshMode {
         self.refresh
     }
+
+    /// Set the handle to never fetch objects that can't be found from the [promisor remote](Self::promisor).
+    ///
+    /// Use this when probing for objects that may legitimately be missing, as each of them would be fetched otherwise.
+    pub fn fetch_missing_never(&mut self) {
+        self.promisor = None;
+    }
+
+    /// Fetch all objects with `ids` that aren't present locally from the [promisor remote](Self::promisor) in one go,
+    /// and return the amount of objects that were requested.
+    ///
+    /// This is much faster than letting each missing object be fetched individually when it's accessed.
+    /// If no promisor is set, nothing happens.
+    pub fn prefetch(&self, ids: impl IntoIterator<Item = gix_hash::ObjectId>) -> Result<usize, crate::promisor::Error> {
+        let Some(promisor) = self.promisor.as_ref() else {
+            return Ok(0);
+        };
+        let mut missing: Vec<_> = ids
+            .into_iter()
+            .filter(|id| !gix_pack::Find::contains(self, id))
+            .collect();
+        missing.sort();
+        missing.dedup();
+        if !missing.is_empty() {
+            promisor.fetch(&missing)?;
+        }
+        Ok(missing.len())
+    }
 }
 
 impl<S> Drop for super::Handle<S>
@@ -364,6 +394,7 @@ impl super::Handle<Rc<super::Store>> {
         let mut cache = store.to_handle_arc();
         cache.refresh = self.refresh;
         cache.max_recursion_depth = self.max_recursion_depth;
+        cache.promisor.clone_from(&self.promisor);
         Ok(cache)
     }
 }
@@ -384,6 +415,7 @@ where
             store: self.store.clone(),
             refresh: self.refresh,
             ignore_replacements: self.ignore_replacements,
+            promisor: self.promisor.clone(),
             token: {
                 let token = self.store.register_handle();
                 match self.token.as_ref().expect("token is always set here ") {
@@ -187,7 +187,10 @@ where
     fn try_header(&self, id: &oid) -> Result<Option<Header>, gix_object::find::Error> {
         let mut snapshot = self.snapshot.borrow_mut();
         let mut inflate = self.inflate.borrow_mut();
-        self.try_header_inner(id, &mut inflate, &mut snapshot, None)
-            .map_err(|err| Box::new(err) as _)
+        let mut res = self.try_header_inner(id, &mut inflate, &mut snapshot, None);
+        if matches!(res, Ok(None)) && self.fetch_missing(id)? {
+            res = self.try_header_inner(id, &mut inflate, &mut snapshot, None);
+        }
+        res.map_err(|err| Box::new(err) as _)
     }
 }
@@ -1,5 +1,5 @@
 //! The standard object store which should fit all needs.
-use std::{cell::RefCell, ops::Deref};
+use std::{cell::RefCell, ops::Deref, sync::Arc};
 
 use gix_features::zlib;
 
@@ -24,6 +24,12 @@ where
     /// If true, replacements will not be performed even if these are available.
     pub ignore_replacements: bool,
 
+    /// If set, objects that can't be found are fetched from the promisor remote before trying to find them once more.
+    ///
+    /// Note that checking for the existence of objects never fetches them, and that [fetching can be turned off](Self::fetch_missing_never())
+    /// when probing for objects.
+    pub promisor: Option<Arc<dyn crate::promisor::Fetch>>,
+
     pub(crate) token: Option<handle::Mode>,
     snapshot: RefCell<load_index::Snapshot>,
     inflate: RefCell<zlib::Inflate>,
@@ -1,7 +1,7 @@
 use std::process::Command;
 
 use gix_hash::ObjectId;
-use gix_object::{Exists, FindExt, Write};
+use gix_object::{Exists, Find, FindExt, Write};
 use gix_odb::{store, store::iter::Ordering, Header};
 use gix_testtools::fixture_path_standalone;
 
@@ -33,6 +33,7 @@ fn db_with_all_object_sources() -> crate::Result<(gix_odb::Handle, gix_testtools
         &std::sync::atomic::AtomicBool::default(),
         gix_odb::pack::multi_index::write::Options {
             object_hash: gix_hash::Kind::Sha1,
+            write_reverse_index: false,
         },
     )?;
     Ok((gix_odb::at(objects_dir.path())?, objects_dir))
@@ -221,6 +222,69 @@ fn write() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn promisor_fetches_missing_objects() -> crate::Result {
+    struct Promisor {
+        objects_dir: std::path::PathBuf,
+        requests: std::sync::Mutex<Vec<Vec<ObjectId>>>,
+    }
+    impl gix_odb::promisor::Fetch for Promisor {
+        fn fetch(&self, ids: &[ObjectId]) -> Result<(), gix_odb::promisor::Error> {
+            self.requests.lock().expect("not poisoned").push(ids.to_vec());
+            let store = gix_odb::loose::Store::at(&self.objects_dir, gix_hash::Kind::Sha1);
+            for id in ids {
+                if *id == hex_to_id("95d09f2b10159347eece71399a7e2e907ea3df4f") {
+                    store.write_buf(gix_object::Kind::Blob, b"hello world")?;
+                }
+            }
+            Ok(())
+        }
+    }
+    let hello_world = hex_to_id("95d09f2b10159347eece71399a7e2e907ea3df4f");
+    let unknown = hex_to_id("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa");
+
+    let dir = gix_testtools::tempfile::tempdir()?;
+    let promisor = std::sync::Arc::new(Promisor {
+        objects_dir: dir.path().to_owned(),
+        requests: Default::default(),
+    });
+    let mut handle = gix_odb::at(dir.path())?;
+    handle.promisor = Some(promisor.clone());
+
+    assert!(!handle.exists(&hello_world), "checking for existence doesn't fetch");
+    assert!(promisor.requests.lock().expect("not poisoned").is_empty());
+
+    let mut buf = Vec::new();
+    assert_eq!(handle.find_blob(&hello_world, &mut buf)?.data, b"hello world");
+    assert!(
+        handle.find(&unknown, &mut buf).is_err(),
+        "objects the remote doesn't have stay missing"
+    );
+    assert_eq!(
+        handle.prefetch([unknown, hello_world, unknown])?,
+        1,
+        "only missing objects are requested, once"
+    );
+    assert_eq!(
+        handle.find_blob(&hello_world, &mut buf)?.data,
+        b"hello world",
+        "present objects are found without asking the remote"
+    );
+    assert!(handle.try_header(&unknown)?.is_none());
+    assert_eq!(
+        *promisor.requests.lock().expect("not poisoned"),
+        [vec![hello_world], vec![unknown], vec![unknown], vec![unknown]]
+    );
+
+    handle.fetch_missing_never();
+    assert!(
+        handle.try_find(&unknown, &mut buf)?.is_none(),
+        "probing for objects can be done without fetching"
+    );
+    assert_eq!(promisor.requests.lock().expect("not poisoned").len(), 4);
+    Ok(())
+}
+
 #[test]
 fn alternate_dbs_query() -> crate::Result {
     let dir = gix_testtools::scripted_fixture_read_only_standalone("make_alternates_odb.sh")?;
@@ -0,0 +1,68 @@
+use std::path::Path;
+
+use gix_hash::ObjectId;
+
+use crate::bitmap::{decode, Bits, File, TYPES};
+
+/// Basic file information
+impl File {
+    /// The path of the opened bitmap file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The kind of hash we assume.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// The checksum of the pack or multi-pack index whose objects the bits refer to.
+    pub fn checksum(&self) -> ObjectId {
+        self.checksum
+    }
+
+    /// The amount of commits that have a bitmap.
+    pub fn num_bitmaps(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Iterate the index positions of all commits that have a bitmap, in the order they are stored.
+    pub fn iter_index_positions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.entries.iter().map(|e| e.index_position)
+    }
+}
+
+/// Access to bitmaps
+impl File {
+    /// Return the bits of all objects of the given `kind`, for an index with `num_objects` objects.
+    pub fn type_bits(&self, kind: gix_object::Kind, num_objects: usize) -> Result<Bits, decode::Error> {
+        let type_idx = TYPES.iter().position(|k| *k == kind).expect("all kinds are known");
+        to_bits(&self.types[type_idx], num_objects)
+    }
+
+    /// Return the bits of all objects reachable from the commit at `index_position` in an index with `num_objects` objects,
+    /// or `None` if there is no bitmap for it.
+    pub fn commit_bits(&self, index_position: u32, num_objects: usize) -> Result<Option<Bits>, decode::Error> {
+        let Ok(pos) = self
+            .entries_by_index_position
+            .binary_search_by_key(&index_position, |(index_position, _)| *index_position)
+        else {
+            return Ok(None);
+        };
+        let mut entry_idx = self.entries_by_index_position[pos].1;
+        let mut bits = to_bits(&self.entries[entry_idx].bitmap, num_objects)?;
+        loop {
+            let xor_offset = usize::from(self.entries[entry_idx].xor_offset);
+            if xor_offset == 0 {
+                break;
+            }
+            entry_idx -= xor_offset;
+            bits.xor_with(&to_bits(&self.entries[entry_idx].bitmap, num_objects)?);
+        }
+        Ok(Some(bits))
+    }
+}
+
+fn to_bits(bitmap: &gix_bitmap::ewah::Vec, num_objects: usize) -> Result<Bits, decode::Error> {
+    Bits::from_ewah(bitmap, num_objects).ok_or(decode::Error::TooManyBits { num_objects })
+}
@@ -0,0 +1,108 @@
+use crate::bitmap::Bits;
+
+impl Bits {
+    /// Create a new instance with `len` bits, none of which are set.
+    pub fn new(len: usize) -> Self {
+        Bits {
+            words: vec![0; len.div_ceil(64)],
+            len,
+        }
+    }
+
+    /// Create a new instance with `len` bits from the compressed `bitmap`, or `None` if `bitmap` has bits set at or past `len`.
+    pub fn from_ewah(bitmap: &gix_bitmap::ewah::Vec, len: usize) -> Option<Self> {
+        let mut words = bitmap.to_bits();
+        let num_words = len.div_ceil(64);
+        if words[num_words.min(words.len())..].iter().any(|w| *w != 0) {
+            return None;
+        }
+        words.resize(num_words, 0);
+        if len % 64 != 0 && words.last().is_some_and(|w| w >> (len % 64) != 0) {
+            return None;
+        }
+        Some(Bits { words, len })
+    }
+
+    /// Compress these bits into an EWAH bitmap.
+    pub fn to_ewah(&self) -> gix_bitmap::ewah::Vec {
+        gix_bitmap::ewah::encode(
+            &self.words,
+            self.len.try_into().expect("bitmaps have at most u32::MAX bits"),
+        )
+    }
+
+    /// The amount of bits we hold, set or not.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Return `true` if we hold no bits at all.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Return the amount of set bits.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    /// Set the bit at `pos` and return `true` if it wasn't set before.
+    ///
+    /// # Panics
+    ///
+    /// If `pos` is out of bounds.
+    pub fn insert(&mut self, pos: usize) -> bool {
+        assert!(pos < self.len, "bit {pos} out of bounds");
+        let (word, mask) = (&mut self.words[pos / 64], 1 << (pos % 64));
+        let is_new = *word & mask == 0;
+        *word |= mask;
+        is_new
+    }
+
+    /// Return `true` if the bit at `pos` is set.
+    pub fn contains(&self, pos: usize) -> bool {
+        pos < self.len && self.words[pos / 64] & (1 << (pos % 64)) != 0
+    }
+
+    /// Set all bits that are set in `other`.
+    pub fn union_with(&mut self, other: &Bits) {
+        for (lhs, rhs) in self.words.iter_mut().zip(&other.words) {
+            *lhs |= rhs;
+        }
+    }
+
+    /// Clear all bits that are set in `other`.
+    pub fn difference_with(&mut self, other: &Bits) {
+        for (lhs, rhs) in self.words.iter_mut().zip(&other.words) {
+            *lhs &= !rhs;
+        }
+    }
+
+    /// Clear all bits that are not set in `other`.
+    pub fn intersect_with(&mut self, other: &Bits) {
+        for (idx, lhs) in self.words.iter_mut().enumerate() {
+            *lhs &= other.words.get(idx).copied().unwrap_or(0);
+        }
+    }
+
+    /// Flip all bits that are set in `other`.
+    pub(crate) fn xor_with(&mut self, other: &Bits) {
+        for (lhs, rhs) in self.words.iter_mut().zip(&other.words) {
+            *lhs ^= rhs;
+        }
+    }
+
+    /// Iterate the positions of all set bits in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(word_idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    word_idx * 64 + bit
+                })
+            })
+        })
+    }
+}
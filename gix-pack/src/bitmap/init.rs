@@ -0,0 +1,116 @@
+use std::path::Path;
+
+use gix_hash::ObjectId;
+
+use crate::bitmap::{Entry, File, FLAG_FULL_DAG};
+
+mod error {
+    /// The error returned by [File::at()][super::File::at()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not open bitmap file at '{path}'")]
+        Io {
+            source: std::io::Error,
+            path: std::path::PathBuf,
+        },
+        #[error("{message}")]
+        Corrupt { message: &'static str },
+        #[error("Unsupported bitmap version: {version}")]
+        UnsupportedVersion { version: u16 },
+        #[error("Bitmaps that aren't closed under reachability are not supported")]
+        UnsupportedPartialBitmaps,
+        #[error(transparent)]
+        Ewah(#[from] gix_bitmap::ewah::decode::Error),
+    }
+}
+
+pub use error::Error;
+
+/// Initialization
+impl File {
+    /// Open the bitmap file at the given `path`, which uses hashes of kind `object_hash`.
+    ///
+    /// All bitmaps are decoded right away.
+    pub fn at(path: impl AsRef<Path>, object_hash: gix_hash::Kind) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let data = crate::mmap::read_only(path).map_err(|source| Error::Io {
+            source,
+            path: path.to_owned(),
+        })?;
+        let hash_len = object_hash.len_in_bytes();
+        if data.len() < Self::HEADER_LEN + 2 * hash_len {
+            return Err(Error::Corrupt {
+                message: "bitmap file is truncated and too short",
+            });
+        }
+        let (header, mut data) = data[..data.len() - hash_len].split_at(Self::HEADER_LEN);
+        if &header[..4] != Self::SIGNATURE {
+            return Err(Error::Corrupt {
+                message: "Invalid signature",
+            });
+        }
+        let version = u16::from_be_bytes([header[4], header[5]]);
+        if version != 1 {
+            return Err(Error::UnsupportedVersion { version });
+        }
+        let flags = u16::from_be_bytes([header[6], header[7]]);
+        if flags & FLAG_FULL_DAG == 0 {
+            return Err(Error::UnsupportedPartialBitmaps);
+        }
+        let num_entries = crate::read_u32(&header[8..12]);
+        let checksum = ObjectId::from_bytes_or_panic(&data[..hash_len]);
+        data = &data[hash_len..];
+
+        let decode_bitmap = |data: &mut &[u8]| -> Result<gix_bitmap::ewah::Vec, Error> {
+            let (bitmap, rest) = gix_bitmap::ewah::decode(data)?;
+            *data = rest;
+            Ok(bitmap)
+        };
+        let types = [
+            decode_bitmap(&mut data)?,
+            decode_bitmap(&mut data)?,
+            decode_bitmap(&mut data)?,
+            decode_bitmap(&mut data)?,
+        ];
+
+        let mut entries = Vec::with_capacity(num_entries as usize);
+        for entry_idx in 0..num_entries as usize {
+            if data.len() < 6 {
+                return Err(Error::Corrupt {
+                    message: "eof while reading bitmap entry",
+                });
+            }
+            let index_position = crate::read_u32(&data[..4]);
+            let xor_offset = data[4];
+            data = &data[6..];
+            if usize::from(xor_offset) > entry_idx {
+                return Err(Error::Corrupt {
+                    message: "bitmap entry refers to a bitmap before the first one",
+                });
+            }
+            entries.push(Entry {
+                index_position,
+                xor_offset,
+                bitmap: decode_bitmap(&mut data)?,
+            });
+        }
+        // The name-hash cache and the lookup table which may follow are not needed.
+
+        let mut entries_by_index_position: Vec<_> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, e)| (e.index_position, idx))
+            .collect();
+        entries_by_index_position.sort_unstable();
+
+        Ok(File {
+            path: path.to_owned(),
+            object_hash,
+            checksum,
+            types,
+            entries,
+            entries_by_index_position,
+        })
+    }
+}
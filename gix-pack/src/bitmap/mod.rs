@@ -0,0 +1,140 @@
+//! Reachability bitmaps for packs and multi-pack indices, as stored in `pack-<hash>.bitmap` and
+//! `multi-pack-index-<hash>.bitmap` files.
+//!
+//! Each bit refers to one object of the pack, or of all packs in a multi-pack index, in _pack order_, which for
+//! a single pack is the order of pack offsets and for multi-pack indices the pseudo-pack order stored in their
+//! reverse-index chunk. See [`Order`] for obtaining it.
+use gix_hash::ObjectId;
+
+/// A bitmap file, with all bitmaps decoded into memory.
+pub struct File {
+    path: std::path::PathBuf,
+    object_hash: gix_hash::Kind,
+    /// The checksum of the pack or multi-pack index this file belongs to.
+    checksum: ObjectId,
+    /// The type bitmaps, in the order commits, trees, blobs, tags.
+    types: [gix_bitmap::ewah::Vec; 4],
+    /// All commit bitmaps in the order of the file, as xor-offsets can only be resolved by that order.
+    entries: Vec<Entry>,
+    /// Pairs of `(index position, entries position)`, sorted by index position for lookup.
+    entries_by_index_position: Vec<(u32, usize)>,
+}
+
+struct Entry {
+    /// The position of the commit in the pack index or multi-pack index.
+    index_position: u32,
+    /// The amount of entries to go back to find the bitmap to xor ours with, or 0 if it's used as is.
+    xor_offset: u8,
+    bitmap: gix_bitmap::ewah::Vec,
+}
+
+/// The index an object was found in, with each object having a position, which isn't necessarily its position
+/// in the [bitmap](File).
+pub trait Index {
+    /// The amount of objects in the index.
+    fn num_objects(&self) -> u32;
+    /// Return the index position of the object with the given `id`, or `None` if it isn't contained.
+    fn lookup(&self, id: &gix_hash::oid) -> Option<u32>;
+    /// Return the id of the object at the given index `position`.
+    fn oid_at_index(&self, position: u32) -> &gix_hash::oid;
+    /// The checksum that bitmaps of this index refer to.
+    fn checksum(&self) -> ObjectId;
+    /// The kind of hash used for object ids.
+    fn object_hash(&self) -> gix_hash::Kind;
+}
+
+impl Index for crate::index::File {
+    fn num_objects(&self) -> u32 {
+        crate::index::File::num_objects(self)
+    }
+
+    fn lookup(&self, id: &gix_hash::oid) -> Option<u32> {
+        crate::index::File::lookup(self, id)
+    }
+
+    fn oid_at_index(&self, position: u32) -> &gix_hash::oid {
+        crate::index::File::oid_at_index(self, position)
+    }
+
+    fn checksum(&self) -> ObjectId {
+        self.pack_checksum()
+    }
+
+    fn object_hash(&self) -> gix_hash::Kind {
+        crate::index::File::object_hash(self)
+    }
+}
+
+impl Index for crate::multi_index::File {
+    fn num_objects(&self) -> u32 {
+        crate::multi_index::File::num_objects(self)
+    }
+
+    fn lookup(&self, id: &gix_hash::oid) -> Option<u32> {
+        crate::multi_index::File::lookup(self, id)
+    }
+
+    fn oid_at_index(&self, position: u32) -> &gix_hash::oid {
+        crate::multi_index::File::oid_at_index(self, position)
+    }
+
+    fn checksum(&self) -> ObjectId {
+        crate::multi_index::File::checksum(self)
+    }
+
+    fn object_hash(&self) -> gix_hash::Kind {
+        crate::multi_index::File::object_hash(self)
+    }
+}
+
+/// The mapping between index positions and bit positions, the order in which objects appear in the pack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Order {
+    /// The index position for each bit position.
+    index_positions: Vec<u32>,
+    /// The bit position for each index position.
+    bit_positions: Vec<u32>,
+}
+
+/// A dense set of bits, one for each object of a pack or multi-pack index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bits {
+    words: Vec<u64>,
+    len: usize,
+}
+
+/// The type of objects, in the order of the type bitmaps in a bitmap file.
+const TYPES: [gix_object::Kind; 4] = [
+    gix_object::Kind::Commit,
+    gix_object::Kind::Tree,
+    gix_object::Kind::Blob,
+    gix_object::Kind::Tag,
+];
+
+/// The file contains bitmaps which are closed under reachability, the only kind that is supported.
+///
+/// Other flags indicate optional tables after the bitmaps, which we don't use.
+const FLAG_FULL_DAG: u16 = 0x1;
+
+mod access;
+mod bits;
+
+///
+pub mod decode {
+    /// The error returned when obtaining the bits of a bitmap.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("A bitmap has bits set past the {num_objects} objects in the index")]
+        TooManyBits { num_objects: usize },
+    }
+}
+
+///
+pub mod init;
+///
+pub mod order;
+///
+pub mod reachable;
+///
+pub mod write;
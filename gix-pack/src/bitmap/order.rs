@@ -0,0 +1,63 @@
+use crate::bitmap::Order;
+
+/// The error returned by [`Order::from_multi_index()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The multi-pack index at '{path}' has no reverse index to provide the pseudo-pack order")]
+    MissingReverseIndex { path: std::path::PathBuf },
+}
+
+/// Initialization
+impl Order {
+    /// Obtain the order of objects in the pack of `index`, which is the order of their pack offsets.
+    pub fn from_index(index: &crate::index::File) -> Self {
+        let mut index_positions: Vec<_> = (0..index.num_objects()).collect();
+        index_positions.sort_by_cached_key(|pos| index.pack_offset_at_index(*pos));
+        Self::from_index_positions(index_positions)
+    }
+
+    /// Obtain the pseudo-pack order of objects in the multi-pack `index`, which requires it to have a reverse index.
+    pub fn from_multi_index(index: &crate::multi_index::File) -> Result<Self, Error> {
+        index
+            .reverse_index()
+            .map(Self::from_index_positions)
+            .ok_or_else(|| Error::MissingReverseIndex {
+                path: index.path().to_owned(),
+            })
+    }
+
+    fn from_index_positions(index_positions: Vec<u32>) -> Self {
+        let mut bit_positions = vec![0; index_positions.len()];
+        for (bit_position, index_position) in index_positions.iter().enumerate() {
+            bit_positions[*index_position as usize] = bit_position as u32;
+        }
+        Order {
+            index_positions,
+            bit_positions,
+        }
+    }
+}
+
+/// Access
+impl Order {
+    /// The amount of objects we know.
+    pub fn len(&self) -> usize {
+        self.index_positions.len()
+    }
+
+    /// Return `true` if there are no objects.
+    pub fn is_empty(&self) -> bool {
+        self.index_positions.is_empty()
+    }
+
+    /// Return the bit position of the object at `index_position`.
+    pub fn bit_position(&self, index_position: u32) -> usize {
+        self.bit_positions[index_position as usize] as usize
+    }
+
+    /// Return the index position of the object at `bit_position`.
+    pub fn index_position(&self, bit_position: usize) -> u32 {
+        self.index_positions[bit_position]
+    }
+}
@@ -0,0 +1,119 @@
+use gix_hash::ObjectId;
+
+use crate::bitmap::{decode, Bits, File, Index, Order};
+
+/// The error returned by [`File::reachable()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The bitmap belongs to {expected}, but the index has checksum {actual}")]
+    ChecksumMismatch { expected: ObjectId, actual: ObjectId },
+    #[error("Object {id} is not contained in the index")]
+    NotIndexed { id: ObjectId },
+    #[error("Object {id} could not be found")]
+    NotFound { id: ObjectId },
+    #[error(transparent)]
+    Find(#[from] gix_object::find::Error),
+    #[error(transparent)]
+    Decode(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    Bitmap(#[from] decode::Error),
+}
+
+/// Reachability
+impl File {
+    /// Return the bits of all objects reachable from `tips` in `index`, whose objects are stored in `order` and can be read from `objects`.
+    ///
+    /// Commits with a bitmap are not traversed, and other objects are only read if needed to reach
+    /// commits with bitmaps, or objects contained in trees.
+    /// This fails if any reachable object isn't contained in `index`.
+    pub fn reachable(
+        &self,
+        tips: impl IntoIterator<Item = ObjectId>,
+        index: &dyn Index,
+        order: &Order,
+        objects: &dyn gix_object::Find,
+    ) -> Result<Bits, Error> {
+        if self.checksum != index.checksum() {
+            return Err(Error::ChecksumMismatch {
+                expected: self.checksum,
+                actual: index.checksum(),
+            });
+        }
+        let num_objects = order.len();
+        traverse(
+            tips,
+            index,
+            order,
+            objects,
+            Bits::new(num_objects),
+            &mut |index_position, bits| {
+                Ok(match self.commit_bits(index_position, num_objects)? {
+                    Some(commit_bits) => {
+                        bits.union_with(&commit_bits);
+                        true
+                    }
+                    None => false,
+                })
+            },
+        )
+    }
+}
+
+/// Set all bits in `bits` for objects reachable from `tips`, skipping those that are set already.
+///
+/// `add_commit_bits(index_position, bits)` is called for each commit to add all bits reachable from it
+/// and return `true`, or return `false` if it can't, in which case the commit is traversed.
+pub(crate) fn traverse(
+    tips: impl IntoIterator<Item = ObjectId>,
+    index: &dyn Index,
+    order: &Order,
+    objects: &dyn gix_object::Find,
+    mut bits: Bits,
+    add_commit_bits: &mut dyn FnMut(u32, &mut Bits) -> Result<bool, Error>,
+) -> Result<Bits, Error> {
+    let bit_position = |id: &gix_hash::oid| -> Result<(u32, usize), Error> {
+        let index_position = index
+            .lookup(id)
+            .ok_or_else(|| Error::NotIndexed { id: id.to_owned() })?;
+        Ok((index_position, order.bit_position(index_position)))
+    };
+    let mut stack: Vec<_> = tips.into_iter().collect();
+    let mut buf = Vec::new();
+    while let Some(id) = stack.pop() {
+        let (index_position, pos) = bit_position(&id)?;
+        if bits.contains(pos) {
+            continue;
+        }
+        let data = objects.try_find(&id, &mut buf)?.ok_or(Error::NotFound { id })?;
+        match data.kind {
+            gix_object::Kind::Commit => {
+                if add_commit_bits(index_position, &mut bits)? {
+                    continue;
+                }
+                let mut commit = data.try_into_commit_iter().expect("commit");
+                stack.push(commit.tree_id()?);
+                stack.extend(commit.parent_ids());
+            }
+            gix_object::Kind::Tag => {
+                stack.push(data.try_into_tag_iter().expect("tag").target_id()?);
+            }
+            gix_object::Kind::Tree => {
+                for entry in data.try_into_tree_iter().expect("tree") {
+                    let entry = entry?;
+                    if entry.mode.is_commit() {
+                        continue;
+                    }
+                    if entry.mode.is_tree() {
+                        stack.push(entry.oid.to_owned());
+                    } else {
+                        bits.insert(bit_position(entry.oid)?.1);
+                    }
+                }
+            }
+            gix_object::Kind::Blob => {}
+        }
+        bits.insert(pos);
+    }
+    Ok(bits)
+}
@@ -0,0 +1,201 @@
+use std::{collections::HashMap, io::Write};
+
+use gix_hash::ObjectId;
+
+use crate::bitmap::{reachable, Bits, File, Index, Order, FLAG_FULL_DAG, TYPES};
+
+mod error {
+    use gix_hash::ObjectId;
+
+    /// The error returned by [`File::write_to()`][crate::bitmap::File::write_to()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Io(#[from] gix_hash::io::Error),
+        #[error("Could not obtain the header of object {id}")]
+        Header {
+            id: ObjectId,
+            source: gix_object::find::Error,
+        },
+        #[error("Object {id} could not be found")]
+        NotFound { id: ObjectId },
+        #[error(transparent)]
+        Reachable(#[from] crate::bitmap::reachable::Error),
+    }
+}
+pub use error::Error;
+
+/// Options for use in [`File::write_to()`].
+#[derive(Debug, Copy, Clone)]
+pub struct Options {
+    /// Besides the tips, store a bitmap for every n-th commit in topological order, starting with the oldest one.
+    ///
+    /// Smaller values speed up reachability queries at the cost of a larger file.
+    pub commit_interval: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options { commit_interval: 100 }
+    }
+}
+
+/// The result of [`File::write_to()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of commits that received a bitmap.
+    pub num_bitmaps: usize,
+    /// The checksum of the written bitmap file.
+    pub checksum: ObjectId,
+}
+
+impl File {
+    pub(crate) const SIGNATURE: &'static [u8] = b"BITM";
+    pub(crate) const HEADER_LEN: usize = 4 /*signature*/ + 2 /*version*/ + 2 /*flags*/ + 4 /*num entries*/;
+
+    /// Write a bitmap file for all objects in `index`, which are stored in `order`, to `out`.
+    ///
+    /// Bitmaps are stored for all commits among `tips`, which are peeled if they are tags, and regularly for their
+    /// ancestors as configured in `options`.
+    /// All objects reachable from `tips` must be contained in `index` and all objects of `index` must be available
+    /// in `objects`.
+    pub fn write_to(
+        out: &mut dyn std::io::Write,
+        tips: impl IntoIterator<Item = ObjectId>,
+        index: &dyn Index,
+        order: &Order,
+        objects: &(impl gix_object::Find + gix_object::FindHeader),
+        Options { commit_interval }: Options,
+    ) -> Result<Outcome, Error> {
+        let num_objects = order.len();
+        let mut types: [Bits; 4] = std::array::from_fn(|_| Bits::new(num_objects));
+        for pos in 0..num_objects {
+            let id = index.oid_at_index(order.index_position(pos));
+            let header = objects
+                .try_header(id)
+                .map_err(|source| Error::Header {
+                    id: id.to_owned(),
+                    source,
+                })?
+                .ok_or_else(|| Error::NotFound { id: id.to_owned() })?;
+            let type_idx = TYPES
+                .iter()
+                .position(|k| *k == header.kind)
+                .expect("all kinds are known");
+            types[type_idx].insert(pos);
+        }
+
+        let (commits, tip_commits) = commits_in_topological_order(tips, index, order, objects)?;
+        let mut bitmaps = HashMap::<u32, gix_bitmap::ewah::Vec>::new();
+        let mut entries = Vec::new();
+        for (nth, index_position) in commits.iter().enumerate() {
+            if nth % commit_interval.max(1) != 0 && !tip_commits.contains(index_position) {
+                continue;
+            }
+            let bits = reachable::traverse(
+                Some(index.oid_at_index(*index_position).to_owned()),
+                index,
+                order,
+                objects,
+                Bits::new(num_objects),
+                &mut |index_position, bits| {
+                    Ok(match bitmaps.get(&index_position) {
+                        Some(bitmap) => {
+                            bits.union_with(&Bits::from_ewah(bitmap, num_objects).expect("we wrote it"));
+                            true
+                        }
+                        None => false,
+                    })
+                },
+            )?;
+            bitmaps.insert(*index_position, bits.to_ewah());
+            entries.push(*index_position);
+        }
+
+        let mut out = gix_hash::io::Write::new(out, index.object_hash());
+        let mut write = || -> std::io::Result<()> {
+            out.write_all(Self::SIGNATURE)?;
+            out.write_all(&1u16.to_be_bytes())?;
+            out.write_all(&FLAG_FULL_DAG.to_be_bytes())?;
+            out.write_all(&(entries.len() as u32).to_be_bytes())?;
+            out.write_all(index.checksum().as_slice())?;
+            for bits in &types {
+                bits.to_ewah().write_to(&mut out)?;
+            }
+            for index_position in &entries {
+                out.write_all(&index_position.to_be_bytes())?;
+                out.write_all(&[0 /* xor offset */, 0 /* flags */])?;
+                bitmaps[index_position].write_to(&mut out)?;
+            }
+            Ok(())
+        };
+        write().map_err(gix_hash::io::Error::from)?;
+        let checksum = out.hash.try_finalize().map_err(gix_hash::io::Error::from)?;
+        out.inner
+            .write_all(checksum.as_slice())
+            .map_err(gix_hash::io::Error::from)?;
+
+        Ok(Outcome {
+            num_bitmaps: entries.len(),
+            checksum,
+        })
+    }
+}
+
+/// Return the index positions of all commits reachable from `tips` with parents before their children, along with
+/// the index positions of the commits `tips` point to.
+fn commits_in_topological_order(
+    tips: impl IntoIterator<Item = ObjectId>,
+    index: &dyn Index,
+    order: &Order,
+    objects: &dyn gix_object::Find,
+) -> Result<(Vec<u32>, Vec<u32>), reachable::Error> {
+    let mut buf = Vec::new();
+    let mut tip_commits = Vec::new();
+    for mut id in tips {
+        loop {
+            let data = objects
+                .try_find(&id, &mut buf)?
+                .ok_or(reachable::Error::NotFound { id })?;
+            match data.kind {
+                gix_object::Kind::Tag => id = data.try_into_tag_iter().expect("tag").target_id()?,
+                gix_object::Kind::Commit => {
+                    tip_commits.push(id);
+                    break;
+                }
+                gix_object::Kind::Tree | gix_object::Kind::Blob => break,
+            }
+        }
+    }
+
+    let index_position = |id: &gix_hash::oid| {
+        index
+            .lookup(id)
+            .ok_or_else(|| reachable::Error::NotIndexed { id: id.to_owned() })
+    };
+    let mut seen = Bits::new(order.len());
+    let mut out = Vec::new();
+    let mut stack: Vec<_> = tip_commits.iter().rev().map(|id| (*id, false)).collect();
+    while let Some((id, parents_done)) = stack.pop() {
+        let index_position = index_position(&id)?;
+        if parents_done {
+            out.push(index_position);
+            continue;
+        }
+        if !seen.insert(order.bit_position(index_position)) {
+            continue;
+        }
+        stack.push((id, true));
+        let commit = objects
+            .try_find(&id, &mut buf)?
+            .and_then(gix_object::Data::try_into_commit_iter)
+            .ok_or(reachable::Error::NotFound { id })?;
+        stack.extend(commit.parent_ids().map(|id| (id, false)));
+    }
+    let tip_commits = tip_commits
+        .iter()
+        .map(|id| index_position(id))
+        .collect::<Result<_, _>>()?;
+    Ok((out, tip_commits))
+}
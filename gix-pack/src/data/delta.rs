@@ -85,3 +85,8 @@ pub(crate) fn apply(base: &[u8], mut target: &mut [u8], data: &[u8]) -> Result<(
 
     Ok(())
 }
+
+#[cfg(feature = "generate")]
+mod index;
+#[cfg(feature = "generate")]
+pub(crate) use index::Index;
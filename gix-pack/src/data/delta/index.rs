@@ -0,0 +1,237 @@
+/// The size of the blocks of the base object that are indexed to find matches in the target object.
+const BLOCK_SIZE: usize = 16;
+/// The maximum amount of base offsets per block hash, to avoid quadratic runtime on highly repetitive data.
+const MAX_ENTRIES_PER_HASH: usize = 64;
+/// The maximum amount of bytes a single copy instruction can reference.
+const MAX_COPY_SIZE: usize = 0x10000;
+/// The maximum amount of bytes a single insert instruction can carry.
+const MAX_INSERT_SIZE: usize = 0x7f;
+const HASH_MULTIPLIER: u32 = 0x0100_0193;
+
+/// An index over the blocks of a base object to efficiently produce deltas of many target objects against it.
+///
+/// It's the counterpart of [`apply()`] and produces deltas in the format used by git, which can be stored as
+/// `OfsDelta` or `RefDelta` entries in a pack.
+pub(crate) struct Index {
+    base: Vec<u8>,
+    /// `(hash of block, offset of block in base)` tuples, sorted by hash and offset.
+    blocks: Vec<(u32, u32)>,
+    /// The multiplier to remove the first byte of a block from its rolling hash.
+    outgoing_multiplier: u32,
+}
+
+impl Index {
+    /// Index `base` so it can serve as base object for deltas.
+    pub(crate) fn new(base: Vec<u8>) -> Self {
+        let mut blocks: Vec<_> = if u32::try_from(base.len()).is_ok() {
+            base.chunks_exact(BLOCK_SIZE)
+                .enumerate()
+                .map(|(idx, block)| (block_hash(block), (idx * BLOCK_SIZE) as u32))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        blocks.sort_unstable();
+        let mut entries_per_hash = 0;
+        let mut last_hash = None;
+        blocks.retain(|(hash, _)| {
+            if last_hash == Some(*hash) {
+                entries_per_hash += 1;
+            } else {
+                last_hash = Some(*hash);
+                entries_per_hash = 1;
+            }
+            entries_per_hash <= MAX_ENTRIES_PER_HASH
+        });
+        Index {
+            base,
+            blocks,
+            outgoing_multiplier: (1..BLOCK_SIZE).fold(1u32, |acc, _| acc.wrapping_mul(HASH_MULTIPLIER)),
+        }
+    }
+
+    /// Return the base object that was indexed.
+    pub(crate) fn base(&self) -> &[u8] {
+        &self.base
+    }
+
+    /// Write a delta to `out` that turns our base object into `target`, after clearing it.
+    ///
+    /// Return `false` if the delta would be larger than `max_size` bytes, in which case `out` contains an incomplete delta.
+    pub(crate) fn encode(&self, target: &[u8], max_size: usize, out: &mut Vec<u8>) -> bool {
+        out.clear();
+        encode_header_size(self.base.len() as u64, out);
+        encode_header_size(target.len() as u64, out);
+
+        let mut insert_start = 0;
+        let mut pos = 0;
+        let mut hash = target.get(..BLOCK_SIZE).map(block_hash);
+        while let Some(current_hash) = hash {
+            if let Some((mut base_ofs, mut len)) = self.longest_match(current_hash, &target[pos..]) {
+                let mut target_ofs = pos;
+                while target_ofs > insert_start && base_ofs > 0 && self.base[base_ofs - 1] == target[target_ofs - 1] {
+                    base_ofs -= 1;
+                    target_ofs -= 1;
+                    len += 1;
+                }
+                encode_inserts(&target[insert_start..target_ofs], out);
+                encode_copies(base_ofs, len, out);
+                if out.len() > max_size {
+                    return false;
+                }
+                pos = target_ofs + len;
+                insert_start = pos;
+                hash = target.get(pos..pos + BLOCK_SIZE).map(block_hash);
+            } else {
+                if pos - insert_start > max_size {
+                    return false;
+                }
+                hash = target.get(pos + BLOCK_SIZE).map(|incoming| {
+                    current_hash
+                        .wrapping_sub(u32::from(target[pos]).wrapping_mul(self.outgoing_multiplier))
+                        .wrapping_mul(HASH_MULTIPLIER)
+                        .wrapping_add(u32::from(*incoming))
+                });
+                pos += 1;
+            }
+        }
+        encode_inserts(&target[insert_start..], out);
+        out.len() <= max_size
+    }
+
+    /// Find the longest prefix of `target` in our base among all blocks with `hash`, and return its offset in the base and length.
+    fn longest_match(&self, hash: u32, target: &[u8]) -> Option<(usize, usize)> {
+        let start = self.blocks.partition_point(|(h, _)| *h < hash);
+        self.blocks[start..]
+            .iter()
+            .take_while(|(h, _)| *h == hash)
+            .map(|(_, ofs)| {
+                let ofs = *ofs as usize;
+                let len = self.base[ofs..]
+                    .iter()
+                    .zip(target)
+                    .take_while(|(lhs, rhs)| lhs == rhs)
+                    .count();
+                (ofs, len)
+            })
+            .filter(|(_, len)| *len >= BLOCK_SIZE)
+            .max_by(|(lhs_ofs, lhs_len), (rhs_ofs, rhs_len)| lhs_len.cmp(rhs_len).then(rhs_ofs.cmp(lhs_ofs)))
+    }
+}
+
+fn block_hash(block: &[u8]) -> u32 {
+    block.iter().fold(0u32, |hash, byte| {
+        hash.wrapping_mul(HASH_MULTIPLIER).wrapping_add(u32::from(*byte))
+    })
+}
+
+/// The inverse of [`decode_header_size()`].
+fn encode_header_size(mut size: u64, out: &mut Vec<u8>) {
+    while size >= 0x80 {
+        out.push((size as u8) | 0x80);
+        size >>= 7;
+    }
+    out.push(size as u8);
+}
+
+fn encode_inserts(data: &[u8], out: &mut Vec<u8>) {
+    for chunk in data.chunks(MAX_INSERT_SIZE) {
+        out.push(chunk.len() as u8);
+        out.extend_from_slice(chunk);
+    }
+}
+
+fn encode_copies(mut ofs: usize, mut len: usize, out: &mut Vec<u8>) {
+    while len != 0 {
+        let size = len.min(MAX_COPY_SIZE);
+        let cmd_pos = out.len();
+        let mut cmd = 0b1000_0000;
+        out.push(cmd);
+        for (bit, byte) in (ofs as u32).to_le_bytes().into_iter().enumerate() {
+            if byte != 0 {
+                cmd |= 1 << bit;
+                out.push(byte);
+            }
+        }
+        // A size of 0 is interpreted as the maximum copy size.
+        if size != MAX_COPY_SIZE {
+            for (bit, byte) in (size as u32).to_le_bytes().into_iter().take(3).enumerate() {
+                if byte != 0 {
+                    cmd |= 1 << (bit + 4);
+                    out.push(byte);
+                }
+            }
+        }
+        out[cmd_pos] = cmd;
+        ofs += size;
+        len -= size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Index;
+    use crate::data::delta::{apply, decode_header_size};
+
+    fn round_trip(base: &[u8], target: &[u8]) -> usize {
+        let index = Index::new(base.to_vec());
+        let mut delta = Vec::new();
+        assert!(index.encode(target, usize::MAX, &mut delta));
+
+        let (base_size, consumed) = decode_header_size(&delta);
+        assert_eq!(base_size, base.len() as u64);
+        let (target_size, consumed_target) = decode_header_size(&delta[consumed..]);
+        assert_eq!(target_size, target.len() as u64);
+
+        let mut out = vec![0; target.len()];
+        apply(base, &mut out, &delta[consumed + consumed_target..]).expect("valid delta");
+        assert_eq!(out, target);
+        delta.len()
+    }
+
+    fn pseudo_random_bytes(len: usize, mut seed: u32) -> Vec<u8> {
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                seed as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn empty_and_small_objects() {
+        round_trip(b"", b"");
+        round_trip(b"", b"hello");
+        round_trip(b"hello", b"");
+        round_trip(b"hello", b"hello");
+    }
+
+    #[test]
+    fn edits_of_large_objects_produce_small_deltas() {
+        let base = pseudo_random_bytes(200_000, 42);
+        let mut target = base.clone();
+        target.splice(1000..1000, *b"inserted");
+        target.drain(50_000..50_100);
+        target[150_000] ^= 0xff;
+        target.extend_from_slice(&base[..300]);
+
+        let delta_size = round_trip(&base, &target);
+        assert!(delta_size < 200, "only edits are stored, got {delta_size} bytes");
+    }
+
+    #[test]
+    fn unrelated_objects_are_all_inserts() {
+        let base = pseudo_random_bytes(1000, 1);
+        let target = pseudo_random_bytes(1000, 2);
+        let delta_size = round_trip(&base, &target);
+        assert!(delta_size > target.len());
+
+        let mut delta = Vec::new();
+        assert!(
+            !Index::new(base).encode(&target, target.len() / 2, &mut delta),
+            "encoding stops once the delta gets too large"
+        );
+    }
+}
@@ -35,10 +35,42 @@ impl Count {
         Count {
             id: oid.into(),
             entry_pack_location: PackLocation::LookedUp(location),
+            no_delta: false,
         }
     }
 }
 
+/// Return counts for all objects reachable from `wants` but not from `haves`, using `bitmap` for the objects in `index`,
+/// which are stored in `order` and can be read from `objects`.
+///
+/// Objects among `haves` that aren't contained in `index` are ignored, as are all `haves` if they reach objects outside of it,
+/// which only means more objects may be counted than strictly needed.
+/// The pack location of the counts isn't looked up yet.
+pub fn objects_from_bitmap(
+    bitmap: &crate::bitmap::File,
+    index: &dyn crate::bitmap::Index,
+    order: &crate::bitmap::Order,
+    objects: &dyn gix_object::Find,
+    wants: impl IntoIterator<Item = ObjectId>,
+    haves: impl IntoIterator<Item = ObjectId>,
+) -> Result<Vec<Count>, crate::bitmap::reachable::Error> {
+    let mut bits = bitmap.reachable(wants, index, order, objects)?;
+    let haves = haves.into_iter().filter(|id| index.lookup(id).is_some());
+    match bitmap.reachable(haves, index, order, objects) {
+        Ok(have_bits) => bits.difference_with(&have_bits),
+        Err(crate::bitmap::reachable::Error::NotIndexed { .. }) => {}
+        Err(err) => return Err(err),
+    }
+    Ok(bits
+        .iter()
+        .map(|pos| Count {
+            id: index.oid_at_index(order.index_position(pos)).to_owned(),
+            entry_pack_location: PackLocation::NotLookedUp,
+            no_delta: false,
+        })
+        .collect())
+}
+
 #[path = "objects/mod.rs"]
 mod objects_impl;
 pub use objects_impl::{objects, objects_unthreaded};
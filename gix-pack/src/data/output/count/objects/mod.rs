@@ -220,6 +220,8 @@ mod expand {
                                     out = objects.dissolve(stats);
                                     &traverse_delegate.non_trees
                                 } else {
+                                    // Additions accumulate across all parents as each object is only recorded once.
+                                    changes_delegate.clear();
                                     for commit_id in &parent_commit_ids {
                                         let parent_tree_id = {
                                             let (parent_commit_obj, location) = db.find(commit_id, buf2)?;
@@ -245,7 +247,6 @@ mod expand {
                                             gix_object::TreeRefIter::from_bytes(parent_tree_obj.data)
                                         };
 
-                                        changes_delegate.clear();
                                         let objects = CountingObjects::new(db);
                                         gix_diff::tree(
                                             parent_tree,
@@ -358,6 +359,7 @@ mod expand {
             } else {
                 PackLocation::NotLookedUp
             },
+            no_delta: false,
         }
     }
 
@@ -10,7 +10,7 @@ pub(crate) mod function {
         },
     };
 
-    use super::{reduce, util, Error, Mode, Options, Outcome, ProgressId};
+    use super::{reduce, search, util, Error, Mode, Options, Outcome, ProgressId};
     use crate::data::output;
 
     /// Given a known list of object `counts`, calculate entries ready to be put into a data pack.
@@ -97,8 +97,8 @@ pub(crate) mod function {
             .expect("infallible - we ignore none-existing objects");
             progress.lock().show_throughput(start);
         }
-        let counts_range_by_pack_id = match mode {
-            Mode::PackCopyAndBaseObjects => {
+        let counts_range_by_pack_id = {
+            {
                 let mut progress = progress.add_child_with_id("sorting".into(), ProgressId::SortEntries.into());
                 progress.init(Some(counts.len()), gix_features::progress::count("counts"));
                 let start = std::time::Instant::now();
@@ -134,8 +134,18 @@ pub(crate) mod function {
                 index
             }
         };
+        let deltas = match mode {
+            Mode::PackCopyAndBaseObjects => Vec::new(),
+            Mode::PackCopyAndDeltaCompression { window, depth } => {
+                let mut progress =
+                    progress.add_child_with_id("searching deltas".into(), ProgressId::DeltaCompression.into());
+                let unpacked_end = counts.partition_point(|e| e.entry_pack_location.is_none());
+                search::deltas(&mut counts[..unpacked_end], &db, window, depth, &mut progress)
+            }
+        };
 
         let counts = Arc::new(counts);
+        let deltas = Arc::new(deltas);
         let progress = Arc::new(parking_lot::Mutex::new(progress));
         let chunks = util::ChunkRanges::new(chunk_size, counts.len());
 
@@ -157,12 +167,19 @@ pub(crate) mod function {
                 let counts = Arc::clone(&counts);
                 move |(chunk_id, chunk_range): (SequenceId, std::ops::Range<usize>), (buf, progress)| {
                     let mut out = Vec::new();
+                    let chunk_deltas = deltas.get(chunk_range.clone()).unwrap_or_default();
                     let chunk = &counts[chunk_range];
                     let mut stats = Outcome::default();
                     let mut pack_offsets_to_id = None;
                     progress.init(Some(chunk.len()), gix_features::progress::count("objects"));
 
-                    for count in chunk.iter() {
+                    for (idx, count) in chunk.iter().enumerate() {
+                        if let Some(delta) = chunk_deltas.get(idx).and_then(Option::as_ref) {
+                            stats.delta_compressed_objects += 1;
+                            out.push(delta.clone());
+                            progress.inc();
+                            continue;
+                        }
                         out.push(match count
                             .entry_pack_location
                             .as_ref()
@@ -244,6 +261,109 @@ pub(crate) mod function {
     }
 }
 
+mod search {
+    use std::{cmp::Reverse, collections::VecDeque};
+
+    use gix_features::progress::{prodash::Count, Progress};
+
+    use crate::data::{delta, output};
+
+    /// Objects smaller than this aren't worth the effort of delta compression.
+    const MIN_OBJECT_SIZE: usize = 50;
+
+    struct Base {
+        /// The index of the object into the list of counts.
+        index: usize,
+        kind: gix_object::Kind,
+        /// The length of the delta chain leading to this object, with 0 for base objects.
+        depth: usize,
+        data: delta::Index,
+    }
+
+    /// Reorder the unpacked objects in `counts` so that similar objects are close to each other, and try to delta-compress
+    /// each of them against the `window` objects before it, so that delta chains are no longer than `depth`.
+    ///
+    /// Return a list of deltas, with an entry for each count, for each object that is smaller as delta.
+    /// Objects that can't be read are ignored here and handled during entry generation.
+    pub(super) fn deltas(
+        counts: &mut [output::Count],
+        db: &dyn crate::Find,
+        window: usize,
+        depth: usize,
+        progress: &mut dyn Progress,
+    ) -> Vec<Option<output::Entry>> {
+        if window == 0 || depth == 0 {
+            return Vec::new();
+        }
+        let start = std::time::Instant::now();
+        progress.init(Some(counts.len()), gix_features::progress::count("objects"));
+
+        let mut buf = Vec::new();
+        let mut keyed: Vec<_> = counts
+            .iter()
+            .map(|count| {
+                let key = (!count.no_delta)
+                    .then(|| db.try_find(&count.id, &mut buf).ok().flatten())
+                    .flatten()
+                    .filter(|(obj, _)| obj.data.len() >= MIN_OBJECT_SIZE)
+                    .map(|(obj, _)| (obj.kind, Reverse(obj.data.len())));
+                (key, count.clone())
+            })
+            .collect();
+        // Place objects of the same kind next to each other, and larger objects first so they become the bases of smaller ones,
+        // with objects that don't partake in the search first.
+        keyed.sort_by_key(|(key, _)| *key);
+        for (count, (_, sorted)) in counts.iter_mut().zip(keyed.iter_mut()) {
+            std::mem::swap(count, sorted);
+        }
+        let candidates_start = keyed.partition_point(|(key, _)| key.is_none());
+        progress.inc_by(candidates_start);
+
+        let mut out = vec![None; counts.len()];
+        let mut window_objects = VecDeque::<Base>::with_capacity(window);
+        let mut delta_buf = Vec::new();
+        let mut best = Vec::new();
+        for (index, count) in counts.iter().enumerate().skip(candidates_start) {
+            progress.inc();
+            let Some((obj, _)) = db.try_find(&count.id, &mut buf).ok().flatten() else {
+                continue;
+            };
+            let kind = obj.kind;
+            let target = obj.data;
+            let mut max_size = (target.len() / 2).saturating_sub(count.id.as_slice().len());
+            let mut best_base = None;
+            for base in window_objects.iter().rev() {
+                if base.kind != kind || base.depth >= depth || base.data.base().len() / 32 > target.len() {
+                    continue;
+                }
+                if max_size != 0 && base.data.encode(target, max_size, &mut delta_buf) {
+                    max_size = delta_buf.len() - 1;
+                    std::mem::swap(&mut best, &mut delta_buf);
+                    best_base = Some((base.index, base.depth));
+                }
+            }
+            let depth = match best_base {
+                Some((base_index, base_depth)) => {
+                    out[index] = output::Entry::from_delta(count, base_index, &best).ok();
+                    base_depth + 1
+                }
+                None => 0,
+            };
+            if window_objects.len() == window {
+                window_objects.pop_front();
+            }
+            window_objects.push_back(Base {
+                index,
+                kind,
+                depth,
+                data: delta::Index::new(target.to_owned()),
+            });
+        }
+        progress.show_throughput(start);
+        out
+    }
+}
+
 mod util {
     #[derive(Clone)]
     pub struct ChunkRanges {
@@ -335,6 +455,8 @@ mod types {
         pub objects_copied_from_pack: usize,
         /// The amount of objects that ref to their base as ref-delta, an indication for a thin back being created.
         pub ref_delta_objects: usize,
+        /// The amount of objects that were delta-compressed against another object in the pack while searching for deltas.
+        pub delta_compressed_objects: usize,
     }
 
     impl Outcome {
@@ -345,12 +467,14 @@ mod types {
                 missing_objects,
                 objects_copied_from_pack,
                 ref_delta_objects,
+                delta_compressed_objects,
             }: Self,
         ) {
             self.decoded_and_recompressed_objects += decoded_objects;
             self.missing_objects += missing_objects;
             self.objects_copied_from_pack += objects_copied_from_pack;
             self.ref_delta_objects += ref_delta_objects;
+            self.delta_compressed_objects += delta_compressed_objects;
         }
     }
 
@@ -363,6 +487,16 @@ mod types {
         /// from existing pack compression and spending the smallest possible time on compressing unpacked objects at
         /// the cost of bandwidth.
         PackCopyAndBaseObjects,
+        /// Like [`PackCopyAndBaseObjects`](Mode::PackCopyAndBaseObjects), but non-packed objects are delta-compressed
+        /// against each other if that makes them smaller, which is slower but can produce much smaller packs.
+        ///
+        /// Objects marked with [`no_delta`](crate::data::output::Count::no_delta) are always written as base objects.
+        PackCopyAndDeltaCompression {
+            /// The amount of objects to try as delta base for each object, like `pack.window` in git.
+            window: usize,
+            /// The maximum length of delta chains, like `pack.depth` in git.
+            depth: usize,
+        },
     }
 
     /// Configuration options for the pack generation functions provided in [`iter_from_counts()`][crate::data::output::entry::iter_from_counts()].
@@ -381,7 +515,6 @@ mod types {
         /// If set to false, delta objects will be decompressed and recompressed as base objects.
         pub allow_thin_pack: bool,
         /// The amount of objects per chunk or unit of work to be sent to threads for processing
-        /// TODO: could this become the window size?
         pub chunk_size: usize,
         /// The pack data version to produce for each entry
         pub version: crate::data::Version,
@@ -418,6 +551,8 @@ mod types {
         ResolveCounts,
         /// Layout pack entries for placement into a pack (by pack-id and by offset).
         SortEntries,
+        /// The amount of non-packed objects that were tried to be delta-compressed against similar objects.
+        DeltaCompression,
     }
 
     impl From<ProgressId> for gix_features::progress::Id {
@@ -425,6 +560,7 @@ mod types {
             match v {
                 ProgressId::ResolveCounts => *b"ECRC",
                 ProgressId::SortEntries => *b"ECSE",
+                ProgressId::DeltaCompression => *b"ECDC",
             }
         }
     }
@@ -14,6 +14,9 @@ pub struct Count {
     pub id: ObjectId,
     /// A way to locate a pack entry in the object database, only available if the object is in a pack.
     pub entry_pack_location: count::PackLocation,
+    /// If `true`, the object must not be delta-compressed against other objects, nor serve as base for other objects,
+    /// when searching for deltas. This is typically set for blobs with the `delta` attribute unset.
+    pub no_delta: bool,
 }
 
 /// An entry to be written to a file.
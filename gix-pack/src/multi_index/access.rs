@@ -129,6 +129,20 @@ impl File {
         (pack_index, pack_offset)
     }
 
+    /// Return the entry index of each object in pseudo-pack order, the order in which objects appear when concatenating
+    /// all packs, or `None` if the file has no reverse index.
+    ///
+    /// This order is used for the bits of multi-pack bitmaps.
+    pub fn reverse_index(&self) -> Option<Vec<EntryIndex>> {
+        let ofs = self.reverse_index_ofs?;
+        Some(
+            self.data[ofs..][..self.num_objects as usize * 4]
+                .chunks_exact(4)
+                .map(crate::read_u32)
+                .collect(),
+        )
+    }
+
     /// Return an iterator over all entries within this file.
     pub fn iter(&self) -> impl Iterator<Item = Entry> + '_ {
         (0..self.num_objects).map(move |idx| {
@@ -272,3 +272,40 @@ pub mod large_offsets {
         8 * large_offsets as u64
     }
 }
+
+/// Information about the reverse index, which stores the pseudo-pack order of all objects, as needed for bitmaps.
+pub mod reverse_index {
+    use std::ops::Range;
+
+    use crate::multi_index;
+
+    /// The id uniquely identifying the reverse index.
+    pub const ID: gix_chunk::Id = *b"RIDX";
+
+    /// Return the amount of bytes needed to store the reverse index of `entries`.
+    pub fn storage_size(entries: usize) -> u64 {
+        4 * entries as u64
+    }
+
+    /// Returns true if the `offset` range seems to match the size required for `num_objects`.
+    pub fn is_valid(offset: &Range<usize>, num_objects: u32) -> bool {
+        offset.end - offset.start == 4 * num_objects as usize
+    }
+
+    /// Write the position of each of `sorted_entries` in the order of packs and their offsets within them, which is the
+    /// pseudo-pack order.
+    pub(crate) fn write(
+        sorted_entries: &[multi_index::write::Entry],
+        out: &mut dyn std::io::Write,
+    ) -> std::io::Result<()> {
+        let mut positions: Vec<_> = (0..sorted_entries.len() as u32).collect();
+        positions.sort_by_key(|pos| {
+            let entry = &sorted_entries[*pos as usize];
+            (entry.pack_index, entry.pack_offset)
+        });
+        for pos in positions {
+            out.write_all(&pos.to_be_bytes())?;
+        }
+        Ok(())
+    }
+}
@@ -36,6 +36,11 @@ pub(crate) struct Entry {
 pub struct Options {
     /// The kind of hash to use for objects and to expect in the input files.
     pub object_hash: gix_hash::Kind,
+    /// If `true`, write the pseudo-pack order of all objects, which is needed to write a [bitmap](crate::bitmap) for the
+    /// multi-pack index.
+    ///
+    /// Objects contained in multiple packs are then preferably taken from the first pack, in the order of file names.
+    pub write_reverse_index: bool,
 }
 
 /// The result of [`multi_index::File::write_from_index_paths()`].
@@ -81,7 +86,10 @@ impl multi_index::File {
         out: &mut dyn std::io::Write,
         progress: &mut dyn DynNestedProgress,
         should_interrupt: &AtomicBool,
-        Options { object_hash }: Options,
+        Options {
+            object_hash,
+            write_reverse_index,
+        }: Options,
     ) -> Result<Outcome, Error> {
         let out = gix_hash::io::Write::new(out, object_hash);
         let (index_paths_sorted, index_filenames_sorted) = {
@@ -129,6 +137,14 @@ impl multi_index::File {
             progress.init(Some(entries.len()), gix_features::progress::count("entries"));
             entries.sort_by(|l, r| {
                 l.id.cmp(&r.id)
+                    .then_with(|| {
+                        if write_reverse_index {
+                            // Keep the first pack complete so readers can reuse it verbatim.
+                            (l.pack_index != 0).cmp(&(r.pack_index != 0))
+                        } else {
+                            std::cmp::Ordering::Equal
+                        }
+                    })
                     .then_with(|| l.index_mtime.cmp(&r.index_mtime).reverse())
                     .then_with(|| l.pack_index.cmp(&r.pack_index))
             });
@@ -163,6 +179,12 @@ impl multi_index::File {
                 multi_index::chunk::large_offsets::storage_size(num_large_offsets),
             );
         }
+        if write_reverse_index {
+            cf.plan_chunk(
+                multi_index::chunk::reverse_index::ID,
+                multi_index::chunk::reverse_index::storage_size(entries.len()),
+            );
+        }
 
         let mut write_progress =
             progress.add_child_with_id("Writing multi-index".into(), ProgressId::BytesWritten.into());
@@ -206,6 +228,9 @@ impl multi_index::File {
                         num_large_offsets.expect("available if planned"),
                         &mut chunk_write,
                     ),
+                    multi_index::chunk::reverse_index::ID => {
+                        multi_index::chunk::reverse_index::write(&entries, &mut chunk_write)
+                    }
                     unknown => unreachable!("BUG: forgot to implement chunk {:?}", std::str::from_utf8(&unknown)),
                 }
                 .map_err(gix_hash::io::Error::from)?;
@@ -0,0 +1,36 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function make_history() {
+  git init -q
+  git checkout -q -b main
+  for round in $(seq 24); do
+    mkdir -p "dir-$((round % 3))"
+    seq "$round" > "dir-$((round % 3))/file-$((round % 5))"
+    echo "$round" > top-level
+    git add .
+    git commit -qm "$round"
+    if [ $((round % 8)) = 0 ]; then
+      git repack -q -d
+    fi
+  done
+  git tag -m "a tag object" annotated HEAD~2
+  git checkout -q -b side HEAD~5
+  echo side > side
+  git add side
+  git commit -qm "side"
+  git checkout -q main
+  git merge -q --no-edit side
+  git rev-parse HEAD HEAD~3 annotated side > tips
+}
+
+(mkdir single && cd single
+  make_history
+  git repack -q -adb
+)
+
+(mkdir multi && cd multi
+  make_history
+  git repack -q -d
+  git multi-pack-index write --bitmap
+)
@@ -0,0 +1,258 @@
+use std::{collections::BTreeSet, path::PathBuf, sync::atomic::AtomicBool};
+
+use gix_features::progress;
+use gix_hash::ObjectId;
+use gix_pack::bitmap::{self, Bits, Index, Order};
+
+fn repo(name: &str) -> crate::Result<(PathBuf, gix_odb::Handle, Vec<ObjectId>)> {
+    let dir = crate::scripted_fixture_read_only("make_pack_bitmap_repo.sh")?.join(name);
+    let objects = gix_odb::at(dir.join(".git/objects"))?;
+    let tips = std::fs::read_to_string(dir.join("tips"))?
+        .lines()
+        .map(|hex| ObjectId::from_hex(hex.as_bytes()))
+        .collect::<Result<_, _>>()?;
+    Ok((dir.join(".git/objects/pack"), objects, tips))
+}
+
+fn bitmap_path(pack_dir: &std::path::Path, prefix: &str) -> crate::Result<PathBuf> {
+    Ok(std::fs::read_dir(pack_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .find(|p| {
+            p.extension().is_some_and(|ext| ext == "bitmap")
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.starts_with(prefix))
+        })
+        .expect("bitmap was written by git"))
+}
+
+fn ids(bits: &Bits, index: &dyn Index, order: &Order) -> BTreeSet<ObjectId> {
+    bits.iter()
+        .map(|pos| index.oid_at_index(order.index_position(pos)).to_owned())
+        .collect()
+}
+
+fn write_and_open(
+    tips: &[ObjectId],
+    index: &dyn Index,
+    order: &Order,
+    objects: &gix_odb::Handle,
+    options: bitmap::write::Options,
+) -> crate::Result<(bitmap::File, bitmap::write::Outcome)> {
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let path = dir.path().join("written.bitmap");
+    let mut out = std::fs::File::create(&path)?;
+    let outcome = bitmap::File::write_to(&mut out, tips.iter().copied(), index, order, objects, options)?;
+    Ok((bitmap::File::at(&path, gix_hash::Kind::Sha1)?, outcome))
+}
+
+/// Assert that `actual` and `expected` agree on everything reachable from each of `tips`, and on the types of all objects.
+fn assert_same_reachability(
+    actual: &bitmap::File,
+    expected: &bitmap::File,
+    tips: &[ObjectId],
+    index: &dyn Index,
+    order: &Order,
+    objects: &gix_odb::Handle,
+) -> crate::Result {
+    for tip in tips {
+        assert_eq!(
+            actual.reachable(Some(*tip), index, order, objects)?,
+            expected.reachable(Some(*tip), index, order, objects)?,
+            "{tip}"
+        );
+    }
+    for kind in [
+        gix_object::Kind::Commit,
+        gix_object::Kind::Tree,
+        gix_object::Kind::Blob,
+        gix_object::Kind::Tag,
+    ] {
+        assert_eq!(
+            actual.type_bits(kind, order.len())?,
+            expected.type_bits(kind, order.len())?,
+            "{kind}"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn ewah_round_trip() -> crate::Result {
+    let mut words = vec![0u64; 3];
+    words.extend([u64::MAX; 2]);
+    words.extend([0x5, 0, 0x8000_0000_0000_0000, u64::MAX]);
+    words.push(0b111);
+    let num_bits = (words.len() - 1) as u32 * 64 + 3;
+    for bits in [&words[..], &[], &[0b1]] {
+        let num_bits = if bits.len() == words.len() {
+            num_bits
+        } else {
+            bits.len() as u32 * 64
+        };
+        let bitmap = gix_bitmap::ewah::encode(bits, num_bits);
+        assert_eq!(bitmap.to_bits(), bits);
+        assert_eq!(bitmap.num_bits(), num_bits as usize);
+
+        let mut buf = Vec::new();
+        bitmap.write_to(&mut buf)?;
+        let (decoded, rest) = gix_bitmap::ewah::decode(&buf)?;
+        assert!(rest.is_empty());
+        assert_eq!(decoded, bitmap);
+
+        let mut set_bits = Vec::new();
+        decoded.for_each_set_bit(|idx| {
+            set_bits.push(idx);
+            Some(())
+        });
+        let expected: Vec<_> = (0..num_bits as usize)
+            .filter(|idx| bits[idx / 64] & (1 << (idx % 64)) != 0)
+            .collect();
+        assert_eq!(set_bits, expected, "the encoding is compatible to what we decode");
+    }
+    Ok(())
+}
+
+#[test]
+fn pack_bitmap_written_by_git() -> crate::Result {
+    let (pack_dir, objects, tips) = repo("single")?;
+    let bitmap_path = bitmap_path(&pack_dir, "pack-")?;
+    let index = gix_pack::index::File::at(bitmap_path.with_extension("idx"), gix_hash::Kind::Sha1)?;
+    let order = Order::from_index(&index);
+    let bitmap = bitmap::File::at(&bitmap_path, gix_hash::Kind::Sha1)?;
+    assert_eq!(bitmap.checksum(), index.pack_checksum());
+    assert!(bitmap.num_bitmaps() > 0);
+
+    let all = bitmap.reachable(tips.iter().copied(), &index, &order, &objects)?;
+    assert_eq!(
+        all.count_ones(),
+        index.num_objects() as usize,
+        "everything in the pack is reachable from all tips"
+    );
+    let num_commits = bitmap.type_bits(gix_object::Kind::Commit, order.len())?.count_ones();
+    assert_eq!(num_commits, 26, "24 commits on main, one on the side and a merge");
+    assert_eq!(bitmap.type_bits(gix_object::Kind::Tag, order.len())?.count_ones(), 1);
+
+    let (written, outcome) = write_and_open(&tips, &index, &order, &objects, Default::default())?;
+    assert_eq!(outcome.num_bitmaps, 4, "one for each tip, and the first commit");
+    assert_eq!(written.checksum(), index.pack_checksum());
+    assert_same_reachability(&written, &bitmap, &tips, &index, &order, &objects)?;
+
+    let (written, outcome) = write_and_open(
+        &tips[..1],
+        &index,
+        &order,
+        &objects,
+        bitmap::write::Options { commit_interval: 5 },
+    )?;
+    assert_eq!(
+        outcome.num_bitmaps, 6,
+        "every fifth of 26 commits, the last one being the tip"
+    );
+    assert_same_reachability(&written, &bitmap, &tips, &index, &order, &objects)?;
+    Ok(())
+}
+
+#[test]
+fn multi_index_bitmap_written_by_git() -> crate::Result {
+    let (pack_dir, objects, tips) = repo("multi")?;
+    let index = gix_pack::multi_index::File::at(pack_dir.join("multi-pack-index"))?;
+    assert!(index.num_indices() > 1);
+    let order = Order::from_multi_index(&index)?;
+    let bitmap = bitmap::File::at(bitmap_path(&pack_dir, "multi-pack-index-")?, gix_hash::Kind::Sha1)?;
+    assert_eq!(bitmap.checksum(), index.checksum());
+
+    let all = bitmap.reachable(tips.iter().copied(), &index, &order, &objects)?;
+    assert_eq!(all.count_ones(), index.num_objects() as usize);
+
+    let (written, _outcome) = write_and_open(&tips, &index, &order, &objects, Default::default())?;
+    assert_same_reachability(&written, &bitmap, &tips, &index, &order, &objects)?;
+
+    let dir = gix_testtools::tempfile::TempDir::new()?;
+    let mut index_paths = Vec::new();
+    for entry in std::fs::read_dir(&pack_dir)? {
+        let path = entry?.path();
+        if matches!(path.extension().and_then(|ext| ext.to_str()), Some("idx" | "pack")) {
+            let copy = dir.path().join(path.file_name().expect("present"));
+            std::fs::copy(&path, &copy)?;
+            if copy.extension().is_some_and(|ext| ext == "idx") {
+                index_paths.push(copy);
+            }
+        }
+    }
+    let midx_path = dir.path().join("multi-pack-index");
+    gix_pack::multi_index::File::write_from_index_paths(
+        index_paths,
+        &mut std::fs::File::create(&midx_path)?,
+        &mut progress::Discard,
+        &AtomicBool::new(false),
+        gix_pack::multi_index::write::Options {
+            object_hash: gix_hash::Kind::Sha1,
+            write_reverse_index: true,
+        },
+    )?;
+    let our_index = gix_pack::multi_index::File::at(&midx_path)?;
+    let our_order = Order::from_multi_index(&our_index)?;
+    assert_eq!(our_order.len(), order.len());
+    let (written, _outcome) = write_and_open(&tips, &our_index, &our_order, &objects, Default::default())?;
+    for tip in &tips {
+        assert_eq!(
+            ids(
+                &written.reachable(Some(*tip), &our_index, &our_order, &objects)?,
+                &our_index,
+                &our_order
+            ),
+            ids(&bitmap.reachable(Some(*tip), &index, &order, &objects)?, &index, &order),
+            "{tip}: bit positions differ, but the objects are the same"
+        );
+    }
+
+    let err = written.reachable(Some(tips[0]), &index, &order, &objects).unwrap_err();
+    assert!(
+        matches!(err, bitmap::reachable::Error::ChecksumMismatch { .. }),
+        "bitmaps can't be used with a different index"
+    );
+    Ok(())
+}
+
+#[test]
+fn count_objects_from_bitmap() -> crate::Result {
+    let (pack_dir, objects, tips) = repo("single")?;
+    let bitmap_path = bitmap_path(&pack_dir, "pack-")?;
+    let index = gix_pack::index::File::at(bitmap_path.with_extension("idx"), gix_hash::Kind::Sha1)?;
+    let order = Order::from_index(&index);
+    let bitmap = bitmap::File::at(&bitmap_path, gix_hash::Kind::Sha1)?;
+
+    let (head, head_3) = (tips[0], tips[1]);
+    let counts = gix_pack::data::output::count::objects_from_bitmap(
+        &bitmap,
+        &index,
+        &order,
+        &objects,
+        Some(head),
+        [head_3, gix_hash::Kind::Sha1.null()],
+    )?;
+    let mut expected = bitmap.reachable(Some(head), &index, &order, &objects)?;
+    expected.difference_with(&bitmap.reachable(Some(head_3), &index, &order, &objects)?);
+    assert_eq!(
+        counts.iter().map(|c| c.id).collect::<BTreeSet<_>>(),
+        ids(&expected, &index, &order),
+        "haves that aren't in the pack are ignored"
+    );
+    assert!(counts.iter().any(|c| c.id == head));
+    assert!(!counts.iter().any(|c| c.id == head_3));
+
+    let err = gix_pack::data::output::count::objects_from_bitmap(
+        &bitmap,
+        &index,
+        &order,
+        &objects,
+        Some(gix_hash::Kind::Sha1.null()),
+        None,
+    )
+    .unwrap_err();
+    assert!(matches!(err, bitmap::reachable::Error::NotIndexed { .. }));
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use std::sync::atomic::AtomicBool;
+use std::sync::{atomic::AtomicBool, Arc};
 
 use gix_features::{
     parallel::{reduce::Finalize, InOrderIter},
@@ -121,6 +121,7 @@ fn traversals() -> crate::Result {
                     missing_objects: 0,
                     objects_copied_from_pack: 16,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("b920bbb055e1efb9080592a409d3975738b6efb3"),
                 None,
@@ -154,6 +155,7 @@ fn traversals() -> crate::Result {
                     missing_objects: 0,
                     objects_copied_from_pack: 103,
                     ref_delta_objects: 74,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("25114bd8820b393c402cd53ad8ec7f6a84bb0633"),
                 Some(hex_to_id("29ab9797aff1ca826afb699680356695d19c5acb")),
@@ -187,6 +189,7 @@ fn traversals() -> crate::Result {
                     missing_objects: 0,
                     objects_copied_from_pack: 29,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("d83d42128e40957c5174920189a0390b5a70f446"),
                 None,
@@ -208,6 +211,7 @@ fn traversals() -> crate::Result {
                     missing_objects: 0,
                     objects_copied_from_pack: 868,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
                 None,
@@ -229,6 +233,7 @@ fn traversals() -> crate::Result {
                     missing_objects: 0,
                     objects_copied_from_pack: 868,
                     ref_delta_objects: 0,
+                    delta_compressed_objects: 0,
                 },
                 hex_to_id("542ad1d1c7c762ea4e36907570ff9e4b5b7dde1b"),
                 None,
@@ -320,6 +325,85 @@ fn traversals() -> crate::Result {
     Ok(())
 }
 
+#[test]
+fn delta_compression_of_loose_objects() -> crate::Result {
+    use gix_object::Write;
+
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let db = Arc::new(gix_odb::Store::at_opts(
+        tmp.path().to_owned(),
+        &mut None.into_iter(),
+        gix_odb::store::init::Options::default(),
+    )?)
+    .to_cache_arc();
+
+    let mut content: Vec<u8> = (0..2000u32).flat_map(|n| format!("line {n}\n").into_bytes()).collect();
+    let mut counts = Vec::new();
+    for revision in 0..10 {
+        content.splice(
+            revision * 500..revision * 500,
+            format!("edit {revision}\n").into_bytes(),
+        );
+        counts.push(output::Count::from_data(
+            db.write_buf(gix_object::Kind::Blob, &content)?,
+            None,
+        ));
+    }
+    let mut unrelated = output::Count::from_data(db.write_buf(gix_object::Kind::Blob, &content.repeat(2))?, None);
+    unrelated.no_delta = true;
+    counts.push(unrelated.clone());
+
+    let pack_size = |mode| -> crate::Result<(usize, entry::iter_from_counts::Outcome, Vec<output::Entry>)> {
+        let mut entries_iter = output::entry::iter_from_counts(
+            counts.clone(),
+            db.clone(),
+            Box::new(progress::Discard),
+            output::entry::iter_from_counts::Options {
+                mode,
+                ..Default::default()
+            },
+        );
+        let entries: Vec<_> = InOrderIter::from(entries_iter.by_ref())
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
+            .flatten()
+            .collect();
+        let stats = entries_iter.finalize()?;
+        Ok((entries.iter().map(|e| e.compressed_data.len()).sum(), stats, entries))
+    };
+
+    let (base_size, base_stats, _) = pack_size(entry::iter_from_counts::Mode::PackCopyAndBaseObjects)?;
+    assert_eq!(base_stats.decoded_and_recompressed_objects, 11);
+    assert_eq!(base_stats.delta_compressed_objects, 0);
+
+    let (delta_size, delta_stats, entries) =
+        pack_size(entry::iter_from_counts::Mode::PackCopyAndDeltaCompression { window: 10, depth: 50 })?;
+    assert_eq!(
+        delta_stats.delta_compressed_objects, 9,
+        "all but the largest revision are stored as deltas"
+    );
+    assert_eq!(delta_stats.decoded_and_recompressed_objects, 2);
+    assert!(
+        delta_size * 3 < base_size,
+        "deltas make the pack much smaller: {delta_size} vs {base_size}"
+    );
+    let unrelated_entry = entries.iter().find(|e| e.id == unrelated.id).expect("present");
+    assert_eq!(
+        unrelated_entry.kind,
+        entry::Kind::Base(gix_object::Kind::Blob),
+        "objects marked as such are never delta-compressed"
+    );
+
+    let (_, shallow_stats, _) =
+        pack_size(entry::iter_from_counts::Mode::PackCopyAndDeltaCompression { window: 1, depth: 1 })?;
+    assert_eq!(
+        shallow_stats.delta_compressed_objects, 5,
+        "delta chains are limited in length, so only every other object can be a delta"
+    );
+
+    write_and_verify(db, entries, hex_to_id("029d08823bd8a8eab510ad6ac75c823cfd3ed31e"), None)
+}
+
 #[test]
 fn empty_pack_is_allowed() {
     assert_eq!(
@@ -422,3 +506,69 @@ fn write_and_verify(
 
     Ok(())
 }
+
+#[test]
+fn tree_additions_of_merges_include_changes_compared_to_all_parents() -> crate::Result {
+    use gix_object::{tree, Write};
+
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    let mut db = std::sync::Arc::new(gix_odb::Store::at_opts(
+        tmp.path().to_owned(),
+        &mut None.into_iter(),
+        gix_odb::store::init::Options::default(),
+    )?)
+    .to_cache_arc();
+    db.prevent_pack_unload();
+
+    let blob = |content: &str| db.write_buf(gix_object::Kind::Blob, content.as_bytes());
+    let tree = |names: &[(&str, gix_hash::ObjectId)]| {
+        let mut entries: Vec<_> = names
+            .iter()
+            .map(|(name, oid)| tree::Entry {
+                mode: tree::EntryKind::Blob.into(),
+                filename: (*name).into(),
+                oid: *oid,
+            })
+            .collect();
+        entries.sort();
+        db.write(&gix_object::Tree { entries })
+    };
+    let commit = |tree: gix_hash::ObjectId, parents: &[gix_hash::ObjectId]| {
+        db.write(&gix_object::Commit {
+            tree,
+            parents: parents.iter().copied().collect(),
+            author: Default::default(),
+            committer: Default::default(),
+            encoding: None,
+            message: "message".into(),
+            extra_headers: Vec::new(),
+        })
+    };
+
+    let (a, b, c, d) = (blob("a")?, blob("b")?, blob("c")?, blob("d")?);
+    let root = commit(tree(&[("a", a)])?, &[])?;
+    let first = commit(tree(&[("a", a), ("b", b)])?, &[root])?;
+    let second = commit(tree(&[("a", a), ("c", c)])?, &[root])?;
+    let merge = commit(tree(&[("a", a), ("b", b), ("c", c), ("d", d)])?, &[first, second])?;
+
+    let (counts, _stats) = output::count::objects(
+        db.clone(),
+        Box::new(std::iter::once(Ok(merge))),
+        &progress::Discard,
+        &AtomicBool::new(false),
+        count::objects::Options {
+            input_object_expansion: count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+            thread_limit: Some(1),
+            ..Default::default()
+        },
+    )?;
+    let ids: Vec<_> = counts.iter().map(|count| count.id).collect();
+    for (blob, name) in [(b, "b"), (c, "c"), (d, "d")] {
+        assert!(
+            ids.contains(&blob),
+            "blob '{name}' is new compared to at least one parent and must be counted"
+        );
+    }
+    assert!(!ids.contains(&a), "blobs present in all parents aren't counted");
+    Ok(())
+}
@@ -28,6 +28,7 @@ fn from_paths() -> crate::Result {
         &AtomicBool::new(false),
         gix_pack::multi_index::write::Options {
             object_hash: gix_hash::Kind::Sha1,
+            write_reverse_index: false,
         },
     )?;
 
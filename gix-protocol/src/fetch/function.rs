@@ -52,6 +52,7 @@ pub async fn fetch<P, T, E>(
         shallow,
         tags,
         reject_shallow_remote,
+        filter,
     }: Options<'_>,
 ) -> Result<Option<Outcome>, Error>
 where
@@ -86,6 +87,15 @@ where
         arguments.use_include_tag();
     }
     let (shallow_commits, mut shallow_lock) = add_shallow_args(&mut arguments, shallow, &shallow_file)?;
+    if let Some(spec) = filter {
+        if !arguments.can_use_filter() {
+            return Err(Error::MissingServerFeature {
+                feature: "filter",
+                description: "partial clones need server support to omit objects, otherwise all objects are sent",
+            });
+        }
+        arguments.filter(spec);
+    }
 
     let negotiate_span = gix_trace::detail!(
         "negotiate",
@@ -250,7 +260,7 @@ fn add_shallow_args(
     Ok((shallow_commits, shallow_lock))
 }
 
-fn setup_remote_progress<'a>(
+pub(crate) fn setup_remote_progress<'a>(
     progress: &mut dyn gix_features::progress::DynNestedProgress,
     reader: &mut Box<dyn ExtendedBufRead<'a> + Unpin + 'a>,
     should_interrupt: &'a AtomicBool,
@@ -16,6 +16,10 @@ pub struct Options<'a> {
     /// If `true`, if we fetch from a remote that only offers shallow clones, the operation will fail with an error
     /// instead of writing the shallow boundary to the shallow file.
     pub reject_shallow_remote: bool,
+    /// If set, the filter spec like `blob:none` to ask the server to omit objects with, to create or update a partial clone.
+    ///
+    /// Note that the server must support filters, or the operation will fail.
+    pub filter: Option<&'a str>,
 }
 
 /// For use in [`crate::Handshake::prepare_lsrefs_or_extract_refmap()`] and [`fetch`](crate::fetch()).
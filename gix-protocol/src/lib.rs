@@ -9,6 +9,13 @@
 //!          - create a mapping between [refspecs and references](fetch::RefMap)
 //!     - [receive a pack](fetch())
 //!
+//! For pushing, the order is similar.
+//!
+//! * perform a [`handshake()`] with the `receive-pack` service
+//! * [send reference updates along with a pack](push())
+//!
+//! The [server side](server) is provided as well, to serve clients that fetch from or push to us.
+//!
 //! ## Feature Flags
 #![cfg_attr(
     all(doc, feature = "document-features"),
@@ -50,6 +57,12 @@ pub mod fetch;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use fetch::function::fetch;
 
+///
+pub mod push;
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+pub use push::function::push;
+
 mod remote_progress;
 pub use remote_progress::RemoteProgress;
 
@@ -69,5 +82,9 @@ pub mod ls_refs;
 #[cfg(any(feature = "blocking-client", feature = "async-client"))]
 pub use ls_refs::function::LsRefsCommand;
 
+///
+#[cfg(feature = "blocking-server")]
+pub mod server;
+
 mod util;
 pub use util::*;
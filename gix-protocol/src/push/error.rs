@@ -0,0 +1,29 @@
+/// The error returned by [`push()`](crate::push()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Client(#[from] crate::transport::client::Error),
+    #[error("Server lack feature {feature:?}: {description}")]
+    MissingServerFeature {
+        feature: &'static str,
+        description: &'static str,
+    },
+    #[error("Failed to write the pack to the remote")]
+    WritePack(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Failed to read the status report sent by the server")]
+    ReadReport(#[source] std::io::Error),
+    #[error("Could not decode packetline in the server report")]
+    DecodeReportLine(#[from] gix_transport::packetline::decode::Error),
+    #[error(transparent)]
+    Report(#[from] crate::push::response::Error),
+}
+
+impl crate::transport::IsSpuriousError for Error {
+    fn is_spurious(&self) -> bool {
+        match self {
+            Error::Client(err) => err.is_spurious(),
+            _ => false,
+        }
+    }
+}
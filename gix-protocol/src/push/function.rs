@@ -0,0 +1,225 @@
+use std::{
+    borrow::Cow,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use bstr::ByteSlice;
+#[cfg(feature = "async-client")]
+use futures_lite::io::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "blocking-client")]
+use std::io::{Read, Write};
+
+use crate::push::{response, Command, Context, Error, MaybeSendStatic, Options, Outcome, WritePack};
+#[cfg(feature = "async-client")]
+use crate::transport::client::async_io::Transport;
+#[cfg(feature = "blocking-client")]
+use crate::transport::client::blocking_io::Transport;
+use crate::transport::{client, packetline};
+
+/// Send `commands` to the `receive-pack` service on the other side of `transport` to update references,
+/// along with a pack written by `write_pack(pack_writer, progress, interrupt)`.
+///
+/// `write_pack` is only called if at least one of the `commands` isn't a deletion, and it's expected to write a complete
+/// pack that contains all objects needed by the remote to make the `new` objects of all `commands` reachable, assuming
+/// it has everything reachable from the `old` objects and the references it advertised.
+/// `progress` and `should_interrupt` is passed to all potentially long-running parts of the operation.
+///
+/// The `Context` and `Options` further define parts of this `push` operation.
+///
+/// If `commands` is empty, nothing is sent and the default [`Outcome`] is returned.
+///
+/// **Note that the interaction will never be ended**, leaving it up to the caller to do that, maybe
+/// with the help of [`SendFlushOnDrop`](crate::SendFlushOnDrop) which can wrap `transport`.
+///
+/// Note that rejected reference updates are not considered an error, and are communicated through the
+/// [report](response::Report) in the [`Outcome`] instead.
+#[maybe_async::maybe_async]
+pub async fn push<P, T, E>(
+    commands: &[Command],
+    write_pack: impl WritePack<E>,
+    mut progress: P,
+    should_interrupt: &AtomicBool,
+    Context {
+        handshake,
+        transport,
+        user_agent,
+        trace_packetlines,
+    }: Context<'_, T>,
+    Options {
+        atomic,
+        push_options,
+        quiet,
+    }: Options<'_>,
+) -> Result<Outcome, Error>
+where
+    P: gix_features::progress::NestedProgress,
+    P::SubProgress: 'static,
+    T: Transport,
+    E: Into<Box<dyn std::error::Error + Send + Sync + 'static>> + MaybeSendStatic,
+{
+    let _span = gix_trace::coarse!("gix_protocol::push()", commands = commands.len());
+    if commands.is_empty() {
+        return Ok(Outcome::default());
+    }
+
+    let capabilities = &handshake.capabilities;
+    let mut features = Vec::<Cow<'_, str>>::new();
+    let report_status = capabilities.contains("report-status");
+    if report_status {
+        features.push("report-status".into());
+    }
+    let sideband = capabilities.contains("side-band-64k");
+    if sideband {
+        features.push("side-band-64k".into());
+    }
+    if quiet && capabilities.contains("quiet") {
+        features.push("quiet".into());
+    }
+    if atomic {
+        if !capabilities.contains("atomic") {
+            return Err(Error::MissingServerFeature {
+                feature: "atomic",
+                description: "atomic pushes need the server to apply all reference updates in a single transaction",
+            });
+        }
+        features.push("atomic".into());
+    }
+    if !push_options.is_empty() {
+        if !capabilities.contains("push-options") {
+            return Err(Error::MissingServerFeature {
+                feature: "push-options",
+                description: "the server must be able to receive push-options to pass them to its hooks",
+            });
+        }
+        features.push("push-options".into());
+    }
+    if commands.iter().any(Command::is_delete) && !capabilities.contains("delete-refs") {
+        return Err(Error::MissingServerFeature {
+            feature: "delete-refs",
+            description: "references can only be deleted if the server allows it",
+        });
+    }
+    if let Some(object_format) = capabilities.capability("object-format").and_then(|c| c.value()) {
+        features.push(format!("object-format={}", object_format.to_str_lossy()).into());
+    }
+    match user_agent {
+        (name, Some(value)) => features.push(format!("{name}={value}").into()),
+        (name, None) => features.push(name.into()),
+    }
+
+    if should_interrupt.load(Ordering::Relaxed) {
+        return Err(Error::Client(client::Error::Io(std::io::Error::other(
+            "interrupted by user",
+        ))));
+    }
+    progress.set_name("send commands".into());
+    let mut writer = transport.request(client::WriteMode::Binary, client::MessageKind::Flush, trace_packetlines)?;
+    for (idx, command) in commands.iter().enumerate() {
+        let line = command.to_line((idx == 0).then(|| features.iter().map(AsRef::as_ref)));
+        writer.write_all(&line).await.map_err(client::Error::Io)?;
+    }
+    writer
+        .write_message(client::MessageKind::Flush)
+        .await
+        .map_err(client::Error::Io)?;
+    if !push_options.is_empty() {
+        for option in push_options {
+            writer.write_all(option).await.map_err(client::Error::Io)?;
+        }
+        writer
+            .write_message(client::MessageKind::Flush)
+            .await
+            .map_err(client::Error::Io)?;
+    }
+
+    let pack_sent = !commands.iter().all(Command::is_delete);
+    let (mut pack_writer, mut reader) = writer.into_parts();
+    if pack_sent {
+        progress.set_name("send pack".into());
+        #[cfg(feature = "async-client")]
+        {
+            // Generate the pack on a thread of its own and stream it to the remote as it's produced.
+            let interrupt = std::sync::Arc::new(AtomicBool::new(false));
+            let (pipe_writer, pipe_reader) = gix_features::io::pipe::unidirectional(16);
+            let mut pack_progress = progress.add_child("write pack");
+            let generate = blocking::unblock({
+                let interrupt = interrupt.clone();
+                move || {
+                    let mut pipe_writer = pipe_writer;
+                    write_pack(&mut pipe_writer, &mut pack_progress, &interrupt)
+                }
+            });
+            let mut pipe_reader = blocking::Unblock::new(pipe_reader);
+            let mut buf = vec![0; 64 * 1024];
+            loop {
+                if should_interrupt.load(Ordering::Relaxed) {
+                    interrupt.store(true, Ordering::Relaxed);
+                }
+                let bytes_read = match pipe_reader.read(&mut buf).await {
+                    Ok(bytes_read) => bytes_read,
+                    Err(err) => {
+                        interrupt.store(true, Ordering::Relaxed);
+                        return Err(client::Error::Io(err).into());
+                    }
+                };
+                if bytes_read == 0 {
+                    break;
+                }
+                if let Err(err) = pack_writer.write_all(&buf[..bytes_read]).await {
+                    interrupt.store(true, Ordering::Relaxed);
+                    return Err(client::Error::Io(err).into());
+                }
+            }
+            generate.await.map_err(|err| Error::WritePack(err.into()))?;
+        }
+        #[cfg(not(feature = "async-client"))]
+        {
+            write_pack(&mut pack_writer, &mut progress, should_interrupt)
+                .map_err(|err| Error::WritePack(err.into()))?;
+        }
+    }
+    pack_writer.flush().await.map_err(client::Error::Io)?;
+    drop(pack_writer);
+
+    if !report_status {
+        return Ok(Outcome {
+            report: None,
+            pack_sent,
+        });
+    }
+    progress.set_name("receive report".into());
+    if sideband {
+        crate::fetch::function::setup_remote_progress(&mut progress, &mut reader, should_interrupt);
+    }
+    let mut buf = Vec::new();
+    reader.read_to_end(&mut buf).await.map_err(Error::ReadReport)?;
+    drop(reader);
+
+    let report = if sideband {
+        let mut lines = Vec::new();
+        let mut data = buf.as_slice();
+        while !data.is_empty() {
+            match packetline::decode::streaming(data)? {
+                packetline::decode::Stream::Complete { line, bytes_consumed } => {
+                    if let Some(line) = line.as_slice() {
+                        lines.push(line);
+                    }
+                    data = &data[bytes_consumed..];
+                }
+                packetline::decode::Stream::Incomplete { .. } => {
+                    return Err(Error::ReadReport(std::io::Error::new(
+                        std::io::ErrorKind::UnexpectedEof,
+                        "report ended within a packetline",
+                    )))
+                }
+            }
+        }
+        response::Report::from_lines(lines)?
+    } else {
+        response::Report::from_lines(buf.lines_with_terminator())?
+    };
+    Ok(Outcome {
+        report: Some(report),
+        pack_sent,
+    })
+}
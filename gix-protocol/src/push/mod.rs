@@ -0,0 +1,68 @@
+//! A module providing low-level primitives to send a pack to the `receive-pack` service of a server and to update its references.
+//!
+//! ### Order for sending a pack
+//!
+//! * [handshake](crate::handshake()) using [`Service::ReceivePack`](crate::transport::Service::ReceivePack)
+//!     * refs are always advertised as part of the handshake as `receive-pack` is only available in protocol V0 and V1.
+//! * compute the [`Command`]s to send, one for each reference to create, update or delete.
+//! * [send the commands and the pack](crate::push()) and receive the [report](response::Report) of the server.
+//! * [officially terminate the connection](crate::indicate_end_of_interaction())
+//!
+//! Note that this flow doesn't involve generating the pack, which is left to the caller. Nor does it update local
+//! remote-tracking references based on the results reported by the server.
+mod types;
+pub use types::*;
+
+///
+pub mod response;
+
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+mod error;
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+pub use error::Error;
+
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+pub(crate) mod function;
+
+/// The function writing the pack for [`push()`](crate::push()), as `write_pack(pack_writer, progress, should_interrupt)`.
+///
+/// In async mode, it runs on a thread of its own so that generating the pack doesn't block the executor,
+/// which is why it has to be `Send` and `'static` there.
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+pub trait WritePack<E>:
+    FnOnce(
+        &mut dyn std::io::Write,
+        &mut dyn gix_features::progress::DynNestedProgress,
+        &std::sync::atomic::AtomicBool,
+    ) -> Result<(), E>
+    + MaybeSendStatic
+{
+}
+
+#[cfg(any(feature = "blocking-client", feature = "async-client"))]
+#[cfg(feature = "push")]
+impl<T, E> WritePack<E> for T where
+    T: FnOnce(
+            &mut dyn std::io::Write,
+            &mut dyn gix_features::progress::DynNestedProgress,
+            &std::sync::atomic::AtomicBool,
+        ) -> Result<(), E>
+        + MaybeSendStatic
+{
+}
+
+/// Implemented by all types that are `Send` and `'static` in async mode, and by all types otherwise.
+#[cfg(all(feature = "async-client", feature = "push"))]
+pub trait MaybeSendStatic: Send + 'static {}
+#[cfg(all(feature = "async-client", feature = "push"))]
+impl<T: Send + 'static> MaybeSendStatic for T {}
+
+/// Implemented by all types that are `Send` and `'static` in async mode, and by all types otherwise.
+#[cfg(all(feature = "blocking-client", feature = "push"))]
+pub trait MaybeSendStatic {}
+#[cfg(all(feature = "blocking-client", feature = "push"))]
+impl<T> MaybeSendStatic for T {}
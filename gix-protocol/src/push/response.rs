@@ -0,0 +1,93 @@
+use bstr::{BStr, BString, ByteSlice};
+
+/// The error returned by [`Report::from_lines()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The server report did not start with the 'unpack' status line")]
+    MissingUnpackStatus,
+    #[error("Could not parse status line {line:?} in server report")]
+    UnknownLine { line: BString },
+}
+
+/// The status of a single reference update as reported by the server.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Status {
+    /// The reference was updated as requested.
+    Ok {
+        /// The full name of the reference on the remote.
+        full_ref_name: BString,
+    },
+    /// The reference update was rejected by the server.
+    Rejected {
+        /// The full name of the reference on the remote.
+        full_ref_name: BString,
+        /// The reason for the rejection, like `non-fast-forward` or `atomic push failed`.
+        reason: BString,
+    },
+}
+
+impl Status {
+    /// Return the full name of the reference this status is for.
+    pub fn full_ref_name(&self) -> &BStr {
+        match self {
+            Status::Ok { full_ref_name } | Status::Rejected { full_ref_name, .. } => full_ref_name.as_ref(),
+        }
+    }
+
+    /// Return `true` if the update was applied on the remote.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Status::Ok { .. })
+    }
+}
+
+/// The parsed `report-status` response of a server after receiving a pack and applying reference updates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Report {
+    /// `None` if the pack was unpacked successfully, or the error message of the server otherwise.
+    pub unpack_error: Option<BString>,
+    /// The status of each reference update, in the order they are sent by the server.
+    pub statuses: Vec<Status>,
+}
+
+impl Report {
+    /// Parse a report from the given `lines`, each without the packetline prefix but possibly with trailing newline.
+    ///
+    /// `option` lines as sent with `report-status-v2` are skipped.
+    pub fn from_lines<'a>(lines: impl IntoIterator<Item = &'a [u8]>) -> Result<Self, Error> {
+        let mut lines = lines
+            .into_iter()
+            .map(|line| line.trim_end_with(|c| c == '\n').as_bstr());
+        let unpack_error = match lines.next().and_then(|line| line.strip_prefix(b"unpack ")) {
+            Some(b"ok") => None,
+            Some(err) => Some(err.into()),
+            None => return Err(Error::MissingUnpackStatus),
+        };
+        let mut statuses = Vec::new();
+        for line in lines {
+            if let Some(name) = line.strip_prefix(b"ok ") {
+                statuses.push(Status::Ok {
+                    full_ref_name: name.into(),
+                });
+            } else if let Some(rest) = line.strip_prefix(b"ng ") {
+                let (name, reason) = rest.split_once_str(b" ").unwrap_or((rest, b"".as_slice()));
+                statuses.push(Status::Rejected {
+                    full_ref_name: name.into(),
+                    reason: reason.into(),
+                });
+            } else if line.starts_with(b"option ") {
+                continue;
+            } else {
+                return Err(Error::UnknownLine { line: line.into() });
+            }
+        }
+        Ok(Report { unpack_error, statuses })
+    }
+
+    /// Return `true` if the pack was unpacked and all reference updates were successful.
+    pub fn is_ok(&self) -> bool {
+        self.unpack_error.is_none() && self.statuses.iter().all(Status::is_ok)
+    }
+}
@@ -0,0 +1,95 @@
+use bstr::{BStr, BString};
+
+/// A single request to change a reference on the remote, as sent to the `receive-pack` service.
+///
+/// The server will only perform the update if the reference currently points to `old`, which makes it possible
+/// to implement `--force-with-lease` style expectations.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Ord, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Command {
+    /// The full name of the reference on the remote, like `refs/heads/main`.
+    pub name: BString,
+    /// The object the reference is expected to point to right now on the remote, or the null-id if it must not exist.
+    pub old: gix_hash::ObjectId,
+    /// The object the reference should point to after the update, or the null-id if it should be deleted.
+    pub new: gix_hash::ObjectId,
+}
+
+impl Command {
+    /// Return `true` if this command creates a reference that must not yet exist.
+    pub fn is_create(&self) -> bool {
+        self.old.is_null() && !self.new.is_null()
+    }
+
+    /// Return `true` if this command deletes the reference.
+    pub fn is_delete(&self) -> bool {
+        self.new.is_null()
+    }
+
+    /// Return the full name of the reference to update on the remote.
+    pub fn name(&self) -> &BStr {
+        self.name.as_ref()
+    }
+
+    /// Produce the line to send to the server for this command, without trailing newline.
+    ///
+    /// If `capabilities` is `Some`, they are appended after a NUL byte, as required for the first command.
+    pub fn to_line<'a>(&self, capabilities: Option<impl IntoIterator<Item = &'a str>>) -> BString {
+        use bstr::ByteVec;
+        let mut out: BString = format!("{} {} ", self.old, self.new).into();
+        out.push_str(&self.name);
+        if let Some(capabilities) = capabilities {
+            out.push_byte(0);
+            for (idx, cap) in capabilities.into_iter().enumerate() {
+                if idx != 0 {
+                    out.push_byte(b' ');
+                }
+                out.push_str(cap);
+            }
+        }
+        out
+    }
+}
+
+/// Options for use in [`push()`](crate::push()).
+#[derive(Default, Debug, Clone, Copy)]
+pub struct Options<'a> {
+    /// If `true`, ask the server to apply either all [commands](Command) or none of them.
+    ///
+    /// This requires the server to support the `atomic` capability, and it's an error if it doesn't.
+    pub atomic: bool,
+    /// Strings to transmit to the server, which passes them to its `pre-receive` and `post-receive` hooks.
+    ///
+    /// If non-empty, it's an error if the server doesn't support the `push-options` capability.
+    pub push_options: &'a [BString],
+    /// If `true`, ask the server not to send progress information about the unpacking of the pack it received.
+    pub quiet: bool,
+}
+
+/// For use in [`push()`](crate::push()).
+#[cfg(feature = "handshake")]
+pub struct Context<'a, T> {
+    /// The outcome of the handshake performed with the remote using the `receive-pack` service.
+    ///
+    /// It's used to determine which capabilities to enable.
+    pub handshake: &'a crate::Handshake,
+    /// The transport to use when sending the commands and the pack.
+    pub transport: &'a mut T,
+    /// How to self-identify when sending the commands.
+    ///
+    /// This could be read from the `gitoxide.userAgent` configuration variable.
+    pub user_agent: (&'static str, Option<std::borrow::Cow<'static, str>>),
+    /// If `true`, output all packetlines using the `gix-trace` machinery.
+    pub trace_packetlines: bool,
+}
+
+/// The outcome of [`push()`](crate::push()).
+#[derive(Default, Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Outcome {
+    /// The report of the server, as long as it supports the `report-status` capability, or `None` if it doesn't or
+    /// if there were no commands to send.
+    pub report: Option<crate::push::response::Report>,
+    /// `true` if a pack was sent, which is the case unless all commands are deletions.
+    pub pack_sent: bool,
+}
@@ -0,0 +1,80 @@
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_packetline::{
+    blocking_io::{encode, StreamingPeekableIter},
+    PacketLineRef,
+};
+
+/// A packet line as sent by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Line {
+    /// A line with data, without trailing newline.
+    Data(BString),
+    /// A delimiter packet as used in V2 to separate capabilities from arguments.
+    Delimiter,
+    /// A flush packet, which typically ends a request.
+    Flush,
+}
+
+/// Read packet lines sent by a client, one at a time or one request at a time.
+pub(crate) struct LineReader<R> {
+    lines: StreamingPeekableIter<R>,
+}
+
+impl<R: std::io::Read> LineReader<R> {
+    pub(crate) fn new(read: R, trace: bool) -> Self {
+        LineReader {
+            lines: StreamingPeekableIter::new(read, &[], trace),
+        }
+    }
+
+    /// Read the next line, or return `None` if the input ended.
+    pub(crate) fn read_line(&mut self) -> std::io::Result<Option<Line>> {
+        let line = match self.lines.read_line() {
+            None => return Ok(None),
+            Some(Err(err)) if err.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Some(line) => line?.map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?,
+        };
+        Ok(Some(match line {
+            PacketLineRef::Data(data) => Line::Data(data.trim_end_with(|c| c == '\n').into()),
+            PacketLineRef::Delimiter => Line::Delimiter,
+            PacketLineRef::Flush | PacketLineRef::ResponseEnd => Line::Flush,
+        }))
+    }
+
+    /// Read all lines up to the next flush packet, which isn't included, or return `None` if the input ended before
+    /// any line was read.
+    ///
+    /// Note that a request that consists only of a flush packet yields an empty list of lines.
+    pub(crate) fn read_request(&mut self) -> std::io::Result<Option<Vec<Line>>> {
+        let mut out = Vec::new();
+        loop {
+            match self.read_line()? {
+                None if out.is_empty() => return Ok(None),
+                None => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+                Some(Line::Flush) => return Ok(Some(out)),
+                Some(line) => out.push(line),
+            }
+        }
+    }
+
+    /// Return the underlying reader, which is positioned right after the last packet line that was read.
+    pub(crate) fn into_inner(self) -> R {
+        self.lines.into_inner()
+    }
+}
+
+/// Write a line advertising the reference `name` pointing to `id`, along with `capabilities` if they weren't sent yet.
+pub(crate) fn advertise(
+    out: &mut impl std::io::Write,
+    id: &gix_hash::oid,
+    name: &BStr,
+    capabilities: &mut Option<BString>,
+) -> std::io::Result<()> {
+    let mut line = BString::from(format!("{id} "));
+    line.push_str(name);
+    if let Some(capabilities) = capabilities.take() {
+        line.push_byte(0);
+        line.push_str(capabilities);
+    }
+    encode::text_to_write(&line, out).map(|_| ())
+}
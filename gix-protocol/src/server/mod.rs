@@ -0,0 +1,16 @@
+//! Server-side implementations of the services git clients connect to, using blocking I/O.
+//!
+//! Servers are driven by reading requests from any [`Read`](std::io::Read) implementation and writing responses
+//! to any [`Write`](std::io::Write) implementation, which allows them to be used over `stdin` and `stdout` of a process
+//! or over a socket alike. Accepting connections and dealing with the initial request of a `git://` daemon is left to the caller.
+//!
+//! Interacting with the repository is abstracted by delegates, which makes these implementations independent of
+//! any particular storage.
+
+///
+pub mod receive_pack;
+///
+pub mod upload_pack;
+
+pub(crate) mod line;
+pub(crate) mod sideband;
@@ -0,0 +1,22 @@
+use bstr::BString;
+
+/// The error returned by [`serve()`](super::serve()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Failed to read from or write to the client")]
+    Io(#[from] std::io::Error),
+    #[error("The delegate failed")]
+    Delegate(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Could not parse command {line:?}")]
+    InvalidCommand { line: BString },
+    #[error("Could not parse object id in line {line:?}")]
+    InvalidObjectId {
+        line: BString,
+        source: gix_hash::decode::Error,
+    },
+    #[error("The delegate returned {actual} statuses for {expected} commands")]
+    StatusCount { expected: usize, actual: usize },
+    #[error("The client sent the unsupported line {line:?}")]
+    UnsupportedLine { line: BString },
+}
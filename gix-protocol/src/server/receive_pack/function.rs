@@ -0,0 +1,244 @@
+use std::{cell::RefCell, io::Write};
+
+use bstr::{BString, ByteSlice};
+use gix_hash::ObjectId;
+use gix_packetline::{blocking_io::encode, Channel};
+use gix_transport::Protocol;
+
+use super::{Delegate, Error, Options, Outcome, UpdateRequest};
+use crate::{
+    push::{
+        response::{Report, Status},
+        Command,
+    },
+    server::{
+        line::{advertise, Line, LineReader},
+        sideband::{BandWriter, MAX_DATA_LEN_64K},
+    },
+};
+
+/// The capabilities we advertise, without the ones that depend on the repository.
+const CAPABILITIES: &[&str] = &[
+    "report-status",
+    "delete-refs",
+    "side-band-64k",
+    "quiet",
+    "atomic",
+    "ofs-delta",
+    "push-options",
+];
+
+/// Serve the `receive-pack` service to a client that sends its commands and pack through `input` and receives our
+/// responses through `output`, with `delegate` providing access to the repository that receives the push.
+///
+/// The reference advertisement is sent right away, so the caller is expected to have handled whatever initial request
+/// the client made to invoke the service, like the one sent to a `git://` daemon, and to have determined the protocol
+/// version to use in `options`.
+///
+/// The function returns once the report was sent, or if the client closes `input` or has nothing to update.
+/// Note that `output` is flushed after each response so it can be buffered.
+///
+/// Rejected commands or a pack that couldn't be received are not considered an error, and are communicated through the
+/// [report](Outcome::report) instead. If the client makes a request we can't handle, an error message is sent to it
+/// before the error is returned.
+pub fn serve(
+    delegate: &mut impl Delegate,
+    input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut outcome = Outcome::default();
+    match serve_inner(delegate, input, &mut output, options, &mut outcome) {
+        Ok(()) => Ok(outcome),
+        Err(err) => {
+            if !matches!(err, Error::Io(_)) {
+                encode::error_to_write(format!("receive-pack: {err}").as_bytes(), &mut output).ok();
+                output.flush().ok();
+            }
+            Err(err)
+        }
+    }
+}
+
+fn serve_inner(
+    delegate: &mut impl Delegate,
+    input: impl std::io::Read,
+    out: &mut impl std::io::Write,
+    options: &Options,
+    outcome: &mut Outcome,
+) -> Result<(), Error> {
+    if options.version == Protocol::V1 {
+        encode::text_to_write(b"version 1", &mut *out)?;
+    }
+    let refs = delegate.refs().map_err(Error::Delegate)?;
+    let mut capabilities: Vec<BString> = CAPABILITIES.iter().map(|c| (*c).into()).collect();
+    capabilities.push(format!("object-format={}", options.object_hash).into());
+    capabilities.push(format!("agent={}", options.agent).into());
+    let mut capabilities = Some(bstr::join(" ", capabilities).into());
+    for r in &refs {
+        if let (name, Some(id), _) = r.unpack() {
+            advertise(out, id, name, &mut capabilities)?;
+        }
+    }
+    if capabilities.is_some() {
+        advertise(
+            out,
+            &options.object_hash.null(),
+            "capabilities^{}".into(),
+            &mut capabilities,
+        )?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+
+    let mut lines = LineReader::new(input, options.trace_packetlines);
+    let Some(request) = lines.read_request()? else {
+        return Ok(());
+    };
+    for (idx, line) in request.into_iter().enumerate() {
+        let Line::Data(line) = line else {
+            return Err(Error::UnsupportedLine { line: "delim".into() });
+        };
+        let command = if idx == 0 {
+            let (command, features) = line.split_once_str(b"\0").unwrap_or((&line, &[]));
+            outcome.features.extend(
+                features
+                    .split_str(b" ")
+                    .filter(|f| !f.is_empty())
+                    .map(|f| f.as_bstr().to_owned()),
+            );
+            command
+        } else {
+            &line
+        };
+        outcome.commands.push(parse_command(command)?);
+    }
+    if outcome.commands.is_empty() {
+        return Ok(());
+    }
+
+    let has = |name: &str| outcome.features.iter().any(|f| f == name);
+    let report_status = has("report-status");
+    let sideband = has("side-band-64k");
+    let quiet = has("quiet");
+    let atomic = has("atomic");
+    if has("push-options") {
+        let options = lines
+            .read_request()?
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+        for line in options {
+            let Line::Data(option) = line else {
+                return Err(Error::UnsupportedLine { line: "delim".into() });
+            };
+            outcome.push_options.push(option);
+        }
+    }
+
+    let out = RefCell::new(out as &mut dyn std::io::Write);
+    let mut progress_out = BandWriter {
+        out: &out,
+        channel: Channel::Progress,
+        max_data_len: Some(MAX_DATA_LEN_64K),
+    };
+    let mut sink = std::io::sink();
+    let unpack_error = if outcome.commands.iter().all(Command::is_delete) {
+        None
+    } else {
+        let mut pack = std::io::BufReader::new(lines.into_inner());
+        let progress: &mut dyn std::io::Write = if sideband && !quiet {
+            &mut progress_out
+        } else {
+            &mut sink
+        };
+        delegate
+            .receive_pack(&mut pack, progress)
+            .err()
+            .map(|err| BString::from(err.to_string()))
+    };
+
+    let statuses = match unpack_error {
+        Some(_) => outcome
+            .commands
+            .iter()
+            .map(|command| Status::Rejected {
+                full_ref_name: command.name.clone(),
+                reason: "unpacker error".into(),
+            })
+            .collect(),
+        None => {
+            let progress: &mut dyn std::io::Write = if sideband { &mut progress_out } else { &mut sink };
+            let statuses = delegate
+                .update(
+                    UpdateRequest {
+                        commands: &outcome.commands,
+                        atomic,
+                        push_options: &outcome.push_options,
+                    },
+                    progress,
+                )
+                .map_err(Error::Delegate)?;
+            if statuses.len() != outcome.commands.len() {
+                return Err(Error::StatusCount {
+                    expected: outcome.commands.len(),
+                    actual: statuses.len(),
+                });
+            }
+            statuses
+        }
+    };
+    let report = Report { unpack_error, statuses };
+
+    if report_status {
+        let mut buf = Vec::new();
+        match &report.unpack_error {
+            None => encode::text_to_write(b"unpack ok", &mut buf)?,
+            Some(err) => encode::text_to_write(format!("unpack {err}").as_bytes(), &mut buf)?,
+        };
+        for status in &report.statuses {
+            let line: BString = match status {
+                Status::Ok { full_ref_name } => format!("ok {full_ref_name}").into(),
+                Status::Rejected { full_ref_name, reason } => format!("ng {full_ref_name} {reason}").into(),
+            };
+            encode::text_to_write(&line, &mut buf)?;
+        }
+        encode::flush_to_write(&mut buf)?;
+        if sideband {
+            BandWriter {
+                out: &out,
+                channel: Channel::Data,
+                max_data_len: Some(MAX_DATA_LEN_64K),
+            }
+            .write_all(&buf)?;
+        } else {
+            out.borrow_mut().write_all(&buf)?;
+        }
+    }
+    let mut out = out.borrow_mut();
+    if sideband {
+        encode::flush_to_write(&mut *out)?;
+    }
+    out.flush()?;
+    outcome.report = Some(report);
+    Ok(())
+}
+
+fn parse_command(line: &[u8]) -> Result<Command, Error> {
+    if line.starts_with(b"shallow ") || line.starts_with(b"push-cert") {
+        return Err(Error::UnsupportedLine { line: line.into() });
+    }
+    let mut tokens = line.splitn_str(3, b" ");
+    let (Some(old), Some(new), Some(name)) = (tokens.next(), tokens.next(), tokens.next()) else {
+        return Err(Error::InvalidCommand { line: line.into() });
+    };
+    let parse_id = |hex: &[u8]| {
+        ObjectId::from_hex(hex).map_err(|source| Error::InvalidObjectId {
+            line: line.into(),
+            source,
+        })
+    };
+    Ok(Command {
+        name: name.into(),
+        old: parse_id(old)?,
+        new: parse_id(new)?,
+    })
+}
@@ -0,0 +1,118 @@
+//! The server side of the `receive-pack` service, which receives packs and updates references for clients that push.
+//!
+//! ### Supported Protocols
+//!
+//! * V0 and V1, with the `report-status`, `delete-refs`, `side-band-64k`, `quiet`, `atomic`, `ofs-delta` and
+//!   `push-options` capabilities.
+//!
+//! There is no V2 version of `receive-pack`, which is why clients asking for it are served with V0 instead.
+//! Shallow pushes and signed pushes are not supported, and requests using them fail.
+//!
+//! ### Order of Operations
+//!
+//! * [references are advertised](Delegate::refs())
+//! * the [commands](Command) of the client are received, along with push-options if negotiated.
+//! * the [pack is received](Delegate::receive_pack()) unless all commands are deletions.
+//! * the [commands are applied](Delegate::update()), unless the pack couldn't be received in which case all of them are rejected.
+//! * a [report](Report) is sent if the client asked for it.
+use bstr::BString;
+
+use crate::{
+    handshake::Ref,
+    push::{
+        response::{Report, Status},
+        Command,
+    },
+};
+
+mod error;
+pub use error::Error;
+
+mod function;
+pub use function::serve;
+
+/// Options for use in [`serve()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The protocol version to speak, typically as requested by the client via the `GIT_PROTOCOL` environment variable
+    /// or the extra parameters of a `git://` request.
+    ///
+    /// [`Protocol::V2`](gix_transport::Protocol::V2) is treated like V0.
+    pub version: gix_transport::Protocol,
+    /// The kind of hash used by the served repository.
+    pub object_hash: gix_hash::Kind,
+    /// The value of the `agent` capability to advertise, like `git/gix-0.1`.
+    pub agent: String,
+    /// If `true`, all packet lines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub trace_packetlines: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: gix_transport::Protocol::V0,
+            object_hash: gix_hash::Kind::Sha1,
+            agent: concat!("git/gix-", env!("CARGO_PKG_VERSION")).into(),
+            trace_packetlines: false,
+        }
+    }
+}
+
+/// A request to apply the commands of a client, as passed to [`Delegate::update()`].
+#[derive(Debug, Clone, Copy)]
+pub struct UpdateRequest<'a> {
+    /// The reference updates to perform, in the order the client sent them.
+    pub commands: &'a [Command],
+    /// If `true`, either all commands must be applied or none of them.
+    pub atomic: bool,
+    /// Strings sent by the client, meant to be passed to the `pre-receive` and `post-receive` hooks.
+    pub push_options: &'a [BString],
+}
+
+/// The interface to the repository receiving a push.
+pub trait Delegate {
+    /// Return all references to advertise.
+    ///
+    /// Only the name and the object a reference points to are advertised, which is why `HEAD` and `Ref::Unborn`
+    /// are typically not included.
+    fn refs(&mut self) -> Result<Vec<Ref>, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Read a pack from `pack` and store it so the objects in it are available to [`update()`](Self::update()).
+    ///
+    /// The pack is the last thing the client sends, and it has to be consumed up to its trailing checksum.
+    /// As the pack may be thin, base objects that aren't contained in it have to be looked up in the repository.
+    ///
+    /// Human-readable progress messages may be written to `progress`, which sends them to the client if it supports it
+    /// and didn't ask us to be quiet, or discards them otherwise. Lines should end in `\n`, or `\r` to overwrite the previous message.
+    fn receive_pack(
+        &mut self,
+        pack: &mut dyn std::io::BufRead,
+        progress: &mut dyn std::io::Write,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Apply the commands of `request`, typically in a reference transaction that is guarded by hooks, and return one
+    /// status for each command, in order.
+    ///
+    /// Messages written to `progress` are sent to the client if it supports side-bands, even if it asked us to be quiet,
+    /// which makes it suitable for passing on the output of hooks.
+    fn update(
+        &mut self,
+        request: UpdateRequest<'_>,
+        progress: &mut dyn std::io::Write,
+    ) -> Result<Vec<Status>, Box<dyn std::error::Error + Send + Sync + 'static>>;
+}
+
+/// The outcome of [`serve()`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The commands the client sent, which is empty if it had nothing to update.
+    pub commands: Vec<Command>,
+    /// The push-options the client sent.
+    pub push_options: Vec<BString>,
+    /// The capabilities the client requested.
+    pub features: Vec<BString>,
+    /// The result of receiving the pack and applying the `commands`, or `None` if there were no commands.
+    ///
+    /// It's what was sent to the client if it asked for `report-status`.
+    pub report: Option<Report>,
+}
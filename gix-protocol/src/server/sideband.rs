@@ -0,0 +1,38 @@
+use std::cell::RefCell;
+
+use gix_packetline::{blocking_io::encode, Channel};
+
+/// The maximum amount of bytes to send in a single packet line when using `side-band-64k`.
+pub(crate) const MAX_DATA_LEN_64K: usize = 65515;
+/// The maximum amount of bytes to send in a single packet line when using `side-band`.
+pub(crate) const MAX_DATA_LEN: usize = 999;
+
+/// A writer to send all data in packet lines of the given side-band `channel`, or as is if `max_data_len` is `None`.
+///
+/// The output is shared so data and progress can be interleaved.
+pub(crate) struct BandWriter<'a, 'out> {
+    pub out: &'a RefCell<&'out mut dyn std::io::Write>,
+    pub channel: Channel,
+    /// The maximum amount of bytes of data per packet line, excluding the channel byte, or `None` to write data as is.
+    pub max_data_len: Option<usize>,
+}
+
+impl std::io::Write for BandWriter<'_, '_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut out = self.out.borrow_mut();
+        match self.max_data_len {
+            Some(max) => {
+                let len = buf.len().min(max);
+                if len != 0 {
+                    encode::band_to_write(self.channel, &buf[..len], &mut *out)?;
+                }
+                Ok(len)
+            }
+            None => out.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.out.borrow_mut().flush()
+    }
+}
@@ -0,0 +1,24 @@
+use bstr::BString;
+
+/// The error returned by [`serve()`](super::serve()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Failed to read from or write to the client")]
+    Io(#[from] std::io::Error),
+    #[error("The delegate failed")]
+    Delegate(#[source] Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error("Could not parse object id in line {line:?}")]
+    InvalidObjectId {
+        line: BString,
+        source: gix_hash::decode::Error,
+    },
+    #[error("The client wants {id}, which isn't advertised by any reference")]
+    NotOurRef { id: gix_hash::ObjectId },
+    #[error("The client sent an unexpected line: {line:?}")]
+    UnexpectedLine { line: BString },
+    #[error("The client requested the unknown command {name:?}")]
+    UnknownCommand { name: BString },
+    #[error("The client sent the unsupported argument {argument:?}")]
+    UnsupportedArgument { argument: BString },
+}
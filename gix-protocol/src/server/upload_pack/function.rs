@@ -0,0 +1,539 @@
+use std::{cell::RefCell, fmt::Write as _};
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_hash::ObjectId;
+use gix_packetline::{blocking_io::encode, Channel};
+use gix_transport::Protocol;
+
+use super::{Delegate, Error, Negotiated, Options, Outcome, PackRequest};
+use crate::{
+    handshake::Ref,
+    server::{
+        line::{advertise, Line, LineReader},
+        sideband::{BandWriter, MAX_DATA_LEN, MAX_DATA_LEN_64K},
+    },
+};
+
+/// The capabilities we advertise in V0 and V1, without the ones that depend on the repository.
+const V1_CAPABILITIES: &[&str] = &[
+    "multi_ack",
+    "thin-pack",
+    "side-band",
+    "side-band-64k",
+    "ofs-delta",
+    "no-progress",
+    "include-tag",
+    "multi_ack_detailed",
+];
+
+/// The features a client may request for a pack in V2 `fetch` commands, besides `want`, `have` and `done`.
+const V2_FETCH_FEATURES: &[&str] = &["thin-pack", "no-progress", "include-tag", "ofs-delta"];
+
+/// Serve the `upload-pack` service to a client that sends its requests through `input` and receives our responses
+/// through `output`, with `delegate` providing access to the repository to serve.
+///
+/// The reference advertisement is sent right away, so the caller is expected to have handled whatever initial request
+/// the client made to invoke the service, like the one sent to a `git://` daemon, and to have determined the protocol
+/// version to use in `options`.
+///
+/// The function returns once the client ends the interaction or closes `input`, or after the pack was sent
+/// for V0 and V1 as these can only send a single pack per connection.
+/// Note that `output` is flushed after each response so it can be buffered.
+///
+/// If the client makes a request we can't handle, an error message is sent to it before the error is returned.
+pub fn serve(
+    delegate: &mut impl Delegate,
+    input: impl std::io::Read,
+    mut output: impl std::io::Write,
+    options: &Options,
+) -> Result<Outcome, Error> {
+    let mut lines = LineReader::new(input, options.trace_packetlines);
+    let mut outcome = Outcome::default();
+    let res = match options.version {
+        Protocol::V2 => serve_v2(delegate, &mut lines, &mut output, options, &mut outcome),
+        Protocol::V0 | Protocol::V1 => serve_v0_v1(delegate, &mut lines, &mut output, options, &mut outcome),
+    };
+    match res {
+        Ok(()) => Ok(outcome),
+        Err(err) => {
+            if !matches!(err, Error::Io(_)) {
+                encode::error_to_write(format!("upload-pack: {err}").as_bytes(), &mut output).ok();
+                output.flush().ok();
+            }
+            Err(err)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum MultiAck {
+    None,
+    Basic,
+    Detailed,
+}
+
+fn serve_v0_v1(
+    delegate: &mut impl Delegate,
+    lines: &mut LineReader<impl std::io::Read>,
+    out: &mut impl std::io::Write,
+    options: &Options,
+    outcome: &mut Outcome,
+) -> Result<(), Error> {
+    if options.version == Protocol::V1 {
+        encode::text_to_write(b"version 1", &mut *out)?;
+    }
+    let refs = delegate.refs().map_err(Error::Delegate)?;
+    let mut capabilities: Vec<BString> = V1_CAPABILITIES.iter().map(|c| (*c).into()).collect();
+    capabilities.extend(refs.iter().filter_map(|r| match r {
+        Ref::Symbolic {
+            full_ref_name, target, ..
+        } => Some(format!("symref={full_ref_name}:{target}").into()),
+        _ => None,
+    }));
+    capabilities.push(format!("object-format={}", options.object_hash).into());
+    capabilities.push(format!("agent={}", options.agent).into());
+    let mut capabilities = Some(bstr::join(" ", capabilities).into());
+    for r in &refs {
+        match r {
+            Ref::Direct { full_ref_name, object } => {
+                advertise(out, object, full_ref_name.as_ref(), &mut capabilities)?;
+            }
+            Ref::Peeled {
+                full_ref_name,
+                tag,
+                object,
+            } => {
+                advertise(out, tag, full_ref_name.as_ref(), &mut capabilities)?;
+                advertise(out, object, peeled(full_ref_name.as_ref()).as_bstr(), &mut capabilities)?;
+            }
+            Ref::Symbolic {
+                full_ref_name,
+                tag,
+                object,
+                ..
+            } => {
+                advertise(
+                    out,
+                    tag.as_ref().unwrap_or(object),
+                    full_ref_name.as_ref(),
+                    &mut capabilities,
+                )?;
+                if tag.is_some() {
+                    advertise(out, object, peeled(full_ref_name.as_ref()).as_bstr(), &mut capabilities)?;
+                }
+            }
+            Ref::Unborn { .. } => {}
+        }
+    }
+    if capabilities.is_some() {
+        advertise(
+            out,
+            &options.object_hash.null(),
+            "capabilities^{}".into(),
+            &mut capabilities,
+        )?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+
+    let Some(request) = lines.read_request()? else {
+        return Ok(());
+    };
+    let mut wants = Vec::new();
+    let mut features = Vec::new();
+    for line in request {
+        let line = data(line)?;
+        let Some(rest) = line.strip_prefix(b"want ") else {
+            return Err(Error::UnsupportedArgument { argument: line });
+        };
+        let (id, requested) = rest.split_once_str(b" ").unwrap_or((rest, &[]));
+        wants.push(parse_id(id, &line)?);
+        features.extend(
+            requested
+                .split_str(b" ")
+                .filter(|f| !f.is_empty())
+                .map(|f| f.as_bstr().to_owned()),
+        );
+    }
+    if wants.is_empty() {
+        return Ok(());
+    }
+    check_wants(&refs, &wants)?;
+
+    let has = |name: &str| features.iter().any(|f| f == name);
+    let multi_ack = if has("multi_ack_detailed") {
+        MultiAck::Detailed
+    } else if has("multi_ack") {
+        MultiAck::Basic
+    } else {
+        MultiAck::None
+    };
+    let mut common = Vec::<ObjectId>::new();
+    // Whether a common or unknown object was received since the last flush.
+    let (mut got_common, mut got_other) = (false, false);
+    loop {
+        match lines.read_line()? {
+            None => return Ok(()),
+            Some(Line::Flush) => {
+                if multi_ack == MultiAck::Detailed
+                    && got_common
+                    && !got_other
+                    && ok_to_give_up(delegate, &wants, &common)?
+                {
+                    let last = common.last().expect("got common");
+                    encode::text_to_write(format!("ACK {last} ready").as_bytes(), &mut *out)?;
+                }
+                if common.is_empty() || multi_ack != MultiAck::None {
+                    encode::text_to_write(b"NAK", &mut *out)?;
+                }
+                out.flush()?;
+                (got_common, got_other) = (false, false);
+            }
+            Some(Line::Delimiter) => return Err(Error::UnexpectedLine { line: "delim".into() }),
+            Some(Line::Data(line)) if line == "done" => {
+                match common.last() {
+                    Some(last) if multi_ack != MultiAck::None => {
+                        encode::text_to_write(format!("ACK {last}").as_bytes(), &mut *out)?;
+                    }
+                    Some(_) => {}
+                    None => {
+                        encode::text_to_write(b"NAK", &mut *out)?;
+                    }
+                }
+                break;
+            }
+            Some(Line::Data(line)) => {
+                let Some(id) = line.strip_prefix(b"have ") else {
+                    return Err(Error::UnexpectedLine { line });
+                };
+                let id = parse_id(id, &line)?;
+                if !delegate.contains(&id) {
+                    got_other = true;
+                    if multi_ack != MultiAck::None && ok_to_give_up(delegate, &wants, &common)? {
+                        let ack = match multi_ack {
+                            MultiAck::Detailed => format!("ACK {id} ready"),
+                            _ => format!("ACK {id} continue"),
+                        };
+                        encode::text_to_write(ack.as_bytes(), &mut *out)?;
+                    }
+                    continue;
+                }
+                got_common = true;
+                if common.contains(&id) {
+                    continue;
+                }
+                common.push(id);
+                let ack = match multi_ack {
+                    MultiAck::Detailed => format!("ACK {id} common"),
+                    MultiAck::Basic => format!("ACK {id} continue"),
+                    MultiAck::None if common.len() == 1 => format!("ACK {id}"),
+                    MultiAck::None => continue,
+                };
+                encode::text_to_write(ack.as_bytes(), &mut *out)?;
+            }
+        }
+    }
+
+    let max_data_len = if has("side-band-64k") {
+        Some(MAX_DATA_LEN_64K)
+    } else if has("side-band") {
+        Some(MAX_DATA_LEN)
+    } else {
+        None
+    };
+    send_pack(
+        delegate,
+        out,
+        max_data_len,
+        !has("no-progress"),
+        PackRequest {
+            wants: &wants,
+            common: &common,
+            thin_pack: has("thin-pack"),
+            ofs_delta: has("ofs-delta"),
+            include_tag: has("include-tag"),
+        },
+    )?;
+    if max_data_len.is_some() {
+        encode::flush_to_write(&mut *out)?;
+    }
+    out.flush()?;
+    outcome.packs.push(Negotiated {
+        wants,
+        common,
+        features,
+    });
+    Ok(())
+}
+
+fn serve_v2(
+    delegate: &mut impl Delegate,
+    lines: &mut LineReader<impl std::io::Read>,
+    out: &mut impl std::io::Write,
+    options: &Options,
+    outcome: &mut Outcome,
+) -> Result<(), Error> {
+    for line in [
+        "version 2".into(),
+        format!("agent={}", options.agent),
+        "ls-refs=unborn".into(),
+        "fetch".into(),
+        format!("object-format={}", options.object_hash),
+    ] {
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+
+    loop {
+        let Some(request) = lines.read_request()? else {
+            return Ok(());
+        };
+        let mut request = request.into_iter();
+        let command = match request.next() {
+            None => return Ok(()),
+            Some(line) => {
+                let line = data(line)?;
+                match line.strip_prefix(b"command=") {
+                    Some(command) => command.as_bstr().to_owned(),
+                    None => return Err(Error::UnexpectedLine { line }),
+                }
+            }
+        };
+        // Capabilities like `agent` come first and are ignored, arguments follow after a delimiter.
+        let args: Vec<BString> = request
+            .skip_while(|line| *line != Line::Delimiter)
+            .skip(1)
+            .map(data)
+            .collect::<Result<_, _>>()?;
+        match command.as_slice() {
+            b"ls-refs" => {
+                ls_refs(delegate, &args, out)?;
+                outcome.ls_refs += 1;
+            }
+            b"fetch" => {
+                if let Some(negotiated) = fetch(delegate, args, out)? {
+                    outcome.packs.push(negotiated);
+                }
+            }
+            _ => return Err(Error::UnknownCommand { name: command }),
+        }
+    }
+}
+
+fn ls_refs(delegate: &mut impl Delegate, args: &[BString], out: &mut impl std::io::Write) -> Result<(), Error> {
+    let (mut symrefs, mut peel, mut unborn) = (false, false, false);
+    let mut prefixes = Vec::new();
+    for arg in args {
+        match arg.as_slice() {
+            b"symrefs" => symrefs = true,
+            b"peel" => peel = true,
+            b"unborn" => unborn = true,
+            _ => match arg.strip_prefix(b"ref-prefix ") {
+                Some(prefix) => prefixes.push(prefix.as_bstr()),
+                None => {
+                    return Err(Error::UnsupportedArgument {
+                        argument: arg.to_owned(),
+                    })
+                }
+            },
+        }
+    }
+    for r in delegate.refs().map_err(Error::Delegate)? {
+        let (name, _, _) = r.unpack();
+        if !prefixes.is_empty() && !prefixes.iter().any(|prefix| name.starts_with(prefix)) {
+            continue;
+        }
+        let line = match &r {
+            Ref::Direct { full_ref_name, object } => format!("{object} {full_ref_name}"),
+            Ref::Peeled {
+                full_ref_name,
+                tag,
+                object,
+            } => {
+                let mut line = format!("{tag} {full_ref_name}");
+                if peel {
+                    write!(line, " peeled:{object}").expect("writing to a string cannot fail");
+                }
+                line
+            }
+            Ref::Symbolic {
+                full_ref_name,
+                target,
+                tag,
+                object,
+            } => {
+                let mut line = format!("{} {full_ref_name}", tag.as_ref().unwrap_or(object));
+                if symrefs {
+                    write!(line, " symref-target:{target}").expect("writing to a string cannot fail");
+                }
+                if peel && tag.is_some() {
+                    write!(line, " peeled:{object}").expect("writing to a string cannot fail");
+                }
+                line
+            }
+            Ref::Unborn { full_ref_name, target } => {
+                if !unborn {
+                    continue;
+                }
+                let mut line = format!("unborn {full_ref_name}");
+                if symrefs {
+                    write!(line, " symref-target:{target}").expect("writing to a string cannot fail");
+                }
+                line
+            }
+        };
+        encode::text_to_write(line.as_bytes(), &mut *out)?;
+    }
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn fetch(
+    delegate: &mut impl Delegate,
+    args: Vec<BString>,
+    out: &mut impl std::io::Write,
+) -> Result<Option<Negotiated>, Error> {
+    let mut wants = Vec::new();
+    let mut haves = Vec::new();
+    let mut done = false;
+    let mut features = Vec::new();
+    for arg in args {
+        if let Some(id) = arg.strip_prefix(b"want ") {
+            wants.push(parse_id(id, &arg)?);
+        } else if let Some(id) = arg.strip_prefix(b"have ") {
+            haves.push(parse_id(id, &arg)?);
+        } else if arg == "done" {
+            done = true;
+        } else if V2_FETCH_FEATURES.iter().any(|f| arg == *f) {
+            features.push(arg);
+        } else {
+            return Err(Error::UnsupportedArgument { argument: arg });
+        }
+    }
+    check_wants(&delegate.refs().map_err(Error::Delegate)?, &wants)?;
+    let mut common = Vec::<ObjectId>::new();
+    for id in haves {
+        if delegate.contains(&id) && !common.contains(&id) {
+            common.push(id);
+        }
+    }
+
+    if !done {
+        encode::text_to_write(b"acknowledgments", &mut *out)?;
+        if common.is_empty() {
+            encode::text_to_write(b"NAK", &mut *out)?;
+        }
+        for id in &common {
+            encode::text_to_write(format!("ACK {id}").as_bytes(), &mut *out)?;
+        }
+        if !ok_to_give_up(delegate, &wants, &common)? {
+            encode::flush_to_write(&mut *out)?;
+            out.flush()?;
+            return Ok(None);
+        }
+        encode::text_to_write(b"ready", &mut *out)?;
+        encode::delim_to_write(&mut *out)?;
+    }
+
+    let has = |name: &str| features.iter().any(|f| f == name);
+    encode::text_to_write(b"packfile", &mut *out)?;
+    send_pack(
+        delegate,
+        out,
+        Some(MAX_DATA_LEN_64K),
+        !has("no-progress"),
+        PackRequest {
+            wants: &wants,
+            common: &common,
+            thin_pack: has("thin-pack"),
+            ofs_delta: has("ofs-delta"),
+            include_tag: has("include-tag"),
+        },
+    )?;
+    encode::flush_to_write(&mut *out)?;
+    out.flush()?;
+    Ok(Some(Negotiated {
+        wants,
+        common,
+        features,
+    }))
+}
+
+/// Let `delegate` write the pack to `out`, using side-band encoding if `max_data_len` is set, in which case progress
+/// is sent as well if `progress` is `true`.
+fn send_pack(
+    delegate: &mut impl Delegate,
+    out: &mut impl std::io::Write,
+    max_data_len: Option<usize>,
+    progress: bool,
+    request: PackRequest<'_>,
+) -> Result<(), Error> {
+    let out = RefCell::new(out as &mut dyn std::io::Write);
+    let mut pack = BandWriter {
+        out: &out,
+        channel: Channel::Data,
+        max_data_len,
+    };
+    let mut progress_out = BandWriter {
+        out: &out,
+        channel: Channel::Progress,
+        max_data_len,
+    };
+    let mut sink = std::io::sink();
+    let progress: &mut dyn std::io::Write = if progress && max_data_len.is_some() {
+        &mut progress_out
+    } else {
+        &mut sink
+    };
+    delegate
+        .write_pack(request, &mut pack, progress)
+        .map_err(Error::Delegate)
+}
+
+fn peeled(name: &BStr) -> BString {
+    let mut name = name.to_owned();
+    name.push_str("^{}");
+    name
+}
+
+fn data(line: Line) -> Result<BString, Error> {
+    match line {
+        Line::Data(line) => Ok(line),
+        Line::Delimiter => Err(Error::UnexpectedLine { line: "delim".into() }),
+        Line::Flush => Err(Error::UnexpectedLine { line: "flush".into() }),
+    }
+}
+
+fn parse_id(hex: &[u8], line: &[u8]) -> Result<ObjectId, Error> {
+    ObjectId::from_hex(hex).map_err(|source| Error::InvalidObjectId {
+        line: line.into(),
+        source,
+    })
+}
+
+/// Return `true` if we have enough in `common` with the client to send a pack for `wants` without negotiating any further.
+fn ok_to_give_up(delegate: &mut impl Delegate, wants: &[ObjectId], common: &[ObjectId]) -> Result<bool, Error> {
+    if common.is_empty() {
+        return Ok(false);
+    }
+    delegate.wants_reach_common(wants, common).map_err(Error::Delegate)
+}
+
+/// Assure that all `wants` are advertised in `refs`, either directly or as the object an annotated tag peels to.
+///
+/// This is what `git` does by default, with neither `uploadpack.allowTipSHA1InWant` nor `uploadpack.allowReachableSHA1InWant` set,
+/// so unreachable objects or objects behind hidden references can't be obtained.
+fn check_wants(refs: &[Ref], wants: &[ObjectId]) -> Result<(), Error> {
+    let is_advertised = |id: &ObjectId| {
+        refs.iter().any(|r| {
+            let (_, object, peeled) = r.unpack();
+            object == Some(id.as_ref()) || peeled == Some(id.as_ref())
+        })
+    };
+    match wants.iter().find(|id| !is_advertised(id)) {
+        Some(id) => Err(Error::NotOurRef { id: *id }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,120 @@
+//! The server side of the `upload-pack` service, which sends packs to clients that fetch or clone.
+//!
+//! ### Supported Protocols
+//!
+//! * V0 and V1, with the `multi_ack`, `multi_ack_detailed`, `side-band`, `side-band-64k`, `thin-pack`, `ofs-delta`,
+//!   `no-progress` and `include-tag` capabilities.
+//! * V2, with the `ls-refs` command supporting `symrefs`, `peel`, `ref-prefix` and `unborn`, and the `fetch` command
+//!   supporting `thin-pack`, `ofs-delta`, `no-progress` and `include-tag`.
+//!
+//! Shallow clones, partial clones and `want-ref` are not supported, and requests using them fail.
+//! Clients may only want objects that references are advertised with, or the objects their annotated tags peel to.
+//!
+//! ### Negotiation
+//!
+//! `have` lines are acknowledged if the [`Delegate`] has the object, and the pack is sent once the client is `done`.
+//! Like `git`, the server claims to be `ready` as soon as all wanted commits have a common object as ancestor,
+//! which lets the client stop negotiating early. In V2, the pack is sent right away then.
+use bstr::BString;
+use gix_hash::ObjectId;
+
+use crate::handshake::Ref;
+
+mod error;
+pub use error::Error;
+
+mod function;
+pub use function::serve;
+
+/// Options for use in [`serve()`].
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The protocol version to speak, typically as requested by the client via the `GIT_PROTOCOL` environment variable
+    /// or the extra parameters of a `git://` request.
+    pub version: gix_transport::Protocol,
+    /// The kind of hash used by the served repository.
+    pub object_hash: gix_hash::Kind,
+    /// The value of the `agent` capability to advertise, like `git/gix-0.1`.
+    pub agent: String,
+    /// If `true`, all packet lines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub trace_packetlines: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: gix_transport::Protocol::V2,
+            object_hash: gix_hash::Kind::Sha1,
+            agent: concat!("git/gix-", env!("CARGO_PKG_VERSION")).into(),
+            trace_packetlines: false,
+        }
+    }
+}
+
+/// A request of a client for a pack, as passed to [`Delegate::write_pack()`].
+#[derive(Debug, Clone, Copy)]
+pub struct PackRequest<'a> {
+    /// The objects the client wants, along with everything reachable from them.
+    pub wants: &'a [ObjectId],
+    /// The objects the client has and that we have too, so that nothing reachable from them needs to be sent.
+    pub common: &'a [ObjectId],
+    /// If `true`, the client can receive packs with deltas against base objects that are not in the pack.
+    pub thin_pack: bool,
+    /// If `true`, the client can decode deltas that refer to their base object by offset.
+    pub ofs_delta: bool,
+    /// If `true`, annotated tags that point to objects in the pack should be sent as well.
+    pub include_tag: bool,
+}
+
+/// The interface to the repository to serve.
+pub trait Delegate {
+    /// Return all references to advertise, with `HEAD` being first if it exists.
+    ///
+    /// `Ref::Unborn` is only advertised to V2 clients which ask for it.
+    fn refs(&mut self) -> Result<Vec<Ref>, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Return `true` if the object with `id` exists.
+    fn contains(&mut self, id: &gix_hash::oid) -> bool;
+
+    /// Return `true` if each of `wants` that peels to a commit has one of the `common` objects as ancestor,
+    /// so that there is no need to negotiate any further.
+    ///
+    /// It's only called if `common` isn't empty.
+    fn wants_reach_common(
+        &mut self,
+        wants: &[ObjectId],
+        common: &[ObjectId],
+    ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>>;
+
+    /// Write a pack as described by `request` to `out`.
+    ///
+    /// Human-readable progress messages may be written to `progress`, which sends them to the client if it supports it,
+    /// or discards them otherwise. Lines should end in `\n`, or `\r` to overwrite the previous message.
+    fn write_pack(
+        &mut self,
+        request: PackRequest<'_>,
+        out: &mut dyn std::io::Write,
+        progress: &mut dyn std::io::Write,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>>;
+}
+
+/// The outcome of [`serve()`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The amount of `ls-refs` commands that were answered, which is always 0 for V0 and V1 which advertise references
+    /// right away.
+    pub ls_refs: usize,
+    /// Information about each pack that was sent, in order.
+    pub packs: Vec<Negotiated>,
+}
+
+/// The result of a negotiation that lead to a pack being sent.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Negotiated {
+    /// The objects the client wanted.
+    pub wants: Vec<ObjectId>,
+    /// The objects the client had and that we had too.
+    pub common: Vec<ObjectId>,
+    /// The capabilities or features the client requested, without `want` or `have` lines.
+    pub features: Vec<BString>,
+}
@@ -8,5 +8,8 @@ pub fn fixture_bytes(path: &str) -> Vec<u8> {
 mod command;
 pub mod fetch;
 mod handshake;
+mod push;
 pub use fetch::_impl::{fetch, FetchConnection};
 pub mod remote_progress;
+#[cfg(feature = "blocking-server")]
+mod server;
@@ -0,0 +1,151 @@
+mod command {
+    use gix_protocol::push::Command;
+
+    fn id(hex: &str) -> gix_hash::ObjectId {
+        gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+    }
+
+    #[test]
+    fn to_line_without_and_with_capabilities() {
+        let cmd = Command {
+            name: "refs/heads/main".into(),
+            old: gix_hash::Kind::Sha1.null(),
+            new: id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"),
+        };
+        assert!(cmd.is_create());
+        assert!(!cmd.is_delete());
+        assert_eq!(
+            cmd.to_line(None::<Vec<&str>>),
+            "0000000000000000000000000000000000000000 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 refs/heads/main"
+        );
+        assert_eq!(
+            cmd.to_line(Some(["report-status", "side-band-64k"])),
+            "0000000000000000000000000000000000000000 e69de29bb2d1d6434b8b29ae775ad8c2e48c5391 refs/heads/main\0report-status side-band-64k"
+        );
+    }
+
+    #[test]
+    fn deletion() {
+        let cmd = Command {
+            name: "refs/heads/main".into(),
+            old: id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"),
+            new: gix_hash::Kind::Sha1.null(),
+        };
+        assert!(cmd.is_delete());
+        assert!(!cmd.is_create());
+    }
+}
+
+mod report {
+    use gix_protocol::push::response::{Error, Report, Status};
+
+    #[test]
+    fn all_ok() -> crate::Result {
+        let report = Report::from_lines([
+            b"unpack ok\n".as_slice(),
+            b"ok refs/heads/main\n",
+            b"option refname refs/heads/main\n",
+            b"ok refs/tags/v1",
+        ])?;
+        assert!(report.is_ok());
+        assert_eq!(report.unpack_error, None);
+        assert_eq!(
+            report.statuses,
+            vec![
+                Status::Ok {
+                    full_ref_name: "refs/heads/main".into()
+                },
+                Status::Ok {
+                    full_ref_name: "refs/tags/v1".into()
+                }
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn unpack_error_and_rejection() -> crate::Result {
+        let report = Report::from_lines([
+            b"unpack index-pack abnormal exit\n".as_slice(),
+            b"ng refs/heads/main unpacker error\n",
+        ])?;
+        assert!(!report.is_ok());
+        assert_eq!(
+            report.unpack_error.as_ref().map(AsRef::as_ref),
+            Some(b"index-pack abnormal exit".as_slice())
+        );
+        assert_eq!(
+            report.statuses,
+            vec![Status::Rejected {
+                full_ref_name: "refs/heads/main".into(),
+                reason: "unpacker error".into()
+            }]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_input() {
+        assert!(matches!(
+            Report::from_lines(std::iter::empty()),
+            Err(Error::MissingUnpackStatus)
+        ));
+        assert!(matches!(
+            Report::from_lines([b"unpack ok".as_slice(), b"what"]),
+            Err(Error::UnknownLine { .. })
+        ));
+    }
+}
+
+mod function {
+    use std::sync::atomic::AtomicBool;
+
+    use gix_features::progress;
+    use gix_protocol::push::{Command, Context, Options};
+    use gix_transport::{client::git::ConnectMode, Protocol};
+
+    use crate::fetch::{oid, transport};
+
+    #[maybe_async::test(feature = "blocking-client", async(feature = "async-client", async_std::test))]
+    async fn commands_are_followed_by_the_pack() -> crate::Result {
+        let mut out = Vec::new();
+        let mut transport = transport(&mut out, "v1/push-report.response", Protocol::V1, ConnectMode::Daemon);
+        let handshake = gix_protocol::Handshake {
+            server_protocol_version: Protocol::V1,
+            refs: Some(Vec::new()),
+            v1_shallow_updates: None,
+            capabilities: gix_transport::client::Capabilities::from_bytes(b"\0report-status")?.0,
+        };
+        let outcome = gix_protocol::push(
+            &[Command {
+                name: "refs/heads/main".into(),
+                old: gix_hash::Kind::Sha1.null(),
+                new: oid("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391"),
+            }],
+            |out, _progress, _should_interrupt| out.write_all(b"PACK and its objects"),
+            progress::Discard,
+            &AtomicBool::default(),
+            Context {
+                handshake: &handshake,
+                transport: &mut transport,
+                user_agent: ("agent", None),
+                trace_packetlines: false,
+            },
+            Options {
+                atomic: false,
+                push_options: &[],
+                quiet: false,
+            },
+        )
+        .await?;
+        drop(transport);
+
+        assert!(outcome.pack_sent);
+        assert!(outcome.report.expect("report-status was requested").is_ok());
+        assert!(
+            out.ends_with(b"0000PACK and its objects"),
+            "the pack follows the flush packet after the commands, as written"
+        );
+        Ok(())
+    }
+}
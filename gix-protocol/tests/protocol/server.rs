@@ -0,0 +1,681 @@
+use bstr::{BString, ByteSlice};
+use gix_packetline::{decode, PacketLineRef};
+
+/// Encode `lines` as packet lines, with `0000` and `0001` being flush and delimiter packets respectively.
+fn input(lines: &[&str]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for line in lines {
+        match *line {
+            "0000" => gix_packetline::blocking_io::encode::flush_to_write(&mut out),
+            "0001" => gix_packetline::blocking_io::encode::delim_to_write(&mut out),
+            line => gix_packetline::blocking_io::encode::text_to_write(line.as_bytes(), &mut out),
+        }
+        .expect("writing to memory works");
+    }
+    out
+}
+
+/// Decode `out` into lines, with bands prefixed by their number, and flush and delimiter packets shown as such.
+fn output(mut out: &[u8]) -> Vec<BString> {
+    let mut lines = Vec::new();
+    while !out.is_empty() {
+        let decode::Stream::Complete { line, bytes_consumed } = decode::streaming(out).expect("valid packet lines")
+        else {
+            panic!("incomplete packet line")
+        };
+        lines.push(match line {
+            PacketLineRef::Data(data) => data.trim_end_with(|c| c == '\n').into(),
+            PacketLineRef::Flush => "0000".into(),
+            PacketLineRef::Delimiter => "0001".into(),
+            PacketLineRef::ResponseEnd => "0002".into(),
+        });
+        out = &out[bytes_consumed..];
+    }
+    lines
+}
+
+mod upload_pack {
+    use bstr::ByteSlice;
+    use gix_protocol::{
+        handshake::Ref,
+        server::upload_pack::{serve, Delegate, Error, Options, PackRequest},
+        transport::Protocol,
+    };
+
+    use super::{input, output};
+
+    const MAIN: &str = "1111111111111111111111111111111111111111";
+    const TAG: &str = "2222222222222222222222222222222222222222";
+    const COMMON: &str = "3333333333333333333333333333333333333333";
+    const UNKNOWN: &str = "4444444444444444444444444444444444444444";
+
+    fn id(hex: &str) -> gix_hash::ObjectId {
+        gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+    }
+
+    #[derive(Default)]
+    struct Repo {
+        unborn: bool,
+        /// If `true`, all wants have a common object as ancestor.
+        ready: bool,
+        packs: Vec<(Vec<gix_hash::ObjectId>, Vec<gix_hash::ObjectId>, bool)>,
+    }
+
+    impl Delegate for Repo {
+        fn refs(&mut self) -> Result<Vec<Ref>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+            if self.unborn {
+                return Ok(vec![Ref::Unborn {
+                    full_ref_name: "HEAD".into(),
+                    target: "refs/heads/main".into(),
+                }]);
+            }
+            Ok(vec![
+                Ref::Symbolic {
+                    full_ref_name: "HEAD".into(),
+                    target: "refs/heads/main".into(),
+                    tag: None,
+                    object: id(MAIN),
+                },
+                Ref::Direct {
+                    full_ref_name: "refs/heads/main".into(),
+                    object: id(MAIN),
+                },
+                Ref::Peeled {
+                    full_ref_name: "refs/tags/v1".into(),
+                    tag: id(TAG),
+                    object: id(MAIN),
+                },
+            ])
+        }
+
+        fn contains(&mut self, oid: &gix_hash::oid) -> bool {
+            [MAIN, TAG, COMMON].iter().any(|hex| id(hex) == oid)
+        }
+
+        fn wants_reach_common(
+            &mut self,
+            _wants: &[gix_hash::ObjectId],
+            common: &[gix_hash::ObjectId],
+        ) -> Result<bool, Box<dyn std::error::Error + Send + Sync + 'static>> {
+            assert!(!common.is_empty(), "only called if there is something in common");
+            Ok(self.ready)
+        }
+
+        fn write_pack(
+            &mut self,
+            request: PackRequest<'_>,
+            out: &mut dyn std::io::Write,
+            progress: &mut dyn std::io::Write,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            self.packs
+                .push((request.wants.to_owned(), request.common.to_owned(), request.include_tag));
+            progress.write_all(b"working\n")?;
+            out.write_all(b"PACK")?;
+            Ok(())
+        }
+    }
+
+    fn options(version: Protocol) -> Options {
+        Options {
+            version,
+            agent: "git/test".into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn v2_ls_refs_and_fetch() -> crate::Result {
+        let mut repo = Repo::default();
+        let mut out = Vec::new();
+        let outcome = serve(
+            &mut repo,
+            input(&[
+                "command=ls-refs",
+                "agent=git/client",
+                "0001",
+                "symrefs",
+                "peel",
+                "ref-prefix HEAD",
+                "ref-prefix refs/tags/",
+                "0000",
+                "command=fetch",
+                "0001",
+                &format!("want {MAIN}"),
+                &format!("have {COMMON}"),
+                &format!("have {UNKNOWN}"),
+                "0000",
+                "command=fetch",
+                "0001",
+                &format!("want {MAIN}"),
+                &format!("have {COMMON}"),
+                "include-tag",
+                "done",
+                "0000",
+                "0000",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V2),
+        )?;
+        assert_eq!(outcome.ls_refs, 1);
+        assert_eq!(outcome.packs.len(), 1);
+        assert_eq!(outcome.packs[0].common, [id(COMMON)]);
+        assert_eq!(repo.packs, [(vec![id(MAIN)], vec![id(COMMON)], true)]);
+
+        let lines = output(&out);
+        assert_eq!(
+            lines,
+            [
+                "version 2",
+                "agent=git/test",
+                "ls-refs=unborn",
+                "fetch",
+                "object-format=sha1",
+                "0000",
+                &format!("{MAIN} HEAD symref-target:refs/heads/main"),
+                &format!("{TAG} refs/tags/v1 peeled:{MAIN}"),
+                "0000",
+                "acknowledgments",
+                &format!("ACK {COMMON}"),
+                "0000",
+                "packfile",
+                "\x02working",
+                "\x01PACK",
+                "0000",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v2_fetch_sends_pack_once_ready() -> crate::Result {
+        let mut repo = Repo {
+            ready: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let outcome = serve(
+            &mut repo,
+            input(&[
+                "command=fetch",
+                "0001",
+                &format!("want {MAIN}"),
+                &format!("have {UNKNOWN}"),
+                "0000",
+                "command=fetch",
+                "0001",
+                &format!("want {MAIN}"),
+                &format!("have {COMMON}"),
+                "0000",
+                "0000",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V2),
+        )?;
+        assert_eq!(outcome.packs.len(), 1, "the client didn't have to say it's done");
+        assert_eq!(repo.packs, [(vec![id(MAIN)], vec![id(COMMON)], false)]);
+        assert_eq!(
+            &output(&out)[6..],
+            [
+                "acknowledgments",
+                "NAK",
+                "0000",
+                "acknowledgments",
+                &format!("ACK {COMMON}"),
+                "ready",
+                "0001",
+                "packfile",
+                "\x02working",
+                "\x01PACK",
+                "0000",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v1_multi_ack_detailed_signals_ready() -> crate::Result {
+        let mut repo = Repo {
+            ready: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        serve(
+            &mut repo,
+            input(&[
+                &format!("want {MAIN} multi_ack_detailed"),
+                "0000",
+                &format!("have {COMMON}"),
+                "0000",
+                &format!("have {UNKNOWN}"),
+                "0000",
+                "done",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V0),
+        )?;
+        assert!(out.ends_with(b"PACK"), "the pack is sent without side-band");
+        assert_eq!(
+            &output(&out[..out.len() - 4])[4..],
+            [
+                "0000".into(),
+                format!("ACK {COMMON} common"),
+                format!("ACK {COMMON} ready"),
+                "NAK".into(),
+                format!("ACK {UNKNOWN} ready"),
+                "NAK".into(),
+                format!("ACK {COMMON}"),
+            ],
+            "ready is signalled after a round with only common objects, or for objects we don't know"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v2_ls_refs_with_unborn_head() -> crate::Result {
+        let mut repo = Repo {
+            unborn: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        serve(
+            &mut repo,
+            input(&[
+                "command=ls-refs",
+                "0001",
+                "symrefs",
+                "0000",
+                "command=ls-refs",
+                "0001",
+                "symrefs",
+                "unborn",
+                "0000",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V2),
+        )?;
+        let lines = output(&out);
+        assert_eq!(
+            &lines[6..],
+            ["0000", "unborn HEAD symref-target:refs/heads/main", "0000"],
+            "unborn refs are only sent when requested"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v1_advertisement_and_multi_ack_detailed_negotiation() -> crate::Result {
+        let mut repo = Repo::default();
+        let mut out = Vec::new();
+        let outcome = serve(
+            &mut repo,
+            input(&[
+                &format!("want {MAIN} multi_ack_detailed side-band-64k ofs-delta agent=git/client"),
+                "0000",
+                &format!("have {UNKNOWN}"),
+                "0000",
+                &format!("have {COMMON}"),
+                "0000",
+                "done",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V1),
+        )?;
+        assert_eq!(outcome.packs.len(), 1);
+        assert_eq!(repo.packs, [(vec![id(MAIN)], vec![id(COMMON)], false)]);
+
+        let lines = output(&out);
+        assert_eq!(
+            lines,
+            [
+                "version 1".into(),
+                format!(
+                    "{MAIN} HEAD\0multi_ack thin-pack side-band side-band-64k ofs-delta no-progress include-tag multi_ack_detailed symref=HEAD:refs/heads/main object-format=sha1 agent=git/test"
+                ),
+                format!("{MAIN} refs/heads/main"),
+                format!("{TAG} refs/tags/v1"),
+                format!("{MAIN} refs/tags/v1^{{}}"),
+                "0000".into(),
+                "NAK".into(),
+                format!("ACK {COMMON} common"),
+                "NAK".into(),
+                format!("ACK {COMMON}"),
+                "\x02working".into(),
+                "\x01PACK".into(),
+                "0000".into(),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v0_without_multi_ack_and_sideband() -> crate::Result {
+        let mut repo = Repo::default();
+        let mut out = Vec::new();
+        serve(
+            &mut repo,
+            input(&[
+                &format!("want {MAIN}"),
+                "0000",
+                &format!("have {COMMON}"),
+                &format!("have {TAG}"),
+                "0000",
+                "done",
+            ])
+            .as_slice(),
+            &mut out,
+            &options(Protocol::V0),
+        )?;
+        let ack_pos = out
+            .find(format!("ACK {COMMON}\n"))
+            .expect("the first common object is acknowledged");
+        let tail = &out[ack_pos - 4..];
+        assert_eq!(
+            tail,
+            format!("0031ACK {COMMON}\nPACK").as_bytes(),
+            "only the first common object is acknowledged, and the pack is sent without side-band"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn empty_repository_and_client_without_wants() -> crate::Result {
+        let mut repo = Repo {
+            unborn: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let outcome = serve(&mut repo, input(&["0000"]).as_slice(), &mut out, &options(Protocol::V0))?;
+        assert!(outcome.packs.is_empty());
+        let lines = output(&out);
+        assert!(lines[0].starts_with(b"0000000000000000000000000000000000000000 capabilities^{}\0"));
+        assert_eq!(lines[1], "0000");
+        Ok(())
+    }
+
+    #[test]
+    fn wanting_unknown_objects_is_an_error_sent_to_the_client() {
+        let mut repo = Repo::default();
+        let mut out = Vec::new();
+        let err = serve(
+            &mut repo,
+            input(&["command=fetch", "0001", &format!("want {UNKNOWN}"), "done", "0000"]).as_slice(),
+            &mut out,
+            &options(Protocol::V2),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::NotOurRef { id: oid } if oid == id(UNKNOWN)));
+        assert!(output(&out)
+            .last()
+            .expect("error line")
+            .starts_with(b"ERR upload-pack: The client wants 4444"));
+    }
+
+    #[test]
+    fn wanting_existing_objects_that_are_not_advertised_is_an_error() {
+        for (version, request) in [
+            (
+                Protocol::V2,
+                input(&["command=fetch", "0001", &format!("want {COMMON}"), "done", "0000"]),
+            ),
+            (
+                Protocol::V1,
+                input(&[&format!("want {MAIN}"), &format!("want {COMMON}"), "0000", "done"]),
+            ),
+        ] {
+            let mut repo = Repo::default();
+            assert!(repo.contains(&id(COMMON)), "the object exists");
+            let err = serve(&mut repo, request.as_slice(), &mut Vec::new(), &options(version)).unwrap_err();
+            assert!(
+                matches!(err, Error::NotOurRef { id: oid } if oid == id(COMMON)),
+                "{version:?}: only the tips of advertised references can be wanted, like git does by default"
+            );
+            assert!(repo.packs.is_empty());
+        }
+    }
+}
+
+mod receive_pack {
+    use gix_protocol::{
+        handshake::Ref,
+        push::response::{Report, Status},
+        server::receive_pack::{serve, Delegate, Error, Options, UpdateRequest},
+        transport::Protocol,
+    };
+
+    use super::{input, output};
+
+    const MAIN: &str = "1111111111111111111111111111111111111111";
+    const NEW: &str = "2222222222222222222222222222222222222222";
+    const NULL: &str = "0000000000000000000000000000000000000000";
+
+    #[derive(Default)]
+    struct Repo {
+        empty: bool,
+        fail_unpack: bool,
+        pack: Option<Vec<u8>>,
+        updates: Vec<(usize, bool, usize)>,
+    }
+
+    impl Delegate for Repo {
+        fn refs(&mut self) -> Result<Vec<Ref>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+            if self.empty {
+                return Ok(Vec::new());
+            }
+            Ok(vec![Ref::Direct {
+                full_ref_name: "refs/heads/main".into(),
+                object: gix_hash::ObjectId::from_hex(MAIN.as_bytes())?,
+            }])
+        }
+
+        fn receive_pack(
+            &mut self,
+            pack: &mut dyn std::io::BufRead,
+            progress: &mut dyn std::io::Write,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            let mut buf = Vec::new();
+            pack.read_to_end(&mut buf)?;
+            self.pack = Some(buf);
+            progress.write_all(b"unpacking\n")?;
+            if self.fail_unpack {
+                return Err("corrupt pack".into());
+            }
+            Ok(())
+        }
+
+        fn update(
+            &mut self,
+            request: UpdateRequest<'_>,
+            progress: &mut dyn std::io::Write,
+        ) -> Result<Vec<Status>, Box<dyn std::error::Error + Send + Sync + 'static>> {
+            self.updates
+                .push((request.commands.len(), request.atomic, request.push_options.len()));
+            progress.write_all(b"hook output\n")?;
+            Ok(request
+                .commands
+                .iter()
+                .map(|command| {
+                    if command.name == "refs/heads/rejected" {
+                        Status::Rejected {
+                            full_ref_name: command.name.clone(),
+                            reason: "hook declined".into(),
+                        }
+                    } else {
+                        Status::Ok {
+                            full_ref_name: command.name.clone(),
+                        }
+                    }
+                })
+                .collect())
+        }
+    }
+
+    fn options(version: Protocol) -> Options {
+        Options {
+            version,
+            agent: "git/test".into(),
+            ..Default::default()
+        }
+    }
+
+    const CAPABILITIES: &str =
+        "report-status delete-refs side-band-64k quiet atomic ofs-delta push-options object-format=sha1 agent=git/test";
+
+    #[test]
+    fn v0_update_with_report_status() -> crate::Result {
+        let mut repo = Repo::default();
+        let mut request = input(&[
+            &format!("{MAIN} {NEW} refs/heads/main\0report-status agent=git/client"),
+            &format!("{NULL} {NEW} refs/heads/rejected"),
+            "0000",
+        ]);
+        request.extend_from_slice(b"PACK");
+        let mut out = Vec::new();
+        let outcome = serve(&mut repo, request.as_slice(), &mut out, &options(Protocol::V0))?;
+
+        assert_eq!(
+            output(&out),
+            [
+                format!("{MAIN} refs/heads/main\0{CAPABILITIES}").as_str(),
+                "0000",
+                "unpack ok",
+                "ok refs/heads/main",
+                "ng refs/heads/rejected hook declined",
+                "0000",
+            ],
+            "without side-band, progress is discarded and the report is sent as is"
+        );
+        assert_eq!(
+            repo.pack.as_deref(),
+            Some(b"PACK".as_slice()),
+            "the pack is read to the end"
+        );
+        assert_eq!(repo.updates, [(2, false, 0)]);
+        assert_eq!(outcome.features, ["report-status", "agent=git/client"]);
+        assert_eq!(outcome.commands.len(), 2);
+        assert!(outcome.commands[1].is_create());
+        assert_eq!(
+            outcome.report,
+            Some(Report {
+                unpack_error: None,
+                statuses: vec![
+                    Status::Ok {
+                        full_ref_name: "refs/heads/main".into()
+                    },
+                    Status::Rejected {
+                        full_ref_name: "refs/heads/rejected".into(),
+                        reason: "hook declined".into()
+                    }
+                ]
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn v1_side_band_with_push_options_and_atomic_deletion() -> crate::Result {
+        let mut repo = Repo::default();
+        let request = input(&[
+            &format!("{MAIN} {NULL} refs/heads/main\0report-status side-band-64k atomic push-options"),
+            "0000",
+            "ci.skip",
+            "0000",
+        ]);
+        let mut out = Vec::new();
+        let outcome = serve(&mut repo, request.as_slice(), &mut out, &options(Protocol::V1))?;
+
+        let lines = output(&out);
+        assert_eq!(lines[0], "version 1");
+        assert_eq!(
+            &lines[3..],
+            [
+                "\u{2}hook output",
+                "\u{1}000eunpack ok\n0017ok refs/heads/main\n0000",
+                "0000"
+            ],
+            "the report is sent through the data band, with progress in its own band"
+        );
+        assert_eq!(repo.pack, None, "deletions don't need a pack");
+        assert_eq!(repo.updates, [(1, true, 1)]);
+        assert_eq!(outcome.push_options, ["ci.skip"]);
+        assert!(outcome.report.expect("commands were sent").is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn failure_to_receive_the_pack_rejects_all_commands() -> crate::Result {
+        let mut repo = Repo {
+            fail_unpack: true,
+            ..Default::default()
+        };
+        let mut request = input(&[
+            &format!("{NULL} {NEW} refs/heads/new\0report-status side-band-64k quiet"),
+            "0000",
+        ]);
+        request.extend_from_slice(b"PACK");
+        let mut out = Vec::new();
+        let outcome = serve(&mut repo, request.as_slice(), &mut out, &options(Protocol::V0))?;
+
+        assert_eq!(
+            &output(&out)[2..],
+            [
+                "\u{1}0018unpack corrupt pack\n0025ng refs/heads/new unpacker error\n0000",
+                "0000"
+            ],
+            "quiet clients don't receive progress"
+        );
+        assert!(
+            repo.updates.is_empty(),
+            "nothing is updated if the pack couldn't be received"
+        );
+        assert_eq!(
+            outcome
+                .report
+                .expect("commands were sent")
+                .unpack_error
+                .as_ref()
+                .map(AsRef::as_ref),
+            Some(b"corrupt pack".as_slice())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn empty_repository_and_client_without_commands() -> crate::Result {
+        let mut repo = Repo {
+            empty: true,
+            ..Default::default()
+        };
+        let mut out = Vec::new();
+        let outcome = serve(&mut repo, input(&["0000"]).as_slice(), &mut out, &options(Protocol::V0))?;
+        assert_eq!(
+            output(&out),
+            [format!("{NULL} capabilities^{{}}\0{CAPABILITIES}").as_str(), "0000"]
+        );
+        assert!(outcome.commands.is_empty());
+        assert_eq!(outcome.report, None);
+        Ok(())
+    }
+
+    #[test]
+    fn shallow_pushes_are_an_error_sent_to_the_client() {
+        let mut repo = Repo::default();
+        let mut out = Vec::new();
+        let err = serve(
+            &mut repo,
+            input(&[&format!("shallow {MAIN}"), "0000"]).as_slice(),
+            &mut out,
+            &options(Protocol::V0),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::UnsupportedLine { .. }));
+        assert!(output(&out)
+            .last()
+            .expect("error line")
+            .starts_with(b"ERR receive-pack: The client sent the unsupported line"));
+    }
+}
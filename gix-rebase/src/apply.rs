@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+use bstr::BString;
+
+use crate::{
+    state::{decode_id, parse_head_name, read, read_file, read_number},
+    Kind,
+};
+
+/// The name of the directory within the `git` directory that holds the state of a rebase with the `apply` backend,
+/// or of `git am`.
+pub const DIR: &str = "rebase-apply";
+
+/// The state of a rebase with the `apply` backend or of `git am` that is in progress, as stored in the `rebase-apply`
+/// directory within the `git` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// Either [`Kind::Apply`] or [`Kind::ApplyMailbox`].
+    pub kind: Kind,
+    /// The full name of the branch that is rebased, or `None` if it's a detached `HEAD` or if patches from a mailbox are applied.
+    pub head_name: Option<BString>,
+    /// The commit the rebased commits are placed on top of, if known.
+    pub onto: Option<gix_hash::ObjectId>,
+    /// The commit `HEAD` pointed to when the operation started, if known.
+    pub orig_head: Option<gix_hash::ObjectId>,
+    /// The number of the patch that is currently applied, starting at 1.
+    pub next: usize,
+    /// The total amount of patches.
+    pub last: usize,
+}
+
+impl State {
+    /// Return the path to the directory that holds the state in `git_dir`.
+    pub fn dir(git_dir: &Path) -> PathBuf {
+        git_dir.join(DIR)
+    }
+
+    /// Read the state of the operation in progress in `git_dir`, or return `None` if there is none.
+    pub fn read(git_dir: &Path) -> Result<Option<Self>, read::Error> {
+        let dir = Self::dir(git_dir);
+        if !dir.is_dir() {
+            return Ok(None);
+        }
+        let kind = if dir.join("rebasing").is_file() {
+            Kind::Apply
+        } else {
+            Kind::ApplyMailbox
+        };
+        let id = |name: &str| -> Result<Option<gix_hash::ObjectId>, read::Error> {
+            let path = dir.join(name);
+            read_file(&path)?.map(|data| decode_id(&data, path)).transpose()
+        };
+        Ok(Some(State {
+            kind,
+            head_name: read_file(&dir.join("head-name"))?.and_then(|data| parse_head_name(&data)),
+            onto: id("onto")?,
+            orig_head: id("orig-head")?,
+            next: read_number(&dir.join("next"))?.unwrap_or_default(),
+            last: read_number(&dir.join("last"))?.unwrap_or_default(),
+        }))
+    }
+}
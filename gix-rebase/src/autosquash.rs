@@ -0,0 +1,141 @@
+use bstr::{BStr, ByteSlice};
+
+use crate::todo::{Commit, Instruction, List};
+
+/// Move each commit whose summary starts with `fixup! `, `amend! ` or `squash! ` right after the commit it refers to,
+/// and change its instruction to `fixup`, `fixup -C` or `squash` respectively, like `git rebase --autosquash` does.
+/// Return `true` if the list was changed.
+///
+/// A commit is referred to by its summary, by its name, or by the beginning of its summary, with the commit that comes first
+/// being preferred. Commits that refer to commits that aren't in the list are left alone.
+pub fn rearrange(list: &mut List) -> bool {
+    let len = list.instructions.len();
+    let mut next: Vec<Option<usize>> = vec![None; len];
+    let mut tail: Vec<Option<usize>> = vec![None; len];
+    let mut is_moved = vec![false; len];
+    let mut subjects: Vec<Option<&BStr>> = vec![None; len];
+    let mut commands = Vec::new();
+
+    for idx in 0..len {
+        let Some(commit) = rearrangeable_commit(&list.instructions[idx]) else {
+            continue;
+        };
+        let subject = commit.summary.as_bstr();
+        let target = strip_fixup_prefix(subject).and_then(|mut target_subject| {
+            while let Some(stripped) = strip_fixup_prefix(target_subject.trim_start().as_bstr()) {
+                target_subject = stripped;
+            }
+            let target_subject = target_subject.trim_start().as_bstr();
+            subjects[..idx]
+                .iter()
+                .position(|subject| *subject == Some(target_subject))
+                .or_else(|| {
+                    let is_name = target_subject.len() >= 4 && target_subject.iter().all(u8::is_ascii_hexdigit);
+                    is_name
+                        .then(|| {
+                            (0..idx).find(|&prev| {
+                                rearrangeable_commit(&list.instructions[prev]).is_some_and(|commit| {
+                                    commit.name.starts_with(target_subject) || target_subject.starts_with(&commit.name)
+                                })
+                            })
+                        })
+                        .flatten()
+                })
+                .or_else(|| {
+                    (0..idx).find(|&prev| {
+                        rearrangeable_commit(&list.instructions[prev])
+                            .is_some_and(|commit| commit.summary.starts_with(target_subject))
+                    })
+                })
+        });
+
+        match target {
+            Some(target) => {
+                commands.push((idx, fixup_kind(subject)));
+                let target_tail = tail[target].unwrap_or(target);
+                next[idx] = next[target_tail];
+                next[target_tail] = Some(idx);
+                tail[target] = Some(idx);
+                is_moved[idx] = true;
+            }
+            None => {
+                if !subjects[..idx].contains(&Some(subject)) {
+                    subjects[idx] = Some(subject);
+                }
+            }
+        }
+    }
+    if commands.is_empty() {
+        return false;
+    }
+
+    let mut instructions: Vec<Option<Instruction>> =
+        std::mem::take(&mut list.instructions).into_iter().map(Some).collect();
+    for (idx, kind) in commands {
+        let commit = rearrangeable_commit(instructions[idx].as_ref().expect("not yet taken"))
+            .expect("only commits are moved")
+            .clone();
+        instructions[idx] = Some(match kind {
+            FixupKind::Fixup => Instruction::Fixup {
+                commit,
+                use_message: false,
+                edit_message: false,
+            },
+            FixupKind::Amend => Instruction::Fixup {
+                commit,
+                use_message: true,
+                edit_message: false,
+            },
+            FixupKind::Squash => Instruction::Squash(commit),
+        });
+    }
+    for (idx, is_moved) in is_moved.into_iter().enumerate() {
+        if is_moved {
+            continue;
+        }
+        let mut cursor = Some(idx);
+        while let Some(current) = cursor {
+            list.instructions
+                .push(instructions[current].take().expect("each instruction is visited once"));
+            cursor = next[current];
+        }
+    }
+    true
+}
+
+#[derive(Debug, Copy, Clone)]
+enum FixupKind {
+    Fixup,
+    Amend,
+    Squash,
+}
+
+fn fixup_kind(subject: &BStr) -> FixupKind {
+    if subject.starts_with(b"fixup!") {
+        FixupKind::Fixup
+    } else if subject.starts_with(b"amend!") {
+        FixupKind::Amend
+    } else {
+        FixupKind::Squash
+    }
+}
+
+fn strip_fixup_prefix(subject: &BStr) -> Option<&BStr> {
+    ["fixup! ", "amend! ", "squash! "]
+        .into_iter()
+        .find_map(|prefix| subject.strip_prefix(prefix.as_bytes()))
+        .map(ByteSlice::as_bstr)
+}
+
+/// Return the commit of `instruction` if it's one that can be rearranged, or be the target of a rearranged commit.
+fn rearrangeable_commit(instruction: &Instruction) -> Option<&Commit> {
+    match instruction {
+        Instruction::Pick(commit)
+        | Instruction::Revert(commit)
+        | Instruction::Edit(commit)
+        | Instruction::Reword(commit)
+        | Instruction::Squash(commit)
+        | Instruction::Fixup { commit, .. } => Some(commit),
+        _ => None,
+    }
+}
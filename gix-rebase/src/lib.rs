@@ -1,2 +1,105 @@
-#![deny(rust_2018_idioms)]
+//! Read and write the persistent [state](State) of rebases, and prepare their [todo lists](todo::List).
+//!
+//! The state of rebases performed with the `merge` backend, which includes all interactive rebases, is kept in the
+//! `rebase-merge` directory of the `git` directory, while the `rebase-apply` directory belongs to the `apply` backend
+//! and `git am`, whose [state](apply::State) can only be read.
+//! The on-disk format is the one of `git`, so a rebase started by `git` can be continued with `gitoxide`, and vice versa.
+//! Performing the instructions, which requires access to objects, references, the index and the worktree, is left to the caller.
+#![deny(missing_docs, rust_2018_idioms)]
 #![forbid(unsafe_code)]
+
+use bstr::BString;
+pub use gix_sequencer::todo;
+
+///
+pub mod state;
+
+///
+pub mod apply;
+
+///
+pub mod status;
+
+///
+pub mod autosquash;
+
+/// The kind of rebase that is in progress.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Kind {
+    /// A rebase with the `merge` backend that performs a todo list that wasn't edited, like `git rebase <upstream>`.
+    Merge,
+    /// A rebase with the `merge` backend that performs a todo list that could be edited, like `git rebase --interactive`.
+    Interactive,
+    /// A rebase with the `apply` backend, like `git rebase --apply`.
+    Apply,
+    /// Patches from a mailbox are applied, like `git am`.
+    ApplyMailbox,
+}
+
+/// A summary of the rebase in progress, as needed to describe it in a prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Status {
+    /// The kind of rebase.
+    pub kind: Kind,
+    /// The full name of the branch that is rebased, or `None` if a detached `HEAD` is rebased or if it's unknown.
+    pub head_name: Option<BString>,
+    /// The number of the step that is currently performed, starting at 1, if known.
+    pub step: Option<usize>,
+    /// The total amount of steps, if known.
+    pub total_steps: Option<usize>,
+}
+
+/// A reference that is updated to a commit created by the rebase once it's finished, like `git rebase --update-refs` does.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct UpdateRef {
+    /// The full name of the reference.
+    pub name: BString,
+    /// The commit the reference pointed to when the rebase started.
+    pub before: gix_hash::ObjectId,
+    /// The commit the reference is updated to, or `None` if the `update-ref` instruction for it wasn't performed yet.
+    pub after: Option<gix_hash::ObjectId>,
+}
+
+/// Options of a rebase with the `merge` backend, as stored in individual files in the `rebase-merge` directory.
+///
+/// Options that aren't listed here are kept as they are on disk.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, commits that become empty because their changes are already present are kept, like `--empty=keep`.
+    pub keep_redundant_commits: bool,
+    /// If `true`, commits that become empty because their changes are already present are dropped, which is the default of
+    /// `git rebase`.
+    pub drop_redundant_commits: bool,
+    /// If `true`, `exec` instructions that failed are put back onto the todo list, like `--reschedule-failed-exec`.
+    pub reschedule_failed_exec: bool,
+}
+
+/// The persistent state of a rebase with the `merge` backend that is in progress, as stored in the `rebase-merge`
+/// directory within the `git` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The full name of the branch that is rebased, or `None` if it's a detached `HEAD`.
+    pub head_name: Option<BString>,
+    /// The commit the rebased commits are placed on top of.
+    pub onto: gix_hash::ObjectId,
+    /// The commit `HEAD` pointed to when the rebase started, which is what it is reset to when the rebase is aborted.
+    pub orig_head: gix_hash::ObjectId,
+    /// If `true`, the todo list could be edited, like with `git rebase --interactive`.
+    pub interactive: bool,
+    /// The instructions that are yet to be performed, with the next one first.
+    pub todo: todo::List,
+    /// The instructions that were performed already, including the one that is currently performed.
+    pub done: todo::List,
+    /// The options of the rebase.
+    pub options: Options,
+    /// The references to update once the rebase is finished, which are added with `update-ref` instructions.
+    pub update_refs: Vec<UpdateRef>,
+    /// The commit the rebase stopped at, because applying it caused conflicts or because it should be edited.
+    pub stopped_at: Option<gix_hash::ObjectId>,
+    /// The message of the commit to create when continuing after the rebase stopped.
+    pub message: Option<BString>,
+    /// The author of the commit to create when continuing after the rebase stopped.
+    pub author: Option<gix_actor::Signature>,
+    /// The commit `HEAD` pointed to when the rebase stopped to edit it, which is amended when continuing with changes.
+    pub amend: Option<gix_hash::ObjectId>,
+}
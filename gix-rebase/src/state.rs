@@ -0,0 +1,395 @@
+use std::path::{Path, PathBuf};
+
+use bstr::{BString, ByteSlice, ByteVec};
+
+use crate::{todo, Options, State, UpdateRef};
+
+/// The name of the directory within the `git` directory that holds the state of a rebase with the `merge` backend.
+pub const DIR: &str = "rebase-merge";
+
+/// The content of the `head-name` file if a detached `HEAD` is rebased.
+const DETACHED_HEAD: &str = "detached HEAD";
+
+///
+pub mod read {
+    /// The error returned by [`State::read()`](crate::State::read()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error("Could not decode the object id in '{}'", path.display())]
+        DecodeId {
+            path: std::path::PathBuf,
+            source: gix_hash::decode::Error,
+        },
+        #[error("Could not parse the todo list in '{}'", path.display())]
+        Todo {
+            path: std::path::PathBuf,
+            source: crate::todo::decode::Error,
+        },
+        #[error("The author script in '{}' is invalid", path.display())]
+        AuthorScript { path: std::path::PathBuf },
+        #[error("The references to update in '{}' are invalid", path.display())]
+        UpdateRefs { path: std::path::PathBuf },
+        #[error("Could not parse the number in '{}'", path.display())]
+        Number { path: std::path::PathBuf },
+    }
+}
+
+///
+pub mod write {
+    /// The error returned by [`State::write()`](crate::State::write()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not write '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error(transparent)]
+        Lock(#[from] gix_lock::acquire::Error),
+        #[error(transparent)]
+        Commit(#[from] gix_lock::commit::Error<gix_lock::File>),
+    }
+}
+
+impl State {
+    /// Create the state of a new rebase of the branch named `head_name`, or of a detached `HEAD` if `None`, which pointed to
+    /// `orig_head`, that performs the instructions in `todo` on top of `onto`.
+    pub fn new(
+        head_name: Option<BString>,
+        onto: gix_hash::ObjectId,
+        orig_head: gix_hash::ObjectId,
+        todo: todo::List,
+    ) -> Self {
+        State {
+            head_name,
+            onto,
+            orig_head,
+            interactive: false,
+            todo,
+            done: Default::default(),
+            options: Default::default(),
+            update_refs: Vec::new(),
+            stopped_at: None,
+            message: None,
+            author: None,
+            amend: None,
+        }
+    }
+
+    /// Return the path to the directory that holds the state of a rebase in `git_dir`.
+    pub fn dir(git_dir: &Path) -> PathBuf {
+        git_dir.join(DIR)
+    }
+
+    /// Return `true` if there is a rebase with the `merge` backend in progress in `git_dir`.
+    pub fn exists(git_dir: &Path) -> bool {
+        Self::dir(git_dir).is_dir()
+    }
+
+    /// Read the state of the rebase in progress in `git_dir`, or return `None` if there is none.
+    pub fn read(git_dir: &Path) -> Result<Option<Self>, read::Error> {
+        use read::Error;
+
+        let dir = Self::dir(git_dir);
+        let head_name_path = dir.join("head-name");
+        let Some(head_name) = read_file(&head_name_path)? else {
+            return Ok(None);
+        };
+        let head_name = parse_head_name(&head_name);
+        let required_id = |name: &str| -> Result<gix_hash::ObjectId, Error> {
+            let path = dir.join(name);
+            let data = read_file(&path)?.ok_or_else(|| Error::Io {
+                path: path.clone(),
+                source: std::io::ErrorKind::NotFound.into(),
+            })?;
+            decode_id(&data, path)
+        };
+        let optional_id = |name: &str| -> Result<Option<gix_hash::ObjectId>, Error> {
+            let path = dir.join(name);
+            read_file(&path)?.map(|data| decode_id(&data, path)).transpose()
+        };
+        let list = |name: &str| -> Result<todo::List, Error> {
+            let path = dir.join(name);
+            let data = read_file(&path)?.unwrap_or_default();
+            todo::List::from_bytes(&data).map_err(|source| Error::Todo { path, source })
+        };
+
+        let author_path = dir.join("author-script");
+        let author = read_file(&author_path)?
+            .map(|data| parse_author_script(&data).ok_or(Error::AuthorScript { path: author_path }))
+            .transpose()?;
+        let update_refs_path = dir.join("update-refs");
+        let update_refs = read_file(&update_refs_path)?
+            .map(|data| parse_update_refs(&data).ok_or(Error::UpdateRefs { path: update_refs_path }))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Some(State {
+            head_name,
+            onto: required_id("onto")?,
+            orig_head: required_id("orig-head")?,
+            interactive: dir.join("interactive").is_file(),
+            todo: list("git-rebase-todo")?,
+            done: list("done")?,
+            options: Options {
+                keep_redundant_commits: dir.join("keep_redundant_commits").is_file(),
+                drop_redundant_commits: dir.join("drop_redundant_commits").is_file(),
+                reschedule_failed_exec: dir.join("reschedule-failed-exec").is_file(),
+            },
+            update_refs,
+            stopped_at: optional_id("stopped-sha")?,
+            message: read_file(&dir.join("message"))?.map(Into::into),
+            author,
+            amend: optional_id("amend")?,
+        }))
+    }
+
+    /// Write this state to `git_dir`, creating the directory for it if needed, and replacing the files of a previous state.
+    ///
+    /// Files that aren't represented by this state are left untouched.
+    pub fn write(&self, git_dir: &Path) -> Result<(), write::Error> {
+        use write::Error;
+
+        let dir = Self::dir(git_dir);
+        std::fs::create_dir_all(&dir).map_err(|source| Error::Io {
+            path: dir.clone(),
+            source,
+        })?;
+        let head_name = self
+            .head_name
+            .as_ref()
+            .map_or(DETACHED_HEAD.as_bytes(), |name| name.as_slice());
+        write_file(&dir.join("head-name"), &[head_name, b"\n"].concat())?;
+        write_file(&dir.join("onto"), format!("{}\n", self.onto).as_bytes())?;
+        write_file(&dir.join("orig-head"), format!("{}\n", self.orig_head).as_bytes())?;
+        write_optional(&dir.join("interactive"), self.interactive.then_some(&[][..]))?;
+
+        let mut buf = Vec::new();
+        self.todo.write_to(&mut buf).expect("writing to memory works");
+        write_file(&dir.join("git-rebase-todo"), &buf)?;
+        buf.clear();
+        self.done.write_to(&mut buf).expect("writing to memory works");
+        write_file(&dir.join("done"), &buf)?;
+        let count = |list: &todo::List| list.instructions.iter().filter(|i| !i.is_comment()).count();
+        let done = count(&self.done);
+        write_file(&dir.join("msgnum"), format!("{done}\n").as_bytes())?;
+        write_file(&dir.join("end"), format!("{}\n", done + count(&self.todo)).as_bytes())?;
+
+        let Options {
+            keep_redundant_commits,
+            drop_redundant_commits,
+            reschedule_failed_exec,
+        } = self.options;
+        write_optional(
+            &dir.join("keep_redundant_commits"),
+            keep_redundant_commits.then_some(&[][..]),
+        )?;
+        write_optional(
+            &dir.join("drop_redundant_commits"),
+            drop_redundant_commits.then_some(&[][..]),
+        )?;
+        write_optional(
+            &dir.join("reschedule-failed-exec"),
+            reschedule_failed_exec.then_some(&[][..]),
+        )?;
+        write_optional(
+            &dir.join("no-reschedule-failed-exec"),
+            (!reschedule_failed_exec).then_some(&[][..]),
+        )?;
+
+        let update_refs = (!self.update_refs.is_empty()).then(|| {
+            let mut out = BString::default();
+            for update in &self.update_refs {
+                let after = update.after.unwrap_or_else(|| update.before.kind().null());
+                out.push_str(&update.name);
+                out.push_str(format!("\n{}\n{after}\n", update.before));
+            }
+            out
+        });
+        write_optional(
+            &dir.join("update-refs"),
+            update_refs.as_ref().map(|data| data.as_slice()),
+        )?;
+        let id_line = |id: &gix_hash::ObjectId| format!("{id}\n");
+        write_optional(
+            &dir.join("stopped-sha"),
+            self.stopped_at.as_ref().map(id_line).as_ref().map(String::as_bytes),
+        )?;
+        write_optional(&dir.join("message"), self.message.as_ref().map(|data| data.as_slice()))?;
+        write_optional(
+            &dir.join("author-script"),
+            self.author
+                .as_ref()
+                .map(author_script)
+                .as_ref()
+                .map(|data| data.as_slice()),
+        )?;
+        write_optional(
+            &dir.join("amend"),
+            self.amend.as_ref().map(id_line).as_ref().map(String::as_bytes),
+        )?;
+        Ok(())
+    }
+
+    /// Remove the state of the rebase in progress in `git_dir`, if there is one.
+    pub fn remove(git_dir: &Path) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(Self::dir(git_dir)) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+}
+
+/// Return the full name of the branch in the `head-name` file, or `None` if a detached `HEAD` is rebased.
+pub(crate) fn parse_head_name(data: &[u8]) -> Option<BString> {
+    let name = data.trim_end();
+    (name != DETACHED_HEAD.as_bytes()).then(|| name.into())
+}
+
+/// Parse a shell script that sets `GIT_AUTHOR_NAME`, `GIT_AUTHOR_EMAIL` and `GIT_AUTHOR_DATE` like `git` writes it.
+fn parse_author_script(data: &[u8]) -> Option<gix_actor::Signature> {
+    let (mut name, mut email, mut time) = (None, None, None);
+    for line in data.lines().filter(|line| !line.trim().is_empty()) {
+        let (key, value) = line.split_once_str(b"=")?;
+        let value = unquote(value)?;
+        match key {
+            b"GIT_AUTHOR_NAME" => name = Some(value),
+            b"GIT_AUTHOR_EMAIL" => email = Some(value),
+            b"GIT_AUTHOR_DATE" => {
+                let value = value.strip_prefix(b"@").unwrap_or(&value).to_str().ok()?;
+                time = Some(gix_date::parse_header(value)?);
+            }
+            _ => return None,
+        }
+    }
+    Some(gix_actor::Signature {
+        name: name?,
+        email: email?,
+        time: time?,
+    })
+}
+
+/// Remove the single quotes around `value`, which may contain quoted quotes and exclamation marks like `'\''` or `'\!'`.
+fn unquote(value: &[u8]) -> Option<BString> {
+    let mut out = BString::default();
+    let mut rest = value.strip_prefix(b"'")?;
+    loop {
+        let end = rest.find_byte(b'\'')?;
+        out.push_str(&rest[..end]);
+        rest = &rest[end + 1..];
+        if rest.is_empty() {
+            return Some(out);
+        }
+        let (escaped, remainder) = rest.strip_prefix(b"\\").and_then(|rest| rest.split_first())?;
+        out.push(*escaped);
+        rest = remainder.strip_prefix(b"'")?;
+    }
+}
+
+fn author_script(author: &gix_actor::Signature) -> BString {
+    let quote = |value: &[u8]| {
+        let mut out = BString::from("'");
+        for byte in value {
+            match byte {
+                b'\'' | b'!' => {
+                    out.push_str(b"'\\");
+                    out.push(*byte);
+                    out.push(b'\'');
+                }
+                _ => out.push(*byte),
+            }
+        }
+        out.push(b'\'');
+        out
+    };
+    let mut time = Vec::new();
+    author.time.write_to(&mut time).expect("writing to memory works");
+    let mut out = BString::default();
+    for (key, value) in [
+        ("GIT_AUTHOR_NAME", author.name.as_slice()),
+        ("GIT_AUTHOR_EMAIL", author.email.as_slice()),
+        ("GIT_AUTHOR_DATE", &[b"@", time.as_slice()].concat()),
+    ] {
+        out.push_str(key);
+        out.push(b'=');
+        out.push_str(quote(value));
+        out.push(b'\n');
+    }
+    out
+}
+
+/// Parse the `update-refs` file, which has the name of the reference, its value before the rebase and the value it should
+/// be updated to in three consecutive lines for each reference.
+fn parse_update_refs(data: &[u8]) -> Option<Vec<UpdateRef>> {
+    let mut out = Vec::new();
+    let mut lines = data.lines();
+    while let Some(name) = lines.next() {
+        let before = gix_hash::ObjectId::from_hex(lines.next()?).ok()?;
+        let after = gix_hash::ObjectId::from_hex(lines.next()?).ok()?;
+        out.push(UpdateRef {
+            name: name.into(),
+            before,
+            after: (!after.is_null()).then_some(after),
+        });
+    }
+    Some(out)
+}
+
+pub(crate) fn read_file(path: &Path) -> Result<Option<Vec<u8>>, read::Error> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(read::Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+pub(crate) fn read_number(path: &Path) -> Result<Option<usize>, read::Error> {
+    read_file(path)?
+        .map(|data| {
+            data.trim()
+                .to_str()
+                .ok()
+                .and_then(|number| number.parse().ok())
+                .ok_or_else(|| read::Error::Number { path: path.to_owned() })
+        })
+        .transpose()
+}
+
+pub(crate) fn decode_id(data: &[u8], path: PathBuf) -> Result<gix_hash::ObjectId, read::Error> {
+    gix_hash::ObjectId::from_hex(data.trim()).map_err(|source| read::Error::DecodeId { path, source })
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<(), write::Error> {
+    use std::io::Write;
+
+    let mut file = gix_lock::File::acquire_to_update_resource(path, gix_lock::acquire::Fail::Immediately, None)?;
+    file.write_all(data).map_err(|source| write::Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    file.commit()?;
+    Ok(())
+}
+
+/// Write `data` to `path`, or remove the file at `path` if `data` is `None`.
+fn write_optional(path: &Path, data: Option<&[u8]>) -> Result<(), write::Error> {
+    match data {
+        Some(data) => write_file(path, data),
+        None => match std::fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(write::Error::Io {
+                path: path.to_owned(),
+                source: err,
+            }),
+            _ => Ok(()),
+        },
+    }
+}
@@ -0,0 +1,36 @@
+use std::path::Path;
+
+use crate::{
+    apply,
+    state::{parse_head_name, read, read_file, read_number},
+    Kind, State, Status,
+};
+
+/// Return a summary of the rebase or `git am` in progress in `git_dir`, or `None` if there is none.
+///
+/// Only the few files that are needed for it are read, which makes this suitable for prompts.
+pub fn status(git_dir: &Path) -> Result<Option<Status>, read::Error> {
+    if let Some(state) = apply::State::read(git_dir)? {
+        return Ok(Some(Status {
+            kind: state.kind,
+            head_name: state.head_name,
+            step: Some(state.next),
+            total_steps: Some(state.last),
+        }));
+    }
+
+    let dir = State::dir(git_dir);
+    if !dir.is_dir() {
+        return Ok(None);
+    }
+    Ok(Some(Status {
+        kind: if dir.join("interactive").is_file() {
+            Kind::Interactive
+        } else {
+            Kind::Merge
+        },
+        head_name: read_file(&dir.join("head-name"))?.and_then(|data| parse_head_name(&data)),
+        step: read_number(&dir.join("msgnum"))?,
+        total_steps: read_number(&dir.join("end"))?,
+    }))
+}
@@ -0,0 +1,55 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function setup() {
+  git init -q
+  echo base > file
+  git add file && git commit -q -m base
+
+  git checkout -q -b topic
+  echo a > a
+  git add a && git commit -q -m "add a"
+  git branch part
+  echo topic > file
+  git commit -q -am "change file"
+  echo b > b
+  git add b && git commit -q -m "add b"
+
+  git checkout -q main
+  echo main > file
+  git commit -q -am "change file on main"
+  git checkout -q topic
+}
+
+mkdir interactive-with-break
+(cd interactive-with-break
+  setup
+  GIT_SEQUENCE_EDITOR="sed -i -e '\$a break'" git rebase -q -i --update-refs --keep-empty --reschedule-failed-exec main~1 || :
+)
+
+mkdir merge-with-conflict
+(cd merge-with-conflict
+  setup
+  git rebase -q main || :
+)
+
+mkdir apply-with-conflict
+(cd apply-with-conflict
+  setup
+  git rebase -q --apply main || :
+)
+
+mkdir am-with-conflict
+(cd am-with-conflict
+  setup
+  git format-patch -q -2 topic
+  git checkout -q main
+  git am -q 0001-change-file.patch 0002-add-b.patch || :
+)
+
+mkdir detached
+(cd detached
+  setup
+  git checkout -q --detach topic
+  GIT_SEQUENCE_EDITOR="sed -i -e '1i break'" git rebase -q -i main || :
+)
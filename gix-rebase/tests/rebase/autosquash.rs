@@ -0,0 +1,91 @@
+use gix_rebase::{autosquash::rearrange, todo::List};
+
+fn rearranged(todo: &str) -> crate::Result<(bool, String)> {
+    let mut list = List::from_bytes(todo.as_bytes())?;
+    let changed = rearrange(&mut list);
+    let mut out = Vec::new();
+    list.write_to(&mut out)?;
+    Ok((changed, String::from_utf8(out)?))
+}
+
+#[test]
+fn fixups_are_moved_after_their_target() -> crate::Result {
+    assert_eq!(
+        rearranged(
+            "pick 1111111 one
+pick 2222222 two
+pick 3333333 fixup! one
+pick 4444444 squash! two
+pick 5555555 amend! one
+pick 6666666 fixup! fixup! one
+"
+        )?,
+        (
+            true,
+            "pick 1111111 one
+fixup 3333333 fixup! one
+fixup -C 5555555 amend! one
+fixup 6666666 fixup! fixup! one
+pick 2222222 two
+squash 4444444 squash! two
+"
+            .into()
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn targets_are_found_by_name_and_summary_prefix() -> crate::Result {
+    assert_eq!(
+        rearranged(
+            "pick 1111111 one with a long summary
+pick 2222222 two
+pick 3333333 fixup! 2222
+pick 4444444 fixup! one with
+"
+        )?,
+        (
+            true,
+            "pick 1111111 one with a long summary
+fixup 4444444 fixup! one with
+pick 2222222 two
+fixup 3333333 fixup! 2222
+"
+            .into()
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn the_first_commit_with_a_summary_is_the_target() -> crate::Result {
+    assert_eq!(
+        rearranged(
+            "pick 1111111 same
+pick 2222222 same
+pick 3333333 squash! same
+"
+        )?,
+        (
+            true,
+            "pick 1111111 same
+squash 3333333 squash! same
+pick 2222222 same
+"
+            .into()
+        )
+    );
+    Ok(())
+}
+
+#[test]
+fn unknown_targets_and_other_instructions_are_left_alone() -> crate::Result {
+    let todo = "pick 1111111 one
+exec make
+pick 2222222 fixup! unknown
+break
+";
+    assert_eq!(rearranged(todo)?, (false, todo.into()));
+    Ok(())
+}
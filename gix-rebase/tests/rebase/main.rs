@@ -0,0 +1,21 @@
+mod autosquash;
+mod state;
+mod status;
+
+pub use gix_testtools::Result;
+use std::path::{Path, PathBuf};
+
+fn git_dir(name: &str) -> crate::Result<PathBuf> {
+    Ok(gix_testtools::scripted_fixture_read_only("make_rebase_repos.sh")?
+        .join(name)
+        .join(".git"))
+}
+
+fn hex_to_id(hex: &str) -> gix_hash::ObjectId {
+    gix_hash::ObjectId::from_hex(hex.as_bytes()).expect("valid hex")
+}
+
+fn read_to_id(git_dir: &Path, path: &str) -> crate::Result<gix_hash::ObjectId> {
+    let data = std::fs::read(git_dir.join(path))?;
+    Ok(hex_to_id(std::str::from_utf8(&data)?.trim()))
+}
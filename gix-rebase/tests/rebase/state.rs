@@ -0,0 +1,195 @@
+use gix_rebase::{
+    todo::{Commit, Instruction, List},
+    Options, State, UpdateRef,
+};
+
+use crate::{git_dir, read_to_id};
+
+#[test]
+fn read_stopped_at_break_by_git() -> crate::Result {
+    let git_dir = git_dir("interactive-with-break")?;
+    assert!(State::exists(&git_dir));
+    let state = State::read(&git_dir)?.expect("rebase in progress");
+
+    assert_eq!(state.head_name, Some("refs/heads/topic".into()));
+    assert_eq!(state.orig_head, read_to_id(&git_dir, "refs/heads/topic")?);
+    assert!(state.interactive);
+    assert!(state.todo.is_done());
+    assert!(
+        matches!(state.done.current(), Some(Instruction::Pick(Commit { name, summary })) if name.len() == 40 && summary == "add a"),
+        "git writes full ids to the list of done instructions"
+    );
+    assert_eq!(state.done.instructions.last(), Some(&Instruction::Break));
+    assert_eq!(
+        state.options,
+        Options {
+            reschedule_failed_exec: true,
+            ..Default::default()
+        }
+    );
+    let part = read_to_id(&git_dir, "refs/heads/part")?;
+    assert_eq!(
+        state.update_refs,
+        [UpdateRef {
+            name: "refs/heads/part".into(),
+            before: part,
+            after: Some(part),
+        }],
+        "the commits were fast-forwarded, so the branch doesn't change"
+    );
+    assert_eq!(state.stopped_at, None);
+    assert_eq!(state.author, None);
+    Ok(())
+}
+
+#[test]
+fn read_stopped_with_conflict_by_git() -> crate::Result {
+    let git_dir = git_dir("merge-with-conflict")?;
+    let state = State::read(&git_dir)?.expect("rebase in progress");
+
+    assert_eq!(state.onto, read_to_id(&git_dir, "refs/heads/main")?);
+    assert!(
+        state.interactive,
+        "git uses the same files for all rebases with the merge backend"
+    );
+    assert_eq!(
+        state
+            .done
+            .instructions
+            .iter()
+            .map(|instruction| instruction.commit().expect("picks only").summary.to_string())
+            .collect::<Vec<_>>(),
+        ["add a", "change file"]
+    );
+    assert_eq!(state.todo.instructions.len(), 1);
+    assert_eq!(
+        state.options,
+        Options {
+            drop_redundant_commits: true,
+            ..Default::default()
+        }
+    );
+    assert_eq!(state.stopped_at, Some(read_to_id(&git_dir, "REBASE_HEAD")?));
+    assert_eq!(state.message, Some("change file\n\n".into()));
+    let author = state.author.expect("written on conflict");
+    assert_eq!(author.name, "author");
+    assert_eq!(author.email, "author@example.com");
+    assert_eq!(author.time, gix_date::Time::new(946684800, 0));
+    assert_eq!(state.amend, None);
+    Ok(())
+}
+
+#[test]
+fn read_detached_by_git() -> crate::Result {
+    let git_dir = git_dir("detached")?;
+    let state = State::read(&git_dir)?.expect("rebase in progress");
+    assert_eq!(state.head_name, None);
+    assert_eq!(state.done.instructions, [Instruction::Break]);
+    assert_eq!(state.todo.instructions.len(), 3);
+    Ok(())
+}
+
+#[test]
+fn write_is_compatible_with_git() -> crate::Result {
+    for (name, files) in [
+        (
+            "interactive-with-break",
+            &[
+                "head-name",
+                "onto",
+                "orig-head",
+                "interactive",
+                "msgnum",
+                "reschedule-failed-exec",
+                "update-refs",
+            ][..],
+        ),
+        (
+            "merge-with-conflict",
+            &[
+                "head-name",
+                "onto",
+                "orig-head",
+                "git-rebase-todo",
+                "done",
+                "msgnum",
+                "end",
+                "drop_redundant_commits",
+                "no-reschedule-failed-exec",
+                "stopped-sha",
+                "message",
+                "author-script",
+            ][..],
+        ),
+        (
+            "detached",
+            &["head-name", "git-rebase-todo", "done", "msgnum", "end"][..],
+        ),
+    ] {
+        // `end` isn't compared if the todo list contains empty lines, as `git` counts them but they aren't retained.
+        let git_dir = git_dir(name)?;
+        let state = State::read(&git_dir)?.expect("rebase in progress");
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        state.write(tmp.path())?;
+        for file in files {
+            assert_eq!(
+                std::fs::read(State::dir(tmp.path()).join(file))?,
+                std::fs::read(State::dir(&git_dir).join(file))?,
+                "{name}: {file} is written exactly like git does"
+            );
+        }
+        assert_eq!(State::read(tmp.path())?, Some(state));
+    }
+    Ok(())
+}
+
+#[test]
+fn write_and_remove() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    assert!(!State::exists(tmp.path()));
+    assert_eq!(State::read(tmp.path())?, None);
+
+    let onto = gix_hash::Kind::Sha1.null();
+    let mut state = State::new(
+        None,
+        onto,
+        onto,
+        List::from_bytes(b"pick 4a1b2c3 one\nexec make\nupdate-ref refs/heads/other\n")?,
+    );
+    state.update_refs.push(UpdateRef {
+        name: "refs/heads/other".into(),
+        before: onto,
+        after: None,
+    });
+    state.amend = Some(onto);
+    state.stopped_at = Some(onto);
+    state.message = Some("one\n".into());
+    state.author = Some(gix_actor::Signature {
+        name: "Name with 'quote'".into(),
+        email: "a@b".into(),
+        time: gix_date::Time::new(1000000000, -5400),
+    });
+    state.write(tmp.path())?;
+    assert!(State::exists(tmp.path()));
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    state.done.instructions.push(state.todo.pop_current().expect("present"));
+    state.amend = None;
+    state.stopped_at = None;
+    state.message = None;
+    state.author = None;
+    state.write(tmp.path())?;
+    for file in ["amend", "stopped-sha", "message", "author-script"] {
+        assert!(
+            !State::dir(tmp.path()).join(file).exists(),
+            "{file} is removed if there is no value"
+        );
+    }
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    State::remove(tmp.path())?;
+    assert!(!State::dir(tmp.path()).exists());
+    State::remove(tmp.path())?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use gix_rebase::{apply, status::status, Kind, Status};
+
+use crate::{git_dir, read_to_id};
+
+#[test]
+fn of_rebases_by_git() -> crate::Result {
+    for (name, expected) in [
+        (
+            "interactive-with-break",
+            Status {
+                kind: Kind::Interactive,
+                head_name: Some("refs/heads/topic".into()),
+                step: Some(5),
+                total_steps: Some(7),
+            },
+        ),
+        (
+            "merge-with-conflict",
+            Status {
+                kind: Kind::Interactive,
+                head_name: Some("refs/heads/topic".into()),
+                step: Some(2),
+                total_steps: Some(3),
+            },
+        ),
+        (
+            "detached",
+            Status {
+                kind: Kind::Interactive,
+                head_name: None,
+                step: Some(1),
+                total_steps: Some(4),
+            },
+        ),
+        (
+            "apply-with-conflict",
+            Status {
+                kind: Kind::Apply,
+                head_name: Some("refs/heads/topic".into()),
+                step: Some(2),
+                total_steps: Some(3),
+            },
+        ),
+        (
+            "am-with-conflict",
+            Status {
+                kind: Kind::ApplyMailbox,
+                head_name: None,
+                step: Some(1),
+                total_steps: Some(2),
+            },
+        ),
+    ] {
+        assert_eq!(status(&git_dir(name)?)?, Some(expected), "{name}");
+    }
+    Ok(())
+}
+
+#[test]
+fn none_without_rebase() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    assert_eq!(status(tmp.path())?, None);
+    assert_eq!(apply::State::read(tmp.path())?, None);
+    Ok(())
+}
+
+#[test]
+fn apply_state_by_git() -> crate::Result {
+    let apply_dir = git_dir("apply-with-conflict")?;
+    let state = apply::State::read(&apply_dir)?.expect("rebase in progress");
+    assert_eq!(state.kind, Kind::Apply);
+    assert_eq!(state.onto, Some(read_to_id(&apply_dir, "refs/heads/main")?));
+    assert_eq!(state.orig_head, Some(read_to_id(&apply_dir, "refs/heads/topic")?));
+    assert_eq!((state.next, state.last), (2, 3));
+
+    let state = apply::State::read(&git_dir("am-with-conflict")?)?.expect("am in progress");
+    assert_eq!(state.kind, Kind::ApplyMailbox);
+    assert_eq!((state.onto, state.orig_head), (None, None));
+    Ok(())
+}
@@ -14,6 +14,8 @@
 //!     * one reference maps to a file on disk
 //!   * **packed**
 //!     * references are stored in a single human-readable file, along with their targets if they are symbolic.
+//! * **[reftable](reftable::Store)**
+//!   * references and their logs are stored in a stack of binary tables.
 //!
 //! ## Feature Flags
 #![cfg_attr(
@@ -29,7 +31,7 @@ use gix_object::bstr::{BStr, BString};
 
 #[path = "store/mod.rs"]
 mod store_impl;
-pub use store_impl::{file, packed};
+pub use store_impl::{file, packed, reftable};
 
 mod fullname;
 ///
@@ -70,8 +72,19 @@ pub mod store {
             /// to avoid side effects. This only needs to be `true` on Windows, but can be `true` on other platforms
             /// if they need to remain compatible with Windows.
             pub prohibit_windows_device_names: bool,
+            /// The way references are stored, typically as configured by `extensions.refStorage`.
+            pub format: super::Format,
         }
     }
+    /// The way references are stored on disk.
+    #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
+    pub enum Format {
+        /// Each reference is stored in its own file, with some of them possibly collected in a `packed-refs` file.
+        #[default]
+        Files,
+        /// All references and their logs are stored in a stack of tables in the `reftable/` directory.
+        Reftable,
+    }
     /// The way a file store handles the reflog
     #[derive(Default, Debug, PartialOrd, PartialEq, Ord, Eq, Hash, Clone, Copy)]
     pub enum WriteReflog {
@@ -96,6 +109,7 @@ pub mod store {
     #[allow(dead_code)]
     pub(crate) enum State {
         Loose { store: file::Store },
+        Reftable { store: crate::reftable::Store },
     }
 
     pub(crate) mod general;
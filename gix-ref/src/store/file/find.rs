@@ -165,6 +165,9 @@ impl file::Store {
         let full_name = precomposed_partial_name
             .unwrap_or(partial_name)
             .construct_full_name_ref(inbetween, path_buf, consider_pseudo_ref);
+        if let Some(reftable) = &self.reftable {
+            return Ok(reftable.find_full(full_name, self.namespace.as_ref())?);
+        }
         let content_buf = self.ref_contents(full_name).map_err(|err| Error::ReadFileContents {
             source: err,
             path: self.reference_path(full_name),
@@ -217,38 +220,7 @@ impl file::Store {
         name: &'a FullNameRef,
         is_reflog: bool,
     ) -> (Cow<'_, Path>, &'a FullNameRef) {
-        let commondir = self.common_dir_resolved();
-        let linked_git_dir =
-            |worktree_name: &BStr| commondir.join("worktrees").join(gix_path::from_bstr(worktree_name));
-        name.category_and_short_name()
-            .map(|(c, sn)| {
-                use crate::Category::*;
-                let sn = FullNameRef::new_unchecked(sn);
-                match c {
-                    LinkedPseudoRef { name: worktree_name } => {
-                        if is_reflog {
-                            (linked_git_dir(worktree_name).into(), sn)
-                        } else {
-                            (commondir.into(), name)
-                        }
-                    }
-                    Tag | LocalBranch | RemoteBranch | Note => (commondir.into(), name),
-                    MainRef | MainPseudoRef => (commondir.into(), sn),
-                    LinkedRef { name: worktree_name } => {
-                        if sn.category().is_some_and(|cat| cat.is_worktree_private()) {
-                            if is_reflog {
-                                (linked_git_dir(worktree_name).into(), sn)
-                            } else {
-                                (commondir.into(), name)
-                            }
-                        } else {
-                            (commondir.into(), sn)
-                        }
-                    }
-                    PseudoRef | Bisect | Rewritten | WorktreePrivate => (self.git_dir.as_path().into(), name),
-                }
-            })
-            .unwrap_or((commondir.into(), name))
+        base_dir_and_relative_name(&self.git_dir, self.common_dir_resolved(), name, is_reflog)
     }
 
     /// Implements the logic required to transform a fully qualified refname into a filesystem path
@@ -303,6 +275,46 @@ impl file::Store {
     }
 }
 
+/// Return the directory in which `name` is stored, either `git_dir` for worktree-private references or `commondir` for shared ones,
+/// along with the name of the reference relative to that directory.
+pub(crate) fn base_dir_and_relative_name<'a, 's>(
+    git_dir: &'s Path,
+    commondir: &'s Path,
+    name: &'a FullNameRef,
+    is_reflog: bool,
+) -> (Cow<'s, Path>, &'a FullNameRef) {
+    let linked_git_dir = |worktree_name: &BStr| commondir.join("worktrees").join(gix_path::from_bstr(worktree_name));
+    name.category_and_short_name()
+        .map(|(c, sn)| {
+            use crate::Category::*;
+            let sn = FullNameRef::new_unchecked(sn);
+            match c {
+                LinkedPseudoRef { name: worktree_name } => {
+                    if is_reflog {
+                        (linked_git_dir(worktree_name).into(), sn)
+                    } else {
+                        (commondir.into(), name)
+                    }
+                }
+                Tag | LocalBranch | RemoteBranch | Note => (commondir.into(), name),
+                MainRef | MainPseudoRef => (commondir.into(), sn),
+                LinkedRef { name: worktree_name } => {
+                    if sn.category().is_some_and(|cat| cat.is_worktree_private()) {
+                        if is_reflog {
+                            (linked_git_dir(worktree_name).into(), sn)
+                        } else {
+                            (commondir.into(), name)
+                        }
+                    } else {
+                        (commondir.into(), sn)
+                    }
+                }
+                PseudoRef | Bisect | Rewritten | WorktreePrivate => (git_dir.into(), name),
+            }
+        })
+        .unwrap_or((commondir.into(), name))
+}
+
 ///
 pub mod existing {
     pub use error::Error;
@@ -411,6 +423,8 @@ mod error {
         PackedRef(#[from] packed::find::Error),
         #[error("Could not open the packed refs buffer when trying to find references.")]
         PackedOpen(#[from] packed::buffer::open::Error),
+        #[error(transparent)]
+        Reftable(#[from] crate::store_impl::reftable::store::find::Error),
     }
 
     impl From<Infallible> for Error {
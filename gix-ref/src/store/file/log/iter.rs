@@ -90,7 +90,7 @@ pub struct Platform<'a, 's> {
 
 impl Platform<'_, '_> {
     /// Return a forward iterator over all log-lines, most recent to oldest.
-    pub fn rev(&mut self) -> std::io::Result<Option<log::iter::Reverse<'_, std::fs::File>>> {
+    pub fn rev(&mut self) -> std::io::Result<Option<log::iter::Reverse<'_, Source>>> {
         self.buf.clear();
         self.buf.resize(1024 * 4, 0);
         self.store
@@ -105,6 +105,32 @@ impl Platform<'_, '_> {
     }
 }
 
+/// The data a [`Reverse`] iterator reads log lines from.
+pub enum Source {
+    /// A reflog file.
+    File(std::fs::File),
+    /// Log lines rendered into memory, as is the case for reflogs stored in reftables.
+    Buffer(std::io::Cursor<Vec<u8>>),
+}
+
+impl std::io::Read for Source {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Source::File(f) => f.read(buf),
+            Source::Buffer(b) => b.read(buf),
+        }
+    }
+}
+
+impl std::io::Seek for Source {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        match self {
+            Source::File(f) => f.seek(pos),
+            Source::Buffer(b) => b.seek(pos),
+        }
+    }
+}
+
 /// An iterator yielding parsed lines in a file in reverse, most recent to oldest.
 pub struct Reverse<'a, F> {
     buf: &'a mut [u8],
@@ -43,9 +43,12 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                format,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
+                reftable: (format == crate::store::Format::Reftable)
+                    .then(|| crate::reftable::Store::at(git_dir.clone(), object_hash).into()),
                 git_dir,
                 packed_buffer_mmap_threshold: packed_refs_mmap_threshold(),
                 common_dir: None,
@@ -71,9 +74,13 @@ mod init {
                 object_hash,
                 precompose_unicode,
                 prohibit_windows_device_names,
+                format,
             }: crate::store::init::Options,
         ) -> Self {
             file::Store {
+                reftable: (format == crate::store::Format::Reftable).then(|| {
+                    crate::reftable::Store::for_linked_worktree(git_dir.clone(), common_dir.clone(), object_hash).into()
+                }),
                 git_dir,
                 packed_buffer_mmap_threshold: packed_refs_mmap_threshold(),
                 common_dir: Some(common_dir),
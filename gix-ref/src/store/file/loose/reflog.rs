@@ -16,7 +16,13 @@ impl file::Store {
         Name: TryInto<&'a FullNameRef, Error = E>,
         crate::name::Error: From<E>,
     {
-        Ok(self.reflog_path(name.try_into()?).is_file())
+        let name = name.try_into()?;
+        if let Some(reftable) = &self.reftable {
+            return Ok(reftable
+                .log(name, self.namespace.as_ref())
+                .is_ok_and(|lines| !lines.is_empty()));
+        }
+        Ok(self.reflog_path(name).is_file())
     }
 
     /// Return a reflog reverse iterator for the given fully qualified `name`, reading chunks from the back into the fixed buffer `buf`.
@@ -27,18 +33,28 @@ impl file::Store {
         &self,
         name: Name,
         buf: &'b mut [u8],
-    ) -> Result<Option<log::iter::Reverse<'b, std::fs::File>>, Error>
+    ) -> Result<Option<log::iter::Reverse<'b, log::iter::Source>>, Error>
     where
         Name: TryInto<&'a FullNameRef, Error = E>,
         crate::name::Error: From<E>,
     {
         let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        if let Some(reftable) = &self.reftable {
+            let lines = reftable.log(name, self.namespace.as_ref())?;
+            if lines.is_empty() {
+                return Ok(None);
+            }
+            let mut rendered = Vec::new();
+            crate::reftable::store::write_log_lines(lines.iter().rev(), &mut rendered)?;
+            let source = log::iter::Source::Buffer(std::io::Cursor::new(rendered));
+            return Ok(Some(log::iter::reverse(source, buf)?));
+        }
         let path = self.reflog_path(name);
         if path.is_dir() {
             return Ok(None);
         }
         match std::fs::File::open(&path) {
-            Ok(file) => Ok(Some(log::iter::reverse(file, buf)?)),
+            Ok(file) => Ok(Some(log::iter::reverse(log::iter::Source::File(file), buf)?)),
             Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
             Err(err) => Err(err.into()),
         }
@@ -58,6 +74,15 @@ impl file::Store {
         crate::name::Error: From<E>,
     {
         let name: &FullNameRef = name.try_into().map_err(|err| Error::RefnameValidation(err.into()))?;
+        if let Some(reftable) = &self.reftable {
+            let lines = reftable.log(name, self.namespace.as_ref())?;
+            if lines.is_empty() {
+                return Ok(None);
+            }
+            buf.clear();
+            crate::reftable::store::write_log_lines(lines.iter().rev(), buf)?;
+            return Ok(Some(log::iter::forward(buf)));
+        }
         let path = self.reflog_path(name);
         match std::fs::File::open(&path) {
             Ok(mut file) => {
@@ -172,7 +197,7 @@ pub mod create_or_update {
             }
         }
 
-        fn should_autocreate_reflog(&self, full_name: &Path) -> bool {
+        pub(in crate::store_impl::file) fn should_autocreate_reflog(&self, full_name: &Path) -> bool {
             full_name.starts_with("refs/heads/")
                 || full_name.starts_with("refs/remotes/")
                 || full_name.starts_with("refs/notes/")
@@ -230,6 +255,101 @@ pub mod create_or_update {
     use crate::FullNameRef;
 }
 
+///
+pub mod retain {
+    use std::path::PathBuf;
+
+    use crate::{
+        store_impl::{file, file::log},
+        FullNameRef,
+    };
+
+    impl file::Store {
+        /// Rewrite the reflog of `name` to only contain the entries for which `keep(idx, line)` returns `true`, and return
+        /// the number of removed entries.
+        ///
+        /// `idx` is the position of `line` counting from the most recent entry at `0`, like in `name@{idx}`.
+        /// `lock_fail_mode` determines how to wait for the lock of the reflog, or of the reftable stack holding it.
+        /// Nothing happens if there is no reflog.
+        ///
+        /// Note that the reference itself isn't changed, even if its most recent reflog entry is removed.
+        pub fn reflog_retain(
+            &self,
+            name: &FullNameRef,
+            lock_fail_mode: gix_lock::acquire::Fail,
+            mut keep: impl FnMut(usize, &crate::log::Line) -> bool,
+        ) -> Result<usize, Error> {
+            if let Some(reftable) = &self.reftable {
+                let mut transaction = reftable.transaction(self.namespace.clone());
+                let (stack, stored_name) = transaction.stack_for(name, lock_fail_mode)?;
+                let removed: Vec<_> = stack
+                    .log(stored_name.as_bstr())
+                    .iter()
+                    .enumerate()
+                    .filter(|(idx, record)| {
+                        !keep(
+                            *idx,
+                            record.line.as_ref().expect("deleted entries are never part of a stack"),
+                        )
+                    })
+                    .map(|(_, record)| record.update_index)
+                    .collect();
+                if !removed.is_empty() {
+                    transaction.delete_log_entries(name, &removed);
+                    transaction.commit().map_err(Error::ReftableCommit)?;
+                }
+                return Ok(removed.len());
+            }
+
+            let path = self.reflog_path(name);
+            if !path.is_file() {
+                return Ok(0);
+            }
+            let mut lock = gix_lock::File::acquire_to_update_resource(&path, lock_fail_mode, None)?;
+            let io_err = |source| Error::Io {
+                source,
+                path: path.clone(),
+            };
+            let buf = std::fs::read(&path).map_err(io_err)?;
+            let lines = log::iter::forward(&buf)
+                .map(|line| line.map(|line| line.to_owned()))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut out = Vec::with_capacity(buf.len());
+            let mut num_removed = 0;
+            for (idx, line) in lines.iter().rev().enumerate().rev() {
+                if keep(idx, line) {
+                    line.write_to(&mut out).map_err(io_err)?;
+                } else {
+                    num_removed += 1;
+                }
+            }
+            if num_removed == 0 {
+                return Ok(0);
+            }
+            lock.with_mut(|file| std::io::Write::write_all(file, &out))
+                .map_err(io_err)?;
+            lock.commit().map_err(|err| io_err(err.error))?;
+            Ok(num_removed)
+        }
+    }
+
+    /// The error returned by [`file::Store::reflog_retain()`].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read or write the reflog at {path:?}")]
+        Io { source: std::io::Error, path: PathBuf },
+        #[error(transparent)]
+        LockAcquire(#[from] gix_lock::acquire::Error),
+        #[error(transparent)]
+        Decode(#[from] log::iter::decode::Error),
+        #[error(transparent)]
+        ReftableLock(#[from] file::transaction::prepare::Error),
+        #[error("Could not write the reftable with the updated reflog")]
+        ReftableCommit(#[source] std::io::Error),
+    }
+}
+
 mod error {
     /// The error returned by [`crate::file::Store::reflog_iter()`].
     #[derive(Debug, thiserror::Error)]
@@ -239,6 +359,8 @@ mod error {
         RefnameValidation(#[from] crate::name::Error),
         #[error("The reflog file could not read")]
         Io(#[from] std::io::Error),
+        #[error("The reftable stack holding the reflog could not be loaded")]
+        Reftable(#[from] crate::store_impl::reftable::stack::Error),
     }
 }
 pub use error::Error;
@@ -6,6 +6,9 @@ use crate::{store::WriteReflog, Namespace};
 ///
 /// Each ref is represented as a single file on disk in a folder structure that follows the relative path
 /// used to identify [references][crate::Reference].
+///
+/// If configured with [`Format::Reftable`](crate::store::Format::Reftable), all reads and writes are delegated to a
+/// [reftable store](crate::reftable::Store) instead, while the API remains the same.
 #[derive(Debug, Clone)]
 pub struct Store {
     /// The location at which loose references can be found as per conventions of a typical git repository.
@@ -38,6 +41,8 @@ pub struct Store {
     /// It's updated only in one spot, which is prior to reading it based on file stamps.
     /// Doing it like this has the benefit of being able to hand snapshots out to people without blocking others from updating it.
     packed: packed::modifiable::MutableSharedBuffer,
+    /// The store to delegate to if references are stored in reftables, boxed as this store is meant to be cheap to clone.
+    reftable: Option<Box<crate::reftable::Store>>,
 }
 
 mod access {
@@ -79,6 +84,20 @@ mod access {
             self.common_dir.as_deref().unwrap_or(&self.git_dir)
         }
 
+        /// Return the way references are stored.
+        pub fn format(&self) -> crate::store::Format {
+            if self.reftable.is_some() {
+                crate::store::Format::Reftable
+            } else {
+                crate::store::Format::Files
+            }
+        }
+
+        /// Return the store all operations are delegated to if references are stored in reftables, or `None` if they are stored in files.
+        pub fn reftable(&self) -> Option<&crate::reftable::Store> {
+            self.reftable.as_deref()
+        }
+
         /// Return `Some(true)` if this is a freshly initialized ref store without any observable changes.
         /// Return `None` if `HEAD` couldn't be read.
         ///
@@ -116,6 +135,7 @@ pub struct Transaction<'s, 'p> {
     packed_transaction: Option<crate::store_impl::packed::Transaction>,
     updates: Option<Vec<transaction::Edit>>,
     packed_refs: transaction::PackedRefs<'p>,
+    reftable: Option<crate::reftable::Transaction>,
 }
 
 ///
@@ -26,6 +26,8 @@ pub struct LooseThenPacked<'p, 's> {
     iter_git_dir: Peekable<SortedLoosePaths>,
     #[allow(dead_code)]
     iter_common_dir: Option<Peekable<SortedLoosePaths>>,
+    /// All references, if they are stored in reftables, in which case there are no loose or packed references.
+    iter_reftable: Option<std::vec::IntoIter<Result<Reference, Error>>>,
     buf: Vec<u8>,
 }
 
@@ -181,6 +183,7 @@ impl Iterator for LooseThenPacked<'_, '_> {
                     }
                 },
             },
+            None if self.iter_reftable.is_some() => self.iter_reftable.as_mut().and_then(Iterator::next),
             None => match peek_loose(&mut self.iter_git_dir, self.iter_common_dir.as_mut()) {
                 None => None,
                 Some((_, kind)) => self.loose_iter(kind).next().map(|res| self.convert_loose(res)),
@@ -429,6 +432,11 @@ impl file::Store {
         common_dir_info: Option<IterInfo<'_>>,
         packed: Option<&'p packed::Buffer>,
     ) -> std::io::Result<LooseThenPacked<'p, 's>> {
+        let iter_reftable = self
+            .reftable
+            .as_ref()
+            .map(|reftable| self.reftable_iter(reftable, &git_dir_info))
+            .transpose()?;
         Ok(LooseThenPacked {
             git_dir: self.git_dir(),
             common_dir: self.common_dir(),
@@ -445,12 +453,51 @@ impl file::Store {
             },
             iter_git_dir: git_dir_info.into_iter(),
             iter_common_dir: common_dir_info.map(IterInfo::into_iter),
+            iter_reftable,
             buf: Vec::new(),
             namespace: self.namespace.as_ref(),
         })
     }
 }
 
+impl file::Store {
+    /// Return all references in our reftables that match the filter implied by `info`.
+    fn reftable_iter(
+        &self,
+        reftable: &crate::reftable::Store,
+        info: &IterInfo<'_>,
+    ) -> std::io::Result<std::vec::IntoIter<Result<Reference, Error>>> {
+        let is_pseudo = matches!(info, IterInfo::Pseudo { .. });
+        let prefix = info.prefix();
+        let prefix = prefix.as_deref().unwrap_or("refs/".into());
+        let records = reftable
+            .records(|name| {
+                if is_pseudo {
+                    !name.contains(&b'/') && name.ends_with(b"HEAD")
+                } else {
+                    name.starts_with(prefix)
+                }
+            })
+            .map_err(std::io::Error::other)?;
+        Ok(records
+            .into_iter()
+            .filter_map(|record| match record.to_reference() {
+                Ok(r) => r.map(|mut r| {
+                    if let Some(namespace) = &self.namespace {
+                        r.strip_namespace(namespace);
+                    }
+                    Ok(r)
+                }),
+                Err(err) => Some(Err(Error::ReftableRecord {
+                    source: err,
+                    name: record.name,
+                })),
+            })
+            .collect::<Vec<_>>()
+            .into_iter())
+    }
+}
+
 mod error {
     use std::{io, path::PathBuf};
 
@@ -473,6 +520,8 @@ mod error {
         },
         #[error("Invalid reference in line {line_number}: {invalid_line:?}")]
         PackedReference { invalid_line: BString, line_number: usize },
+        #[error("The reftable record {name:?} could not be turned into a reference")]
+        ReftableRecord { source: crate::name::Error, name: BString },
     }
 }
 pub use error::Error;
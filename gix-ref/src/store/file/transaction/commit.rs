@@ -1,5 +1,8 @@
 use crate::{
-    store_impl::file::{transaction::PackedRefs, Transaction},
+    store_impl::{
+        file::{self, transaction::PackedRefs, Transaction, WriteReflog},
+        reftable,
+    },
     transaction::{Change, LogChange, RefEdit, RefLog},
     Target,
 };
@@ -31,6 +34,9 @@ impl Transaction<'_, '_> {
 
     fn commit_inner(self, committer: Option<gix_actor::SignatureRef<'_>>) -> Result<Vec<RefEdit>, Error> {
         let mut updates = self.updates.expect("BUG: must call prepare before commit");
+        if let Some(reftable) = self.reftable {
+            return commit_reftable(self.store, reftable, updates, committer);
+        }
         let delete_loose_refs = matches!(
             self.packed_refs,
             PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(_)
@@ -48,41 +54,15 @@ impl Transaction<'_, '_> {
                         RefLog::AndReference => (true, true),
                     };
                     if update_reflog {
-                        let log_update = match new {
-                            Target::Symbolic(_) => {
-                                // Special HACK: no reflog for symref changes as there is no OID involved which the reflog needs.
-                                // Unless, the ref is new and we can obtain a peeled id
-                                // identified by the expectation of what could be there, as is the case when cloning.
-                                match expected {
-                                    PreviousValue::ExistingMustMatch(Target::Object(oid)) => {
-                                        Some((Some(gix_hash::ObjectId::null(oid.kind())), oid))
-                                    }
-                                    _ => None,
-                                }
-                            }
-                            Target::Object(new_oid) => {
-                                let previous = match expected {
-                                    // Here, this means that the ref already existed, and that it will receive (even transitively)
-                                    // the given value
-                                    PreviousValue::MustExistAndMatch(Target::Object(oid)) => Some(oid.to_owned()),
-                                    _ => None,
-                                }
-                                .or(change.leaf_referent_previous_oid);
-                                Some((previous, new_oid))
-                            }
-                        };
-                        if let Some((previous, new_oid)) = log_update {
-                            let do_update = previous.as_ref() != Some(new_oid);
-                            if do_update {
-                                self.store.reflog_create_or_append(
-                                    change.update.name.as_ref(),
-                                    previous,
-                                    new_oid,
-                                    committer,
-                                    log.message.as_ref(),
-                                    log.force_create_reflog,
-                                )?;
-                            }
+                        if let Some((previous, new_oid)) = log_update(change, new, expected) {
+                            self.store.reflog_create_or_append(
+                                change.update.name.as_ref(),
+                                previous,
+                                new_oid,
+                                committer,
+                                log.message.as_ref(),
+                                log.force_create_reflog,
+                            )?;
                         }
                     }
                     // Don't do anything else while keeping the lock after potentially updating the reflog.
@@ -176,6 +156,100 @@ impl Transaction<'_, '_> {
         Ok(updates.into_iter().map(|edit| edit.update).collect())
     }
 }
+
+/// Return the previous and the new object id to write into the reflog for an update of `change` to `new`,
+/// or `None` if no reflog entry should be written.
+fn log_update<'a>(
+    change: &file::transaction::Edit,
+    new: &'a Target,
+    expected: &'a PreviousValue,
+) -> Option<(Option<gix_hash::ObjectId>, &'a gix_hash::oid)> {
+    let log_update = match new {
+        Target::Symbolic(_) => {
+            // Special HACK: no reflog for symref changes as there is no OID involved which the reflog needs.
+            // Unless, the ref is new and we can obtain a peeled id
+            // identified by the expectation of what could be there, as is the case when cloning.
+            match expected {
+                PreviousValue::ExistingMustMatch(Target::Object(oid)) => {
+                    Some((Some(gix_hash::ObjectId::null(oid.kind())), oid.as_ref()))
+                }
+                _ => None,
+            }
+        }
+        Target::Object(new_oid) => {
+            let previous = match expected {
+                // Here, this means that the ref already existed, and that it will receive (even transitively)
+                // the given value
+                PreviousValue::MustExistAndMatch(Target::Object(oid)) => Some(oid.to_owned()),
+                _ => None,
+            }
+            .or(change.leaf_referent_previous_oid);
+            Some((previous, new_oid.as_ref()))
+        }
+    };
+    log_update.filter(|(previous, new_oid)| previous.as_deref() != Some(*new_oid))
+}
+
+/// Write all `updates` into a single new table for each affected reftable stack.
+fn commit_reftable(
+    store: &file::Store,
+    mut reftable: reftable::Transaction,
+    updates: Vec<file::transaction::Edit>,
+    committer: Option<gix_actor::SignatureRef<'_>>,
+) -> Result<Vec<RefEdit>, Error> {
+    use file::log::create_or_update::Error as LogError;
+    for change in &updates {
+        assert!(!change.update.deref, "Deref mode is turned into splits and turned off");
+        let name = change.update.name.as_ref();
+        match &change.update.change {
+            Change::Update { log, new, expected } => {
+                if log.mode == RefLog::AndReference {
+                    let value = match new {
+                        Target::Object(id) => reftable::Value::Object(*id),
+                        Target::Symbolic(name) => reftable::Value::Symbolic(name.as_bstr().to_owned()),
+                    };
+                    reftable.set_ref(name, value);
+                }
+                let Some((previous, new_oid)) = log_update(change, new, expected) else {
+                    continue;
+                };
+                let write_log = match store.write_reflog {
+                    WriteReflog::Disable => false,
+                    WriteReflog::Always => true,
+                    WriteReflog::Normal => {
+                        log.force_create_reflog
+                            || store
+                                .should_autocreate_reflog(&gix_path::from_bstr(reftable.stored_name(name).as_bstr()))
+                            || reftable.has_log(name)
+                    }
+                };
+                if !write_log {
+                    continue;
+                }
+                if log.message.contains(&b'\n') {
+                    return Err(LogError::MessageWithNewlines.into());
+                }
+                let committer = committer.ok_or(LogError::MissingCommitter)?;
+                let line = crate::log::Line {
+                    previous_oid: previous.unwrap_or_else(|| new_oid.kind().null()),
+                    new_oid: new_oid.to_owned(),
+                    signature: committer.to_owned()?,
+                    message: log.message.clone(),
+                };
+                reftable.append_log(name, line);
+            }
+            Change::Delete { log: mode, .. } => {
+                if *mode == RefLog::AndReference {
+                    reftable.set_ref(name, reftable::Value::Deletion);
+                }
+                reftable.delete_log(name);
+            }
+        }
+    }
+    reftable.commit().map_err(Error::ReftableCommit)?;
+    Ok(updates.into_iter().map(|edit| edit.update).collect())
+}
+
 mod error {
     use gix_object::bstr::BString;
 
@@ -197,6 +271,10 @@ mod error {
         DeleteReflog { full_name: BString, source: std::io::Error },
         #[error("The reflog could not be created or updated")]
         CreateOrUpdateRefLog(#[from] file::log::create_or_update::Error),
+        #[error("The time of the committer could not be parsed")]
+        CommitterTime(#[from] gix_actor::date::Error),
+        #[error("The new table could not be written to the reftable stack")]
+        ReftableCommit(#[source] std::io::Error),
     }
 }
 pub use error::Error;
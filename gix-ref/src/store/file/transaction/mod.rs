@@ -73,6 +73,7 @@ impl file::Store {
             packed_transaction: None,
             updates: None,
             packed_refs: PackedRefs::default(),
+            reftable: None,
         }
     }
 }
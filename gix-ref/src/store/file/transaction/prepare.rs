@@ -18,6 +18,7 @@ impl Transaction<'_, '_> {
         store: &file::Store,
         lock_fail_mode: gix_lock::acquire::Fail,
         packed: Option<&packed::Buffer>,
+        reftable: Option<&mut crate::reftable::Transaction>,
         change: &mut Edit,
         has_global_lock: bool,
         direct_to_packed_refs: bool,
@@ -28,30 +29,44 @@ impl Transaction<'_, '_> {
             "locks can only be acquired once and it's all or nothing"
         );
 
-        let existing_ref = store
-            .ref_contents(change.update.name.as_ref())
-            .map_err(Error::from)
-            .and_then(|maybe_loose| {
-                maybe_loose
-                    .map(|buf| {
-                        loose::Reference::try_from_path(change.update.name.clone(), &buf)
-                            .map(Reference::from)
-                            .map_err(Error::from)
-                    })
-                    .transpose()
-            })
-            .or_else(|err| match err {
-                Error::ReferenceDecode(_) => Ok(None),
-                other => Err(other),
-            })
-            .and_then(|maybe_loose| match (maybe_loose, packed) {
-                (None, Some(packed)) => packed
-                    .try_find(change.update.name.as_ref())
-                    .map(|opt| opt.map(Into::into))
-                    .map_err(Error::from),
-                (None, None) => Ok(None),
-                (maybe_loose, _) => Ok(maybe_loose),
-            })?;
+        // Reftables are locked as a whole, so individual references never need a lock.
+        let uses_reftable = reftable.is_some();
+        let has_global_lock = has_global_lock || uses_reftable;
+        let existing_ref = if let Some(reftable) = reftable {
+            let (stack, stored_name) = reftable.stack_for(change.update.name.as_ref(), lock_fail_mode)?;
+            stack
+                .find(stored_name.as_bstr())
+                .and_then(|record| record.to_reference().ok().flatten())
+                .map(|mut r| {
+                    r.name = change.update.name.clone();
+                    r
+                })
+        } else {
+            store
+                .ref_contents(change.update.name.as_ref())
+                .map_err(Error::from)
+                .and_then(|maybe_loose| {
+                    maybe_loose
+                        .map(|buf| {
+                            loose::Reference::try_from_path(change.update.name.clone(), &buf)
+                                .map(Reference::from)
+                                .map_err(Error::from)
+                        })
+                        .transpose()
+                })
+                .or_else(|err| match err {
+                    Error::ReferenceDecode(_) => Ok(None),
+                    other => Err(other),
+                })
+                .and_then(|maybe_loose| match (maybe_loose, packed) {
+                    (None, Some(packed)) => packed
+                        .try_find(change.update.name.as_ref())
+                        .map(|opt| opt.map(Into::into))
+                        .map_err(Error::from),
+                    (None, None) => Ok(None),
+                    (maybe_loose, _) => Ok(maybe_loose),
+                })?
+        };
         let lock = match &mut change.update.change {
             Change::Delete { expected, .. } => {
                 let (base, relative_path) = store.reference_path_with_base(change.update.name.as_ref());
@@ -178,7 +193,7 @@ impl Transaction<'_, '_> {
                     (true, matches!(new, Target::Symbolic(_)))
                 };
 
-                if (is_effective && !direct_to_packed_refs) || is_symbolic {
+                if !uses_reftable && ((is_effective && !direct_to_packed_refs) || is_symbolic) {
                     let mut lock = lock.take().map_or_else(obtain_lock, Ok)?;
 
                     lock.with_mut(|file| match new {
@@ -254,7 +269,9 @@ impl Transaction<'_, '_> {
             | PackedRefs::DeletionsAndNonSymbolicUpdatesRemoveLooseSourceReference(_) => Some(0_usize),
             PackedRefs::DeletionsOnly => None,
         };
-        if maybe_updates_for_packed_refs.is_some()
+        if let Some(reftable) = &store.reftable {
+            self.reftable = Some(reftable.transaction(store.namespace.clone()));
+        } else if maybe_updates_for_packed_refs.is_some()
             || self.store.packed_refs_path().is_file()
             || self.store.packed_refs_lock_path().is_file()
         {
@@ -356,6 +373,7 @@ impl Transaction<'_, '_> {
                 self.store,
                 ref_files_lock_fail_mode,
                 self.packed_transaction.as_ref().and_then(packed::Transaction::buffer),
+                self.reftable.as_mut(),
                 change,
                 self.packed_transaction.is_some(),
                 matches!(
@@ -418,6 +436,14 @@ impl Transaction<'_, '_> {
             .map(|updates| updates.into_iter().map(|u| u.update).collect())
             .unwrap_or_default()
     }
+
+    /// Return the edits of this transaction after it was [prepared](Self::prepare()), or nothing if it wasn't.
+    ///
+    /// Like the ones returned by [`rollback()`](Self::rollback()), they have been split and know about the current
+    /// state of their references on disk.
+    pub fn edits(&self) -> impl Iterator<Item = &RefEdit> + '_ {
+        self.updates.iter().flatten().map(|edit| &edit.update)
+    }
 }
 
 fn possibly_adjust_name_for_prefixes(name: &FullNameRef) -> Option<FullName> {
@@ -486,6 +512,8 @@ mod error {
         },
         #[error("Could not read reference")]
         ReferenceDecode(#[from] file::loose::reference::decode::Error),
+        #[error("The reftable stack could not be loaded")]
+        Reftable(#[from] crate::store_impl::reftable::stack::Error),
     }
 }
 
@@ -9,6 +9,8 @@ mod error {
     pub enum Error {
         #[error("An error occurred while finding a reference in the loose file database")]
         Loose(#[from] crate::file::find::Error),
+        #[error("An error occurred while finding a reference in the reftable stack")]
+        Reftable(#[from] crate::reftable::store::find::Error),
         #[error("The ref name or path is not a valid ref name")]
         RefnameValidation(#[from] crate::name::Error),
     }
@@ -31,11 +33,12 @@ impl store::Handle {
         Name: TryInto<&'a PartialNameRef, Error = E>,
         Error: From<E>,
     {
-        let _name = partial.try_into()?;
+        let name = partial.try_into()?;
         match &self.state {
             handle::State::Loose { store: _, .. } => {
                 todo!()
             }
+            handle::State::Reftable { store, namespace } => Ok(store.try_find(name, namespace.as_ref())?),
         }
     }
 }
@@ -3,7 +3,13 @@ use crate::{store, Namespace};
 
 #[derive(Clone)]
 pub(crate) enum State {
-    Loose { store: crate::file::Store },
+    Loose {
+        store: crate::file::Store,
+    },
+    Reftable {
+        store: crate::reftable::Store,
+        namespace: Option<Namespace>,
+    },
 }
 
 impl crate::Store {
@@ -28,6 +34,10 @@ impl crate::Store {
                         store
                     },
                 },
+                store::State::Reftable { store } => store::handle::State::Reftable {
+                    store: store.clone(),
+                    namespace,
+                },
             },
         }
     }
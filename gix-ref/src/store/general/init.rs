@@ -22,11 +22,16 @@ impl crate::Store {
     /// Note that if [`precompose_unicode`](crate::store::init::Options::precompose_unicode) is set in the options,
     /// the `git_dir` is also expected to use precomposed unicode, or else some operations that strip prefixes will fail.
     pub fn at(git_dir: PathBuf, opts: crate::store::init::Options) -> Result<Self, Error> {
-        // for now, just try to read the directory - later we will do that naturally as we have to figure out if it's a ref-table or not.
+        // for now, just try to read the directory. The way references are stored is configured by the caller.
         std::fs::read_dir(&git_dir)?;
         Ok(crate::Store {
-            inner: crate::store::State::Loose {
-                store: file::Store::at(git_dir, opts),
+            inner: match opts.format {
+                crate::store::Format::Files => crate::store::State::Loose {
+                    store: file::Store::at(git_dir, opts),
+                },
+                crate::store::Format::Reftable => crate::store::State::Reftable {
+                    store: crate::reftable::Store::at(git_dir, opts.object_hash),
+                },
             },
         })
     }
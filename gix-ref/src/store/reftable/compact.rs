@@ -0,0 +1,79 @@
+use std::{ops::Range, path::Path};
+
+use crate::store_impl::reftable::{decode::header_len, stack, Table};
+
+/// Like git, keep the sizes of tables in a stack a geometric sequence with this factor, each table at least
+/// this many times larger than the next newer one.
+const GEOMETRIC_FACTOR: u64 = 2;
+
+/// Return the range of tables whose `sizes`, oldest first, have to be merged into one to restore a geometric sequence,
+/// or `None` if the sequence is intact.
+///
+/// This is the algorithm git uses for auto-compaction.
+pub(crate) fn segment(sizes: &[u64]) -> Option<Range<usize>> {
+    if sizes.len() <= 1 {
+        return None;
+    }
+    // Find the newest table that is too large compared to its predecessor. Newer tables than that are valid members of the
+    // sequence already, and the end of the segment is exclusive.
+    let mut idx = sizes.len() - 1;
+    let mut bytes = 0;
+    let mut end = None;
+    while idx > 0 {
+        if sizes[idx - 1] < sizes[idx] * GEOMETRIC_FACTOR {
+            end = Some(idx + 1);
+            bytes = sizes[idx];
+            break;
+        }
+        idx -= 1;
+    }
+    let end = end?;
+    // Tables are merged backwards, so each older table is compared to the accumulated size of all tables up to the end
+    // of the segment. Keep going after the first match as even older tables may violate the sequence, too.
+    let mut start = None;
+    while idx > 0 {
+        let current = bytes;
+        bytes += sizes[idx - 1];
+        if sizes[idx - 1] < current * GEOMETRIC_FACTOR {
+            start = Some(idx - 1);
+        }
+        idx -= 1;
+    }
+    start.map(|start| start..end)
+}
+
+/// Return the sizes of all `tables` in `dir` as used for finding a compaction [`segment()`].
+pub(crate) fn table_sizes(dir: &Path, tables: &[String], object_hash: gix_hash::Kind) -> std::io::Result<Vec<u64>> {
+    let version = if object_hash == gix_hash::Kind::Sha1 { 1 } else { 2 };
+    let overhead = header_len(version) as u64 - 1;
+    tables
+        .iter()
+        .map(|name| Ok(std::fs::metadata(dir.join(name))?.len().saturating_sub(overhead)))
+        .collect()
+}
+
+/// Merge the given `tables` in `dir`, oldest first, into a single new table.
+///
+/// Deletions are only dropped if `is_bottom_of_stack` is `true`, as they might shadow records of older tables otherwise.
+pub(crate) fn merge(
+    dir: &Path,
+    tables: &[String],
+    is_bottom_of_stack: bool,
+    object_hash: gix_hash::Kind,
+) -> Result<Table, stack::Error> {
+    let tables = tables
+        .iter()
+        .map(|name| stack::read_table(dir, name))
+        .collect::<Result<Vec<_>, _>>()?;
+    let min_update_index = tables.first().map_or(0, |t| t.min_update_index);
+    let max_update_index = tables.iter().map(|t| t.max_update_index).max().unwrap_or(0);
+    let keep_deletions = !is_bottom_of_stack;
+    let (refs, logs) = stack::merge(tables, keep_deletions);
+    Ok(Table {
+        object_hash,
+        min_update_index,
+        max_update_index,
+        refs,
+        logs,
+    })
+}
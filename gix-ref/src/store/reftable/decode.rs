@@ -0,0 +1,329 @@
+use gix_hash::ObjectId;
+use gix_object::bstr::BString;
+
+use crate::{
+    log,
+    store_impl::reftable::{varint, LogRecord, Record, Table, Value},
+};
+
+/// The error returned by [`Table::from_bytes()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The table is too short or truncated")]
+    Truncated,
+    #[error("The table did not start with the 'REFT' signature")]
+    Signature,
+    #[error("Table version {version} is not supported")]
+    UnsupportedVersion { version: u8 },
+    #[error("The hash function {id:?} is not supported")]
+    UnsupportedHash { id: BString },
+    #[error("The footer checksum did not match")]
+    Checksum,
+    #[error("Encountered block of unknown type {kind:?} at offset {offset}")]
+    UnknownBlock { kind: char, offset: usize },
+    #[error("The block at offset {offset} is malformed")]
+    MalformedBlock { offset: usize },
+    #[error("A log block could not be decompressed")]
+    Decompress(#[from] gix_features::zlib::DecompressError),
+    #[error("Unknown value type {value_type} in record {name:?}")]
+    UnknownValueType { value_type: u8, name: BString },
+}
+
+const HEADER_V1_LEN: usize = 24;
+const HEADER_V2_LEN: usize = 28;
+
+pub(crate) const BLOCK_REF: u8 = b'r';
+pub(crate) const BLOCK_LOG: u8 = b'g';
+pub(crate) const BLOCK_OBJ: u8 = b'o';
+pub(crate) const BLOCK_INDEX: u8 = b'i';
+
+/// The length of the footer for a table of the given `version`.
+pub(crate) fn footer_len(version: u8) -> usize {
+    header_len(version) + 5 * 8 + 4
+}
+
+pub(crate) fn header_len(version: u8) -> usize {
+    if version == 1 {
+        HEADER_V1_LEN
+    } else {
+        HEADER_V2_LEN
+    }
+}
+
+struct Header {
+    version: u8,
+    block_size: usize,
+    min_update_index: u64,
+    max_update_index: u64,
+    object_hash: gix_hash::Kind,
+}
+
+impl Table {
+    /// Decode all references and reflog entries from `data`, the entire contents of a table file.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, Error> {
+        let header = parse_header(data)?;
+        let footer_len = footer_len(header.version);
+        let footer_start = data.len().checked_sub(footer_len).ok_or(Error::Truncated)?;
+        let header_len = header_len(header.version);
+        if footer_start < header_len {
+            return Err(Error::Truncated);
+        }
+        let footer = &data[footer_start..];
+        let (footer_data, crc) = footer.split_at(footer_len - 4);
+        if gix_features::hash::crc32(footer_data) != u32::from_be_bytes(crc.try_into().expect("4 bytes")) {
+            return Err(Error::Checksum);
+        }
+        if footer_data[..header_len] != data[..header_len] {
+            return Err(Error::Signature);
+        }
+
+        let mut table = Table {
+            object_hash: header.object_hash,
+            min_update_index: header.min_update_index,
+            max_update_index: header.max_update_index,
+            refs: Vec::new(),
+            logs: Vec::new(),
+        };
+        let mut pos = header_len;
+        while pos < footer_start {
+            // The first block is considered to start at the beginning of the file as its length includes the file header.
+            let block_start = if pos == header_len { 0 } else { pos };
+            let kind = data[pos];
+            let block_len = read_u24(&data[pos + 1..footer_start]).ok_or(Error::Truncated)?;
+            let block_end = block_start
+                .checked_add(block_len)
+                .filter(|end| *end > pos + 4)
+                .ok_or(Error::MalformedBlock { offset: pos })?;
+            let full_block_len = match kind {
+                BLOCK_LOG => {
+                    let mut uncompressed = vec![0; block_end - (pos + 4)];
+                    let consumed = inflate(&data[pos + 4..footer_start], &mut uncompressed)?;
+                    let records = records(&uncompressed).ok_or(Error::MalformedBlock { offset: pos })?;
+                    decode_logs(records, header.object_hash, &mut table.logs, pos)?;
+                    pos + 4 + consumed - block_start
+                }
+                BLOCK_REF | BLOCK_OBJ | BLOCK_INDEX => {
+                    if block_end > footer_start {
+                        return Err(Error::MalformedBlock { offset: pos });
+                    }
+                    if kind == BLOCK_REF {
+                        let records =
+                            records(&data[pos + 4..block_end]).ok_or(Error::MalformedBlock { offset: pos })?;
+                        decode_refs(records, &header, &mut table.refs, pos)?;
+                    }
+                    // Padded blocks are followed by zeroes and fill the whole block.
+                    if header.block_size != 0 && block_end < footer_start && data[block_end] == 0 {
+                        header.block_size
+                    } else {
+                        block_len
+                    }
+                }
+                _ => {
+                    return Err(Error::UnknownBlock {
+                        kind: kind as char,
+                        offset: pos,
+                    })
+                }
+            };
+            pos = block_start + full_block_len;
+        }
+        Ok(table)
+    }
+}
+
+fn parse_header(data: &[u8]) -> Result<Header, Error> {
+    if data.len() < HEADER_V1_LEN {
+        return Err(Error::Truncated);
+    }
+    if &data[..4] != b"REFT" {
+        return Err(Error::Signature);
+    }
+    let version = data[4];
+    let object_hash = match version {
+        1 => gix_hash::Kind::Sha1,
+        2 => {
+            let id = data.get(24..28).ok_or(Error::Truncated)?;
+            match id {
+                b"sha1" => gix_hash::Kind::Sha1,
+                b"s256" => gix_hash::Kind::from_hex_len(64).ok_or_else(|| Error::UnsupportedHash { id: id.into() })?,
+                _ => return Err(Error::UnsupportedHash { id: id.into() }),
+            }
+        }
+        _ => return Err(Error::UnsupportedVersion { version }),
+    };
+    Ok(Header {
+        version,
+        block_size: read_u24(&data[5..]).expect("enough bytes"),
+        min_update_index: read_u64(&data[8..]).expect("enough bytes"),
+        max_update_index: read_u64(&data[16..]).expect("enough bytes"),
+        object_hash,
+    })
+}
+
+/// Decompress the zlib stream at the beginning of `input` into `out`, which must have the size of the uncompressed data,
+/// and return the amount of consumed bytes.
+fn inflate(input: &[u8], out: &mut [u8]) -> Result<usize, Error> {
+    let mut inflate = gix_features::zlib::Decompress::new();
+    let status = inflate.decompress(input, out, gix_features::zlib::FlushDecompress::Finish)?;
+    if status != gix_features::zlib::Status::StreamEnd || inflate.total_out() as usize != out.len() {
+        return Err(Error::Decompress(gix_features::zlib::DecompressError::DataError));
+    }
+    Ok(inflate.total_in() as usize)
+}
+
+/// Return the portion of `block`, the block data past its 4 byte header, that contains records, which means the
+/// restart table at its end is removed.
+fn records(block: &[u8]) -> Option<&[u8]> {
+    let count_pos = block.len().checked_sub(2)?;
+    let restart_count = usize::from(u16::from_be_bytes(block[count_pos..].try_into().ok()?));
+    block.get(..count_pos.checked_sub(restart_count * 3)?)
+}
+
+/// Decode a record key and return it along with the value type and the remaining data.
+fn next_key<'a>(mut data: &'a [u8], last_key: &mut Vec<u8>) -> Option<(u8, &'a [u8])> {
+    let prefix_len;
+    (prefix_len, data) = varint::decode(data)?;
+    let suffix_len_and_type;
+    (suffix_len_and_type, data) = varint::decode(data)?;
+    let suffix_len = usize::try_from(suffix_len_and_type >> 3).ok()?;
+    let value_type = (suffix_len_and_type & 0x7) as u8;
+    let prefix_len = usize::try_from(prefix_len).ok()?;
+    if prefix_len > last_key.len() || suffix_len > data.len() {
+        return None;
+    }
+    last_key.truncate(prefix_len);
+    last_key.extend_from_slice(&data[..suffix_len]);
+    Some((value_type, &data[suffix_len..]))
+}
+
+fn decode_refs(mut data: &[u8], header: &Header, out: &mut Vec<Record>, offset: usize) -> Result<(), Error> {
+    let malformed = || Error::MalformedBlock { offset };
+    let hash_len = header.object_hash.len_in_bytes();
+    let mut name = Vec::new();
+    while !data.is_empty() {
+        let value_type;
+        (value_type, data) = next_key(data, &mut name).ok_or_else(malformed)?;
+        let update_index_delta;
+        (update_index_delta, data) = varint::decode(data).ok_or_else(malformed)?;
+        let take_id = |data: &mut &[u8]| -> Result<ObjectId, Error> {
+            let id = data.get(..hash_len).ok_or_else(malformed)?;
+            *data = &data[hash_len..];
+            Ok(ObjectId::from_bytes_or_panic(id))
+        };
+        let value = match value_type {
+            0 => Value::Deletion,
+            1 => Value::Object(take_id(&mut data)?),
+            2 => Value::Peeled {
+                target: take_id(&mut data)?,
+                peeled: take_id(&mut data)?,
+            },
+            3 => {
+                let len;
+                (len, data) = varint::decode(data).ok_or_else(malformed)?;
+                let len = usize::try_from(len).map_err(|_| malformed())?;
+                let target = data.get(..len).ok_or_else(malformed)?;
+                data = &data[len..];
+                Value::Symbolic(target.into())
+            }
+            _ => {
+                return Err(Error::UnknownValueType {
+                    value_type,
+                    name: name.into(),
+                })
+            }
+        };
+        out.push(Record {
+            name: name.as_slice().into(),
+            update_index: header.min_update_index.saturating_add(update_index_delta),
+            value,
+        });
+    }
+    Ok(())
+}
+
+fn decode_logs(
+    mut data: &[u8],
+    object_hash: gix_hash::Kind,
+    out: &mut Vec<LogRecord>,
+    offset: usize,
+) -> Result<(), Error> {
+    let malformed = || Error::MalformedBlock { offset };
+    let hash_len = object_hash.len_in_bytes();
+    let mut key = Vec::new();
+    while !data.is_empty() {
+        let value_type;
+        (value_type, data) = next_key(data, &mut key).ok_or_else(malformed)?;
+        let name_len = key
+            .len()
+            .checked_sub(9)
+            .filter(|len| key[*len] == 0)
+            .ok_or_else(malformed)?;
+        let name: BString = key[..name_len].into();
+        let update_index = u64::MAX - read_u64(&key[name_len + 1..]).expect("8 bytes");
+        let line = match value_type {
+            0 => None,
+            1 => {
+                let mut take = |len: usize| -> Result<&[u8], Error> {
+                    let bytes = data.get(..len).ok_or_else(malformed)?;
+                    data = &data[len..];
+                    Ok(bytes)
+                };
+                let previous_oid = ObjectId::from_bytes_or_panic(take(hash_len)?);
+                let new_oid = ObjectId::from_bytes_or_panic(take(hash_len)?);
+                let take_string = |data: &mut &[u8]| -> Result<BString, Error> {
+                    let (len, rest) = varint::decode(data).ok_or_else(malformed)?;
+                    let len = usize::try_from(len).map_err(|_| malformed())?;
+                    let bytes = rest.get(..len).ok_or_else(malformed)?;
+                    *data = &rest[len..];
+                    Ok(bytes.into())
+                };
+                let signature_name = take_string(&mut data)?;
+                let email = take_string(&mut data)?;
+                let seconds;
+                (seconds, data) = varint::decode(data).ok_or_else(malformed)?;
+                let offset_minutes = data.get(..2).ok_or_else(malformed)?;
+                let offset_minutes = i16::from_be_bytes(offset_minutes.try_into().expect("2 bytes"));
+                data = &data[2..];
+                let mut message = take_string(&mut data)?;
+                if message.ends_with(b"\n") {
+                    message.pop();
+                }
+                Some(log::Line {
+                    previous_oid,
+                    new_oid,
+                    signature: gix_actor::Signature {
+                        name: signature_name,
+                        email,
+                        time: gix_actor::date::Time {
+                            seconds: seconds as gix_actor::date::SecondsSinceUnixEpoch,
+                            offset: i32::from(offset_minutes) * 60,
+                        },
+                    },
+                    message,
+                })
+            }
+            _ => {
+                return Err(Error::UnknownValueType {
+                    value_type,
+                    name: name.clone(),
+                })
+            }
+        };
+        out.push(LogRecord {
+            name,
+            update_index,
+            line,
+        });
+    }
+    Ok(())
+}
+
+fn read_u24(data: &[u8]) -> Option<usize> {
+    let bytes = data.get(..3)?;
+    Some(usize::from(bytes[0]) << 16 | usize::from(bytes[1]) << 8 | usize::from(bytes[2]))
+}
+
+fn read_u64(data: &[u8]) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(..8)?.try_into().ok()?))
+}
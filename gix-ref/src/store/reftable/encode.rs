@@ -0,0 +1,195 @@
+use std::io::Write;
+
+use crate::store_impl::reftable::{
+    decode::{header_len, BLOCK_LOG, BLOCK_REF},
+    varint, Table, Value,
+};
+
+/// Restart points allow readers to binary-search within a block, and are placed every this many records.
+const RESTART_INTERVAL: usize = 16;
+
+impl Table {
+    /// Encode this table and write it to `out`, with blocks of at most `block_size` bytes unless a single record is larger.
+    ///
+    /// `refs` must be sorted by name, and `logs` by name and by `update_index` with the most recent entry first.
+    /// Note that only reference and log blocks are written, without the optional object and index blocks, and
+    /// blocks aren't padded.
+    pub fn write_to(&self, out: &mut dyn Write, block_size: u32) -> std::io::Result<()> {
+        let block_size = block_size.clamp(256, 0xff_ffff) as usize;
+        let version = if self.object_hash == gix_hash::Kind::Sha1 { 1 } else { 2 };
+        let mut buf = Vec::new();
+        buf.extend_from_slice(b"REFT");
+        buf.push(version);
+        buf.extend_from_slice(&(block_size as u32).to_be_bytes()[1..]);
+        buf.extend_from_slice(&self.min_update_index.to_be_bytes());
+        buf.extend_from_slice(&self.max_update_index.to_be_bytes());
+        if version == 2 {
+            buf.extend_from_slice(b"s256");
+        }
+        let header = buf.clone();
+        let first_block_pos = header_len(version);
+
+        let mut value = Vec::new();
+        let mut block = BlockWriter::new(BLOCK_REF, buf.len(), first_block_pos, block_size);
+        for record in &self.refs {
+            value.clear();
+            varint::encode(record.update_index - self.min_update_index, &mut value);
+            let value_type = match &record.value {
+                Value::Deletion => 0,
+                Value::Object(id) => {
+                    value.extend_from_slice(id.as_bytes());
+                    1
+                }
+                Value::Peeled { target, peeled } => {
+                    value.extend_from_slice(target.as_bytes());
+                    value.extend_from_slice(peeled.as_bytes());
+                    2
+                }
+                Value::Symbolic(target) => {
+                    varint::encode(target.len() as u64, &mut value);
+                    value.extend_from_slice(target);
+                    3
+                }
+            };
+            block = block.add(&record.name, value_type, &value, &mut buf)?;
+        }
+        block.finish(&mut buf)?;
+
+        let log_pos = if self.logs.is_empty() { 0 } else { buf.len() };
+        let mut block = BlockWriter::new(BLOCK_LOG, buf.len(), first_block_pos, block_size);
+        let mut key = Vec::new();
+        for record in &self.logs {
+            key.clear();
+            key.extend_from_slice(&record.name);
+            key.push(0);
+            key.extend_from_slice(&(u64::MAX - record.update_index).to_be_bytes());
+            value.clear();
+            let value_type = match &record.line {
+                None => 0,
+                Some(line) => {
+                    value.extend_from_slice(line.previous_oid.as_bytes());
+                    value.extend_from_slice(line.new_oid.as_bytes());
+                    let mut push_string = |s: &[u8]| {
+                        varint::encode(s.len() as u64, &mut value);
+                        value.extend_from_slice(s);
+                    };
+                    push_string(&line.signature.name);
+                    push_string(&line.signature.email);
+                    varint::encode(line.signature.time.seconds.max(0) as u64, &mut value);
+                    value.extend_from_slice(&((line.signature.time.offset / 60) as i16).to_be_bytes());
+                    varint::encode(line.message.len() as u64 + 1, &mut value);
+                    value.extend_from_slice(&line.message);
+                    value.push(b'\n');
+                    1
+                }
+            };
+            block = block.add(&key, value_type, &value, &mut buf)?;
+        }
+        block.finish(&mut buf)?;
+
+        let footer_start = buf.len();
+        buf.extend_from_slice(&header);
+        for pos in [0u64, 0, 0, log_pos as u64, 0] {
+            buf.extend_from_slice(&pos.to_be_bytes());
+        }
+        let crc = gix_features::hash::crc32(&buf[footer_start..]);
+        buf.extend_from_slice(&crc.to_be_bytes());
+        out.write_all(&buf)
+    }
+}
+
+/// Accumulates records of one block until it is full.
+struct BlockWriter {
+    kind: u8,
+    /// The position of the block in the file.
+    pos: usize,
+    /// The offset that the block length and restart offsets are relative to, which includes the file header for the first block.
+    base: usize,
+    block_size: usize,
+    first_block_pos: usize,
+    records: Vec<u8>,
+    restarts: Vec<usize>,
+    last_key: Vec<u8>,
+    count: usize,
+}
+
+impl BlockWriter {
+    fn new(kind: u8, pos: usize, first_block_pos: usize, block_size: usize) -> Self {
+        BlockWriter {
+            kind,
+            pos,
+            base: if pos == first_block_pos { 0 } else { pos },
+            block_size,
+            first_block_pos,
+            records: Vec::new(),
+            restarts: Vec::new(),
+            last_key: Vec::new(),
+            count: 0,
+        }
+    }
+
+    fn block_len(&self, restarts: usize, records: usize) -> usize {
+        self.pos - self.base + 4 + records + restarts * 3 + 2
+    }
+
+    /// Add the record with `key`, `value_type` and `value`, and return the block to add the next record to, which is a
+    /// new one if this one was full and written to `out`.
+    fn add(mut self, key: &[u8], value_type: u8, value: &[u8], out: &mut Vec<u8>) -> std::io::Result<Self> {
+        let is_restart = self.count % RESTART_INTERVAL == 0;
+        let prefix_len = if is_restart {
+            0
+        } else {
+            key.iter().zip(&self.last_key).take_while(|(a, b)| a == b).count()
+        };
+        let mut record = Vec::with_capacity(key.len() + value.len() + 4);
+        varint::encode(prefix_len as u64, &mut record);
+        varint::encode(
+            (((key.len() - prefix_len) as u64) << 3) | u64::from(value_type),
+            &mut record,
+        );
+        record.extend_from_slice(&key[prefix_len..]);
+        record.extend_from_slice(value);
+
+        let new_len = self.block_len(
+            self.restarts.len() + usize::from(is_restart),
+            self.records.len() + record.len(),
+        );
+        if self.count != 0 && new_len > self.block_size {
+            self.finish(out)?;
+            let next = BlockWriter::new(self.kind, out.len(), self.first_block_pos, self.block_size);
+            return next.add(key, value_type, value, out);
+        }
+        if is_restart {
+            self.restarts.push(self.pos - self.base + 4 + self.records.len());
+        }
+        self.records.extend_from_slice(&record);
+        self.last_key.clear();
+        self.last_key.extend_from_slice(key);
+        self.count += 1;
+        Ok(self)
+    }
+
+    /// Write the block to `out` unless it is empty.
+    fn finish(&self, out: &mut Vec<u8>) -> std::io::Result<()> {
+        if self.count == 0 {
+            return Ok(());
+        }
+        let block_len = self.block_len(self.restarts.len(), self.records.len());
+        out.push(self.kind);
+        out.extend_from_slice(&(block_len as u32).to_be_bytes()[1..]);
+        let mut data = Vec::with_capacity(block_len);
+        data.extend_from_slice(&self.records);
+        for restart in &self.restarts {
+            data.extend_from_slice(&(*restart as u32).to_be_bytes()[1..]);
+        }
+        data.extend_from_slice(&(self.restarts.len() as u16).to_be_bytes());
+        if self.kind == BLOCK_LOG {
+            let mut compress = gix_features::zlib::stream::deflate::Write::new(out);
+            compress.write_all(&data)?;
+            compress.flush()?;
+        } else {
+            out.extend_from_slice(&data);
+        }
+        Ok(())
+    }
+}
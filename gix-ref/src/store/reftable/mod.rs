@@ -0,0 +1,137 @@
+//! Support for the [reftable format](https://git-scm.com/docs/reftable), which stores references and their logs
+//! in a stack of binary tables.
+//!
+//! Each table is written once and never changed. Newer tables in the stack shadow the values of older ones,
+//! which is how updates and deletions are performed without rewriting existing tables.
+use std::path::PathBuf;
+
+use gix_hash::ObjectId;
+use gix_object::bstr::BString;
+
+use crate::{log, Reference, Target};
+
+/// The value a reference [`Record`] stores.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub enum Value {
+    /// The reference was deleted, shadowing the value of older tables.
+    Deletion,
+    /// The reference points to an object.
+    Object(ObjectId),
+    /// The reference points to an annotated tag, along with the object the tag ultimately points to.
+    Peeled {
+        /// The object the reference points to.
+        target: ObjectId,
+        /// The object that `target` peels to.
+        peeled: ObjectId,
+    },
+    /// The reference points to another reference by its full name.
+    Symbolic(BString),
+}
+
+/// A reference as stored in a table.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct Record {
+    /// The full name of the reference.
+    pub name: BString,
+    /// The logical timestamp of the transaction that wrote this record.
+    pub update_index: u64,
+    /// The value of the reference.
+    pub value: Value,
+}
+
+/// A reflog entry as stored in a table.
+#[derive(PartialEq, Eq, Debug, Hash, Ord, PartialOrd, Clone)]
+pub struct LogRecord {
+    /// The full name of the reference the entry belongs to.
+    pub name: BString,
+    /// The logical timestamp of the transaction that wrote this entry, which also identifies it.
+    pub update_index: u64,
+    /// The reflog entry, or `None` if the entry with this `update_index` was deleted.
+    ///
+    /// Note that the message is stored without trailing newline.
+    pub line: Option<log::Line>,
+}
+
+/// A single, fully decoded table.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Table {
+    /// The kind of hash used for all object ids in the table.
+    pub object_hash: gix_hash::Kind,
+    /// The smallest `update_index` of all records in the table.
+    pub min_update_index: u64,
+    /// The largest `update_index` of all records in the table.
+    pub max_update_index: u64,
+    /// All reference records, sorted by name.
+    pub refs: Vec<Record>,
+    /// All reflog records, sorted by name and by `update_index`, most recent first.
+    pub logs: Vec<LogRecord>,
+}
+
+/// A merged view of all tables of a stack, as listed in its `tables.list` file.
+///
+/// Deletions are applied, so only references and reflog entries that currently exist remain.
+#[derive(Debug, Clone)]
+pub struct Stack {
+    /// The contents of `tables.list` at the time the stack was loaded.
+    list: BString,
+    /// The names of all tables, oldest first.
+    tables: Vec<String>,
+    /// The largest `update_index` of all tables.
+    max_update_index: u64,
+    /// All references, sorted by name.
+    refs: Vec<Record>,
+    /// All reflog entries, sorted by name and by `update_index`, most recent first.
+    logs: Vec<LogRecord>,
+}
+
+/// A store for references and their logs which keeps them in reftable stacks.
+///
+/// Like the [files store](crate::file::Store), it handles linked worktrees, whose private references like `HEAD` are kept
+/// in a stack in their own `git_dir`, while all other references are kept in the stack of the common directory.
+#[derive(Debug, Clone)]
+pub struct Store {
+    /// The `.git` directory, which is the private directory of a linked worktree if `common_dir` is set.
+    git_dir: PathBuf,
+    /// The common directory holding shared references, only set for linked worktrees.
+    common_dir: Option<PathBuf>,
+    /// The kind of hash used for all object ids in new tables.
+    object_hash: gix_hash::Kind,
+    /// The cached stacks of `git_dir` and `common_dir`.
+    stacks: store::SharedStacks,
+}
+
+impl Record {
+    /// Convert this record into a reference, or return `None` if it is a [deletion](Value::Deletion).
+    pub fn to_reference(&self) -> Result<Option<Reference>, crate::name::Error> {
+        let (target, peeled) = match &self.value {
+            Value::Deletion => return Ok(None),
+            Value::Object(id) => (Target::Object(*id), None),
+            Value::Peeled { target, peeled } => (Target::Object(*target), Some(*peeled)),
+            Value::Symbolic(name) => (Target::Symbolic(name.try_into()?), None),
+        };
+        Ok(Some(Reference {
+            name: (&self.name).try_into()?,
+            target,
+            peeled,
+        }))
+    }
+}
+
+mod varint;
+
+mod compact;
+
+///
+pub mod decode;
+
+mod encode;
+
+///
+pub mod stack;
+
+///
+pub mod store;
+pub use store::SharedStackSnapshot;
+
+mod transaction;
+pub(crate) use transaction::Transaction;
@@ -0,0 +1,152 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use gix_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::store_impl::reftable::{decode, LogRecord, Record, Stack, Table, Value};
+
+/// The error returned by [`Stack::at()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not read {path:?}")]
+    Io { source: std::io::Error, path: PathBuf },
+    #[error("Could not decode table at {path:?}")]
+    Decode { source: decode::Error, path: PathBuf },
+}
+
+/// How often to retry loading the stack if tables disappear while reading them due to concurrent compaction.
+const MAX_ATTEMPTS: usize = 10;
+
+impl Stack {
+    /// Load all tables listed in `tables.list` in `dir`, typically `.git/reftable`, and merge them.
+    ///
+    /// If `tables.list` doesn't exist, the stack is empty.
+    pub fn at(dir: &Path) -> Result<Self, Error> {
+        let mut attempt = 0;
+        loop {
+            let list = read_list(dir)?;
+            match Self::from_list(dir, list) {
+                Err(Error::Io { source, .. })
+                    if source.kind() == std::io::ErrorKind::NotFound && attempt < MAX_ATTEMPTS =>
+                {
+                    attempt += 1;
+                }
+                res => return res,
+            }
+        }
+    }
+
+    /// Load the stack described by `list`, the contents of its `tables.list` file, if it differs from the one of `self`,
+    /// or return `None` to indicate that `self` is up to date.
+    pub(crate) fn reload_if_changed(&self, dir: &Path) -> Result<Option<Self>, Error> {
+        if read_list(dir)? == self.list {
+            return Ok(None);
+        }
+        Self::at(dir).map(Some)
+    }
+
+    fn from_list(dir: &Path, list: BString) -> Result<Self, Error> {
+        let tables: Vec<String> = list
+            .lines()
+            .filter(|line| !line.is_empty())
+            .map(|line| line.to_str_lossy().into_owned())
+            .collect();
+        let mut max_update_index = 0;
+        let mut loaded = Vec::with_capacity(tables.len());
+        for name in &tables {
+            let table = read_table(dir, name)?;
+            max_update_index = max_update_index.max(table.max_update_index);
+            loaded.push(table);
+        }
+        let keep_deletions = false;
+        let (refs, logs) = merge(loaded, keep_deletions);
+        Ok(Stack {
+            list,
+            tables,
+            max_update_index,
+            refs,
+            logs,
+        })
+    }
+}
+
+/// Read and decode the table with the given `name` in `dir`.
+pub(crate) fn read_table(dir: &Path, name: &str) -> Result<Table, Error> {
+    let path = dir.join(name);
+    let data = std::fs::read(&path).map_err(|source| Error::Io {
+        source,
+        path: path.clone(),
+    })?;
+    Table::from_bytes(&data).map_err(|source| Error::Decode { source, path })
+}
+
+/// Merge all references and reflog entries of `tables`, oldest first, so that newer records shadow older ones, and return them
+/// sorted like in a [`Table`].
+///
+/// If `keep_deletions` is `true`, deletions are retained so they can still shadow records in tables older than `tables`.
+pub(crate) fn merge(tables: impl IntoIterator<Item = Table>, keep_deletions: bool) -> (Vec<Record>, Vec<LogRecord>) {
+    let mut refs = BTreeMap::<BString, Record>::new();
+    let mut logs = BTreeMap::<(BString, Reverse<u64>), LogRecord>::new();
+    for table in tables {
+        for record in table.refs {
+            refs.insert(record.name.clone(), record);
+        }
+        for record in table.logs {
+            logs.insert((record.name.clone(), Reverse(record.update_index)), record);
+        }
+    }
+    (
+        refs.into_values()
+            .filter(|r| keep_deletions || r.value != Value::Deletion)
+            .collect(),
+        logs.into_values()
+            .filter(|r| keep_deletions || r.line.is_some())
+            .collect(),
+    )
+}
+
+fn read_list(dir: &Path) -> Result<BString, Error> {
+    let path = dir.join("tables.list");
+    match std::fs::read(&path) {
+        Ok(list) => Ok(list.into()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(BString::default()),
+        Err(source) => Err(Error::Io { source, path }),
+    }
+}
+
+/// Access
+impl Stack {
+    /// Return the names of all tables in the stack, oldest first.
+    pub fn tables(&self) -> &[String] {
+        &self.tables
+    }
+
+    /// Return the largest `update_index` of all tables, or 0 if the stack is empty.
+    pub fn max_update_index(&self) -> u64 {
+        self.max_update_index
+    }
+
+    /// Return all references, sorted by name.
+    pub fn refs(&self) -> &[Record] {
+        &self.refs
+    }
+
+    /// Return the reference with the given full `name`, if it exists.
+    pub fn find(&self, name: &BStr) -> Option<&Record> {
+        self.refs
+            .binary_search_by(|r| r.name.as_bstr().cmp(name))
+            .ok()
+            .map(|idx| &self.refs[idx])
+    }
+
+    /// Return all reflog entries of the reference with the given full `name`, most recent first.
+    pub fn log(&self, name: &BStr) -> &[LogRecord] {
+        let start = self.logs.partition_point(|r| r.name.as_bstr() < name);
+        let end = start + self.logs[start..].partition_point(|r| r.name.as_bstr() == name);
+        &self.logs[start..end]
+    }
+}
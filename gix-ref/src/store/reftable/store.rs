@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use gix_features::threading::{get_mut, get_ref, MutableOnDemand, OwnShared};
+use gix_object::bstr::{BStr, BString, ByteSlice};
+
+use crate::{
+    log,
+    name::is_pseudo_ref,
+    store_impl::{file, reftable},
+    FullName, FullNameRef, Namespace, PartialNameRef, Reference,
+};
+
+type SharedStack = OwnShared<MutableOnDemand<Option<SharedStackSnapshot>>>;
+
+/// An up-to-date snapshot of a reftable stack, shared with the store that produced it.
+pub type SharedStackSnapshot = OwnShared<reftable::Stack>;
+
+/// The cached reftable stacks of the git directory and, for linked worktrees, the common directory.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SharedStacks {
+    git_dir: SharedStack,
+    common_dir: SharedStack,
+}
+
+///
+pub mod find {
+    /// The error returned by [`Store::try_find()`](crate::reftable::Store::try_find()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("The ref name is not a valid ref name")]
+        RefnameValidation(#[from] crate::name::Error),
+        #[error("Could not load the reftable stack when trying to find references")]
+        Stack(#[from] crate::reftable::stack::Error),
+    }
+
+    impl From<std::convert::Infallible> for Error {
+        fn from(_: std::convert::Infallible) -> Self {
+            unreachable!("this impl is needed to allow passing a known valid partial path as parameter")
+        }
+    }
+}
+
+/// Initialization
+impl reftable::Store {
+    /// Create a new instance for the repository at `git_dir`, whose stack is expected in its `reftable/` directory.
+    /// `object_hash` is the kind of hash used when writing new tables.
+    pub fn at(git_dir: PathBuf, object_hash: gix_hash::Kind) -> Self {
+        reftable::Store {
+            git_dir,
+            common_dir: None,
+            object_hash,
+            stacks: Default::default(),
+        }
+    }
+
+    /// Like [`at()`](Self::at()), but for _linked_ work-trees which keep their private references in the stack of `git_dir`
+    /// and all shared references in the stack of `common_dir`.
+    pub fn for_linked_worktree(git_dir: PathBuf, common_dir: PathBuf, object_hash: gix_hash::Kind) -> Self {
+        reftable::Store {
+            git_dir,
+            common_dir: Some(common_dir),
+            object_hash,
+            stacks: Default::default(),
+        }
+    }
+}
+
+/// Access
+impl reftable::Store {
+    /// Return the `.git` directory, which holds the private references of a linked worktree if
+    /// [`common_dir()`](Self::common_dir()) is set.
+    pub fn git_dir(&self) -> &Path {
+        &self.git_dir
+    }
+
+    /// If this is a linked work tree, return the directory of the parent repository which holds all shared references.
+    pub fn common_dir(&self) -> Option<&Path> {
+        self.common_dir.as_deref()
+    }
+
+    /// Return the [common directory](Self::common_dir()), or the git directory if there is none.
+    pub fn common_dir_resolved(&self) -> &Path {
+        self.common_dir.as_deref().unwrap_or(&self.git_dir)
+    }
+
+    /// Return the kind of hash used for object ids in new tables.
+    pub fn object_hash(&self) -> gix_hash::Kind {
+        self.object_hash
+    }
+
+    /// Return an up-to-date snapshot of the stack holding all shared references.
+    ///
+    /// Note that references private to a linked worktree, like its `HEAD`, are stored in a separate stack.
+    pub fn stack(&self) -> Result<SharedStackSnapshot, reftable::stack::Error> {
+        self.stack_at(self.common_dir_resolved())
+    }
+
+    /// Return an up-to-date snapshot of the stack in the `reftable` directory in `base`.
+    pub(crate) fn stack_at(&self, base: &Path) -> Result<SharedStackSnapshot, reftable::stack::Error> {
+        let dir = base.join("reftable");
+        let shared = if base == self.common_dir_resolved() {
+            &self.stacks.common_dir
+        } else if base == self.git_dir {
+            &self.stacks.git_dir
+        } else {
+            return reftable::Stack::at(&dir).map(OwnShared::new);
+        };
+        let cached = get_ref(shared).clone();
+        let stack = match cached {
+            Some(cached) => match cached.reload_if_changed(&dir)? {
+                Some(stack) => stack,
+                None => return Ok(cached),
+            },
+            None => reftable::Stack::at(&dir)?,
+        };
+        let stack = OwnShared::new(stack);
+        *get_mut(shared) = Some(stack.clone());
+        Ok(stack)
+    }
+
+    /// Return the directory whose `reftable` stack stores `name` within `namespace`, along with the name under which it is stored there.
+    pub(crate) fn location(&self, name: &FullNameRef, namespace: Option<&Namespace>) -> (PathBuf, FullName) {
+        let is_reflog = true;
+        let (base, relative_name) =
+            file::find::base_dir_and_relative_name(&self.git_dir, self.common_dir_resolved(), name, is_reflog);
+        let stored_name = match namespace {
+            Some(namespace) => namespace.to_owned().into_namespaced_name(relative_name),
+            None => relative_name.to_owned(),
+        };
+        (base.into_owned(), stored_name)
+    }
+}
+
+/// Finding references
+impl reftable::Store {
+    /// Find a single reference by the given `partial` name within `namespace`, using the same lookup rules as
+    /// [`file::Store::try_find()`], or return `None` if it doesn't exist.
+    pub fn try_find<'a, Name, E>(
+        &self,
+        partial: Name,
+        namespace: Option<&Namespace>,
+    ) -> Result<Option<Reference>, find::Error>
+    where
+        Name: TryInto<&'a PartialNameRef, Error = E>,
+        find::Error: From<E>,
+    {
+        let partial_name = partial.try_into()?;
+        let mut buf = BString::default();
+        for consider_pseudo_ref in [true, false] {
+            if !consider_pseudo_ref && !is_pseudo_ref(partial_name.as_bstr()) {
+                break;
+            }
+            for inbetween in &["", "tags", "heads", "remotes"] {
+                let full_name = partial_name.construct_full_name_ref(inbetween, &mut buf, consider_pseudo_ref);
+                if let Some(r) = self.find_full(full_name, namespace)? {
+                    return Ok(Some(r));
+                }
+                if consider_pseudo_ref && is_pseudo_ref(partial_name.as_bstr()) {
+                    break;
+                }
+            }
+        }
+        if partial_name.as_bstr() == "HEAD" {
+            return Ok(None);
+        }
+        let remote_head = partial_name.to_owned().join("HEAD".into()).expect("HEAD is valid name");
+        let full_name = remote_head
+            .as_ref()
+            .construct_full_name_ref("remotes", &mut buf, true /* consider-pseudo-ref */);
+        self.find_full(full_name, namespace)
+    }
+
+    /// Find the reference with the given full `name` within `namespace` in the stack that stores it.
+    pub(crate) fn find_full(
+        &self,
+        name: &FullNameRef,
+        namespace: Option<&Namespace>,
+    ) -> Result<Option<Reference>, find::Error> {
+        let (base, stored_name) = self.location(name, namespace);
+        let stack = self.stack_at(&base)?;
+        let Some(record) = stack.find(stored_name.as_bstr()) else {
+            return Ok(None);
+        };
+        Ok(record.to_reference()?.map(|mut r| {
+            if let Some(namespace) = namespace {
+                r.strip_namespace(namespace);
+            }
+            r.name = name.to_owned();
+            r
+        }))
+    }
+
+    /// Return all records of references visible from our worktree whose name passes `filter`, sorted by name.
+    pub(crate) fn records(
+        &self,
+        mut filter: impl FnMut(&BStr) -> bool,
+    ) -> Result<Vec<reftable::Record>, reftable::stack::Error> {
+        let shared = self.stack()?;
+        let mut records: Vec<_> = match self.common_dir() {
+            None => shared
+                .refs()
+                .iter()
+                .filter(|r| filter(r.name.as_bstr()))
+                .cloned()
+                .collect(),
+            Some(_) => {
+                let is_private = |name: &BStr| {
+                    FullNameRef::new_unchecked(name)
+                        .category()
+                        .is_some_and(|c| c.is_worktree_private())
+                };
+                let private = self.stack_at(&self.git_dir)?;
+                shared
+                    .refs()
+                    .iter()
+                    .filter(|r| !is_private(r.name.as_bstr()))
+                    .chain(private.refs().iter().filter(|r| is_private(r.name.as_bstr())))
+                    .filter(|r| filter(r.name.as_bstr()))
+                    .cloned()
+                    .collect()
+            }
+        };
+        records.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(records)
+    }
+
+    /// Return the reflog entries of the reference with the given full `name` within `namespace`, most recent first.
+    pub fn log(
+        &self,
+        name: &FullNameRef,
+        namespace: Option<&Namespace>,
+    ) -> Result<Vec<log::Line>, reftable::stack::Error> {
+        let (base, stored_name) = self.location(name, namespace);
+        let stack = self.stack_at(&base)?;
+        Ok(stack
+            .log(stored_name.as_bstr())
+            .iter()
+            .filter_map(|r| r.line.clone())
+            .collect())
+    }
+
+    /// Return a new transaction to change references within `namespace`, which locks the stacks it writes to.
+    pub(crate) fn transaction(&self, namespace: Option<Namespace>) -> reftable::Transaction {
+        reftable::Transaction::new(self.clone(), namespace)
+    }
+}
+
+/// Write `lines` in the format of reflog files to `out`.
+pub(crate) fn write_log_lines<'a>(
+    lines: impl IntoIterator<Item = &'a log::Line>,
+    out: &mut Vec<u8>,
+) -> std::io::Result<()> {
+    lines.into_iter().try_for_each(|line| line.write_to(out))
+}
@@ -0,0 +1,230 @@
+use std::path::{Path, PathBuf};
+
+use gix_object::bstr::BString;
+
+use crate::{
+    log,
+    store_impl::{file, reftable},
+    FullName, FullNameRef, Namespace,
+};
+
+/// The state of a transaction on reftables, with one locked stack for each directory that references are written to.
+pub(crate) struct Transaction {
+    store: reftable::Store,
+    namespace: Option<Namespace>,
+    stacks: Vec<LockedStack>,
+}
+
+struct LockedStack {
+    dir: PathBuf,
+    lock: gix_lock::File,
+    stack: reftable::Stack,
+    refs: Vec<reftable::Record>,
+    logs: Vec<reftable::LogRecord>,
+}
+
+impl Transaction {
+    pub(crate) fn new(store: reftable::Store, namespace: Option<Namespace>) -> Self {
+        Transaction {
+            store,
+            namespace,
+            stacks: Vec::new(),
+        }
+    }
+
+    /// Return the name under which `name` is stored in its stack.
+    pub(crate) fn stored_name(&self, name: &FullNameRef) -> FullName {
+        self.store.location(name, self.namespace.as_ref()).1
+    }
+
+    /// Lock the stack that stores `name` unless it is locked already, and return its state as of when the lock was obtained,
+    /// along with the name under which `name` is stored.
+    pub(crate) fn stack_for(
+        &mut self,
+        name: &FullNameRef,
+        fail_mode: gix_lock::acquire::Fail,
+    ) -> Result<(&reftable::Stack, FullName), file::transaction::prepare::Error> {
+        let (base, stored_name) = self.store.location(name, self.namespace.as_ref());
+        let dir = base.join("reftable");
+        let idx = match self.stacks.iter().position(|s| s.dir == dir) {
+            Some(idx) => idx,
+            None => {
+                let lock = gix_lock::File::acquire_to_update_resource(dir.join("tables.list"), fail_mode, Some(base))
+                    .map_err(|err| file::transaction::prepare::Error::LockAcquire {
+                    source: err,
+                    full_name: name.as_bstr().into(),
+                })?;
+                let stack = reftable::Stack::at(&dir)?;
+                self.stacks.push(LockedStack {
+                    dir,
+                    lock,
+                    stack,
+                    refs: Vec::new(),
+                    logs: Vec::new(),
+                });
+                self.stacks.len() - 1
+            }
+        };
+        Ok((&self.stacks[idx].stack, stored_name))
+    }
+
+    fn locked_stack(&mut self, name: &FullNameRef) -> (&mut LockedStack, FullName) {
+        let (base, stored_name) = self.store.location(name, self.namespace.as_ref());
+        let dir = base.join("reftable");
+        let stack = self
+            .stacks
+            .iter_mut()
+            .find(|s| s.dir == dir)
+            .expect("BUG: all stacks are locked when preparing the transaction");
+        (stack, stored_name)
+    }
+
+    /// Record that `name` should be set to `value` when committing.
+    pub(crate) fn set_ref(&mut self, name: &FullNameRef, value: reftable::Value) {
+        let (stack, stored_name) = self.locked_stack(name);
+        let update_index = stack.stack.max_update_index() + 1;
+        stack.refs.retain(|r| r.name != stored_name.as_bstr());
+        stack.refs.push(reftable::Record {
+            name: stored_name.into(),
+            update_index,
+            value,
+        });
+    }
+
+    /// Return `true` if a reflog entry for `name` should be written in a repository configured to write reflogs normally.
+    pub(crate) fn has_log(&mut self, name: &FullNameRef) -> bool {
+        let (stack, stored_name) = self.locked_stack(name);
+        !stack.stack.log(stored_name.as_bstr()).is_empty()
+    }
+
+    /// Record a new reflog entry for `name`, to be written when committing.
+    pub(crate) fn append_log(&mut self, name: &FullNameRef, line: log::Line) {
+        let (stack, stored_name) = self.locked_stack(name);
+        let update_index = stack.stack.max_update_index() + 1;
+        stack.logs.push(reftable::LogRecord {
+            name: stored_name.into(),
+            update_index,
+            line: Some(line),
+        });
+    }
+
+    /// Record that all reflog entries of `name` should be deleted when committing.
+    pub(crate) fn delete_log(&mut self, name: &FullNameRef) {
+        let (stack, stored_name) = self.locked_stack(name);
+        let deletions: Vec<_> = stack
+            .stack
+            .log(stored_name.as_bstr())
+            .iter()
+            .map(|r| reftable::LogRecord {
+                name: r.name.clone(),
+                update_index: r.update_index,
+                line: None,
+            })
+            .collect();
+        stack.logs.extend(deletions);
+    }
+
+    /// Record that the reflog entries of `name` with the given `update_indices` should be deleted when committing.
+    pub(crate) fn delete_log_entries(&mut self, name: &FullNameRef, update_indices: &[u64]) {
+        let (stack, stored_name) = self.locked_stack(name);
+        stack
+            .logs
+            .extend(update_indices.iter().map(|update_index| reftable::LogRecord {
+                name: stored_name.as_bstr().to_owned(),
+                update_index: *update_index,
+                line: None,
+            }));
+    }
+
+    /// Write one new table for each stack with changes and add it to the stack.
+    pub(crate) fn commit(self) -> std::io::Result<()> {
+        let object_hash = self.store.object_hash();
+        for LockedStack {
+            dir,
+            mut lock,
+            stack,
+            mut refs,
+            mut logs,
+        } in self.stacks
+        {
+            if refs.is_empty() && logs.is_empty() {
+                continue;
+            }
+            let update_index = stack.max_update_index() + 1;
+            refs.sort_by(|a, b| a.name.cmp(&b.name));
+            logs.sort_by(|a, b| a.name.cmp(&b.name).then(b.update_index.cmp(&a.update_index)));
+            logs.dedup_by(|a, b| a.name == b.name && a.update_index == b.update_index);
+            // Log tombstones keep the `update_index` of the entry they delete, but like git we don't let them widen the range
+            // of the table as tables in a stack must not overlap.
+            let table = reftable::Table {
+                object_hash,
+                min_update_index: update_index,
+                max_update_index: update_index,
+                refs,
+                logs,
+            };
+
+            let table_name = write_table(&dir, &table)?;
+            let mut tables: Vec<_> = stack.tables().iter().cloned().chain(Some(table_name)).collect();
+            let obsolete = auto_compact(&dir, &mut tables, object_hash)?;
+
+            let mut list = BString::default();
+            for name in &tables {
+                list.extend_from_slice(name.as_bytes());
+                list.push(b'\n');
+            }
+            lock.with_mut(|file| std::io::Write::write_all(file, &list))?;
+            lock.commit().map_err(|err| err.error)?;
+            for name in obsolete {
+                // Readers that still see the old list retry once the table is gone, and on some platforms tables can't be
+                // removed while they are read, which leaves them for the next compaction to clean up.
+                std::fs::remove_file(dir.join(name)).ok();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Write `table` to a new file in `dir` and return its name.
+fn write_table(dir: &Path, table: &reftable::Table) -> std::io::Result<String> {
+    let table_name = format!(
+        "0x{:012x}-0x{:012x}-{:08x}.ref",
+        table.min_update_index,
+        table.max_update_index,
+        random_suffix()
+    );
+    let mut table_lock =
+        gix_lock::File::acquire_to_update_resource(dir.join(&table_name), gix_lock::acquire::Fail::Immediately, None)
+            .map_err(std::io::Error::other)?;
+    table_lock.with_mut(|file| table.write_to(file, BLOCK_SIZE))?;
+    table_lock.commit().map_err(|err| err.error)?;
+    Ok(table_name)
+}
+
+/// Merge tables in `tables` like git does to keep their sizes a geometric sequence, so the stack doesn't grow
+/// with each transaction, and return the names of the tables that were replaced.
+fn auto_compact(dir: &Path, tables: &mut Vec<String>, object_hash: gix_hash::Kind) -> std::io::Result<Vec<String>> {
+    let sizes = reftable::compact::table_sizes(dir, tables, object_hash)?;
+    let Some(segment) = reftable::compact::segment(&sizes) else {
+        return Ok(Vec::new());
+    };
+    let is_bottom_of_stack = segment.start == 0;
+    let table = reftable::compact::merge(dir, &tables[segment.clone()], is_bottom_of_stack, object_hash)
+        .map_err(std::io::Error::other)?;
+    let table_name = write_table(dir, &table)?;
+    Ok(tables.splice(segment, Some(table_name)).collect())
+}
+
+/// The block size git uses by default.
+const BLOCK_SIZE: u32 = 4096;
+
+fn random_suffix() -> u32 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos()),
+    );
+    hasher.finish() as u32
+}
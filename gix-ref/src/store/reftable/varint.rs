@@ -0,0 +1,50 @@
+/// Decode a variable-length integer from the front of `data` and return it along with the remaining bytes.
+///
+/// The encoding is the one also used for offsets in packs, where each continuation adds one to the value so
+/// that each number has exactly one representation.
+pub(crate) fn decode(data: &[u8]) -> Option<(u64, &[u8])> {
+    let (&first, mut data) = data.split_first()?;
+    let mut value = u64::from(first & 0x7f);
+    let mut byte = first;
+    while byte & 0x80 != 0 {
+        (byte, data) = data.split_first().map(|(b, rest)| (*b, rest))?;
+        value = value.checked_add(1)?.checked_mul(128)? | u64::from(byte & 0x7f);
+    }
+    Some((value, data))
+}
+
+/// Append the encoding of `value` to `out`.
+pub(crate) fn encode(mut value: u64, out: &mut Vec<u8>) {
+    let mut buf = [0u8; 10];
+    let mut pos = buf.len() - 1;
+    buf[pos] = (value & 0x7f) as u8;
+    value >>= 7;
+    while value != 0 {
+        value -= 1;
+        pos -= 1;
+        buf[pos] = 0x80 | (value & 0x7f) as u8;
+        value >>= 7;
+    }
+    out.extend_from_slice(&buf[pos..]);
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn round_trip() {
+        for value in [0, 1, 127, 128, 155, 16_511, 16_512, 946_771_200, u64::MAX] {
+            let mut buf = Vec::new();
+            super::encode(value, &mut buf);
+            assert_eq!(super::decode(&buf), Some((value, &[][..])), "{value}");
+        }
+    }
+
+    #[test]
+    fn known_encodings() {
+        assert_eq!(super::decode(&[0x80, 0x1b]), Some((155, &[][..])));
+        assert_eq!(
+            super::decode(&[0x82, 0xc2, 0xb9, 0xa9, 0x00, 0xff]),
+            Some((946_771_200, &[0xff][..]))
+        );
+    }
+}
@@ -67,3 +67,48 @@ mod iter_rev {
         Ok(())
     }
 }
+
+mod retain {
+    use gix_lock::acquire::Fail;
+
+    #[test]
+    fn removes_entries_by_position_from_the_most_recent_one() -> crate::Result {
+        let dir = gix_testtools::scripted_fixture_writable_standalone("make_repo_for_reflog.sh")?;
+        let store = crate::file::Store::at(dir.path().join(".git"), Default::default());
+        let messages = |store: &crate::file::Store| -> crate::Result<Vec<String>> {
+            let mut buf = Vec::new();
+            Ok(store
+                .reflog_iter("refs/heads/main", &mut buf)?
+                .expect("exists")
+                .map(|line| line.map(|line| line.message.to_string()))
+                .collect::<Result<_, _>>()?)
+        };
+        let mut expected = messages(&store)?;
+        assert_eq!(expected.len(), 5);
+
+        let name = "refs/heads/main".try_into()?;
+        assert_eq!(store.reflog_retain(name, Fail::Immediately, |_, _| true)?, 0);
+        assert_eq!(messages(&store)?, expected, "nothing changes if everything is kept");
+
+        let mut seen = Vec::new();
+        let removed = store.reflog_retain(name, Fail::Immediately, |idx, line| {
+            seen.push((idx, line.message.to_string()));
+            idx != 1
+        })?;
+        assert_eq!(removed, 1);
+        assert_eq!(
+            seen.first().map(|(idx, _)| *idx),
+            Some(4),
+            "entries are presented oldest first, but numbered from the most recent one"
+        );
+        expected.remove(3);
+        assert_eq!(messages(&store)?, expected);
+
+        assert_eq!(
+            store.reflog_retain("refs/heads/does-not-exist".try_into()?, Fail::Immediately, |_, _| false)?,
+            0,
+            "missing reflogs are ignored"
+        );
+        Ok(())
+    }
+}
@@ -37,6 +37,14 @@ fn intermediate_directories_are_removed_on_rollback() -> crate::Result {
             Fail::Immediately,
             Fail::Immediately,
         )?;
+        assert_eq!(
+            transaction
+                .edits()
+                .map(|edit| edit.name.as_bstr().to_owned())
+                .collect::<Vec<_>>(),
+            ["refs/heads/a/b/ref", "refs/heads/a/c/ref"],
+            "prepared edits can be observed"
+        );
 
         assert!(
             dir.path().join("refs/heads/a/b").exists(),
@@ -0,0 +1,488 @@
+use gix_date::parse::TimeBuf;
+use gix_lock::acquire::Fail;
+use gix_ref::{
+    file,
+    reftable::{Stack, Table, Value},
+    store::{init, Format, WriteReflog},
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+use crate::{
+    file::transaction::prepare_and_commit::{committer, create_at},
+    hex_to_id,
+};
+
+const MAIN: &str = "3189cd3cb0af8586c39a838aa3e54fd72a872a41";
+
+fn options() -> init::Options {
+    init::Options {
+        format: Format::Reftable,
+        write_reflog: WriteReflog::Normal,
+        ..Default::default()
+    }
+}
+
+fn git_dir() -> crate::Result<std::path::PathBuf> {
+    Ok(gix_testtools::scripted_fixture_read_only_standalone("make_reftable_repo.sh")?.join("reftable-clone/.git"))
+}
+
+fn store() -> crate::Result<file::Store> {
+    Ok(file::Store::at(git_dir()?, options()))
+}
+
+fn store_writable() -> crate::Result<(gix_testtools::tempfile::TempDir, file::Store)> {
+    let dir = gix_testtools::scripted_fixture_writable_standalone("make_reftable_repo.sh")?;
+    let store = file::Store::at(dir.path().join("reftable-clone/.git"), options());
+    Ok((dir, store))
+}
+
+fn ref_names(store: &file::Store) -> crate::Result<Vec<String>> {
+    Ok(store
+        .iter()?
+        .all()?
+        .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+        .collect::<Result<_, _>>()?)
+}
+
+fn reflog_messages(store: &file::Store, name: &str) -> crate::Result<Vec<String>> {
+    let mut buf = Vec::new();
+    Ok(match store.reflog_iter(name, &mut buf)? {
+        Some(iter) => iter
+            .map(|line| line.map(|line| line.message.to_string()))
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    })
+}
+
+mod table {
+    use super::*;
+
+    #[test]
+    fn decode_and_round_trip() -> crate::Result {
+        let stack_dir = git_dir()?.join("reftable");
+        let stack = Stack::at(&stack_dir)?;
+        assert_eq!(stack.tables().len(), 1, "a fresh clone writes a single table");
+        let data = std::fs::read(stack_dir.join(&stack.tables()[0]))?;
+        let table = Table::from_bytes(&data)?;
+        assert_eq!(table.object_hash, gix_hash::Kind::Sha1);
+        assert_eq!((table.min_update_index, table.max_update_index), (1, 4));
+        assert_eq!(
+            table
+                .refs
+                .iter()
+                .map(|r| (r.name.to_string(), r.value.clone()))
+                .collect::<Vec<_>>(),
+            [
+                ("HEAD".into(), Value::Symbolic("refs/heads/main".into())),
+                ("refs/heads/main".into(), Value::Object(hex_to_id(MAIN))),
+                (
+                    "refs/remotes/origin/HEAD".into(),
+                    Value::Symbolic("refs/remotes/origin/main".into())
+                ),
+                ("refs/remotes/origin/main".into(), Value::Object(hex_to_id(MAIN))),
+            ]
+        );
+        let line = table.logs[0].line.as_ref().expect("not a deletion");
+        assert_eq!(line.signature.name, "committer");
+        assert!(
+            line.message.starts_with(b"clone: from "),
+            "the trailing newline is removed"
+        );
+
+        for block_size in [256, 4096] {
+            let mut buf = Vec::new();
+            table.write_to(&mut buf, block_size)?;
+            assert_eq!(
+                Table::from_bytes(&buf)?,
+                table,
+                "what we write can be read back without loss"
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn many_records_span_multiple_blocks() -> crate::Result {
+        let table = Table {
+            object_hash: gix_hash::Kind::Sha1,
+            min_update_index: 1,
+            max_update_index: 1,
+            refs: (0..500)
+                .map(|idx| gix_ref::reftable::Record {
+                    name: format!("refs/heads/branch-{idx:04}").into(),
+                    update_index: 1,
+                    value: Value::Object(hex_to_id(MAIN)),
+                })
+                .collect(),
+            logs: Vec::new(),
+        };
+        let mut buf = Vec::new();
+        table.write_to(&mut buf, 256)?;
+        assert_eq!(Table::from_bytes(&buf)?, table);
+        Ok(())
+    }
+
+    #[test]
+    fn corruption_is_detected() -> crate::Result {
+        let stack_dir = git_dir()?.join("reftable");
+        let stack = Stack::at(&stack_dir)?;
+        let mut data = std::fs::read(stack_dir.join(&stack.tables()[0]))?;
+        let last = data.len() - 1;
+        data[last] ^= 0xff;
+        assert!(matches!(
+            Table::from_bytes(&data),
+            Err(gix_ref::reftable::decode::Error::Checksum)
+        ));
+        assert!(Table::from_bytes(b"REFT").is_err());
+        Ok(())
+    }
+}
+
+mod store {
+    use super::*;
+
+    #[test]
+    fn standalone_lookup_and_log() -> crate::Result {
+        let store = gix_ref::reftable::Store::at(git_dir()?, gix_hash::Kind::Sha1);
+        let main = store.try_find("main", None)?.expect("present");
+        assert_eq!(main.name.as_bstr(), "refs/heads/main");
+        assert_eq!(main.target, Target::Object(hex_to_id(MAIN)));
+        assert_eq!(
+            store.try_find("origin", None)?.expect("present").name.as_bstr(),
+            "refs/remotes/origin/HEAD"
+        );
+        assert!(store.try_find("does-not-exist", None)?.is_none());
+        assert_eq!(store.log("refs/heads/main".try_into()?, None)?.len(), 1);
+        assert_eq!(store.stack()?.tables().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn find_iter_and_reflog() -> crate::Result {
+        let store = store()?;
+        assert_eq!(store.format(), Format::Reftable);
+        assert!(store.reftable().is_some(), "operations are delegated to a reftable store");
+
+        let head = store.find("HEAD")?;
+        assert_eq!(head.target, Target::Symbolic("refs/heads/main".try_into()?));
+        let main = store.find("main")?;
+        assert_eq!(main.name.as_bstr(), "refs/heads/main");
+        assert_eq!(main.target, Target::Object(hex_to_id(MAIN)));
+        assert_eq!(
+            store.find("origin")?.name.as_bstr(),
+            "refs/remotes/origin/HEAD",
+            "lookup rules are the same as for loose references"
+        );
+        assert!(store.try_find("does-not-exist")?.is_none());
+
+        assert_eq!(
+            ref_names(&store)?,
+            [
+                "refs/heads/main",
+                "refs/remotes/origin/HEAD",
+                "refs/remotes/origin/main"
+            ]
+        );
+        let remote_refs = store
+            .iter()?
+            .prefixed(b"refs/remotes/".try_into()?)?
+            .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(remote_refs, ["refs/remotes/origin/HEAD", "refs/remotes/origin/main"]);
+        let pseudo_refs = store
+            .iter()?
+            .pseudo()?
+            .map(|r| r.map(|r| r.name.as_bstr().to_string()))
+            .collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(pseudo_refs, ["HEAD"]);
+
+        assert!(store.reflog_exists("refs/heads/main")?);
+        assert!(!store.reflog_exists("refs/tags/does-not-exist")?);
+        assert_eq!(reflog_messages(&store, "HEAD")?.len(), 1);
+        let mut buf = [0u8; 1024];
+        let mut rev = store.reflog_iter_rev("refs/heads/main", &mut buf)?.expect("log exists");
+        let line = rev.next().expect("one entry")?;
+        assert_eq!(line.new_oid, hex_to_id(MAIN));
+        assert_eq!(line.previous_oid, gix_hash::Kind::Sha1.null());
+        assert!(rev.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_add_a_table_to_the_stack() -> crate::Result {
+        let (dir, store) = store_writable()?;
+        let edits = store
+            .transaction()
+            .prepare(
+                [
+                    create_at("refs/heads/new"),
+                    RefEdit {
+                        change: Change::Update {
+                            log: LogChange {
+                                message: "advance".into(),
+                                ..Default::default()
+                            },
+                            expected: PreviousValue::MustExistAndMatch(Target::Object(hex_to_id(MAIN))),
+                            new: Target::Object(hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391")),
+                        },
+                        name: "HEAD".try_into()?,
+                        deref: true,
+                    },
+                    RefEdit {
+                        change: Change::Delete {
+                            expected: PreviousValue::MustExist,
+                            log: RefLog::AndReference,
+                        },
+                        name: "refs/remotes/origin/main".try_into()?,
+                        deref: false,
+                    },
+                ],
+                Fail::Immediately,
+                Fail::Immediately,
+            )?
+            .commit(committer().to_ref(&mut TimeBuf::default()))?;
+        assert_eq!(edits.len(), 4, "HEAD is split into an edit for itself and its referent");
+
+        let stack = Stack::at(&dir.path().join("reftable-clone/.git/reftable"))?;
+        assert_eq!(
+            stack.tables().len(),
+            1,
+            "the new table isn't small enough compared to the one of the clone, so both are compacted into one"
+        );
+        assert_eq!(stack.max_update_index(), 5);
+
+        for store in [store.clone(), file::Store::at(store.git_dir().to_owned(), options())] {
+            assert_eq!(
+                ref_names(&store)?,
+                ["refs/heads/main", "refs/heads/new", "refs/remotes/origin/HEAD"],
+                "the deletion shadows the reference in the older table"
+            );
+            assert_eq!(
+                store.find("main")?.target,
+                Target::Object(hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391")),
+                "HEAD was dereferenced"
+            );
+            assert_eq!(
+                reflog_messages(&store, "refs/heads/main")?.last().map(String::as_str),
+                Some("advance")
+            );
+            assert_eq!(reflog_messages(&store, "HEAD")?.len(), 2);
+            assert_eq!(reflog_messages(&store, "refs/heads/new")?, ["log peeled"]);
+            assert!(
+                !store.reflog_exists("refs/remotes/origin/main")?,
+                "the log was deleted along with the reference"
+            );
+        }
+
+        let err = store
+            .transaction()
+            .prepare(
+                Some(create_at("refs/remotes/origin/HEAD")),
+                Fail::Immediately,
+                Fail::Immediately,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(err, file::transaction::prepare::Error::MustNotExist { .. }),
+            "constraints are checked against the stack: {err:?}"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn reflog_entries_can_be_removed() -> crate::Result {
+        let (dir, store) = store_writable()?;
+        let mut expected = reflog_messages(&store, "HEAD")?;
+        store
+            .transaction()
+            .prepare(
+                [RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            message: "advance".into(),
+                            ..Default::default()
+                        },
+                        expected: PreviousValue::MustExistAndMatch(Target::Object(hex_to_id(MAIN))),
+                        new: Target::Object(hex_to_id("e69de29bb2d1d6434b8b29ae775ad8c2e48c5391")),
+                    },
+                    name: "HEAD".try_into()?,
+                    deref: true,
+                }],
+                Fail::Immediately,
+                Fail::Immediately,
+            )?
+            .commit(committer().to_ref(&mut TimeBuf::default()))?;
+        assert_eq!(
+            reflog_messages(&store, "HEAD")?.last().map(String::as_str),
+            Some("advance")
+        );
+
+        let removed = store.reflog_retain("HEAD".try_into()?, Fail::Immediately, |idx, line| {
+            assert_eq!(idx == 0, line.message == "advance", "the most recent entry is first");
+            idx != 0
+        })?;
+        assert_eq!(removed, 1);
+        for store in [store.clone(), file::Store::at(store.git_dir().to_owned(), options())] {
+            assert_eq!(reflog_messages(&store, "HEAD")?, expected);
+        }
+        let stack = Stack::at(&dir.path().join("reftable-clone/.git/reftable"))?;
+        assert_eq!(stack.tables().len(), 3, "the deletion is recorded in a new table");
+
+        expected.clear();
+        store.reflog_retain("HEAD".try_into()?, Fail::Immediately, |_, _| false)?;
+        assert_eq!(reflog_messages(&store, "HEAD")?, expected);
+        assert!(!store.reflog_exists("HEAD")?);
+        Ok(())
+    }
+
+    #[test]
+    fn the_stack_is_locked_during_transactions() -> crate::Result {
+        let (_dir, store) = store_writable()?;
+        let transaction =
+            store
+                .transaction()
+                .prepare(Some(create_at("refs/heads/new")), Fail::Immediately, Fail::Immediately)?;
+        let err = store
+            .transaction()
+            .prepare(
+                Some(create_at("refs/heads/other")),
+                Fail::Immediately,
+                Fail::Immediately,
+            )
+            .unwrap_err();
+        assert!(matches!(err, file::transaction::prepare::Error::LockAcquire { .. }));
+        drop(transaction);
+        assert!(store.try_find("new")?.is_none(), "nothing happens on rollback");
+        store
+            .transaction()
+            .prepare(
+                Some(create_at("refs/heads/other")),
+                Fail::Immediately,
+                Fail::Immediately,
+            )?
+            .commit(committer().to_ref(&mut TimeBuf::default()))?;
+        assert!(store.try_find("other")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn tables_with_log_tombstones_can_be_read_by_git() -> crate::Result {
+        use gix_ref::bstr::ByteSlice;
+        let (dir, store) = store_writable()?;
+        let parent_of_main = {
+            use gix_object::Find;
+            let odb = gix_odb::at(store.git_dir().join("objects"))?;
+            let mut buf = Vec::new();
+            let main = odb.try_find(&hex_to_id(MAIN), &mut buf)?.expect("main exists");
+            let parent = gix_object::CommitRefIter::from_bytes(main.data)
+                .parent_ids()
+                .next()
+                .expect("main has a parent");
+            parent
+        };
+        for (message, target) in [("advance", parent_of_main), ("return", hex_to_id(MAIN))] {
+            store
+                .transaction()
+                .prepare(
+                    [RefEdit {
+                        change: Change::Update {
+                            log: LogChange {
+                                message: message.into(),
+                                ..Default::default()
+                            },
+                            expected: PreviousValue::MustExist,
+                            new: Target::Object(target),
+                        },
+                        name: "HEAD".try_into()?,
+                        deref: true,
+                    }],
+                    Fail::Immediately,
+                    Fail::Immediately,
+                )?
+                .commit(committer().to_ref(&mut TimeBuf::default()))?;
+        }
+        store.reflog_retain("HEAD".try_into()?, Fail::Immediately, |idx, _| idx != 1)?;
+
+        let stack_dir = store.git_dir().join("reftable");
+        let mut previous_max_update_index = 0;
+        for name in Stack::at(&stack_dir)?.tables() {
+            let table = Table::from_bytes(&std::fs::read(stack_dir.join(name))?)?;
+            assert!(
+                table.min_update_index > previous_max_update_index,
+                "tables in a stack never overlap, even if they delete older log entries"
+            );
+            assert!(table.min_update_index <= table.max_update_index);
+            previous_max_update_index = table.max_update_index;
+        }
+        let expected_messages = reflog_messages(&store, "HEAD")?;
+        assert_eq!(expected_messages.last().map(String::as_str), Some("return"));
+        assert!(!expected_messages.iter().any(|m| m == "advance"));
+
+        if gix_testtools::should_skip_as_git_version_is_smaller_than(2, 45, 0) {
+            return Ok(());
+        }
+        let git = |args: &[&str]| -> crate::Result<Vec<String>> {
+            let out = std::process::Command::new("git")
+                .args(args)
+                .current_dir(dir.path().join("reftable-clone"))
+                .output()?;
+            assert!(out.status.success(), "git {args:?} failed: {}", out.stderr.as_bstr());
+            Ok(out.stdout.lines().map(|line| line.to_str_lossy().into_owned()).collect())
+        };
+        assert_eq!(git(&["for-each-ref", "--format=%(refname)"])?, ref_names(&store)?);
+        let mut messages = git(&["reflog", "show", "--format=%gs", "HEAD"])?;
+        messages.reverse();
+        assert_eq!(messages, expected_messages, "git sees the same reflog, without the deleted entry");
+        git(&["fsck", "--no-dangling"])?;
+        Ok(())
+    }
+
+    #[test]
+    fn transactions_compact_the_stack_geometrically() -> crate::Result {
+        let (dir, store) = store_writable()?;
+        let num_branches = 32;
+        for idx in 0..num_branches {
+            store
+                .transaction()
+                .prepare(
+                    Some(create_at(&format!("refs/heads/b{idx}"))),
+                    Fail::Immediately,
+                    Fail::Immediately,
+                )?
+                .commit(committer().to_ref(&mut TimeBuf::default()))?;
+        }
+        store.reflog_retain("HEAD".try_into()?, Fail::Immediately, |_, _| false)?;
+
+        let stack_dir = dir.path().join("reftable-clone/.git/reftable");
+        let stack = Stack::at(&stack_dir)?;
+        assert!(
+            stack.tables().len() < 8,
+            "without compaction there would be one table per transaction, got {}",
+            stack.tables().len()
+        );
+        assert_eq!(
+            std::fs::read_dir(&stack_dir)?.count(),
+            stack.tables().len() + 1,
+            "compacted tables are deleted, leaving only the listed ones and tables.list"
+        );
+        let mut previous_max_update_index = 0;
+        for name in stack.tables() {
+            let table = Table::from_bytes(&std::fs::read(stack_dir.join(name))?)?;
+            assert!(table.min_update_index > previous_max_update_index);
+            previous_max_update_index = table.max_update_index;
+        }
+        assert_eq!(stack.max_update_index(), 4 + num_branches + 1);
+
+        let names = ref_names(&store)?;
+        assert_eq!(names.len(), 3 + num_branches as usize, "no reference is lost");
+        for idx in 0..num_branches {
+            let name = format!("refs/heads/b{idx}");
+            assert!(names.contains(&name));
+            assert_eq!(reflog_messages(&store, &name)?, ["log peeled"]);
+        }
+        assert!(!store.reflog_exists("HEAD")?, "deleted entries stay deleted");
+        assert_eq!(reflog_messages(&store, "refs/heads/main")?.len(), 1);
+        Ok(())
+    }
+}
@@ -1,2 +1,78 @@
-#![deny(rust_2018_idioms)]
+//! Read and write the persistent [state](State) of operations on sequences of commits, like `git cherry-pick <range>`
+//! and `git revert <range>`, along with the [todo lists](todo::List) that are shared with `git rebase`.
+//!
+//! The on-disk format is the one of `git`, so a sequence started by `git` can be continued with `gitoxide`, and vice versa.
+//! Applying the instructions, which requires access to objects, the index and the worktree, is left to the caller.
+#![deny(missing_docs, rust_2018_idioms)]
 #![forbid(unsafe_code)]
+
+use bstr::BString;
+
+///
+pub mod todo;
+
+///
+pub mod options;
+
+///
+pub mod state;
+
+/// The operations that are performed by a [sequence](State).
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Apply the changes of commits, like `git cherry-pick`.
+    CherryPick,
+    /// Undo the changes of commits, like `git revert`.
+    Revert,
+}
+
+/// The options of a [sequence](State), as stored in the `opts` file in `git-config` format.
+///
+/// Everything that isn't set in the file has its default value.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, changes are applied without committing them, like `--no-commit`.
+    pub no_commit: bool,
+    /// If `Some(true)`, the commit message is edited before committing, like `--edit`, or never if `Some(false)`.
+    pub edit: Option<bool>,
+    /// If `true`, a `Signed-off-by` trailer is added to commit messages, like `--signoff`.
+    pub signoff: bool,
+    /// If `true`, a line saying which commit a cherry-pick came from is added to commit messages, like `-x`.
+    pub record_origin: bool,
+    /// If `true`, commits are fast-forwarded to instead of being recreated if possible, like `--ff`.
+    pub allow_ff: bool,
+    /// The 1-based number of the parent of merge commits to apply changes relative to, like `--mainline`.
+    pub mainline: Option<usize>,
+    /// The name of the merge strategy to use, like `--strategy`.
+    pub strategy: Option<BString>,
+    /// Options for the merge strategy, like `--strategy-option`.
+    pub strategy_options: Vec<BString>,
+    /// The key to sign commits with, like `--gpg-sign`, which is empty if the default key should be used.
+    pub gpg_sign: Option<BString>,
+    /// If `true`, commits that are empty to begin with are kept, like `--allow-empty`.
+    pub allow_empty: bool,
+    /// If `true`, commits with an empty message are allowed, like `--allow-empty-message`.
+    pub allow_empty_message: bool,
+    /// If `true`, commits that become empty because their changes are already present are kept, like `--keep-redundant-commits`.
+    pub keep_redundant_commits: bool,
+    /// If `true`, commits that become empty because their changes are already present are dropped,
+    /// like `--empty=drop`.
+    pub drop_redundant_commits: bool,
+}
+
+/// The persistent state of a sequence of cherry-picks or reverts that is in progress, as stored in the `sequencer`
+/// directory within the `git` directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct State {
+    /// The instructions that are yet to be performed. The first one is the one that is currently being performed,
+    /// for instance because it stopped with conflicts.
+    pub todo: todo::List,
+    /// The options to perform the instructions with.
+    pub options: Options,
+    /// The commit `HEAD` pointed to when the sequence started, which is what it is reset to when the sequence is aborted.
+    pub head: gix_hash::ObjectId,
+    /// The commit `HEAD` pointed to after the last change made by the sequence, if any.
+    ///
+    /// If `HEAD` was moved since, the sequence isn't rolled back when aborting it, to avoid losing work.
+    pub abort_safety: Option<gix_hash::ObjectId>,
+}
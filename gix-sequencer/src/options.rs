@@ -0,0 +1,130 @@
+use bstr::{BStr, BString};
+
+use crate::Options;
+
+/// The name of the section in the `opts` file that holds all options.
+const SECTION: &str = "options";
+
+///
+pub mod decode {
+    /// The error returned by [`Options::from_bytes()`](crate::Options::from_bytes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Parse(#[from] gix_config::file::init::Error),
+        #[error("The value of 'options.{key}' is invalid")]
+        Value {
+            key: &'static str,
+            source: gix_config::value::Error,
+        },
+        #[error("The value of 'options.mainline' must be a positive parent number, got {value}")]
+        Mainline { value: i64 },
+    }
+}
+
+impl Options {
+    /// Parse the options in `data`, which is in `git-config` format like the `opts` file of a sequence.
+    ///
+    /// Unknown options are ignored.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, decode::Error> {
+        use decode::Error;
+
+        let file = gix_config::File::from_bytes_no_includes(
+            data,
+            gix_config::file::Metadata::from(gix_config::Source::Local),
+            Default::default(),
+        )?;
+        let boolean = |key: &'static str| -> Result<Option<bool>, Error> {
+            file.boolean(format!("{SECTION}.{key}").as_str())
+                .transpose()
+                .map_err(|source| Error::Value { key, source })
+        };
+        let string = |key: &str| {
+            file.string(format!("{SECTION}.{key}").as_str())
+                .map(std::borrow::Cow::into_owned)
+        };
+
+        let mainline = file
+            .integer(format!("{SECTION}.mainline").as_str())
+            .transpose()
+            .map_err(|source| Error::Value {
+                key: "mainline",
+                source,
+            })?
+            .map(|value| {
+                usize::try_from(value)
+                    .ok()
+                    .filter(|value| *value > 0)
+                    .ok_or(Error::Mainline { value })
+            })
+            .transpose()?;
+        Ok(Options {
+            no_commit: boolean("no-commit")?.unwrap_or_default(),
+            edit: boolean("edit")?,
+            signoff: boolean("signoff")?.unwrap_or_default(),
+            record_origin: boolean("record-origin")?.unwrap_or_default(),
+            allow_ff: boolean("allow-ff")?.unwrap_or_default(),
+            mainline,
+            strategy: string("strategy"),
+            strategy_options: file
+                .strings(format!("{SECTION}.strategy-option").as_str())
+                .unwrap_or_default()
+                .into_iter()
+                .map(std::borrow::Cow::into_owned)
+                .collect(),
+            gpg_sign: string("gpg-sign"),
+            allow_empty: boolean("allow-empty")?.unwrap_or_default(),
+            allow_empty_message: boolean("allow-empty-message")?.unwrap_or_default(),
+            keep_redundant_commits: boolean("keep-redundant-commits")?.unwrap_or_default(),
+            drop_redundant_commits: boolean("drop-redundant-commits")?.unwrap_or_default(),
+        })
+    }
+
+    /// Write all options that don't have their default value to `out` in `git-config` format,
+    /// or nothing at all if all options have their default value.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let mut values: Vec<(&str, BString)> = Vec::new();
+        let mut push_true = |key, value: bool| {
+            if value {
+                values.push((key, "true".into()));
+            }
+        };
+        push_true("no-commit", self.no_commit);
+        push_true("signoff", self.signoff);
+        push_true("record-origin", self.record_origin);
+        push_true("allow-ff", self.allow_ff);
+        push_true("allow-empty", self.allow_empty);
+        push_true("allow-empty-message", self.allow_empty_message);
+        push_true("keep-redundant-commits", self.keep_redundant_commits);
+        push_true("drop-redundant-commits", self.drop_redundant_commits);
+        if let Some(edit) = self.edit {
+            values.push(("edit", if edit { "true" } else { "false" }.into()));
+        }
+        if let Some(mainline) = self.mainline {
+            values.push(("mainline", mainline.to_string().into()));
+        }
+        if let Some(strategy) = &self.strategy {
+            values.push(("strategy", strategy.clone()));
+        }
+        if let Some(key) = &self.gpg_sign {
+            values.push(("gpg-sign", key.clone()));
+        }
+        for option in &self.strategy_options {
+            values.push(("strategy-option", option.clone()));
+        }
+        if values.is_empty() {
+            return Ok(());
+        }
+
+        let mut file = gix_config::File::new(gix_config::file::Metadata::from(gix_config::Source::Local));
+        let mut section = file.new_section(SECTION, None).expect("the section name is valid");
+        for (key, value) in &values {
+            section.push(
+                (*key).try_into().expect("value names are valid"),
+                Some(<&BStr>::from(value.as_slice())),
+            );
+        }
+        file.write_to(out)
+    }
+}
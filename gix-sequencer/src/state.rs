@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+
+use bstr::ByteSlice;
+
+use crate::{todo, Operation, Options, State};
+
+/// The name of the directory within the `git` directory that holds the state of a sequence.
+pub const DIR: &str = "sequencer";
+
+///
+pub mod read {
+    /// The error returned by [`State::read()`](crate::State::read()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error("Could not decode the object id in '{}'", path.display())]
+        DecodeId {
+            path: std::path::PathBuf,
+            source: gix_hash::decode::Error,
+        },
+        #[error("Could not parse the todo list")]
+        Todo(#[from] crate::todo::decode::Error),
+        #[error("Could not parse the options")]
+        Options(#[from] crate::options::decode::Error),
+    }
+}
+
+///
+pub mod write {
+    /// The error returned by [`State::write()`](crate::State::write()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not write '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+        #[error(transparent)]
+        Lock(#[from] gix_lock::acquire::Error),
+        #[error(transparent)]
+        Commit(#[from] gix_lock::commit::Error<gix_lock::File>),
+    }
+}
+
+impl State {
+    /// Create the state of a new sequence that performs the instructions in `todo` with `options`,
+    /// starting at the commit `head` points to.
+    pub fn new(todo: todo::List, options: Options, head: gix_hash::ObjectId) -> Self {
+        State {
+            todo,
+            options,
+            head,
+            abort_safety: Some(head),
+        }
+    }
+
+    /// Return the path to the directory that holds the state of a sequence in `git_dir`.
+    pub fn dir(git_dir: &Path) -> PathBuf {
+        git_dir.join(DIR)
+    }
+
+    /// Return `true` if there is a sequence in progress in `git_dir`, which is when there is a todo list.
+    pub fn exists(git_dir: &Path) -> bool {
+        Self::dir(git_dir).join("todo").is_file()
+    }
+
+    /// Read the state of the sequence in progress in `git_dir`, or return `None` if there is none.
+    pub fn read(git_dir: &Path) -> Result<Option<Self>, read::Error> {
+        use read::Error;
+
+        let dir = Self::dir(git_dir);
+        let Some(todo) = read_file(&dir.join("todo"))? else {
+            return Ok(None);
+        };
+        let head_path = dir.join("head");
+        let head = read_file(&head_path)?.ok_or_else(|| Error::Io {
+            path: head_path.clone(),
+            source: std::io::ErrorKind::NotFound.into(),
+        })?;
+        let head = decode_id(&head, head_path)?;
+        let abort_safety_path = dir.join("abort-safety");
+        let abort_safety = read_file(&abort_safety_path)?
+            .map(|data| decode_id(&data, abort_safety_path))
+            .transpose()?;
+        let options = read_file(&dir.join("opts"))?
+            .map(|data| Options::from_bytes(&data))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Some(State {
+            todo: todo::List::from_bytes(&todo)?,
+            options,
+            head,
+            abort_safety,
+        }))
+    }
+
+    /// Write this state to `git_dir`, creating the directory for it if needed, and replacing all files of a previous state.
+    pub fn write(&self, git_dir: &Path) -> Result<(), write::Error> {
+        use write::Error;
+
+        let dir = Self::dir(git_dir);
+        std::fs::create_dir_all(&dir).map_err(|source| Error::Io {
+            path: dir.clone(),
+            source,
+        })?;
+
+        let mut buf = Vec::new();
+        self.todo.write_to(&mut buf).expect("writing to memory works");
+        write_file(&dir.join("todo"), &buf)?;
+        write_file(&dir.join("head"), format!("{}\n", self.head).as_bytes())?;
+        let abort_safety = dir.join("abort-safety");
+        match self.abort_safety {
+            Some(id) => write_file(&abort_safety, format!("{id}\n").as_bytes())?,
+            None => remove_file(&abort_safety).map_err(|source| Error::Io {
+                path: abort_safety,
+                source,
+            })?,
+        }
+        buf.clear();
+        self.options.write_to(&mut buf).expect("writing to memory works");
+        let opts = dir.join("opts");
+        if buf.is_empty() {
+            remove_file(&opts).map_err(|source| Error::Io { path: opts, source })?;
+        } else {
+            write_file(&opts, &buf)?;
+        }
+        Ok(())
+    }
+
+    /// Remove the state of the sequence in progress in `git_dir`, if there is one, like `git cherry-pick --quit` does.
+    pub fn remove(git_dir: &Path) -> std::io::Result<()> {
+        match std::fs::remove_dir_all(Self::dir(git_dir)) {
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            res => res,
+        }
+    }
+
+    /// Return the operation of the sequence, as determined by the [current instruction](todo::List::current()),
+    /// or `None` if it is neither a `pick` nor a `revert` instruction.
+    pub fn operation(&self) -> Option<Operation> {
+        match self.todo.current()? {
+            todo::Instruction::Pick(_) => Some(Operation::CherryPick),
+            todo::Instruction::Revert(_) => Some(Operation::Revert),
+            _ => None,
+        }
+    }
+
+    /// Return `true` if the sequence can be rolled back to [`head`](Self::head) without losing work, as `HEAD`
+    /// still points to `current_head`, the commit that was recorded after the last change made by the sequence.
+    pub fn is_rollback_safe(&self, current_head: Option<&gix_hash::oid>) -> bool {
+        self.abort_safety.as_deref() == current_head
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>, read::Error> {
+    match std::fs::read(path) {
+        Ok(data) => Ok(Some(data)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(source) => Err(read::Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+fn decode_id(data: &[u8], path: PathBuf) -> Result<gix_hash::ObjectId, read::Error> {
+    gix_hash::ObjectId::from_hex(data.trim()).map_err(|source| read::Error::DecodeId { path, source })
+}
+
+fn write_file(path: &Path, data: &[u8]) -> Result<(), write::Error> {
+    use std::io::Write;
+
+    let mut file = gix_lock::File::acquire_to_update_resource(path, gix_lock::acquire::Fail::Immediately, None)?;
+    file.write_all(data).map_err(|source| write::Error::Io {
+        path: path.to_owned(),
+        source,
+    })?;
+    file.commit()?;
+    Ok(())
+}
+
+fn remove_file(path: &Path) -> std::io::Result<()> {
+    match std::fs::remove_file(path) {
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        res => res,
+    }
+}
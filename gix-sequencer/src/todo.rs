@@ -0,0 +1,371 @@
+use bstr::{BStr, BString, ByteSlice};
+
+/// A commit as referred to by an instruction of a todo list.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Commit {
+    /// The name of the commit, typically its abbreviated hash, but any revision that names a commit is valid.
+    pub name: BString,
+    /// The summary of the commit, which is informational only. It may be empty.
+    pub summary: BString,
+}
+
+/// A single line of a todo list, like `pick 4a1b2c3 add feature`.
+///
+/// Each command can also be written with its first letter only, except for `revert` and `noop`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Instruction {
+    /// Apply the changes of a commit, like `git cherry-pick`.
+    Pick(Commit),
+    /// Undo the changes of a commit, like `git revert`.
+    Revert(Commit),
+    /// Apply the changes of a commit, but stop afterward to amend it.
+    Edit(Commit),
+    /// Apply the changes of a commit, but edit its message.
+    Reword(Commit),
+    /// Apply the changes of a commit and meld them into the previous commit, combining their messages.
+    Squash(Commit),
+    /// Apply the changes of a commit and meld them into the previous commit.
+    Fixup {
+        /// The commit to apply.
+        commit: Commit,
+        /// If `true`, the message of this commit replaces the one of the previous commit, written as `fixup -C`,
+        /// or `fixup -c` if the message should also be edited.
+        use_message: bool,
+        /// If `true`, the message is to be edited before committing.
+        edit_message: bool,
+    },
+    /// Skip the commit.
+    Drop(Commit),
+    /// Run a shell command, and stop if it fails.
+    Exec {
+        /// The command to run.
+        command: BString,
+    },
+    /// Stop, to be continued later.
+    Break,
+    /// Give the current `HEAD` a name that can be used by later instructions.
+    Label {
+        /// The name to give to `HEAD`.
+        name: BString,
+    },
+    /// Reset `HEAD` to a label or a commit.
+    Reset {
+        /// The label or the name of the commit to reset to.
+        name: BString,
+    },
+    /// Create a merge commit of `HEAD` and the given `parents`.
+    Merge {
+        /// The merge commit whose message should be used, if any, written as `merge -C <commit>`, or `merge -c <commit>`
+        /// if the message should also be edited.
+        commit: Option<BString>,
+        /// If `true`, the message of the merge commit is to be edited before committing.
+        edit_message: bool,
+        /// The labels or names of the commits to merge into `HEAD`.
+        parents: Vec<BString>,
+        /// The summary of the merge commit, which is informational only.
+        summary: Option<BString>,
+    },
+    /// Update a reference to point to `HEAD` once the sequence is finished.
+    UpdateRef {
+        /// The full name of the reference to update.
+        name: BString,
+    },
+    /// Do nothing.
+    Noop,
+    /// A line with a comment, which is kept as is, including the comment character.
+    Comment(BString),
+}
+
+/// A list of instructions, like the one in `.git/sequencer/todo`, or the one of an interactive rebase.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct List {
+    /// All instructions, in the order in which they are to be performed. Empty lines aren't retained.
+    pub instructions: Vec<Instruction>,
+}
+
+///
+pub mod decode {
+    use bstr::BString;
+
+    /// The error returned by [`List::from_bytes()`](super::List::from_bytes()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Line {line_number} has an unknown command: '{line}'")]
+        UnknownCommand { line_number: usize, line: BString },
+        #[error("Line {line_number} is missing an argument: '{line}'")]
+        MissingArgument { line_number: usize, line: BString },
+        #[error("Line {line_number} has a command that doesn't accept arguments: '{line}'")]
+        UnexpectedArgument { line_number: usize, line: BString },
+    }
+}
+
+impl Instruction {
+    /// Return the commit this instruction applies, if there is one.
+    pub fn commit(&self) -> Option<&Commit> {
+        match self {
+            Instruction::Pick(commit)
+            | Instruction::Revert(commit)
+            | Instruction::Edit(commit)
+            | Instruction::Reword(commit)
+            | Instruction::Squash(commit)
+            | Instruction::Drop(commit)
+            | Instruction::Fixup { commit, .. } => Some(commit),
+            Instruction::Exec { .. }
+            | Instruction::Break
+            | Instruction::Label { .. }
+            | Instruction::Reset { .. }
+            | Instruction::Merge { .. }
+            | Instruction::UpdateRef { .. }
+            | Instruction::Noop
+            | Instruction::Comment(_) => None,
+        }
+    }
+
+    /// Return `true` if this instruction is a comment.
+    pub fn is_comment(&self) -> bool {
+        matches!(self, Instruction::Comment(_))
+    }
+
+    /// Return the name of the command of this instruction as it's written to todo lists, or `None` for comments.
+    pub fn command(&self) -> Option<&'static str> {
+        Some(match self {
+            Instruction::Pick(_) => "pick",
+            Instruction::Revert(_) => "revert",
+            Instruction::Edit(_) => "edit",
+            Instruction::Reword(_) => "reword",
+            Instruction::Squash(_) => "squash",
+            Instruction::Fixup { .. } => "fixup",
+            Instruction::Drop(_) => "drop",
+            Instruction::Exec { .. } => "exec",
+            Instruction::Break => "break",
+            Instruction::Label { .. } => "label",
+            Instruction::Reset { .. } => "reset",
+            Instruction::Merge { .. } => "merge",
+            Instruction::UpdateRef { .. } => "update-ref",
+            Instruction::Noop => "noop",
+            Instruction::Comment(_) => return None,
+        })
+    }
+
+    /// Parse a single non-empty `line` of a todo list at `line_number`, with comments starting with `#`.
+    pub fn from_line(line: &BStr, line_number: usize) -> Result<Self, decode::Error> {
+        use decode::Error;
+
+        let trimmed = line.trim();
+        if trimmed.starts_with(b"#") {
+            return Ok(Instruction::Comment(line.trim_end().into()));
+        }
+        let (command, rest) = split_word(trimmed);
+        let missing_argument = || Error::MissingArgument {
+            line_number,
+            line: line.into(),
+        };
+        let commit = |rest: &[u8]| -> Result<Commit, Error> {
+            let (name, summary) = split_word(rest);
+            if name.is_empty() {
+                return Err(missing_argument());
+            }
+            Ok(Commit {
+                name: name.into(),
+                summary: summary.into(),
+            })
+        };
+        let argument = |rest: &[u8]| -> Result<BString, Error> {
+            if rest.is_empty() {
+                Err(missing_argument())
+            } else {
+                Ok(rest.into())
+            }
+        };
+        let no_argument = |instruction: Instruction| {
+            if rest.is_empty() {
+                Ok(instruction)
+            } else {
+                Err(Error::UnexpectedArgument {
+                    line_number,
+                    line: line.into(),
+                })
+            }
+        };
+
+        Ok(match command {
+            b"pick" | b"p" => Instruction::Pick(commit(rest)?),
+            b"revert" => Instruction::Revert(commit(rest)?),
+            b"edit" | b"e" => Instruction::Edit(commit(rest)?),
+            b"reword" | b"r" => Instruction::Reword(commit(rest)?),
+            b"squash" | b"s" => Instruction::Squash(commit(rest)?),
+            b"fixup" | b"f" => {
+                let (flag, after_flag) = split_word(rest);
+                let (use_message, edit_message, rest) = match flag {
+                    b"-C" => (true, false, after_flag),
+                    b"-c" => (true, true, after_flag),
+                    _ => (false, false, rest),
+                };
+                Instruction::Fixup {
+                    commit: commit(rest)?,
+                    use_message,
+                    edit_message,
+                }
+            }
+            b"drop" | b"d" => Instruction::Drop(commit(rest)?),
+            b"exec" | b"x" => Instruction::Exec {
+                command: argument(rest)?,
+            },
+            b"break" | b"b" => no_argument(Instruction::Break)?,
+            b"label" | b"l" => Instruction::Label { name: argument(rest)? },
+            b"reset" | b"t" => Instruction::Reset { name: argument(rest)? },
+            b"merge" | b"m" => {
+                let (flag, after_flag) = split_word(rest);
+                let (commit, edit_message, rest) = match flag {
+                    b"-C" | b"-c" => {
+                        let (commit, rest) = split_word(after_flag);
+                        if commit.is_empty() {
+                            return Err(missing_argument());
+                        }
+                        (Some(commit.into()), flag == b"-c", rest)
+                    }
+                    _ => (None, false, rest),
+                };
+                let (parents, summary) = match rest.find(b"# ").filter(|pos| *pos == 0 || rest[pos - 1] == b' ') {
+                    Some(pos) => (&rest[..pos], Some(rest[pos + 2..].trim().into())),
+                    None => (rest, None),
+                };
+                let parents: Vec<BString> = parents.fields().map(Into::into).collect();
+                if parents.is_empty() {
+                    return Err(missing_argument());
+                }
+                Instruction::Merge {
+                    commit,
+                    edit_message,
+                    parents,
+                    summary,
+                }
+            }
+            b"update-ref" | b"u" => Instruction::UpdateRef { name: argument(rest)? },
+            b"noop" => no_argument(Instruction::Noop)?,
+            _ => {
+                return Err(Error::UnknownCommand {
+                    line_number,
+                    line: line.into(),
+                })
+            }
+        })
+    }
+
+    /// Write this instruction as a single line, including the trailing newline, to `out`.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        let command = match self {
+            Instruction::Comment(line) => {
+                out.write_all(line)?;
+                return out.write_all(b"\n");
+            }
+            _ => self.command().expect("not a comment"),
+        };
+        out.write_all(command.as_bytes())?;
+        match self {
+            Instruction::Pick(commit)
+            | Instruction::Revert(commit)
+            | Instruction::Edit(commit)
+            | Instruction::Reword(commit)
+            | Instruction::Squash(commit)
+            | Instruction::Drop(commit) => write_commit(out, commit)?,
+            Instruction::Fixup {
+                commit,
+                use_message,
+                edit_message,
+            } => {
+                if *use_message {
+                    out.write_all(if *edit_message { b" -c" } else { b" -C" })?;
+                }
+                write_commit(out, commit)?;
+            }
+            Instruction::Exec { command: arg }
+            | Instruction::Label { name: arg }
+            | Instruction::Reset { name: arg }
+            | Instruction::UpdateRef { name: arg } => {
+                out.write_all(b" ")?;
+                out.write_all(arg)?;
+            }
+            Instruction::Merge {
+                commit,
+                edit_message,
+                parents,
+                summary,
+            } => {
+                if let Some(commit) = commit {
+                    out.write_all(if *edit_message { b" -c " } else { b" -C " })?;
+                    out.write_all(commit)?;
+                }
+                for parent in parents {
+                    out.write_all(b" ")?;
+                    out.write_all(parent)?;
+                }
+                if let Some(summary) = summary {
+                    out.write_all(b" # ")?;
+                    out.write_all(summary)?;
+                }
+            }
+            Instruction::Break | Instruction::Noop | Instruction::Comment(_) => {}
+        }
+        out.write_all(b"\n")
+    }
+}
+
+impl List {
+    /// Parse the todo list in `data`, skipping empty lines.
+    pub fn from_bytes(data: &[u8]) -> Result<Self, decode::Error> {
+        let instructions = data
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| Instruction::from_line(line.as_bstr(), idx + 1))
+            .collect::<Result<_, _>>()?;
+        Ok(List { instructions })
+    }
+
+    /// Write all instructions, one per line, to `out`.
+    pub fn write_to(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        for instruction in &self.instructions {
+            instruction.write_to(out)?;
+        }
+        Ok(())
+    }
+
+    /// Return the first instruction that isn't a comment, which is the one to perform next.
+    pub fn current(&self) -> Option<&Instruction> {
+        self.instructions.iter().find(|instruction| !instruction.is_comment())
+    }
+
+    /// Remove the [current instruction](Self::current()) and all comments before it, and return it.
+    pub fn pop_current(&mut self) -> Option<Instruction> {
+        let idx = self
+            .instructions
+            .iter()
+            .position(|instruction| !instruction.is_comment())?;
+        let instruction = self.instructions.remove(idx);
+        self.instructions.drain(..idx);
+        Some(instruction)
+    }
+
+    /// Return `true` if there are no instructions left to perform, ignoring comments.
+    pub fn is_done(&self) -> bool {
+        self.current().is_none()
+    }
+}
+
+fn write_commit(out: &mut dyn std::io::Write, commit: &Commit) -> std::io::Result<()> {
+    out.write_all(b" ")?;
+    out.write_all(&commit.name)?;
+    if !commit.summary.is_empty() {
+        out.write_all(b" ")?;
+        out.write_all(&commit.summary)?;
+    }
+    Ok(())
+}
+
+/// Split `input` into its first word and the remainder with leading whitespace removed.
+fn split_word(input: &[u8]) -> (&[u8], &[u8]) {
+    let end = input.iter().position(u8::is_ascii_whitespace).unwrap_or(input.len());
+    (&input[..end], input[end..].trim_start())
+}
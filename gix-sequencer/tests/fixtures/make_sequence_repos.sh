@@ -0,0 +1,35 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function setup() {
+  git init -q
+  echo base > file
+  git add file && git commit -q -m base
+
+  git checkout -q -b other
+  echo a > a
+  git add a && git commit -q -m "add a"
+  echo other > file
+  git commit -q -am "change file"
+  echo c > c
+  git add c && git commit -q -m "add c"
+
+  git checkout -q main
+  echo main > file
+  git commit -q -am "change file on main"
+}
+
+mkdir cherry-pick-with-conflict
+(cd cherry-pick-with-conflict
+  setup
+  git cherry-pick -x main..other || :
+)
+
+mkdir revert-with-conflict
+(cd revert-with-conflict
+  setup
+  git checkout -q other
+  echo more > file
+  git commit -q -am "change file again"
+  git revert --no-edit --strategy recursive -X patience HEAD~1 HEAD~4 HEAD~3 || :
+)
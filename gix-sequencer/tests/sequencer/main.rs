@@ -0,0 +1,5 @@
+mod options;
+mod state;
+mod todo;
+
+pub use gix_testtools::Result;
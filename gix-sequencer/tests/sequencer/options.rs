@@ -0,0 +1,61 @@
+use gix_sequencer::{options::decode, Options};
+
+#[test]
+fn round_trip() -> crate::Result {
+    let options = Options {
+        no_commit: true,
+        edit: Some(false),
+        signoff: true,
+        record_origin: true,
+        allow_ff: true,
+        mainline: Some(2),
+        strategy: Some("ort".into()),
+        strategy_options: vec!["patience".into(), "ignore-space-change".into()],
+        gpg_sign: Some("".into()),
+        allow_empty: true,
+        allow_empty_message: true,
+        keep_redundant_commits: true,
+        drop_redundant_commits: true,
+    };
+    let mut out = Vec::new();
+    options.write_to(&mut out)?;
+    assert_eq!(Options::from_bytes(&out)?, options);
+    Ok(())
+}
+
+#[test]
+fn defaults_are_not_written() -> crate::Result {
+    let mut out = Vec::new();
+    Options::default().write_to(&mut out)?;
+    assert!(out.is_empty());
+    assert_eq!(Options::from_bytes(&out)?, Options::default());
+
+    Options {
+        record_origin: true,
+        ..Default::default()
+    }
+    .write_to(&mut out)?;
+    assert_eq!(out, b"[options]\n\trecord-origin = true\n", "this is what git writes");
+    Ok(())
+}
+
+#[test]
+fn unknown_keys_are_ignored() -> crate::Result {
+    let options = Options::from_bytes(b"[options]\n\tfuture-option = 1\n\tmainline = 1\n[other]\n\tsignoff = true\n")?;
+    assert_eq!(
+        options,
+        Options {
+            mainline: Some(1),
+            ..Default::default()
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn invalid_values() {
+    let err = Options::from_bytes(b"[options]\n\tsignoff = maybe\n").unwrap_err();
+    assert!(matches!(err, decode::Error::Value { key: "signoff", .. }), "{err:?}");
+    let err = Options::from_bytes(b"[options]\n\tmainline = 0\n").unwrap_err();
+    assert!(matches!(err, decode::Error::Mainline { value: 0 }), "{err:?}");
+}
@@ -0,0 +1,124 @@
+use std::path::{Path, PathBuf};
+
+use gix_sequencer::{
+    todo::{Commit, Instruction, List},
+    Operation, Options, State,
+};
+
+fn git_dir(name: &str) -> crate::Result<PathBuf> {
+    Ok(gix_testtools::scripted_fixture_read_only("make_sequence_repos.sh")?
+        .join(name)
+        .join(".git"))
+}
+
+fn read_state_file(git_dir: &Path, name: &str) -> std::io::Result<Vec<u8>> {
+    std::fs::read(State::dir(git_dir).join(name))
+}
+
+#[test]
+fn read_cherry_pick_by_git() -> crate::Result {
+    let git_dir = git_dir("cherry-pick-with-conflict")?;
+    assert!(State::exists(&git_dir));
+    let state = State::read(&git_dir)?.expect("sequence in progress");
+
+    assert_eq!(state.operation(), Some(Operation::CherryPick));
+    assert_eq!(
+        state.options,
+        Options {
+            record_origin: true,
+            ..Default::default()
+        }
+    );
+    assert_eq!(state.todo.instructions.len(), 2);
+    assert!(
+        matches!(state.todo.current(), Some(Instruction::Pick(Commit { summary, .. })) if summary == "change file"),
+        "the instruction that stopped with conflicts remains"
+    );
+    assert_ne!(state.abort_safety, Some(state.head), "the first pick was committed");
+    assert!(state.is_rollback_safe(state.abort_safety.as_deref()));
+    assert!(!state.is_rollback_safe(Some(&state.head)));
+    Ok(())
+}
+
+#[test]
+fn read_revert_by_git() -> crate::Result {
+    let git_dir = git_dir("revert-with-conflict")?;
+    let state = State::read(&git_dir)?.expect("sequence in progress");
+
+    assert_eq!(state.operation(), Some(Operation::Revert));
+    assert_eq!(
+        state.options,
+        Options {
+            edit: Some(false),
+            strategy: Some("recursive".into()),
+            strategy_options: vec!["patience".into()],
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        state
+            .todo
+            .instructions
+            .iter()
+            .map(|instruction| instruction.commit().expect("reverts only").summary.to_string())
+            .collect::<Vec<_>>(),
+        ["base", "add a"]
+    );
+    Ok(())
+}
+
+#[test]
+fn write_is_compatible_with_git() -> crate::Result {
+    for name in ["cherry-pick-with-conflict", "revert-with-conflict"] {
+        let git_dir = git_dir(name)?;
+        let state = State::read(&git_dir)?.expect("sequence in progress");
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        state.write(tmp.path())?;
+        for file in ["todo", "head", "abort-safety", "opts"] {
+            assert_eq!(
+                read_state_file(tmp.path(), file)?,
+                read_state_file(&git_dir, file)?,
+                "{name}: {file} is written exactly like git does"
+            );
+        }
+        assert_eq!(State::read(tmp.path())?, Some(state));
+    }
+    Ok(())
+}
+
+#[test]
+fn write_and_remove() -> crate::Result {
+    let tmp = gix_testtools::tempfile::TempDir::new()?;
+    assert!(!State::exists(tmp.path()));
+    assert_eq!(State::read(tmp.path())?, None);
+
+    let head = gix_hash::Kind::Sha1.null();
+    let mut state = State::new(
+        List::from_bytes(b"pick 4a1b2c3 one\npick 5b2c3d4 two\n")?,
+        Options {
+            no_commit: true,
+            ..Default::default()
+        },
+        head,
+    );
+    state.write(tmp.path())?;
+    assert!(State::exists(tmp.path()));
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+    assert!(state.is_rollback_safe(Some(&head)));
+
+    state.todo.pop_current();
+    state.options = Options::default();
+    state.abort_safety = None;
+    state.write(tmp.path())?;
+    assert!(
+        !State::dir(tmp.path()).join("opts").exists() && !State::dir(tmp.path()).join("abort-safety").exists(),
+        "files for default values are removed"
+    );
+    assert_eq!(State::read(tmp.path())?.as_ref(), Some(&state));
+
+    State::remove(tmp.path())?;
+    assert!(!State::dir(tmp.path()).exists());
+    State::remove(tmp.path())?;
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use gix_sequencer::todo::{decode, Commit, Instruction, List};
+
+fn commit(name: &str, summary: &str) -> Commit {
+    Commit {
+        name: name.into(),
+        summary: summary.into(),
+    }
+}
+
+#[test]
+fn all_commands_round_trip() -> crate::Result {
+    let input = "pick 4a1b2c3 first commit\n\
+                 # a comment\n\
+                 revert 5b2c3d4 second\n\
+                 edit 6c3d4e5\n\
+                 reword 7d4e5f6 fourth\n\
+                 squash 8e5f607 fifth\n\
+                 fixup 9f60718 sixth\n\
+                 fixup -C a071829 seventh\n\
+                 fixup -c b18293a eighth\n\
+                 drop c2930ab ninth\n\
+                 exec cargo test --workspace\n\
+                 break\n\
+                 label onto\n\
+                 reset onto\n\
+                 merge -C d3a4b5c topic # Merge branch 'topic'\n\
+                 merge -c e4b5c6d one two\n\
+                 merge topic\n\
+                 update-ref refs/heads/topic\n\
+                 noop\n";
+    let list = List::from_bytes(input.as_bytes())?;
+    assert_eq!(list.instructions.len(), 19);
+    assert_eq!(
+        list.instructions[0],
+        Instruction::Pick(commit("4a1b2c3", "first commit"))
+    );
+    assert_eq!(list.instructions[1], Instruction::Comment("# a comment".into()));
+    assert_eq!(list.instructions[3], Instruction::Edit(commit("6c3d4e5", "")));
+    assert_eq!(
+        list.instructions[7],
+        Instruction::Fixup {
+            commit: commit("a071829", "seventh"),
+            use_message: true,
+            edit_message: false,
+        }
+    );
+    assert_eq!(
+        list.instructions[10],
+        Instruction::Exec {
+            command: "cargo test --workspace".into()
+        }
+    );
+    assert_eq!(
+        list.instructions[14],
+        Instruction::Merge {
+            commit: Some("d3a4b5c".into()),
+            edit_message: false,
+            parents: vec!["topic".into()],
+            summary: Some("Merge branch 'topic'".into()),
+        }
+    );
+    assert_eq!(
+        list.instructions[15],
+        Instruction::Merge {
+            commit: Some("e4b5c6d".into()),
+            edit_message: true,
+            parents: vec!["one".into(), "two".into()],
+            summary: None,
+        }
+    );
+
+    let mut out = Vec::new();
+    list.write_to(&mut out)?;
+    assert_eq!(out, input.as_bytes(), "everything is written as it was read");
+    Ok(())
+}
+
+#[test]
+fn abbreviated_commands_and_whitespace() -> crate::Result {
+    let list = List::from_bytes(
+        b"p 4a1b2c3   first\n\n  f -C 5b2c3d4\nx make\nb\nl onto\nt onto\nm two\nu refs/heads/main\n",
+    )?;
+    assert_eq!(
+        list.instructions,
+        [
+            Instruction::Pick(commit("4a1b2c3", "first")),
+            Instruction::Fixup {
+                commit: commit("5b2c3d4", ""),
+                use_message: true,
+                edit_message: false,
+            },
+            Instruction::Exec { command: "make".into() },
+            Instruction::Break,
+            Instruction::Label { name: "onto".into() },
+            Instruction::Reset { name: "onto".into() },
+            Instruction::Merge {
+                commit: None,
+                edit_message: false,
+                parents: vec!["two".into()],
+                summary: None,
+            },
+            Instruction::UpdateRef {
+                name: "refs/heads/main".into()
+            },
+        ]
+    );
+    let mut out = Vec::new();
+    list.write_to(&mut out)?;
+    assert_eq!(
+        out,
+        b"pick 4a1b2c3 first\nfixup -C 5b2c3d4\nexec make\nbreak\nlabel onto\nreset onto\nmerge two\nupdate-ref refs/heads/main\n",
+        "commands are always written in full"
+    );
+    Ok(())
+}
+
+#[test]
+fn current_skips_comments_and_pop_removes_them() -> crate::Result {
+    let mut list = List::from_bytes(b"# first\npick 4a1b2c3 one\n# second\nrevert 5b2c3d4 two\n")?;
+    assert_eq!(list.current(), Some(&Instruction::Pick(commit("4a1b2c3", "one"))));
+    assert_eq!(list.pop_current(), Some(Instruction::Pick(commit("4a1b2c3", "one"))));
+    assert_eq!(list.instructions.len(), 2, "only the leading comment was removed");
+    assert!(!list.is_done());
+    assert_eq!(list.pop_current(), Some(Instruction::Revert(commit("5b2c3d4", "two"))));
+    assert!(list.is_done());
+    assert_eq!(list.pop_current(), None);
+    Ok(())
+}
+
+#[test]
+fn invalid_lines() {
+    for (input, expected_line_number) in [("pick 4a1b2c3\nfrobnicate 4a1b2c3\n", 2), ("\n\nunknown", 3)] {
+        let err = List::from_bytes(input.as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, decode::Error::UnknownCommand { line_number, .. } if line_number == expected_line_number),
+            "{err:?}"
+        );
+    }
+    for input in ["pick", "exec  ", "label", "merge -C", "merge -C 4a1b2c3", "fixup -C"] {
+        let err = List::from_bytes(input.as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, decode::Error::MissingArgument { line_number: 1, .. }),
+            "{input}: {err:?}"
+        );
+    }
+    for input in ["break now", "noop 4a1b2c3"] {
+        let err = List::from_bytes(input.as_bytes()).unwrap_err();
+        assert!(
+            matches!(err, decode::Error::UnexpectedArgument { line_number: 1, .. }),
+            "{input}: {err:?}"
+        );
+    }
+}
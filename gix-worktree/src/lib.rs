@@ -63,3 +63,5 @@ pub(crate) type PathIdMapping = (BString, gix_hash::ObjectId);
 
 ///
 pub mod stack;
+
+pub mod sparse;
@@ -0,0 +1,266 @@
+//! Sparse checkouts, which only check out the index entries matched by the patterns in `.git/info/sparse-checkout`.
+//!
+//! All other entries receive the [`SKIP_WORKTREE`](gix_index::entry::Flags::SKIP_WORKTREE) flag, which makes
+//! checkouts and status checks ignore them.
+use std::collections::BTreeSet;
+
+use bstr::{BStr, BString, ByteSlice, ByteVec};
+use gix_glob::{pattern::Case, search::Pattern as _};
+
+/// The patterns of a sparse checkout, which determine which paths are included in the worktree.
+#[derive(Debug, Clone)]
+pub struct Patterns {
+    inner: Inner,
+}
+
+#[derive(Debug, Clone)]
+enum Inner {
+    /// Patterns in *cone mode*, which include entire directories.
+    Cone {
+        /// Directories which are included with all their content, without leading or trailing slashes.
+        recursive: BTreeSet<BString>,
+        /// Directories of which only files directly within are included, without leading or trailing slashes.
+        parents: BTreeSet<BString>,
+    },
+    /// Patterns like the ones in `.gitignore` files, just that matches are included instead of excluded.
+    NonCone(gix_glob::search::pattern::List<gix_ignore::search::Ignore>),
+}
+
+/// Initialization
+impl Patterns {
+    /// Parse the content of a `.git/info/sparse-checkout` file from `bytes`, in *cone mode* if `cone` is `true`.
+    ///
+    /// Like `git`, patterns that can't be represented in cone mode are interpreted in non-cone mode instead.
+    pub fn from_bytes(bytes: &[u8], cone: bool) -> Self {
+        cone.then(|| parse_cone(bytes)).flatten().unwrap_or_else(|| Patterns {
+            inner: Inner::NonCone(gix_glob::search::pattern::List {
+                patterns: gix_ignore::search::Ignore::default().bytes_to_patterns(bytes, std::path::Path::new("")),
+                source: None,
+                base: None,
+            }),
+        })
+    }
+
+    /// Create cone-mode patterns which include all files in the root of the worktree, along with everything in
+    /// the slash-separated `directories`, which are relative to the root of the worktree.
+    ///
+    /// Note that leading and trailing slashes are ignored, and that directories that are contained in another one are removed.
+    pub fn from_cone_directories(directories: impl IntoIterator<Item = impl AsRef<BStr>>) -> Self {
+        let mut recursive: BTreeSet<BString> = directories
+            .into_iter()
+            .map(|dir| dir.as_ref().trim_with(|c| c == '/').as_bstr().to_owned())
+            .filter(|dir| !dir.is_empty())
+            .collect();
+        let contained: Vec<_> = recursive
+            .iter()
+            .filter(|dir| leading_directories(dir.as_bstr()).any(|parent| recursive.contains(parent)))
+            .cloned()
+            .collect();
+        for dir in contained {
+            recursive.remove(&dir);
+        }
+        let parents = recursive
+            .iter()
+            .flat_map(|dir| leading_directories(dir.as_bstr()).map(ToOwned::to_owned))
+            .collect();
+        Patterns {
+            inner: Inner::Cone { recursive, parents },
+        }
+    }
+}
+
+/// Access
+impl Patterns {
+    /// Return `true` if these patterns are in *cone mode*.
+    pub fn is_cone(&self) -> bool {
+        matches!(self.inner, Inner::Cone { .. })
+    }
+
+    /// Return the directories that are included recursively, in sorted order, or `None` if these patterns aren't in *cone mode*.
+    pub fn cone_directories(&self) -> Option<impl Iterator<Item = &BStr> + '_> {
+        match &self.inner {
+            Inner::Cone { recursive, .. } => Some(recursive.iter().map(AsRef::as_ref)),
+            Inner::NonCone(_) => None,
+        }
+    }
+
+    /// Return `true` if the slash-separated `path` relative to the root of the worktree is included in the sparse checkout.
+    ///
+    /// `is_dir` is `true` if `path` is a directory, which is included if at least some of its content is included.
+    /// `case` determines if the case is folded when comparing paths.
+    pub fn is_included(&self, path: &BStr, is_dir: bool, case: Case) -> bool {
+        let path = path.trim_end_with(|c| c == '/').as_bstr();
+        match &self.inner {
+            Inner::Cone { recursive, parents } => {
+                let dir = if is_dir {
+                    path
+                } else {
+                    match path.rfind_byte(b'/') {
+                        Some(pos) => path[..pos].as_bstr(),
+                        None => return true,
+                    }
+                };
+                if dir.is_empty() {
+                    return true;
+                }
+                let contains = |set: &BTreeSet<BString>, dir: &BStr| match case {
+                    Case::Sensitive => set.contains(dir),
+                    Case::Fold => set.iter().any(|candidate| candidate.eq_ignore_ascii_case(dir)),
+                };
+                contains(recursive, dir)
+                    || contains(parents, dir)
+                    || leading_directories(dir).any(|parent| contains(recursive, parent))
+            }
+            Inner::NonCone(list) => {
+                // Like `git`, if the path itself isn't matched, the decision is made by the closest matching leading directory.
+                let mut is_dir = is_dir;
+                let mut end = path.len();
+                loop {
+                    let candidate = path[..end].as_bstr();
+                    let basename_pos = candidate.rfind_byte(b'/').map(|pos| pos + 1);
+                    if let Some(m) = gix_ignore::search::pattern_matching_relative_path(
+                        list,
+                        candidate,
+                        basename_pos,
+                        Some(is_dir),
+                        case,
+                    ) {
+                        return !m.pattern.is_negative();
+                    }
+                    match basename_pos {
+                        Some(pos) => end = pos - 1,
+                        None => return false,
+                    }
+                    is_dir = true;
+                }
+            }
+        }
+    }
+}
+
+/// Serialization
+impl Patterns {
+    /// Write these patterns to `out` in the format of the `.git/info/sparse-checkout` file.
+    pub fn write_to(&self, mut out: impl std::io::Write) -> std::io::Result<()> {
+        match &self.inner {
+            Inner::Cone { recursive, parents } => {
+                out.write_all(b"/*\n!/*/\n")?;
+                for dir in parents {
+                    let dir = escape(dir.as_bstr());
+                    writeln!(out, "/{dir}/\n!/{dir}/*/")?;
+                }
+                for dir in recursive {
+                    writeln!(out, "/{}/", escape(dir.as_bstr()))?;
+                }
+            }
+            Inner::NonCone(list) => {
+                for mapping in &list.patterns {
+                    writeln!(out, "{}", mapping.pattern)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The index entries whose [`SKIP_WORKTREE`](gix_index::entry::Flags::SKIP_WORKTREE) flag was changed by [`update_index()`].
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Changes {
+    /// The positions of all entries that were included in the worktree, and thus are expected to be checked out.
+    pub included: Vec<usize>,
+    /// The positions of all entries that were excluded from the worktree, and thus are expected to be removed from it.
+    pub excluded: Vec<usize>,
+}
+
+/// Set the [`SKIP_WORKTREE`](gix_index::entry::Flags::SKIP_WORKTREE) flag of all entries in `index` which aren't included by
+/// `patterns`, and clear it on all entries that are, comparing paths according to `case`.
+///
+/// Like `git`, conflicting entries are always included. Sparse directory entries are left untouched.
+pub fn update_index(index: &mut gix_index::State, patterns: &Patterns, case: Case) -> Changes {
+    use gix_index::entry::{Flags, Mode, Stage};
+
+    let mut changes = Changes::default();
+    for (idx, (entry, path)) in index.entries_mut_with_paths().enumerate() {
+        if entry.mode == Mode::DIR {
+            continue;
+        }
+        let is_included = entry.stage() != Stage::Unconflicted || patterns.is_included(path, false, case);
+        let is_skipped = entry.flags.contains(Flags::SKIP_WORKTREE);
+        if is_included && is_skipped {
+            entry.flags.remove(Flags::SKIP_WORKTREE);
+            if !entry.flags.intersects(Flags::INTENT_TO_ADD) {
+                entry.flags.remove(Flags::EXTENDED);
+            }
+            changes.included.push(idx);
+        } else if !is_included && !is_skipped {
+            entry.flags.insert(Flags::SKIP_WORKTREE | Flags::EXTENDED);
+            changes.excluded.push(idx);
+        }
+    }
+    changes
+}
+
+/// Return all leading directories of the slash-separated `path`, the longest one first.
+fn leading_directories(path: &BStr) -> impl Iterator<Item = &BStr> {
+    path.rfind_iter("/").map(move |pos| path[..pos].as_bstr())
+}
+
+/// Parse `bytes` as cone-mode patterns, or return `None` if they aren't in cone mode.
+fn parse_cone(bytes: &[u8]) -> Option<Patterns> {
+    let mut recursive = BTreeSet::new();
+    let mut parents = BTreeSet::new();
+    for line in bytes.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(b"#") || line == b"/*" || line == b"!/*/" {
+            continue;
+        }
+        if let Some(dir) = line.strip_prefix(b"!/").and_then(|p| p.strip_suffix(b"/*/")) {
+            let dir = unescape(dir)?;
+            if !recursive.remove(&dir) {
+                return None;
+            }
+            parents.insert(dir);
+        } else {
+            let dir = line.strip_prefix(b"/").and_then(|p| p.strip_suffix(b"/"))?;
+            if dir.is_empty() {
+                return None;
+            }
+            recursive.insert(unescape(dir)?);
+        }
+    }
+    parents.extend(
+        recursive
+            .iter()
+            .flat_map(|dir| leading_directories(dir.as_bstr()).map(ToOwned::to_owned))
+            .collect::<Vec<_>>(),
+    );
+    Some(Patterns {
+        inner: Inner::Cone { recursive, parents },
+    })
+}
+
+/// Remove the backslashes that escape glob characters in `dir`, or return `None` if it contains unescaped glob characters.
+fn unescape(dir: &[u8]) -> Option<BString> {
+    let mut out = BString::default();
+    let mut bytes = dir.iter();
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\\' => out.push(*bytes.next()?),
+            b'*' | b'?' | b'[' => return None,
+            _ => out.push(*b),
+        }
+    }
+    Some(out)
+}
+
+/// Escape all glob characters in `dir` with a backslash.
+fn escape(dir: &BStr) -> BString {
+    let mut out = BString::default();
+    for b in dir.iter() {
+        if matches!(b, b'\\' | b'*' | b'?' | b'[') {
+            out.push_byte(b'\\');
+        }
+        out.push_byte(*b);
+    }
+    out
+}
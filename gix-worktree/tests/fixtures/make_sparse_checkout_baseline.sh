@@ -0,0 +1,27 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  mkdir -p a/b/c a/x d/e "with space" "glob[chars]"
+  for file in root x.txt a/file a/x.txt a/b/file a/b/c/file a/x/file d/file d/e/file d/e/y.txt "with space/file" "glob[chars]/file"; do
+    echo "$file" > "$file"
+  done
+  git add .
+  git commit -q -m "init"
+)
+
+function baseline() {
+  local name=${1:?First argument is the name of the clone}
+  shift
+  git clone -q base "$name"
+  (cd "$name"
+    git sparse-checkout set "$@"
+    git ls-files -t > ../"$name".baseline
+  )
+}
+
+baseline cone --cone --skip-checks a/b d "glob[chars]"
+baseline cone-nested --cone d/e a
+baseline no-cone --no-cone --skip-checks '/*' '!/a/' '/a/b/c/' '*.txt'
+baseline no-cone-dirs --no-cone --skip-checks 'd/' '!e/'
@@ -0,0 +1,136 @@
+use bstr::{BStr, ByteSlice};
+use gix_glob::pattern::Case;
+use gix_index::entry::Flags;
+use gix_worktree::sparse::{update_index, Patterns};
+
+struct Baseline {
+    patterns: Patterns,
+    /// The `info/sparse-checkout` file as written by `git`.
+    file: Vec<u8>,
+    index: gix_index::File,
+    /// Whether the entry at the path was skipped by `git`.
+    expected: Vec<(bool, String)>,
+}
+
+fn baseline(name: &str) -> crate::Result<Baseline> {
+    let dir = gix_testtools::scripted_fixture_read_only_standalone("make_sparse_checkout_baseline.sh")?;
+    let git_dir = dir.join(name).join(".git");
+    let cone = !name.starts_with("no-cone");
+    let file = std::fs::read(git_dir.join("info").join("sparse-checkout"))?;
+    let index = gix_index::File::at(git_dir.join("index"), gix_hash::Kind::Sha1, false, Default::default())?;
+    let expected = std::fs::read_to_string(dir.join(format!("{name}.baseline")))?
+        .lines()
+        .map(|line| {
+            let (status, path) = line.split_once(' ').expect("status and path");
+            (status == "S", path.to_owned())
+        })
+        .collect();
+    Ok(Baseline {
+        patterns: Patterns::from_bytes(&file, cone),
+        file,
+        index,
+        expected,
+    })
+}
+
+#[test]
+fn skip_worktree_flags_match_git() -> crate::Result {
+    for name in ["cone", "cone-nested", "no-cone", "no-cone-dirs"] {
+        let Baseline {
+            patterns,
+            index,
+            expected,
+            ..
+        } = baseline(name)?;
+        assert_eq!(patterns.is_cone(), !name.starts_with("no-cone"), "{name}");
+
+        let mut state = index.into_parts().0;
+        for entry in state.entries_mut() {
+            entry.flags.remove(Flags::SKIP_WORKTREE | Flags::EXTENDED);
+        }
+        let changes = update_index(&mut state, &patterns, Case::Sensitive);
+        assert!(changes.included.is_empty(), "{name}: nothing was skipped before");
+
+        let actual: Vec<_> = state
+            .entries()
+            .iter()
+            .map(|e| {
+                (
+                    e.flags.contains(Flags::SKIP_WORKTREE),
+                    e.path(&state).to_str_lossy().into_owned(),
+                )
+            })
+            .collect();
+        assert_eq!(actual, expected, "{name}");
+        assert_eq!(
+            changes.excluded.len(),
+            expected.iter().filter(|(skipped, _)| *skipped).count(),
+            "{name}"
+        );
+
+        let changes = update_index(&mut state, &patterns, Case::Sensitive);
+        assert_eq!(
+            changes,
+            Default::default(),
+            "{name}: applying the same patterns again changes nothing"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn cone_patterns_are_written_like_git() -> crate::Result {
+    for name in ["cone", "cone-nested"] {
+        let Baseline { patterns, file, .. } = baseline(name)?;
+        let mut buf = Vec::new();
+        patterns.write_to(&mut buf)?;
+        assert_eq!(buf.as_bstr(), file.as_bstr(), "{name}");
+
+        let dirs: Vec<_> = patterns.cone_directories().expect("cone mode").collect();
+        let mut buf = Vec::new();
+        Patterns::from_cone_directories(dirs).write_to(&mut buf)?;
+        assert_eq!(
+            buf.as_bstr(),
+            file.as_bstr(),
+            "{name}: directories are all that's needed"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn cone_directories_are_normalized() {
+    let patterns = Patterns::from_cone_directories(["/a/b/", "a/b/c", "d", ""]);
+    assert_eq!(
+        patterns.cone_directories().expect("cone mode").collect::<Vec<_>>(),
+        [BStr::new("a/b"), "d".into()],
+        "slashes are trimmed and contained directories are removed"
+    );
+    for (path, is_dir, expected) in [
+        ("file", false, true),
+        ("a", true, true),
+        ("a/file", false, true),
+        ("a/x", true, false),
+        ("a/x/file", false, false),
+        ("a/b", true, true),
+        ("a/b/c/file", false, true),
+        ("d/e/file", false, true),
+        ("D/file", false, false),
+    ] {
+        assert_eq!(
+            patterns.is_included(path.into(), is_dir, Case::Sensitive),
+            expected,
+            "{path}"
+        );
+    }
+    assert!(patterns.is_included("D/file".into(), false, Case::Fold));
+}
+
+#[test]
+fn non_cone_patterns_are_used_if_cone_patterns_are_invalid() {
+    let patterns = Patterns::from_bytes(b"/*\n!/*/\n/a/*.txt\n", true);
+    assert!(!patterns.is_cone());
+    assert!(patterns.cone_directories().is_none());
+    assert!(patterns.is_included("a/x.txt".into(), false, Case::Sensitive));
+    assert!(!patterns.is_included("a/file".into(), false, Case::Sensitive));
+}
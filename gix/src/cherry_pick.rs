@@ -0,0 +1,123 @@
+//! Apply the changes introduced by a commit onto `HEAD` like `git cherry-pick`, or undo them like `git revert`.
+//!
+//! Both merge the changes into the index and the worktree with the parent of the commit as merge base,
+//! see [`Repository::cherry_pick()`](crate::Repository::cherry_pick()) and [`Repository::revert()`](crate::Repository::revert()).
+//! If there are conflicts, [`CHERRY_PICK_HEAD`](crate::cherry_pick::CHERRY_PICK_HEAD) or
+//! [`REVERT_HEAD`](crate::cherry_pick::REVERT_HEAD) are written to the `git` directory, along with the message for the commit
+//! that concludes the operation in `MERGE_MSG`, so that `git commit` can be used after resolving them.
+use crate::bstr::BString;
+
+/// The name of the file in the `git` directory that refers to the commit that is being cherry-picked while there are conflicts.
+pub const CHERRY_PICK_HEAD: &str = "CHERRY_PICK_HEAD";
+/// The name of the file in the `git` directory that refers to the commit that is being reverted while there are conflicts.
+pub const REVERT_HEAD: &str = "REVERT_HEAD";
+/// The name of the file in the `git` directory with the message to use for the commit that concludes the operation.
+pub const MERGE_MSG: &str = "MERGE_MSG";
+
+/// Options for [`Repository::cherry_pick()`](crate::Repository::cherry_pick())
+/// and [`Repository::revert()`](crate::Repository::revert()).
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Options {
+    /// The 1-based number of the parent of a merge commit whose changes relative to the merge commit should be applied,
+    /// like `git cherry-pick --mainline <n>`. It must be set for merge commits, and must not be set for other commits.
+    pub mainline: Option<usize>,
+    /// If `true`, the changes are applied to the index and the worktree without committing them, like `git cherry-pick --no-commit`.
+    /// The message for the commit is written to `MERGE_MSG`, and the index may differ from `HEAD` beforehand.
+    pub no_commit: bool,
+    /// If `true`, a line saying `(cherry picked from commit <id>)` is appended to the message of a cherry-picked commit,
+    /// like `git cherry-pick -x`. It's ignored when reverting.
+    pub record_origin: bool,
+    /// If `true`, a commit is created even if it doesn't change the tree of `HEAD` because its changes are already present,
+    /// instead of failing with [`Error::Empty`].
+    pub allow_empty: bool,
+}
+
+/// The outcome of [`Repository::cherry_pick()`](crate::Repository::cherry_pick())
+/// and [`Repository::revert()`](crate::Repository::revert()).
+#[derive(Debug)]
+pub struct Outcome {
+    /// The id of the newly created commit that `HEAD` now points to, or `None` if there were conflicts
+    /// or if [`Options::no_commit`] was set.
+    pub commit: Option<gix_hash::ObjectId>,
+    /// The unresolved conflicts of merging the changes into `HEAD`. Their stages were written to the index,
+    /// and conflict markers to the worktree.
+    pub conflicts: Vec<crate::merge::tree::Conflict>,
+    /// The outcome of updating the worktree.
+    pub worktree: crate::repository::update_worktree::Outcome,
+}
+
+/// The error returned by [`Repository::cherry_pick()`](crate::Repository::cherry_pick())
+/// and [`Repository::revert()`](crate::Repository::revert()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Repository at \"{}\" is a bare repository and has no worktree to apply changes to", git_dir.display())]
+    BareRepository { git_dir: std::path::PathBuf },
+    #[error("Cannot cherry-pick or revert while another operation is in progress: {state:?}")]
+    InProgress { state: crate::state::InProgress },
+    #[error(transparent)]
+    FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error("Commit {id} is a merge but no mainline was specified")]
+    MissingMainline { id: gix_hash::ObjectId },
+    #[error("A mainline was specified but commit {id} is not a merge")]
+    UnexpectedMainline { id: gix_hash::ObjectId },
+    #[error("Commit {id} does not have parent number {mainline}")]
+    InvalidMainline { id: gix_hash::ObjectId, mainline: usize },
+    #[error(transparent)]
+    HeadCommit(#[from] crate::reference::head_commit::Error),
+    #[error(transparent)]
+    ShortId(#[from] crate::id::shorten::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error("Could not expand the sparse index")]
+    ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+    #[error("Cannot cherry-pick or revert while the index has conflicts")]
+    UnmergedIndex,
+    #[error(transparent)]
+    WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+    #[error("The index contains changes that aren't committed yet")]
+    DirtyIndex,
+    #[error(transparent)]
+    TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+    #[error(transparent)]
+    MergeTrees(#[from] crate::repository::merge_trees::Error),
+    #[error(transparent)]
+    WriteTree(#[from] crate::object::tree::editor::write::Error),
+    #[error("The changes of commit {id} are already present, there is nothing to commit")]
+    Empty { id: gix_hash::ObjectId },
+    #[error(transparent)]
+    SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+    #[error(transparent)]
+    IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+    #[error("The local changes to these files would be overwritten: {paths:?}")]
+    WouldOverwriteLocalChanges { paths: Vec<BString> },
+    #[error(transparent)]
+    Status(#[from] crate::status::Error),
+    #[error(transparent)]
+    StatusIter(#[from] crate::status::into_iter::Error),
+    #[error(transparent)]
+    StatusItem(#[from] crate::status::index_worktree::Error),
+    #[error(transparent)]
+    UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+    #[error("Could not collapse the index into a sparse index")]
+    CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+    #[error(transparent)]
+    IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+    #[error(transparent)]
+    WriteIndex(#[from] gix_index::file::write::Error),
+    #[error(transparent)]
+    ParseTime(#[from] crate::config::time::Error),
+    #[error("Committer identity is not configured")]
+    CommitterMissing,
+    #[error("Author identity is not configured")]
+    AuthorMissing,
+    #[error(transparent)]
+    Commit(#[from] crate::commit::Error),
+    #[error("Could not write '{}'", path.display())]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
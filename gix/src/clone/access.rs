@@ -35,6 +35,15 @@ impl PrepareFetch {
         self
     }
 
+    /// Make this a partial clone which omits objects according to the filter `spec`, like `blob:none`.
+    ///
+    /// The remote is configured as promisor remote and as `extensions.partialClone`, so that missing objects
+    /// are fetched from it when they are needed.
+    pub fn with_filter(mut self, spec: impl Into<String>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
+
     /// Apply the given configuration `values` right before readying the actual fetch from the remote.
     /// The configuration is marked with [source API](gix_config::Source::Api), and will not be written back, it's
     /// retained only in memory.
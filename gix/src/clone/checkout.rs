@@ -22,8 +22,16 @@ pub mod main_worktree {
         #[error("Couldn't obtain configuration for core.protect*")]
         BooleanConfig(#[from] crate::config::boolean::Error),
         #[error(transparent)]
+        IndexVersion(#[from] crate::config::key::GenericError),
+        #[error(transparent)]
+        SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+        #[error("Could not collapse the index into a sparse index")]
+        CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+        #[error(transparent)]
         WriteIndex(#[from] gix_index::file::write::Error),
         #[error(transparent)]
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+        #[error(transparent)]
         CheckoutOptions(#[from] crate::config::checkout_options::Error),
         #[error(transparent)]
         IndexCheckout(#[from] gix_worktree_state::checkout::Error),
@@ -35,6 +43,12 @@ pub mod main_worktree {
         FindHead(#[from] crate::reference::find::existing::Error),
         #[error("The HEAD reference could not be located")]
         PeelHeadToId(#[from] crate::head::peel::Error),
+        #[error("Could not fetch the missing objects to check out from the promisor remote")]
+        PromisorFetch(#[source] gix_odb::promisor::Error),
+        #[error(transparent)]
+        FindHook(#[from] crate::hook::find::Error),
+        #[error("The post-checkout hook failed after the checkout")]
+        Hook(#[from] crate::hook::run::Error),
     }
 
     /// The progress ids used in [`PrepareCheckout::main_worktree()`].
@@ -65,6 +79,9 @@ pub mod main_worktree {
         /// Note that this is a no-op if the remote was empty, leaving this repository empty as well. This can be validated by checking
         /// if the `head()` of the returned repository is *not* unborn.
         ///
+        /// Like `git clone`, the [`post-checkout` hook](crate::Repository::hook()) is run after the checkout
+        /// if [enabled](crate::open::Options::run_hooks()).
+        ///
         /// # Panics
         ///
         /// If called after it was successful. The reason here is that it auto-deletes the contained repository,
@@ -101,8 +118,11 @@ pub mod main_worktree {
                 None => repo.head()?.try_peel_to_id()?,
             };
 
-            let root_tree = match root_tree_id {
-                Some(id) => id.object().expect("downloaded from remote").peel_to_tree()?.id,
+            let (head_id, root_tree) = match root_tree_id {
+                Some(id) => (
+                    id.detach(),
+                    id.object().expect("downloaded from remote").peel_to_tree()?.id,
+                ),
                 None => {
                     return Ok((
                         self.repo.take().expect("still present"),
@@ -111,12 +131,19 @@ pub mod main_worktree {
                 }
             };
 
-            let index = gix_index::State::from_tree(&root_tree, &repo.objects, repo.config.protect_options()?)
+            let mut index = gix_index::State::from_tree(&root_tree, &repo.objects, repo.config.protect_options()?)
                 .map_err(|err| Error::IndexFromTree {
                     id: root_tree,
                     source: err,
                 })?;
+            if let Some(version) = repo.config.index_version()? {
+                index.set_version(version);
+            }
             let mut index = gix_index::File::from_state(index, repo.index_path());
+            let sparse_checkout_patterns = repo.sparse_checkout_patterns()?;
+            if let Some(patterns) = &sparse_checkout_patterns {
+                gix_worktree::sparse::update_index(&mut index, patterns, repo.sparse_checkout_case());
+            }
 
             let mut opts = repo.checkout_options(gix_worktree::stack::state::attributes::Source::IdMapping)?;
             opts.destination_is_initially_empty = true;
@@ -127,6 +154,20 @@ pub mod main_worktree {
             files.init(Some(index.entries().len()), crate::progress::count("files"));
             bytes.init(None, crate::progress::bytes());
 
+            // In partial clones, fetch all missing blobs at once instead of one by one.
+            repo.objects
+                .prefetch(
+                    index
+                        .entries()
+                        .iter()
+                        .filter(|entry| {
+                            entry.mode != gix_index::entry::Mode::COMMIT
+                                && !entry.flags.contains(gix_index::entry::Flags::SKIP_WORKTREE)
+                        })
+                        .map(|entry| entry.id),
+                )
+                .map_err(Error::PromisorFetch)?;
+
             let start = std::time::Instant::now();
             let outcome = gix_worktree_state::checkout(
                 &mut index,
@@ -140,7 +181,15 @@ pub mod main_worktree {
             files.show_throughput(start);
             bytes.show_throughput(start);
 
-            index.write(Default::default())?;
+            if let Some(patterns) = &sparse_checkout_patterns {
+                repo.collapse_sparse_index(&mut index, patterns)?;
+            }
+            index.write(repo.index_write_options(&index)?)?;
+
+            if let Some(hook) = repo.hook_to_run("post-checkout")? {
+                hook.args([repo.object_hash().null().to_string(), head_id.to_string(), "1".into()])
+                    .run()?;
+            }
             Ok((self.repo.take().expect("still present").clone(), outcome))
         }
     }
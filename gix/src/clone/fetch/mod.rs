@@ -52,6 +52,10 @@ pub enum Error {
     RefMap(#[from] crate::remote::ref_map::Error),
     #[error(transparent)]
     ReferenceName(#[from] gix_validate::reference::name::Error),
+    #[error("Could not configure the remote as promisor remote in memory")]
+    PromisorConfig(#[from] crate::config::set_value::Error),
+    #[error(transparent)]
+    RegisterPromisorRemote(#[from] crate::remote::promisor::register::Error),
 }
 
 /// Modification
@@ -105,6 +109,15 @@ impl PrepareFetch {
                 .unwrap_or_else(|| "origin".into()),
         };
 
+        // Configure the partial clone in memory for the fetch to use the filter, and to be able to fetch missing objects later.
+        if let Some(filter) = &self.filter {
+            use crate::config::tree::{Extensions, Remote};
+            let mut config = repo.config_snapshot_mut();
+            config.set_subsection_value(&Remote::PROMISOR, remote_name.as_bstr(), "true")?;
+            config.set_subsection_value(&Remote::PARTIAL_CLONE_FILTER, remote_name.as_bstr(), filter.as_str())?;
+            config.set_value(&Extensions::PARTIAL_CLONE, remote_name.as_bstr())?;
+        }
+
         let mut remote = repo.remote_at(self.url.clone())?;
 
         // For shallow clones without custom configuration, we'll use a single-branch refspec
@@ -204,6 +217,9 @@ impl PrepareFetch {
         }
 
         let config = util::write_remote_to_local_config_file(&mut remote, remote_name.clone())?;
+        if let Some(filter) = &self.filter {
+            remote::promisor::register(&repo.common_dir().join("config"), remote_name.as_bstr(), filter)?;
+        }
 
         // Now we are free to apply remote configuration we don't want to be written to disk.
         if let Some(fetch_tags) = clone_fetch_tags {
@@ -22,6 +22,24 @@ pub enum Error {
     WriteObject(#[from] crate::object::write::Error),
     #[error(transparent)]
     ReferenceEdit(#[from] crate::reference::edit::Error),
+    #[cfg(feature = "command")]
+    #[error(transparent)]
+    FindHook(#[from] crate::hook::find::Error),
+    #[cfg(feature = "command")]
+    #[error(transparent)]
+    Hook(#[from] crate::hook::run::Error),
+    #[cfg(feature = "command")]
+    #[error("Could not write or read the commit message file for the commit-msg hook")]
+    CommitMessageFile(#[source] std::io::Error),
+    #[cfg(feature = "command")]
+    #[error(transparent)]
+    ConfigBoolean(#[from] crate::config::boolean::Error),
+    #[cfg(feature = "command")]
+    #[error(transparent)]
+    Signer(#[from] crate::gpg::signer::Error),
+    #[cfg(feature = "command")]
+    #[error("Could not sign the commit")]
+    Sign(#[from] crate::gpg::sign::Error),
 }
 
 impl From<std::convert::Infallible> for Error {
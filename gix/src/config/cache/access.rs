@@ -178,8 +178,44 @@ impl Cache {
             .unwrap_or(512 * 1024 * 1024))
     }
 
+    /// Return the way objects that aren't yet in a pack are written into newly generated packs, as configured
+    /// by `pack.window` and `pack.depth`.
+    #[cfg(any(
+        feature = "async-network-client",
+        feature = "blocking-network-client",
+        feature = "blocking-network-server"
+    ))]
+    pub(crate) fn pack_generation_mode(
+        &self,
+    ) -> Result<gix_pack::data::output::entry::iter_from_counts::Mode, config::unsigned_integer::Error> {
+        use config::tree::Pack;
+        use gix_pack::data::output::entry::iter_from_counts::Mode;
+        let value = |key: &'static config::tree::keys::UnsignedInteger, default: usize| {
+            Ok::<_, config::unsigned_integer::Error>(
+                self.resolved
+                    .integer(key)
+                    .map(|number| key.try_into_usize(number))
+                    .transpose()
+                    .with_leniency(self.lenient_config)?
+                    .unwrap_or(default),
+            )
+        };
+        let window = value(&Pack::WINDOW, 10)?;
+        // Like git, we don't allow delta chains to become longer than what it can handle.
+        let depth = value(&Pack::DEPTH, 50)?.min(4095);
+        Ok(if window == 0 || depth == 0 {
+            Mode::PackCopyAndBaseObjects
+        } else {
+            Mode::PackCopyAndDeltaCompression { window, depth }
+        })
+    }
+
     /// Returns a user agent for use with servers.
-    #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+    #[cfg(any(
+        feature = "async-network-client",
+        feature = "blocking-network-client",
+        feature = "blocking-network-server"
+    ))]
     pub(crate) fn user_agent_tuple(&self) -> (&'static str, Option<Cow<'static, str>>) {
         use config::tree::Gitoxide;
         let agent = self
@@ -228,6 +264,14 @@ impl Cache {
         })
     }
 
+    /// Return `true` if objects should be signed according to `key`, which is `commit.gpgSign` or `tag.gpgSign`.
+    #[cfg(feature = "command")]
+    pub(crate) fn gpg_sign(&self, key: &'static config::tree::keys::Boolean) -> Result<bool, config::boolean::Error> {
+        Ok(self
+            .apply_leniency(self.resolved.boolean(key).map(|v| key.enrich_error(v)))?
+            .unwrap_or(false))
+    }
+
     /// Returns (file-timeout, pack-refs timeout)
     pub(crate) fn lock_timeout(
         &self,
@@ -301,6 +345,16 @@ impl Cache {
         })
     }
 
+    /// Return the version of new index files as configured by `index.version`, if set.
+    #[cfg(feature = "index")]
+    pub(crate) fn index_version(&self) -> Result<Option<gix_index::Version>, config::key::GenericError> {
+        self.resolved
+            .integer(config::tree::Index::VERSION)
+            .map(|value| config::tree::Index::VERSION.try_into_index_version(value))
+            .transpose()
+            .with_leniency(self.lenient_config)
+    }
+
     #[cfg(any(feature = "index", feature = "tree-editor"))]
     pub(crate) fn protect_options(&self) -> Result<gix_validate::path::component::Options, config::boolean::Error> {
         const IS_WINDOWS: bool = cfg!(windows);
@@ -16,6 +16,7 @@ pub(crate) struct StageOne {
     pub lossy: bool,
     pub object_hash: gix_hash::Kind,
     pub reflog: Option<gix_ref::store::WriteReflog>,
+    pub ref_format: gix_ref::store::Format,
     pub precompose_unicode: bool,
     pub protect_windows: bool,
 }
@@ -54,6 +55,11 @@ impl StageOne {
             })
             .transpose()?
             .unwrap_or(gix_hash::Kind::Sha1);
+        let ref_format = config
+            .string(Extensions::REF_STORAGE)
+            .map(|format| Extensions::REF_STORAGE.try_into_ref_storage(format))
+            .transpose()?
+            .unwrap_or_default();
 
         let extension_worktree = util::config_bool(
             &config,
@@ -98,6 +104,7 @@ impl StageOne {
             lossy,
             object_hash,
             reflog,
+            ref_format,
             precompose_unicode,
             protect_windows,
         })
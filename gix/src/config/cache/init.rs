@@ -27,6 +27,7 @@ impl Cache {
             is_bare,
             object_hash,
             reflog: _,
+            ref_format: _,
             precompose_unicode: _,
             protect_windows: _,
         }: StageOne,
@@ -299,6 +300,13 @@ impl crate::Repository {
         {
             setup_objects(&mut self.objects, &self.config);
         }
+        #[cfg(feature = "blocking-network-client")]
+        crate::remote::promisor::setup(
+            &mut self.objects,
+            &self.config.resolved,
+            self.refs.git_dir(),
+            &self.options,
+        );
         Ok(())
     }
 
@@ -0,0 +1,19 @@
+use crate::{
+    config,
+    config::tree::{keys, Commit, Key, Section},
+};
+
+impl Commit {
+    /// The `commit.gpgSign` key.
+    pub const GPG_SIGN: keys::Boolean = keys::Boolean::new_boolean("gpgSign", &config::Tree::COMMIT);
+}
+
+impl Section for Commit {
+    fn name(&self) -> &str {
+        "commit"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::GPG_SIGN]
+    }
+}
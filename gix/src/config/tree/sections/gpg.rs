@@ -0,0 +1,235 @@
+use crate::{
+    config,
+    config::tree::{keys, Gpg, Key, Section},
+};
+
+impl Gpg {
+    /// The `gpg.format` key.
+    pub const FORMAT: Format = Format::new_with_validate("format", &config::Tree::GPG, validate::Format);
+    /// The `gpg.program` key.
+    pub const PROGRAM: keys::Program = keys::Program::new_program("program", &config::Tree::GPG)
+        .with_note("used for the 'openpgp' format if gpg.openpgp.program isn't set");
+    /// The `gpg.minTrustLevel` key.
+    pub const MIN_TRUST_LEVEL: MinTrustLevel =
+        MinTrustLevel::new_with_validate("minTrustLevel", &config::Tree::GPG, validate::MinTrustLevel);
+
+    /// The `gpg.openpgp` section.
+    pub const OPENPGP: OpenPgp = OpenPgp;
+    /// The `gpg.x509` section.
+    pub const X509: X509 = X509;
+    /// The `gpg.ssh` section.
+    pub const SSH: Ssh = Ssh;
+}
+
+/// The `gpg.format` key.
+pub type Format = keys::Any<validate::Format>;
+
+/// The kind of signatures to create, as configured by `gpg.format`.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum SignatureFormat {
+    /// Signatures created with `gpg`.
+    #[default]
+    OpenPgp,
+    /// Signatures based on X.509 certificates, created with `gpgsm`.
+    X509,
+    /// Signatures created with `ssh-keygen`.
+    Ssh,
+}
+
+/// The `gpg.minTrustLevel` key.
+pub type MinTrustLevel = keys::Any<validate::MinTrustLevel>;
+
+/// The trust `gpg` or `gpgsm` have in the key that made a signature, ordered from least to most trusted.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TrustLevel {
+    /// The trust in the key is unknown.
+    #[default]
+    Undefined,
+    /// The key is explicitly not trusted.
+    Never,
+    /// The key is marginally trusted.
+    Marginal,
+    /// The key is fully trusted.
+    Fully,
+    /// The key is ultimately trusted, typically as it's one of our own.
+    Ultimate,
+}
+
+impl SignatureFormat {
+    /// Return the name of this format as used in `gpg.format` and in the name of the `gpg.<format>` subsections.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SignatureFormat::OpenPgp => "openpgp",
+            SignatureFormat::X509 => "x509",
+            SignatureFormat::Ssh => "ssh",
+        }
+    }
+}
+
+impl Section for Gpg {
+    fn name(&self) -> &str {
+        "gpg"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::FORMAT, &Self::PROGRAM, &Self::MIN_TRUST_LEVEL]
+    }
+
+    fn sub_sections(&self) -> &[&dyn Section] {
+        &[&Self::OPENPGP, &Self::X509, &Self::SSH]
+    }
+}
+
+mod format {
+    use std::borrow::Cow;
+
+    use crate::{
+        bstr::{BStr, ByteSlice},
+        config,
+        config::tree::gpg::{Format, SignatureFormat},
+    };
+
+    impl Format {
+        /// Try to interpret `value` as the format of signatures, which is one of `openpgp`, `x509` or `ssh`.
+        pub fn try_into_format(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<SignatureFormat, config::key::GenericErrorWithValue> {
+            Ok(match value.as_ref().as_bytes() {
+                b"openpgp" => SignatureFormat::OpenPgp,
+                b"x509" => SignatureFormat::X509,
+                b"ssh" => SignatureFormat::Ssh,
+                _ => return Err(config::key::GenericErrorWithValue::from_value(self, value.into_owned())),
+            })
+        }
+    }
+}
+
+mod min_trust_level {
+    use std::borrow::Cow;
+
+    use crate::{
+        bstr::BStr,
+        config,
+        config::tree::gpg::{MinTrustLevel, TrustLevel},
+    };
+
+    impl MinTrustLevel {
+        /// Try to interpret `value` as trust level, which is one of `undefined`, `never`, `marginal`, `fully` or `ultimate`,
+        /// ignoring case.
+        pub fn try_into_trust_level(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<TrustLevel, config::key::GenericErrorWithValue> {
+            Ok(match value.as_ref().to_ascii_lowercase().as_slice() {
+                b"undefined" => TrustLevel::Undefined,
+                b"never" => TrustLevel::Never,
+                b"marginal" => TrustLevel::Marginal,
+                b"fully" => TrustLevel::Fully,
+                b"ultimate" => TrustLevel::Ultimate,
+                _ => return Err(config::key::GenericErrorWithValue::from_value(self, value.into_owned())),
+            })
+        }
+    }
+}
+
+mod subsections {
+    use crate::config::{
+        tree::{keys, Gpg, Key, Section},
+        Tree,
+    };
+
+    /// The `gpg.openpgp` sub-section.
+    #[derive(Copy, Clone, Default)]
+    pub struct OpenPgp;
+
+    impl OpenPgp {
+        /// The `gpg.openpgp.program` key.
+        pub const PROGRAM: keys::Program = keys::Program::new_program("program", &Gpg::OPENPGP);
+    }
+
+    impl Section for OpenPgp {
+        fn name(&self) -> &str {
+            "openpgp"
+        }
+
+        fn keys(&self) -> &[&dyn Key] {
+            &[&Self::PROGRAM]
+        }
+
+        fn parent(&self) -> Option<&dyn Section> {
+            Some(&Tree::GPG)
+        }
+    }
+
+    /// The `gpg.x509` sub-section.
+    #[derive(Copy, Clone, Default)]
+    pub struct X509;
+
+    impl X509 {
+        /// The `gpg.x509.program` key.
+        pub const PROGRAM: keys::Program = keys::Program::new_program("program", &Gpg::X509);
+    }
+
+    impl Section for X509 {
+        fn name(&self) -> &str {
+            "x509"
+        }
+
+        fn keys(&self) -> &[&dyn Key] {
+            &[&Self::PROGRAM]
+        }
+
+        fn parent(&self) -> Option<&dyn Section> {
+            Some(&Tree::GPG)
+        }
+    }
+
+    /// The `gpg.ssh` sub-section.
+    #[derive(Copy, Clone, Default)]
+    pub struct Ssh;
+
+    impl Ssh {
+        /// The `gpg.ssh.program` key.
+        pub const PROGRAM: keys::Program = keys::Program::new_program("program", &Gpg::SSH);
+        /// The `gpg.ssh.allowedSignersFile` key.
+        pub const ALLOWED_SIGNERS_FILE: keys::Path = keys::Path::new_path("allowedSignersFile", &Gpg::SSH);
+    }
+
+    impl Section for Ssh {
+        fn name(&self) -> &str {
+            "ssh"
+        }
+
+        fn keys(&self) -> &[&dyn Key] {
+            &[&Self::PROGRAM, &Self::ALLOWED_SIGNERS_FILE]
+        }
+
+        fn parent(&self) -> Option<&dyn Section> {
+            Some(&Tree::GPG)
+        }
+    }
+}
+pub use subsections::{OpenPgp, Ssh, X509};
+
+mod validate {
+    use std::{borrow::Cow, error::Error};
+
+    use crate::{bstr::BStr, config::tree::keys::Validate};
+
+    pub struct Format;
+    impl Validate for Format {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+            super::Gpg::FORMAT.try_into_format(Cow::Borrowed(value))?;
+            Ok(())
+        }
+    }
+
+    pub struct MinTrustLevel;
+    impl Validate for MinTrustLevel {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+            super::Gpg::MIN_TRUST_LEVEL.try_into_trust_level(Cow::Borrowed(value))?;
+            Ok(())
+        }
+    }
+}
@@ -10,6 +10,35 @@ impl Index {
     /// The `index.skipHash` key.
     pub const SKIP_HASH: keys::Boolean = keys::Boolean::new_boolean("skipHash", &config::Tree::INDEX)
         .with_deviation("also used to skip the hash when reading, even if a hash exists in the index file");
+    /// The `index.sparse` key.
+    pub const SPARSE: keys::Boolean = keys::Boolean::new_boolean("sparse", &config::Tree::INDEX);
+    /// The `index.version` key.
+    pub const VERSION: IndexVersion =
+        IndexVersion::new_with_validate("version", &config::Tree::INDEX, validate::IndexVersion);
+}
+
+/// The `index.version` key.
+pub type IndexVersion = keys::Any<validate::IndexVersion>;
+
+#[cfg(feature = "index")]
+mod index_version {
+    use crate::{config, config::tree::sections::index::IndexVersion};
+
+    impl IndexVersion {
+        /// Try to interpret an integer value as the version of new index files.
+        pub fn try_into_index_version(
+            &'static self,
+            value: Result<i64, gix_config::value::Error>,
+        ) -> Result<gix_index::Version, config::key::GenericError> {
+            let value = value.map_err(|err| config::key::GenericError::from(self).with_source(err))?;
+            Ok(match value {
+                2 => gix_index::Version::V2,
+                3 => gix_index::Version::V3,
+                4 => gix_index::Version::V4,
+                _ => return Err(config::key::GenericError::from(self)),
+            })
+        }
+    }
 }
 
 /// The `index.threads` key.
@@ -50,7 +79,7 @@ impl Section for Index {
     }
 
     fn keys(&self) -> &[&dyn Key] {
-        &[&Self::THREADS, &Self::SKIP_HASH]
+        &[&Self::THREADS, &Self::SKIP_HASH, &Self::SPARSE, &Self::VERSION]
     }
 }
 
@@ -64,4 +93,17 @@ mod validate {
             Ok(())
         }
     }
+
+    pub struct IndexVersion;
+    impl keys::Validate for IndexVersion {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            let version = gix_config::Integer::try_from(value)?
+                .to_decimal()
+                .ok_or_else(|| gix_config::value::Error::new("integer out of range", value))?;
+            if !(2..=4).contains(&version) {
+                return Err(gix_config::value::Error::new("index versions 2, 3 and 4 are supported", value).into());
+            }
+            Ok(())
+        }
+    }
 }
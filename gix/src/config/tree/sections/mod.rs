@@ -21,6 +21,16 @@ pub mod checkout;
 pub struct Clone;
 mod clone;
 
+/// The `commit` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Commit;
+mod commit;
+
+/// The `commitGraph` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct CommitGraph;
+pub mod commit_graph;
+
 /// The `committer` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Committer;
@@ -58,6 +68,11 @@ pub mod fetch;
 pub struct Gitoxide;
 pub mod gitoxide;
 
+/// The `gpg` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Gpg;
+pub mod gpg;
+
 /// The `http` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Http;
@@ -96,6 +111,11 @@ pub mod protocol;
 pub struct Push;
 pub mod push;
 
+/// The `receive` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Receive;
+pub mod receive;
+
 /// The `remote` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Remote;
@@ -106,6 +126,11 @@ pub mod remote;
 pub struct Safe;
 mod safe;
 
+/// The `splitIndex` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct SplitIndex;
+pub mod split_index;
+
 /// The `ssh` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct Ssh;
@@ -118,6 +143,11 @@ pub struct Status;
 #[cfg(feature = "status")]
 pub mod status;
 
+/// The `tag` top-level section.
+#[derive(Copy, Clone, Default)]
+pub struct Tag;
+mod tag;
+
 /// The `user` top-level section.
 #[derive(Copy, Clone, Default)]
 pub struct User;
@@ -0,0 +1,102 @@
+use crate::{
+    config,
+    config::tree::{keys, Key, Receive, Section},
+};
+
+impl Receive {
+    /// The `receive.denyCurrentBranch` key.
+    pub const DENY_CURRENT_BRANCH: Deny =
+        Deny::new_with_validate("denyCurrentBranch", &config::Tree::RECEIVE, validate::Deny)
+            .with_deviation("'updateInstead' is not supported and refuses the update instead");
+    /// The `receive.denyDeleteCurrent` key.
+    pub const DENY_DELETE_CURRENT: Deny =
+        Deny::new_with_validate("denyDeleteCurrent", &config::Tree::RECEIVE, validate::Deny);
+    /// The `receive.denyDeletes` key.
+    pub const DENY_DELETES: keys::Boolean = keys::Boolean::new_boolean("denyDeletes", &config::Tree::RECEIVE);
+    /// The `receive.denyNonFastForwards` key.
+    pub const DENY_NON_FAST_FORWARDS: keys::Boolean =
+        keys::Boolean::new_boolean("denyNonFastForwards", &config::Tree::RECEIVE);
+}
+
+impl Section for Receive {
+    fn name(&self) -> &str {
+        "receive"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[
+            &Self::DENY_CURRENT_BRANCH,
+            &Self::DENY_DELETE_CURRENT,
+            &Self::DENY_DELETES,
+            &Self::DENY_NON_FAST_FORWARDS,
+        ]
+    }
+}
+
+/// The `receive.denyCurrentBranch` and `receive.denyDeleteCurrent` keys.
+pub type Deny = keys::Any<validate::Deny>;
+
+/// What to do when a client pushes a change that is restricted by a [`Deny`] key.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DenyAction {
+    /// Allow the change.
+    Ignore,
+    /// Allow the change, but tell the client about it.
+    Warn,
+    /// Reject the change.
+    Refuse,
+}
+
+mod deny {
+    use std::borrow::Cow;
+
+    use crate::{
+        bstr::{BStr, ByteSlice},
+        config,
+        config::tree::receive::{Deny, DenyAction},
+    };
+
+    impl Deny {
+        /// Try to interpret `value` as action to take, which is either one of `ignore`, `warn`, `refuse`
+        /// or `updateInstead`, or a boolean.
+        ///
+        /// ### Deviation
+        ///
+        /// `updateInstead` is treated like `refuse` as the worktree isn't updated when receiving a push.
+        pub fn try_into_action(
+            &'static self,
+            value: Cow<'_, BStr>,
+        ) -> Result<DenyAction, config::key::GenericErrorWithValue> {
+            Ok(match value.as_ref().as_bytes() {
+                b"ignore" => DenyAction::Ignore,
+                b"warn" => DenyAction::Warn,
+                b"refuse" | b"updateInstead" => DenyAction::Refuse,
+                _ => {
+                    let value = gix_config::Boolean::try_from(value.as_ref()).map_err(|err| {
+                        config::key::GenericErrorWithValue::from_value(self, value.clone().into_owned())
+                            .with_source(err)
+                    })?;
+                    if value.0 {
+                        DenyAction::Refuse
+                    } else {
+                        DenyAction::Ignore
+                    }
+                }
+            })
+        }
+    }
+}
+
+mod validate {
+    use std::{borrow::Cow, error::Error};
+
+    use crate::{bstr::BStr, config::tree::keys::Validate};
+
+    pub struct Deny;
+    impl Validate for Deny {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
+            super::Receive::DENY_CURRENT_BRANCH.try_into_action(Cow::Borrowed(value))?;
+            Ok(())
+        }
+    }
+}
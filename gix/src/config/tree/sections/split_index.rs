@@ -0,0 +1,78 @@
+use crate::{
+    config,
+    config::tree::{keys, Key, Section, SplitIndex},
+};
+
+impl SplitIndex {
+    /// The `splitIndex.maxPercentChange` key.
+    pub const MAX_PERCENT_CHANGE: keys::UnsignedInteger =
+        keys::UnsignedInteger::new_unsigned_integer("maxPercentChange", &config::Tree::SPLIT_INDEX);
+    /// The `splitIndex.sharedIndexExpire` key.
+    pub const SHARED_INDEX_EXPIRE: SharedIndexExpire = SharedIndexExpire::new_with_validate(
+        "sharedIndexExpire",
+        &config::Tree::SPLIT_INDEX,
+        validate::SharedIndexExpire,
+    );
+}
+
+impl Section for SplitIndex {
+    fn name(&self) -> &str {
+        "splitIndex"
+    }
+
+    fn keys(&self) -> &[&dyn Key] {
+        &[&Self::MAX_PERCENT_CHANGE, &Self::SHARED_INDEX_EXPIRE]
+    }
+}
+
+/// The `splitIndex.sharedIndexExpire` key.
+pub type SharedIndexExpire = keys::Any<validate::SharedIndexExpire>;
+
+mod shared_index_expire {
+    use std::{borrow::Cow, time::SystemTime};
+
+    use crate::{
+        bstr::{BStr, ByteSlice},
+        config,
+        config::tree::split_index::SharedIndexExpire,
+    };
+
+    impl SharedIndexExpire {
+        /// Parse `value` into the time before which unused shared indices expire, with `now` as reference for relative dates,
+        /// or `None` if they should never expire.
+        ///
+        /// Besides all dates understood by [`gix_date::parse()`], this supports `never` and `now`, and allows the
+        /// components of relative dates to be separated by dots, as in `2.weeks.ago`.
+        pub fn try_into_expire(
+            &'static self,
+            value: Cow<'_, BStr>,
+            now: SystemTime,
+        ) -> Result<Option<SystemTime>, config::time::Error> {
+            let err = || config::time::Error::from_value(self, value.clone().into_owned());
+            let input = value.to_str().map_err(|_| err())?;
+            Ok(match input {
+                "never" | "false" => None,
+                "now" | "all" => Some(now),
+                _ => {
+                    let time = gix_date::parse(input, Some(now))
+                        .or_else(|_| gix_date::parse(&input.replace('.', " "), Some(now)))
+                        .map_err(|e| err().with_source(e.into_inner()))?;
+                    let seconds = u64::try_from(time.seconds).unwrap_or_default();
+                    Some(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(seconds))
+                }
+            })
+        }
+    }
+}
+
+mod validate {
+    use crate::{bstr::BStr, config::tree::keys};
+
+    pub struct SharedIndexExpire;
+    impl keys::Validate for SharedIndexExpire {
+        fn validate(&self, value: &BStr) -> Result<(), Box<dyn std::error::Error + Send + Sync + 'static>> {
+            super::SplitIndex::SHARED_INDEX_EXPIRE.try_into_expire(value.into(), std::time::SystemTime::now())?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Sign objects and verify their signatures with `gpg`, `gpgsm` or `ssh-keygen`, depending on the
+//! [signature format](SignatureFormat).
+//!
+//! Obtain a [`Signer`] with [`Repository::signer()`](crate::Repository::signer()), and a [`Verifier`] with
+//! [`Repository::signature_verifier()`](crate::Repository::signature_verifier()).
+use std::{
+    ffi::{OsStr, OsString},
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+use crate::bstr::{BString, ByteSlice};
+pub use crate::config::tree::gpg::{SignatureFormat, TrustLevel};
+
+///
+pub mod signer {
+    /// The error returned by [`Repository::signer()`](crate::Repository::signer()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Format(#[from] crate::config::key::GenericErrorWithValue),
+        #[error("user.signingKey must be set to sign with ssh")]
+        SshKeyMissing,
+        #[error("Could not interpolate the path to the ssh key in user.signingKey")]
+        SshKeyPath(#[from] gix_config::path::interpolate::Error),
+        #[error("Committer identity is not configured, and is needed as signing key if user.signingKey isn't set")]
+        CommitterMissing,
+        #[error(transparent)]
+        ParseTime(#[from] crate::config::time::Error),
+    }
+}
+
+///
+pub mod verifier {
+    /// The error returned by [`Repository::signature_verifier()`](crate::Repository::signature_verifier()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not interpolate the path in gpg.ssh.allowedSignersFile")]
+        AllowedSignersFile(#[from] gix_config::path::interpolate::Error),
+        #[error(transparent)]
+        MinTrustLevel(#[from] crate::config::key::GenericErrorWithValue),
+    }
+}
+
+///
+pub mod verify;
+
+///
+pub mod sign {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Signer::sign()`](crate::gpg::Signer::sign()) and related methods.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not run the signing program {program:?}")]
+        Io {
+            program: std::ffi::OsString,
+            source: std::io::Error,
+        },
+        #[error("The signing program {program:?} failed with {status}: {stderr}")]
+        Failed {
+            program: std::ffi::OsString,
+            status: std::process::ExitStatus,
+            stderr: BString,
+        },
+        #[error("The signing program {program:?} didn't create a signature: {stderr}")]
+        NoSignature {
+            program: std::ffi::OsString,
+            stderr: BString,
+        },
+        #[error("Could not write the literal ssh key in user.signingKey to a temporary file")]
+        WriteSshKey(#[source] std::io::Error),
+        #[error("Could not serialize the object to sign")]
+        Encode(#[source] std::io::Error),
+    }
+}
+
+/// The key to sign with, as configured by `user.signingKey`.
+#[derive(Debug, Clone)]
+pub(crate) enum Key {
+    /// The id of a `gpg` or `gpgsm` key, or a name and email address to find it by.
+    Id(BString),
+    /// The path to an ssh private key, or to a public key whose private key is held by `ssh-agent`.
+    SshFile(PathBuf),
+    /// A public ssh key, whose private key is held by `ssh-agent`.
+    SshLiteral(BString),
+}
+
+/// A utility to sign objects with the configured program and key, like `git` does.
+#[derive(Debug, Clone)]
+pub struct Signer {
+    format: SignatureFormat,
+    program: OsString,
+    key: Key,
+}
+
+/// Initialization
+impl Signer {
+    /// Create a new instance that signs in `format` with `program` and `key`.
+    pub(crate) fn new(format: SignatureFormat, program: OsString, key: Key) -> Self {
+        Signer { format, program, key }
+    }
+}
+
+/// Access
+impl Signer {
+    /// The format of the signatures that this instance creates.
+    pub fn format(&self) -> SignatureFormat {
+        self.format
+    }
+
+    /// The program used to create signatures.
+    pub fn program(&self) -> &std::ffi::OsStr {
+        &self.program
+    }
+}
+
+/// A utility to verify signatures with the program matching their format, like `git` does.
+#[derive(Debug, Clone)]
+pub struct Verifier {
+    openpgp_program: OsString,
+    x509_program: OsString,
+    ssh_program: OsString,
+    allowed_signers_file: Option<PathBuf>,
+    min_trust_level: TrustLevel,
+}
+
+/// Initialization
+impl Verifier {
+    /// Create a new instance which verifies signatures with the given programs, which trusts the ssh keys
+    /// in `allowed_signers_file`, and which considers signatures good only if their key is trusted at least
+    /// as much as `min_trust_level`.
+    pub(crate) fn new(
+        openpgp_program: OsString,
+        x509_program: OsString,
+        ssh_program: OsString,
+        allowed_signers_file: Option<PathBuf>,
+        min_trust_level: TrustLevel,
+    ) -> Self {
+        Verifier {
+            openpgp_program,
+            x509_program,
+            ssh_program,
+            allowed_signers_file,
+            min_trust_level,
+        }
+    }
+}
+
+/// Access
+impl Verifier {
+    /// The program used to verify signatures in `format`.
+    pub fn program(&self, format: SignatureFormat) -> &OsStr {
+        match format {
+            SignatureFormat::OpenPgp => &self.openpgp_program,
+            SignatureFormat::X509 => &self.x509_program,
+            SignatureFormat::Ssh => &self.ssh_program,
+        }
+    }
+
+    /// The file with the ssh keys that are trusted to make signatures, as configured by `gpg.ssh.allowedSignersFile`.
+    pub fn allowed_signers_file(&self) -> Option<&Path> {
+        self.allowed_signers_file.as_deref()
+    }
+
+    /// The trust level the key of a signature must have at least for the signature to be good, as configured by `gpg.minTrustLevel`.
+    pub fn min_trust_level(&self) -> TrustLevel {
+        self.min_trust_level
+    }
+}
+
+/// Signing
+impl Signer {
+    /// Sign `payload` and return the ASCII-armored signature.
+    pub fn sign(&self, payload: &[u8]) -> Result<BString, sign::Error> {
+        let mut cmd = gix_command::prepare(self.program.clone());
+        // Keep the temporary file alive until the program ran.
+        let mut _literal_key_file = None;
+        match &self.key {
+            Key::Id(key) => {
+                cmd = cmd
+                    .arg("--status-fd=2")
+                    .arg("-bsau")
+                    .arg(gix_path::from_bstr(key.as_bstr()).into_owned());
+            }
+            Key::SshFile(path) => {
+                cmd = cmd.args(["-Y", "sign", "-n", "git", "-f"]).arg(path);
+            }
+            Key::SshLiteral(key) => {
+                let key = key.strip_prefix(b"key::").unwrap_or(key);
+                let (file, path) = tempfile_with(&[key, b"\n"]).map_err(sign::Error::WriteSshKey)?;
+                cmd = cmd.args(["-Y", "sign", "-n", "git", "-f"]).arg(path).arg("-U");
+                _literal_key_file = Some(file);
+            }
+        }
+
+        let output = run(cmd, payload).map_err(|source| sign::Error::Io {
+            program: self.program.clone(),
+            source,
+        })?;
+
+        if !output.status.success() {
+            return Err(sign::Error::Failed {
+                program: self.program.clone(),
+                status: output.status,
+                stderr: output.stderr.into(),
+            });
+        }
+        let created = match self.format {
+            SignatureFormat::Ssh => !output.stdout.is_empty(),
+            SignatureFormat::OpenPgp | SignatureFormat::X509 => output
+                .stderr
+                .lines()
+                .any(|line| line.starts_with(b"[GNUPG:] SIG_CREATED ")),
+        };
+        if !created {
+            return Err(sign::Error::NoSignature {
+                program: self.program.clone(),
+                stderr: output.stderr.into(),
+            });
+        }
+        Ok(output.stdout.into())
+    }
+
+    /// Sign `commit` and store the signature in its `gpgsig` header, replacing a signature it may already have.
+    pub fn sign_commit(&self, commit: &mut gix_object::Commit) -> Result<(), sign::Error> {
+        use gix_object::{commit::SIGNATURE_FIELD_NAME, WriteTo};
+
+        commit.extra_headers.retain(|(name, _)| name != SIGNATURE_FIELD_NAME);
+        let mut payload = Vec::new();
+        commit.write_to(&mut payload).map_err(sign::Error::Encode)?;
+        let mut signature = self.sign(&payload)?;
+        // The header value is written with a trailing newline already.
+        if signature.last() == Some(&b'\n') {
+            signature.pop();
+        }
+        commit.extra_headers.push((SIGNATURE_FIELD_NAME.into(), signature));
+        Ok(())
+    }
+
+    /// Sign `tag` and store the signature after its message, replacing a signature it may already have.
+    ///
+    /// Like `git`, a newline is added to the message if it doesn't end with one.
+    pub fn sign_tag(&self, tag: &mut gix_object::Tag) -> Result<(), sign::Error> {
+        use gix_object::WriteTo;
+
+        tag.pgp_signature = None;
+        if !tag.message.ends_with(b"\n") {
+            tag.message.push(b'\n');
+        }
+        let mut payload = Vec::new();
+        tag.write_to(&mut payload).map_err(sign::Error::Encode)?;
+        let signature = self.sign(&payload)?;
+        // The signature is separated from the message by a newline when encoding, which is already part of the payload.
+        tag.message.pop();
+        tag.pgp_signature = Some(signature);
+        Ok(())
+    }
+}
+
+/// Run `cmd` with `input` on stdin, and collect its output.
+fn run(cmd: gix_command::Prepare, input: &[u8]) -> std::io::Result<std::process::Output> {
+    let mut cmd: std::process::Command = cmd
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .into();
+    gix_trace::debug!(cmd = ?cmd, "running gpg program");
+    let mut child = cmd.spawn()?;
+    let mut stdin = child.stdin.take().expect("configured");
+    std::thread::scope(|scope| {
+        // Errors are ignored as the program may exit without reading all input, which it would tell with its status.
+        scope.spawn(move || stdin.write_all(input).ok());
+        child.wait_with_output()
+    })
+}
+
+/// Write all `parts` into a new temporary file and return it along with its path.
+fn tempfile_with(parts: &[&[u8]]) -> std::io::Result<(gix_tempfile::Handle<gix_tempfile::handle::Writable>, PathBuf)> {
+    let mut file = gix_tempfile::new(
+        std::env::temp_dir(),
+        gix_tempfile::ContainingDirectory::Exists,
+        gix_tempfile::AutoRemove::Tempfile,
+    )?;
+    for part in parts {
+        file.write_all(part)?;
+    }
+    let path = file.with_mut(|file| file.path().to_owned())?;
+    Ok((file, path))
+}
@@ -0,0 +1,330 @@
+use crate::{
+    bstr::{BStr, BString, ByteSlice},
+    gpg::{run, tempfile_with, SignatureFormat, TrustLevel, Verifier},
+};
+
+/// The error returned by [`Verifier::verify()`].
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("The format of the signature could not be determined")]
+    UnknownFormat,
+    #[error("gpg.ssh.allowedSignersFile must be set to verify ssh signatures")]
+    AllowedSignersFileMissing,
+    #[error("Could not write the signature to a temporary file")]
+    WriteSignature(#[source] std::io::Error),
+    #[error("Could not run the verification program {program:?}")]
+    Io {
+        program: std::ffi::OsString,
+        source: std::io::Error,
+    },
+    #[error("The verification program {program:?} reported more than one signature, which isn't supported")]
+    MultipleSignatures { program: std::ffi::OsString },
+    #[error("The verification program {program:?} failed with {status} without reporting a result: {stderr}")]
+    Failed {
+        program: std::ffi::OsString,
+        status: std::process::ExitStatus,
+        stderr: BString,
+    },
+}
+
+///
+pub mod object {
+    /// The error returned by [`Commit::verify_signature()`](crate::Commit::verify_signature())
+    /// and [`Tag::verify_signature()`](crate::Tag::verify_signature()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Decode(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        Verifier(#[from] crate::gpg::verifier::Error),
+        #[error(transparent)]
+        Verify(#[from] super::Error),
+    }
+}
+
+/// The result of verifying a signature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Status {
+    /// The signature is valid and was made by a known key.
+    Good,
+    /// The signature doesn't match the signed data, or is corrupt.
+    Bad,
+    /// The key that made the signature isn't known, so the signature can't be trusted.
+    ///
+    /// For `ssh` signatures this means that the signature is valid, but that its key isn't in the allowed signers file.
+    UnknownKey,
+    /// The signature is valid, but it expired.
+    ExpiredSignature,
+    /// The signature is valid, but was made by a key that expired.
+    ExpiredKey,
+    /// The signature is valid, but was made by a key that was revoked.
+    RevokedKey,
+}
+
+/// The outcome of [`Verifier::verify()`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// The format of the verified signature.
+    pub format: SignatureFormat,
+    /// The result of the verification.
+    pub status: Status,
+    /// The user id of the key as reported by `gpg` or `gpgsm`, or the principal which the key is allowed for with `ssh`,
+    /// if known.
+    pub signer: Option<BString>,
+    /// The id of the key as reported by `gpg` or `gpgsm`, or the fingerprint of the `ssh` key, if known.
+    pub key: Option<BString>,
+    /// The fingerprint of the key as reported by `gpg` or `gpgsm` for valid signatures, or the fingerprint of the `ssh` key, if known.
+    pub fingerprint: Option<BString>,
+    /// The trust in the key as reported by `gpg` or `gpgsm`.
+    ///
+    /// Keys of `ssh` signatures are fully trusted if they are in the allowed signers file.
+    pub trust: TrustLevel,
+    /// The trust the key must have at least for the signature to be [good](Self::is_good()), as configured by `gpg.minTrustLevel`.
+    pub min_trust_level: TrustLevel,
+    /// The output of the verification program, for display to the user.
+    pub output: BString,
+}
+
+impl Outcome {
+    /// Return `true` if the signature is valid and made by a known key which is trusted at least as much as the
+    /// [minimal trust level](Self::min_trust_level).
+    pub fn is_good(&self) -> bool {
+        self.status == Status::Good && self.trust >= self.min_trust_level
+    }
+}
+
+impl SignatureFormat {
+    /// Determine the format of `signature` from its armor, or return `None` if it's not known.
+    pub fn from_signature(signature: &[u8]) -> Option<Self> {
+        let signature = signature.trim_start();
+        Some(
+            if signature.starts_with(b"-----BEGIN PGP SIGNATURE-----")
+                || signature.starts_with(b"-----BEGIN PGP MESSAGE-----")
+            {
+                SignatureFormat::OpenPgp
+            } else if signature.starts_with(b"-----BEGIN SIGNED MESSAGE-----") {
+                SignatureFormat::X509
+            } else if signature.starts_with(b"-----BEGIN SSH SIGNATURE-----") {
+                SignatureFormat::Ssh
+            } else {
+                return None;
+            },
+        )
+    }
+}
+
+/// Verification
+impl Verifier {
+    /// Verify that `signature` was made for `payload`, using the program matching the format of the signature.
+    ///
+    /// Note that a signature that doesn't match isn't an error, but is reported as [`Status::Bad`].
+    /// Signatures made with `ssh` are only trusted if their key is listed in `gpg.ssh.allowedSignersFile`.
+    pub fn verify(&self, signature: &[u8], payload: &[u8]) -> Result<Outcome, Error> {
+        let format = SignatureFormat::from_signature(signature).ok_or(Error::UnknownFormat)?;
+        let mut signature = signature.to_owned();
+        if signature.last() != Some(&b'\n') {
+            signature.push(b'\n');
+        }
+        let (_signature_file, signature_path) = tempfile_with(&[&signature]).map_err(Error::WriteSignature)?;
+        match format {
+            SignatureFormat::OpenPgp | SignatureFormat::X509 => self.verify_gpg(format, &signature_path, payload),
+            SignatureFormat::Ssh => self.verify_ssh(&signature_path, payload),
+        }
+    }
+
+    fn verify_gpg(
+        &self,
+        format: SignatureFormat,
+        signature_path: &std::path::Path,
+        payload: &[u8],
+    ) -> Result<Outcome, Error> {
+        let program = self.program(format);
+        let mut cmd = gix_command::prepare(program).arg("--status-fd=1");
+        if format == SignatureFormat::OpenPgp {
+            cmd = cmd.arg("--keyid-format=long");
+        }
+        let cmd = cmd.arg("--verify").arg(signature_path).arg("-");
+        let output = run(cmd, payload).map_err(|source| Error::Io {
+            program: program.to_owned(),
+            source,
+        })?;
+
+        let gpg_status = parse_gpg_status(&output.stdout).ok_or_else(|| Error::MultipleSignatures {
+            program: program.to_owned(),
+        })?;
+        let Some((status, key, signer)) = gpg_status.result else {
+            return Err(Error::Failed {
+                program: program.to_owned(),
+                status: output.status,
+                stderr: output.stderr.into(),
+            });
+        };
+        Ok(Outcome {
+            format,
+            status,
+            signer: signer.map(ToOwned::to_owned),
+            key: Some(key.to_owned()),
+            fingerprint: gpg_status.fingerprint.map(ToOwned::to_owned),
+            trust: gpg_status.trust,
+            min_trust_level: self.min_trust_level,
+            output: output.stderr.into(),
+        })
+    }
+
+    fn verify_ssh(&self, signature_path: &std::path::Path, payload: &[u8]) -> Result<Outcome, Error> {
+        let allowed_signers = self
+            .allowed_signers_file
+            .as_deref()
+            .ok_or(Error::AllowedSignersFileMissing)?;
+        let program = self.program(SignatureFormat::Ssh);
+        let io_err = |source| Error::Io {
+            program: program.to_owned(),
+            source,
+        };
+
+        let principals = run(
+            gix_command::prepare(program)
+                .args(["-Y", "find-principals", "-f"])
+                .arg(allowed_signers)
+                .arg("-s")
+                .arg(signature_path),
+            &[],
+        )
+        .map_err(io_err)?;
+        let principals: Vec<_> = if principals.status.success() {
+            principals.stdout.lines().filter(|line| !line.is_empty()).collect()
+        } else {
+            Vec::new()
+        };
+
+        if principals.is_empty() {
+            let output = run(
+                gix_command::prepare(program)
+                    .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+                    .arg(signature_path),
+                payload,
+            )
+            .map_err(io_err)?;
+            let mut text = output.stdout;
+            text.extend_from_slice(&output.stderr);
+            let key = ssh_key_fingerprint(text.as_bstr());
+            return Ok(Outcome {
+                format: SignatureFormat::Ssh,
+                status: if output.status.success() {
+                    Status::UnknownKey
+                } else {
+                    Status::Bad
+                },
+                signer: None,
+                fingerprint: key.clone(),
+                key,
+                trust: TrustLevel::Undefined,
+                min_trust_level: self.min_trust_level,
+                output: text.into(),
+            });
+        }
+
+        let mut outcome = None;
+        for principal in principals {
+            let output = run(
+                gix_command::prepare(program)
+                    .args(["-Y", "verify", "-n", "git", "-f"])
+                    .arg(allowed_signers)
+                    .arg("-I")
+                    .arg(gix_path::from_bstr(principal.as_bstr()).into_owned())
+                    .arg("-s")
+                    .arg(signature_path),
+                payload,
+            )
+            .map_err(io_err)?;
+            let mut text = output.stdout;
+            text.extend_from_slice(&output.stderr);
+            let good = output.status.success();
+            let key = ssh_key_fingerprint(text.as_bstr());
+            outcome = Some(Outcome {
+                format: SignatureFormat::Ssh,
+                status: if good { Status::Good } else { Status::Bad },
+                signer: Some(principal.into()),
+                fingerprint: key.clone(),
+                key,
+                trust: if good { TrustLevel::Fully } else { TrustLevel::Undefined },
+                min_trust_level: self.min_trust_level,
+                output: text.into(),
+            });
+            if good {
+                break;
+            }
+        }
+        Ok(outcome.expect("at least one principal"))
+    }
+}
+
+/// What `gpg` or `gpgsm` report about a signature on their status file descriptor.
+#[derive(Default)]
+struct GpgStatus<'a> {
+    /// The status of the signature along with the key and the user id, if there was a status line for it.
+    result: Option<(Status, &'a BStr, Option<&'a BStr>)>,
+    /// The fingerprint of the key for valid signatures.
+    fingerprint: Option<&'a BStr>,
+    trust: TrustLevel,
+}
+
+/// Parse all lines like `[GNUPG:] GOODSIG <key> <user id>` in `output`, or return `None` if more than one signature was
+/// reported, which `git` refuses to handle as well.
+fn parse_gpg_status(output: &[u8]) -> Option<GpgStatus<'_>> {
+    let mut out = GpgStatus::default();
+    for line in output.lines() {
+        let Some(line) = line.strip_prefix(b"[GNUPG:] ") else {
+            continue;
+        };
+        let mut tokens = line.splitn_str(3, " ");
+        let keyword = tokens.next().unwrap_or_default();
+        let status = match keyword {
+            b"GOODSIG" => Status::Good,
+            b"BADSIG" => Status::Bad,
+            b"ERRSIG" => Status::UnknownKey,
+            b"EXPSIG" => Status::ExpiredSignature,
+            b"EXPKEYSIG" => Status::ExpiredKey,
+            b"REVKEYSIG" => Status::RevokedKey,
+            b"VALIDSIG" => {
+                out.fingerprint = tokens.next().map(ByteSlice::as_bstr);
+                continue;
+            }
+            _ => {
+                if let Some(trust) = keyword.strip_prefix(b"TRUST_") {
+                    out.trust = match trust {
+                        b"NEVER" => TrustLevel::Never,
+                        b"MARGINAL" => TrustLevel::Marginal,
+                        b"FULLY" => TrustLevel::Fully,
+                        b"ULTIMATE" => TrustLevel::Ultimate,
+                        _ => TrustLevel::Undefined,
+                    };
+                }
+                continue;
+            }
+        };
+        if out.result.is_some() {
+            return None;
+        }
+        let Some(key) = tokens.next() else {
+            continue;
+        };
+        // `ERRSIG` is followed by algorithm ids instead of the user id.
+        let signer = (status != Status::UnknownKey)
+            .then(|| tokens.next().map(ByteSlice::as_bstr))
+            .flatten();
+        out.result = Some((status, key.as_bstr(), signer));
+    }
+    Some(out)
+}
+
+/// Extract the fingerprint from output like `Good "git" signature for <principal> with ED25519 key SHA256:<hash>`.
+fn ssh_key_fingerprint(output: &BStr) -> Option<BString> {
+    output.lines().find_map(|line| {
+        let line = line.strip_prefix(b"Good \"git\" signature")?;
+        let (_, fingerprint) = line.rsplit_once_str(" key ")?;
+        Some(fingerprint.trim().into())
+    })
+}
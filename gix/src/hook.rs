@@ -0,0 +1,165 @@
+//! Hooks, programs in the hooks directory of a repository that are run at certain points of an operation, like `git` does.
+//!
+//! Obtain them with [`Repository::hook()`](crate::Repository::hook()).
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::{Path, PathBuf},
+    process::Stdio,
+};
+
+///
+pub mod find {
+    /// The error returned by [`Repository::hook()`](crate::Repository::hook()) and
+    /// [`Repository::hooks_dir()`](crate::Repository::hooks_dir()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not interpolate the value of core.hooksPath")]
+        HooksPath(#[from] gix_config::path::interpolate::Error),
+        #[error("Could not resolve the absolute path of the git directory")]
+        RealPath(#[from] gix_path::realpath::Error),
+    }
+}
+
+///
+pub mod run {
+    use std::path::PathBuf;
+
+    /// The error returned by [`Hook::run()`](crate::hook::Hook::run()) and [`Hook::output()`](crate::hook::Hook::output()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not run the '{name}' hook at '{}'", path.display())]
+        Io {
+            name: String,
+            path: PathBuf,
+            source: std::io::Error,
+        },
+        #[error("The '{name}' hook failed with {status}")]
+        Failed {
+            name: String,
+            status: std::process::ExitStatus,
+        },
+    }
+}
+
+/// A hook that exists and is executable, ready to be configured with arguments, environment variables and input, and run.
+pub struct Hook {
+    name: String,
+    path: PathBuf,
+    current_dir: PathBuf,
+    cmd: gix_command::Prepare,
+    stdin: Vec<u8>,
+}
+
+/// Lifecycle
+impl Hook {
+    /// Return the hook `name` in `hooks_dir` if it is an executable file, to be run in `current_dir` with `git_dir` set in
+    /// its environment.
+    pub(crate) fn at(name: &str, hooks_dir: &Path, current_dir: &Path, git_dir: &Path) -> Option<Self> {
+        let path = hooks_dir.join(name);
+        match std::fs::metadata(&path) {
+            Ok(metadata) if metadata.is_file() && gix_fs::is_executable(&metadata) => {}
+            _ => return None,
+        }
+        Some(Hook {
+            name: name.into(),
+            cmd: gix_command::prepare(path.as_os_str()).env("GIT_DIR", git_dir.as_os_str()),
+            path,
+            current_dir: current_dir.to_owned(),
+            stdin: Vec::new(),
+        })
+    }
+}
+
+/// Builder
+impl Hook {
+    /// Add `arg` to the arguments of the hook.
+    pub fn arg(mut self, arg: impl Into<OsString>) -> Self {
+        self.cmd = self.cmd.arg(arg);
+        self
+    }
+
+    /// Add all `args` to the arguments of the hook.
+    pub fn args(mut self, args: impl IntoIterator<Item = impl Into<OsString>>) -> Self {
+        self.cmd = self.cmd.args(args);
+        self
+    }
+
+    /// Set the environment variable `key` to `value` for the hook.
+    pub fn env(mut self, key: impl Into<OsString>, value: impl Into<OsString>) -> Self {
+        self.cmd = self.cmd.env(key, value);
+        self
+    }
+
+    /// Pass `input` to the hook on standard input. Otherwise, its standard input will be empty.
+    pub fn stdin(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.stdin = input.into();
+        self
+    }
+}
+
+/// Access
+impl Hook {
+    /// The name of the hook, like `pre-commit`.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The path to the executable of the hook.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Execution
+impl Hook {
+    /// Run the hook to completion, and fail if it doesn't succeed.
+    ///
+    /// Like `git`, everything the hook prints to standard output is redirected to our standard error, which it inherits.
+    pub fn run(self) -> Result<(), run::Error> {
+        let name = self.name.clone();
+        let output = self.spawn_and_wait(Stdio::from(std::io::stderr()), Stdio::inherit())?;
+        if !output.status.success() {
+            return Err(run::Error::Failed {
+                name,
+                status: output.status,
+            });
+        }
+        Ok(())
+    }
+
+    /// Run the hook to completion and return everything it printed to standard output and standard error, along with its
+    /// exit status, which is not checked.
+    pub fn output(self) -> Result<std::process::Output, run::Error> {
+        self.spawn_and_wait(Stdio::piped(), Stdio::piped())
+    }
+
+    fn spawn_and_wait(self, stdout: Stdio, stderr: Stdio) -> Result<std::process::Output, run::Error> {
+        let Hook {
+            name,
+            path,
+            current_dir,
+            cmd,
+            stdin,
+        } = self;
+        let io_err = |source| run::Error::Io {
+            name: name.clone(),
+            path: path.clone(),
+            source,
+        };
+        let mut cmd: std::process::Command = cmd.stdin(Stdio::piped()).stdout(stdout).stderr(stderr).into();
+        gix_trace::debug!(cmd = ?cmd, "running hook");
+        let mut child = cmd.current_dir(current_dir).spawn().map_err(io_err)?;
+        let mut child_stdin = child.stdin.take().expect("configured");
+        std::thread::scope(|scope| {
+            scope.spawn(move || {
+                // Hooks don't have to read their input.
+                child_stdin.write_all(&stdin).ok();
+            });
+            child.wait_with_output()
+        })
+        .map_err(io_err)
+    }
+}
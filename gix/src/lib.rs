@@ -185,7 +185,11 @@ pub mod commit;
 ///
 #[cfg(feature = "dirwalk")]
 pub mod dirwalk;
+#[cfg(feature = "command")]
+pub mod gpg;
 pub mod head;
+#[cfg(feature = "command")]
+pub mod hook;
 pub mod id;
 pub mod object;
 #[cfg(feature = "attributes")]
@@ -210,6 +214,26 @@ pub mod diff;
 #[cfg(feature = "merge")]
 pub mod merge;
 
+///
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+pub mod cherry_pick;
+
+///
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+pub mod rebase;
+
+///
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+pub mod sequencer;
+
+///
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+pub mod stash;
+
+///
+#[cfg(all(feature = "dirwalk", feature = "worktree-mutation"))]
+pub mod staging;
+
 /// Try to open a git repository in `directory` and search upwards through its parents until one is found,
 /// using default trust options which matters in case the found repository isn't owned by the current user.
 ///
@@ -344,6 +368,10 @@ pub mod filter;
 ///
 pub mod remote;
 
+///
+#[cfg(feature = "blocking-network-server")]
+pub mod server;
+
 ///
 pub mod init;
 
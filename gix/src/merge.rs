@@ -143,14 +143,19 @@ pub mod tree {
         /// their conflicted counterparts.
         ///
         /// It's important that `index` is at the state of [`Self::tree`].
+        /// If `index` is [sparse](gix_index::State::is_sparse()) and there are unresolved conflicts, all of its sparse directories
+        /// are expanded first so conflicting entries can be added anywhere.
         /// Note that in practice, whenever there is a single [conflict](Conflict), this function will return `true`.
         pub fn index_changed_after_applying_conflicts(
             &self,
             index: &mut gix_index::State,
             how: TreatAsUnresolved,
             removal_mode: apply_index_entries::RemovalMode,
-        ) -> bool {
-            apply_index_entries(&self.conflicts, how, index, removal_mode)
+        ) -> Result<bool, gix_index::sparse::expand::Error> {
+            if index.is_sparse() && self.has_unresolved_conflicts(how) {
+                index.expand_all_sparse_directories(self.tree.repo)?;
+            }
+            Ok(apply_index_entries(&self.conflicts, how, index, removal_mode))
         }
     }
 
@@ -283,3 +288,150 @@ pub mod tree {
         }
     }
 }
+
+/// Merge a commit into `HEAD`, the index and the worktree like `git merge` does, see
+/// [`Repository::merge()`](crate::Repository::merge()).
+///
+/// If there are conflicts, their stages are written to the index and conflict markers to the worktree, while
+/// [`MERGE_HEAD`](crate::merge::worktree::MERGE_HEAD), [`MERGE_MSG`](crate::merge::worktree::MERGE_MSG) and
+/// [`MERGE_MODE`](crate::merge::worktree::MERGE_MODE) are written to the `git` directory so that `git commit` can be used
+/// to conclude the merge after resolving them.
+#[cfg(all(feature = "status", feature = "worktree-mutation"))]
+pub mod worktree {
+    use crate::bstr::BString;
+
+    pub use crate::cherry_pick::MERGE_MSG;
+
+    /// The name of the file in the `git` directory that refers to the commit that is being merged into `HEAD`.
+    pub const MERGE_HEAD: &str = "MERGE_HEAD";
+    /// The name of the file in the `git` directory that records if the merge was made with `--no-ff`.
+    pub const MERGE_MODE: &str = "MERGE_MODE";
+
+    /// Determine if `HEAD` may be fast-forwarded to the merged commit instead of creating a merge commit.
+    #[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+    pub enum FastForward {
+        /// Fast-forward if `HEAD` is an ancestor of the merged commit, and create a merge commit otherwise.
+        #[default]
+        Allow,
+        /// Always create a merge commit, like `git merge --no-ff`.
+        Never,
+        /// Only fast-forward, and fail with [`Error::NotPossibleToFastForward`] otherwise, like `git merge --ff-only`.
+        Only,
+    }
+
+    /// Options for [`Repository::merge()`](crate::Repository::merge()).
+    #[derive(Default, Debug, Clone)]
+    pub struct Options {
+        /// Determine if `HEAD` may be fast-forwarded.
+        pub fast_forward: FastForward,
+        /// If `true`, the merged tree is written to the index and the worktree without committing it, like
+        /// `git merge --no-commit`, so that the merge is concluded with the next commit.
+        pub no_commit: bool,
+        /// The message of the merge commit, or `None` to use `Merge commit '<id>'` like `git merge <id>` does,
+        /// followed by ` into <branch>` unless the current branch is `main` or `master`, or ` into HEAD` if it's detached.
+        pub message: Option<BString>,
+    }
+
+    /// The outcome of [`Repository::merge()`](crate::Repository::merge()).
+    #[derive(Debug)]
+    pub enum Outcome {
+        /// The merged commit is reachable from `HEAD` already, so nothing was changed.
+        UpToDate,
+        /// `HEAD` was an ancestor of the merged `commit` and now points to it, with the index and the worktree
+        /// changed to match it.
+        FastForward {
+            /// The merged commit.
+            commit: gix_hash::ObjectId,
+            /// The outcome of updating the worktree.
+            worktree: crate::repository::update_worktree::Outcome,
+        },
+        /// The merged commit was merged into `HEAD` with a three-way merge.
+        Merge {
+            /// The merge commit that `HEAD` now points to, or `None` if there were conflicts or if
+            /// [`Options::no_commit`] was set.
+            commit: Option<gix_hash::ObjectId>,
+            /// The unresolved conflicts, whose stages were written to the index and conflict markers to the worktree.
+            conflicts: Vec<crate::merge::tree::Conflict>,
+            /// The outcome of updating the worktree.
+            worktree: crate::repository::update_worktree::Outcome,
+        },
+    }
+
+    /// The error returned by [`Repository::merge()`](crate::Repository::merge()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no worktree to merge into", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("Cannot merge while another operation is in progress: {state:?}")]
+        InProgress { state: crate::state::InProgress },
+        #[error("Cannot fast-forward to commit {id} as HEAD isn't one of its ancestors")]
+        NotPossibleToFastForward { id: gix_hash::ObjectId },
+        #[error(transparent)]
+        HeadCommit(#[from] crate::reference::head_commit::Error),
+        #[error(transparent)]
+        HeadName(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        OpenCommitGraph(#[from] crate::repository::commit_graph_if_enabled::Error),
+        #[error(transparent)]
+        FindMergeBase(#[from] gix_revision::merge_base::Error),
+        #[error(transparent)]
+        OpenIndex(#[from] crate::worktree::open_index::Error),
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error("Cannot merge while the index has conflicts")]
+        UnmergedIndex,
+        #[error(transparent)]
+        WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+        #[error("The index contains changes that aren't committed yet")]
+        DirtyIndex,
+        #[error(transparent)]
+        TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+        #[error(transparent)]
+        MergeCommits(#[from] crate::repository::merge_commits::Error),
+        #[error(transparent)]
+        WriteTree(#[from] crate::object::tree::editor::write::Error),
+        #[error(transparent)]
+        SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+        #[error(transparent)]
+        IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+        #[error("The local changes to these files would be overwritten: {paths:?}")]
+        WouldOverwriteLocalChanges { paths: Vec<BString> },
+        #[error(transparent)]
+        Status(#[from] crate::status::Error),
+        #[error(transparent)]
+        StatusIter(#[from] crate::status::into_iter::Error),
+        #[error(transparent)]
+        StatusItem(#[from] crate::status::index_worktree::Error),
+        #[error(transparent)]
+        UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+        #[error("Could not collapse the index into a sparse index")]
+        CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+        #[error(transparent)]
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+        #[error(transparent)]
+        WriteIndex(#[from] gix_index::file::write::Error),
+        #[error(transparent)]
+        EditReference(#[from] crate::reference::edit::Error),
+        #[error(transparent)]
+        ParseTime(#[from] crate::config::time::Error),
+        #[error("Committer identity is not configured")]
+        CommitterMissing,
+        #[error("Author identity is not configured")]
+        AuthorMissing,
+        #[error(transparent)]
+        Commit(#[from] crate::commit::Error),
+        #[cfg(feature = "command")]
+        #[error(transparent)]
+        FindHook(#[from] crate::hook::find::Error),
+        #[error("Could not write '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
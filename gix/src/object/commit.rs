@@ -160,13 +160,26 @@ impl<'repo> Commit<'repo> {
     }
 
     /// Extracts the PGP signature and the data that was used to create the signature, or `None` if it wasn't signed.
-    // TODO: make it possible to verify the signature, probably by wrapping `SignedData`. It's quite some work to do it properly.
+    ///
+    /// Use [`verify_signature()`](Self::verify_signature()) to verify it.
     pub fn signature(
         &self,
     ) -> Result<Option<(std::borrow::Cow<'_, BStr>, gix_object::commit::SignedData<'_>)>, gix_object::decode::Error>
     {
         gix_object::CommitRefIter::signature(&self.data)
     }
+
+    /// Verify the signature of this commit with the program configured for its format, or return `None` if it isn't signed.
+    ///
+    /// See [`Repository::signature_verifier()`](crate::Repository::signature_verifier()) for details.
+    #[cfg(feature = "command")]
+    pub fn verify_signature(&self) -> Result<Option<crate::gpg::verify::Outcome>, crate::gpg::verify::object::Error> {
+        let Some((signature, signed_data)) = self.signature()? else {
+            return Ok(None);
+        };
+        let verifier = self.repo.signature_verifier()?;
+        Ok(Some(verifier.verify(&signature, &signed_data.to_bstring())?))
+    }
 }
 
 impl std::fmt::Debug for Commit<'_> {
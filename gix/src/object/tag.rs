@@ -1,4 +1,8 @@
-use crate::{ext::ObjectIdExt, ObjectDetached, Tag};
+use crate::{
+    bstr::{BStr, ByteSlice},
+    ext::ObjectIdExt,
+    ObjectDetached, Tag,
+};
 
 impl<'repo> Tag<'repo> {
     /// Decode the entire tag object and return it for accessing all tag information.
@@ -23,6 +27,28 @@ impl<'repo> Tag<'repo> {
     pub fn tagger(&self) -> Result<Option<gix_actor::SignatureRef<'_>>, gix_object::decode::Error> {
         gix_object::TagRefIter::from_bytes(&self.data).tagger()
     }
+
+    /// Extracts the signature and the data that was used to create the signature, or `None` if it wasn't signed.
+    pub fn signature(&self) -> Result<Option<(&BStr, &BStr)>, gix_object::decode::Error> {
+        let Some(signature) = self.decode()?.pgp_signature else {
+            return Ok(None);
+        };
+        // The signature is always at the end, and everything before it was signed.
+        let signed_data = &self.data[..self.data.len() - signature.len()];
+        Ok(Some((signature, signed_data.as_bstr())))
+    }
+
+    /// Verify the signature of this tag with the program configured for its format, or return `None` if it isn't signed.
+    ///
+    /// See [`Repository::signature_verifier()`](crate::Repository::signature_verifier()) for details.
+    #[cfg(feature = "command")]
+    pub fn verify_signature(&self) -> Result<Option<crate::gpg::verify::Outcome>, crate::gpg::verify::object::Error> {
+        let Some((signature, signed_data)) = self.signature()? else {
+            return Ok(None);
+        };
+        let verifier = self.repo.signature_verifier()?;
+        Ok(Some(verifier.verify(signature, signed_data)?))
+    }
 }
 
 /// Remove Lifetime
@@ -14,6 +14,7 @@ impl Default for Options {
             lenient_config: true,
             bail_if_untrusted: false,
             open_path_as_is: false,
+            run_hooks: false,
             api_config_overrides: Vec::new(),
             cli_config_overrides: Vec::new(),
             current_dir: None,
@@ -70,6 +71,16 @@ impl Options {
         self
     }
 
+    /// If `true`, default `false`, hooks are run by operations that support them, like creating commits with
+    /// [`Repository::commit()`](crate::Repository::commit()), editing references or checking out a worktree.
+    ///
+    /// Note that hooks are never run if the repository isn't [fully trusted](gix_sec::Trust::Full), as they are arbitrary
+    /// programs that anyone with write access to the repository can place.
+    pub fn run_hooks(mut self, toggle: bool) -> Self {
+        self.run_hooks = toggle;
+        self
+    }
+
     /// If `true`, default `false`, we will not modify the incoming path to open to assure it is a `.git` directory.
     ///
     /// If `false`, we will try to open the input directory as is, even though it doesn't appear to be a `git` repository
@@ -166,6 +177,7 @@ impl gix_sec::trust::DefaultForLevel for Options {
                 bail_if_untrusted: false,
                 lenient_config: true,
                 open_path_as_is: false,
+                run_hooks: false,
                 api_config_overrides: Vec::new(),
                 cli_config_overrides: Vec::new(),
                 current_dir: None,
@@ -178,6 +190,7 @@ impl gix_sec::trust::DefaultForLevel for Options {
                 bail_if_untrusted: false,
                 lenient_config: true,
                 open_path_as_is: false,
+                run_hooks: false,
                 lossy_config: false,
                 api_config_overrides: Vec::new(),
                 cli_config_overrides: Vec::new(),
@@ -171,6 +171,7 @@ impl ThreadSafeRepository {
             lenient_config,
             bail_if_untrusted,
             open_path_as_is: _,
+            run_hooks: _,
             permissions:
                 Permissions {
                     ref env,
@@ -227,6 +228,7 @@ impl ThreadSafeRepository {
                 object_hash,
                 precompose_unicode: repo_config.precompose_unicode,
                 prohibit_windows_device_names: repo_config.protect_windows,
+                format: repo_config.ref_format,
             };
             match &common_dir {
                 Some(common_dir) => {
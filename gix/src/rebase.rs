@@ -0,0 +1,200 @@
+//! Replay commits on top of another commit like `git rebase`, driven by a [todo list](crate::rebase::todo::List)
+//! that may have been edited.
+//!
+//! The [state](crate::rebase::State) of the rebase is kept in the `rebase-merge` directory of the `git` directory,
+//! in the format `git` uses, so that it can be continued after resolving conflicts or editing a commit, skipped over
+//! or aborted, no matter if it was started by `git` or by `gitoxide`.
+//! See [`Repository::rebase_todo()`](crate::Repository::rebase_todo()) and
+//! [`Repository::rebase_start()`](crate::Repository::rebase_start()) and the methods that follow it, and
+//! [`Repository::rebase_status()`](crate::Repository::rebase_status()) to learn about a rebase in progress.
+pub use gix_rebase as plumbing;
+pub use gix_rebase::{autosquash, todo, Kind, State, Status, UpdateRef};
+
+use crate::bstr::BString;
+
+/// The name of the file in the `git` directory that refers to the commit a rebase stopped at.
+pub const REBASE_HEAD: &str = "REBASE_HEAD";
+
+/// Options for [`Repository::rebase_todo()`](crate::Repository::rebase_todo())
+/// and [`Repository::rebase_start()`](crate::Repository::rebase_start()).
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// If `true`, commits whose summary starts with `fixup! `, `amend! ` or `squash! ` are moved after the commit they
+    /// refer to and melded into it, like `git rebase --autosquash` does.
+    pub autosquash: bool,
+    /// If `true`, local branches that point to rebased commits are updated to their rewritten versions once the rebase
+    /// is finished, like `git rebase --update-refs` does.
+    pub update_refs: bool,
+    /// If `true`, the rebase is recorded as one whose todo list could be edited, like `git rebase --interactive`.
+    pub interactive: bool,
+    /// If `true`, commits that become empty because their changes are already present are kept, instead of being dropped.
+    pub keep_redundant_commits: bool,
+    /// If `true`, `exec` instructions that failed are put back onto the todo list, so they are run again when continuing.
+    pub reschedule_failed_exec: bool,
+}
+
+/// The outcome of [`Repository::rebase_start()`](crate::Repository::rebase_start()) and the methods that continue a rebase.
+#[derive(Debug)]
+pub struct Outcome {
+    /// The commit `HEAD` points to.
+    pub head: gix_hash::ObjectId,
+    /// The reason the rebase stopped, or `None` if all instructions were performed and the state of the rebase was removed.
+    pub stop: Option<Stop>,
+}
+
+/// The reason a rebase stopped before performing all of its instructions.
+#[derive(Debug)]
+pub enum Stop {
+    /// The changes of `commit` couldn't be applied without conflicts, whose stages were written to the index
+    /// and whose conflict markers were written to the worktree.
+    Conflicts {
+        /// The commit that was picked, or merged into `HEAD`.
+        commit: gix_hash::ObjectId,
+        /// The unresolved conflicts.
+        conflicts: Vec<crate::merge::tree::Conflict>,
+    },
+    /// `commit` was picked and is to be amended, due to an `edit` instruction.
+    Edit {
+        /// The commit that was picked.
+        commit: gix_hash::ObjectId,
+    },
+    /// `commit` was picked and its message is to be replaced by passing it when continuing, due to a `reword` instruction.
+    Reword {
+        /// The commit that was picked.
+        commit: gix_hash::ObjectId,
+    },
+    /// A `break` instruction was performed.
+    Break,
+    /// The shell `command` of an `exec` instruction failed with `status`, or left changes in the index or the worktree
+    /// if `status` is `None`.
+    ExecFailed {
+        /// The command that was run.
+        command: BString,
+        /// The exit status of the command if it failed.
+        status: Option<std::process::ExitStatus>,
+    },
+}
+
+/// The error returned by [`Repository::rebase_start()`](crate::Repository::rebase_start()) and the methods that drive
+/// a rebase.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Repository at \"{}\" is a bare repository and has no worktree to rebase in", git_dir.display())]
+    BareRepository { git_dir: std::path::PathBuf },
+    #[error("Cannot start a rebase while another operation is in progress: {state:?}")]
+    InProgress { state: crate::state::InProgress },
+    #[error("There is no rebase in progress")]
+    NothingInProgress,
+    #[error("The index or the worktree have changes that aren't committed yet")]
+    DirtyWorktree,
+    #[error("The instruction '{command}' can't be performed in a rebase")]
+    UnsupportedInstruction { command: &'static str },
+    #[error("Merges with {parents} other parents aren't supported, only one can be merged into HEAD")]
+    UnsupportedOctopusMerge { parents: usize },
+    #[error("Cannot '{command}' without a previous commit")]
+    NoPreviousCommit { command: &'static str },
+    #[error("Could not resolve '{name}' to a commit")]
+    ResolveCommit { name: BString },
+    #[error("'{name}' is not a valid label")]
+    InvalidLabel {
+        name: BString,
+        source: gix_validate::reference::name::Error,
+    },
+    #[error(transparent)]
+    LookupPrefix(#[from] gix_odb::store::prefix::lookup::Error),
+    #[error("The operation was interrupted")]
+    Interrupted,
+    #[error(transparent)]
+    ReadState(#[from] gix_rebase::state::read::Error),
+    #[error(transparent)]
+    WriteState(#[from] gix_rebase::state::write::Error),
+    #[error(transparent)]
+    IsDirty(#[from] crate::status::is_dirty::Error),
+    #[error(transparent)]
+    Head(#[from] crate::reference::find::existing::Error),
+    #[error(transparent)]
+    HeadId(#[from] crate::reference::head_id::Error),
+    #[error(transparent)]
+    HeadCommit(#[from] crate::reference::head_commit::Error),
+    #[error(transparent)]
+    FindReference(#[from] crate::reference::find::Error),
+    #[error(transparent)]
+    PeelReference(#[from] crate::reference::peel::Error),
+    #[error(transparent)]
+    InitReferences(#[from] crate::reference::iter::Error),
+    #[error(transparent)]
+    InitReferencesIter(#[from] crate::reference::iter::init::Error),
+    #[error(transparent)]
+    IterReferences(Box<dyn std::error::Error + Send + Sync + 'static>),
+    #[error(transparent)]
+    WalkCommits(#[from] crate::revision::walk::Error),
+    #[error(transparent)]
+    IterCommits(#[from] crate::revision::walk::iter::Error),
+    #[error(transparent)]
+    ShortId(#[from] crate::id::shorten::Error),
+    #[error(transparent)]
+    FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    CherryPick(#[from] crate::cherry_pick::Error),
+    #[error(transparent)]
+    TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+    #[error(transparent)]
+    MergeCommits(#[from] crate::repository::merge_commits::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error("Could not expand the sparse index")]
+    ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+    #[error("The index still has conflicts that need to be resolved")]
+    UnmergedIndex,
+    #[error(transparent)]
+    WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+    #[error("The index contains changes that aren't committed yet")]
+    DirtyIndex,
+    #[error("Could not decode the object id in '{}'", path.display())]
+    DecodeId {
+        path: std::path::PathBuf,
+        source: gix_hash::decode::Error,
+    },
+    #[error(transparent)]
+    Status(#[from] crate::status::Error),
+    #[error(transparent)]
+    StatusIter(#[from] crate::status::into_iter::Error),
+    #[error(transparent)]
+    StatusIndexWorktree(#[from] crate::status::index_worktree::Error),
+    #[error("The local changes to these files would be overwritten: {paths:?}")]
+    WouldOverwriteLocalChanges { paths: Vec<BString> },
+    #[error(transparent)]
+    SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+    #[error(transparent)]
+    IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+    #[error(transparent)]
+    UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+    #[error("Could not collapse the index into a sparse index")]
+    CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+    #[error(transparent)]
+    IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+    #[error(transparent)]
+    WriteIndex(#[from] gix_index::file::write::Error),
+    #[error(transparent)]
+    EditReference(#[from] crate::reference::edit::Error),
+    #[error(transparent)]
+    ParseTime(#[from] crate::config::time::Error),
+    #[error(transparent)]
+    ParseSignatureTime(#[from] gix_date::Error),
+    #[error("Committer identity is not configured")]
+    CommitterMissing,
+    #[error("Author identity is not configured")]
+    AuthorMissing,
+    #[error(transparent)]
+    Commit(#[from] crate::commit::Error),
+    #[error("Could not run '{command}'")]
+    Exec { command: BString, source: std::io::Error },
+    #[error("Could not access '{}'", path.display())]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
@@ -17,6 +17,12 @@ pub mod edit {
         LockTimeoutConfiguration(#[from] config::lock_timeout::Error),
         #[error(transparent)]
         ParseCommitterTime(#[from] crate::config::time::Error),
+        #[cfg(feature = "command")]
+        #[error(transparent)]
+        FindHook(#[from] crate::hook::find::Error),
+        #[cfg(feature = "command")]
+        #[error("The reference-transaction hook rejected the transaction")]
+        Hook(#[from] crate::hook::run::Error),
     }
 }
 
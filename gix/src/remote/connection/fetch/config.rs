@@ -1,6 +1,9 @@
 use super::Error;
 use crate::{
-    config::{cache::util::ApplyLeniency, tree::Pack},
+    config::{
+        cache::util::ApplyLeniency,
+        tree::{Fetch, Pack},
+    },
     Repository,
 };
 
@@ -24,3 +27,19 @@ pub fn pack_index_version(repo: &Repository) -> Result<gix_pack::index::Version,
         .with_leniency(repo.options.lenient_config)?
         .unwrap_or(gix_pack::index::Version::V2))
 }
+
+pub fn write_commit_graph(repo: &Repository) -> Result<bool, Error> {
+    Ok(repo
+        .config
+        .resolved
+        .boolean(Fetch::WRITE_COMMIT_GRAPH)
+        .map(|value| Fetch::WRITE_COMMIT_GRAPH.enrich_error(value))
+        .transpose()
+        .with_leniency(repo.options.lenient_config)
+        .map_err(Error::WriteCommitGraphConfig)?
+        .unwrap_or(false)
+        && repo
+            .config
+            .may_use_commit_graph()
+            .map_err(Error::WriteCommitGraphConfig)?)
+}
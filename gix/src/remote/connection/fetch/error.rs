@@ -34,8 +34,19 @@ pub enum Error {
     },
     #[error("Could not obtain configuration to learn if shallow remotes should be rejected")]
     RejectShallowRemoteConfig(#[from] config::boolean::Error),
+    #[error("Could not obtain configuration to learn if the commit-graph should be updated")]
+    WriteCommitGraphConfig(#[source] config::boolean::Error),
+    #[error("Could not update the commit-graph as configured by fetch.writeCommitGraph")]
+    WriteCommitGraph(#[from] crate::repository::write_commit_graph::Error),
     #[error(transparent)]
     NegotiationAlgorithmConfig(#[from] config::key::GenericErrorWithValue),
+    #[error("Failed to write .promisor file at \"{}\"", path.display())]
+    WritePromisorFile {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Could not configure the remote as promisor remote")]
+    RegisterPromisorRemote(#[from] crate::remote::promisor::register::Error),
 }
 
 impl gix_protocol::transport::IsSpuriousError for Error {
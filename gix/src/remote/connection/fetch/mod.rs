@@ -153,6 +153,7 @@ where
             reflog_message: None,
             write_packed_refs: WritePackedRefs::Never,
             shallow: Default::default(),
+            filter: None,
         })
     }
 }
@@ -184,6 +185,7 @@ where
     reflog_message: Option<RefLogMessage>,
     write_packed_refs: WritePackedRefs,
     shallow: remote::fetch::Shallow,
+    filter: Option<String>,
 }
 
 /// Builder
@@ -225,4 +227,16 @@ where
         self.shallow = shallow;
         self
     }
+
+    /// Ask the server to omit objects according to the filter `spec`, like `blob:none`, to create or extend a partial clone.
+    ///
+    /// Unless this is a dry run, a named remote will be configured as promisor remote to lazily fetch missing objects from
+    /// with `spec` as its `remote.<name>.partialCloneFilter`, and it becomes the `extensions.partialClone` remote if there is none yet.
+    /// Note that this only affects the configuration file, and thus repositories opened from now on.
+    ///
+    /// If unset, the `remote.<name>.partialCloneFilter` of a promisor remote is used.
+    pub fn with_filter(mut self, spec: impl Into<String>) -> Self {
+        self.filter = Some(spec.into());
+        self
+    }
 }
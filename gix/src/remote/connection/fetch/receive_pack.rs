@@ -8,9 +8,10 @@ use gix_transport::client::async_io::Transport;
 use gix_transport::client::blocking_io::Transport;
 
 use crate::{
+    bstr::ByteSlice,
     config::{
         cache::util::ApplyLeniency,
-        tree::{Clone, Fetch},
+        tree::{Clone, Fetch, Remote, Section},
     },
     remote,
     remote::{
@@ -69,6 +70,14 @@ where
     /// ### Configuration
     ///
     /// - `gitoxide.userAgent` is read to obtain the application user agent for git servers and for HTTP servers as well.
+    /// - `fetch.writeCommitGraph` is read to update the commit-graph with the received commits if a pack was received,
+    ///   unless `core.commitGraph` is `false`.
+    /// - `remote.<name>.partialCloneFilter` is used as filter when fetching from a promisor remote without a
+    ///   [filter](Self::with_filter()) of its own.
+    ///
+    /// ### Partial Clones
+    ///
+    /// Packs received with a filter or from a promisor remote are marked as promisor packs with a `.promisor` file.
     ///
     #[gix_protocol::maybe_async::maybe_async]
     pub async fn receive<P>(mut self, progress: P, should_interrupt: &AtomicBool) -> Result<Outcome, Error>
@@ -98,6 +107,22 @@ where
             });
         }
 
+        let remote_name = con.remote.name().and_then(remote::Name::as_symbol);
+        let is_promisor_remote = remote_name.is_some_and(|name| {
+            remote::promisor::remote_names(&repo.config.resolved)
+                .iter()
+                .any(|promisor| promisor == name)
+        });
+        let configured_filter = remote_name
+            .filter(|_| is_promisor_remote)
+            .and_then(|name| {
+                repo.config
+                    .resolved
+                    .string_by(Remote.name(), Some(name.into()), Remote::PARTIAL_CLONE_FILTER.name)
+            })
+            .map(|spec| spec.to_str_lossy().into_owned());
+        let filter = self.filter.as_ref().or(configured_filter.as_ref());
+
         let fetch_options = gix_protocol::fetch::Options {
             shallow_file: repo.shallow_file(),
             shallow: &self.shallow,
@@ -109,6 +134,7 @@ where
                 .map(|val| Clone::REJECT_SHALLOW.enrich_error(val))
                 .transpose()?
                 .unwrap_or(false),
+            filter: filter.map(String::as_str),
         };
         let context = gix_protocol::fetch::Context {
             handshake: &mut handshake,
@@ -132,6 +158,8 @@ where
             r.objects.refresh = RefreshMode::Never;
             // we cache everything of importance in the graph and thus don't need an object cache.
             r.objects.unset_object_cache();
+            // objects we don't have are what we are negotiating about, so don't fetch them.
+            r.objects.fetch_missing_never();
             r
         };
         let cache = graph_repo.commit_graph_if_enabled().ok().flatten();
@@ -210,6 +238,22 @@ where
             self.write_packed_refs,
         )?;
 
+        if let Some(bundle) = write_pack_bundle.as_ref() {
+            if let Some(data_path) = bundle
+                .data_path
+                .as_ref()
+                .filter(|_| filter.is_some() || is_promisor_remote)
+            {
+                let path = data_path.with_extension("promisor");
+                std::fs::write(&path, b"").map_err(|source| Error::WritePromisorFile { path, source })?;
+            }
+        }
+        if let Some((spec, name)) = self.filter.as_ref().zip(remote_name) {
+            if matches!(self.dry_run, fetch::DryRun::No) && configured_filter.as_ref() != Some(spec) {
+                remote::promisor::register(&repo.common_dir().join("config"), name.into(), spec)?;
+            }
+        }
+
         if let Some(bundle) = write_pack_bundle.as_mut() {
             if !update_refs.edits.is_empty() || bundle.index.num_objects == 0 {
                 if let Some(path) = bundle.keep_path.take() {
@@ -218,6 +262,10 @@ where
             }
         }
 
+        if write_pack_bundle.is_some() && config::write_commit_graph(repo)? && !repo.is_shallow() {
+            repo.write_commit_graph(Some(Default::default()))?;
+        }
+
         let out = Outcome {
             handshake,
             ref_map: std::mem::take(&mut self.ref_map),
@@ -31,3 +31,6 @@ pub mod ref_map;
 
 ///
 pub mod fetch;
+
+///
+pub mod push;
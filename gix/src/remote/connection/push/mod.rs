@@ -0,0 +1,447 @@
+#[cfg(feature = "async-network-client")]
+use gix_transport::client::async_io::Transport;
+#[cfg(feature = "blocking-network-client")]
+use gix_transport::client::blocking_io::Transport;
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice},
+    remote,
+    remote::Connection,
+    Progress,
+};
+
+mod error;
+pub use error::Error;
+
+mod send_pack;
+
+/// What to expect of a reference on the remote for it to be updated, similar to `git push --force-with-lease`.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lease {
+    /// There is no expectation, and the update must be a fast-forward unless it is forced.
+    #[default]
+    None,
+    /// The remote reference must point to the given object, or must not exist if `None`.
+    ///
+    /// If the expectation is met, the update is forced.
+    /// Note that the expectation is also checked by the remote while applying the update, so it can't be raced.
+    Expect(Option<gix_hash::ObjectId>),
+}
+
+/// A reference update to send to the remote, usually derived from push refspecs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Update {
+    /// The full name of the local reference that provides the new value, or `None` if the source was an object id
+    /// or if this is a deletion.
+    pub local: Option<gix_ref::FullName>,
+    /// The object the remote reference should point to, or `None` to delete it.
+    pub new: Option<gix_hash::ObjectId>,
+    /// The full name of the reference on the remote.
+    pub remote: gix_ref::FullName,
+    /// The object the remote reference currently points to as advertised by the remote, or `None` if it doesn't exist.
+    pub remote_old: Option<gix_hash::ObjectId>,
+    /// If `true`, the update doesn't have to be a fast-forward.
+    pub force: bool,
+    /// What to expect of the remote reference for the update to be allowed.
+    pub lease: Lease,
+}
+
+/// The status of a single reference [update](Update) after pushing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The remote reference already pointed to the desired object, so nothing was sent.
+    UpToDate,
+    /// The update was applied on the remote, or would have been applied in dry-run mode.
+    Ok,
+    /// The update was rejected locally as the remote reference isn't an ancestor of the new object, and it wasn't forced.
+    RejectedNonFastForward,
+    /// The update was rejected locally as the remote tag already exists, and it wasn't forced.
+    RejectedAlreadyExists,
+    /// The update was rejected locally as the remote reference points to an object we don't have, so it's unknown
+    /// if the update would be a fast-forward.
+    RejectedFetchFirst,
+    /// The update was rejected locally as the remote reference doesn't match the [lease](Lease).
+    RejectedStale {
+        /// The object the remote reference actually points to, or `None` if it doesn't exist.
+        actual: Option<gix_hash::ObjectId>,
+    },
+    /// The deletion was rejected locally as the remote reference doesn't exist.
+    RejectedRemoteRefMissing,
+    /// The update was rejected by the remote.
+    RejectedByRemote {
+        /// The reason for rejection as sent by the remote.
+        reason: BString,
+    },
+    /// The update was not attempted as other updates of an atomic push were rejected locally.
+    AtomicPushFailed,
+}
+
+impl Status {
+    /// Return `true` if the remote reference has the desired value, either because it was updated, or because it
+    /// was up-to-date already.
+    pub fn is_ok(&self) -> bool {
+        matches!(self, Status::Ok | Status::UpToDate)
+    }
+}
+
+/// The outcome of pushing via [`Prepare::transmit()`].
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The outcome of the handshake with the remote.
+    pub handshake: gix_protocol::Handshake,
+    /// All updates along with their status.
+    pub updates: Vec<(Update, Status)>,
+    /// The report of the remote, if commands were sent and the remote supports reporting.
+    pub report: Option<gix_protocol::push::response::Report>,
+    /// The edits made to local remote-tracking references to reflect the successful updates on the remote.
+    pub tracking_ref_edits: Vec<gix_ref::transaction::RefEdit>,
+    /// If `true`, nothing was sent to the remote and no local reference was changed.
+    pub dry_run: bool,
+}
+
+///
+pub mod prepare {
+    use crate::bstr::BString;
+
+    /// The error returned by [`prepare_push()`][super::Connection::prepare_push()].
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Cannot perform a meaningful push operation without any configured ref-specs")]
+        MissingRefSpecs,
+        #[error(transparent)]
+        Handshake(#[from] crate::remote::ref_map::Error),
+        #[error("The remote didn't advertise its references, which is required for pushing")]
+        MissingRefAdvertisement,
+        #[error(transparent)]
+        DecodeRemoteRefs(#[from] gix_protocol::handshake::refs::parse::Error),
+        #[error(transparent)]
+        ReferenceIter(#[from] Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error(transparent)]
+        OpenPackedRefs(#[from] gix_ref::packed::buffer::open::Error),
+        #[error(transparent)]
+        ReferenceIterInit(#[from] crate::reference::iter::init::Error),
+        #[error(transparent)]
+        FindHead(#[from] crate::reference::find::existing::Error),
+        #[error("The remote reference name {name:?} is invalid")]
+        InvalidRemoteRefName {
+            name: BString,
+            source: gix_validate::reference::name::Error,
+        },
+        #[error("Cannot push object {id} without a destination reference name")]
+        MissingDestination { id: gix_hash::ObjectId },
+        #[error("Remote reference {name:?} would be updated multiple times with different values")]
+        ConflictingDestination { name: BString },
+    }
+
+    impl gix_protocol::transport::IsSpuriousError for Error {
+        fn is_spurious(&self) -> bool {
+            match self {
+                Error::Handshake(err) => err.is_spurious(),
+                _ => false,
+            }
+        }
+    }
+
+    /// Options for use in [`prepare_push()`][super::Connection::prepare_push()].
+    #[derive(Default, Debug, Clone)]
+    pub struct Options {
+        /// Parameters in the form of `(name, optional value)` to add to the handshake.
+        ///
+        /// This is useful in case of custom servers.
+        pub handshake_parameters: Vec<(String, Option<String>)>,
+        /// Push refspecs to use in addition to the ones configured for the remote.
+        ///
+        /// This is useful to push refspecs provided on the command-line, without persisting them.
+        pub extra_refspecs: Vec<gix_refspec::RefSpec>,
+    }
+}
+
+impl<'remote, 'repo, T> Connection<'remote, 'repo, T>
+where
+    T: Transport,
+{
+    /// Perform a handshake with the `receive-pack` service of the remote and compute the reference updates from
+    /// the push refspecs of our remote and `options`, matched against our local references.
+    ///
+    /// From there the updates can be adjusted, for instance to add [leases](Lease), before pushing them with
+    /// [`Prepare::transmit()`].
+    ///
+    /// Note that `push.default` isn't used if no push refspecs are configured, as it's up to the caller to obtain
+    /// refspecs from it, for instance by means of [`Repository::branch_remote_ref_name()`](crate::Repository::branch_remote_ref_name()).
+    #[allow(clippy::result_large_err)]
+    #[gix_protocol::maybe_async::maybe_async]
+    pub async fn prepare_push(
+        mut self,
+        mut progress: impl Progress,
+        prepare::Options {
+            handshake_parameters,
+            extra_refspecs,
+        }: prepare::Options,
+    ) -> Result<Prepare<'remote, 'repo, T>, prepare::Error> {
+        let _span = gix_trace::coarse!("remote::Connection::prepare_push()");
+        let mut specs = self.remote.refspecs(remote::Direction::Push).to_vec();
+        specs.extend(extra_refspecs);
+        if specs.is_empty() {
+            return Err(prepare::Error::MissingRefSpecs);
+        }
+        let handshake = self
+            .perform_handshake(
+                remote::Direction::Push,
+                gix_transport::Service::ReceivePack,
+                handshake_parameters,
+                &mut progress,
+            )
+            .await?;
+        let remote_refs = handshake
+            .refs
+            .as_ref()
+            .ok_or(prepare::Error::MissingRefAdvertisement)?
+            .iter()
+            .filter_map(|r| {
+                let (name, target, _peeled) = r.unpack();
+                Some((name.to_owned(), target?.to_owned()))
+            })
+            .filter(|(name, _)| name != ".have")
+            .collect::<Vec<_>>();
+        let updates = updates_from_refspecs(self.remote.repo, &specs, &remote_refs)?;
+        self.handshake = Some(handshake);
+        Ok(Prepare {
+            con: Some(self),
+            updates,
+            atomic: false,
+            push_options: Vec::new(),
+            dry_run: false,
+        })
+    }
+}
+
+fn updates_from_refspecs(
+    repo: &crate::Repository,
+    specs: &[gix_refspec::RefSpec],
+    remote_refs: &[(BString, gix_hash::ObjectId)],
+) -> Result<Vec<Update>, prepare::Error> {
+    use gix_refspec::{instruction, match_group::SourceRef, Instruction};
+
+    let mut local_refs = Vec::new();
+    let head = repo.head()?;
+    if let Some(id) = head.id() {
+        local_refs.push((
+            BString::from("HEAD"),
+            id.detach(),
+            head.referent_name().map(ToOwned::to_owned),
+        ));
+    }
+    for r in repo.references()?.all()? {
+        let mut r = r?;
+        // Like when mapping refs for a fetch, refs that don't point to an object can't be pushed and are ignored.
+        let Ok(id) = r.follow_to_object() else {
+            continue;
+        };
+        local_refs.push((r.name().as_bstr().to_owned(), id.detach(), None));
+    }
+
+    let remote_ref = |name: &BStr| remote_refs.iter().find(|(n, _)| n == name).map(|(_, id)| *id);
+    let to_full_name = |name: BString| {
+        gix_ref::FullName::try_from(name.clone())
+            .map_err(|source| prepare::Error::InvalidRemoteRefName { name, source })
+    };
+    let mut updates = Vec::<Update>::new();
+    let mut push_update = |update: Update| -> Result<(), prepare::Error> {
+        match updates.iter().find(|u| u.remote == update.remote) {
+            Some(existing) if existing.new != update.new => Err(prepare::Error::ConflictingDestination {
+                name: update.remote.as_bstr().to_owned(),
+            }),
+            Some(_) => Ok(()),
+            None => {
+                updates.push(update);
+                Ok(())
+            }
+        }
+    };
+
+    let group = gix_refspec::MatchGroup::from_push_specs(specs.iter().map(gix_refspec::RefSpec::to_ref));
+    let items: Vec<_> = local_refs
+        .iter()
+        .map(|(name, id, _)| gix_refspec::match_group::Item {
+            full_ref_name: name.as_ref(),
+            target: id,
+            object: None,
+        })
+        .collect();
+    let outcome = group.match_lhs(items.iter().copied());
+    for mapping in &outcome.mappings {
+        let spec = &specs[mapping.spec_index];
+        let (local, new, local_name) = match &mapping.lhs {
+            SourceRef::ObjectId(id) => (None, *id, None),
+            SourceRef::FullName(name) => {
+                let (_, id, referent) = &local_refs[mapping.item_index.expect("always set for names")];
+                let local_name = referent
+                    .as_ref()
+                    .map_or_else(|| name.as_ref().to_owned(), |r| r.as_bstr().to_owned());
+                (Some(to_full_name(local_name.clone())?), *id, Some(local_name))
+            }
+        };
+        let remote_name = match (mapping.rhs.as_ref(), local_name.as_ref()) {
+            (Some(rhs), _) => expand_remote_name(rhs.as_ref(), local_name.as_ref().map(AsRef::as_ref), remote_refs),
+            (None, Some(local_name)) => local_name.clone(),
+            (None, None) => return Err(prepare::Error::MissingDestination { id: new }),
+        };
+        let remote_old = remote_ref(remote_name.as_ref());
+        push_update(Update {
+            local,
+            new: Some(new),
+            remote: to_full_name(remote_name)?,
+            remote_old,
+            force: spec.allow_non_fast_forward(),
+            lease: Lease::None,
+        })?;
+    }
+
+    for spec in specs.iter().map(gix_refspec::RefSpec::to_ref) {
+        match spec.instruction() {
+            Instruction::Push(instruction::Push::Delete { ref_or_pattern }) => {
+                let names: Vec<BString> = match ref_or_pattern.find_byte(b'*') {
+                    Some(pos) => {
+                        let (prefix, suffix) = (&ref_or_pattern[..pos], &ref_or_pattern[pos + 1..]);
+                        remote_refs
+                            .iter()
+                            .filter(|(name, _)| name.starts_with(prefix) && name.ends_with(suffix))
+                            .map(|(name, _)| name.clone())
+                            .collect()
+                    }
+                    None => vec![expand_remote_name(ref_or_pattern, None, remote_refs)],
+                };
+                for name in names {
+                    let remote_old = remote_ref(name.as_ref());
+                    push_update(Update {
+                        local: None,
+                        new: None,
+                        remote: to_full_name(name)?,
+                        remote_old,
+                        force: true,
+                        lease: Lease::None,
+                    })?;
+                }
+            }
+            Instruction::Push(instruction::Push::AllMatchingBranches { allow_non_fast_forward }) => {
+                for (name, id, _) in local_refs
+                    .iter()
+                    .filter(|(name, _, _)| name.starts_with(b"refs/heads/"))
+                {
+                    if let Some(remote_old) = remote_ref(name.as_ref()) {
+                        push_update(Update {
+                            local: Some(to_full_name(name.clone())?),
+                            new: Some(*id),
+                            remote: to_full_name(name.clone())?,
+                            remote_old: Some(remote_old),
+                            force: allow_non_fast_forward,
+                            lease: Lease::None,
+                        })?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(updates)
+}
+
+/// Turn a possibly partial `name` of a remote reference into a full one, by looking for a unique match among `remote_refs`
+/// or by placing it next to `local_name`, similar to what `git push` does.
+fn expand_remote_name(
+    name: &BStr,
+    local_name: Option<&BStr>,
+    remote_refs: &[(BString, gix_hash::ObjectId)],
+) -> BString {
+    if name.starts_with(b"refs/") || name == "HEAD" {
+        return name.to_owned();
+    }
+    let mut matches = remote_refs.iter().filter(|(remote_name, _)| {
+        ["refs/heads/", "refs/tags/"]
+            .iter()
+            .any(|prefix| remote_name.strip_prefix(prefix.as_bytes()) == Some(name.as_bytes()))
+    });
+    if let (Some((remote_name, _)), None) = (matches.next(), matches.next()) {
+        return remote_name.clone();
+    }
+    let prefix = if local_name.is_some_and(|n| n.starts_with(b"refs/tags/")) {
+        "refs/tags/"
+    } else {
+        "refs/heads/"
+    };
+    let mut out = BString::from(prefix);
+    out.extend_from_slice(name);
+    out
+}
+
+/// A structure to hold the result of the handshake with the remote and configure the upcoming push operation.
+pub struct Prepare<'remote, 'repo, T>
+where
+    T: Transport,
+{
+    con: Option<Connection<'remote, 'repo, T>>,
+    updates: Vec<Update>,
+    atomic: bool,
+    push_options: Vec<BString>,
+    dry_run: bool,
+}
+
+/// Builder
+impl<T> Prepare<'_, '_, T>
+where
+    T: Transport,
+{
+    /// If dry run is enabled, no change to the remote or to the local repository will be made, but the status of
+    /// each update will be computed nonetheless.
+    pub fn with_dry_run(mut self, enabled: bool) -> Self {
+        self.dry_run = enabled;
+        self
+    }
+
+    /// If enabled, ask the remote to apply either all updates or none of them.
+    pub fn with_atomic(mut self, enabled: bool) -> Self {
+        self.atomic = enabled;
+        self
+    }
+
+    /// Transmit `options` to the remote, which passes them to its hooks.
+    pub fn with_push_options(mut self, options: impl IntoIterator<Item = impl Into<BString>>) -> Self {
+        self.push_options = options.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Require the remote reference named `remote` to point to `expected`, or to not exist if `None`, to be updated.
+    ///
+    /// This is like `git push --force-with-lease=<remote>:<expected>` and has no effect if no update for `remote` exists.
+    pub fn with_lease(mut self, remote: &gix_ref::FullNameRef, expected: Option<gix_hash::ObjectId>) -> Self {
+        for update in self.updates.iter_mut().filter(|u| u.remote.as_ref() == remote) {
+            update.lease = Lease::Expect(expected);
+        }
+        self
+    }
+}
+
+/// Access
+impl<T> Prepare<'_, '_, T>
+where
+    T: Transport,
+{
+    /// Return the updates that will be sent to the remote.
+    pub fn updates(&self) -> &[Update] {
+        &self.updates
+    }
+
+    /// Return the updates that will be sent to the remote for adjustment.
+    pub fn updates_mut(&mut self) -> &mut Vec<Update> {
+        &mut self.updates
+    }
+
+    /// Return the handshake performed with the remote, which contains all references it advertised.
+    pub fn handshake(&self) -> &gix_protocol::Handshake {
+        self.con
+            .as_ref()
+            .and_then(|con| con.handshake.as_ref())
+            .expect("set while preparing")
+    }
+}
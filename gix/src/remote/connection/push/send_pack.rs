@@ -0,0 +1,410 @@
+use std::{str::FromStr, sync::atomic::AtomicBool};
+
+use gix_features::{parallel::InOrderIter, progress::Progress};
+use gix_hash::ObjectId;
+use gix_pack::data::output;
+use gix_ref::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+#[cfg(feature = "async-network-client")]
+use gix_transport::client::async_io::Transport;
+#[cfg(feature = "blocking-network-client")]
+use gix_transport::client::blocking_io::Transport;
+
+use crate::{
+    bstr::ByteSlice,
+    remote,
+    remote::push::{Error, Lease, Outcome, Prepare, Status, Update},
+};
+
+impl<T> Prepare<'_, '_, T>
+where
+    T: Transport,
+{
+    /// Check all updates for validity, send the ones that pass along with a pack of all objects the remote doesn't have yet,
+    /// and update our remote-tracking references for all updates the remote accepted.
+    ///
+    /// Updates are rejected locally if…
+    ///
+    /// * …they are not fast-forwards and not forced,
+    /// * …the remote reference points to an object we don't have,
+    /// * …a [lease](Lease) isn't satisfied,
+    /// * …a tag would be changed without being forced.
+    ///
+    /// If the push is atomic, any local rejection causes nothing to be sent at all.
+    ///
+    /// Note that rejections aren't errors, use [`Outcome::updates`] to learn about the status of each update.
+    ///
+    /// ### Pack Creation
+    ///
+    /// The pack contains all commits reachable from the new objects that aren't reachable from any object the remote advertised,
+    /// along with all trees and blobs added compared to their parents. Objects are copied from existing packs where possible,
+    /// and we never send thin packs. Note that objects that only exist in memory aren't seen.
+    /// Objects that aren't yet packed are delta-compressed as configured by `pack.window` and `pack.depth`, unless
+    /// their path has the `delta` attribute unset.
+    ///
+    /// ### Async Mode Shortcoming
+    ///
+    /// Counting the objects to send blocks the executor, while the pack itself is written on a thread of its own.
+    #[gix_protocol::maybe_async::maybe_async]
+    pub async fn transmit<P>(mut self, mut progress: P, should_interrupt: &AtomicBool) -> Result<Outcome, Error>
+    where
+        P: gix_features::progress::NestedProgress,
+        P::SubProgress: 'static,
+    {
+        let mut con = self.con.take().expect("transmit() can only be called once");
+        let handshake = con.handshake.take().expect("transmit() can only be called once");
+        let repo = con.remote.repo;
+
+        let remote_object_hash = handshake
+            .capabilities
+            .capability("object-format")
+            .and_then(|c| c.value())
+            .and_then(|v| gix_hash::Kind::from_str(v.to_str().ok()?).ok())
+            .unwrap_or(gix_hash::Kind::Sha1);
+        if remote_object_hash != repo.object_hash() {
+            return Err(Error::IncompatibleObjectHash {
+                local: repo.object_hash(),
+                remote: remote_object_hash,
+            });
+        }
+
+        let mut updates: Vec<_> = std::mem::take(&mut self.updates)
+            .into_iter()
+            .map(|update| {
+                let status = local_status(repo, &update)?;
+                Ok((update, status))
+            })
+            .collect::<Result<_, Error>>()?;
+        if self.atomic && updates.iter().any(|(_, status)| is_rejected(status)) {
+            for (_, status) in updates.iter_mut().filter(|(_, status)| *status == Status::Ok) {
+                *status = Status::AtomicPushFailed;
+            }
+        }
+
+        let commands: Vec<_> = updates
+            .iter()
+            .filter(|(_, status)| *status == Status::Ok)
+            .map(|(update, _)| {
+                let null = ObjectId::null(repo.object_hash());
+                gix_protocol::push::Command {
+                    name: update.remote.as_bstr().to_owned(),
+                    old: match update.lease {
+                        Lease::Expect(expected) => expected,
+                        Lease::None => update.remote_old,
+                    }
+                    .unwrap_or(null),
+                    new: update.new.unwrap_or(null),
+                }
+            })
+            .collect();
+
+        if self.dry_run || commands.is_empty() {
+            return Ok(Outcome {
+                handshake,
+                updates,
+                report: None,
+                tracking_ref_edits: Vec::new(),
+                dry_run: self.dry_run,
+            });
+        }
+
+        let mode = repo.config.pack_generation_mode()?;
+        let counts = if commands.iter().all(gix_protocol::push::Command::is_delete) {
+            Vec::new()
+        } else {
+            let remote_tips = handshake
+                .refs
+                .iter()
+                .flatten()
+                .filter_map(|r| r.unpack().1.map(ToOwned::to_owned));
+            count_objects(repo, &commands, remote_tips, mode, &mut progress, should_interrupt)?
+        };
+
+        let db = pack_generation_db(repo)?;
+        let object_hash = repo.object_hash();
+        let outcome = gix_protocol::push(
+            &commands,
+            move |out,
+                  progress,
+                  _should_interrupt|
+                  -> Result<(), output::bytes::Error<output::entry::iter_from_counts::Error>> {
+                let num_entries = counts.len() as u32;
+                let entries = InOrderIter::from(output::entry::iter_from_counts(
+                    counts,
+                    db,
+                    Box::new(progress.add_child("creating entries".into())),
+                    output::entry::iter_from_counts::Options {
+                        allow_thin_pack: false,
+                        mode,
+                        ..Default::default()
+                    },
+                ));
+                for written in output::bytes::FromEntriesIter::new(
+                    entries,
+                    out,
+                    num_entries,
+                    gix_pack::data::Version::V2,
+                    object_hash,
+                ) {
+                    written?;
+                }
+                Ok(())
+            },
+            progress,
+            should_interrupt,
+            gix_protocol::push::Context {
+                handshake: &handshake,
+                transport: &mut con.transport.inner,
+                user_agent: repo.config.user_agent_tuple(),
+                trace_packetlines: con.trace,
+            },
+            gix_protocol::push::Options {
+                atomic: self.atomic,
+                push_options: &self.push_options,
+                quiet: false,
+            },
+        )
+        .await?;
+
+        if let Some(report) = outcome.report.as_ref() {
+            for (update, status) in updates.iter_mut().filter(|(_, status)| *status == Status::Ok) {
+                let remote_status = report
+                    .statuses
+                    .iter()
+                    .find(|s| s.full_ref_name() == update.remote.as_bstr());
+                match remote_status {
+                    Some(gix_protocol::push::response::Status::Ok { .. }) => {}
+                    Some(gix_protocol::push::response::Status::Rejected { reason, .. }) => {
+                        *status = Status::RejectedByRemote { reason: reason.clone() };
+                    }
+                    None => {
+                        *status = Status::RejectedByRemote {
+                            reason: report
+                                .unpack_error
+                                .clone()
+                                .unwrap_or_else(|| "no status reported".into()),
+                        };
+                    }
+                }
+            }
+        }
+
+        let tracking_ref_edits = update_tracking_refs(con.remote, &updates)?;
+        Ok(Outcome {
+            handshake,
+            updates,
+            report: outcome.report,
+            tracking_ref_edits,
+            dry_run: false,
+        })
+    }
+}
+
+/// Return an object database handle suitable for generating packs, which must not unload packs while their entries are referenced.
+fn pack_generation_db(
+    repo: &crate::Repository,
+) -> std::io::Result<gix_odb::Cache<gix_odb::store::Handle<std::sync::Arc<gix_odb::Store>>>> {
+    let mut db = repo.objects.clone().into_inner().into_arc()?;
+    db.prevent_pack_unload();
+    Ok(db)
+}
+
+fn is_rejected(status: &Status) -> bool {
+    !status.is_ok()
+}
+
+/// Determine if `update` may be sent, or why it is rejected.
+fn local_status(repo: &crate::Repository, update: &Update) -> Result<Status, Error> {
+    if let Lease::Expect(expected) = update.lease {
+        if expected != update.remote_old {
+            return Ok(Status::RejectedStale {
+                actual: update.remote_old,
+            });
+        }
+    }
+    let Some(new) = update.new else {
+        return Ok(if update.remote_old.is_some() {
+            Status::Ok
+        } else {
+            Status::RejectedRemoteRefMissing
+        });
+    };
+    let Some(old) = update.remote_old else {
+        return Ok(Status::Ok);
+    };
+    if old == new {
+        return Ok(Status::UpToDate);
+    }
+    if update.force || matches!(update.lease, Lease::Expect(_)) {
+        return Ok(Status::Ok);
+    }
+    if update.remote.as_bstr().starts_with(b"refs/tags/") {
+        return Ok(Status::RejectedAlreadyExists);
+    }
+    if !repo.has_object(old) {
+        return Ok(Status::RejectedFetchFirst);
+    }
+    Ok(if is_ancestor(repo, old, new)? {
+        Status::Ok
+    } else {
+        Status::RejectedNonFastForward
+    })
+}
+
+/// Return `true` if `ancestor` is a commit reachable from the commit that `descendant` peels to.
+///
+/// This is independent of commit times, which may be skewed.
+fn is_ancestor(repo: &crate::Repository, ancestor: ObjectId, descendant: ObjectId) -> Result<bool, Error> {
+    if repo.find_object(ancestor)?.kind != gix_object::Kind::Commit {
+        return Ok(false);
+    }
+    let Ok(descendant) = repo.find_object(descendant)?.peel_to_kind(gix_object::Kind::Commit) else {
+        return Ok(false);
+    };
+    let cache = repo.commit_graph_if_enabled()?;
+    let mut graph = repo.revision_graph(cache.as_ref());
+    Ok(
+        gix_revision::merge_base(ancestor, &[descendant.id], &mut graph)?
+            .is_some_and(|bases| bases.contains(&ancestor)),
+    )
+}
+
+/// Count all objects reachable from the new objects of `commands` which aren't reachable from `remote_tips`, and prepare
+/// them for being written with `mode`.
+fn count_objects(
+    repo: &crate::Repository,
+    commands: &[gix_protocol::push::Command],
+    remote_tips: impl IntoIterator<Item = ObjectId>,
+    mode: output::entry::iter_from_counts::Mode,
+    progress: &mut impl gix_features::progress::NestedProgress,
+    should_interrupt: &AtomicBool,
+) -> Result<Vec<output::Count>, Error> {
+    let mut tips = Vec::new();
+    let mut extra_objects = Vec::new();
+    for id in commands.iter().filter(|c| !c.is_delete()).map(|c| c.new) {
+        let object = repo.find_object(id)?;
+        match object.kind {
+            gix_object::Kind::Commit => tips.push(id),
+            gix_object::Kind::Tag => {
+                extra_objects.push(id);
+                let peeled = object.peel_tags_to_end()?;
+                match peeled.kind {
+                    gix_object::Kind::Commit => tips.push(peeled.id),
+                    _ => extra_objects.push(peeled.id),
+                }
+            }
+            _ => extra_objects.push(id),
+        }
+    }
+    let hidden: Vec<_> = remote_tips
+        .into_iter()
+        .filter(|id| repo.has_object(id))
+        .filter_map(|id| {
+            repo.find_object(id)
+                .ok()?
+                .peel_to_kind(gix_object::Kind::Commit)
+                .ok()
+                .map(|c| c.id)
+        })
+        .collect();
+
+    let commits = if tips.is_empty() {
+        Vec::new()
+    } else {
+        repo.rev_walk(tips)
+            .with_hidden(hidden)
+            .all()?
+            .map(|info| info.map(|info| info.id))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let db = pack_generation_db(repo)?;
+    let mut counting = progress.add_child("counting objects");
+    counting.init(None, gix_features::progress::count("objects"));
+    let mut input = commits
+        .iter()
+        .copied()
+        .chain(extra_objects)
+        .map(Ok::<_, Box<dyn std::error::Error + Send + Sync + 'static>>);
+    #[cfg_attr(not(feature = "attributes"), allow(unused_mut))]
+    let (mut counts, _outcome) = output::count::objects_unthreaded(
+        &db,
+        &mut input,
+        &counting,
+        should_interrupt,
+        output::count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+    )?;
+    #[cfg(feature = "attributes")]
+    if matches!(
+        mode,
+        output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression { .. }
+    ) {
+        repo.mark_no_delta(&commits, &mut counts)?;
+    }
+    #[cfg(not(feature = "attributes"))]
+    let _ = mode;
+    Ok(counts)
+}
+
+/// Update the remote-tracking references of all accepted `updates` as mapped through the fetch refspecs of `remote`.
+fn update_tracking_refs(remote: &crate::Remote<'_>, updates: &[(Update, Status)]) -> Result<Vec<RefEdit>, Error> {
+    let repo = remote.repo;
+    let fetch_specs = remote.refspecs(remote::Direction::Fetch);
+    if fetch_specs.is_empty() {
+        return Ok(Vec::new());
+    }
+    let accepted: Vec<_> = updates
+        .iter()
+        .filter(|(_, status)| *status == Status::Ok)
+        .map(|(update, _)| update)
+        .collect();
+    let null = ObjectId::null(repo.object_hash());
+    let items: Vec<_> = accepted
+        .iter()
+        .map(|update| gix_refspec::match_group::Item {
+            full_ref_name: update.remote.as_bstr(),
+            target: update.new.as_ref().map_or(null.as_ref(), |id| id.as_ref()),
+            object: None,
+        })
+        .collect();
+    let outcome = gix_refspec::MatchGroup::from_fetch_specs(fetch_specs.iter().map(|s| s.to_ref()))
+        .match_lhs(items.iter().copied());
+
+    let mut edits = Vec::new();
+    for mapping in outcome.mappings {
+        let (Some(item_index), Some(rhs)) = (mapping.item_index, mapping.rhs) else {
+            continue;
+        };
+        let Ok(name) = gix_ref::FullName::try_from(rhs.into_owned()) else {
+            continue;
+        };
+        let change = match accepted[item_index].new {
+            Some(new) => Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: "update by push".into(),
+                },
+                expected: PreviousValue::Any,
+                new: gix_ref::Target::Object(new),
+            },
+            None => {
+                if repo.try_find_reference(name.as_ref()).ok().flatten().is_none() {
+                    continue;
+                }
+                Change::Delete {
+                    expected: PreviousValue::Any,
+                    log: RefLog::AndReference,
+                }
+            }
+        };
+        edits.push(RefEdit {
+            change,
+            name,
+            deref: false,
+        });
+    }
+    if edits.is_empty() {
+        return Ok(edits);
+    }
+    Ok(repo.edit_references(edits)?)
+}
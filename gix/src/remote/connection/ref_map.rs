@@ -117,12 +117,56 @@ where
                 extra_refspecs.push(tag_spec);
             }
         }
+        let mut handshake = self
+            .perform_handshake(
+                Direction::Fetch,
+                gix_transport::Service::UploadPack,
+                handshake_parameters,
+                &mut progress,
+            )
+            .await?;
+
+        let context = fetch::refmap::init::Context {
+            fetch_refspecs: self.remote.fetch_specs.clone(),
+            extra_refspecs,
+        };
+
+        let fetch_refmap = handshake.prepare_lsrefs_or_extract_refmap(
+            self.remote.repo.config.user_agent_tuple(),
+            prefix_from_spec_as_filter_on_remote,
+            context,
+        )?;
+
+        #[cfg(feature = "async-network-client")]
+        let ref_map = fetch_refmap
+            .fetch_async(progress, &mut self.transport.inner, self.trace)
+            .await?;
+
+        #[cfg(feature = "blocking-network-client")]
+        let ref_map = fetch_refmap.fetch_blocking(progress, &mut self.transport.inner, self.trace)?;
+
+        self.handshake = Some(handshake);
+        Ok(ref_map)
+    }
+
+    /// Perform a handshake with the remote using `service`, authenticating with the URL for `direction` if needed.
+    ///
+    /// The transport is configured before the handshake, using the repository configuration unless options were set explicitly.
+    #[allow(clippy::result_large_err)]
+    #[gix_protocol::maybe_async::maybe_async]
+    pub(crate) async fn perform_handshake(
+        &mut self,
+        direction: Direction,
+        service: gix_transport::Service,
+        handshake_parameters: Vec<(String, Option<String>)>,
+        progress: &mut impl Progress,
+    ) -> Result<gix_protocol::Handshake, Error> {
         let mut credentials_storage;
         let url = self.transport.inner.to_url();
         let authenticate = match self.authenticate.as_mut() {
             Some(f) => f,
             None => {
-                let url = self.remote.url(Direction::Fetch).map_or_else(
+                let url = self.remote.url(direction).map_or_else(
                     || gix_url::parse(url.as_ref()).expect("valid URL to be provided by transport"),
                     ToOwned::to_owned,
                 );
@@ -143,35 +187,13 @@ where
         if let Some(config) = self.transport_options.as_ref() {
             self.transport.inner.configure(&**config)?;
         }
-        let mut handshake = gix_protocol::handshake(
+        Ok(gix_protocol::handshake(
             &mut self.transport.inner,
-            gix_transport::Service::UploadPack,
+            service,
             authenticate,
             handshake_parameters,
-            &mut progress,
+            progress,
         )
-        .await?;
-
-        let context = fetch::refmap::init::Context {
-            fetch_refspecs: self.remote.fetch_specs.clone(),
-            extra_refspecs,
-        };
-
-        let fetch_refmap = handshake.prepare_lsrefs_or_extract_refmap(
-            self.remote.repo.config.user_agent_tuple(),
-            prefix_from_spec_as_filter_on_remote,
-            context,
-        )?;
-
-        #[cfg(feature = "async-network-client")]
-        let ref_map = fetch_refmap
-            .fetch_async(progress, &mut self.transport.inner, self.trace)
-            .await?;
-
-        #[cfg(feature = "blocking-network-client")]
-        let ref_map = fetch_refmap.fetch_blocking(progress, &mut self.transport.inner, self.trace)?;
-
-        self.handshake = Some(handshake);
-        Ok(ref_map)
+        .await?)
     }
 }
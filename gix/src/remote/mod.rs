@@ -49,6 +49,10 @@ pub mod init;
 ///
 pub mod fetch;
 
+///
+#[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+pub mod push;
+
 ///
 #[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
 pub mod connect;
@@ -61,6 +65,10 @@ pub use connection::{ref_map, AuthenticateFn, Connection};
 ///
 pub mod save;
 
+///
+#[cfg(any(feature = "async-network-client", feature = "blocking-network-client"))]
+pub mod promisor;
+
 mod access;
 ///
 pub mod url;
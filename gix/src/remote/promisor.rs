@@ -0,0 +1,161 @@
+use std::path::Path;
+
+use crate::{
+    bstr::{BStr, BString},
+    config::tree::{Extensions, Remote, Section},
+};
+
+///
+pub mod register {
+    /// The error returned when registering a remote as promisor remote in the repository configuration.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not read the repository configuration file")]
+        Load(#[from] gix_config::file::init::from_paths::Error),
+        #[error("Could not set the promisor remote configuration")]
+        SetValue(#[from] gix_config::file::set_raw_value::Error),
+        #[error("Could not lock the repository configuration file for writing")]
+        AcquireLock(#[from] gix_lock::acquire::Error),
+        #[error("Could not commit the changed repository configuration file")]
+        CommitLock(#[from] gix_lock::commit::Error<gix_lock::File>),
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+    }
+}
+
+/// Return the names of all promisor remotes in `config`, with the one configured in `extensions.partialClone` first,
+/// followed by all remotes with `remote.<name>.promisor` set, in configuration order.
+pub(crate) fn remote_names(config: &gix_config::File<'_>) -> Vec<BString> {
+    let mut out: Vec<BString> = config
+        .string(Extensions::PARTIAL_CLONE)
+        .map(std::borrow::Cow::into_owned)
+        .into_iter()
+        .collect();
+    for section in config.sections_by_name(Remote.name()).into_iter().flatten() {
+        let Some(name) = section.header().subsection_name() else {
+            continue;
+        };
+        if out.iter().any(|existing| existing == name) {
+            continue;
+        }
+        if let Some(Ok(true)) = config.boolean_by(Remote.name(), Some(name), Remote::PROMISOR.name) {
+            out.push(name.to_owned());
+        }
+    }
+    out
+}
+
+/// Configure the remote with `remote_name` in the local configuration file at `config_path` to be a promisor remote
+/// from which objects are fetched with `filter`, and make it the remote of the partial clone if there is none yet.
+///
+/// Return the values that were set for application to the in-memory configuration.
+pub(crate) fn register(
+    config_path: &Path,
+    remote_name: &BStr,
+    filter: &str,
+) -> Result<gix_config::File<'static>, register::Error> {
+    let mut config = gix_config::File::from_path_no_includes(config_path.to_owned(), gix_config::Source::Local)?;
+    let mut changes = gix_config::File::new(config.meta().clone());
+    for file in [&mut config, &mut changes] {
+        file.set_raw_value_by(Remote.name(), Some(remote_name), Remote::PROMISOR.name, "true")?;
+        file.set_raw_value_by(
+            Remote.name(),
+            Some(remote_name),
+            Remote::PARTIAL_CLONE_FILTER.name,
+            filter,
+        )?;
+    }
+    if config.string(Extensions::PARTIAL_CLONE).is_none() {
+        for file in [&mut config, &mut changes] {
+            file.set_raw_value(&Extensions::PARTIAL_CLONE, remote_name)?;
+        }
+    }
+
+    let mut lock = gix_lock::File::acquire_to_update_resource(config_path, gix_lock::acquire::Fail::Immediately, None)?;
+    config.write_to(&mut lock)?;
+    lock.commit()?;
+    Ok(changes)
+}
+
+/// Set up `objects` to fetch missing objects from the promisor remotes in `config`, if there are any, for the repository
+/// at `git_dir` which is opened with `options` to perform the fetch.
+#[cfg(feature = "blocking-network-client")]
+pub(crate) fn setup(
+    objects: &mut crate::OdbHandle,
+    config: &gix_config::File<'_>,
+    git_dir: &Path,
+    options: &crate::open::Options,
+) {
+    let remote_names = remote_names(config);
+    objects.promisor = (!remote_names.is_empty()).then(|| {
+        std::sync::Arc::new(blocking::LazyFetch {
+            git_dir: git_dir.to_owned(),
+            options: options.clone(),
+            remote_names,
+        }) as _
+    });
+}
+
+#[cfg(feature = "blocking-network-client")]
+mod blocking {
+    use std::{path::PathBuf, sync::atomic::AtomicBool};
+
+    use gix_hash::ObjectId;
+
+    use crate::{
+        bstr::{BString, ByteSlice},
+        config::tree::{Fetch, Remote},
+        remote,
+    };
+
+    /// Fetch missing objects from promisor remotes using a new instance of the repository, like `git` does.
+    pub(crate) struct LazyFetch {
+        pub(crate) git_dir: PathBuf,
+        pub(crate) options: crate::open::Options,
+        pub(crate) remote_names: Vec<BString>,
+    }
+
+    impl gix_odb::promisor::Fetch for LazyFetch {
+        fn fetch(&self, ids: &[ObjectId]) -> Result<(), gix_odb::promisor::Error> {
+            let mut repo = crate::open_opts(&self.git_dir, self.options.clone())?;
+            // Negotiation would prevent the server from sending objects reachable from what we have, which are exactly
+            // the ones we are missing.
+            {
+                let mut config = repo.config_snapshot_mut();
+                config.set_value(&Fetch::NEGOTIATION_ALGORITHM, "noop")?;
+                // Like `git`, don't receive blobs that trees we ask for refer to. Configuring the filter this way
+                // leaves the configuration file alone.
+                for remote_name in &self.remote_names {
+                    config.set_subsection_value(&Remote::PARTIAL_CLONE_FILTER, remote_name.as_bstr(), "blob:none")?;
+                }
+            }
+            repo.objects.fetch_missing_never();
+
+            let mut missing = ids.to_vec();
+            for remote_name in &self.remote_names {
+                let mut remote = repo
+                    .find_remote(remote_name.as_bstr())?
+                    .with_fetch_tags(remote::fetch::Tags::None);
+                remote.replace_refspecs(
+                    missing.iter().map(|id| BString::from(id.to_string())),
+                    remote::Direction::Fetch,
+                )?;
+                let outcome = remote
+                    .connect(remote::Direction::Fetch)?
+                    .prepare_fetch(gix_features::progress::Discard, Default::default())?
+                    .receive(gix_features::progress::Discard, &AtomicBool::default())?;
+                if let remote::fetch::Status::Change { write_pack_bundle, .. } = outcome.status {
+                    if let Some(keep_path) = write_pack_bundle.keep_path {
+                        std::fs::remove_file(keep_path)?;
+                    }
+                }
+                missing.retain(|id| !repo.has_object(id));
+                if missing.is_empty() {
+                    break;
+                }
+            }
+            Ok(())
+        }
+    }
+}
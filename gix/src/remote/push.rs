@@ -0,0 +1 @@
+pub use super::connection::push::{prepare, Error, Lease, Outcome, Prepare, Status, Update};
@@ -1,4 +1,11 @@
-use crate::{config, Repository};
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::AtomicBool,
+};
+
+use gix_index::entry::{Flags, Stage};
+
+use crate::{bstr::BStr, config, repository::update_worktree, Repository};
 
 impl Repository {
     /// Return options that can be used to drive a low-level checkout operation.
@@ -11,4 +18,157 @@ impl Repository {
     ) -> Result<gix_worktree_state::checkout::Options, config::checkout_options::Error> {
         self.config.checkout_options(self, attributes_source)
     }
+
+    /// Change the files in the worktree from the state of the index `from` to the state of the index `to`, similar to
+    /// `git read-tree -m -u`, and check `should_interrupt` to stop the checkout.
+    ///
+    /// Files of entries that are only in `from` are removed along with the directories that become empty, and files of
+    /// entries that are new or changed in `to` are written, overwriting what's in the worktree.
+    /// Entries of `to` that are unchanged receive the `stat` information of their counterpart in `from`, the others
+    /// receive the `stat` information of the files that were written.
+    /// Entries that are skipped in the worktree or that are conflicting in `to` are ignored.
+    ///
+    /// Note that files aren't checked for local modifications, so `from` should reflect what's in the worktree, and
+    /// `to` isn't written.
+    pub fn update_worktree(
+        &self,
+        from: &gix_index::State,
+        to: &mut gix_index::State,
+        should_interrupt: &AtomicBool,
+    ) -> Result<update_worktree::Outcome, update_worktree::Error> {
+        use update_worktree::Error;
+
+        let workdir = self.workdir().ok_or_else(|| Error::BareRepository {
+            git_dir: self.git_dir().to_owned(),
+        })?;
+        let mut outcome = update_worktree::Outcome::default();
+        let mut opts = self.checkout_options(gix_worktree::stack::state::attributes::Source::IdMappingThenWorktree)?;
+
+        let mut stack = removal_stack(workdir, &opts, from);
+        let mut last_removed = None;
+        for entry in from.entries() {
+            let rela_path = entry.path(from);
+            if entry.flags.contains(Flags::SKIP_WORKTREE)
+                || to.entry_by_path(rela_path).is_some()
+                || last_removed == Some(rela_path)
+            {
+                continue;
+            }
+            last_removed = Some(rela_path);
+            if remove_file(&mut stack, workdir, rela_path, entry.mode, &self.objects)
+                .map_err(|(path, source)| Error::Io { path, source })?
+            {
+                outcome.files_removed += 1;
+            }
+        }
+
+        let mut to_checkout = gix_index::State::new(self.object_hash());
+        let mut checkout_indices = Vec::new();
+        let mut unchanged = Vec::new();
+        for (idx, entry) in to.entries().iter().enumerate() {
+            if entry.stage() != Stage::Unconflicted || entry.flags.contains(Flags::SKIP_WORKTREE) {
+                continue;
+            }
+            let rela_path = entry.path(to);
+            match from.entry_by_path_and_stage(rela_path, Stage::Unconflicted) {
+                Some(prev)
+                    if prev.id == entry.id && prev.mode == entry.mode && !prev.flags.contains(Flags::SKIP_WORKTREE) =>
+                {
+                    unchanged.push((idx, prev.stat));
+                }
+                _ => {
+                    to_checkout.dangerously_push_entry(
+                        Default::default(),
+                        entry.id,
+                        entry.flags,
+                        entry.mode,
+                        rela_path,
+                    );
+                    checkout_indices.push(idx);
+                }
+            }
+        }
+        for (idx, stat) in unchanged {
+            to.entries_mut()[idx].stat = stat;
+        }
+
+        if !checkout_indices.is_empty() {
+            // In partial clones, fetch all missing blobs at once instead of one by one.
+            self.objects
+                .prefetch(
+                    to_checkout
+                        .entries()
+                        .iter()
+                        .filter(|entry| entry.mode != gix_index::entry::Mode::COMMIT)
+                        .map(|entry| entry.id),
+                )
+                .map_err(Error::PromisorFetch)?;
+
+            opts.destination_is_initially_empty = false;
+            opts.overwrite_existing = true;
+            outcome.checkout = gix_worktree_state::checkout(
+                &mut to_checkout,
+                workdir,
+                self.objects.clone().into_arc()?,
+                &gix_features::progress::Discard,
+                &gix_features::progress::Discard,
+                should_interrupt,
+                opts,
+            )?;
+            for (idx, entry) in checkout_indices.into_iter().zip(to_checkout.entries()) {
+                to.entries_mut()[idx].stat = entry.stat;
+            }
+        }
+        Ok(outcome)
+    }
+}
+
+/// Return a stack to [remove files](remove_file()) from `workdir` with, which validates paths like a checkout with `opts`,
+/// using `index` to look up attributes.
+pub(crate) fn removal_stack(
+    workdir: &Path,
+    opts: &gix_worktree_state::checkout::Options,
+    index: &gix_index::State,
+) -> gix_worktree::Stack {
+    gix_worktree::Stack::from_state_and_ignore_case(
+        workdir,
+        opts.fs.ignore_case,
+        gix_worktree::stack::State::for_checkout(false, opts.validate, opts.attributes.clone()),
+        index,
+        index.path_backing(),
+    )
+}
+
+/// Remove the file at `rela_path` with `mode` from `workdir` through `stack`, along with all leading directories that
+/// became empty, and return `true` if it existed.
+///
+/// Nothing is removed if one of the leading directories is a symlink or a file, and errors come with the path they refer to.
+pub(crate) fn remove_file(
+    stack: &mut gix_worktree::Stack,
+    workdir: &Path,
+    rela_path: &BStr,
+    mode: gix_index::entry::Mode,
+    objects: &dyn gix_object::Find,
+) -> Result<bool, (PathBuf, std::io::Error)> {
+    let path = match stack.at_entry(rela_path, Some(mode), objects) {
+        Ok(platform) => platform.path().to_owned(),
+        Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => return Ok(false),
+        Err(source) => return Err((workdir.join(gix_path::from_bstr(rela_path)), source)),
+    };
+    let existed = match std::fs::remove_file(&path) {
+        Ok(()) => true,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => false,
+        Err(source) => return Err((path, source)),
+    };
+    remove_empty_leading_directories(&path, workdir);
+    Ok(existed)
+}
+
+/// Remove all empty directories leading up to the file at `path`, but not `workdir` itself.
+pub(crate) fn remove_empty_leading_directories(path: &Path, workdir: &Path) {
+    for dir in path.ancestors().skip(1) {
+        if dir == workdir || !dir.starts_with(workdir) || std::fs::remove_dir(dir).is_err() {
+            break;
+        }
+    }
 }
@@ -0,0 +1,294 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_hash::ObjectId;
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    cherry_pick::{Error, Options, Outcome, CHERRY_PICK_HEAD, MERGE_MSG, REVERT_HEAD},
+    repository::merge_worktree::{AppliedMerge, Refusal},
+    Id, Repository,
+};
+
+/// The result of merging the changes of a commit into the index and the worktree.
+pub(crate) struct MergedChanges {
+    /// The parent of the commit the changes were computed relative to.
+    pub parent: Option<ObjectId>,
+    /// The summary of the commit.
+    pub subject: BString,
+    /// The commit `HEAD` pointed to.
+    pub head: ObjectId,
+    /// The tree of `head`.
+    pub head_tree: ObjectId,
+    /// The merged changes.
+    pub applied: AppliedMerge,
+}
+
+/// The operations that apply the changes of a single commit.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum Operation {
+    CherryPick,
+    Revert,
+}
+
+impl Operation {
+    fn as_str(self) -> &'static str {
+        match self {
+            Operation::CherryPick => "cherry-pick",
+            Operation::Revert => "revert",
+        }
+    }
+}
+
+/// Cherry-picking and reverting
+impl Repository {
+    /// Apply the changes introduced by `commit` to the index and the worktree with `options` and commit them on top of `HEAD`
+    /// with the message and author of `commit`, like `git cherry-pick`, checking `should_interrupt` to stop the checkout.
+    ///
+    /// The changes are merged into `HEAD` with the parent of `commit` as merge base, and the operation fails without changes
+    /// if local modifications or untracked files would be overwritten.
+    /// If there are conflicts, their stages are written to the index and [`CHERRY_PICK_HEAD`] refers to `commit`.
+    pub fn cherry_pick(
+        &self,
+        commit: impl Into<ObjectId>,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        self.apply_commit(commit.into(), Operation::CherryPick, options, should_interrupt)
+    }
+
+    /// Undo the changes introduced by `commit` in the index and the worktree with `options` and commit the result on top
+    /// of `HEAD` with a message saying that `commit` is reverted, like `git revert`, checking `should_interrupt` to stop
+    /// the checkout.
+    ///
+    /// The changes are merged into `HEAD` with `commit` as merge base and its parent as the other side, and the operation
+    /// fails without changes if local modifications or untracked files would be overwritten.
+    /// If there are conflicts, their stages are written to the index and [`REVERT_HEAD`] refers to `commit`.
+    pub fn revert(
+        &self,
+        commit: impl Into<ObjectId>,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        self.apply_commit(commit.into(), Operation::Revert, options, should_interrupt)
+    }
+
+    fn apply_commit(
+        &self,
+        id: ObjectId,
+        operation: Operation,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        if self.workdir().is_none() {
+            return Err(Error::BareRepository {
+                git_dir: self.git_dir().to_owned(),
+            });
+        }
+        if let Some(state) = self.state() {
+            return Err(Error::InProgress { state });
+        }
+
+        let commit = self.find_commit(id)?;
+        let merged = self.merge_commit_changes(
+            &commit,
+            operation,
+            options.mainline,
+            options.no_commit,
+            should_interrupt,
+        )?;
+        let has_conflicts = !merged.applied.conflicts.is_empty();
+        if !has_conflicts && !options.no_commit && !options.allow_empty && merged.applied.tree == merged.head_tree {
+            return Err(Error::Empty { id });
+        }
+
+        let message = match operation {
+            Operation::CherryPick => cherry_pick_message(commit.message_raw()?, options.record_origin.then_some(id)),
+            Operation::Revert => revert_message(
+                merged.subject.as_ref(),
+                id,
+                merged.parent.filter(|_| options.mainline.is_some()),
+            ),
+        };
+        let worktree = merged.applied.worktree;
+        if has_conflicts || options.no_commit {
+            let mut merge_message = message;
+            append_conflicts(&mut merge_message, &merged.applied.conflicted_paths);
+            self.write_git_dir_file(MERGE_MSG, &merge_message)?;
+            if !options.no_commit {
+                let head_file = match operation {
+                    Operation::CherryPick => CHERRY_PICK_HEAD,
+                    Operation::Revert => REVERT_HEAD,
+                };
+                self.write_git_dir_file(head_file, format!("{id}\n").as_bytes())?;
+            }
+            return Ok(Outcome {
+                commit: None,
+                conflicts: merged.applied.conflicts,
+                worktree,
+            });
+        }
+
+        let committer = self.committer().ok_or(Error::CommitterMissing)??;
+        let author = match operation {
+            Operation::CherryPick => commit.author()?,
+            Operation::Revert => self.author().ok_or(Error::AuthorMissing)??,
+        };
+        let new_commit = gix_object::Commit {
+            message,
+            tree: merged.applied.tree,
+            author: author.into(),
+            committer: committer.into(),
+            encoding: None,
+            parents: [merged.head].into(),
+            extra_headers: Default::default(),
+        };
+        let new_id = self
+            .write_commit_and_update_reference(
+                new_commit,
+                committer,
+                "HEAD".try_into().expect("valid"),
+                operation.as_str(),
+            )?
+            .detach();
+        #[cfg(feature = "command")]
+        self.run_hook_after_commit()?;
+        Ok(Outcome {
+            commit: Some(new_id),
+            conflicts: merged.applied.conflicts,
+            worktree,
+        })
+    }
+
+    /// Merge the changes introduced by `commit` relative to its parent, or to its parent number `mainline`, into `HEAD`,
+    /// the index and the worktree, or undo them if `operation` is a revert, and check `should_interrupt` to stop the checkout.
+    ///
+    /// The index must match `HEAD` unless `allow_dirty_index` is `true`.
+    pub(crate) fn merge_commit_changes(
+        &self,
+        commit: &crate::Commit<'_>,
+        operation: Operation,
+        mainline: Option<usize>,
+        allow_dirty_index: bool,
+        should_interrupt: &AtomicBool,
+    ) -> Result<MergedChanges, Error> {
+        let id = commit.id;
+        let parents: Vec<_> = commit.parent_ids().map(Id::detach).collect();
+        let parent = match (mainline, parents.len()) {
+            (None, 0) => None,
+            (None, 1) => Some(parents[0]),
+            (None, _) => return Err(Error::MissingMainline { id }),
+            (Some(_), 0 | 1) => return Err(Error::UnexpectedMainline { id }),
+            (Some(mainline), _) => Some(
+                mainline
+                    .checked_sub(1)
+                    .and_then(|idx| parents.get(idx).copied())
+                    .ok_or(Error::InvalidMainline { id, mainline })?,
+            ),
+        };
+        let commit_tree = commit.tree_id()?.detach();
+        let parent_tree = match parent {
+            Some(parent) => self.find_commit(parent)?.tree_id()?.detach(),
+            None => ObjectId::empty_tree(self.object_hash()),
+        };
+        let head = self.head_commit()?;
+        let head_tree = head.tree_id()?.detach();
+        let (current, current_tree) = self.index_to_merge_into::<Error>(&head_tree, allow_dirty_index)?;
+
+        let subject = commit.message()?.summary().into_owned();
+        let picked = format!("{} ({subject})", commit.short_id()?);
+        let parent_of = format!("parent of {picked}");
+        let (base_tree, their_tree, base_label, their_label) = match operation {
+            Operation::CherryPick => (parent_tree, commit_tree, parent_of.as_str(), picked.as_str()),
+            Operation::Revert => (commit_tree, parent_tree, picked.as_str(), parent_of.as_str()),
+        };
+        let labels = gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: Some(base_label.into()),
+            current: Some("HEAD".into()),
+            other: Some(their_label.into()),
+        };
+        let outcome = self.merge_trees(base_tree, current_tree, their_tree, labels, self.tree_merge_options()?)?;
+        let applied = self.apply_merge_outcome::<Error>(&current, outcome, should_interrupt)?;
+        Ok(MergedChanges {
+            parent,
+            subject,
+            head: head.id,
+            head_tree,
+            applied,
+        })
+    }
+
+    pub(crate) fn write_git_dir_file(&self, name: &str, content: &[u8]) -> Result<(), Error> {
+        let path = self.git_dir().join(name);
+        std::fs::write(&path, content).map_err(|source| Error::Io { path, source })
+    }
+}
+
+impl From<Refusal> for Error {
+    fn from(value: Refusal) -> Self {
+        match value {
+            Refusal::UnmergedIndex => Error::UnmergedIndex,
+            Refusal::DirtyIndex => Error::DirtyIndex,
+            Refusal::WouldOverwriteLocalChanges(paths) => Error::WouldOverwriteLocalChanges { paths },
+        }
+    }
+}
+
+/// Append a comment that lists the `conflicted_paths` to `message`, if there are any, like `git` does in `MERGE_MSG`.
+pub(crate) fn append_conflicts(message: &mut BString, conflicted_paths: &[BString]) {
+    if conflicted_paths.is_empty() {
+        return;
+    }
+    message.push_str("\n# Conflicts:\n");
+    for rela_path in conflicted_paths {
+        message.push_str("#\t");
+        message.push_str(rela_path);
+        message.push_byte(b'\n');
+    }
+}
+
+/// Return `message` with a line recording that it was cherry-picked from `origin`, if set, like `git cherry-pick -x`.
+fn cherry_pick_message(message: &BStr, origin: Option<ObjectId>) -> BString {
+    let mut out = BString::from(message);
+    if let Some(origin) = origin {
+        if !out.ends_with(b"\n") {
+            out.push_byte(b'\n');
+        }
+        if !ends_with_trailers(out.as_ref()) {
+            out.push_byte(b'\n');
+        }
+        out.push_str(format!("(cherry picked from commit {origin})\n"));
+    }
+    out
+}
+
+/// Return the message of a commit that reverts commit `id` with `subject`, which was a merge if `mainline_parent` is set.
+fn revert_message(subject: &BStr, id: ObjectId, mainline_parent: Option<ObjectId>) -> BString {
+    // Like `git`, reverting a revert reapplies the original commit.
+    let title = match subject
+        .strip_prefix(b"Revert \"")
+        .and_then(|rest| rest.strip_suffix(b"\""))
+    {
+        Some(reverted_subject) => format!("Reapply \"{}\"", reverted_subject.as_bstr()),
+        None => format!("Revert \"{subject}\""),
+    };
+    match mainline_parent {
+        Some(parent) => format!("{title}\n\nThis reverts commit {id}, reversing\nchanges made to {parent}.\n"),
+        None => format!("{title}\n\nThis reverts commit {id}.\n"),
+    }
+    .into()
+}
+
+/// Return `true` if the last paragraph of `message`, which isn't its subject, consists only of trailers like
+/// `Signed-off-by: name` or of lines recording cherry-picks.
+fn ends_with_trailers(message: &BStr) -> bool {
+    let message = message.trim_end();
+    let Some(pos) = message.rfind(b"\n\n") else {
+        return false;
+    };
+    message[pos + 2..].lines().all(|line| {
+        line.starts_with(b"(cherry picked from commit ")
+            || line
+                .find(b": ")
+                .is_some_and(|pos| pos > 0 && line[..pos].iter().all(|b| b.is_ascii_alphanumeric() || *b == b'-'))
+    })
+}
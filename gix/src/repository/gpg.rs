@@ -0,0 +1,85 @@
+use crate::{
+    config::tree::{
+        gpg::{OpenPgp, SignatureFormat, Ssh, X509},
+        Gpg, User,
+    },
+    gpg,
+};
+
+/// Signing and verification
+impl crate::Repository {
+    /// Return a signer which creates signatures in the format configured in `gpg.format`, using the key in
+    /// `user.signingKey` and the program in `gpg.<format>.program`.
+    ///
+    /// Like `git`, the name and email of the committer identify the key to use with `gpg` and `gpgsm` if
+    /// `user.signingKey` isn't set, and `gpg.program` is used if `gpg.openpgp.program` isn't set.
+    /// For `ssh`, `user.signingKey` is the path to a key, or a public key prefixed with `key::` whose private key
+    /// is held by `ssh-agent`.
+    pub fn signer(&self) -> Result<gpg::Signer, gpg::signer::Error> {
+        let config = self.config_snapshot();
+        let format = config
+            .string(&Gpg::FORMAT)
+            .map(|value| Gpg::FORMAT.try_into_format(value))
+            .transpose()?
+            .unwrap_or_default();
+        let program = self.gpg_program(format);
+
+        let key = match (format, config.string(&User::SIGNING_KEY)) {
+            (SignatureFormat::Ssh, Some(key)) if key.starts_with(b"key::") || key.starts_with(b"ssh-") => {
+                gpg::Key::SshLiteral(key.into_owned())
+            }
+            (SignatureFormat::Ssh, Some(key)) => match config.trusted_path(&User::SIGNING_KEY).transpose()? {
+                Some(path) => gpg::Key::SshFile(path.into_owned()),
+                None => gpg::Key::SshFile(gix_path::from_bstr(key.as_ref()).into_owned()),
+            },
+            (SignatureFormat::Ssh, None) => return Err(gpg::signer::Error::SshKeyMissing),
+            (_, Some(key)) => gpg::Key::Id(key.into_owned()),
+            (_, None) => {
+                let committer = self.committer().ok_or(gpg::signer::Error::CommitterMissing)??;
+                gpg::Key::Id(format!("{} <{}>", committer.name, committer.email).into())
+            }
+        };
+        Ok(gpg::Signer::new(format, program, key))
+    }
+
+    /// Return a verifier for signatures in all formats, which uses the program in `gpg.<format>.program`
+    /// and trusts the ssh keys in `gpg.ssh.allowedSignersFile`.
+    ///
+    /// Like `git`, `gpg.program` is used if `gpg.openpgp.program` isn't set, and signatures are only good if their key is
+    /// trusted at least as much as configured in `gpg.minTrustLevel`.
+    pub fn signature_verifier(&self) -> Result<gpg::Verifier, gpg::verifier::Error> {
+        let config = self.config_snapshot();
+        let allowed_signers_file = config
+            .trusted_path(&Ssh::ALLOWED_SIGNERS_FILE)
+            .transpose()?
+            .map(std::borrow::Cow::into_owned);
+        let min_trust_level = config
+            .string(&Gpg::MIN_TRUST_LEVEL)
+            .map(|value| Gpg::MIN_TRUST_LEVEL.try_into_trust_level(value))
+            .transpose()?
+            .unwrap_or_default();
+        Ok(gpg::Verifier::new(
+            self.gpg_program(SignatureFormat::OpenPgp),
+            self.gpg_program(SignatureFormat::X509),
+            self.gpg_program(SignatureFormat::Ssh),
+            allowed_signers_file,
+            min_trust_level,
+        ))
+    }
+
+    /// Return the program to create and verify signatures in `format` with.
+    fn gpg_program(&self, format: SignatureFormat) -> std::ffi::OsString {
+        let config = self.config_snapshot();
+        let (program, default_program) = match format {
+            SignatureFormat::OpenPgp => (
+                config
+                    .trusted_program(&OpenPgp::PROGRAM)
+                    .or_else(|| config.trusted_program(&Gpg::PROGRAM)),
+                "gpg",
+            ),
+            SignatureFormat::X509 => (config.trusted_program(&X509::PROGRAM), "gpgsm"),
+            SignatureFormat::Ssh => (config.trusted_program(&Ssh::PROGRAM), "ssh-keygen"),
+        };
+        program.map_or_else(|| default_program.into(), std::borrow::Cow::into_owned)
+    }
+}
@@ -1,4 +1,9 @@
-use crate::Error;
+use gix_object::FindExt;
+
+use crate::{
+    config::{cache::util::ApplyLeniency, tree::CommitGraph},
+    Error,
+};
 
 impl crate::Repository {
     /// Create a graph data-structure capable of accelerating graph traversals and storing state of type `T` with each commit
@@ -44,4 +49,69 @@ impl crate::Repository {
                 _ => Err(err.into_error()),
             })?)
     }
+
+    /// Write or update the commit-graph so that it contains all commits reachable from all references, writing
+    /// a single `commit-graph` file if `split` is `None`, or a chain of files otherwise.
+    ///
+    /// Only commits that aren't in the commit-graph yet are read from the object database, which makes this
+    /// suitable to be called after fetches to add the commits that were received, as with `fetch.writeCommitGraph`.
+    /// Corrected commit dates are written unless `commitGraph.generationVersion` is `1`.
+    pub fn write_commit_graph(
+        &self,
+        split: Option<gix_commitgraph::write::Split>,
+    ) -> Result<gix_commitgraph::write::Outcome, super::write_commit_graph::Error> {
+        use super::write_commit_graph::Error;
+        if self.is_shallow() {
+            return Err(Error::Shallow);
+        }
+        let generation_data = self
+            .config
+            .resolved
+            .integer(CommitGraph::GENERATION_VERSION)
+            .map(|value| CommitGraph::GENERATION_VERSION.try_into_generation_data(value))
+            .transpose()
+            .with_leniency(self.options.lenient_config)?
+            .unwrap_or(true);
+        let existing = self.commit_graph().ok();
+        let is_known = |id: &gix_hash::oid| existing.as_ref().is_some_and(|graph| graph.lookup(id).is_some());
+
+        let mut tips = Vec::new();
+        for reference in self.references()?.all()? {
+            let mut reference = reference.map_err(Error::IterReferences)?;
+            let id = reference.peel_to_id()?.detach();
+            if self.find_header(id)?.kind() == gix_object::Kind::Commit {
+                tips.push(id);
+            }
+        }
+        if let Ok(head) = self.head_id() {
+            tips.push(head.detach());
+        }
+
+        let mut commits = Vec::new();
+        let mut seen = gix_hashtable::HashSet::default();
+        let mut buf = Vec::new();
+        while let Some(id) = tips.pop() {
+            if is_known(&id) || !seen.insert(id) {
+                continue;
+            }
+            let commit = self.objects.find_commit(&id, &mut buf)?;
+            let parents: Vec<_> = commit.parents().collect();
+            tips.extend(parents.iter().copied());
+            commits.push(gix_commitgraph::write::Commit {
+                id,
+                tree: commit.tree(),
+                parents,
+                commit_time: commit.time()?.seconds.max(0) as u64,
+            });
+        }
+        drop(existing);
+
+        Ok(gix_commitgraph::write::at(
+            &self.objects.store_ref().path().join("info"),
+            commits,
+            self.object_hash(),
+            gix_commitgraph::write::Options { split, generation_data },
+        )
+        .map_err(crate::Error::from)?)
+    }
 }
@@ -0,0 +1,64 @@
+use std::path::{Path, PathBuf};
+
+use crate::{config::tree::Core, hook, hook::Hook};
+
+/// Hooks
+impl crate::Repository {
+    /// Return the directory in which hooks are looked up, which is `core.hooksPath` if set, or the `hooks` directory
+    /// in the [common directory](Self::common_dir()).
+    ///
+    /// Like `git`, a relative `core.hooksPath` is relative to the working tree, or to the git directory in bare repositories.
+    pub fn hooks_dir(&self) -> Result<PathBuf, hook::find::Error> {
+        let current_dir = self.hook_dir_to_run_in()?;
+        self.hooks_dir_relative_to(&current_dir)
+    }
+
+    /// Return the hook with `name`, like `pre-commit`, if it exists in the [hooks directory](Self::hooks_dir()) and is
+    /// executable, or `None` otherwise.
+    ///
+    /// It runs in the working tree, or in the git directory of bare repositories, with `GIT_DIR` set to the git directory.
+    /// Note that `None` is also returned if the repository isn't [fully trusted](Self::git_dir_trust()), as anyone who can
+    /// write to it could make us run arbitrary programs otherwise.
+    ///
+    /// Hooks are only run by operations like [`commit()`](Self::commit()) if [enabled](crate::open::Options::run_hooks()).
+    pub fn hook(&self, name: &str) -> Result<Option<Hook>, hook::find::Error> {
+        let current_dir = self.hook_dir_to_run_in()?;
+        self.hook_in(name, current_dir)
+    }
+
+    /// Like [`hook()`](Self::hook()), but return `None` if operations aren't supposed to run hooks as per
+    /// [`open::Options::run_hooks()`](crate::open::Options::run_hooks()).
+    pub(crate) fn hook_to_run(&self, name: &str) -> Result<Option<Hook>, hook::find::Error> {
+        if !self.options.run_hooks {
+            return Ok(None);
+        }
+        self.hook(name)
+    }
+
+    /// Like [`hook()`](Self::hook()), but run the hook in `current_dir`, which is also what a relative `core.hooksPath`
+    /// is relative to.
+    pub(crate) fn hook_in(&self, name: &str, current_dir: PathBuf) -> Result<Option<Hook>, hook::find::Error> {
+        if self.git_dir_trust() != gix_sec::Trust::Full {
+            return Ok(None);
+        }
+        let hooks_dir = self.hooks_dir_relative_to(&current_dir)?;
+        let git_dir = gix_path::realpath(self.git_dir())?;
+        Ok(Hook::at(name, &hooks_dir, &current_dir, &git_dir))
+    }
+
+    fn hook_dir_to_run_in(&self) -> Result<PathBuf, hook::find::Error> {
+        Ok(match self.workdir() {
+            Some(workdir) => gix_path::realpath(workdir)?,
+            None => gix_path::realpath(self.git_dir())?,
+        })
+    }
+
+    fn hooks_dir_relative_to(&self, current_dir: &Path) -> Result<PathBuf, hook::find::Error> {
+        Ok(
+            match self.config_snapshot().trusted_path(&Core::HOOKS_PATH).transpose()? {
+                Some(path) => current_dir.join(path),
+                None => gix_path::realpath(self.common_dir())?.join("hooks"),
+            },
+        )
+    }
+}
@@ -1,5 +1,8 @@
 use crate::{
-    config::{cache::util::ApplyLeniencyDefault, tree::Index},
+    config::{
+        cache::util::ApplyLeniencyDefault,
+        tree::{Core, Index, SplitIndex},
+    },
     worktree,
     worktree::IndexPersistedOrInMemory,
 };
@@ -41,6 +44,56 @@ impl crate::Repository {
         Ok(index)
     }
 
+    /// Return the options to write `index` with, as configured by `index.skipHash`, `core.splitIndex` and the `splitIndex` section.
+    ///
+    /// Like `git`, `index` is written as split index if it was read from one and `core.splitIndex` isn't set.
+    pub fn index_write_options(
+        &self,
+        index: &gix_index::State,
+    ) -> Result<gix_index::write::Options, crate::repository::index_write_options::Error> {
+        let config = &self.config.resolved;
+        let lenient = self.config.lenient_config;
+        let skip_hash = config
+            .boolean(Index::SKIP_HASH)
+            .map(|res| Index::SKIP_HASH.enrich_error(res))
+            .transpose()
+            .with_lenient_default(lenient)?
+            .unwrap_or_default();
+        let split_index = config
+            .boolean(Core::SPLIT_INDEX)
+            .map(|res| Core::SPLIT_INDEX.enrich_error(res))
+            .transpose()
+            .with_lenient_default(lenient)?
+            .unwrap_or_else(|| index.link().is_some());
+        let split_index = if split_index {
+            let mut split = gix_index::write::SplitIndex::default();
+            if let Some(max_percent_change) = config
+                .integer(SplitIndex::MAX_PERCENT_CHANGE)
+                .map(|value| SplitIndex::MAX_PERCENT_CHANGE.try_into_u64(value))
+                .transpose()
+                .with_lenient_default(lenient)?
+            {
+                split.max_percent_change = max_percent_change.min(100) as u8;
+            }
+            if let Some(expire) = config
+                .string(SplitIndex::SHARED_INDEX_EXPIRE)
+                .map(|value| SplitIndex::SHARED_INDEX_EXPIRE.try_into_expire(value, std::time::SystemTime::now()))
+                .transpose()
+                .with_lenient_default(lenient)?
+            {
+                split.shared_index_expire = expire;
+            }
+            Some(split)
+        } else {
+            None
+        };
+        Ok(gix_index::write::Options {
+            extensions: Default::default(),
+            skip_hash,
+            split_index,
+        })
+    }
+
     /// Return a shared worktree index which is updated automatically if the in-memory snapshot has become stale as the underlying file
     /// on disk has changed.
     ///
@@ -140,19 +193,35 @@ impl crate::Repository {
         })
     }
 
+    /// Write the trees of all entries in `index` to the object database and return the id of the root tree,
+    /// similar to `git write-tree`.
+    ///
+    /// Trees that are still valid in the `TREE` extension of `index` are reused, and the extension is updated with all written trees
+    /// so that it can be persisted along with `index`.
+    /// Note that `index` must not have conflicts.
+    pub fn write_tree_from_index(
+        &self,
+        index: &mut gix_index::State,
+    ) -> Result<crate::Id<'_>, gix_index::write_tree::Error> {
+        let id = index.write_tree(self)?;
+        Ok(crate::Id::from_id(id, self))
+    }
+
     /// Create new index-file, which would live at the correct location, in memory from the given `tree`.
+    /// Its version is configured by `index.version`.
     ///
     /// Note that this is an expensive operation as it requires recursively traversing the entire tree to unpack it into the index.
     pub fn index_from_tree(&self, tree: &gix_hash::oid) -> Result<gix_index::File, super::index_from_tree::Error> {
-        Ok(gix_index::File::from_state(
-            gix_index::State::from_tree(tree, self, self.config.protect_options()?).map_err(|err| {
-                super::index_from_tree::Error::IndexFromTree {
-                    id: tree.into(),
-                    source: err,
-                }
-            })?,
-            self.git_dir().join("index"),
-        ))
+        let mut index = gix_index::State::from_tree(tree, self, self.config.protect_options()?).map_err(|err| {
+            super::index_from_tree::Error::IndexFromTree {
+                id: tree.into(),
+                source: err,
+            }
+        })?;
+        if let Some(version) = self.config.index_version()? {
+            index.set_version(version);
+        }
+        Ok(gix_index::File::from_state(index, self.git_dir().join("index")))
     }
 }
 
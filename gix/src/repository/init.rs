@@ -14,6 +14,8 @@ impl crate::Repository {
         #[cfg(feature = "attributes")] modules: crate::submodule::ModulesFileStorage,
     ) -> Self {
         setup_objects(&mut objects, &config);
+        #[cfg(feature = "blocking-network-client")]
+        crate::remote::promisor::setup(&mut objects, &config.resolved, refs.git_dir(), &linked_worktree_options);
         crate::Repository {
             bufs: Some(RefCell::new(Vec::with_capacity(4))),
             work_tree,
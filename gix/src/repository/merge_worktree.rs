@@ -0,0 +1,450 @@
+use std::{collections::BTreeSet, sync::atomic::AtomicBool};
+
+use gix_hash::ObjectId;
+use gix_index::entry::Stage;
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+use gix_status::index_as_worktree::EntryStatus;
+use gix_worktree::sparse::Patterns;
+
+use crate::{
+    bstr::BString,
+    merge::{
+        tree::{apply_index_entries::RemovalMode, TreatAsUnresolved},
+        worktree::{Error, FastForward, Options, Outcome, MERGE_HEAD, MERGE_MODE, MERGE_MSG},
+    },
+    repository::cherry_pick::append_conflicts,
+    status::{index_worktree, UntrackedFiles},
+    Repository,
+};
+
+/// The result of merging a tree into the index and the worktree.
+pub(crate) struct AppliedMerge {
+    /// The merged tree, which may contain conflict markers.
+    pub tree: ObjectId,
+    /// The unresolved conflicts.
+    pub conflicts: Vec<crate::merge::tree::Conflict>,
+    /// The paths with conflicting entries in the index, in order.
+    pub conflicted_paths: Vec<BString>,
+    /// The outcome of updating the worktree.
+    pub worktree: crate::repository::update_worktree::Outcome,
+}
+
+/// The reasons for not merging changes into the index and the worktree, to be turned into the error of the operation.
+pub(crate) enum Refusal {
+    /// The index has conflicts.
+    UnmergedIndex,
+    /// The index doesn't match the tree of `HEAD`.
+    DirtyIndex,
+    /// Local modifications or untracked files at these paths would be overwritten.
+    WouldOverwriteLocalChanges(Vec<BString>),
+}
+
+/// Utilities to merge changes into the index and the worktree.
+impl Repository {
+    /// Read the index from disk as the shared snapshot may be stale, or return an empty one if there is none.
+    pub(crate) fn fresh_index(&self) -> Result<gix_index::File, crate::worktree::open_index::Error> {
+        self.open_index().or_else(|err| match err {
+            crate::worktree::open_index::Error::IndexFile(gix_index::file::init::Error::Io(err))
+                if err.kind() == std::io::ErrorKind::NotFound =>
+            {
+                Ok(gix_index::File::from_state(
+                    gix_index::State::new(self.object_hash()),
+                    self.index_path(),
+                ))
+            }
+            err => Err(err),
+        })
+    }
+
+    /// Iterate the changes between `index` and the worktree, ignoring submodules, and with untracked files if
+    /// `include_untracked` is `true`.
+    pub(crate) fn index_worktree_changes<E>(
+        &self,
+        index: &gix_index::File,
+        include_untracked: bool,
+    ) -> Result<index_worktree::Iter, E>
+    where
+        E: From<crate::status::Error> + From<crate::status::into_iter::Error>,
+    {
+        Ok(self
+            .status(gix_features::progress::Discard)?
+            .index(index.clone().into())
+            .index_worktree_submodules(None)
+            .index_worktree_rewrites(None)
+            .untracked_files(if include_untracked {
+                UntrackedFiles::Files
+            } else {
+                UntrackedFiles::None
+            })
+            .into_index_worktree_iter(Vec::new())?)
+    }
+
+    /// Return the paths of the files that would lose local modifications if the worktree was changed from `current`
+    /// to `merged`, along with untracked files that `merged` would overwrite, sorted by path.
+    pub(crate) fn local_changes_overwritten_by<E>(
+        &self,
+        current: &gix_index::File,
+        merged: &gix_index::File,
+    ) -> Result<Vec<BString>, E>
+    where
+        E: From<crate::status::Error>
+            + From<crate::status::into_iter::Error>
+            + From<crate::status::index_worktree::Error>,
+    {
+        let workdir = self.workdir().expect("only called with a worktree");
+        let mut modified = BTreeSet::new();
+        for item in self.index_worktree_changes::<E>(current, false)? {
+            if let index_worktree::Item::Modification { rela_path, status, .. } = item? {
+                if !matches!(status, EntryStatus::NeedsUpdate(_)) {
+                    modified.insert(rela_path);
+                }
+            }
+        }
+
+        let mut overwritten = Vec::new();
+        for entry in merged.entries() {
+            let rela_path = entry.path(merged);
+            match current.entry_by_path(rela_path) {
+                Some(prev) if prev.id == entry.id && prev.mode == entry.mode => {}
+                Some(_) => {
+                    if modified.contains(rela_path) {
+                        overwritten.push(rela_path.to_owned());
+                    }
+                }
+                None => {
+                    if workdir.join(gix_path::from_bstr(rela_path)).symlink_metadata().is_ok() {
+                        overwritten.push(rela_path.to_owned());
+                    }
+                }
+            }
+        }
+        for entry in current.entries() {
+            let rela_path = entry.path(current);
+            if merged.entry_by_path(rela_path).is_none() && modified.contains(rela_path) {
+                overwritten.push(rela_path.to_owned());
+            }
+        }
+        overwritten.sort();
+        Ok(overwritten)
+    }
+
+    /// Create an index from `tree`, with entries outside of the sparse checkout with `patterns` marked as skipped.
+    pub(crate) fn index_for_tree<E>(
+        &self,
+        tree: &gix_hash::oid,
+        patterns: Option<&Patterns>,
+    ) -> Result<gix_index::File, E>
+    where
+        E: From<crate::repository::index_from_tree::Error>,
+    {
+        let mut index = gix_index::File::from_state(self.index_from_tree(tree)?.into_parts().0, self.index_path());
+        if let Some(patterns) = patterns {
+            gix_worktree::sparse::update_index(&mut index, patterns, self.sparse_checkout_case());
+        }
+        Ok(index)
+    }
+
+    /// Write `index` to disk, collapsing it into a sparse index first if there are sparse checkout `patterns`.
+    pub(crate) fn write_index_with_patterns<E>(
+        &self,
+        mut index: gix_index::File,
+        patterns: Option<&Patterns>,
+    ) -> Result<(), E>
+    where
+        E: From<crate::repository::collapse_sparse_index::Error>
+            + From<crate::repository::index_write_options::Error>
+            + From<gix_index::file::write::Error>,
+    {
+        if let Some(patterns) = patterns {
+            self.collapse_sparse_index(&mut index, patterns)?;
+        }
+        index.write(self.index_write_options(&index)?)?;
+        Ok(())
+    }
+
+    /// Read the index to merge changes into, and return it along with its tree, after assuring it has no conflicts
+    /// and that it matches `head_tree` unless `allow_dirty_index` is `true`.
+    pub(crate) fn index_to_merge_into<E>(
+        &self,
+        head_tree: &gix_hash::oid,
+        allow_dirty_index: bool,
+    ) -> Result<(gix_index::File, ObjectId), E>
+    where
+        E: From<Refusal>
+            + From<crate::worktree::open_index::Error>
+            + From<gix_index::sparse::expand::Error>
+            + From<gix_index::write_tree::Error>,
+    {
+        let mut current = self.fresh_index()?;
+        current.expand_all_sparse_directories(&self.objects)?;
+        if current
+            .entries()
+            .iter()
+            .any(|entry| entry.stage() != Stage::Unconflicted)
+        {
+            return Err(Refusal::UnmergedIndex.into());
+        }
+        let current_tree = self.write_tree_from_index(&mut current)?.detach();
+        if !allow_dirty_index && current_tree != head_tree {
+            return Err(Refusal::DirtyIndex.into());
+        }
+        Ok((current, current_tree))
+    }
+
+    /// Write the tree of the merge `outcome`, and change the index and the worktree from `current` to it, with unresolved
+    /// conflicts written to the index as stages, and check `should_interrupt` to stop the checkout.
+    ///
+    /// Nothing is changed if local modifications or untracked files would be overwritten.
+    pub(crate) fn apply_merge_outcome<E>(
+        &self,
+        current: &gix_index::File,
+        mut outcome: crate::merge::tree::Outcome<'_>,
+        should_interrupt: &AtomicBool,
+    ) -> Result<AppliedMerge, E>
+    where
+        E: From<Refusal>
+            + From<crate::object::tree::editor::write::Error>
+            + From<crate::repository::sparse_checkout_patterns::Error>
+            + From<crate::repository::index_from_tree::Error>
+            + From<crate::status::Error>
+            + From<crate::status::into_iter::Error>
+            + From<crate::status::index_worktree::Error>
+            + From<crate::repository::update_worktree::Error>
+            + From<gix_index::sparse::expand::Error>
+            + From<crate::repository::collapse_sparse_index::Error>
+            + From<crate::repository::index_write_options::Error>
+            + From<gix_index::file::write::Error>,
+    {
+        let how = TreatAsUnresolved::git();
+        let tree = outcome.tree.write()?.detach();
+        let patterns = self.sparse_checkout_patterns()?;
+        let mut merged_index = self.index_for_tree::<E>(&tree, patterns.as_ref())?;
+        let overwritten = self.local_changes_overwritten_by::<E>(current, &merged_index)?;
+        if !overwritten.is_empty() {
+            return Err(Refusal::WouldOverwriteLocalChanges(overwritten).into());
+        }
+        let worktree = self.update_worktree(current, &mut merged_index, should_interrupt)?;
+
+        let conflicts: Vec<_> = outcome
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.is_unresolved(how))
+            .cloned()
+            .collect();
+        let mut conflicted_paths = Vec::new();
+        if !conflicts.is_empty() {
+            outcome.index_changed_after_applying_conflicts(&mut merged_index, how, RemovalMode::Prune)?;
+            conflicted_paths = merged_index
+                .entries()
+                .iter()
+                .filter(|entry| entry.stage() != Stage::Unconflicted)
+                .map(|entry| entry.path(&merged_index).to_owned())
+                .collect();
+            conflicted_paths.dedup();
+        }
+        self.write_index_with_patterns::<E>(merged_index, patterns.as_ref())?;
+        Ok(AppliedMerge {
+            tree,
+            conflicts,
+            conflicted_paths,
+            worktree,
+        })
+    }
+
+    /// Change the index and the worktree to match `tree`, like `git reset --merge` does, and check `should_interrupt`
+    /// to stop updating the worktree.
+    pub(crate) fn reset_index_and_worktree<E>(
+        &self,
+        tree: &gix_hash::oid,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(), E>
+    where
+        E: From<crate::worktree::open_index::Error>
+            + From<crate::repository::sparse_checkout_patterns::Error>
+            + From<crate::repository::index_from_tree::Error>
+            + From<crate::repository::update_worktree::Error>
+            + From<crate::repository::collapse_sparse_index::Error>
+            + From<crate::repository::index_write_options::Error>
+            + From<gix_index::file::write::Error>,
+    {
+        let current = self.fresh_index()?;
+        let patterns = self.sparse_checkout_patterns()?;
+        let mut index = self.index_for_tree::<E>(tree, patterns.as_ref())?;
+        self.update_worktree(&current, &mut index, should_interrupt)?;
+        self.write_index_with_patterns::<E>(index, patterns.as_ref())
+    }
+}
+
+/// Merging into the worktree
+impl Repository {
+    /// Merge the commit `other` into `HEAD`, the index and the worktree with `options`, like `git merge <other>` does,
+    /// and check `should_interrupt` to stop the checkout.
+    ///
+    /// The index must match `HEAD`, and the operation fails without changes if local modifications or untracked files would
+    /// be overwritten. If `HEAD` is an ancestor of `other`, it's fast-forwarded to it unless [`Options::fast_forward`]
+    /// says otherwise, and otherwise `other` is merged into `HEAD` with a merge commit that has both as parents.
+    /// If there are conflicts, their stages are written to the index and [`MERGE_HEAD`] refers to `other`.
+    /// The `post-merge` hook runs once `HEAD` was changed if [hooks are enabled](crate::open::Options::run_hooks()), and its failure is ignored.
+    pub fn merge(
+        &self,
+        other: impl Into<ObjectId>,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        if self.workdir().is_none() {
+            return Err(Error::BareRepository {
+                git_dir: self.git_dir().to_owned(),
+            });
+        }
+        if let Some(state) = self.state() {
+            return Err(Error::InProgress { state });
+        }
+
+        let other = other.into();
+        let other_tree = self.find_commit(other)?.tree_id()?.detach();
+        let head = self.head_commit()?;
+        let head_tree = head.tree_id()?.detach();
+        let (current, _) = self.index_to_merge_into::<Error>(&head_tree, false)?;
+
+        let commit_graph = self.commit_graph_if_enabled()?;
+        let mut graph = self.revision_graph(commit_graph.as_ref());
+        let merge_bases = gix_revision::merge_base(head.id, &[other], &mut graph)?.unwrap_or_default();
+        if merge_bases.contains(&other) {
+            return Ok(Outcome::UpToDate);
+        }
+        let can_fast_forward = merge_bases.contains(&head.id);
+        if options.fast_forward == FastForward::Only && !can_fast_forward {
+            return Err(Error::NotPossibleToFastForward { id: other });
+        }
+
+        if can_fast_forward && options.fast_forward != FastForward::Never {
+            let patterns = self.sparse_checkout_patterns()?;
+            let mut index = self.index_for_tree::<Error>(&other_tree, patterns.as_ref())?;
+            let overwritten = self.local_changes_overwritten_by::<Error>(&current, &index)?;
+            if !overwritten.is_empty() {
+                return Err(Error::WouldOverwriteLocalChanges { paths: overwritten });
+            }
+            let worktree = self.update_worktree(&current, &mut index, should_interrupt)?;
+            self.write_index_with_patterns::<Error>(index, patterns.as_ref())?;
+            self.edit_reference(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: format!("merge {other}: Fast-forward").into(),
+                    },
+                    expected: PreviousValue::MustExistAndMatch(Target::Object(head.id)),
+                    new: Target::Object(other),
+                },
+                name: "HEAD".try_into().expect("valid"),
+                deref: true,
+            })?;
+            #[cfg(feature = "command")]
+            self.run_hook_after_merge()?;
+            return Ok(Outcome::FastForward {
+                commit: other,
+                worktree,
+            });
+        }
+
+        let other_label = other.to_string();
+        let labels = gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: None,
+            current: Some("HEAD".into()),
+            other: Some(other_label.as_str().into()),
+        };
+        let outcome = self.merge_commits(head.id, other, labels, self.tree_merge_options()?.into())?;
+        let applied = self.apply_merge_outcome::<Error>(&current, outcome.tree_merge, should_interrupt)?;
+        let message = match options.message {
+            Some(message) => message,
+            None => self.default_merge_message(other)?,
+        };
+        if !applied.conflicts.is_empty() || options.no_commit {
+            let mut merge_message = message;
+            append_conflicts(&mut merge_message, &applied.conflicted_paths);
+            let mode: &[u8] = if options.fast_forward == FastForward::Never {
+                b"no-ff"
+            } else {
+                b""
+            };
+            for (name, content) in [
+                (MERGE_HEAD, format!("{other}\n").as_bytes()),
+                (MERGE_MSG, merge_message.as_slice()),
+                (MERGE_MODE, mode),
+            ] {
+                let path = self.git_dir().join(name);
+                std::fs::write(&path, content).map_err(|source| Error::Io { path, source })?;
+            }
+            return Ok(Outcome::Merge {
+                commit: None,
+                conflicts: applied.conflicts,
+                worktree: applied.worktree,
+            });
+        }
+
+        let committer = self.committer().ok_or(Error::CommitterMissing)??;
+        let author = self.author().ok_or(Error::AuthorMissing)??;
+        let merge_commit = gix_object::Commit {
+            message,
+            tree: applied.tree,
+            author: author.into(),
+            committer: committer.into(),
+            encoding: None,
+            parents: smallvec::smallvec![head.id, other],
+            extra_headers: Default::default(),
+        };
+        let commit = self
+            .write_commit_and_update_reference_as(
+                merge_commit,
+                committer,
+                "HEAD".try_into().expect("valid"),
+                PreviousValue::MustExistAndMatch(Target::Object(head.id)),
+                format!("merge {other}: Merge made by the 'ort' strategy.").into(),
+            )?
+            .detach();
+        #[cfg(feature = "command")]
+        self.run_hook_after_merge()?;
+        Ok(Outcome::Merge {
+            commit: Some(commit),
+            conflicts: applied.conflicts,
+            worktree: applied.worktree,
+        })
+    }
+
+    /// Return the message `git merge <other>` uses for the merge commit, which mentions the current branch, or `HEAD` if
+    /// it's detached, unless it's `main` or `master`.
+    fn default_merge_message(&self, other: ObjectId) -> Result<BString, Error> {
+        let head_name = self.head_name()?;
+        let branch = head_name.as_ref().map_or("HEAD".into(), gix_ref::FullName::shorten);
+        Ok(if branch == "main" || branch == "master" {
+            format!("Merge commit '{other}'\n")
+        } else {
+            format!("Merge commit '{other}' into {branch}\n")
+        }
+        .into())
+    }
+
+    /// Run the `post-merge` hook, ignoring its outcome like `git merge` does.
+    #[cfg(feature = "command")]
+    fn run_hook_after_merge(&self) -> Result<(), Error> {
+        if let Some(hook) = self.hook_to_run("post-merge")? {
+            if let Err(_err) = hook.arg("0").run() {
+                gix_trace::warn!("Ignoring failure of the post-merge hook: {_err}");
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<Refusal> for Error {
+    fn from(value: Refusal) -> Self {
+        match value {
+            Refusal::UnmergedIndex => Error::UnmergedIndex,
+            Refusal::DirtyIndex => Error::DirtyIndex,
+            Refusal::WouldOverwriteLocalChanges(paths) => Error::WouldOverwriteLocalChanges { paths },
+        }
+    }
+}
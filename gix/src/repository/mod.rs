@@ -22,6 +22,8 @@ mod blame;
 mod cache;
 #[cfg(feature = "worktree-mutation")]
 mod checkout;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod cherry_pick;
 mod config;
 
 ///
@@ -35,7 +37,11 @@ mod dirwalk;
 pub mod filter;
 ///
 pub mod freelist;
+#[cfg(feature = "command")]
+mod gpg;
 mod graph;
+#[cfg(feature = "command")]
+mod hook;
 pub(crate) mod identity;
 mod impls;
 #[cfg(feature = "index")]
@@ -47,13 +53,34 @@ mod mailmap;
 ///
 #[cfg(feature = "merge")]
 mod merge;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod merge_worktree;
 mod object;
+#[cfg(all(
+    feature = "attributes",
+    any(
+        feature = "async-network-client",
+        feature = "blocking-network-client",
+        feature = "blocking-network-server"
+    )
+))]
+mod pack;
 #[cfg(feature = "attributes")]
 mod pathspec;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod rebase;
 mod reference;
 mod remote;
 mod revision;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod sequencer;
 mod shallow;
+#[cfg(feature = "excludes")]
+mod sparse;
+#[cfg(all(feature = "dirwalk", feature = "worktree-mutation"))]
+mod staging;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod stash;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
@@ -61,7 +88,7 @@ mod thread_safe;
 mod worktree;
 
 ///
-mod new_commit {
+pub mod new_commit {
     /// The error returned by [`new_commit(…)`](crate::Repository::new_commit()).
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
@@ -78,7 +105,7 @@ mod new_commit {
 }
 
 ///
-mod new_commit_as {
+pub mod new_commit_as {
     /// The error returned by [`new_commit_as(…)`](crate::Repository::new_commit_as()).
     #[derive(Debug, thiserror::Error)]
     #[allow(missing_docs)]
@@ -388,6 +415,64 @@ pub mod commit_graph_if_enabled {
     }
 }
 
+///
+pub mod write_commit_graph {
+    /// The error returned by [Repository::write_commit_graph()](crate::Repository::write_commit_graph()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Commit-graphs can't be written in shallow repositories")]
+        Shallow,
+        #[error("The value of commitGraph.generationVersion should be 1 or 2")]
+        GenerationVersion(#[from] crate::config::key::GenericError),
+        #[error(transparent)]
+        InitReferences(#[from] crate::reference::iter::Error),
+        #[error(transparent)]
+        InitReferencesIter(#[from] crate::reference::iter::init::Error),
+        #[error(transparent)]
+        IterReferences(Box<dyn std::error::Error + Send + Sync + 'static>),
+        #[error(transparent)]
+        PeelReference(#[from] crate::reference::peel::Error),
+        #[error(transparent)]
+        FindHeader(#[from] crate::object::find::existing::Error),
+        #[error(transparent)]
+        FindCommit(#[from] gix_object::find::existing_object::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        Write(#[from] crate::Error),
+    }
+}
+
+///
+#[cfg(all(
+    feature = "attributes",
+    any(
+        feature = "async-network-client",
+        feature = "blocking-network-client",
+        feature = "blocking-network-server"
+    )
+))]
+pub mod mark_no_delta {
+    /// The error returned by [Repository::mark_no_delta()](crate::Repository::mark_no_delta()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        IndexOrLoadFromHead(#[from] crate::repository::index_or_load_from_head_or_empty::Error),
+        #[error(transparent)]
+        AttributeStack(#[from] crate::config::attribute_stack::Error),
+        #[error(transparent)]
+        FindObject(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        FindCommitTree(#[from] crate::object::commit::Error),
+        #[error(transparent)]
+        DecodeTree(#[from] gix_object::decode::Error),
+        #[error("Could not match attributes of a path")]
+        Attributes(#[from] std::io::Error),
+    }
+}
+
 ///
 #[cfg(feature = "index")]
 pub mod index_from_tree {
@@ -402,6 +487,139 @@ pub mod index_from_tree {
         },
         #[error("Couldn't obtain configuration for core.protect*")]
         BooleanConfig(#[from] crate::config::boolean::Error),
+        #[error(transparent)]
+        IndexVersion(#[from] crate::config::key::GenericError),
+    }
+}
+
+///
+#[cfg(feature = "excludes")]
+pub mod sparse_checkout_patterns {
+    /// The error returned by [Repository::sparse_checkout_patterns()](crate::Repository::sparse_checkout_patterns()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        BooleanConfig(#[from] crate::config::boolean::Error),
+        #[error("Could not read the sparse checkout patterns from '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+#[cfg(feature = "excludes")]
+pub mod collapse_sparse_index {
+    /// The error returned by [Repository::collapse_sparse_index()](crate::Repository::collapse_sparse_index()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        BooleanConfig(#[from] crate::config::boolean::Error),
+        #[error(transparent)]
+        Collapse(#[from] gix_index::sparse::collapse::Error),
+    }
+}
+
+///
+#[cfg(feature = "worktree-mutation")]
+pub mod sparse_checkout_set {
+    use crate::bstr::BString;
+
+    /// The error returned by [Repository::sparse_checkout_set()](crate::Repository::sparse_checkout_set()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and cannot have a sparse checkout", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("There is no sparse checkout to change")]
+        NotEnabled,
+        #[error("Directories can only be added to or removed from sparse checkouts in cone mode")]
+        NotConeMode,
+        #[error(transparent)]
+        Patterns(#[from] super::sparse_checkout_patterns::Error),
+        #[error(transparent)]
+        OpenIndex(#[from] crate::worktree::open_index::Error),
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error("Could not collapse the index into a sparse index")]
+        CollapseSparseIndex(#[from] super::collapse_sparse_index::Error),
+        #[error(transparent)]
+        StatOptions(#[from] crate::config::stat_options::Error),
+        #[error(transparent)]
+        CheckoutOptions(#[from] crate::config::checkout_options::Error),
+        #[error(transparent)]
+        Checkout(#[from] gix_worktree_state::checkout::Error),
+        #[error("Could not fetch the missing objects to check out from the promisor remote")]
+        PromisorFetch(#[source] gix_odb::promisor::Error),
+        #[error("Failed to reopen object database as Arc (only if thread-safety wasn't compiled in)")]
+        OpenArcOdb(#[from] std::io::Error),
+        #[error(transparent)]
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+        #[error(transparent)]
+        WriteIndex(#[from] gix_index::file::write::Error),
+        #[error("Could not read the repository configuration file")]
+        LoadConfig(#[from] gix_config::file::init::from_paths::Error),
+        #[error("Could not enable the sparse checkout in the repository configuration")]
+        SetConfig(#[from] gix_config::file::set_raw_value::Error),
+        #[error("Could not enable the sparse checkout in memory")]
+        SetConfigInMemory(#[from] crate::config::set_value::Error),
+        #[error("Could not lock a file for writing")]
+        AcquireLock(#[from] gix_lock::acquire::Error),
+        #[error("Could not commit a changed file")]
+        CommitLock(#[from] gix_lock::commit::Error<gix_lock::File>),
+        #[error("Could not update '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+
+    /// The outcome of [Repository::sparse_checkout_set()](crate::Repository::sparse_checkout_set()).
+    #[derive(Debug, Default)]
+    pub struct Outcome {
+        /// The outcome of checking out the files that were newly included.
+        pub checkout: gix_worktree_state::checkout::Outcome,
+        /// The amount of files that were removed from the worktree as they were newly excluded.
+        pub files_removed: usize,
+        /// The paths of the files that were newly excluded but kept in the worktree as they were modified.
+        pub files_kept: Vec<BString>,
+    }
+}
+
+///
+#[cfg(feature = "worktree-mutation")]
+pub mod update_worktree {
+    /// The error returned by [Repository::update_worktree()](crate::Repository::update_worktree()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no worktree to update", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error(transparent)]
+        CheckoutOptions(#[from] crate::config::checkout_options::Error),
+        #[error(transparent)]
+        Checkout(#[from] gix_worktree_state::checkout::Error),
+        #[error("Could not fetch the missing objects to check out from the promisor remote")]
+        PromisorFetch(#[source] gix_odb::promisor::Error),
+        #[error("Failed to reopen object database as Arc (only if thread-safety wasn't compiled in)")]
+        OpenArcOdb(#[from] std::io::Error),
+        #[error("Could not remove '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+
+    /// The outcome of [Repository::update_worktree()](crate::Repository::update_worktree()).
+    #[derive(Debug, Default)]
+    pub struct Outcome {
+        /// The outcome of checking out the files that were added or changed.
+        pub checkout: gix_worktree_state::checkout::Outcome,
+        /// The amount of files that were removed from the worktree.
+        pub files_removed: usize,
     }
 }
 
@@ -468,6 +686,22 @@ pub mod pathspec_defaults_ignore_case {
     }
 }
 
+///
+#[cfg(feature = "index")]
+pub mod index_write_options {
+    /// The error returned by [`Repository::index_write_options()`](crate::Repository::index_write_options()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Boolean(#[from] crate::config::boolean::Error),
+        #[error(transparent)]
+        UnsignedInteger(#[from] crate::config::unsigned_integer::Error),
+        #[error(transparent)]
+        Time(#[from] crate::config::time::Error),
+    }
+}
+
 ///
 #[cfg(feature = "index")]
 pub mod index_or_load_from_head {
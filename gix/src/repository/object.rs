@@ -6,7 +6,7 @@ use gix_object::{Exists, Find, FindExt, Write};
 use gix_odb::{Header, HeaderExt};
 use gix_ref::{
     transaction::{LogChange, PreviousValue, RefLog},
-    FullName,
+    FullName, Target,
 };
 use smallvec::SmallVec;
 
@@ -239,6 +239,8 @@ impl crate::Repository {
     ///
     /// It will be created with `constraint` which is most commonly to [only create it](PreviousValue::MustNotExist)
     /// or to [force overwriting a possibly existing tag](PreviousValue::Any).
+    ///
+    /// With the `command` feature, the tag object is [signed](Self::signer()) if `tag.gpgSign` is enabled.
     pub fn tag(
         &self,
         name: impl AsRef<str>,
@@ -248,7 +250,8 @@ impl crate::Repository {
         message: impl AsRef<str>,
         constraint: PreviousValue,
     ) -> Result<Reference<'_>, tag::Error> {
-        let tag = gix_object::Tag {
+        #[cfg_attr(not(feature = "command"), allow(unused_mut))]
+        let mut tag = gix_object::Tag {
             target: target.as_ref().into(),
             target_kind,
             name: name.as_ref().into(),
@@ -256,6 +259,10 @@ impl crate::Repository {
             message: message.as_ref().into(),
             pgp_signature: None,
         };
+        #[cfg(feature = "command")]
+        if self.config.gpg_sign(&crate::config::tree::Tag::GPG_SIGN)? {
+            self.signer()?.sign_tag(&mut tag)?;
+        }
         let tag_id = self.write_object(&tag)?;
         self.tag_reference(name, tag_id, constraint).map_err(Into::into)
     }
@@ -295,15 +302,15 @@ impl crate::Repository {
         tree: ObjectId,
         parents: SmallVec<[ObjectId; 1]>,
     ) -> Result<Id<'_>, commit::Error> {
-        use gix_ref::{
-            transaction::{Change, RefEdit},
-            Target,
-        };
+        #[cfg(feature = "command")]
+        let message = self.run_hooks_before_commit(message)?;
+        #[cfg(not(feature = "command"))]
+        let message = crate::bstr::BString::from(message);
 
         // TODO: possibly use CommitRef to save a few allocations (but will have to allocate for object ids anyway.
         //       This can be made vastly more efficient though if we wanted to, so we lie in the API
         let commit = gix_object::Commit {
-            message: message.into(),
+            message,
             tree,
             author: author.into(),
             committer: committer.into(),
@@ -311,6 +318,54 @@ impl crate::Repository {
             parents,
             extra_headers: Default::default(),
         };
+        let commit_id = self.write_commit_and_update_reference(commit, committer, reference, "commit")?;
+
+        #[cfg(feature = "command")]
+        self.run_hook_after_commit()?;
+        Ok(commit_id)
+    }
+
+    /// Sign `commit` if configured, write it and point `reference` to it, with a reflog message for `operation`
+    /// made by `committer`. The first parent of `commit` is expected to be the current value of `reference`.
+    pub(crate) fn write_commit_and_update_reference(
+        &self,
+        commit: gix_object::Commit,
+        committer: gix_actor::SignatureRef<'_>,
+        reference: FullName,
+        operation: &str,
+    ) -> Result<Id<'_>, commit::Error> {
+        let reflog_message = crate::reference::log::message(operation, commit.message.as_ref(), commit.parents.len());
+        let expected = match commit.parents.first().map(|p| Target::Object(*p)) {
+            Some(previous) => {
+                if reference.as_bstr() == "HEAD" {
+                    PreviousValue::MustExistAndMatch(previous)
+                } else {
+                    PreviousValue::ExistingMustMatch(previous)
+                }
+            }
+            None => PreviousValue::MustNotExist,
+        };
+        self.write_commit_and_update_reference_as(commit, committer, reference, expected, reflog_message)
+    }
+
+    /// Sign `commit` if configured, write it and point `reference` to it if its value is `expected`, with `reflog_message`
+    /// made by `committer`.
+    pub(crate) fn write_commit_and_update_reference_as(
+        &self,
+        commit: gix_object::Commit,
+        committer: gix_actor::SignatureRef<'_>,
+        reference: FullName,
+        expected: PreviousValue,
+        reflog_message: crate::bstr::BString,
+    ) -> Result<Id<'_>, commit::Error> {
+        use gix_ref::transaction::{Change, RefEdit};
+
+        #[cfg_attr(not(feature = "command"), allow(unused_mut))]
+        let mut commit = commit;
+        #[cfg(feature = "command")]
+        if self.config.gpg_sign(&crate::config::tree::Commit::GPG_SIGN)? {
+            self.signer()?.sign_commit(&mut commit)?;
+        }
 
         let commit_id = self.write_object(&commit)?;
         self.edit_references_as(
@@ -319,22 +374,9 @@ impl crate::Repository {
                     log: LogChange {
                         mode: RefLog::AndReference,
                         force_create_reflog: false,
-                        message: crate::reference::log::message(
-                            "commit",
-                            commit.message.as_ref(),
-                            commit.parents.len(),
-                        ),
-                    },
-                    expected: match commit.parents.first().map(|p| Target::Object(*p)) {
-                        Some(previous) => {
-                            if reference.as_bstr() == "HEAD" {
-                                PreviousValue::MustExistAndMatch(previous)
-                            } else {
-                                PreviousValue::ExistingMustMatch(previous)
-                            }
-                        }
-                        None => PreviousValue::MustNotExist,
+                        message: reflog_message,
                     },
+                    expected,
                     new: Target::Object(commit_id.inner),
                 },
                 name: reference,
@@ -345,6 +387,48 @@ impl crate::Repository {
         Ok(commit_id)
     }
 
+    /// Run the `pre-commit` and `commit-msg` hooks like `git commit` does if there is a worktree, and return the `message`
+    /// as possibly edited by the latter.
+    #[cfg(feature = "command")]
+    fn run_hooks_before_commit(&self, message: &str) -> Result<crate::bstr::BString, commit::Error> {
+        if self.workdir().is_none() {
+            return Ok(message.into());
+        }
+        if let Some(hook) = self.hook_to_run("pre-commit")? {
+            hook.env("GIT_INDEX_FILE", self.index_path())
+                .env("GIT_EDITOR", ":")
+                .run()?;
+        }
+        let Some(hook) = self.hook_to_run("commit-msg")? else {
+            return Ok(message.into());
+        };
+        let message_path = gix_path::realpath(self.git_dir())
+            .map_err(crate::hook::find::Error::from)?
+            .join("COMMIT_EDITMSG");
+        std::fs::write(&message_path, message).map_err(commit::Error::CommitMessageFile)?;
+        hook.env("GIT_INDEX_FILE", self.index_path())
+            .env("GIT_EDITOR", ":")
+            .arg(&message_path)
+            .run()?;
+        Ok(std::fs::read(&message_path)
+            .map_err(commit::Error::CommitMessageFile)?
+            .into())
+    }
+
+    /// Run the `post-commit` hook if there is a worktree, ignoring its outcome like `git commit` does.
+    #[cfg(feature = "command")]
+    pub(crate) fn run_hook_after_commit(&self) -> Result<(), commit::Error> {
+        if self.workdir().is_none() {
+            return Ok(());
+        }
+        if let Some(hook) = self.hook_to_run("post-commit")? {
+            if let Err(_err) = hook.env("GIT_INDEX_FILE", self.index_path()).run() {
+                gix_trace::warn!("Ignoring failure of the post-commit hook: {_err}");
+            }
+        }
+        Ok(())
+    }
+
     /// Create a new commit object with `message` referring to `tree` with `parents`, and point `reference`
     /// to it. The commit is written without message encoding field, which can be assumed to be UTF-8.
     /// `author` and `committer` fields are pre-set from the configuration, which can be altered
@@ -363,6 +447,17 @@ impl crate::Repository {
     ///
     /// If the reference shouldn't be updated, use [`Self::write_object()`] along with a newly created [`crate::objs::Object`] whose fields
     /// can be fully defined.
+    ///
+    /// ### Signing
+    ///
+    /// With the `command` feature, the commit is [signed](Self::signer()) if `commit.gpgSign` is enabled.
+    ///
+    /// ### Hooks
+    ///
+    /// With the `command` feature, if there is a worktree and if [enabled](crate::open::Options::run_hooks()),
+    /// the [`pre-commit`, `commit-msg` and `post-commit` hooks](Self::hook())
+    /// are run like `git commit` does, and the commit is aborted if one of the former two fails. The `commit-msg` hook may change
+    /// the message. Like all reference edits, the `reference` update runs the `reference-transaction` hook.
     pub fn commit<Name, E>(
         &self,
         reference: Name,
@@ -0,0 +1,81 @@
+use gix_hash::ObjectId;
+use gix_object::bstr::{BString, ByteSlice, ByteVec};
+use gix_pack::data::output;
+
+use crate::{repository::mark_no_delta, Repository};
+
+/// Pack generation
+impl Repository {
+    /// Set [`no_delta`](output::Count::no_delta) on all non-packed blobs in `counts` which are stored in the trees of `commits`
+    /// at a path that has the `delta` attribute unset, so they are never delta-compressed when writing a pack.
+    ///
+    /// Attributes are read from the worktree and the index, or from the tree at `HEAD` in bare repositories.
+    /// Like git, only the first path of a blob that is encountered is used to match attributes.
+    pub fn mark_no_delta(
+        &self,
+        commits: &[ObjectId],
+        counts: &mut [output::Count],
+    ) -> Result<(), mark_no_delta::Error> {
+        let mut unmarked: gix_hashtable::HashMap<_, _> = counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| {
+                !matches!(
+                    count.entry_pack_location,
+                    output::count::PackLocation::LookedUp(Some(_))
+                )
+            })
+            .map(|(idx, count)| (count.id, idx))
+            .collect();
+        if unmarked.is_empty() {
+            return Ok(());
+        }
+
+        let index = self.index_or_load_from_head_or_empty()?;
+        let mut attributes = self.attributes_only(
+            &index,
+            if self.is_bare() {
+                gix_worktree::stack::state::attributes::Source::IdMapping
+            } else {
+                gix_worktree::stack::state::attributes::Source::WorktreeThenIdMapping
+            },
+        )?;
+        let mut outcome = attributes.selected_attribute_matches(Some("delta"));
+
+        let mut seen_trees = gix_hashtable::HashSet::default();
+        let mut trees = Vec::new();
+        for commit in commits {
+            let tree_id = self.find_commit(*commit)?.tree_id()?.detach();
+            if seen_trees.insert(tree_id) {
+                trees.push((tree_id, BString::default()));
+            }
+            while let Some((tree_id, prefix)) = trees.pop() {
+                let tree = self.find_tree(tree_id)?;
+                for entry in tree.decode()?.entries {
+                    let mut path = prefix.clone();
+                    if !path.is_empty() {
+                        path.push(b'/');
+                    }
+                    path.push_str(entry.filename);
+                    if entry.mode.is_tree() {
+                        if seen_trees.insert(entry.oid.to_owned()) {
+                            trees.push((entry.oid.to_owned(), path));
+                        }
+                    } else if let Some(idx) = unmarked.remove(entry.oid) {
+                        attributes
+                            .at_entry(path.as_bstr(), Some(entry.mode.into()))?
+                            .matching_attributes(&mut outcome);
+                        counts[idx].no_delta = outcome
+                            .iter_selected()
+                            .next()
+                            .is_some_and(|m| m.assignment.state.is_unset());
+                        if unmarked.is_empty() {
+                            return Ok(());
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
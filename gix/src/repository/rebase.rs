@@ -0,0 +1,896 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_hash::ObjectId;
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    merge::worktree::{MERGE_HEAD, MERGE_MSG},
+    rebase::{todo, Error, Options, Outcome, State, Status, Stop, REBASE_HEAD},
+    repository::{
+        cherry_pick::{append_conflicts, Operation},
+        sequencer::strip_comments_and_whitespace,
+    },
+    Id, Repository,
+};
+
+/// The prefix of the references created by `label` instructions.
+const REWRITTEN_PREFIX: &str = "refs/rewritten/";
+
+/// Rebasing
+impl Repository {
+    /// Return a summary of the rebase or `git am` in progress, or `None` if there is none.
+    pub fn rebase_status(&self) -> Result<Option<Status>, gix_rebase::state::read::Error> {
+        gix_rebase::status::status(self.git_dir())
+    }
+
+    /// Return the todo list to rebase the commits reachable from `head` but not from `upstream` with `options`, like
+    /// `git rebase <upstream>` would perform them.
+    ///
+    /// Each commit is picked, parents first, and merge commits are left out. With [`Options::autosquash`] the list is
+    /// [rearranged](crate::rebase::autosquash::rearrange()), and with [`Options::update_refs`] there is an `update-ref`
+    /// instruction for each local branch that points to a picked commit, except for the one `HEAD` refers to.
+    /// Commits whose changes are present in `upstream` already are dropped while the rebase is performed.
+    pub fn rebase_todo(
+        &self,
+        upstream: impl Into<ObjectId>,
+        head: impl Into<ObjectId>,
+        options: &Options,
+    ) -> Result<todo::List, Error> {
+        let head = head.into();
+        let mut parents_by_id = HashMap::new();
+        for info in self.rev_walk([head]).with_hidden([upstream.into()]).all()? {
+            let info = info?;
+            parents_by_id.insert(info.id, info.parent_ids().map(Id::detach).collect::<Vec<_>>());
+        }
+
+        let mut list = todo::List::default();
+        let mut full_ids = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut stack = vec![(head, false)];
+        while let Some((id, parents_are_done)) = stack.pop() {
+            let Some(parents) = parents_by_id.get(&id) else {
+                continue;
+            };
+            if parents_are_done {
+                if parents.len() > 1 {
+                    continue;
+                }
+                let commit = self.find_commit(id)?;
+                let name: BString = commit.short_id()?.to_string().into();
+                full_ids.insert(name.clone(), id);
+                list.instructions.push(todo::Instruction::Pick(todo::Commit {
+                    name,
+                    summary: commit.message()?.summary().into_owned(),
+                }));
+                continue;
+            }
+            if !seen.insert(id) {
+                continue;
+            }
+            stack.push((id, true));
+            stack.extend(
+                parents
+                    .iter()
+                    .rev()
+                    .filter(|parent| parents_by_id.contains_key(*parent) && !seen.contains(*parent))
+                    .map(|parent| (*parent, false)),
+            );
+        }
+
+        if options.autosquash {
+            gix_rebase::autosquash::rearrange(&mut list);
+        }
+        if options.update_refs {
+            let head_name = self.head_name()?;
+            let mut branches_by_id = HashMap::<_, Vec<FullName>>::new();
+            for branch in self.references()?.local_branches()? {
+                let branch = branch.map_err(Error::IterReferences)?;
+                if Some(branch.name()) == head_name.as_ref().map(AsRef::as_ref) {
+                    continue;
+                }
+                if let Some(id) = branch.target().try_id() {
+                    branches_by_id
+                        .entry(id.to_owned())
+                        .or_default()
+                        .push(branch.name().to_owned());
+                }
+            }
+
+            let mut idx = 0;
+            while idx < list.instructions.len() {
+                let branches = list.instructions[idx]
+                    .commit()
+                    .and_then(|commit| full_ids.get(&commit.name))
+                    .and_then(|id| branches_by_id.get(id));
+                idx += 1;
+                let Some(branches) = branches else { continue };
+                while matches!(
+                    list.instructions.get(idx),
+                    Some(todo::Instruction::Squash(_) | todo::Instruction::Fixup { .. })
+                ) {
+                    idx += 1;
+                }
+                for name in branches {
+                    list.instructions.insert(
+                        idx,
+                        todo::Instruction::UpdateRef {
+                            name: name.as_bstr().to_owned(),
+                        },
+                    );
+                    idx += 1;
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// Rebase the branch `HEAD` refers to, or a detached `HEAD`, by performing the instructions of `todo` on top of `onto`
+    /// with `options`, like `git rebase --onto <onto>` does, and check `should_interrupt` to stop between instructions and
+    /// while updating the worktree.
+    ///
+    /// The [state](State) of the rebase is written to the `git` directory, and `HEAD` is detached at `onto` until
+    /// the rebase is finished, when the branch is updated to the last rewritten commit and `HEAD` refers to it again.
+    /// If the rebase [stops](Stop), it can be resumed with [`rebase_continue()`](Self::rebase_continue()) or
+    /// [`rebase_skip()`](Self::rebase_skip()), or undone with [`rebase_abort()`](Self::rebase_abort()).
+    ///
+    /// Commit messages are never edited, so a `reword` instruction and the `-c` flags of `fixup` and `merge` stop the
+    /// rebase to let the message be passed to [`rebase_continue()`](Self::rebase_continue()), while `squash` instructions
+    /// join the messages of the commits that are melded together.
+    pub fn rebase_start(
+        &self,
+        onto: impl Into<ObjectId>,
+        todo: todo::List,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        if self.workdir().is_none() {
+            return Err(Error::BareRepository {
+                git_dir: self.git_dir().to_owned(),
+            });
+        }
+        if let Some(state) = self.state() {
+            return Err(Error::InProgress { state });
+        }
+        if self.is_dirty()? {
+            return Err(Error::DirtyWorktree);
+        }
+
+        let onto = onto.into();
+        let orig_head = self.head_id()?.detach();
+        let head_name = self.head_name()?.map(|name| name.as_bstr().to_owned());
+        let mut state = State::new(head_name, onto, orig_head, todo);
+        state.interactive = options.interactive;
+        state.options = gix_rebase::Options {
+            keep_redundant_commits: options.keep_redundant_commits,
+            drop_redundant_commits: !options.keep_redundant_commits,
+            reschedule_failed_exec: options.reschedule_failed_exec,
+        };
+        for instruction in &state.todo.instructions {
+            if let todo::Instruction::UpdateRef { name } = instruction {
+                state.update_refs.push(gix_rebase::UpdateRef {
+                    name: name.clone(),
+                    before: self.reference_id(name.as_ref())?,
+                    after: None,
+                });
+            }
+        }
+        state.write(self.git_dir())?;
+
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: "rebase: updating ORIG_HEAD".into(),
+                },
+                expected: PreviousValue::Any,
+                new: Target::Object(orig_head),
+            },
+            name: "ORIG_HEAD".try_into().expect("valid"),
+            deref: false,
+        })?;
+        let tree = self.find_commit(onto)?.tree_id()?.detach();
+        self.checkout_tree_for_rebase(&tree, should_interrupt)?;
+        self.detach_head_for_rebase(onto, format!("rebase (start): checkout {onto}").into())?;
+        self.rebase_run(state, should_interrupt)
+    }
+
+    /// Commit the changes in the index to conclude the instruction the rebase stopped at, and perform the remaining
+    /// instructions, like `git rebase --continue`, and check `should_interrupt` to stop between instructions and while
+    /// updating the worktree.
+    ///
+    /// If the rebase stopped due to conflicts, they must be resolved in the index, and the commit is created with the
+    /// `message` if given, or with the message of the commit that was picked or merged.
+    /// If it stopped to edit or reword a commit, that commit is amended with the changes in the index and the `message`,
+    /// if there are any.
+    /// Otherwise, the index must not have changes compared to `HEAD`.
+    pub fn rebase_continue(&self, message: Option<BString>, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        let mut state = State::read(self.git_dir())?.ok_or(Error::NothingInProgress)?;
+        let mut index = self.fresh_index()?;
+        index.expand_all_sparse_directories(&self.objects)?;
+        if index
+            .entries()
+            .iter()
+            .any(|entry| entry.stage() != gix_index::entry::Stage::Unconflicted)
+        {
+            return Err(Error::UnmergedIndex);
+        }
+        let tree = self.write_tree_from_index(&mut index)?.detach();
+        let head = self.head_commit()?;
+        let head_tree = head.tree_id()?.detach();
+
+        match (state.stopped_at, state.amend) {
+            (Some(_), None) => {
+                let message = match message.or_else(|| state.message.clone()) {
+                    Some(message) => message,
+                    None => strip_comments_and_whitespace(&self.read_git_dir_file(MERGE_MSG)?.unwrap_or_default()),
+                };
+                let author = state.author.clone();
+                match state.done.instructions.last() {
+                    Some(todo::Instruction::Squash(_) | todo::Instruction::Fixup { .. }) => {
+                        let parents = head.parent_ids().map(Id::detach).collect();
+                        let author = head.author()?.to_owned()?;
+                        self.commit_for_rebase(tree, parents, author, message, head.id, "continue")?;
+                    }
+                    Some(todo::Instruction::Merge { .. }) => {
+                        let path = self.git_dir().join(MERGE_HEAD);
+                        let other = self.read_git_dir_file(MERGE_HEAD)?.unwrap_or_default();
+                        let other =
+                            ObjectId::from_hex(other.trim()).map_err(|source| Error::DecodeId { path, source })?;
+                        let author = self.author_or_default(author)?;
+                        self.commit_for_rebase(
+                            tree,
+                            smallvec::smallvec![head.id, other],
+                            author,
+                            message,
+                            head.id,
+                            "continue",
+                        )?;
+                    }
+                    _ if tree == head_tree => {}
+                    _ => {
+                        let author = self.author_or_default(author)?;
+                        self.commit_for_rebase(tree, [head.id].into(), author, message, head.id, "continue")?;
+                    }
+                }
+            }
+            (_, Some(amend)) if amend == head.id && (message.is_some() || tree != head_tree) => {
+                let message = match message {
+                    Some(message) => message,
+                    None => head.message_raw()?.to_owned(),
+                };
+                let parents = head.parent_ids().map(Id::detach).collect();
+                let author = head.author()?.to_owned()?;
+                self.commit_for_rebase(tree, parents, author, message, head.id, "continue")?;
+            }
+            _ if tree != head_tree => return Err(Error::DirtyIndex),
+            _ => {}
+        }
+        self.remove_stopped_instruction(&mut state)?;
+        self.rebase_run(state, should_interrupt)
+    }
+
+    /// Reset the index and the worktree to `HEAD` to leave out the changes of the instruction the rebase stopped at,
+    /// and perform the remaining instructions, like `git rebase --skip`, and check `should_interrupt` to stop between
+    /// instructions and while updating the worktree.
+    pub fn rebase_skip(&self, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        let mut state = State::read(self.git_dir())?.ok_or(Error::NothingInProgress)?;
+        let head_tree = self.head_commit()?.tree_id()?.detach();
+        self.reset_index_and_worktree::<Error>(&head_tree, should_interrupt)?;
+        self.remove_stopped_instruction(&mut state)?;
+        self.rebase_run(state, should_interrupt)
+    }
+
+    /// Stop the rebase and reset `HEAD`, the index and the worktree to where they were when it started, like
+    /// `git rebase --abort`, and check `should_interrupt` to stop updating the worktree.
+    pub fn rebase_abort(&self, should_interrupt: &AtomicBool) -> Result<(), Error> {
+        let state = State::read(self.git_dir())?.ok_or(Error::NothingInProgress)?;
+        let tree = self.find_commit(state.orig_head)?.tree_id()?.detach();
+        self.reset_index_and_worktree::<Error>(&tree, should_interrupt)?;
+        match &state.head_name {
+            Some(name) => self.attach_head_after_rebase(name.as_ref(), "abort")?,
+            None => {
+                self.detach_head_for_rebase(
+                    state.orig_head,
+                    format!("rebase (abort): returning to {}", state.orig_head).into(),
+                )?;
+            }
+        }
+        self.remove_rebase_state()
+    }
+
+    /// Forget about the rebase in progress without changing `HEAD`, the index or the worktree, like `git rebase --quit`.
+    pub fn rebase_quit(&self) -> Result<(), Error> {
+        if !State::exists(self.git_dir()) {
+            return Err(Error::NothingInProgress);
+        }
+        self.remove_rebase_state()
+    }
+
+    /// Perform the instructions of `state` until it's done or there is a reason to stop, and finish the rebase if it is.
+    fn rebase_run(&self, mut state: State, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        loop {
+            if should_interrupt.load(Ordering::Relaxed) {
+                state.write(self.git_dir())?;
+                return Err(Error::Interrupted);
+            }
+            let Some(instruction) = state.todo.pop_current() else {
+                break;
+            };
+            state.done.instructions.push(instruction.clone());
+            state.write(self.git_dir())?;
+            if let Some(stop) = self.rebase_perform(&mut state, &instruction, should_interrupt)? {
+                state.write(self.git_dir())?;
+                return Ok(Outcome {
+                    head: self.head_id()?.detach(),
+                    stop: Some(stop),
+                });
+            }
+        }
+
+        let head = self.head_id()?.detach();
+        if let Some(name) = &state.head_name {
+            self.edit_reference(RefEdit {
+                change: Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: format!("rebase (finish): {name} onto {}", state.onto).into(),
+                    },
+                    expected: PreviousValue::Any,
+                    new: Target::Object(head),
+                },
+                name: name.as_bstr().try_into().map_err(|source| Error::InvalidLabel {
+                    name: name.clone(),
+                    source,
+                })?,
+                deref: false,
+            })?;
+            self.attach_head_after_rebase(name.as_ref(), "finish")?;
+        }
+        let edits = state
+            .update_refs
+            .iter()
+            .filter_map(|update| update.after.map(|after| (update, after)))
+            .map(|(update, after)| {
+                Ok(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: "rebase (update-refs): rewritten during rebase".into(),
+                        },
+                        expected: PreviousValue::Any,
+                        new: Target::Object(after),
+                    },
+                    name: update.name.as_bstr().try_into().map_err(|source| Error::InvalidLabel {
+                        name: update.name.clone(),
+                        source,
+                    })?,
+                    deref: false,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        self.edit_references(edits)?;
+        self.remove_rebase_state()?;
+        Ok(Outcome { head, stop: None })
+    }
+
+    /// Perform `instruction`, which is the last one in the list of done instructions of `state`, and return the reason
+    /// to stop afterward, if there is one.
+    fn rebase_perform(
+        &self,
+        state: &mut State,
+        instruction: &todo::Instruction,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Option<Stop>, Error> {
+        use todo::Instruction;
+        Ok(match instruction {
+            Instruction::Pick(commit)
+            | Instruction::Edit(commit)
+            | Instruction::Reword(commit)
+            | Instruction::Squash(commit)
+            | Instruction::Fixup { commit, .. } => {
+                let id = self.resolve_for_rebase(commit.name.as_ref())?;
+                self.rebase_pick(state, instruction, id, should_interrupt)?
+            }
+            Instruction::Drop(_) | Instruction::Noop | Instruction::Comment(_) => None,
+            Instruction::Break => Some(Stop::Break),
+            Instruction::Exec { command } => self.rebase_exec(state, command.as_ref())?,
+            Instruction::Label { name } => {
+                let head = self.head_id()?.detach();
+                self.edit_reference(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: format!("rebase (label) '{name}'").into(),
+                        },
+                        expected: PreviousValue::Any,
+                        new: Target::Object(head),
+                    },
+                    name: label_reference_name(name.as_ref())?,
+                    deref: false,
+                })?;
+                None
+            }
+            Instruction::Reset { name } => {
+                let id = self.resolve_for_rebase(name.as_ref())?;
+                let tree = self.find_commit(id)?.tree_id()?.detach();
+                self.checkout_tree_for_rebase(&tree, should_interrupt)?;
+                self.detach_head_for_rebase(id, format!("rebase (reset): '{name}'").into())?;
+                None
+            }
+            Instruction::Merge {
+                commit,
+                edit_message,
+                parents,
+                ..
+            } => {
+                let [other] = parents.as_slice() else {
+                    return Err(Error::UnsupportedOctopusMerge { parents: parents.len() });
+                };
+                let other = self.resolve_for_rebase(other.as_ref())?;
+                let original = commit
+                    .as_ref()
+                    .map(|name| self.resolve_for_rebase(name.as_ref()))
+                    .transpose()?;
+                let stop = self.rebase_merge(state, original, other, parents[0].as_ref(), should_interrupt)?;
+                match (stop, original) {
+                    (None, Some(original)) if *edit_message => {
+                        let head = self.head_id()?.detach();
+                        Some(self.stop_to_amend(state, original, head, Stop::Reword { commit: original })?)
+                    }
+                    (stop, _) => stop,
+                }
+            }
+            Instruction::UpdateRef { name } => {
+                let head = self.head_id()?.detach();
+                match state.update_refs.iter_mut().find(|update| update.name == *name) {
+                    Some(update) => update.after = Some(head),
+                    None => {
+                        let before = self.reference_id(name.as_ref())?;
+                        state.update_refs.push(gix_rebase::UpdateRef {
+                            name: name.clone(),
+                            before,
+                            after: Some(head),
+                        });
+                    }
+                }
+                None
+            }
+            Instruction::Revert(_) => {
+                return Err(Error::UnsupportedInstruction {
+                    command: instruction.command().expect("not a comment"),
+                })
+            }
+        })
+    }
+
+    /// Apply the changes of commit `id` to `HEAD` according to `instruction`, or fast-forward to it if possible.
+    fn rebase_pick(
+        &self,
+        state: &mut State,
+        instruction: &todo::Instruction,
+        id: ObjectId,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Option<Stop>, Error> {
+        use todo::Instruction;
+        let command = instruction.command().expect("not a comment");
+        let commit = self.find_commit(id)?;
+        let head = self.head_commit()?;
+        let is_squash = matches!(instruction, Instruction::Squash(_) | Instruction::Fixup { .. });
+        if is_squash
+            && !state.done.instructions.iter().rev().skip(1).any(|done| {
+                matches!(
+                    done,
+                    Instruction::Pick(_)
+                        | Instruction::Edit(_)
+                        | Instruction::Reword(_)
+                        | Instruction::Squash(_)
+                        | Instruction::Fixup { .. }
+                        | Instruction::Merge { .. }
+                )
+            })
+        {
+            return Err(Error::NoPreviousCommit { command });
+        }
+
+        let parents: Vec<_> = commit.parent_ids().map(Id::detach).collect();
+        let new_head = if !is_squash && parents == [head.id] {
+            let tree = commit.tree_id()?.detach();
+            self.checkout_tree_for_rebase(&tree, should_interrupt)?;
+            self.detach_head_for_rebase(id, "rebase: fast-forward".into())?;
+            id
+        } else {
+            let merged = self.merge_commit_changes(&commit, Operation::CherryPick, None, false, should_interrupt)?;
+            let message = match instruction {
+                Instruction::Squash(_) => squash_message(head.message_raw()?, commit.message_raw()?),
+                Instruction::Fixup { use_message: false, .. } => head.message_raw()?.to_owned(),
+                Instruction::Fixup { use_message: true, .. } => without_fixup_subject(commit.message_raw()?).to_owned(),
+                _ => commit.message_raw()?.to_owned(),
+            };
+            let author = if is_squash { head.author()? } else { commit.author()? }.to_owned()?;
+            if !merged.applied.conflicts.is_empty() {
+                let mut merge_message = message.clone();
+                append_conflicts(&mut merge_message, &merged.applied.conflicted_paths);
+                self.write_git_dir_file(MERGE_MSG, &merge_message)?;
+                self.write_git_dir_file(REBASE_HEAD, format!("{id}\n").as_bytes())?;
+                state.stopped_at = Some(id);
+                state.message = Some(message);
+                state.author = Some(author);
+                return Ok(Some(Stop::Conflicts {
+                    commit: id,
+                    conflicts: merged.applied.conflicts,
+                }));
+            }
+
+            let parent_tree = match parents.first() {
+                Some(parent) => self.find_commit(*parent)?.tree_id()?.detach(),
+                None => ObjectId::empty_tree(self.object_hash()),
+            };
+            let is_redundant = merged.applied.tree == merged.head_tree && commit.tree_id()? != parent_tree;
+            if !is_squash && is_redundant && !state.options.keep_redundant_commits {
+                return Ok(None);
+            }
+            let parents = if is_squash {
+                head.parent_ids().map(Id::detach).collect()
+            } else {
+                [head.id].into()
+            };
+            self.commit_for_rebase(merged.applied.tree, parents, author, message, head.id, command)?
+        };
+
+        Ok(match instruction {
+            Instruction::Edit(_) => Some(self.stop_to_amend(state, id, new_head, Stop::Edit { commit: id })?),
+            Instruction::Reword(_) | Instruction::Fixup { edit_message: true, .. } => {
+                Some(self.stop_to_amend(state, id, new_head, Stop::Reword { commit: id })?)
+            }
+            _ => None,
+        })
+    }
+
+    /// Record that the rebase stops with `stop` to amend `new_head`, which was created from commit `id`.
+    fn stop_to_amend(&self, state: &mut State, id: ObjectId, new_head: ObjectId, stop: Stop) -> Result<Stop, Error> {
+        self.write_git_dir_file(REBASE_HEAD, format!("{id}\n").as_bytes())?;
+        state.stopped_at = Some(id);
+        state.amend = Some(new_head);
+        Ok(stop)
+    }
+
+    /// Merge commit `other`, which was resolved from `label`, into `HEAD`, reusing the message and author of the merge
+    /// commit `original` if given, or fast-forward to `original` if it has the same parents.
+    fn rebase_merge(
+        &self,
+        state: &mut State,
+        original: Option<ObjectId>,
+        other: ObjectId,
+        label: &BStr,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Option<Stop>, Error> {
+        let head = self.head_commit()?;
+        let original = original.map(|id| self.find_commit(id)).transpose()?;
+        if let Some(original) = &original {
+            if original.parent_ids().map(Id::detach).eq([head.id, other]) {
+                let tree = original.tree_id()?.detach();
+                self.checkout_tree_for_rebase(&tree, should_interrupt)?;
+                self.detach_head_for_rebase(original.id, "rebase: fast-forward".into())?;
+                return Ok(None);
+            }
+        }
+
+        let head_tree = head.tree_id()?.detach();
+        let (current, _) = self.index_to_merge_into::<crate::cherry_pick::Error>(&head_tree, false)?;
+        let labels = gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: None,
+            current: Some("HEAD".into()),
+            other: Some(label),
+        };
+        let outcome = self.merge_commits(head.id, other, labels, self.tree_merge_options()?.into())?;
+        let applied =
+            self.apply_merge_outcome::<crate::cherry_pick::Error>(&current, outcome.tree_merge, should_interrupt)?;
+        let (message, author) = match &original {
+            Some(original) => (original.message_raw()?.to_owned(), original.author()?.to_owned()?),
+            None => (
+                format!("Merge branch '{label}'\n").into(),
+                self.author().ok_or(Error::AuthorMissing)??.to_owned()?,
+            ),
+        };
+        if !applied.conflicts.is_empty() {
+            let mut merge_message = message.clone();
+            append_conflicts(&mut merge_message, &applied.conflicted_paths);
+            self.write_git_dir_file(MERGE_MSG, &merge_message)?;
+            self.write_git_dir_file(MERGE_HEAD, format!("{other}\n").as_bytes())?;
+            let stopped_at = original.as_ref().map_or(other, |original| original.id);
+            state.stopped_at = Some(stopped_at);
+            state.message = Some(message);
+            state.author = Some(author);
+            return Ok(Some(Stop::Conflicts {
+                commit: stopped_at,
+                conflicts: applied.conflicts,
+            }));
+        }
+        self.commit_for_rebase(
+            applied.tree,
+            smallvec::smallvec![head.id, other],
+            author,
+            message,
+            head.id,
+            "merge",
+        )?;
+        Ok(None)
+    }
+
+    #[cfg(feature = "command")]
+    fn rebase_exec(&self, state: &mut State, command: &BStr) -> Result<Option<Stop>, Error> {
+        let mut cmd: std::process::Command = gix_command::prepare(gix_path::from_bstr(command).into_owned())
+            .with_shell()
+            .into();
+        let status = cmd
+            .current_dir(self.workdir().expect("checked when starting"))
+            .status()
+            .map_err(|source| Error::Exec {
+                command: command.to_owned(),
+                source,
+            })?;
+        let failed_status = (!status.success()).then_some(status);
+        if failed_status.is_none() && !self.is_dirty()? {
+            return Ok(None);
+        }
+        if state.options.reschedule_failed_exec {
+            state.todo.instructions.insert(
+                0,
+                todo::Instruction::Exec {
+                    command: command.to_owned(),
+                },
+            );
+        }
+        Ok(Some(Stop::ExecFailed {
+            command: command.to_owned(),
+            status: failed_status,
+        }))
+    }
+
+    #[cfg(not(feature = "command"))]
+    fn rebase_exec(&self, _state: &mut State, _command: &BStr) -> Result<Option<Stop>, Error> {
+        Err(Error::UnsupportedInstruction { command: "exec" })
+    }
+
+    /// Write a commit with the given fields, and point `HEAD`, which is expected to point to `head`, to it with a reflog
+    /// message for `command`.
+    fn commit_for_rebase(
+        &self,
+        tree: ObjectId,
+        parents: smallvec::SmallVec<[ObjectId; 1]>,
+        author: gix_actor::Signature,
+        message: BString,
+        head: ObjectId,
+        command: &str,
+    ) -> Result<ObjectId, Error> {
+        let committer = self.committer().ok_or(Error::CommitterMissing)??;
+        let reflog_message = format!(
+            "rebase ({command}): {}",
+            gix_object::commit::MessageRef::from_bytes(&message).summary()
+        );
+        let commit = gix_object::Commit {
+            message,
+            tree,
+            author,
+            committer: committer.into(),
+            encoding: None,
+            parents,
+            extra_headers: Default::default(),
+        };
+        let id = self
+            .write_commit_and_update_reference_as(
+                commit,
+                committer,
+                "HEAD".try_into().expect("valid"),
+                PreviousValue::MustExistAndMatch(Target::Object(head)),
+                reflog_message.into(),
+            )?
+            .detach();
+        #[cfg(feature = "command")]
+        self.run_hook_after_commit()?;
+        Ok(id)
+    }
+
+    /// Return `author` if set, or the configured author otherwise.
+    fn author_or_default(&self, author: Option<gix_actor::Signature>) -> Result<gix_actor::Signature, Error> {
+        match author {
+            Some(author) => Ok(author),
+            None => Ok(self.author().ok_or(Error::AuthorMissing)??.to_owned()?),
+        }
+    }
+
+    /// Change the index and the worktree from the current index to `tree`, unless local changes would be overwritten.
+    fn checkout_tree_for_rebase(&self, tree: &gix_hash::oid, should_interrupt: &AtomicBool) -> Result<(), Error> {
+        let current = self.fresh_index()?;
+        let patterns = self.sparse_checkout_patterns()?;
+        let mut index = self.index_for_tree::<Error>(tree, patterns.as_ref())?;
+        let overwritten = self.local_changes_overwritten_by::<Error>(&current, &index)?;
+        if !overwritten.is_empty() {
+            return Err(Error::WouldOverwriteLocalChanges { paths: overwritten });
+        }
+        self.update_worktree(&current, &mut index, should_interrupt)?;
+        self.write_index_with_patterns::<Error>(index, patterns.as_ref())
+    }
+
+    /// Point `HEAD` to `id` directly, with `reflog_message`.
+    fn detach_head_for_rebase(&self, id: ObjectId, reflog_message: BString) -> Result<(), Error> {
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: false,
+                    message: reflog_message,
+                },
+                expected: PreviousValue::MustExist,
+                new: Target::Object(id),
+            },
+            name: "HEAD".try_into().expect("valid"),
+            deref: false,
+        })?;
+        Ok(())
+    }
+
+    /// Let `HEAD` refer to the branch `name` again, with a reflog message for `operation`.
+    fn attach_head_after_rebase(&self, name: &BStr, operation: &str) -> Result<(), Error> {
+        let name: FullName = name.try_into().map_err(|source| Error::InvalidLabel {
+            name: name.to_owned(),
+            source,
+        })?;
+        let head = self.head_id()?.detach();
+        let branch = self.reference_id(name.as_bstr())?;
+        // Symbolic updates don't get a reflog entry, so write the one `git` writes for the switch from `head` first,
+        // which is left out if the id doesn't change.
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::Only,
+                    force_create_reflog: false,
+                    message: format!("rebase ({operation}): returning to {}", name.as_bstr()).into(),
+                },
+                expected: PreviousValue::ExistingMustMatch(Target::Object(head)),
+                new: Target::Object(branch),
+            },
+            name: "HEAD".try_into().expect("valid"),
+            deref: false,
+        })?;
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange::default(),
+                expected: PreviousValue::MustExist,
+                new: Target::Symbolic(name),
+            },
+            name: "HEAD".try_into().expect("valid"),
+            deref: false,
+        })?;
+        Ok(())
+    }
+
+    /// Resolve `name` of a todo list, which is a label, an abbreviated commit id or the name of a reference, to a commit.
+    fn resolve_for_rebase(&self, name: &BStr) -> Result<ObjectId, Error> {
+        if let Ok(label) = label_reference_name(name) {
+            if let Some(mut reference) = self.try_find_reference(&label)? {
+                return Ok(reference.peel_to_id()?.detach());
+            }
+        }
+        if let Some(id) = self.lookup_abbreviated_id(name)? {
+            return Ok(id);
+        }
+        match self.try_find_reference(name) {
+            Ok(Some(mut reference)) => Ok(reference.peel_to_id()?.detach()),
+            Ok(None) | Err(_) => Err(Error::ResolveCommit { name: name.to_owned() }),
+        }
+    }
+
+    /// Return the id the reference `name` points to, or the null id if it doesn't exist.
+    fn reference_id(&self, name: &BStr) -> Result<ObjectId, Error> {
+        Ok(match self.try_find_reference(name)? {
+            Some(mut reference) => reference.peel_to_id()?.detach(),
+            None => self.object_hash().null(),
+        })
+    }
+
+    /// Forget about the instruction the rebase stopped at, after it was concluded or skipped.
+    fn remove_stopped_instruction(&self, state: &mut State) -> Result<(), Error> {
+        state.stopped_at = None;
+        state.message = None;
+        state.author = None;
+        state.amend = None;
+        for name in [REBASE_HEAD, MERGE_HEAD, MERGE_MSG] {
+            remove_file(&self.git_dir().join(name))?;
+        }
+        Ok(())
+    }
+
+    /// Remove the state of the rebase along with the references created by `label` instructions.
+    fn remove_rebase_state(&self) -> Result<(), Error> {
+        let mut edits = Vec::new();
+        for reference in self.references()?.prefixed(REWRITTEN_PREFIX)? {
+            let reference = reference.map_err(Error::IterReferences)?;
+            edits.push(RefEdit {
+                change: Change::Delete {
+                    expected: PreviousValue::Any,
+                    log: RefLog::AndReference,
+                },
+                name: reference.name().to_owned(),
+                deref: false,
+            });
+        }
+        self.edit_references(edits)?;
+        for name in [REBASE_HEAD, MERGE_HEAD, MERGE_MSG] {
+            remove_file(&self.git_dir().join(name))?;
+        }
+        State::remove(self.git_dir()).map_err(|source| Error::Io {
+            path: State::dir(self.git_dir()),
+            source,
+        })
+    }
+
+    /// Return the content of the file `name` in the `git` directory, or `None` if it doesn't exist.
+    fn read_git_dir_file(&self, name: &str) -> Result<Option<Vec<u8>>, Error> {
+        let path = self.git_dir().join(name);
+        match std::fs::read(&path) {
+            Ok(data) => Ok(Some(data)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(Error::Io { path, source }),
+        }
+    }
+}
+
+/// Return the name of the reference that holds the commit labelled `name`.
+fn label_reference_name(name: &BStr) -> Result<FullName, Error> {
+    let mut full_name = BString::from(REWRITTEN_PREFIX);
+    full_name.push_str(name);
+    full_name.try_into().map_err(|source| Error::InvalidLabel {
+        name: name.to_owned(),
+        source,
+    })
+}
+
+/// Return the message of melding a commit with `message` into the one with `previous_message`, which is both of them
+/// without comments, separated by an empty line.
+fn squash_message(previous_message: &BStr, message: &BStr) -> BString {
+    let mut out = strip_comments_and_whitespace(previous_message);
+    let message = strip_comments_and_whitespace(without_fixup_subject(message));
+    if !message.is_empty() {
+        out.push_byte(b'\n');
+        out.push_str(message);
+    }
+    out
+}
+
+/// Return `message` without its first paragraph if it starts with `squash! `, `fixup! ` or `amend! `, as these
+/// subjects are only used to find the commit to meld into.
+fn without_fixup_subject(message: &BStr) -> &BStr {
+    if !["squash! ", "fixup! ", "amend! "]
+        .iter()
+        .any(|prefix| message.starts_with(prefix.as_bytes()))
+    {
+        return message;
+    }
+    match message.find("\n\n") {
+        Some(pos) => message[pos + 2..].as_bstr(),
+        None => b"".as_bstr(),
+    }
+}
+
+fn remove_file(path: &std::path::Path) -> Result<(), Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
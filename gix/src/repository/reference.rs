@@ -140,6 +140,11 @@ impl crate::Repository {
     ///
     /// Returns all reference edits, which might be more than where provided due the splitting of symbolic references, and
     /// whose previous (_old_) values are the ones seen on in storage after the reference was locked.
+    ///
+    /// With the `command` feature and if [enabled](crate::open::Options::run_hooks()), the
+    /// [`reference-transaction` hook](Self::hook()) is run like `git` does, once the
+    /// references are locked with `prepared`, and then with `committed` or `aborted`. If it fails in the `prepared` state,
+    /// the transaction is aborted.
     pub fn edit_references(
         &self,
         edits: impl IntoIterator<Item = RefEdit>,
@@ -156,11 +161,48 @@ impl crate::Repository {
         committer: Option<gix_actor::SignatureRef<'_>>,
     ) -> Result<Vec<RefEdit>, reference::edit::Error> {
         let (file_lock_fail, packed_refs_lock_fail) = self.config.lock_timeout()?;
-        self.refs
+        let transaction = self
+            .refs
             .transaction()
-            .prepare(edits, file_lock_fail, packed_refs_lock_fail)?
-            .commit(committer)
-            .map_err(Into::into)
+            .prepare(edits, file_lock_fail, packed_refs_lock_fail)?;
+
+        #[cfg(feature = "command")]
+        let hook_input = if self.options.run_hooks {
+            reference_transaction_hook_input(transaction.edits(), self.object_hash())
+        } else {
+            Vec::new()
+        };
+        #[cfg(feature = "command")]
+        if let Err(err) = self.run_reference_transaction_hook("prepared", &hook_input) {
+            transaction.rollback();
+            self.run_reference_transaction_hook("aborted", &hook_input).ok();
+            return Err(err);
+        }
+        let edits = match transaction.commit(committer) {
+            Ok(edits) => edits,
+            Err(err) => {
+                #[cfg(feature = "command")]
+                self.run_reference_transaction_hook("aborted", &hook_input).ok();
+                return Err(err.into());
+            }
+        };
+        #[cfg(feature = "command")]
+        if let Err(_err) = self.run_reference_transaction_hook("committed", &hook_input) {
+            gix_trace::warn!("Ignoring failure of the reference-transaction hook after committing: {_err}");
+        }
+        Ok(edits)
+    }
+
+    /// Run the `reference-transaction` hook in `state` with `input`, unless there is nothing to tell it about.
+    #[cfg(feature = "command")]
+    fn run_reference_transaction_hook(&self, state: &str, input: &[u8]) -> Result<(), reference::edit::Error> {
+        if input.is_empty() {
+            return Ok(());
+        }
+        if let Some(hook) = self.hook_to_run("reference-transaction")? {
+            hook.arg(state).stdin(input).run()?;
+        }
+        Ok(())
     }
 
     /// Return the repository head, an abstraction to help dealing with the `HEAD` reference.
@@ -304,3 +346,38 @@ impl crate::Repository {
         }
     }
 }
+
+/// Produce the input of the `reference-transaction` hook for the prepared `edits`, one `<old-value> <new-value> <ref-name>` line
+/// per changed reference, with the null id standing in for values that don't exist.
+#[cfg(feature = "command")]
+fn reference_transaction_hook_input<'a>(
+    edits: impl Iterator<Item = &'a RefEdit>,
+    object_hash: gix_hash::Kind,
+) -> Vec<u8> {
+    use std::io::Write;
+
+    let format = |target: Option<gix_ref::TargetRef<'_>>| match target {
+        Some(gix_ref::TargetRef::Object(id)) => id.to_string(),
+        Some(gix_ref::TargetRef::Symbolic(name)) => format!("ref:{}", name.as_bstr()),
+        None => object_hash.null().to_string(),
+    };
+    let mut out = Vec::new();
+    for edit in edits {
+        let log_only = match &edit.change {
+            Change::Update { log, .. } => log.mode == RefLog::Only,
+            Change::Delete { log, .. } => *log == RefLog::Only,
+        };
+        if log_only {
+            continue;
+        }
+        writeln!(
+            out,
+            "{} {} {}",
+            format(edit.change.previous_value()),
+            format(edit.change.new_value()),
+            edit.name.as_bstr()
+        )
+        .expect("writing to a vec can't fail");
+    }
+    out
+}
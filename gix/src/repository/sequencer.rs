@@ -0,0 +1,406 @@
+use std::{
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use gix_hash::ObjectId;
+use gix_index::entry::Stage;
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+use crate::{
+    bstr::{BString, ByteSlice, ByteVec},
+    cherry_pick::{self, CHERRY_PICK_HEAD, MERGE_MSG, REVERT_HEAD},
+    sequencer::{todo, Error, Operation, Options, Outcome, State},
+    Repository,
+};
+
+/// Cherry-picking and reverting multiple commits
+impl Repository {
+    /// Apply the changes of `commits` one after another on top of `HEAD` with `operation` and `options`, like
+    /// `git cherry-pick <commit>...` or `git revert <commit>...`, and check `should_interrupt` to stop between commits
+    /// and while updating the worktree.
+    ///
+    /// Each commit is applied like [`cherry_pick()`](Self::cherry_pick()) or [`revert()`](Self::revert()) do, and the
+    /// [state](State) of the sequence is written to the `git` directory before each of them.
+    /// If there are conflicts, the sequence stops so that it can be resumed with [`sequence_continue()`](Self::sequence_continue())
+    /// once the resolution was added to the index, or with [`sequence_skip()`](Self::sequence_skip()) to leave out the commit.
+    ///
+    /// Commit messages are never edited, and [`Options::strategy`] and [`Options::strategy_options`] are ignored as
+    /// changes are always merged like [`merge_trees()`](Self::merge_trees()) does.
+    pub fn sequence_start(
+        &self,
+        operation: Operation,
+        commits: impl IntoIterator<Item = impl Into<ObjectId>>,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        if self.workdir().is_none() {
+            return Err(Error::BareRepository {
+                git_dir: self.git_dir().to_owned(),
+            });
+        }
+        if let Some(state) = self.state() {
+            return Err(Error::InProgress { state });
+        }
+        if State::exists(self.git_dir()) {
+            return Err(Error::SequenceInProgress);
+        }
+        cherry_pick_options(&options)?;
+
+        let mut todo = todo::List::default();
+        for id in commits {
+            let commit = self.find_commit(id)?;
+            let commit = todo::Commit {
+                name: commit.short_id()?.to_string().into(),
+                summary: commit.message()?.summary().into_owned(),
+            };
+            todo.instructions.push(match operation {
+                Operation::CherryPick => todo::Instruction::Pick(commit),
+                Operation::Revert => todo::Instruction::Revert(commit),
+            });
+        }
+        let state = State::new(todo, options, self.head_id()?.detach());
+        self.sequence_run(state, Vec::new(), should_interrupt)
+    }
+
+    /// Commit the resolved conflicts of the commit the sequence stopped at and apply the remaining ones, like
+    /// `git cherry-pick --continue`, and check `should_interrupt` to stop between commits and while updating the worktree.
+    ///
+    /// The commit message is read from `MERGE_MSG`, without comment lines. If there is no sequence in progress,
+    /// this concludes a single [`cherry_pick()`](Self::cherry_pick()) or [`revert()`](Self::revert()) that stopped
+    /// with conflicts.
+    /// If the resolution was committed already, the index must not have changes compared to `HEAD`.
+    pub fn sequence_continue(&self, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        let state = State::read(self.git_dir())?;
+        let mut commits = Vec::new();
+        match self.stopped_operation()? {
+            Some((operation, id)) => {
+                commits.push(self.commit_resolution(operation, id, state.as_ref().map(|state| &state.options))?);
+            }
+            None if state.is_none() => return Err(Error::NothingInProgress),
+            None => {
+                let mut index = self.fresh_index()?;
+                if self.write_tree_from_index(&mut index)? != self.head_commit()?.tree_id()? {
+                    return Err(Error::DirtyIndex);
+                }
+            }
+        }
+
+        let Some(mut state) = state else {
+            return Ok(Outcome {
+                commits,
+                stopped_at: None,
+                conflicts: Vec::new(),
+            });
+        };
+        state.todo.pop_current();
+        if let Some(id) = commits.last() {
+            state.abort_safety = Some(*id);
+        }
+        self.sequence_run(state, commits, should_interrupt)
+    }
+
+    /// Reset the index and the worktree to `HEAD` to leave out the commit the sequence stopped at, and apply the remaining
+    /// ones, like `git cherry-pick --skip`, and check `should_interrupt` to stop between commits and while updating the worktree.
+    ///
+    /// If there is no sequence in progress, this undoes a single [`cherry_pick()`](Self::cherry_pick()) or
+    /// [`revert()`](Self::revert()) that stopped with conflicts.
+    pub fn sequence_skip(&self, should_interrupt: &AtomicBool) -> Result<Outcome, Error> {
+        let state = State::read(self.git_dir())?;
+        if state.is_none() && self.stopped_operation()?.is_none() {
+            return Err(Error::NothingInProgress);
+        }
+        let head_tree = self.head_commit()?.tree_id()?.detach();
+        self.reset_index_and_worktree::<Error>(&head_tree, should_interrupt)?;
+        self.remove_stopped_operation()?;
+
+        let Some(mut state) = state else {
+            return Ok(Outcome {
+                commits: Vec::new(),
+                stopped_at: None,
+                conflicts: Vec::new(),
+            });
+        };
+        state.todo.pop_current();
+        self.sequence_run(state, Vec::new(), should_interrupt)
+    }
+
+    /// Stop the sequence and reset `HEAD`, the index and the worktree to where they were when it started, like
+    /// `git cherry-pick --abort`, and check `should_interrupt` to stop updating the worktree.
+    /// Return `true` if everything was reset.
+    ///
+    /// If `HEAD` was changed since the sequence made its last commit, it isn't reset to avoid losing work and
+    /// `false` is returned, but the state of the sequence is removed nonetheless.
+    /// If there is no sequence in progress, this undoes a single [`cherry_pick()`](Self::cherry_pick()) or
+    /// [`revert()`](Self::revert()) that stopped with conflicts.
+    pub fn sequence_abort(&self, should_interrupt: &AtomicBool) -> Result<bool, Error> {
+        let Some(state) = State::read(self.git_dir())? else {
+            if self.stopped_operation()?.is_none() {
+                return Err(Error::NothingInProgress);
+            }
+            let head_tree = self.head_commit()?.tree_id()?.detach();
+            self.reset_index_and_worktree::<Error>(&head_tree, should_interrupt)?;
+            self.remove_stopped_operation()?;
+            return Ok(true);
+        };
+
+        let head = self.head_id()?.detach();
+        let is_rollback_safe = state.is_rollback_safe(Some(&head));
+        if is_rollback_safe {
+            let tree = self.find_commit(state.head)?.tree_id()?.detach();
+            self.reset_index_and_worktree::<Error>(&tree, should_interrupt)?;
+            if head != state.head {
+                self.edit_reference(RefEdit {
+                    change: Change::Update {
+                        log: LogChange {
+                            mode: RefLog::AndReference,
+                            force_create_reflog: false,
+                            message: format!("reset: moving to {}", state.head).into(),
+                        },
+                        expected: PreviousValue::MustExistAndMatch(Target::Object(head)),
+                        new: Target::Object(state.head),
+                    },
+                    name: "HEAD".try_into().expect("valid"),
+                    deref: true,
+                })?;
+            }
+            self.remove_stopped_operation()?;
+        } else {
+            gix_trace::warn!("HEAD was moved since the last commit of the sequence, it's not reset");
+        }
+        self.remove_sequence_state()?;
+        Ok(is_rollback_safe)
+    }
+
+    /// Forget about the sequence in progress without changing `HEAD`, the index or the worktree, like `git cherry-pick --quit`.
+    ///
+    /// This also forgets about a single [`cherry_pick()`](Self::cherry_pick()) or [`revert()`](Self::revert()) that
+    /// stopped with conflicts.
+    pub fn sequence_quit(&self) -> Result<(), Error> {
+        self.remove_sequence_state()?;
+        self.remove_stopped_operation()
+    }
+
+    /// Perform the instructions of `state` until it's done or there are conflicts, with `commits` being the ones that
+    /// were created by the sequence so far.
+    fn sequence_run(
+        &self,
+        mut state: State,
+        mut commits: Vec<ObjectId>,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let options = cherry_pick_options(&state.options)?;
+        while let Some(instruction) = state.todo.current() {
+            state.write(self.git_dir())?;
+            if should_interrupt.load(Ordering::Relaxed) {
+                return Err(Error::Interrupted);
+            }
+            let id = match instruction {
+                todo::Instruction::Pick(commit) | todo::Instruction::Revert(commit) => {
+                    self.resolve_todo_commit(commit.name.as_ref())?
+                }
+                instruction => {
+                    return Err(Error::UnsupportedInstruction {
+                        command: instruction.command().expect("not a comment"),
+                    })
+                }
+            };
+            let res = match instruction {
+                todo::Instruction::Revert(_) => self.revert(id, options, should_interrupt),
+                _ => self.cherry_pick(id, options, should_interrupt),
+            };
+            let outcome = match res {
+                Err(cherry_pick::Error::Empty { .. }) if state.options.drop_redundant_commits => {
+                    state.todo.pop_current();
+                    continue;
+                }
+                res => res?,
+            };
+            if !outcome.conflicts.is_empty() {
+                return Ok(Outcome {
+                    commits,
+                    stopped_at: Some(id),
+                    conflicts: outcome.conflicts,
+                });
+            }
+            state.todo.pop_current();
+            if let Some(id) = outcome.commit {
+                commits.push(id);
+                state.abort_safety = Some(id);
+            }
+        }
+        self.remove_sequence_state()?;
+        Ok(Outcome {
+            commits,
+            stopped_at: None,
+            conflicts: Vec::new(),
+        })
+    }
+
+    /// Commit the resolved conflicts in the index that were caused by applying commit `id` with `operation`, and return
+    /// the id of the new commit.
+    fn commit_resolution(
+        &self,
+        operation: Operation,
+        id: ObjectId,
+        options: Option<&Options>,
+    ) -> Result<ObjectId, Error> {
+        let mut index = self.fresh_index()?;
+        index.expand_all_sparse_directories(&self.objects)?;
+        if index.entries().iter().any(|entry| entry.stage() != Stage::Unconflicted) {
+            return Err(Error::UnmergedIndex);
+        }
+        let tree = self.write_tree_from_index(&mut index)?.detach();
+        let head = self.head_commit()?;
+        let allow_empty = options.is_some_and(|options| options.allow_empty || options.keep_redundant_commits);
+        if tree == head.tree_id()? && !allow_empty {
+            return Err(Error::Empty { id });
+        }
+
+        let message_path = self.git_dir().join(MERGE_MSG);
+        let message = std::fs::read(&message_path).map_err(|source| Error::Io {
+            path: message_path,
+            source,
+        })?;
+        let message = strip_comments_and_whitespace(&message);
+        if message.is_empty() && !options.is_some_and(|options| options.allow_empty_message) {
+            return Err(Error::EmptyMessage);
+        }
+
+        let committer = self.committer().ok_or(Error::CommitterMissing)??;
+        let applied = self.find_commit(id)?;
+        let author = match operation {
+            Operation::CherryPick => applied.author()?,
+            Operation::Revert => self.author().ok_or(Error::AuthorMissing)??,
+        };
+        let commit = gix_object::Commit {
+            message,
+            tree,
+            author: author.into(),
+            committer: committer.into(),
+            encoding: None,
+            parents: [head.id].into(),
+            extra_headers: Default::default(),
+        };
+        let new_id = self
+            .write_commit_and_update_reference(
+                commit,
+                committer,
+                "HEAD".try_into().expect("valid"),
+                match operation {
+                    Operation::CherryPick => "commit (cherry-pick)",
+                    Operation::Revert => "commit",
+                },
+            )?
+            .detach();
+        self.remove_stopped_operation()?;
+        #[cfg(feature = "command")]
+        self.run_hook_after_commit()?;
+        Ok(new_id)
+    }
+
+    /// Return the operation and the commit it was applying if it stopped with conflicts.
+    fn stopped_operation(&self) -> Result<Option<(Operation, ObjectId)>, Error> {
+        for (name, operation) in [
+            (CHERRY_PICK_HEAD, Operation::CherryPick),
+            (REVERT_HEAD, Operation::Revert),
+        ] {
+            let path = self.git_dir().join(name);
+            match std::fs::read(&path) {
+                Ok(data) => {
+                    let id = ObjectId::from_hex(data.trim()).map_err(|source| Error::DecodeId { path, source })?;
+                    return Ok(Some((operation, id)));
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                Err(source) => return Err(Error::Io { path, source }),
+            }
+        }
+        Ok(None)
+    }
+
+    fn remove_stopped_operation(&self) -> Result<(), Error> {
+        for name in [CHERRY_PICK_HEAD, REVERT_HEAD, MERGE_MSG] {
+            remove_file(&self.git_dir().join(name))?;
+        }
+        Ok(())
+    }
+
+    fn remove_sequence_state(&self) -> Result<(), Error> {
+        State::remove(self.git_dir()).map_err(|source| Error::Io {
+            path: State::dir(self.git_dir()),
+            source,
+        })
+    }
+
+    /// Resolve `name` of a todo list, which is an abbreviated commit id, to the full id of the commit.
+    fn resolve_todo_commit(&self, name: &[u8]) -> Result<ObjectId, Error> {
+        self.lookup_abbreviated_id(name)?
+            .ok_or_else(|| Error::ResolveCommit { name: name.into() })
+    }
+
+    /// Return the full id of the object whose id starts with the hexadecimal `name`, or `None` if `name` isn't
+    /// a hexadecimal id or if it doesn't identify exactly one object.
+    pub(crate) fn lookup_abbreviated_id(
+        &self,
+        name: &[u8],
+    ) -> Result<Option<ObjectId>, gix_odb::store::prefix::lookup::Error> {
+        let Some(prefix) = name.to_str().ok().and_then(|hex| gix_hash::Prefix::from_hex(hex).ok()) else {
+            return Ok(None);
+        };
+        Ok(self.objects.lookup_prefix(prefix, None)?.and_then(Result::ok))
+    }
+}
+
+/// Return the options to apply each commit of a sequence with `options`, or fail if some of them aren't supported.
+fn cherry_pick_options(options: &Options) -> Result<cherry_pick::Options, Error> {
+    for (is_set, name) in [
+        (options.signoff, "signoff"),
+        (options.allow_ff, "allow-ff"),
+        (options.gpg_sign.is_some(), "gpg-sign"),
+    ] {
+        if is_set {
+            return Err(Error::UnsupportedOption { name });
+        }
+    }
+    Ok(cherry_pick::Options {
+        mainline: options.mainline,
+        no_commit: options.no_commit,
+        record_origin: options.record_origin,
+        allow_empty: options.allow_empty || options.keep_redundant_commits,
+    })
+}
+
+/// Remove lines starting with `#` from `message`, along with trailing whitespace of all lines and empty lines at the
+/// beginning, at the end and in a row, like `git commit --cleanup=strip` does.
+pub(crate) fn strip_comments_and_whitespace(message: &[u8]) -> BString {
+    let mut out = BString::default();
+    let mut pending_empty_line = false;
+    for line in message.lines().filter(|line| !line.starts_with(b"#")) {
+        let line = line.trim_end();
+        if line.is_empty() {
+            pending_empty_line = !out.is_empty();
+            continue;
+        }
+        if pending_empty_line {
+            out.push_byte(b'\n');
+            pending_empty_line = false;
+        }
+        out.push_str(line);
+        out.push_byte(b'\n');
+    }
+    out
+}
+
+fn remove_file(path: &Path) -> Result<(), Error> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(source) => Err(Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
@@ -0,0 +1,375 @@
+use gix_worktree::sparse::Patterns;
+
+use crate::{
+    config::{
+        cache::util::ApplyLeniencyDefault,
+        tree::{Core, Index},
+    },
+    repository::{collapse_sparse_index, sparse_checkout_patterns},
+    Repository,
+};
+
+/// Sparse checkouts
+impl Repository {
+    /// Return the patterns of the sparse checkout of this worktree as read from `info/sparse-checkout` in its git directory,
+    /// or `None` if `core.sparseCheckout` isn't enabled or if there is no such file.
+    ///
+    /// The patterns are interpreted in *cone mode* if `core.sparseCheckoutCone` is enabled.
+    pub fn sparse_checkout_patterns(&self) -> Result<Option<Patterns>, sparse_checkout_patterns::Error> {
+        let config = &self.config.resolved;
+        let lenient = self.config.lenient_config;
+        let enabled = config
+            .boolean(Core::SPARSE_CHECKOUT)
+            .map(|res| Core::SPARSE_CHECKOUT.enrich_error(res))
+            .transpose()
+            .with_lenient_default(lenient)?
+            .unwrap_or_default();
+        if !enabled {
+            return Ok(None);
+        }
+        let cone = config
+            .boolean(Core::SPARSE_CHECKOUT_CONE)
+            .map(|res| Core::SPARSE_CHECKOUT_CONE.enrich_error(res))
+            .transpose()
+            .with_lenient_default(lenient)?
+            .unwrap_or_default();
+        let path = self.sparse_checkout_path();
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(Some(Patterns::from_bytes(&bytes, cone))),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(sparse_checkout_patterns::Error::Io { path, source }),
+        }
+    }
+
+    /// Turn all directories of `index` that are entirely outside of the sparse checkout with `patterns` into sparse directory
+    /// entries if `index.sparse` is enabled and `patterns` are in *cone mode*, like `git` does before writing the index.
+    /// Trees of these directories are written to the object database if they aren't known yet.
+    ///
+    /// Return `true` if `index` was turned into a [sparse index](gix_index::State::is_sparse()).
+    ///
+    /// Use [`gix_index::State::expand_all_sparse_directories()`] to turn it back into a regular index.
+    pub fn collapse_sparse_index(
+        &self,
+        index: &mut gix_index::State,
+        patterns: &Patterns,
+    ) -> Result<bool, collapse_sparse_index::Error> {
+        let enabled = self
+            .config
+            .resolved
+            .boolean(Index::SPARSE)
+            .map(|res| Index::SPARSE.enrich_error(res))
+            .transpose()
+            .with_lenient_default(self.config.lenient_config)?
+            .unwrap_or_default();
+        if !enabled || !patterns.is_cone() {
+            return Ok(false);
+        }
+        let case = self.sparse_checkout_case();
+        index.collapse_sparse_directories(&self.objects, |dir| !patterns.is_included(dir, true, case))?;
+        Ok(true)
+    }
+
+    fn sparse_checkout_path(&self) -> std::path::PathBuf {
+        self.git_dir().join("info").join("sparse-checkout")
+    }
+
+    pub(crate) fn sparse_checkout_case(&self) -> gix_glob::pattern::Case {
+        if self.config.ignore_case {
+            gix_glob::pattern::Case::Fold
+        } else {
+            gix_glob::pattern::Case::Sensitive
+        }
+    }
+}
+
+#[cfg(feature = "worktree-mutation")]
+mod set {
+    use std::sync::atomic::AtomicBool;
+
+    use gix_index::entry::Flags;
+    use gix_worktree::sparse::Patterns;
+
+    use crate::{
+        bstr::{BStr, ByteSlice},
+        config::tree::{Core, Extensions},
+        repository::{
+            checkout::remove_empty_leading_directories,
+            sparse_checkout_set::{Error, Outcome},
+        },
+        Repository,
+    };
+
+    /// Sparse checkouts
+    impl Repository {
+        /// Add `directories` to the sparse checkout in *cone mode* and check out the files within them, like
+        /// `git sparse-checkout add` does.
+        ///
+        /// See [`sparse_checkout_set()`](Self::sparse_checkout_set()) for details.
+        pub fn sparse_checkout_add_directories(
+            &mut self,
+            directories: impl IntoIterator<Item = impl AsRef<BStr>>,
+            should_interrupt: &AtomicBool,
+        ) -> Result<Outcome, Error> {
+            let patterns = self.sparse_checkout_patterns()?.ok_or(Error::NotEnabled)?;
+            let existing = patterns.cone_directories().ok_or(Error::NotConeMode)?;
+            let patterns = Patterns::from_cone_directories(
+                existing
+                    .map(ToOwned::to_owned)
+                    .chain(directories.into_iter().map(|dir| dir.as_ref().to_owned()))
+                    .collect::<Vec<_>>(),
+            );
+            self.sparse_checkout_set(patterns, should_interrupt)
+        }
+
+        /// Remove `directories` from the sparse checkout in *cone mode* and remove the files within them from the worktree.
+        ///
+        /// Note that only directories which were previously added can be removed, their sub-directories can't be excluded that way.
+        /// See [`sparse_checkout_set()`](Self::sparse_checkout_set()) for details.
+        pub fn sparse_checkout_remove_directories(
+            &mut self,
+            directories: impl IntoIterator<Item = impl AsRef<BStr>>,
+            should_interrupt: &AtomicBool,
+        ) -> Result<Outcome, Error> {
+            let patterns = self.sparse_checkout_patterns()?.ok_or(Error::NotEnabled)?;
+            let existing = patterns.cone_directories().ok_or(Error::NotConeMode)?;
+            let remove: Vec<_> = directories
+                .into_iter()
+                .map(|dir| Patterns::from_cone_directories([dir]))
+                .flat_map(|p| {
+                    p.cone_directories()
+                        .into_iter()
+                        .flatten()
+                        .map(ToOwned::to_owned)
+                        .collect::<Vec<_>>()
+                })
+                .collect();
+            let patterns = Patterns::from_cone_directories(
+                existing
+                    .filter(|dir| !remove.iter().any(|removed| removed == dir))
+                    .map(ToOwned::to_owned)
+                    .collect::<Vec<_>>(),
+            );
+            self.sparse_checkout_set(patterns, should_interrupt)
+        }
+
+        /// Change the sparse checkout of this worktree to `patterns`, enabling it if needed, and update the index and the
+        /// worktree incrementally, checking `should_interrupt` to stop the checkout.
+        ///
+        /// Files that are newly included are checked out, while unmodified files that are newly excluded are removed along
+        /// with the directories that become empty.
+        /// Like `git`, modified files are kept in the worktree and remain included.
+        ///
+        /// `patterns` are written to `info/sparse-checkout` of the worktree's git directory, and `core.sparseCheckout` as well as
+        /// `core.sparseCheckoutCone` are set in the local configuration file if needed.
+        pub fn sparse_checkout_set(
+            &mut self,
+            patterns: Patterns,
+            should_interrupt: &AtomicBool,
+        ) -> Result<Outcome, Error> {
+            let workdir = self
+                .workdir()
+                .ok_or_else(|| Error::BareRepository {
+                    git_dir: self.git_dir().to_owned(),
+                })?
+                .to_owned();
+            let mut outcome = Outcome::default();
+            // Read the index from disk as the shared snapshot may be stale if it was changed recently.
+            let index = self.open_index().map(Some).or_else(|err| match err {
+                crate::worktree::open_index::Error::IndexFile(gix_index::file::init::Error::Io(err))
+                    if err.kind() == std::io::ErrorKind::NotFound =>
+                {
+                    Ok(None)
+                }
+                err => Err(err),
+            })?;
+            if let Some(mut index) = index {
+                // Sparse directories may contain entries that are now included.
+                index.expand_all_sparse_directories(&self.objects)?;
+                let changes = gix_worktree::sparse::update_index(&mut index, &patterns, self.sparse_checkout_case());
+
+                let stat_options = self.stat_options()?;
+                for idx in changes.excluded {
+                    let timestamp = index.timestamp();
+                    let (entries, path_backing) = index.entries_mut_and_pathbacking();
+                    let entry = &mut entries[idx];
+                    let rela_path = entry.path_in(path_backing);
+                    let path = workdir.join(gix_path::from_bstr(rela_path));
+                    let is_unchanged = || -> std::io::Result<bool> {
+                        let metadata = gix_index::fs::Metadata::from_path_no_follow(&path)?;
+                        if gix_index::entry::Stat::from_fs(&metadata)
+                            .is_ok_and(|stat| stat.matches(&entry.stat, stat_options))
+                            && !entry.stat.is_racy(timestamp, stat_options)
+                        {
+                            return Ok(true);
+                        }
+                        Ok(metadata.is_file()
+                            && gix_object::compute_hash(
+                                self.object_hash(),
+                                gix_object::Kind::Blob,
+                                &std::fs::read(&path)?,
+                            )
+                            .is_ok_and(|id| id == entry.id))
+                    };
+                    match is_unchanged() {
+                        Ok(true) => {
+                            std::fs::remove_file(&path).map_err(|source| Error::Io {
+                                path: path.clone(),
+                                source,
+                            })?;
+                            remove_empty_leading_directories(&path, &workdir);
+                            outcome.files_removed += 1;
+                        }
+                        Ok(false) => {
+                            outcome.files_kept.push(rela_path.to_owned());
+                            entry.flags.remove(Flags::SKIP_WORKTREE);
+                            if !entry.flags.contains(Flags::INTENT_TO_ADD) {
+                                entry.flags.remove(Flags::EXTENDED);
+                            }
+                        }
+                        Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+                        Err(source) => return Err(Error::Io { path, source }),
+                    }
+                }
+
+                if !changes.included.is_empty() {
+                    let mut to_checkout = gix_index::State::new(self.object_hash());
+                    for idx in &changes.included {
+                        let entry = index.entry(*idx);
+                        to_checkout.dangerously_push_entry(
+                            entry.stat,
+                            entry.id,
+                            entry.flags,
+                            entry.mode,
+                            entry.path(&index),
+                        );
+                    }
+                    // In partial clones, fetch all missing blobs at once instead of one by one.
+                    self.objects
+                        .prefetch(
+                            to_checkout
+                                .entries()
+                                .iter()
+                                .filter(|entry| entry.mode != gix_index::entry::Mode::COMMIT)
+                                .map(|entry| entry.id),
+                        )
+                        .map_err(Error::PromisorFetch)?;
+
+                    let mut opts =
+                        self.checkout_options(gix_worktree::stack::state::attributes::Source::IdMappingThenWorktree)?;
+                    opts.destination_is_initially_empty = false;
+                    opts.overwrite_existing = false;
+                    outcome.checkout = gix_worktree_state::checkout(
+                        &mut to_checkout,
+                        &workdir,
+                        self.objects.clone().into_arc()?,
+                        &gix_features::progress::Discard,
+                        &gix_features::progress::Discard,
+                        should_interrupt,
+                        opts,
+                    )?;
+                    for (idx, entry) in changes.included.iter().zip(to_checkout.entries()) {
+                        index.entries_mut()[*idx].stat = entry.stat;
+                    }
+                }
+                self.collapse_sparse_index(&mut index, &patterns)?;
+                index.write(self.index_write_options(&index)?)?;
+            }
+
+            self.write_sparse_checkout_patterns(&patterns)?;
+            self.enable_sparse_checkout(patterns.is_cone())?;
+            Ok(outcome)
+        }
+
+        fn write_sparse_checkout_patterns(&self, patterns: &Patterns) -> Result<(), Error> {
+            let path = self.sparse_checkout_path();
+            let info_dir = path.parent().expect("file in directory");
+            std::fs::create_dir_all(info_dir).map_err(|source| Error::Io {
+                path: info_dir.to_owned(),
+                source,
+            })?;
+            let mut lock =
+                gix_lock::File::acquire_to_update_resource(&path, gix_lock::acquire::Fail::Immediately, None)?;
+            patterns
+                .write_to(&mut lock)
+                .map_err(|source| Error::Io { path, source })?;
+            lock.commit()?;
+            Ok(())
+        }
+
+        /// Set `core.sparseCheckout` and `core.sparseCheckoutCone` according to `cone` in the worktree configuration file
+        /// and in memory, unless they already have the desired values.
+        ///
+        /// Like `git`, `extensions.worktreeConfig` is enabled first if needed so other worktrees aren't affected, which
+        /// moves `core.worktree` from the local configuration file into the one of the main worktree.
+        fn enable_sparse_checkout(&mut self, cone: bool) -> Result<(), Error> {
+            let cone = if cone { "true" } else { "false" };
+            let config = &self.config.resolved;
+            if config.boolean(Core::SPARSE_CHECKOUT) == Some(Ok(true))
+                && config.boolean(Core::SPARSE_CHECKOUT_CONE) == Some(Ok(cone == "true"))
+            {
+                return Ok(());
+            }
+
+            let worktree_config_path = self.git_dir().join("config.worktree");
+            let mut worktree_config = load_worktree_config(worktree_config_path.clone())?;
+            let has_worktree_config = config.boolean(Extensions::WORKTREE_CONFIG) == Some(Ok(true));
+            if !has_worktree_config {
+                let path = self.common_dir().join("config");
+                let mut file = gix_config::File::from_path_no_includes(path.clone(), gix_config::Source::Local)?;
+                if let Ok(mut value) = file.raw_value_mut(&Core::WORKTREE) {
+                    let worktree = value.get().expect("value exists").into_owned();
+                    value.delete();
+                    if self.git_dir() == self.common_dir() {
+                        worktree_config.set_raw_value(&Core::WORKTREE, worktree.as_bstr())?;
+                    } else {
+                        let path = self.common_dir().join("config.worktree");
+                        let mut main_worktree_config = load_worktree_config(path.clone())?;
+                        main_worktree_config.set_raw_value(&Core::WORKTREE, worktree.as_bstr())?;
+                        write_config(&main_worktree_config, path)?;
+                    }
+                }
+                if file
+                    .integer(&Core::REPOSITORY_FORMAT_VERSION)
+                    .and_then(Result::ok)
+                    .is_none_or(|version| version < 1)
+                {
+                    file.set_raw_value(&Core::REPOSITORY_FORMAT_VERSION, "1")?;
+                }
+                file.set_raw_value(&Extensions::WORKTREE_CONFIG, "true")?;
+                write_config(&file, path)?;
+            }
+            worktree_config.set_raw_value(&Core::SPARSE_CHECKOUT, "true")?;
+            worktree_config.set_raw_value(&Core::SPARSE_CHECKOUT_CONE, cone)?;
+            write_config(&worktree_config, worktree_config_path)?;
+
+            let mut config = self.config_snapshot_mut();
+            if !has_worktree_config {
+                config.set_value(&Extensions::WORKTREE_CONFIG, "true")?;
+            }
+            config.set_value(&Core::SPARSE_CHECKOUT, "true")?;
+            config.set_value(&Core::SPARSE_CHECKOUT_CONE, cone)?;
+            Ok(())
+        }
+    }
+
+    /// Load the worktree configuration file at `path`, or create a new one if it doesn't exist yet.
+    fn load_worktree_config(path: std::path::PathBuf) -> Result<gix_config::File<'static>, Error> {
+        match std::fs::symlink_metadata(&path) {
+            Ok(_) => Ok(gix_config::File::from_path_no_includes(
+                path,
+                gix_config::Source::Worktree,
+            )?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(gix_config::File::new(
+                gix_config::file::Metadata::from(gix_config::Source::Worktree).at(path),
+            )),
+            Err(source) => Err(Error::Io { path, source }),
+        }
+    }
+
+    fn write_config(file: &gix_config::File<'_>, path: std::path::PathBuf) -> Result<(), Error> {
+        let mut lock = gix_lock::File::acquire_to_update_resource(&path, gix_lock::acquire::Fail::Immediately, None)?;
+        file.write_to(&mut lock).map_err(|source| Error::Io { path, source })?;
+        lock.commit()?;
+        Ok(())
+    }
+}
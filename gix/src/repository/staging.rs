@@ -0,0 +1,329 @@
+use std::{io::Read, path::Path, sync::atomic::AtomicBool};
+
+use gix_dir::{
+    entry::{Kind, PathspecMatch, Status},
+    walk::EmissionMode,
+};
+use gix_hash::ObjectId;
+use gix_index::entry::{Flags, Mode, Stage, Stat};
+
+use crate::{
+    bstr::{BStr, BString, ByteSlice},
+    repository::checkout::remove_empty_leading_directories,
+    staging::{add, remove},
+    worktree::stack::state::attributes,
+    Repository,
+};
+
+impl Repository {
+    /// Add the worktree files matching `patterns` to `index` according to `options`, like `git add --all` does,
+    /// and return the paths of the changed entries.
+    ///
+    /// Untracked files are found with a [directory walk](Self::dirwalk()) which respects `.gitignore` files, and
+    /// tracked files are updated with their current content, or removed if they were deleted. The content of each
+    /// file passes through the clean filters of the [filter pipeline](Self::filter_pipeline()) before it's written
+    /// to the object database. Adding a file resolves the conflicts at its path.
+    /// If `patterns` is empty, all files are considered. `should_interrupt` is checked during the directory walk.
+    ///
+    /// Note that `index` isn't written. If it's a sparse index, it's expanded and should be
+    /// [collapsed](Self::collapse_sparse_index()) before writing it.
+    pub fn add_to_index(
+        &self,
+        index: &mut gix_index::State,
+        patterns: impl IntoIterator<Item = impl AsRef<BStr>>,
+        options: add::Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<add::Outcome, add::Error> {
+        let workdir = self.workdir().ok_or_else(|| add::Error::BareRepository {
+            git_dir: self.git_dir().to_owned(),
+        })?;
+        let patterns: Vec<BString> = patterns.into_iter().map(|p| p.as_ref().to_owned()).collect();
+        index.expand_all_sparse_directories(&self.objects)?;
+        let mut outcome = add::Outcome::default();
+
+        let mut untracked = Vec::new();
+        if !options.update {
+            let dirwalk_options = self
+                .dirwalk_options()?
+                .emit_untracked(EmissionMode::Matching)
+                .emit_ignored(Some(if options.force {
+                    EmissionMode::Matching
+                } else {
+                    EmissionMode::CollapseDirectory
+                }));
+            let mut collect = gix_dir::walk::delegate::Collect::default();
+            self.dirwalk(index, &patterns, should_interrupt, dirwalk_options, &mut collect)?;
+            for (entry, _dir_status) in collect.into_entries_by_path() {
+                match entry.status {
+                    Status::Untracked => {}
+                    Status::Ignored(_) if options.force => {}
+                    Status::Ignored(_) => {
+                        if entry.pathspec_match == Some(PathspecMatch::Verbatim) {
+                            outcome.ignored.push(entry.rela_path);
+                        }
+                        continue;
+                    }
+                    Status::Pruned | Status::Tracked => continue,
+                }
+                if matches!(entry.disk_kind, Some(Kind::File | Kind::Symlink | Kind::Repository)) {
+                    untracked.push((entry.rela_path, entry.disk_kind));
+                }
+            }
+        }
+
+        let stat_options = self.stat_options()?;
+        let (mut pipeline, _) = self.filter_pipeline(None)?;
+        let mut tracked = Vec::<BString>::new();
+        if !options.intent_to_add {
+            let mut pathspec = self.pathspec(
+                false, /* empty patterns match prefix */
+                &patterns,
+                true, /* inherit ignore case */
+                index,
+                attributes::Source::WorktreeThenIdMapping,
+            )?;
+            for entry in index.entries() {
+                let rela_path = entry.path(index);
+                if entry.flags.contains(Flags::SKIP_WORKTREE) || tracked.last().is_some_and(|last| last == rela_path) {
+                    continue;
+                }
+                if pathspec.is_included(rela_path, Some(false)) {
+                    tracked.push(rela_path.to_owned());
+                }
+            }
+        }
+
+        let timestamp = index.timestamp();
+        for rela_path in tracked {
+            let range = index
+                .entry_range(rela_path.as_ref())
+                .expect("tracked paths are in the index");
+            let entry = &index.entries()[range.start];
+            let is_conflicted = range.len() > 1 || entry.stage() != Stage::Unconflicted;
+            let is_intended = entry.flags.contains(Flags::INTENT_TO_ADD);
+            let path = workdir.join(gix_path::from_bstr(rela_path.as_bstr()));
+            let stat = stat_of(&path)?;
+            if !is_conflicted
+                && !is_intended
+                && stat.is_some_and(|stat| stat.matches(&entry.stat, stat_options))
+                && !entry.stat.is_racy(timestamp, stat_options)
+            {
+                continue;
+            }
+
+            let (prev_id, prev_mode) = (entry.id, entry.mode);
+            match pipeline.worktree_file_to_object(rela_path.as_ref(), index)? {
+                Some((id, kind, _)) => {
+                    let mode = Mode::from(gix_object::tree::EntryMode::from(kind));
+                    let stat = stat.unwrap_or_default();
+                    if is_conflicted || is_intended || prev_id != id || prev_mode != mode {
+                        index.upsert_entry(stat, id, Flags::empty(), mode, rela_path.as_ref());
+                        outcome.modified.push(rela_path);
+                    } else if let Some(entry) =
+                        index.entry_mut_by_path_and_stage(rela_path.as_ref(), Stage::Unconflicted)
+                    {
+                        entry.stat = stat;
+                    }
+                }
+                // Submodules that aren't checked out are left alone.
+                None if prev_mode.is_submodule() => {}
+                None => {
+                    index.remove_entries_by_path(rela_path.as_ref());
+                    outcome.removed.push(rela_path);
+                }
+            }
+        }
+
+        for (rela_path, disk_kind) in untracked {
+            let path = workdir.join(gix_path::from_bstr(rela_path.as_bstr()));
+            if options.intent_to_add {
+                let mode = match disk_kind {
+                    Some(Kind::File) => {
+                        let metadata = gix_index::fs::Metadata::from_path_no_follow(&path)
+                            .map_err(|source| add::Error::Io { path, source })?;
+                        if metadata.is_executable() {
+                            Mode::FILE_EXECUTABLE
+                        } else {
+                            Mode::FILE
+                        }
+                    }
+                    Some(Kind::Symlink) => Mode::SYMLINK,
+                    _ => continue,
+                };
+                index.upsert_entry(
+                    Stat::default(),
+                    ObjectId::empty_blob(self.object_hash()),
+                    Flags::INTENT_TO_ADD | Flags::EXTENDED,
+                    mode,
+                    rela_path.as_ref(),
+                );
+                outcome.added.push(rela_path);
+                continue;
+            }
+
+            let stat = stat_of(&path)?;
+            if let Some((id, kind, _)) = pipeline.worktree_file_to_object(rela_path.as_ref(), index)? {
+                let mode = Mode::from(gix_object::tree::EntryMode::from(kind));
+                index.upsert_entry(stat.unwrap_or_default(), id, Flags::empty(), mode, rela_path.as_ref());
+                outcome.added.push(rela_path);
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Remove the entries matching `patterns` from `index` and delete their files from the worktree according to `options`,
+    /// like `git rm -r` does, and return the paths of the removed entries.
+    ///
+    /// Unless [forced](remove::Options::force), entries are only removed if their files are unmodified and if they don't
+    /// have changes staged in the index, compared to `HEAD`. With [`cached`](remove::Options::cached), it's enough if the file
+    /// matches either the index or `HEAD`. Directories that become empty are removed, while submodules are only removed
+    /// from the index. Nothing is removed if `patterns` is empty.
+    ///
+    /// Note that `index` isn't written. If it's a sparse index, it's expanded and should be
+    /// [collapsed](Self::collapse_sparse_index()) before writing it.
+    pub fn remove_from_index(
+        &self,
+        index: &mut gix_index::State,
+        patterns: impl IntoIterator<Item = impl AsRef<BStr>>,
+        options: remove::Options,
+    ) -> Result<remove::Outcome, remove::Error> {
+        let workdir = self.workdir().ok_or_else(|| remove::Error::BareRepository {
+            git_dir: self.git_dir().to_owned(),
+        })?;
+        let patterns: Vec<BString> = patterns.into_iter().map(|p| p.as_ref().to_owned()).collect();
+        let mut outcome = remove::Outcome::default();
+        if patterns.is_empty() {
+            return Ok(outcome);
+        }
+        index.expand_all_sparse_directories(&self.objects)?;
+
+        let mut pathspec = self.pathspec(
+            false, /* empty patterns match prefix */
+            &patterns,
+            true, /* inherit ignore case */
+            index,
+            attributes::Source::WorktreeThenIdMapping,
+        )?;
+        let mut paths = Vec::<BString>::new();
+        for entry in index.entries() {
+            let rela_path = entry.path(index);
+            if paths.last().is_some_and(|last| last == rela_path) {
+                continue;
+            }
+            if pathspec.is_included(rela_path, Some(false)) {
+                paths.push(rela_path.to_owned());
+            }
+        }
+
+        if !options.force {
+            let head_index = self.index_from_tree(&self.head_tree_id_or_empty()?)?;
+            let stat_options = self.stat_options()?;
+            let (mut pipeline, _) = self.filter_pipeline(None)?;
+            let mut modified = Vec::new();
+            for rela_path in &paths {
+                // Conflicted entries can always be removed.
+                let Some(entry) = index.entry_by_path_and_stage(rela_path.as_ref(), Stage::Unconflicted) else {
+                    continue;
+                };
+                if entry.mode.is_submodule() {
+                    continue;
+                }
+                let is_staged = head_index
+                    .entry_by_path(rela_path.as_ref())
+                    .is_none_or(|head| head.id != entry.id || head.mode != entry.mode);
+                if is_staged && !options.cached {
+                    modified.push(rela_path.clone());
+                    continue;
+                }
+
+                let path = workdir.join(gix_path::from_bstr(rela_path.as_bstr()));
+                let metadata = match gix_index::fs::Metadata::from_path_no_follow(&path) {
+                    Ok(metadata) => metadata,
+                    Err(err) if gix_fs::io_err::is_not_found(err.kind(), err.raw_os_error()) => continue,
+                    Err(source) => return Err(remove::Error::Io { path, source }),
+                };
+                if Stat::from_fs(&metadata).is_ok_and(|stat| stat.matches(&entry.stat, stat_options))
+                    && !entry.stat.is_racy(index.timestamp(), stat_options)
+                {
+                    continue;
+                }
+                let is_modified = if metadata.is_symlink() {
+                    let target = std::fs::read_link(&path).map_err(|source| remove::Error::Io {
+                        path: path.clone(),
+                        source,
+                    })?;
+                    blob_id(self, gix_path::into_bstr(target).as_ref())? != entry.id
+                } else if metadata.is_file() {
+                    let file = std::fs::File::open(&path).map_err(|source| remove::Error::Io {
+                        path: path.clone(),
+                        source,
+                    })?;
+                    let mut buf = Vec::new();
+                    let read = match pipeline.convert_to_git(
+                        file,
+                        gix_path::from_bstr(rela_path.as_bstr()).as_ref(),
+                        index,
+                    )? {
+                        gix_filter::pipeline::convert::ToGitOutcome::Unchanged(mut file) => file.read_to_end(&mut buf),
+                        gix_filter::pipeline::convert::ToGitOutcome::Process(mut read) => read.read_to_end(&mut buf),
+                        gix_filter::pipeline::convert::ToGitOutcome::Buffer(data) => {
+                            buf.extend_from_slice(data);
+                            Ok(data.len())
+                        }
+                    };
+                    read.map_err(|source| remove::Error::Io { path, source })?;
+                    blob_id(self, &buf)? != entry.id
+                } else {
+                    true
+                };
+                // With `cached`, the file only has to match either the index or `HEAD`.
+                if is_modified && (!options.cached || is_staged) {
+                    modified.push(rela_path.clone());
+                }
+            }
+            if !modified.is_empty() {
+                return Err(remove::Error::LocalModifications { paths: modified });
+            }
+        }
+
+        for rela_path in paths {
+            let is_submodule = index
+                .entry_by_path(rela_path.as_ref())
+                .is_some_and(|entry| entry.mode.is_submodule());
+            index.remove_entries_by_path(rela_path.as_ref());
+            if !options.cached && !is_submodule {
+                let path = workdir.join(gix_path::from_bstr(rela_path.as_bstr()));
+                match std::fs::remove_file(&path) {
+                    Ok(()) => {
+                        outcome.files_removed += 1;
+                        remove_empty_leading_directories(&path, workdir);
+                    }
+                    Err(err) if gix_fs::io_err::is_not_found(err.kind(), err.raw_os_error()) => {}
+                    Err(source) => return Err(remove::Error::Io { path, source }),
+                }
+            }
+            outcome.removed.push(rela_path);
+        }
+        Ok(outcome)
+    }
+}
+
+/// Return the stat information of the file at `path`, or `None` if it doesn't exist.
+fn stat_of(path: &Path) -> Result<Option<Stat>, add::Error> {
+    match gix_index::fs::Metadata::from_path_no_follow(path) {
+        Ok(metadata) => Ok(Some(Stat::from_fs(&metadata).unwrap_or_default())),
+        Err(err) if gix_fs::io_err::is_not_found(err.kind(), err.raw_os_error()) => Ok(None),
+        Err(source) => Err(add::Error::Io {
+            path: path.to_owned(),
+            source,
+        }),
+    }
+}
+
+fn blob_id(repo: &Repository, data: &[u8]) -> Result<ObjectId, remove::Error> {
+    Ok(gix_object::compute_hash(
+        repo.object_hash(),
+        gix_object::Kind::Blob,
+        data,
+    )?)
+}
@@ -0,0 +1,453 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_hash::ObjectId;
+use gix_index::entry::{Flags, Stage};
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    FullName, Target,
+};
+use gix_status::index_as_worktree::{self, EntryStatus};
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    ext::ObjectIdExt,
+    merge::tree::{apply_index_entries::RemovalMode, TreatAsUnresolved},
+    repository::checkout::{removal_stack, remove_file},
+    stash,
+    stash::{apply, create, list, reset, save, store, REFERENCE},
+    status::index_worktree,
+    Id, Repository,
+};
+
+/// A stash commit along with what's needed to remove the stashed changes from the index and the worktree.
+struct Created {
+    id: ObjectId,
+    message: BString,
+    head_tree: ObjectId,
+    /// The index with all stashed changes of the worktree applied to it, which reflects the worktree.
+    worktree_index: gix_index::State,
+    untracked: Vec<BString>,
+}
+
+/// Stashes
+impl Repository {
+    /// Create a stash commit of the changes to the index and the worktree compared to `HEAD` with `options`,
+    /// like `git stash create`, and return its id, or `None` if there is nothing to stash.
+    ///
+    /// Neither the index nor the worktree are changed, and the stash isn't [stored](Self::stash_store()).
+    /// Changes to submodules aren't stashed.
+    pub fn stash_create(&self, options: create::Options) -> Result<Option<Id<'_>>, create::Error> {
+        Ok(self
+            .stash_create_inner(&options)?
+            .map(|created| created.id.attach(self)))
+    }
+
+    /// Make the stash commit `id` the most recent stash, with `message` in the reflog of [`REFERENCE`],
+    /// like `git stash store`.
+    pub fn stash_store(&self, id: impl Into<ObjectId>, message: impl Into<BString>) -> Result<(), store::Error> {
+        let id = id.into();
+        if self.find_commit(id)?.parent_ids().count() < 2 {
+            return Err(store::Error::NotAStash { id });
+        }
+        self.edit_reference(RefEdit {
+            change: Change::Update {
+                log: LogChange {
+                    mode: RefLog::AndReference,
+                    force_create_reflog: true,
+                    message: message.into(),
+                },
+                expected: PreviousValue::Any,
+                new: Target::Object(id),
+            },
+            name: stash_reference_name(),
+            deref: false,
+        })?;
+        Ok(())
+    }
+
+    /// Stash the changes to the index and the worktree with `options` and reset both to `HEAD`, like `git stash push`,
+    /// checking `should_interrupt` to stop the checkout. Return the id of the stash, or `None` if there is nothing to stash.
+    ///
+    /// Stashed untracked files are removed from the worktree.
+    pub fn stash_save(
+        &self,
+        options: create::Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Option<Id<'_>>, save::Error> {
+        let Some(created) = self.stash_create_inner(&options)? else {
+            return Ok(None);
+        };
+        self.stash_store(created.id, created.message)?;
+
+        let patterns = self.sparse_checkout_patterns().map_err(reset::Error::from)?;
+        let mut index = self.index_for_tree::<reset::Error>(&created.head_tree, patterns.as_ref())?;
+        self.update_worktree(&created.worktree_index, &mut index, should_interrupt)
+            .map_err(reset::Error::from)?;
+        self.write_index_with_patterns::<reset::Error>(index, patterns.as_ref())?;
+
+        let workdir = self.workdir().expect("stashes are only created with a worktree");
+        let opts = self
+            .checkout_options(gix_worktree::stack::state::attributes::Source::IdMappingThenWorktree)
+            .map_err(reset::Error::from)?;
+        let mut stack = removal_stack(workdir, &opts, &created.worktree_index);
+        for rela_path in &created.untracked {
+            remove_file(
+                &mut stack,
+                workdir,
+                rela_path.as_bstr(),
+                gix_index::entry::Mode::FILE,
+                &self.objects,
+            )
+            .map_err(|(path, source)| reset::Error::Io { path, source })?;
+        }
+        Ok(Some(created.id.attach(self)))
+    }
+
+    /// Return all stashes, from the most recent one at index `0` to the oldest one, like `git stash list`.
+    pub fn stash_list(&self) -> Result<Vec<stash::Entry>, list::Error> {
+        let Some(reference) = self.try_find_reference(REFERENCE)? else {
+            return Ok(Vec::new());
+        };
+        let mut platform = reference.log_iter();
+        let Some(lines) = platform.rev()? else {
+            return Ok(Vec::new());
+        };
+        lines
+            .map(|line| {
+                let line = line?;
+                Ok(stash::Entry {
+                    id: line.new_oid,
+                    message: line.message,
+                    time: line.signature.time,
+                })
+            })
+            .collect()
+    }
+
+    /// Apply the stash at `index`, with `0` being the most recent one, to the worktree with `options`,
+    /// like `git stash apply`, and check `should_interrupt` to stop the checkout.
+    ///
+    /// The stashed changes are merged into the current worktree, and the operation fails without changes if local
+    /// modifications or untracked files would be overwritten.
+    /// Unless the index is restored as well, it is left as is, except for files that were added by the stash.
+    /// If there are conflicts, their stages are written to the index instead.
+    pub fn stash_apply(
+        &self,
+        index: usize,
+        options: apply::Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<apply::Outcome, apply::Error> {
+        use apply::Error;
+
+        let workdir = self.workdir().ok_or_else(|| Error::BareRepository {
+            git_dir: self.git_dir().to_owned(),
+        })?;
+        let id = self
+            .stash_list()?
+            .into_iter()
+            .nth(index)
+            .ok_or(Error::NoSuchStash { index })?
+            .id;
+        let stash = self.find_commit(id)?;
+        let parents: Vec<_> = stash.parent_ids().map(Id::detach).collect();
+        if parents.len() < 2 {
+            return Err(Error::NotAStash { id });
+        }
+        let tree_of = |id: ObjectId| -> Result<ObjectId, Error> { Ok(self.find_commit(id)?.tree_id()?.detach()) };
+        let base_tree = tree_of(parents[0])?;
+        let stashed_index_tree = tree_of(parents[1])?;
+        let untracked_tree = parents.get(2).copied().map(tree_of).transpose()?;
+        let stashed_tree = stash.tree_id()?.detach();
+
+        let mut current = self.fresh_index()?;
+        current.expand_all_sparse_directories(&self.objects)?;
+        if current
+            .entries()
+            .iter()
+            .any(|entry| entry.stage() != Stage::Unconflicted)
+        {
+            return Err(Error::UnmergedIndex);
+        }
+        let current_tree = self.write_tree_from_index(&mut current)?.detach();
+
+        let labels = gix_merge::blob::builtin_driver::text::Labels {
+            ancestor: Some("Stash base".into()),
+            current: Some("Updated upstream".into()),
+            other: Some("Stashed changes".into()),
+        };
+        let merge_options = self.tree_merge_options()?;
+        let how = TreatAsUnresolved::git();
+        let restored_index_tree = if !options.index {
+            None
+        } else if stashed_index_tree == base_tree {
+            Some(current_tree)
+        } else {
+            let mut outcome = self.merge_trees(
+                base_tree,
+                current_tree,
+                stashed_index_tree,
+                labels,
+                merge_options.clone(),
+            )?;
+            if outcome.has_unresolved_conflicts(how) {
+                return Err(Error::IndexConflicts);
+            }
+            Some(outcome.tree.write()?.detach())
+        };
+
+        let patterns = self.sparse_checkout_patterns().map_err(reset::Error::from)?;
+        let mut untracked_index = untracked_tree
+            .map(|tree| self.index_for_tree::<reset::Error>(&tree, None))
+            .transpose()?;
+        if let Some(untracked_index) = &untracked_index {
+            for entry in untracked_index.entries() {
+                let path = workdir.join(gix_path::from_bstr(entry.path(untracked_index)));
+                if path.symlink_metadata().is_ok() {
+                    return Err(Error::UntrackedFileExists { path });
+                }
+            }
+        }
+
+        let mut outcome = self.merge_trees(base_tree, current_tree, stashed_tree, labels, merge_options)?;
+        let merged_tree = outcome.tree.write()?.detach();
+        let mut merged_index = self.index_for_tree::<reset::Error>(&merged_tree, patterns.as_ref())?;
+
+        let overwritten = self.local_changes_overwritten_by::<Error>(&current, &merged_index)?;
+        if !overwritten.is_empty() {
+            return Err(Error::WouldOverwriteLocalChanges { paths: overwritten });
+        }
+
+        if let Some(untracked_index) = &mut untracked_index {
+            self.update_worktree(
+                &gix_index::State::new(self.object_hash()),
+                untracked_index,
+                should_interrupt,
+            )
+            .map_err(reset::Error::from)?;
+        }
+        let worktree = self
+            .update_worktree(&current, &mut merged_index, should_interrupt)
+            .map_err(reset::Error::from)?;
+
+        let conflicts: Vec<_> = outcome
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.is_unresolved(how))
+            .cloned()
+            .collect();
+        let index = if !conflicts.is_empty() {
+            outcome.index_changed_after_applying_conflicts(&mut merged_index, how, RemovalMode::Prune)?;
+            merged_index
+        } else if let Some(tree) = restored_index_tree {
+            let mut index = self.index_for_tree::<reset::Error>(&tree, patterns.as_ref())?;
+            for (entry, rela_path) in index.entries_mut_with_paths() {
+                if let Some(checked_out) = merged_index.entry_by_path(rela_path) {
+                    if checked_out.id == entry.id && checked_out.mode == entry.mode {
+                        entry.stat = checked_out.stat;
+                    }
+                }
+            }
+            index
+        } else {
+            // Like `git`, keep the index, but add the files that are new so they aren't untracked.
+            let new_entries: Vec<_> = merged_index
+                .entries()
+                .iter()
+                .filter(|entry| current.entry_by_path(entry.path(&merged_index)).is_none())
+                .collect();
+            for entry in new_entries {
+                current.dangerously_push_entry(
+                    entry.stat,
+                    entry.id,
+                    entry.flags,
+                    entry.mode,
+                    entry.path(&merged_index),
+                );
+            }
+            current.sort_entries();
+            current
+        };
+        self.write_index_with_patterns::<reset::Error>(index, patterns.as_ref())?;
+
+        Ok(apply::Outcome {
+            id,
+            conflicts,
+            worktree,
+        })
+    }
+
+    /// Like [`stash_apply()`](Self::stash_apply()), but [drop](Self::stash_drop()) the stash at `index` afterwards
+    /// if it applied without conflicts, like `git stash pop`.
+    pub fn stash_pop(
+        &self,
+        index: usize,
+        options: apply::Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<apply::Outcome, stash::pop::Error> {
+        let outcome = self.stash_apply(index, options, should_interrupt)?;
+        if outcome.conflicts.is_empty() {
+            self.stash_drop(index)?;
+        }
+        Ok(outcome)
+    }
+
+    /// Remove the stash at `index`, with `0` being the most recent one, like `git stash drop`, and return its id.
+    ///
+    /// [`REFERENCE`] is updated to point to the most recent remaining stash, or removed if there is none left.
+    pub fn stash_drop(&self, index: usize) -> Result<ObjectId, stash::drop::Error> {
+        let name = stash_reference_name();
+        let (lock_fail_mode, _) = self.config.lock_timeout()?;
+        let mut dropped = None;
+        self.refs.reflog_retain(name.as_ref(), lock_fail_mode, |idx, line| {
+            if idx == index {
+                dropped = Some(line.new_oid);
+            }
+            idx != index
+        })?;
+        let dropped = dropped.ok_or(stash::drop::Error::NoSuchStash { index })?;
+
+        if index == 0 {
+            let change = match self.stash_list()?.first() {
+                Some(newest) => Change::Update {
+                    log: LogChange::default(),
+                    expected: PreviousValue::Any,
+                    new: Target::Object(newest.id),
+                },
+                None => Change::Delete {
+                    expected: PreviousValue::Any,
+                    log: RefLog::AndReference,
+                },
+            };
+            // The reflog is the list of stashes, so it must not record this update.
+            let mut repo = self.clone();
+            repo.refs.write_reflog = gix_ref::store::WriteReflog::Disable;
+            repo.edit_reference(RefEdit {
+                change,
+                name,
+                deref: false,
+            })?;
+        }
+        Ok(dropped)
+    }
+
+    fn stash_create_inner(&self, options: &create::Options) -> Result<Option<Created>, create::Error> {
+        use create::Error;
+
+        if self.workdir().is_none() {
+            return Err(Error::BareRepository {
+                git_dir: self.git_dir().to_owned(),
+            });
+        }
+        let head = self.head_commit()?;
+        let head_tree = head.tree_id()?.detach();
+        let mut index = self.fresh_index()?;
+        index.expand_all_sparse_directories(&self.objects)?;
+        if index.entries().iter().any(|entry| entry.stage() != Stage::Unconflicted) {
+            return Err(Error::UnmergedIndex);
+        }
+        let index_tree = self.write_tree_from_index(&mut index)?.detach();
+
+        let (mut pipeline, _) = self.filter_pipeline(Some(head_tree))?;
+        let mut worktree_index = gix_index::State::clone(&index);
+        let mut untracked = Vec::new();
+        for item in self.index_worktree_changes::<Error>(&index, options.include_untracked)? {
+            match item? {
+                index_worktree::Item::Modification {
+                    entry_index,
+                    rela_path,
+                    status,
+                    ..
+                } => match status {
+                    EntryStatus::Conflict { .. } => return Err(Error::UnmergedIndex),
+                    EntryStatus::Change(index_as_worktree::Change::Removed) => {
+                        worktree_index.invalidate_tree_at(rela_path.as_ref());
+                        worktree_index.entries_mut()[entry_index].flags.insert(Flags::REMOVE);
+                    }
+                    EntryStatus::Change(
+                        index_as_worktree::Change::Modification { .. } | index_as_worktree::Change::Type { .. },
+                    )
+                    | EntryStatus::IntentToAdd => {
+                        worktree_index.invalidate_tree_at(rela_path.as_ref());
+                        let entry = &mut worktree_index.entries_mut()[entry_index];
+                        match pipeline.worktree_file_to_object(rela_path.as_ref(), &index)? {
+                            Some((id, kind, _)) => {
+                                entry.id = id;
+                                entry.mode = kind.into();
+                                entry.flags.remove(Flags::INTENT_TO_ADD);
+                            }
+                            None => entry.flags.insert(Flags::REMOVE),
+                        }
+                    }
+                    EntryStatus::Change(index_as_worktree::Change::SubmoduleModification(_))
+                    | EntryStatus::NeedsUpdate(_) => {}
+                },
+                index_worktree::Item::DirectoryContents { entry, .. } => {
+                    if entry.status == gix_dir::entry::Status::Untracked
+                        && matches!(
+                            entry.disk_kind,
+                            Some(gix_dir::entry::Kind::File | gix_dir::entry::Kind::Symlink)
+                        )
+                    {
+                        untracked.push(entry.rela_path);
+                    }
+                }
+                index_worktree::Item::Rewrite { .. } => unreachable!("rewrite tracking is disabled"),
+            }
+        }
+        worktree_index.remove_entries(|_, _, entry| entry.flags.contains(Flags::REMOVE));
+        let worktree_tree = self.write_tree_from_index(&mut worktree_index)?.detach();
+        if index_tree == head_tree && worktree_tree == head_tree && untracked.is_empty() {
+            return Ok(None);
+        }
+
+        let branch = match self.head_name()? {
+            Some(name) => name.shorten().to_str_lossy().into_owned(),
+            None => "(no branch)".into(),
+        };
+        let subject = format!(
+            "{branch}: {} {}",
+            head.id().shorten_or_id(),
+            head.message()?.summary().to_str_lossy()
+        );
+        let index_commit = self
+            .new_commit(format!("index on {subject}\n"), index_tree, Some(head.id))?
+            .id;
+        let untracked_commit = if untracked.is_empty() {
+            None
+        } else {
+            let mut editor = self.empty_tree().edit()?;
+            for rela_path in &untracked {
+                if let Some((id, kind, _)) = pipeline.worktree_file_to_object(rela_path.as_ref(), &index)? {
+                    editor.upsert(rela_path.as_bstr(), kind, id)?;
+                }
+            }
+            let tree = editor.write()?;
+            Some(
+                self.new_commit(format!("untracked files on {subject}\n"), tree, None::<ObjectId>)?
+                    .id,
+            )
+        };
+        let message = match &options.message {
+            Some(message) => format!("On {branch}: {message}"),
+            None => format!("WIP on {subject}"),
+        };
+        let id = self
+            .new_commit(
+                &message,
+                worktree_tree,
+                [head.id, index_commit].into_iter().chain(untracked_commit),
+            )?
+            .id;
+        Ok(Some(Created {
+            id,
+            message: message.into(),
+            head_tree,
+            worktree_index,
+            untracked,
+        }))
+    }
+}
+
+fn stash_reference_name() -> FullName {
+    REFERENCE.try_into().expect("valid reference name")
+}
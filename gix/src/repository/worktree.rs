@@ -1,5 +1,11 @@
-use crate::bstr::BStr;
-use crate::{worktree, Worktree};
+use std::path::Path;
+
+use crate::{
+    bstr::{BStr, BString},
+    worktree,
+    worktree::{proxy::write_gitdir_file, prune, repair},
+    Worktree,
+};
 
 /// Interact with individual worktrees and their information.
 impl crate::Repository {
@@ -37,6 +43,401 @@ impl crate::Repository {
         )
     }
 
+    /// Create a new linked worktree at `path`, check out `commitish` into it and return it, like `git worktree add <path> [<commitish>]`.
+    /// Use `should_interrupt` to stop the checkout.
+    ///
+    /// Without `commitish`, a branch named after the last component of `path` is checked out, and created at `HEAD` if it doesn't exist yet.
+    /// If `commitish` names a local branch, that branch is checked out, otherwise `HEAD` of the new worktree is detached at the commit
+    /// it names. Branches can only be checked out in one worktree at a time, unless forced in `options`, which also allow to create
+    /// a new branch or to detach `HEAD`.
+    ///
+    /// `path` must not exist, or be an empty directory. The administrative directory of the new worktree is created in the
+    /// `worktrees` directory of the [common directory](Self::common_dir()), named after the last component of `path`.
+    /// Like `git`, the [`post-checkout` hook](Self::hook()) is run after the checkout if [enabled](crate::open::Options::run_hooks()),
+    /// and the worktree is kept even if it fails with [`PostCheckoutHook`](worktree::add::Error::PostCheckoutHook).
+    /// On any other error, everything that was set up is removed, and a branch that was created or reset is deleted or restored.
+    #[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+    pub fn worktree_add(
+        &self,
+        path: impl AsRef<Path>,
+        commitish: Option<&BStr>,
+        options: worktree::add::Options,
+        should_interrupt: &std::sync::atomic::AtomicBool,
+    ) -> Result<worktree::Proxy<'_>, worktree::add::Error> {
+        use crate::bstr::ByteSlice;
+        use gix_ref::{transaction::PreviousValue, FullName};
+        use worktree::add::Error;
+
+        let path = path.as_ref();
+        let io_err = |path: &Path| {
+            let path = path.to_owned();
+            move |source| Error::Io { path, source }
+        };
+        let is_empty_dir = match std::fs::read_dir(path) {
+            Ok(mut entries) => entries.next().is_none(),
+            Err(_) => false,
+        };
+        if !is_empty_dir && path.exists() {
+            return Err(Error::PathExists { path: path.to_owned() });
+        }
+        let name = path
+            .file_name()
+            .map(|name| gix_path::into_bstr(Path::new(name)).into_owned())
+            .ok_or_else(|| Error::PathExists { path: path.to_owned() })?;
+        let name = name.as_bstr();
+
+        let branch_name = |name: &BStr| -> Result<FullName, Error> {
+            let mut full_name = BString::from("refs/heads/");
+            full_name.extend_from_slice(name);
+            Ok(FullName::try_from(full_name)?)
+        };
+        let mut previous_branch_target = None;
+        let (commit_id, branch, create_branch) = match (&options.new_branch, commitish) {
+            (Some(new_branch), _) => {
+                let branch = branch_name(new_branch.as_ref())?;
+                let commit = match commitish {
+                    Some(spec) => self.rev_parse_single(spec)?.object()?.peel_to_commit()?,
+                    None => self.head_commit()?,
+                };
+                let existing = self.try_find_reference(branch.as_ref())?;
+                if !options.force && existing.is_some() {
+                    return Err(Error::BranchExists {
+                        name: new_branch.clone(),
+                    });
+                }
+                previous_branch_target = existing.map(|reference| reference.inner.target);
+                (commit.id, Some(branch), true)
+            }
+            (None, None) => {
+                let commit = self.head_commit()?;
+                match (!options.detach).then(|| branch_name(name)).transpose()? {
+                    Some(branch) => {
+                        let (commit_id, create_branch) = match self.try_find_reference(branch.as_ref())? {
+                            Some(mut reference) => (reference.peel_to_commit()?.id, false),
+                            None => (commit.id, true),
+                        };
+                        (commit_id, Some(branch), create_branch)
+                    }
+                    None => (commit.id, None, false),
+                }
+            }
+            (None, Some(spec)) => {
+                let branch = if options.detach {
+                    None
+                } else {
+                    branch_name(spec)
+                        .ok()
+                        .map(|branch| self.try_find_reference(branch.as_ref()).map(|r| r.map(|_| branch)))
+                        .transpose()?
+                        .flatten()
+                };
+                let commit = self.rev_parse_single(spec)?.object()?.peel_to_commit()?;
+                (commit.id, branch, false)
+            }
+        };
+        if let Some(branch) = branch.as_ref().filter(|_| !options.force) {
+            if let Some(path) = self.worktree_with_branch_checked_out(branch.as_bstr())? {
+                return Err(Error::BranchCheckedOut {
+                    name: branch.shorten().to_owned(),
+                    path,
+                });
+            }
+        }
+
+        let worktrees_dir = self.common_dir().join("worktrees");
+        std::fs::create_dir_all(&worktrees_dir).map_err(io_err(&worktrees_dir))?;
+        let id = sanitize_worktree_id(name);
+        let mut git_dir = worktrees_dir.join(gix_path::from_bstr(id.as_bstr()));
+        let mut counter = 0;
+        loop {
+            match std::fs::create_dir(&git_dir) {
+                Ok(()) => break,
+                Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => {
+                    counter += 1;
+                    git_dir = worktrees_dir.join(format!("{id}{counter}"));
+                }
+                Err(source) => return Err(Error::Io { path: git_dir, source }),
+            }
+        }
+
+        let mut changed_branch = None;
+        let res = (|| -> Result<(worktree::Proxy<'_>, Option<crate::Repository>), Error> {
+            std::fs::create_dir_all(path).map_err(io_err(path))?;
+            let base = gix_path::realpath(path).map_err(|err| Error::Io {
+                path: path.to_owned(),
+                source: std::io::Error::other(err),
+            })?;
+            let git_dir = gix_path::realpath(&git_dir).map_err(|err| Error::Io {
+                path: git_dir.clone(),
+                source: std::io::Error::other(err),
+            })?;
+            write_gitdir_file(&git_dir, &base).map_err(io_err(&git_dir))?;
+            std::fs::write(git_dir.join("commondir"), "../..\n").map_err(io_err(&git_dir))?;
+            if let Some(reason) = &options.lock_reason {
+                let mut content = reason.clone();
+                content.push(b'\n');
+                std::fs::write(git_dir.join("locked"), content).map_err(io_err(&git_dir))?;
+            }
+            if let Some(branch) = branch.as_ref().filter(|_| create_branch) {
+                let constraint = if options.force {
+                    PreviousValue::Any
+                } else {
+                    PreviousValue::MustNotExist
+                };
+                let message = format!(
+                    "branch: Created from {}",
+                    commitish.map_or_else(|| "HEAD".into(), ToString::to_string)
+                );
+                self.reference(branch.clone(), commit_id, constraint, message)?;
+                changed_branch = Some(branch);
+            }
+            let head = match &branch {
+                Some(branch) => format!("ref: {}\n", branch.as_bstr()),
+                None => format!("{commit_id}\n"),
+            };
+            std::fs::write(git_dir.join("HEAD"), head).map_err(io_err(&git_dir))?;
+            let mut dot_git = BString::from("gitdir: ");
+            dot_git.extend_from_slice(&gix_path::into_bstr(git_dir.as_path()));
+            dot_git.push(b'\n');
+            let dot_git_path = base.join(gix_discover::DOT_GIT_DIR);
+            std::fs::write(&dot_git_path, dot_git).map_err(io_err(&dot_git_path))?;
+
+            let proxy = worktree::Proxy::new(self, git_dir);
+            let mut checked_out = None;
+            if !options.no_checkout {
+                let repo = proxy.clone().into_repo()?;
+                let tree_id = repo.find_object(commit_id)?.peel_to_commit()?.tree_id()?;
+                let mut index = repo.index_from_tree(&tree_id)?;
+                repo.update_worktree(&gix_index::State::new(repo.object_hash()), &mut index, should_interrupt)?;
+                index.write(repo.index_write_options(&index)?)?;
+                checked_out = Some(repo);
+            }
+            Ok((proxy, checked_out))
+        })();
+        let (proxy, checked_out) = match res {
+            Ok(res) => res,
+            Err(err) => {
+                self.undo_worktree_add(
+                    path,
+                    &git_dir,
+                    is_empty_dir,
+                    changed_branch.map(|branch| (branch.clone(), commit_id, previous_branch_target)),
+                );
+                return Err(err);
+            }
+        };
+
+        // Like `git`, the worktree is kept even if the hook fails.
+        if let Some(repo) = checked_out {
+            if let Some(hook) = repo.hook_to_run("post-checkout")? {
+                hook.args([repo.object_hash().null().to_string(), commit_id.to_string(), "1".into()])
+                    .run()
+                    .map_err(|source| Error::PostCheckoutHook {
+                        path: path.to_owned(),
+                        source,
+                    })?;
+            }
+        }
+        Ok(proxy)
+    }
+
+    /// Remove what was set up for a worktree at `path` with the administrative directory `git_dir` after adding it failed,
+    /// keeping `path` if it `was_empty_dir`. If set, `branch` was changed to point to the checked out commit, and is either
+    /// deleted if it had no `previous_target`, or reset to it.
+    #[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+    fn undo_worktree_add(
+        &self,
+        path: &Path,
+        git_dir: &Path,
+        was_empty_dir: bool,
+        branch: Option<(gix_ref::FullName, gix_hash::ObjectId, Option<gix_ref::Target>)>,
+    ) {
+        use gix_ref::transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog};
+
+        if let Some((name, commit_id, previous_target)) = branch {
+            // Like `git`, don't leave a branch behind that was only created for the new worktree,
+            // and don't leave one pointing to a commit that was never checked out.
+            let expected = PreviousValue::MustExistAndMatch(commit_id.into());
+            let change = match previous_target {
+                Some(previous) => Change::Update {
+                    log: LogChange {
+                        mode: RefLog::AndReference,
+                        force_create_reflog: false,
+                        message: "branch: Reset after failing to add a worktree".into(),
+                    },
+                    expected,
+                    new: previous,
+                },
+                None => Change::Delete {
+                    expected,
+                    log: RefLog::AndReference,
+                },
+            };
+            self.edit_reference(RefEdit {
+                change,
+                name,
+                deref: false,
+            })
+            .ok();
+        }
+        std::fs::remove_dir_all(git_dir).ok();
+        if was_empty_dir {
+            for entry in std::fs::read_dir(path).into_iter().flatten().flatten() {
+                std::fs::remove_dir_all(entry.path())
+                    .or_else(|_| std::fs::remove_file(entry.path()))
+                    .ok();
+            }
+        } else {
+            std::fs::remove_dir_all(path).ok();
+        }
+    }
+
+    /// Remove the administrative directories of worktrees that don't exist anymore, like `git worktree prune`, and return
+    /// what was pruned along with the reason, configured by `options`.
+    ///
+    /// Locked worktrees are never pruned, see [`worktree::Proxy::prunable()`] for details.
+    pub fn worktrees_prune(&self, options: prune::Options) -> Result<Vec<prune::Pruned>, prune::Error> {
+        let worktrees_dir = self.common_dir().join("worktrees");
+        let io_err = |path: &Path| {
+            let path = path.to_owned();
+            move |source| prune::Error::Io { path, source }
+        };
+        let iter = match std::fs::read_dir(&worktrees_dir) {
+            Ok(iter) => iter,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => {
+                return Err(prune::Error::Io {
+                    path: worktrees_dir,
+                    source,
+                })
+            }
+        };
+        let mut pruned = Vec::new();
+        for entry in iter {
+            let git_dir = entry.map_err(io_err(&worktrees_dir))?.path();
+            let Some(reason) = worktree::proxy::prune_reason(&git_dir, options.expire) else {
+                continue;
+            };
+            if !options.dry_run {
+                if git_dir.is_dir() {
+                    std::fs::remove_dir_all(&git_dir)
+                } else {
+                    std::fs::remove_file(&git_dir)
+                }
+                .map_err(io_err(&git_dir))?;
+            }
+            pruned.push(prune::Pruned {
+                id: gix_path::into_bstr(Path::new(
+                    git_dir.file_name().expect("entries of a directory have a name"),
+                ))
+                .into_owned(),
+                reason,
+            });
+        }
+        if !options.dry_run {
+            // Like `git`, remove the `worktrees` directory if it's empty now, ignoring failure.
+            std::fs::remove_dir(&worktrees_dir).ok();
+        }
+        pruned.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(pruned)
+    }
+
+    /// Repair the links between linked worktrees and their administrative directories after they were moved manually,
+    /// like `git worktree repair [<path>...]`, and return what was repaired.
+    ///
+    /// The `.git` files of all worktrees that still exist at their known location are rewritten if they don't point
+    /// to their administrative directory anymore, which happens if the repository was moved.
+    /// Further, the worktrees that were moved to the given `paths` are made known to their administrative directory again.
+    pub fn worktrees_repair(
+        &self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<Vec<repair::Repaired>, repair::Error> {
+        use repair::{Error, Kind, Repaired};
+
+        let io_err = |path: &Path| {
+            let path = path.to_owned();
+            move |source| Error::Io { path, source }
+        };
+        let mut repaired = Vec::new();
+        let proxies = self.worktrees().map_err(io_err(&self.common_dir().join("worktrees")))?;
+        for proxy in &proxies {
+            let Some(base) = proxy.base().ok().filter(|base| base.is_dir()) else {
+                continue;
+            };
+            let git_dir = gix_path::realpath(proxy.git_dir()).map_err(|err| Error::Io {
+                path: proxy.git_dir().to_owned(),
+                source: std::io::Error::other(err),
+            })?;
+            let dot_git = base.join(gix_discover::DOT_GIT_DIR);
+            if points_to(&dot_git, &git_dir) {
+                continue;
+            }
+            write_dot_git_file(&dot_git, &git_dir).map_err(io_err(&dot_git))?;
+            repaired.push(Repaired {
+                path: base,
+                kind: Kind::DotGitFile,
+            });
+        }
+
+        for path in paths {
+            let path = path.as_ref();
+            let base = gix_path::realpath(path).map_err(|err| Error::Io {
+                path: path.to_owned(),
+                source: std::io::Error::other(err),
+            })?;
+            let dot_git = base.join(gix_discover::DOT_GIT_DIR);
+            let not_linked = || Error::NotALinkedWorktree { path: path.to_owned() };
+            let previous_git_dir = gix_discover::path::from_gitdir_file(&dot_git).map_err(|_| not_linked())?;
+            let id = previous_git_dir.file_name().ok_or_else(not_linked)?;
+            let proxy = proxies
+                .iter()
+                .find(|proxy| proxy.git_dir().file_name() == Some(id))
+                .ok_or_else(not_linked)?;
+            let git_dir = gix_path::realpath(proxy.git_dir()).map_err(|err| Error::Io {
+                path: proxy.git_dir().to_owned(),
+                source: std::io::Error::other(err),
+            })?;
+            if !points_to(&dot_git, &git_dir) {
+                write_dot_git_file(&dot_git, &git_dir).map_err(io_err(&dot_git))?;
+                repaired.push(Repaired {
+                    path: base.clone(),
+                    kind: Kind::DotGitFile,
+                });
+            }
+            if proxy.base().ok().as_deref() != Some(base.as_path()) {
+                write_gitdir_file(&git_dir, &base).map_err(io_err(&git_dir))?;
+                repaired.push(Repaired {
+                    path: base,
+                    kind: Kind::GitDirFile,
+                });
+            }
+        }
+        Ok(repaired)
+    }
+
+    /// Return the location of the worktree that has the branch with the full reference `name` checked out, if any.
+    #[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+    fn worktree_with_branch_checked_out(&self, name: &BStr) -> Result<Option<std::path::PathBuf>, crate::open::Error> {
+        use crate::bstr::ByteSlice;
+
+        let has_head = |git_dir: &Path| {
+            std::fs::read(git_dir.join("HEAD")).is_ok_and(|head| {
+                head.trim()
+                    .strip_prefix(b"ref:")
+                    .is_some_and(|target| target.trim_start() == name)
+            })
+        };
+        let main = self.main_repo()?;
+        if let Some(workdir) = main.workdir().filter(|_| has_head(main.git_dir())) {
+            return Ok(Some(workdir.to_owned()));
+        }
+        Ok(self
+            .worktrees()?
+            .into_iter()
+            .find(|proxy| has_head(proxy.git_dir()))
+            .and_then(|proxy| proxy.base().ok()))
+    }
+
     /// Return the repository owning the main worktree, typically from a linked worktree.
     ///
     /// Note that it might be the one that is currently open if this repository doesn't point to a linked worktree.
@@ -150,3 +551,43 @@ impl crate::Repository {
         Ok(())
     }
 }
+
+/// Turn the name of a worktree directory into an id that is valid as a reference name component, like `git` does.
+#[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+fn sanitize_worktree_id(name: &BStr) -> BString {
+    let mut id: BString = name
+        .iter()
+        .map(|&b| {
+            if b < 0x20 || b == 0x7f || b" :?[\\^~*".contains(&b) {
+                b'-'
+            } else {
+                b
+            }
+        })
+        .collect();
+    while let Some(stripped) = id.strip_suffix(b".lock") {
+        id = stripped.into();
+    }
+    while id.first() == Some(&b'.') {
+        id.remove(0);
+    }
+    if id.is_empty() {
+        id = "worktree".into();
+    }
+    id
+}
+
+/// Return `true` if the `.git` file at `dot_git` points to `git_dir`.
+fn points_to(dot_git: &Path, git_dir: &Path) -> bool {
+    gix_discover::path::from_gitdir_file(dot_git)
+        .ok()
+        .and_then(|path| gix_path::realpath(path).ok())
+        .is_some_and(|path| path == git_dir)
+}
+
+fn write_dot_git_file(dot_git: &Path, git_dir: &Path) -> std::io::Result<()> {
+    let mut content = BString::from("gitdir: ");
+    content.extend_from_slice(&gix_path::into_bstr(git_dir));
+    content.push(b'\n');
+    std::fs::write(dot_git, content)
+}
@@ -0,0 +1,145 @@
+use gix_hash::ObjectId;
+use gix_object::{
+    bstr::{BStr, BString, ByteSlice},
+    FindExt,
+};
+use gix_traverse::commit::Info;
+
+use super::{iter::Error, simplify::Inner, treesame::find_entry};
+use crate::Repository;
+
+/// An iterator over the commits of an `inner` walk which change the file at `path`, following it across renames and copies
+/// like `git log --follow` does.
+pub(super) struct Follow<'repo> {
+    pub(super) repo: &'repo Repository,
+    pub(super) inner: Inner<'repo>,
+    /// The path of the file in the commits that are yet to be seen.
+    pub(super) path: BString,
+    pub(super) shallow: Option<crate::shallow::Commits>,
+    pub(super) commit_graph: Option<gix_commitgraph::Graph>,
+    pub(super) resource_cache: Option<gix_diff::blob::Platform>,
+    pub(super) state: gix_diff::tree::State,
+    pub(super) buf: Vec<u8>,
+    pub(super) buf2: Vec<u8>,
+}
+
+impl Iterator for Follow<'_> {
+    type Item = Result<Info, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let info = match self.inner.next()? {
+                Ok(info) => info,
+                Err(err) => return Some(Err(err)),
+            };
+            match self.changes_path(&info) {
+                Ok(true) => return Some(Ok(info)),
+                Ok(false) => continue,
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+}
+
+impl Follow<'_> {
+    /// Return `true` if the commit of `info` changes the file at our path compared to its only parent, and follow
+    /// its rename if it was added.
+    ///
+    /// Merges never change the file, like in `git log` which doesn't show the changes of merges by default.
+    fn changes_path(&mut self, info: &Info) -> Result<bool, Error> {
+        if info.parent_ids.len() > 1 {
+            return Ok(false);
+        }
+        let is_shallow = self
+            .shallow
+            .as_ref()
+            .is_some_and(|commits| commits.binary_search(&info.id).is_ok());
+        let tree = super::simplify::tree_id(self.repo, self.commit_graph.as_ref(), info.id, &mut self.buf)?;
+        let parent_tree = match info.parent_ids.first().filter(|_| !is_shallow) {
+            Some(parent) => Some(super::simplify::tree_id(
+                self.repo,
+                self.commit_graph.as_ref(),
+                *parent,
+                &mut self.buf,
+            )?),
+            None => None,
+        };
+
+        let entry = self.entry_at_path(Some(tree))?;
+        let parent_entry = self.entry_at_path(parent_tree)?;
+        if entry == parent_entry {
+            return Ok(false);
+        }
+        if let Some(parent_tree) = parent_tree.filter(|_| parent_entry.is_none()) {
+            if let Some(source) = self.find_rewrite_source(parent_tree, tree)? {
+                self.path = source;
+            }
+        }
+        Ok(true)
+    }
+
+    fn entry_at_path(
+        &mut self,
+        tree: Option<ObjectId>,
+    ) -> Result<Option<(gix_object::tree::EntryMode, ObjectId)>, Error> {
+        let Some(mut tree) = tree else {
+            return Ok(None);
+        };
+        let mut components = self.path.split_str("/").peekable();
+        while let Some(name) = components.next() {
+            let entry = find_entry(&self.repo.objects, tree, name.as_bstr(), &mut self.buf)?;
+            match entry {
+                Some((mode, id)) if mode.is_tree() && components.peek().is_some() => tree = id,
+                Some(entry) if components.peek().is_none() => return Ok(Some(entry)),
+                _ => break,
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find the file that was renamed or copied to our path when going from `parent_tree` to `tree`.
+    fn find_rewrite_source(&mut self, parent_tree: ObjectId, tree: ObjectId) -> Result<Option<BString>, Error> {
+        let resource_cache = match self.resource_cache.as_mut() {
+            Some(cache) => cache,
+            None => self
+                .resource_cache
+                .insert(self.repo.diff_resource_cache_for_tree_diff()?),
+        };
+        let objects = &self.repo.objects;
+        let path: &BStr = self.path.as_ref();
+        let mut source = None;
+        gix_diff::tree_with_rewrites(
+            objects.find_tree_iter(&parent_tree, &mut self.buf)?,
+            objects.find_tree_iter(&tree, &mut self.buf2)?,
+            resource_cache,
+            &mut self.state,
+            objects,
+            |change| -> Result<_, std::convert::Infallible> {
+                if let gix_diff::tree_with_rewrites::ChangeRef::Rewrite {
+                    source_location,
+                    location,
+                    ..
+                } = change
+                {
+                    if location == path {
+                        source = Some(source_location.to_owned());
+                        return Ok(std::ops::ControlFlow::Break(()));
+                    }
+                }
+                Ok(std::ops::ControlFlow::Continue(()))
+            },
+            gix_diff::tree_with_rewrites::Options {
+                location: Some(gix_diff::tree::recorder::Location::Path),
+                rewrites: Some(gix_diff::Rewrites {
+                    copies: Some(gix_diff::rewrites::Copies {
+                        source: gix_diff::rewrites::CopySource::FromSetOfModifiedFilesAndAllSources,
+                        percentage: None,
+                    }),
+                    ..Default::default()
+                }),
+            },
+        )?;
+        resource_cache.clear_resource_cache_keep_allocation();
+        Ok(source)
+    }
+}
@@ -1,9 +1,17 @@
+use std::{cell::RefCell, rc::Rc};
+
 use gix_hash::ObjectId;
 use gix_object::FindExt;
 use gix_traverse::commit::simple::CommitTimeOrder;
 
 use crate::{ext::ObjectIdExt, revision, Repository};
 
+#[cfg(feature = "blob-diff")]
+mod follow;
+mod simplify;
+#[cfg(feature = "attributes")]
+mod treesame;
+
 /// The error returned by [`Platform::all()`] and [`Platform::selected()`].
 #[derive(Debug, thiserror::Error)]
 #[allow(missing_docs)]
@@ -14,6 +22,8 @@ pub enum Error {
     ShallowCommits(#[from] crate::shallow::read::Error),
     #[error(transparent)]
     ConfigBoolean(#[from] crate::config::boolean::Error),
+    #[error("An ancestry path needs hidden commits to start from")]
+    AncestryPathWithoutHiddenTips,
 }
 
 /// Specify how to sort commits during a [revision::Walk] traversal.
@@ -66,6 +76,27 @@ pub enum Sorting {
     },
 }
 
+/// Specify how to simplify the history of a traversal [limited to paths](Platform::with_pathspec()).
+///
+/// A commit is *TREESAME* to a parent if both have the same content in all paths of interest.
+/// Commits that are TREESAME are never returned, but may still be traversed to find commits that change the paths.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Simplification {
+    /// If a commit is TREESAME to one of its parents, only traverse that parent, and only return commits that aren't
+    /// TREESAME to any of their parents, like `git log -- <paths>`.
+    ///
+    /// This skips side branches whose changes to the paths didn't end up in the merge.
+    #[default]
+    Default,
+    /// Traverse all parents, and return all commits that aren't TREESAME to all of their parents,
+    /// like `git log --full-history -- <paths>`.
+    FullHistory,
+    /// Like [`FullHistory`](Simplification::FullHistory), but skip merges that end up TREESAME to their only parent
+    /// after each parent was replaced with the commit it simplifies to and redundant parents were removed,
+    /// like `git log --simplify-merges -- <paths>`.
+    SimplifyMerges,
+}
+
 impl Sorting {
     fn into_simple(self) -> Option<gix_traverse::commit::simple::Sorting> {
         Some(match self {
@@ -161,6 +192,12 @@ pub struct Platform<'repo> {
     pub(crate) parents: gix_traverse::commit::Parents,
     pub(crate) use_commit_graph: Option<bool>,
     pub(crate) commit_graph: Option<gix_commitgraph::Graph>,
+    #[cfg(feature = "attributes")]
+    pub(crate) pathspec: Option<crate::Pathspec<'repo>>,
+    #[cfg(feature = "blob-diff")]
+    pub(crate) follow: Option<crate::bstr::BString>,
+    pub(crate) simplification: Simplification,
+    pub(crate) ancestry_path: bool,
 }
 
 impl<'repo> Platform<'repo> {
@@ -174,12 +211,18 @@ impl<'repo> Platform<'repo> {
             use_commit_graph: None,
             commit_graph: None,
             boundary: Vec::new(),
+            #[cfg(feature = "attributes")]
+            pathspec: None,
+            #[cfg(feature = "blob-diff")]
+            follow: None,
+            simplification: Default::default(),
+            ancestry_path: false,
         }
     }
 }
 
 /// Create-time builder methods
-impl Platform<'_> {
+impl<'repo> Platform<'repo> {
     /// Set the sort mode for commits to the given value. The default is to order topologically breadth-first.
     pub fn sorting(mut self, sorting: Sorting) -> Self {
         self.sorting = sorting;
@@ -258,6 +301,54 @@ impl Platform<'_> {
         self.hidden = tips.into_iter().map(Into::into).collect();
         self
     }
+
+    /// Only return commits that change paths matching `pathspec`, with the history being simplified as configured
+    /// by [`simplification()`](Self::simplification()), like `git log -- <paths>`.
+    ///
+    /// Note that all commits are traversed before the first one is returned unless the [default simplification](Simplification::Default)
+    /// is used without [hidden tips](Self::with_hidden()), and that the returned [parent ids](Info::parent_ids) are the ones
+    /// of the commit, not the ones it would have in the simplified history.
+    /// This replaces the path set by [`follow()`](Self::follow()).
+    #[cfg(feature = "attributes")]
+    pub fn with_pathspec(mut self, pathspec: crate::Pathspec<'repo>) -> Self {
+        self.pathspec = Some(pathspec);
+        #[cfg(feature = "blob-diff")]
+        {
+            self.follow = None;
+        }
+        self
+    }
+
+    /// Set how the history of a traversal [limited to paths](Self::with_pathspec()) is simplified.
+    /// The default is [`Simplification::Default`].
+    pub fn simplification(mut self, simplification: Simplification) -> Self {
+        self.simplification = simplification;
+        self
+    }
+
+    /// If `toggle` is `true`, only return commits that are descendants of one of the [hidden tips](Self::with_hidden()),
+    /// like `git log --ancestry-path ^hidden tip`.
+    ///
+    /// This implies [`Simplification::FullHistory`] unless [`Simplification::SimplifyMerges`] is used, and that all commits
+    /// are traversed before the first one is returned.
+    /// Note that the traversal fails if there are no hidden tips.
+    pub fn ancestry_path(mut self, toggle: bool) -> Self {
+        self.ancestry_path = toggle;
+        self
+    }
+
+    /// Only return commits that change the file at the repository-relative `path`, and continue with the path it
+    /// was renamed or copied from once its addition is encountered, like `git log --follow -- <path>`.
+    ///
+    /// Merges are only returned if just [first parents](Self::first_parent_only()) are traversed, and the path is changed
+    /// as commits are seen, which is why renames are only followed correctly in a linear history.
+    /// This replaces the [pathspec](Self::with_pathspec()), and no [simplification](Self::simplification()) is performed.
+    #[cfg(feature = "blob-diff")]
+    pub fn follow(mut self, path: impl Into<crate::bstr::BString>) -> Self {
+        self.follow = Some(path.into());
+        self.pathspec = None;
+        self
+    }
 }
 
 /// Produce the iterator
@@ -279,55 +370,123 @@ impl<'repo> Platform<'repo> {
             commit_graph,
             mut boundary,
             hidden,
+            #[cfg(feature = "attributes")]
+            pathspec,
+            #[cfg(feature = "blob-diff")]
+            follow,
+            simplification,
+            ancestry_path,
         } = self;
         boundary.sort();
-        Ok(revision::Walk {
-            repo,
-            inner: Box::new(
-                gix_traverse::commit::Simple::filtered(tips, &repo.objects, {
-                    // Note that specific shallow handling for commit-graphs isn't needed as these contain
-                    // all information there is, and exclude shallow parents to be structurally consistent.
-                    let shallow_commits = repo.shallow_commits()?;
-                    let mut grafted_parents_to_skip = Vec::new();
-                    let mut buf = Vec::new();
-                    move |id| {
-                        if !filter(id) {
-                            return false;
-                        }
-                        let id = id.to_owned();
-                        if boundary.binary_search(&id).is_ok() {
-                            return false;
+        let may_use_commit_graph = use_commit_graph.map_or_else(|| repo.config.may_use_commit_graph(), Ok)?;
+        #[cfg(feature = "attributes")]
+        let is_path_limited = pathspec.is_some();
+        #[cfg(not(feature = "attributes"))]
+        let is_path_limited = false;
+        let ancestry_path = match ancestry_path {
+            true if hidden.is_empty() => return Err(Error::AncestryPathWithoutHiddenTips),
+            true => Some(hidden.clone()),
+            false => None,
+        };
+        let limit_tips = (is_path_limited || ancestry_path.is_some()).then(|| tips.clone());
+        // Like `git`, stream commits unless all of them have to be known to simplify the history.
+        let excluded = (limit_tips.is_some()
+            && simplification == Simplification::Default
+            && ancestry_path.is_none()
+            && hidden.is_empty())
+        .then(|| Rc::new(RefCell::new(gix_hashtable::HashSet::default())));
+
+        let mut inner: simplify::Inner<'repo> = Box::new(
+            gix_traverse::commit::Simple::filtered(tips, &repo.objects, {
+                // Note that specific shallow handling for commit-graphs isn't needed as these contain
+                // all information there is, and exclude shallow parents to be structurally consistent.
+                let shallow_commits = repo.shallow_commits()?;
+                let mut grafted_parents_to_skip = Vec::new();
+                let mut buf = Vec::new();
+                let excluded = excluded.clone();
+                move |id| {
+                    if !filter(id) || boundary.binary_search_by(|probe| probe.as_ref().cmp(id)).is_ok() {
+                        if let Some(excluded) = &excluded {
+                            excluded.borrow_mut().insert(id.to_owned());
                         }
-                        match shallow_commits.as_ref() {
-                            Some(commits) => {
-                                if let Ok(idx) = grafted_parents_to_skip.binary_search(&id) {
-                                    grafted_parents_to_skip.remove(idx);
-                                    return false;
-                                }
-                                if commits.binary_search(&id).is_ok() {
-                                    if let Ok(commit) = repo.objects.find_commit_iter(&id, &mut buf) {
-                                        grafted_parents_to_skip.extend(commit.parent_ids());
-                                        grafted_parents_to_skip.sort();
-                                    }
+                        return false;
+                    }
+                    let id = id.to_owned();
+                    match shallow_commits.as_ref() {
+                        Some(commits) => {
+                            if let Ok(idx) = grafted_parents_to_skip.binary_search(&id) {
+                                grafted_parents_to_skip.remove(idx);
+                                return false;
+                            }
+                            if commits.binary_search(&id).is_ok() {
+                                if let Ok(commit) = repo.objects.find_commit_iter(&id, &mut buf) {
+                                    grafted_parents_to_skip.extend(commit.parent_ids());
+                                    grafted_parents_to_skip.sort();
                                 }
-                                true
                             }
-                            None => true,
+                            true
                         }
+                        None => true,
                     }
-                })
-                .sorting(sorting.into_simple().expect("for now there is nothing else"))?
-                .parents(parents)
-                .hide(hidden)?
-                .commit_graph(
-                    commit_graph.or(use_commit_graph
-                        .map_or_else(|| self.repo.config.may_use_commit_graph(), Ok)?
-                        .then(|| self.repo.commit_graph().ok())
-                        .flatten()),
-                )
-                .map(|res| res.map_err(iter::Error::from)),
-            ),
-        })
+                }
+            })
+            .sorting(sorting.into_simple().expect("for now there is nothing else"))?
+            .parents(parents)
+            .hide(hidden)?
+            .commit_graph(commit_graph.or(may_use_commit_graph.then(|| repo.commit_graph().ok()).flatten()))
+            .map(|res| res.map_err(iter::Error::from)),
+        );
+
+        // The traversal above has its own commit-graph instance, so paths are checked with another one.
+        let commit_graph_for_paths = || may_use_commit_graph.then(|| repo.commit_graph().ok()).flatten();
+        #[cfg(feature = "blob-diff")]
+        if let Some(path) = follow {
+            inner = Box::new(follow::Follow {
+                repo,
+                inner,
+                path,
+                shallow: repo.shallow_commits()?,
+                commit_graph: commit_graph_for_paths(),
+                resource_cache: None,
+                state: Default::default(),
+                buf: Vec::new(),
+                buf2: Vec::new(),
+            });
+        }
+        match (limit_tips, excluded) {
+            (Some(tips), Some(excluded)) => {
+                inner = Box::new(simplify::Pruned {
+                    repo,
+                    inner,
+                    #[cfg(feature = "attributes")]
+                    pathspec: pathspec.map(treesame::Pathspec::new),
+                    shallow: repo.shallow_commits()?,
+                    commit_graph: commit_graph_for_paths(),
+                    excluded,
+                    reached: tips.into_iter().collect(),
+                    pending: Default::default(),
+                    trees: Default::default(),
+                    buf: Vec::new(),
+                    out: Default::default(),
+                });
+            }
+            (Some(tips), None) => {
+                inner = Box::new(simplify::Limited {
+                    repo,
+                    inner: Some(inner),
+                    tips,
+                    #[cfg(feature = "attributes")]
+                    pathspec: pathspec.map(treesame::Pathspec::new),
+                    simplification,
+                    ancestry_path,
+                    shallow: repo.shallow_commits()?,
+                    commit_graph: commit_graph_for_paths(),
+                    out: Default::default(),
+                });
+            }
+            (None, _) => {}
+        }
+        Ok(revision::Walk { repo, inner })
     }
     /// Return an iterator to traverse all commits reachable as configured by the [Platform].
     ///
@@ -348,6 +507,20 @@ pub mod iter {
     pub enum Error {
         #[error(transparent)]
         SimpleTraversal(#[from] gix_traverse::commit::simple::Error),
+        #[error(transparent)]
+        FindObject(#[from] gix_object::find::existing_iter::Error),
+        #[error(transparent)]
+        DecodeObject(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        LookupCommit(#[from] gix_revwalk::graph::get_or_insert_default::Error),
+        #[error(transparent)]
+        DiffTree(#[from] gix_diff::tree::Error),
+        #[cfg(feature = "blob-diff")]
+        #[error(transparent)]
+        DiffTreeWithRewrites(#[from] gix_diff::tree_with_rewrites::Error),
+        #[cfg(feature = "blob-diff")]
+        #[error(transparent)]
+        ResourceCache(#[from] crate::repository::diff_resource_cache::Error),
     }
 }
 
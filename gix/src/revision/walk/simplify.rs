@@ -0,0 +1,549 @@
+use std::{cell::RefCell, cmp::Reverse, collections::VecDeque, rc::Rc};
+
+use gix_hash::ObjectId;
+use gix_object::FindExt;
+use gix_traverse::commit::Info;
+use smallvec::SmallVec;
+
+use super::{iter::Error, Simplification};
+use crate::Repository;
+
+pub(super) type Inner<'repo> = Box<dyn Iterator<Item = Result<Info, Error>> + 'repo>;
+
+/// An iterator over the commits of an `inner` walk which change the paths of interest, with the history simplified
+/// like `git log` does.
+///
+/// As this requires knowing all commits, `inner` is exhausted before the first commit is returned.
+pub(super) struct Limited<'repo> {
+    pub(super) repo: &'repo Repository,
+    pub(super) inner: Option<Inner<'repo>>,
+    pub(super) tips: Vec<ObjectId>,
+    #[cfg(feature = "attributes")]
+    pub(super) pathspec: Option<super::treesame::Pathspec<'repo>>,
+    pub(super) simplification: Simplification,
+    /// The hidden tips to start the ancestry path from, if only commits on it should be returned.
+    pub(super) ancestry_path: Option<Vec<ObjectId>>,
+    pub(super) shallow: Option<crate::shallow::Commits>,
+    pub(super) commit_graph: Option<gix_commitgraph::Graph>,
+    pub(super) out: VecDeque<Info>,
+}
+
+/// An iterator over the commits of an `inner` walk which change the paths of interest, with the history simplified
+/// like `git log` does by default.
+///
+/// Commits are returned as they are seen, and only the parent a commit is TREESAME to is followed. Commits that
+/// weren't reached through a followed parent yet are held back until they are, and are dropped if they never are.
+pub(super) struct Pruned<'repo> {
+    pub(super) repo: &'repo Repository,
+    pub(super) inner: Inner<'repo>,
+    #[cfg(feature = "attributes")]
+    pub(super) pathspec: Option<super::treesame::Pathspec<'repo>>,
+    pub(super) shallow: Option<crate::shallow::Commits>,
+    pub(super) commit_graph: Option<gix_commitgraph::Graph>,
+    /// The commits that `inner` won't traverse, which are thus not relevant as parents.
+    pub(super) excluded: Rc<RefCell<gix_hashtable::HashSet<ObjectId>>>,
+    /// The tips, and all parents that were followed so far.
+    pub(super) reached: gix_hashtable::HashSet<ObjectId>,
+    /// Commits that were seen before they were reached.
+    pub(super) pending: gix_hashtable::HashMap<ObjectId, Info>,
+    /// The trees of parents that were compared to, to not look them up again once the parent itself is seen.
+    pub(super) trees: gix_hashtable::HashMap<ObjectId, ObjectId>,
+    pub(super) buf: Vec<u8>,
+    pub(super) out: VecDeque<Info>,
+}
+
+impl Iterator for Pruned<'_> {
+    type Item = Result<Info, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.out.is_empty() {
+            let info = match self.inner.next()? {
+                Ok(info) => info,
+                Err(err) => return Some(Err(err)),
+            };
+            if !self.reached.contains(&info.id) {
+                self.pending.insert(info.id, info);
+                continue;
+            }
+            if let Err(err) = self.process(info) {
+                return Some(Err(err));
+            }
+        }
+        self.out.pop_front().map(Ok)
+    }
+}
+
+impl Pruned<'_> {
+    /// Decide if the reached commit `info` is returned, and mark the parents it follows as reached.
+    fn process(&mut self, info: Info) -> Result<(), Error> {
+        let mut queue = vec![info];
+        while let Some(info) = queue.pop() {
+            let tree = match self.trees.remove(&info.id) {
+                Some(tree) => tree,
+                None => tree_id(self.repo, self.commit_graph.as_ref(), info.id, &mut self.buf)?,
+            };
+            let is_shallow = self
+                .shallow
+                .as_ref()
+                .is_some_and(|commits| commits.binary_search(&info.id).is_ok());
+            let parents: &[ObjectId] = if is_shallow { &[] } else { &info.parent_ids };
+
+            let (is_treesame, followed): (_, SmallVec<[ObjectId; 2]>) = if parents.is_empty() {
+                (self.is_treesame(None, tree)?, SmallVec::new())
+            } else {
+                let (mut relevant_parents, mut relevant_change, mut irrelevant_change) = (0, false, false);
+                let mut followed_parent = None;
+                for (pos, parent) in parents.iter().enumerate() {
+                    let is_relevant = !self.excluded.borrow().contains(parent);
+                    let parent_tree = match self.trees.get(parent) {
+                        Some(tree) => *tree,
+                        None => {
+                            let tree = tree_id(self.repo, self.commit_graph.as_ref(), *parent, &mut self.buf)?;
+                            self.trees.insert(*parent, tree);
+                            tree
+                        }
+                    };
+                    let is_treesame = (pos == 0 && self.is_unchanged_according_to_bloom_filter(&info.id))
+                        || self.is_treesame(Some(parent_tree), tree)?;
+                    relevant_parents += usize::from(is_relevant);
+                    if is_treesame {
+                        if is_relevant {
+                            followed_parent = Some(*parent);
+                            break;
+                        }
+                    } else if is_relevant {
+                        relevant_change = true;
+                    } else {
+                        irrelevant_change = true;
+                    }
+                }
+                match followed_parent {
+                    Some(parent) => (true, smallvec::smallvec![parent]),
+                    None if relevant_parents > 0 => (!relevant_change, parents.iter().copied().collect()),
+                    None => (!irrelevant_change, parents.iter().copied().collect()),
+                }
+            };
+
+            for parent in followed {
+                if self.reached.insert(parent) {
+                    if let Some(info) = self.pending.remove(&parent) {
+                        queue.push(info);
+                    }
+                }
+            }
+            if !is_treesame {
+                self.out.push_back(info);
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "attributes")]
+    fn is_treesame(&mut self, parent_tree: Option<ObjectId>, tree: ObjectId) -> Result<bool, Error> {
+        match self.pathspec.as_mut() {
+            Some(pathspec) => pathspec.is_treesame(&self.repo.objects, parent_tree, Some(tree)),
+            None => Ok(false),
+        }
+    }
+
+    #[cfg(not(feature = "attributes"))]
+    fn is_treesame(&mut self, _parent_tree: Option<ObjectId>, _tree: ObjectId) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    #[cfg(feature = "attributes")]
+    fn is_unchanged_according_to_bloom_filter(&mut self, id: &ObjectId) -> bool {
+        match (self.pathspec.as_mut(), self.commit_graph.as_ref()) {
+            (Some(pathspec), Some(graph)) => pathspec.is_unchanged_according_to_bloom_filter(graph, id),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(feature = "attributes"))]
+    fn is_unchanged_according_to_bloom_filter(&mut self, _id: &ObjectId) -> bool {
+        false
+    }
+}
+
+/// A commit of the walk as seen by the history simplification.
+struct Commit {
+    /// The parents of the commit, which may be rewritten or removed during simplification.
+    parents: SmallVec<[ObjectId; 2]>,
+    /// For each of `parents`, `true` if this commit is TREESAME to it.
+    treesame: SmallVec<[bool; 2]>,
+    /// `true` if this commit doesn't change the paths of interest, and is thus not returned.
+    is_treesame: bool,
+}
+
+impl Iterator for Limited<'_> {
+    type Item = Result<Info, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(inner) = self.inner.take() {
+            if let Err(err) = self.limit(inner) {
+                return Some(Err(err));
+            }
+        }
+        self.out.pop_front().map(Ok)
+    }
+}
+
+impl Limited<'_> {
+    fn limit(&mut self, inner: Inner<'_>) -> Result<(), Error> {
+        let infos = inner.collect::<Result<Vec<_>, _>>()?;
+        let index: gix_hashtable::HashMap<_, _> = infos.iter().enumerate().map(|(idx, info)| (info.id, idx)).collect();
+        let mut commits: Vec<_> = infos
+            .iter()
+            .map(|info| Commit {
+                parents: if self.is_shallow(&info.id) {
+                    Default::default()
+                } else {
+                    info.parent_ids.iter().copied().collect()
+                },
+                treesame: Default::default(),
+                is_treesame: false,
+            })
+            .collect();
+        // This is only called once, and the graph is borrowed while `self` is used mutably.
+        let commit_graph = self.commit_graph.take();
+        let mut graph = self.repo.revision_graph(commit_graph.as_ref());
+        let order = topological_order(&infos, &commits, &index, commit_graph.is_some().then_some(&mut graph))?;
+
+        let on_path = self.ancestry_path.as_ref().map(|bottoms| {
+            let mut on_path = vec![false; commits.len()];
+            for &idx in order.iter().rev() {
+                on_path[idx] = commits[idx]
+                    .parents
+                    .iter()
+                    .any(|parent| bottoms.contains(parent) || index.get(parent).is_some_and(|&parent| on_path[parent]));
+            }
+            on_path
+        });
+        let simplification = match self.simplification {
+            Simplification::Default if on_path.is_some() => Simplification::FullHistory,
+            simplification => simplification,
+        };
+
+        let mut reached = vec![simplification != Simplification::Default; commits.len()];
+        for tip in &self.tips {
+            if let Some(&idx) = index.get(tip) {
+                reached[idx] = true;
+            }
+        }
+        let mut trees = gix_hashtable::HashMap::default();
+        let mut buf = Vec::new();
+        for &idx in &order {
+            if !reached[idx] {
+                continue;
+            }
+            let tree = self.tree_id(commit_graph.as_ref(), &mut trees, infos[idx].id, &mut buf)?;
+            let commit = &mut commits[idx];
+            if commit.parents.is_empty() {
+                commit.is_treesame = self.is_treesame(None, tree)?;
+                continue;
+            }
+            let (mut relevant_parents, mut relevant_change, mut irrelevant_change) = (0, false, false);
+            let mut followed_parent = None;
+            for (pos, parent) in commit.parents.iter().enumerate() {
+                // Like `git`, parents that aren't on the ancestry path don't count once it's known.
+                let is_relevant = index
+                    .get(parent)
+                    .is_some_and(|&parent| on_path.as_ref().is_none_or(|on_path| on_path[parent]));
+                let parent_tree = self.tree_id(commit_graph.as_ref(), &mut trees, *parent, &mut buf)?;
+                let is_treesame = (pos == 0
+                    && self.is_unchanged_according_to_bloom_filter(commit_graph.as_ref(), &infos[idx].id))
+                    || self.is_treesame(Some(parent_tree), tree)?;
+                commit.treesame.push(is_treesame);
+                relevant_parents += usize::from(is_relevant);
+                if is_treesame {
+                    if simplification == Simplification::Default && is_relevant {
+                        followed_parent = Some(pos);
+                        break;
+                    }
+                } else if is_relevant {
+                    relevant_change = true;
+                } else {
+                    irrelevant_change = true;
+                }
+            }
+            match followed_parent {
+                Some(pos) => {
+                    commit.parents = smallvec::smallvec![commit.parents[pos]];
+                    commit.treesame = smallvec::smallvec![true];
+                    commit.is_treesame = true;
+                }
+                None => {
+                    commit.is_treesame = if relevant_parents > 0 {
+                        !relevant_change
+                    } else {
+                        !irrelevant_change
+                    };
+                }
+            }
+            for parent in &commit.parents {
+                if let Some(&parent) = index.get(parent) {
+                    reached[parent] = true;
+                }
+            }
+        }
+
+        let show = match simplification {
+            Simplification::Default | Simplification::FullHistory => (0..commits.len())
+                .map(|idx| reached[idx] && !commits[idx].is_treesame && on_path.as_ref().is_none_or(|p| p[idx]))
+                .collect(),
+            Simplification::SimplifyMerges => {
+                simplify_merges(&infos, &mut commits, &index, &order, on_path.as_deref(), &mut graph)?
+            }
+        };
+        self.out = infos
+            .into_iter()
+            .zip(show)
+            .filter_map(|(info, show)| show.then_some(info))
+            .collect();
+        Ok(())
+    }
+
+    fn is_shallow(&self, id: &ObjectId) -> bool {
+        self.shallow
+            .as_ref()
+            .is_some_and(|commits| commits.binary_search(id).is_ok())
+    }
+
+    fn tree_id(
+        &self,
+        commit_graph: Option<&gix_commitgraph::Graph>,
+        trees: &mut gix_hashtable::HashMap<ObjectId, ObjectId>,
+        id: ObjectId,
+        buf: &mut Vec<u8>,
+    ) -> Result<ObjectId, Error> {
+        if let Some(tree) = trees.get(&id) {
+            return Ok(*tree);
+        }
+        let tree = tree_id(self.repo, commit_graph, id, buf)?;
+        trees.insert(id, tree);
+        Ok(tree)
+    }
+
+    #[cfg(feature = "attributes")]
+    fn is_treesame(&mut self, parent_tree: Option<ObjectId>, tree: ObjectId) -> Result<bool, Error> {
+        match self.pathspec.as_mut() {
+            Some(pathspec) => pathspec.is_treesame(&self.repo.objects, parent_tree, Some(tree)),
+            None => Ok(false),
+        }
+    }
+
+    #[cfg(not(feature = "attributes"))]
+    fn is_treesame(&mut self, _parent_tree: Option<ObjectId>, _tree: ObjectId) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    #[cfg(feature = "attributes")]
+    fn is_unchanged_according_to_bloom_filter(
+        &mut self,
+        commit_graph: Option<&gix_commitgraph::Graph>,
+        id: &ObjectId,
+    ) -> bool {
+        match (self.pathspec.as_mut(), commit_graph) {
+            (Some(pathspec), Some(graph)) => pathspec.is_unchanged_according_to_bloom_filter(graph, id),
+            _ => false,
+        }
+    }
+
+    #[cfg(not(feature = "attributes"))]
+    fn is_unchanged_according_to_bloom_filter(
+        &mut self,
+        _commit_graph: Option<&gix_commitgraph::Graph>,
+        _id: &ObjectId,
+    ) -> bool {
+        false
+    }
+}
+
+/// Order the commits so children come before their parents, using the generation numbers of `graph` if it is backed
+/// by a commit-graph.
+fn topological_order(
+    infos: &[Info],
+    commits: &[Commit],
+    index: &gix_hashtable::HashMap<ObjectId, usize>,
+    graph: Option<&mut gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<()>>>,
+) -> Result<Vec<usize>, Error> {
+    if let Some(graph) = graph {
+        let mut generations = Vec::with_capacity(infos.len());
+        for info in infos {
+            match graph.get_or_insert_commit(info.id, |_| {})?.and_then(|c| c.generation) {
+                Some(generation) => generations.push(generation),
+                None => break,
+            }
+        }
+        if generations.len() == infos.len() {
+            let mut order: Vec<_> = (0..infos.len()).collect();
+            order.sort_by_key(|&idx| Reverse(generations[idx]));
+            return Ok(order);
+        }
+    }
+
+    let mut children = vec![0_usize; commits.len()];
+    for commit in commits {
+        for parent in &commit.parents {
+            if let Some(&parent) = index.get(parent) {
+                children[parent] += 1;
+            }
+        }
+    }
+    let mut ready: VecDeque<_> = (0..commits.len()).filter(|&idx| children[idx] == 0).collect();
+    let mut order = Vec::with_capacity(commits.len());
+    while let Some(idx) = ready.pop_front() {
+        order.push(idx);
+        for parent in &commits[idx].parents {
+            if let Some(&parent) = index.get(parent) {
+                children[parent] -= 1;
+                if children[parent] == 0 {
+                    ready.push_back(parent);
+                }
+            }
+        }
+    }
+    Ok(order)
+}
+
+/// Return the id of the tree of the commit with `id`, using `commit_graph` if possible.
+pub(super) fn tree_id(
+    repo: &Repository,
+    commit_graph: Option<&gix_commitgraph::Graph>,
+    id: ObjectId,
+    buf: &mut Vec<u8>,
+) -> Result<ObjectId, Error> {
+    if let Some(commit) = commit_graph.and_then(|graph| graph.commit_by_id(id)) {
+        return Ok(commit.root_tree_id().to_owned());
+    }
+    Ok(repo.objects.find_commit_iter(&id, buf)?.tree_id()?)
+}
+
+/// Rewrite the parents of all commits to the ones they simplify to, and return for each commit if it should be shown,
+/// like `git log --simplify-merges` does.
+fn simplify_merges(
+    infos: &[Info],
+    commits: &mut [Commit],
+    index: &gix_hashtable::HashMap<ObjectId, usize>,
+    order: &[usize],
+    on_path: Option<&[bool]>,
+    graph: &mut gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<()>>,
+) -> Result<Vec<bool>, Error> {
+    let is_on_path = |idx: usize| on_path.is_none_or(|on_path| on_path[idx]);
+    let is_relevant = |id: &ObjectId| index.get(id).is_some_and(|&idx| is_on_path(idx));
+    let mut simplified = vec![None; commits.len()];
+    let simplified_of =
+        |simplified: &[Option<ObjectId>], id: ObjectId| index.get(&id).and_then(|&idx| simplified[idx]).unwrap_or(id);
+    for &idx in order.iter().rev() {
+        let id = infos[idx].id;
+        if commits[idx].parents.is_empty() || !is_on_path(idx) {
+            simplified[idx] = Some(id);
+            continue;
+        }
+
+        let mut parents: SmallVec<[(ObjectId, bool); 2]> = SmallVec::new();
+        for (parent, is_treesame) in commits[idx].parents.iter().zip(&commits[idx].treesame) {
+            let parent = simplified_of(&simplified, *parent);
+            if !parents.iter().any(|(id, _)| *id == parent) {
+                parents.push((parent, *is_treesame));
+            }
+        }
+        let mut is_treesame = commits[idx].is_treesame;
+        if parents.len() > 1 {
+            // Parents that are ancestors of other parents are redundant, and so are roots that don't touch the paths.
+            let mut marked: SmallVec<[bool; 2]> = SmallVec::with_capacity(parents.len());
+            for (pos, (parent, _)) in parents.iter().enumerate() {
+                let mut is_redundant = index
+                    .get(parent)
+                    .is_some_and(|&parent| commits[parent].parents.is_empty() && commits[parent].is_treesame);
+                for (other_pos, (other, _)) in parents.iter().enumerate() {
+                    if is_redundant {
+                        break;
+                    }
+                    is_redundant = other_pos != pos && is_ancestor(graph, *parent, *other)?;
+                }
+                marked.push(is_redundant);
+            }
+            // Keep the parent the default simplification would have followed.
+            let is_treesame_to_unmarked = parents.iter().zip(&marked).any(|((_, same), marked)| *same && !marked);
+            if !is_treesame_to_unmarked {
+                if let Some(pos) = parents
+                    .iter()
+                    .zip(&marked)
+                    .position(|((_, same), marked)| *same && *marked)
+                {
+                    marked[pos] = false;
+                }
+            }
+            if marked.iter().any(|marked| *marked) {
+                let mut marked = marked.into_iter();
+                parents.retain(|_| !marked.next().expect("one per parent"));
+                is_treesame = if parents.len() == 1 {
+                    parents[0].1
+                } else {
+                    let relevant = parents.iter().filter(|(parent, _)| is_relevant(parent));
+                    if relevant.clone().next().is_some() {
+                        relevant.clone().all(|(_, same)| *same)
+                    } else {
+                        parents.iter().all(|(_, same)| *same)
+                    }
+                };
+            }
+        }
+
+        let one_relevant_parent = if parents.len() == 1 {
+            Some(parents[0].0)
+        } else {
+            let mut relevant = parents.iter().filter(|(parent, _)| is_relevant(parent));
+            match (relevant.next(), relevant.next()) {
+                (Some((parent, _)), None) => Some(*parent),
+                _ => None,
+            }
+        };
+        simplified[idx] = Some(match one_relevant_parent {
+            Some(parent) if is_treesame => simplified_of(&simplified, parent),
+            _ => id,
+        });
+        let commit = &mut commits[idx];
+        commit.parents = parents.iter().map(|(parent, _)| *parent).collect();
+        commit.treesame = parents.iter().map(|(_, same)| *same).collect();
+        commit.is_treesame = is_treesame;
+    }
+
+    Ok((0..commits.len())
+        .map(|idx| {
+            let commit = &commits[idx];
+            is_on_path(idx)
+                && simplified[idx] == Some(infos[idx].id)
+                && (!commit.is_treesame || commit.parents.iter().filter(|parent| is_relevant(parent)).count() >= 2)
+        })
+        .collect())
+}
+
+/// Return `true` if `ancestor` can be reached from `commit`, using generation numbers to stop early if available.
+fn is_ancestor(
+    graph: &mut gix_revwalk::Graph<'_, '_, gix_revwalk::graph::Commit<()>>,
+    ancestor: ObjectId,
+    commit: ObjectId,
+) -> Result<bool, Error> {
+    let ancestor_generation = graph
+        .get_or_insert_commit(ancestor, |_| {})?
+        .and_then(|commit| commit.generation);
+    let mut seen = gix_hashtable::HashSet::default();
+    let mut queue = vec![commit];
+    while let Some(id) = queue.pop() {
+        if id == ancestor {
+            return Ok(true);
+        }
+        let Some(commit) = graph.get_or_insert_commit(id, |_| {})? else {
+            continue;
+        };
+        if let Some((generation, ancestor_generation)) = commit.generation.zip(ancestor_generation) {
+            if generation <= ancestor_generation {
+                continue;
+            }
+        }
+        queue.extend(commit.parents.iter().filter(|parent| seen.insert(**parent)).copied());
+    }
+    Ok(false)
+}
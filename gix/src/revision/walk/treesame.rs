@@ -0,0 +1,283 @@
+use std::collections::VecDeque;
+
+use gix_hash::ObjectId;
+use gix_object::{
+    bstr::{BStr, BString, ByteSlice, ByteVec},
+    tree::EntryMode,
+    FindExt, TreeRefIter,
+};
+
+use super::iter::Error;
+
+/// Compare trees only in the paths matched by a pathspec.
+pub(super) struct Pathspec<'repo> {
+    pathspec: crate::Pathspec<'repo>,
+    /// The directories that contain all matching paths, outermost first.
+    directories: Vec<BString>,
+    /// The beginning of the name of all entries in the innermost of `directories` which may match.
+    name_prefix: BString,
+    state: gix_diff::tree::State,
+    lhs_buf: Vec<u8>,
+    rhs_buf: Vec<u8>,
+    /// The keys of each pattern to query changed-path Bloom filters with, created when first needed.
+    /// It's empty if the patterns can't be represented by keys.
+    bloom_keys: Option<Vec<gix_commitgraph::bloom::PathKeys>>,
+}
+
+impl<'repo> Pathspec<'repo> {
+    pub fn new(pathspec: crate::Pathspec<'repo>) -> Self {
+        let prefix = pathspec.search().common_prefix();
+        let (directories, name_prefix) = match prefix.rfind_byte(b'/') {
+            Some(pos) => (
+                prefix[..pos]
+                    .split_str("/")
+                    .map(|name| name.as_bstr().to_owned())
+                    .collect(),
+                prefix[pos + 1..].as_bstr().to_owned(),
+            ),
+            None => (Vec::new(), prefix.to_owned()),
+        };
+        Pathspec {
+            pathspec,
+            directories,
+            name_prefix,
+            state: Default::default(),
+            lhs_buf: Vec::new(),
+            rhs_buf: Vec::new(),
+            bloom_keys: None,
+        }
+    }
+
+    /// Return `true` if the changed-path Bloom filter of the commit with `id` in `graph` shows that none of the paths
+    /// matched by the pathspec changed compared to the first parent of the commit, making it TREESAME to it.
+    ///
+    /// Like `git`, Bloom filters are only used if all patterns are plain paths without wildcards or special magic,
+    /// and `false` is returned if the filter can't rule out a change.
+    pub fn is_unchanged_according_to_bloom_filter(
+        &mut self,
+        graph: &gix_commitgraph::Graph,
+        id: &gix_hash::oid,
+    ) -> bool {
+        let Some(filter) = graph.commit_by_id(id).and_then(|commit| commit.bloom_filter()) else {
+            return false;
+        };
+        let pathspec = &self.pathspec;
+        let keys = self.bloom_keys.get_or_insert_with(|| {
+            let is_plain = |pattern: &gix_pathspec::Pattern| {
+                !pattern.is_nil()
+                    && !pattern.path().is_empty()
+                    && pattern.attributes.is_empty()
+                    && !pattern
+                        .signature
+                        .intersects(gix_pathspec::MagicSignature::ICASE | gix_pathspec::MagicSignature::EXCLUDE)
+                    && (pattern.search_mode == gix_pathspec::SearchMode::Literal
+                        || !pattern.path().iter().any(|b| b"*?[\\".contains(b)))
+            };
+            let patterns = pathspec.search().patterns();
+            if patterns.len() == 0 || !pathspec.search().patterns().all(is_plain) {
+                return Vec::new();
+            }
+            patterns
+                .map(|pattern| gix_commitgraph::bloom::PathKeys::new(pattern.path(), filter.settings()))
+                .collect()
+        });
+        !keys.is_empty() && keys.iter().all(|keys| !filter.may_contain_path(keys))
+    }
+
+    /// Return `true` if the trees `lhs` and `rhs` have the same content in all paths matched by the pathspec,
+    /// with `None` denoting an empty tree.
+    ///
+    /// Only the trees leading to the common prefix of the pathspec are looked at before comparing entries, which
+    /// makes this as cheap as looking up a path if the pathspec refers to a single directory or file.
+    pub fn is_treesame(
+        &mut self,
+        objects: &impl gix_object::Find,
+        mut lhs: Option<ObjectId>,
+        mut rhs: Option<ObjectId>,
+    ) -> Result<bool, Error> {
+        if lhs == rhs {
+            return Ok(true);
+        }
+        let mut location = BString::default();
+        for name in &self.directories {
+            lhs = subtree(objects, lhs, name.as_ref(), &mut self.lhs_buf)?;
+            rhs = subtree(objects, rhs, name.as_ref(), &mut self.rhs_buf)?;
+            if lhs == rhs {
+                return Ok(true);
+            }
+            push_component(&mut location, name.as_ref());
+        }
+
+        let mut lhs_entries = entries_with_prefix(objects, lhs, self.name_prefix.as_ref(), &mut self.lhs_buf)?;
+        let mut rhs_entries = entries_with_prefix(objects, rhs, self.name_prefix.as_ref(), &mut self.rhs_buf)?;
+        lhs_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        rhs_entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let (mut lhs_entries, mut rhs_entries) =
+            (lhs_entries.into_iter().peekable(), rhs_entries.into_iter().peekable());
+        loop {
+            let (name, lhs, rhs) = match (lhs_entries.peek(), rhs_entries.peek()) {
+                (None, None) => break,
+                (Some(l), Some(r)) if l.0 == r.0 => {
+                    let (name, lhs_mode, lhs_id) = lhs_entries.next().expect("peeked");
+                    let (_, rhs_mode, rhs_id) = rhs_entries.next().expect("peeked");
+                    (name, Some((lhs_mode, lhs_id)), Some((rhs_mode, rhs_id)))
+                }
+                (Some(l), Some(r)) if l.0 < r.0 => {
+                    let (name, mode, id) = lhs_entries.next().expect("peeked");
+                    (name, Some((mode, id)), None)
+                }
+                (Some(_), None) => {
+                    let (name, mode, id) = lhs_entries.next().expect("peeked");
+                    (name, Some((mode, id)), None)
+                }
+                (_, Some(_)) => {
+                    let (name, mode, id) = rhs_entries.next().expect("peeked");
+                    (name, None, Some((mode, id)))
+                }
+            };
+            if lhs == rhs {
+                continue;
+            }
+            let mut path = location.clone();
+            push_component(&mut path, name.as_ref());
+            if [lhs, rhs]
+                .into_iter()
+                .flatten()
+                .any(|(mode, _)| !mode.is_tree() && self.pathspec.is_included(path.as_bstr(), Some(false)))
+            {
+                return Ok(false);
+            }
+            let lhs_tree = lhs.filter(|(mode, _)| mode.is_tree()).map(|(_, id)| id);
+            let rhs_tree = rhs.filter(|(mode, _)| mode.is_tree()).map(|(_, id)| id);
+            if lhs_tree != rhs_tree && self.trees_change_matches(objects, lhs_tree, rhs_tree, path)? {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Diff the trees `lhs` and `rhs` located at `path` and return `true` if a change to a path matched by the pathspec was found.
+    fn trees_change_matches(
+        &mut self,
+        objects: &impl gix_object::Find,
+        lhs: Option<ObjectId>,
+        rhs: Option<ObjectId>,
+        path: BString,
+    ) -> Result<bool, Error> {
+        let lhs = match lhs {
+            Some(id) => objects.find_tree_iter(&id, &mut self.lhs_buf)?,
+            None => TreeRefIter::from_bytes(&[]),
+        };
+        let rhs = match rhs {
+            Some(id) => objects.find_tree_iter(&id, &mut self.rhs_buf)?,
+            None => TreeRefIter::from_bytes(&[]),
+        };
+        let mut delegate = Matches {
+            pathspec: &mut self.pathspec,
+            path_deque: Default::default(),
+            path,
+        };
+        match gix_diff::tree(lhs, rhs, &mut self.state, objects, &mut delegate) {
+            Ok(()) => Ok(false),
+            Err(gix_diff::tree::Error::Cancelled) => Ok(true),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// Return the mode and id of the entry called `name` in `tree`, if it exists.
+pub(super) fn find_entry(
+    objects: &impl gix_object::Find,
+    tree: ObjectId,
+    name: &BStr,
+    buf: &mut Vec<u8>,
+) -> Result<Option<(EntryMode, ObjectId)>, Error> {
+    for entry in objects.find_tree_iter(&tree, buf)? {
+        let entry = entry?;
+        if entry.filename == name {
+            return Ok(Some((entry.mode, entry.oid.to_owned())));
+        }
+    }
+    Ok(None)
+}
+
+/// Return the tree called `name` in `tree`, or `None` if there is no such tree.
+fn subtree(
+    objects: &impl gix_object::Find,
+    tree: Option<ObjectId>,
+    name: &BStr,
+    buf: &mut Vec<u8>,
+) -> Result<Option<ObjectId>, Error> {
+    let Some(tree) = tree else {
+        return Ok(None);
+    };
+    Ok(find_entry(objects, tree, name, buf)?
+        .filter(|(mode, _)| mode.is_tree())
+        .map(|(_, id)| id))
+}
+
+fn entries_with_prefix(
+    objects: &impl gix_object::Find,
+    tree: Option<ObjectId>,
+    name_prefix: &BStr,
+    buf: &mut Vec<u8>,
+) -> Result<Vec<(BString, EntryMode, ObjectId)>, Error> {
+    let Some(tree) = tree else {
+        return Ok(Vec::new());
+    };
+    let mut out = Vec::new();
+    for entry in objects.find_tree_iter(&tree, buf)? {
+        let entry = entry?;
+        if entry.filename.starts_with(name_prefix) {
+            out.push((entry.filename.to_owned(), entry.mode, entry.oid.to_owned()));
+        }
+    }
+    Ok(out)
+}
+
+fn push_component(path: &mut BString, name: &BStr) {
+    if !path.is_empty() {
+        path.push(b'/');
+    }
+    path.push_str(name);
+}
+
+/// A delegate to stop the tree-diff at the first change to a path matched by `pathspec`.
+struct Matches<'a, 'repo> {
+    pathspec: &'a mut crate::Pathspec<'repo>,
+    path_deque: VecDeque<BString>,
+    path: BString,
+}
+
+impl gix_diff::tree::Visit for Matches<'_, '_> {
+    fn pop_front_tracked_path_and_set_current(&mut self) {
+        self.path = self.path_deque.pop_front().expect("every parent is set only once");
+    }
+
+    fn push_back_tracked_path_component(&mut self, component: &BStr) {
+        self.push_path_component(component);
+        self.path_deque.push_back(self.path.clone());
+    }
+
+    fn push_path_component(&mut self, component: &BStr) {
+        if !component.is_empty() {
+            push_component(&mut self.path, component);
+        }
+    }
+
+    fn pop_path_component(&mut self) {
+        if let Some(pos) = self.path.rfind_byte(b'/') {
+            self.path.resize(pos, 0);
+        } else {
+            self.path.clear();
+        }
+    }
+
+    fn visit(&mut self, change: gix_diff::tree::visit::Change) -> gix_diff::tree::visit::Action {
+        if !change.entry_mode().is_tree() && self.pathspec.is_included(self.path.as_bstr(), Some(false)) {
+            std::ops::ControlFlow::Break(())
+        } else {
+            std::ops::ControlFlow::Continue(())
+        }
+    }
+}
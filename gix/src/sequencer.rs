@@ -0,0 +1,112 @@
+//! Apply the changes of multiple commits one after another like `git cherry-pick <range>` and `git revert <range>`.
+//!
+//! The [state](crate::sequencer::State) of the sequence is kept in the `sequencer` directory of the `git` directory,
+//! in the format `git` uses, so that it can be continued after resolving conflicts, skipped over, aborted or abandoned,
+//! no matter if it was started by `git` or by `gitoxide`.
+//! See [`Repository::sequence_start()`](crate::Repository::sequence_start()) and the methods that follow it.
+pub use gix_sequencer as plumbing;
+pub use gix_sequencer::{todo, Operation, Options, State};
+
+use crate::bstr::BString;
+
+/// The outcome of [`Repository::sequence_start()`](crate::Repository::sequence_start()),
+/// [`Repository::sequence_continue()`](crate::Repository::sequence_continue())
+/// and [`Repository::sequence_skip()`](crate::Repository::sequence_skip()).
+#[derive(Debug)]
+pub struct Outcome {
+    /// The ids of the commits that were created, in order, with the last one being the one `HEAD` points to.
+    pub commits: Vec<gix_hash::ObjectId>,
+    /// The commit whose changes couldn't be applied without conflicts, or `None` if the sequence is finished
+    /// and its state was removed.
+    pub stopped_at: Option<gix_hash::ObjectId>,
+    /// The unresolved conflicts of the commit that the sequence stopped at. Their stages were written to the index,
+    /// and conflict markers to the worktree.
+    pub conflicts: Vec<crate::merge::tree::Conflict>,
+}
+
+/// The error returned by [`Repository::sequence_start()`](crate::Repository::sequence_start()) and the methods that
+/// drive a sequence.
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Repository at \"{}\" is a bare repository and has no worktree to apply changes to", git_dir.display())]
+    BareRepository { git_dir: std::path::PathBuf },
+    #[error("Cannot start a sequence while another operation is in progress: {state:?}")]
+    InProgress { state: crate::state::InProgress },
+    #[error("A cherry-pick or revert of multiple commits is already in progress")]
+    SequenceInProgress,
+    #[error("There is no cherry-pick or revert in progress")]
+    NothingInProgress,
+    #[error("The option '{name}' isn't supported")]
+    UnsupportedOption { name: &'static str },
+    #[error("The instruction '{command}' can't be performed when cherry-picking or reverting")]
+    UnsupportedInstruction { command: &'static str },
+    #[error("Could not resolve '{name}' to a single commit")]
+    ResolveCommit { name: BString },
+    #[error(transparent)]
+    LookupPrefix(#[from] gix_odb::store::prefix::lookup::Error),
+    #[error("The operation was interrupted")]
+    Interrupted,
+    #[error(transparent)]
+    ReadState(#[from] gix_sequencer::state::read::Error),
+    #[error(transparent)]
+    WriteState(#[from] gix_sequencer::state::write::Error),
+    #[error(transparent)]
+    HeadId(#[from] crate::reference::head_id::Error),
+    #[error(transparent)]
+    HeadCommit(#[from] crate::reference::head_commit::Error),
+    #[error(transparent)]
+    ShortId(#[from] crate::id::shorten::Error),
+    #[error(transparent)]
+    FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+    #[error(transparent)]
+    DecodeCommit(#[from] gix_object::decode::Error),
+    #[error(transparent)]
+    CherryPick(#[from] crate::cherry_pick::Error),
+    #[error(transparent)]
+    OpenIndex(#[from] crate::worktree::open_index::Error),
+    #[error("Could not expand the sparse index")]
+    ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+    #[error("The index still has conflicts that need to be resolved")]
+    UnmergedIndex,
+    #[error(transparent)]
+    WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+    #[error("The index contains changes that aren't committed yet")]
+    DirtyIndex,
+    #[error("The changes of commit {id} are already present after resolving conflicts, there is nothing to commit")]
+    Empty { id: gix_hash::ObjectId },
+    #[error("The commit message is empty")]
+    EmptyMessage,
+    #[error("Could not decode the object id in '{}'", path.display())]
+    DecodeId {
+        path: std::path::PathBuf,
+        source: gix_hash::decode::Error,
+    },
+    #[error(transparent)]
+    SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+    #[error(transparent)]
+    IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+    #[error(transparent)]
+    UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+    #[error("Could not collapse the index into a sparse index")]
+    CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+    #[error(transparent)]
+    IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+    #[error(transparent)]
+    WriteIndex(#[from] gix_index::file::write::Error),
+    #[error(transparent)]
+    EditReference(#[from] crate::reference::edit::Error),
+    #[error(transparent)]
+    ParseTime(#[from] crate::config::time::Error),
+    #[error("Committer identity is not configured")]
+    CommitterMissing,
+    #[error("Author identity is not configured")]
+    AuthorMissing,
+    #[error(transparent)]
+    Commit(#[from] crate::commit::Error),
+    #[error("Could not access '{}'", path.display())]
+    Io {
+        path: std::path::PathBuf,
+        source: std::io::Error,
+    },
+}
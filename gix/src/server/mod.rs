@@ -0,0 +1,9 @@
+//! Serve repositories to git clients that fetch or push, with blocking I/O.
+//!
+//! Accepting connections and determining the protocol version the client asked for, for instance through the `GIT_PROTOCOL`
+//! environment variable or the extra parameters of a `git://` request, is left to the caller.
+
+///
+pub mod receive_pack;
+///
+pub mod upload_pack;
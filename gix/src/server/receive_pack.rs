@@ -0,0 +1,546 @@
+use std::{
+    ffi::OsString,
+    io::Write,
+    path::PathBuf,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use gix_hash::ObjectId;
+use gix_protocol::{
+    handshake::Ref,
+    push::{response::Status, Command},
+    server::receive_pack::{Delegate, UpdateRequest},
+};
+use gix_ref::{
+    transaction::{Change, LogChange, PreviousValue, RefEdit, RefLog},
+    Target,
+};
+
+pub use gix_protocol::server::receive_pack::Outcome;
+
+use crate::{
+    bstr::{BString, ByteSlice},
+    config::tree::{receive::DenyAction, Receive},
+};
+
+/// The error returned by [`Repository::serve_receive_pack()`](crate::Repository::serve_receive_pack()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error(transparent)]
+    Serve(#[from] gix_protocol::server::receive_pack::Error),
+}
+
+/// Options for use in [`Repository::serve_receive_pack()`](crate::Repository::serve_receive_pack()).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The protocol version to speak, as requested by the client.
+    pub version: gix_protocol::transport::Protocol,
+    /// If `true`, all packet lines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub trace_packetlines: bool,
+    /// If `true`, the `pre-receive`, `update` and `post-receive` hooks are run if they exist.
+    pub hooks: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: gix_protocol::transport::Protocol::V0,
+            trace_packetlines: false,
+            hooks: true,
+        }
+    }
+}
+
+impl crate::Repository {
+    /// Serve the `receive-pack` service to a client that sends its commands and pack through `input` and receives our
+    /// responses through `output`, to let it push into this repository.
+    ///
+    /// The received pack is indexed into a quarantine directory within the object database, which is only moved into
+    /// the object database once all objects needed by the pushed references are present and the `pre-receive` hook accepted
+    /// the push. Then the `update` hook and the `receive.deny*` configuration decide which reference can be updated, before
+    /// the `post-receive` hook is informed about all updated references.
+    /// Indexing the pack may be interrupted with `should_interrupt`.
+    ///
+    /// See [`gix_protocol::server::receive_pack::serve()`] for details.
+    pub fn serve_receive_pack(
+        &self,
+        input: impl std::io::Read,
+        output: impl std::io::Write,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let _span = gix_trace::coarse!("gix::Repository::serve_receive_pack()");
+        let mut delegate = RepositoryDelegate {
+            repo: self,
+            hooks: options.hooks,
+            should_interrupt,
+            quarantine: None,
+        };
+        let (_, agent) = self.config.user_agent_tuple();
+        Ok(gix_protocol::server::receive_pack::serve(
+            &mut delegate,
+            input,
+            output,
+            &gix_protocol::server::receive_pack::Options {
+                version: options.version,
+                object_hash: self.object_hash(),
+                agent: agent.map(Into::into).unwrap_or_default(),
+                trace_packetlines: options.trace_packetlines,
+            },
+        )?)
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+struct RepositoryDelegate<'a> {
+    repo: &'a crate::Repository,
+    hooks: bool,
+    should_interrupt: &'a AtomicBool,
+    quarantine: Option<Quarantine>,
+}
+
+/// A directory next to the object database which receives new objects until they are accepted.
+struct Quarantine {
+    /// The objects directory of the quarantine, which is deleted when dropped.
+    objects_dir: PathBuf,
+    /// The files to move into the `pack` directory of the object database to accept the received objects.
+    files: Vec<PathBuf>,
+    /// A repository that sees the objects in quarantine along with all objects of the actual repository.
+    repo: crate::Repository,
+}
+
+impl Quarantine {
+    fn new(repo: &crate::Repository) -> std::io::Result<Self> {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let objects = gix_path::realpath(repo.objects.store_ref().path()).map_err(std::io::Error::other)?;
+        let objects_dir = objects.join(format!(
+            "incoming-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir(&objects_dir)?;
+        let mut quarantine = Quarantine {
+            objects_dir,
+            files: Vec::new(),
+            repo: repo.clone(),
+        };
+        std::fs::create_dir(quarantine.objects_dir.join("pack"))?;
+        std::fs::create_dir(quarantine.objects_dir.join("info"))?;
+        let mut alternates = gix_path::into_bstr(objects).into_owned();
+        alternates.push(b'\n');
+        std::fs::write(quarantine.objects_dir.join("info").join("alternates"), alternates)?;
+        quarantine.repo.objects = gix_odb::at_opts(
+            quarantine.objects_dir.clone(),
+            Vec::new(),
+            gix_odb::store::init::Options {
+                object_hash: repo.object_hash(),
+                ..Default::default()
+            },
+        )?
+        .into();
+        Ok(quarantine)
+    }
+
+    /// Move all received files into the object database, in order.
+    fn migrate(&mut self, repo: &crate::Repository) -> std::io::Result<()> {
+        let pack_dir = repo.objects.store_ref().path().join("pack");
+        for file in self.files.drain(..) {
+            let name = file.file_name().expect("written files have a name");
+            std::fs::rename(&file, pack_dir.join(name))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Quarantine {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.objects_dir).ok();
+    }
+}
+
+impl Delegate for RepositoryDelegate<'_> {
+    fn refs(&mut self) -> Result<Vec<Ref>, BoxError> {
+        let mut out = Vec::new();
+        for reference in self.repo.references()?.all()? {
+            let mut reference = reference?;
+            out.push(Ref::Direct {
+                full_ref_name: reference.name().as_bstr().to_owned(),
+                object: reference.follow_to_object()?.detach(),
+            });
+        }
+        Ok(out)
+    }
+
+    fn receive_pack(&mut self, pack: &mut dyn std::io::BufRead, _progress: &mut dyn Write) -> Result<(), BoxError> {
+        let mut quarantine = Quarantine::new(self.repo)?;
+        let outcome = gix_pack::Bundle::write_to_directory(
+            pack,
+            Some(&quarantine.objects_dir.join("pack")),
+            &mut gix_features::progress::Discard,
+            self.should_interrupt,
+            Some(self.repo.objects.clone()),
+            gix_pack::bundle::write::Options {
+                thread_limit: None,
+                index_version: Default::default(),
+                iteration_mode: gix_pack::data::input::Mode::Verify,
+                object_hash: self.repo.object_hash(),
+            },
+        )?;
+        quarantine.files.extend(outcome.data_path);
+        quarantine.files.extend(outcome.index_path);
+        self.quarantine = Some(quarantine);
+        Ok(())
+    }
+
+    fn update(&mut self, request: UpdateRequest<'_>, progress: &mut dyn Write) -> Result<Vec<Status>, BoxError> {
+        let UpdateRequest {
+            commands,
+            atomic,
+            push_options,
+        } = request;
+        let mut rejections: Vec<Option<&'static str>> = vec![None; commands.len()];
+
+        let repo = self.quarantine.as_ref().map_or(self.repo, |q| &q.repo);
+        let mut hidden = Vec::new();
+        for reference in self.repo.references()?.all()? {
+            let id = reference?.peel_to_id()?.detach();
+            if repo.find_header(id)?.kind() == gix_object::Kind::Commit {
+                hidden.push(id);
+            }
+        }
+        let mut connectivity = Connectivity {
+            repo,
+            hidden: &hidden,
+            seen: Default::default(),
+        };
+        for (command, rejection) in commands.iter().zip(rejections.iter_mut()) {
+            if !command.name.starts_with(b"refs/") || gix_validate::reference::name(command.name.as_bstr()).is_err() {
+                *rejection = Some("funny refname");
+            } else if !command.is_delete() && !connectivity.is_complete(command.new)? {
+                *rejection = Some("missing necessary objects");
+            }
+        }
+        reject_all_if_atomic(atomic, &mut rejections);
+
+        let accepted = accepted_commands(commands, &rejections);
+        if !accepted.is_empty() {
+            let mut env = Vec::new();
+            if let Some(quarantine) = &self.quarantine {
+                env.push(("GIT_QUARANTINE_PATH", quarantine.objects_dir.clone().into_os_string()));
+                env.push(("GIT_OBJECT_DIRECTORY", quarantine.objects_dir.clone().into_os_string()));
+                env.push((
+                    "GIT_ALTERNATE_OBJECT_DIRECTORIES",
+                    self.repo.objects.store_ref().path().to_owned().into_os_string(),
+                ));
+            }
+            if !self.run_hook("pre-receive", &[], &hook_input(&accepted), push_options, &env, progress)? {
+                for rejection in rejections.iter_mut().filter(|r| r.is_none()) {
+                    *rejection = Some("pre-receive hook declined");
+                }
+            }
+        }
+        if rejections.iter().any(Option::is_none) {
+            if let Some(quarantine) = self.quarantine.as_mut() {
+                quarantine.migrate(self.repo)?;
+            }
+        }
+
+        let head_name = self.repo.head_name()?;
+        let mut edits = Vec::new();
+        for (command, rejection) in commands.iter().zip(rejections.iter_mut()) {
+            if rejection.is_some() {
+                continue;
+            }
+            *rejection = self.check_policy(command, head_name.as_ref(), progress)?;
+            if rejection.is_none()
+                && !self.run_hook(
+                    "update",
+                    &[
+                        command.name.to_str_lossy().into_owned().into(),
+                        command.old.to_string().into(),
+                        command.new.to_string().into(),
+                    ],
+                    &[],
+                    &[],
+                    &[],
+                    progress,
+                )?
+            {
+                *rejection = Some("hook declined");
+            }
+            if rejection.is_none() {
+                edits.push(to_edit(command)?);
+            }
+        }
+        reject_all_if_atomic(atomic, &mut rejections);
+
+        if atomic {
+            if !edits.is_empty() && self.repo.edit_references(edits).is_err() {
+                for rejection in rejections.iter_mut() {
+                    *rejection = Some("atomic transaction failed");
+                }
+            }
+        } else {
+            let mut edits = edits.into_iter();
+            for rejection in rejections.iter_mut().filter(|r| r.is_none()) {
+                let edit = edits.next().expect("one edit per accepted command");
+                if self.repo.edit_reference(edit).is_err() {
+                    *rejection = Some("failed to update ref");
+                }
+            }
+        }
+
+        let updated = accepted_commands(commands, &rejections);
+        if !updated.is_empty() {
+            self.run_hook("post-receive", &[], &hook_input(&updated), push_options, &[], progress)?;
+        }
+        Ok(commands
+            .iter()
+            .zip(rejections)
+            .map(|(command, rejection)| match rejection {
+                None => Status::Ok {
+                    full_ref_name: command.name.clone(),
+                },
+                Some(reason) => Status::Rejected {
+                    full_ref_name: command.name.clone(),
+                    reason: reason.into(),
+                },
+            })
+            .collect())
+    }
+}
+
+impl RepositoryDelegate<'_> {
+    /// Return the reason for rejecting `command` due to the `receive.deny*` configuration, if any.
+    fn check_policy(
+        &self,
+        command: &Command,
+        head_name: Option<&gix_ref::FullName>,
+        progress: &mut dyn Write,
+    ) -> Result<Option<&'static str>, BoxError> {
+        let config = self.repo.config_snapshot();
+        let deny_action = |key: &'static crate::config::tree::receive::Deny| -> Result<DenyAction, BoxError> {
+            Ok(match config.string(key) {
+                Some(value) => key.try_into_action(value)?,
+                None => DenyAction::Refuse,
+            })
+        };
+        let is_head = head_name.is_some_and(|name| name.as_bstr() == command.name);
+        if command.is_delete() {
+            if command.name.starts_with(b"refs/heads/") && config.boolean(&Receive::DENY_DELETES).unwrap_or(false) {
+                return Ok(Some("deletion prohibited"));
+            }
+            if is_head {
+                match deny_action(&Receive::DENY_DELETE_CURRENT)? {
+                    DenyAction::Refuse => return Ok(Some("deletion of the current branch prohibited")),
+                    DenyAction::Warn => writeln!(progress, "warning: deleting the current branch")?,
+                    DenyAction::Ignore => {}
+                }
+            }
+            return Ok(None);
+        }
+        if is_head && !self.repo.is_bare() {
+            match deny_action(&Receive::DENY_CURRENT_BRANCH)? {
+                DenyAction::Refuse => return Ok(Some("branch is currently checked out")),
+                DenyAction::Warn => writeln!(progress, "warning: updating the current branch")?,
+                DenyAction::Ignore => {}
+            }
+        }
+        if !command.old.is_null()
+            && command.name.starts_with(b"refs/heads/")
+            && config.boolean(&Receive::DENY_NON_FAST_FORWARDS).unwrap_or(false)
+            && self.repo.find_header(command.old)?.kind() == gix_object::Kind::Commit
+            && !self
+                .repo
+                .rev_walk(Some(command.new))
+                .all()?
+                .any(|info| info.is_ok_and(|info| info.id == command.old))
+        {
+            return Ok(Some("non-fast-forward"));
+        }
+        Ok(None)
+    }
+
+    /// Run the hook `name` with `args` and `stdin` if it exists, and pass everything it prints to `progress`.
+    ///
+    /// Return `true` if it succeeded or doesn't exist.
+    fn run_hook(
+        &self,
+        name: &str,
+        args: &[OsString],
+        stdin: &[u8],
+        push_options: &[BString],
+        env: &[(&str, OsString)],
+        progress: &mut dyn Write,
+    ) -> Result<bool, BoxError> {
+        if !self.hooks {
+            return Ok(true);
+        }
+        let git_dir = gix_path::realpath(self.repo.git_dir())?;
+        let Some(mut hook) = self.repo.hook_in(name, git_dir)? else {
+            return Ok(true);
+        };
+        hook = hook.args(args.iter().cloned()).stdin(stdin);
+        if !push_options.is_empty() {
+            hook = hook.env("GIT_PUSH_OPTION_COUNT", push_options.len().to_string());
+            for (idx, option) in push_options.iter().enumerate() {
+                hook = hook.env(
+                    format!("GIT_PUSH_OPTION_{idx}"),
+                    gix_path::from_bstr(option.as_bstr()).as_os_str(),
+                );
+            }
+        }
+        for (key, value) in env {
+            hook = hook.env(key, value);
+        }
+        let output = hook.output()?;
+        progress.write_all(&output.stdout)?;
+        progress.write_all(&output.stderr)?;
+        Ok(output.status.success())
+    }
+}
+
+/// Check that all objects reachable from an object are present, assuming that everything reachable from `hidden` is.
+struct Connectivity<'a> {
+    repo: &'a crate::Repository,
+    hidden: &'a [ObjectId],
+    seen: gix_hashtable::HashSet,
+}
+
+impl Connectivity<'_> {
+    fn is_complete(&mut self, id: ObjectId) -> Result<bool, BoxError> {
+        let Some(header) = self.repo.try_find_header(id)? else {
+            return Ok(false);
+        };
+        Ok(match header.kind() {
+            gix_object::Kind::Blob => true,
+            gix_object::Kind::Tree => self.is_tree_complete(id)?,
+            gix_object::Kind::Tag => match self.repo.find_object(id)?.peel_tags_to_end() {
+                Ok(object) => self.is_complete(object.id)?,
+                Err(_) => false,
+            },
+            gix_object::Kind::Commit => {
+                let walk = match self
+                    .repo
+                    .rev_walk(Some(id))
+                    .with_hidden(self.hidden.iter().copied())
+                    .all()
+                {
+                    Ok(walk) => walk,
+                    Err(_) => return Ok(false),
+                };
+                for info in walk {
+                    let Ok(info) = info else { return Ok(false) };
+                    let tree = self.repo.find_commit(info.id)?.tree_id()?.detach();
+                    if !self.is_tree_complete(tree)? {
+                        return Ok(false);
+                    }
+                }
+                true
+            }
+        })
+    }
+
+    fn is_tree_complete(&mut self, id: ObjectId) -> Result<bool, BoxError> {
+        let mut added = Vec::new();
+        let is_complete = self.add_complete_tree(id, &mut added)?;
+        if !is_complete {
+            // Trees added so far may contain the missing object, so later commands have to check them again.
+            for id in added {
+                self.seen.remove(&id);
+            }
+        }
+        Ok(is_complete)
+    }
+
+    /// Add the tree `id` and all objects it contains to our set of seen objects, keeping track of them in `added`,
+    /// and return `false` if any of them is missing.
+    fn add_complete_tree(&mut self, id: ObjectId, added: &mut Vec<ObjectId>) -> Result<bool, BoxError> {
+        let mut trees = vec![id];
+        while let Some(id) = trees.pop() {
+            if self.seen.contains(&id) {
+                continue;
+            }
+            let Some(tree) = self.repo.try_find_object(id)? else {
+                return Ok(false);
+            };
+            self.seen.insert(id);
+            added.push(id);
+            for entry in tree.try_into_tree()?.iter() {
+                let entry = entry?;
+                let id = entry.oid().to_owned();
+                if entry.mode().is_tree() {
+                    trees.push(id);
+                } else if !entry.mode().is_commit() && !self.seen.contains(&id) {
+                    if !self.repo.has_object(id) {
+                        return Ok(false);
+                    }
+                    self.seen.insert(id);
+                }
+            }
+        }
+        Ok(true)
+    }
+}
+
+fn reject_all_if_atomic(atomic: bool, rejections: &mut [Option<&'static str>]) {
+    if atomic && rejections.iter().any(Option::is_some) {
+        for rejection in rejections.iter_mut().filter(|r| r.is_none()) {
+            *rejection = Some("atomic push failure");
+        }
+    }
+}
+
+fn accepted_commands<'a>(commands: &'a [Command], rejections: &[Option<&'static str>]) -> Vec<&'a Command> {
+    commands
+        .iter()
+        .zip(rejections)
+        .filter_map(|(command, rejection)| rejection.is_none().then_some(command))
+        .collect()
+}
+
+/// The input of the `pre-receive` and `post-receive` hooks.
+fn hook_input(commands: &[&Command]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for command in commands {
+        out.extend_from_slice(format!("{} {} ", command.old, command.new).as_bytes());
+        out.extend_from_slice(&command.name);
+        out.push(b'\n');
+    }
+    out
+}
+
+fn to_edit(command: &Command) -> Result<RefEdit, BoxError> {
+    let name = command.name.clone().try_into()?;
+    let change = if command.is_delete() {
+        Change::Delete {
+            expected: if command.old.is_null() {
+                PreviousValue::MustExist
+            } else {
+                PreviousValue::MustExistAndMatch(Target::Object(command.old))
+            },
+            log: RefLog::AndReference,
+        }
+    } else {
+        Change::Update {
+            log: LogChange {
+                mode: RefLog::AndReference,
+                force_create_reflog: false,
+                message: "push".into(),
+            },
+            expected: if command.old.is_null() {
+                PreviousValue::MustNotExist
+            } else {
+                PreviousValue::MustExistAndMatch(Target::Object(command.old))
+            },
+            new: Target::Object(command.new),
+        }
+    };
+    Ok(RefEdit {
+        change,
+        name,
+        deref: false,
+    })
+}
@@ -0,0 +1,355 @@
+use std::sync::atomic::AtomicBool;
+
+use gix_features::parallel::InOrderIter;
+use gix_hash::ObjectId;
+use gix_pack::data::output;
+use gix_protocol::{
+    handshake::Ref,
+    server::upload_pack::{Delegate, PackRequest},
+};
+
+pub use gix_protocol::server::upload_pack::{Negotiated, Outcome};
+
+/// The error returned by [`Repository::serve_upload_pack()`](crate::Repository::serve_upload_pack()).
+#[derive(Debug, thiserror::Error)]
+#[allow(missing_docs)]
+pub enum Error {
+    #[error("Could not obtain a thread-safe handle to the object database")]
+    OpenObjectDatabase(#[source] std::io::Error),
+    #[error(transparent)]
+    Serve(#[from] gix_protocol::server::upload_pack::Error),
+}
+
+/// Options for use in [`Repository::serve_upload_pack()`](crate::Repository::serve_upload_pack()).
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The protocol version to speak, as requested by the client.
+    pub version: gix_protocol::transport::Protocol,
+    /// If `true`, all packet lines received or sent will be passed to the facilities of the `gix-trace` crate.
+    pub trace_packetlines: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            version: gix_protocol::transport::Protocol::V2,
+            trace_packetlines: false,
+        }
+    }
+}
+
+impl crate::Repository {
+    /// Serve the `upload-pack` service to a client that sends its requests through `input` and receives our responses through
+    /// `output`, to let it fetch or clone from this repository.
+    ///
+    /// All references are advertised, and packs never contain deltas against objects that aren't in the pack.
+    /// Objects that aren't yet packed are delta-compressed as configured by `pack.window` and `pack.depth`.
+    /// Objects to send are counted using a reachability bitmap of the multi-pack index or a pack if one contains all of them.
+    /// Generating packs may be interrupted with `should_interrupt`.
+    ///
+    /// See [`gix_protocol::server::upload_pack::serve()`] for details.
+    pub fn serve_upload_pack(
+        &self,
+        input: impl std::io::Read,
+        output: impl std::io::Write,
+        options: Options,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Outcome, Error> {
+        let _span = gix_trace::coarse!("gix::Repository::serve_upload_pack()");
+        let mut db = self
+            .objects
+            .clone()
+            .into_inner()
+            .into_arc()
+            .map_err(Error::OpenObjectDatabase)?;
+        db.prevent_pack_unload();
+        let mut delegate = RepositoryDelegate {
+            repo: self,
+            db,
+            should_interrupt,
+        };
+        let (_, agent) = self.config.user_agent_tuple();
+        Ok(gix_protocol::server::upload_pack::serve(
+            &mut delegate,
+            input,
+            output,
+            &gix_protocol::server::upload_pack::Options {
+                version: options.version,
+                object_hash: self.object_hash(),
+                agent: agent.map(Into::into).unwrap_or_default(),
+                trace_packetlines: options.trace_packetlines,
+            },
+        )?)
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+struct RepositoryDelegate<'a> {
+    repo: &'a crate::Repository,
+    db: gix_odb::Cache<gix_odb::store::Handle<std::sync::Arc<gix_odb::Store>>>,
+    should_interrupt: &'a AtomicBool,
+}
+
+impl RepositoryDelegate<'_> {
+    /// Count all objects reachable from the wants of `request` by traversing the commit graph.
+    fn count_by_traversal(
+        &mut self,
+        request: &PackRequest<'_>,
+        #[cfg_attr(not(feature = "attributes"), allow(unused_variables))] mode: output::entry::iter_from_counts::Mode,
+    ) -> Result<Vec<output::Count>, BoxError> {
+        let repo = self.repo;
+        let mut tips = Vec::new();
+        let mut extra_objects = Vec::new();
+        for id in request.wants {
+            match self.peel(*id)? {
+                (Some(tag), object) => {
+                    extra_objects.push(tag);
+                    if repo.find_header(object)?.kind() == gix_object::Kind::Commit {
+                        tips.push(object);
+                    } else {
+                        extra_objects.push(object);
+                    }
+                }
+                (None, object) => {
+                    if repo.find_header(object)?.kind() == gix_object::Kind::Commit {
+                        tips.push(object);
+                    } else {
+                        extra_objects.push(object);
+                    }
+                }
+            }
+        }
+        let mut hidden = Vec::new();
+        for id in request.common {
+            if let Ok(commit) = repo.find_object(*id)?.peel_to_kind(gix_object::Kind::Commit) {
+                hidden.push(commit.id);
+            }
+        }
+        let commits = if tips.is_empty() {
+            Vec::new()
+        } else {
+            repo.rev_walk(tips)
+                .with_hidden(hidden)
+                .all()?
+                .map(|info| info.map(|info| info.id))
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        if request.include_tag {
+            let sent: gix_hashtable::HashSet<_> = commits.iter().chain(extra_objects.iter()).copied().collect();
+            for tag in self.refs()?.into_iter().filter_map(|r| match r {
+                Ref::Peeled { tag, object, .. } if sent.contains(&object) && !sent.contains(&tag) => Some(tag),
+                _ => None,
+            }) {
+                extra_objects.push(tag);
+            }
+        }
+
+        let mut input = commits.iter().copied().chain(extra_objects).map(Ok::<_, BoxError>);
+        #[cfg_attr(not(feature = "attributes"), allow(unused_mut))]
+        let (mut counts, _outcome) = output::count::objects_unthreaded(
+            &self.db,
+            &mut input,
+            &gix_features::progress::Discard,
+            self.should_interrupt,
+            output::count::objects::ObjectExpansion::TreeAdditionsComparedToAncestor,
+        )?;
+        #[cfg(feature = "attributes")]
+        if matches!(
+            mode,
+            output::entry::iter_from_counts::Mode::PackCopyAndDeltaCompression { .. }
+        ) {
+            repo.mark_no_delta(&commits, &mut counts)?;
+        }
+
+        Ok(counts)
+    }
+
+    /// Count all objects reachable from the wants of `request` using a reachability bitmap, or return `None` if there
+    /// is no bitmap that contains all of them.
+    fn count_with_bitmap(&mut self, request: &PackRequest<'_>) -> Result<Option<Vec<output::Count>>, BoxError> {
+        use gix_pack::bitmap;
+
+        let object_hash = self.repo.object_hash();
+        let pack_dir = self.repo.objects.store_ref().path().join("pack");
+        let count = |bitmap_path: &std::path::Path, index: &dyn bitmap::Index, order: &bitmap::Order| {
+            let bitmap = bitmap::File::at(bitmap_path, object_hash).ok()?;
+            output::count::objects_from_bitmap(
+                &bitmap,
+                index,
+                order,
+                &self.db,
+                request.wants.iter().copied(),
+                request.common.iter().copied(),
+            )
+            .ok()
+        };
+
+        let mut counts = gix_pack::multi_index::File::at(pack_dir.join("multi-pack-index"))
+            .ok()
+            .and_then(|index| {
+                let bitmap_path = pack_dir.join(format!("multi-pack-index-{}.bitmap", index.checksum()));
+                count(&bitmap_path, &index, &bitmap::Order::from_multi_index(&index).ok()?)
+            });
+        if counts.is_none() {
+            let mut bitmap_paths = std::fs::read_dir(&pack_dir).into_iter().flatten().filter_map(|entry| {
+                let path = entry.ok()?.path();
+                (path.extension()? == "bitmap" && path.file_name()?.to_str()?.starts_with("pack-")).then_some(path)
+            });
+            counts = bitmap_paths.find_map(|bitmap_path| {
+                let index = gix_pack::index::File::at(bitmap_path.with_extension("idx"), object_hash).ok()?;
+                count(&bitmap_path, &index, &bitmap::Order::from_index(&index))
+            });
+        }
+        let Some(mut counts) = counts else {
+            return Ok(None);
+        };
+
+        if request.include_tag {
+            let sent: gix_hashtable::HashSet<_> = counts.iter().map(|count| count.id).collect();
+            for tag in self.refs()?.into_iter().filter_map(|r| match r {
+                Ref::Peeled { tag, object, .. } if sent.contains(&object) && !sent.contains(&tag) => Some(tag),
+                _ => None,
+            }) {
+                counts.push(output::Count {
+                    id: tag,
+                    entry_pack_location: output::count::PackLocation::NotLookedUp,
+                    no_delta: false,
+                });
+            }
+        }
+        Ok(Some(counts))
+    }
+
+    /// Return `(tag, object)` where `tag` is set if `id` is an annotated tag, with `object` being what it ultimately points to.
+    fn peel(&self, id: ObjectId) -> Result<(Option<ObjectId>, ObjectId), BoxError> {
+        let object = self.repo.find_object(id)?;
+        Ok(match object.kind {
+            gix_object::Kind::Tag => (Some(id), object.peel_tags_to_end()?.id),
+            _ => (None, id),
+        })
+    }
+
+    fn to_ref(&self, full_ref_name: crate::bstr::BString, id: ObjectId) -> Result<Ref, BoxError> {
+        Ok(match self.peel(id)? {
+            (Some(tag), object) => Ref::Peeled {
+                full_ref_name,
+                tag,
+                object,
+            },
+            (None, object) => Ref::Direct { full_ref_name, object },
+        })
+    }
+}
+
+impl Delegate for RepositoryDelegate<'_> {
+    fn refs(&mut self) -> Result<Vec<Ref>, BoxError> {
+        let mut out = Vec::new();
+        let head = self.repo.head()?;
+        match (head.referent_name(), head.id()) {
+            (Some(target), Some(id)) => {
+                let (tag, object) = self.peel(id.detach())?;
+                out.push(Ref::Symbolic {
+                    full_ref_name: "HEAD".into(),
+                    target: target.as_bstr().to_owned(),
+                    tag,
+                    object,
+                });
+            }
+            (Some(target), None) => out.push(Ref::Unborn {
+                full_ref_name: "HEAD".into(),
+                target: target.as_bstr().to_owned(),
+            }),
+            (None, Some(id)) => out.push(self.to_ref("HEAD".into(), id.detach())?),
+            (None, None) => {}
+        }
+        for reference in self.repo.references()?.all()? {
+            let mut reference = reference?;
+            let id = reference.follow_to_object()?.detach();
+            out.push(self.to_ref(reference.name().as_bstr().to_owned(), id)?);
+        }
+        Ok(out)
+    }
+
+    fn contains(&mut self, id: &gix_hash::oid) -> bool {
+        self.repo.has_object(id)
+    }
+
+    fn wants_reach_common(&mut self, wants: &[ObjectId], common: &[ObjectId]) -> Result<bool, BoxError> {
+        let repo = self.repo;
+        let mut common_commits = gix_hashtable::HashSet::default();
+        let mut oldest = None;
+        for id in common {
+            if let Ok(commit) = repo.find_object(*id)?.peel_to_kind(gix_object::Kind::Commit) {
+                common_commits.insert(commit.id);
+                let time = commit.into_commit().time()?.seconds;
+                oldest = Some(oldest.map_or(time, |oldest: gix_date::SecondsSinceUnixEpoch| oldest.min(time)));
+            }
+        }
+        let Some(oldest) = oldest else {
+            return Ok(false);
+        };
+        for id in wants {
+            let Ok(commit) = repo.find_object(*id)?.peel_to_kind(gix_object::Kind::Commit) else {
+                continue;
+            };
+            // Like `git`, don't look at commits older than the oldest common one. Clock skew may make us miss a common
+            // ancestor, which only means that the client keeps negotiating.
+            let mut reaches_common = false;
+            for info in repo
+                .rev_walk(Some(commit.id))
+                .sorting(crate::revision::walk::Sorting::ByCommitTimeCutoff {
+                    order: Default::default(),
+                    seconds: oldest,
+                })
+                .all()?
+            {
+                if common_commits.contains(&info?.id) {
+                    reaches_common = true;
+                    break;
+                }
+            }
+            if !reaches_common {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    fn write_pack(
+        &mut self,
+        request: PackRequest<'_>,
+        out: &mut dyn std::io::Write,
+        progress: &mut dyn std::io::Write,
+    ) -> Result<(), BoxError> {
+        let repo = self.repo;
+        let mode = repo.config.pack_generation_mode()?;
+        let counts = match self.count_with_bitmap(&request)? {
+            Some(counts) => counts,
+            None => self.count_by_traversal(&request, mode)?,
+        };
+        writeln!(progress, "Enumerating objects: {}, done.", counts.len())?;
+
+        let num_entries = counts.len() as u32;
+        let entries = InOrderIter::from(output::entry::iter_from_counts(
+            counts,
+            self.db.clone(),
+            Box::new(gix_features::progress::Discard),
+            output::entry::iter_from_counts::Options {
+                allow_thin_pack: false,
+                mode,
+                ..Default::default()
+            },
+        ));
+        for written in output::bytes::FromEntriesIter::new(
+            entries,
+            out,
+            num_entries,
+            gix_pack::data::Version::V2,
+            repo.object_hash(),
+        ) {
+            written?;
+        }
+        Ok(())
+    }
+}
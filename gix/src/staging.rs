@@ -0,0 +1,120 @@
+//! Add worktree files to the index or remove them from it, similar to `git add` and `git rm`.
+//!
+//! The methods to do so operate on an index in memory which is not written back to disk, see
+//! [`Repository::add_to_index()`](crate::Repository::add_to_index()) and
+//! [`Repository::remove_from_index()`](crate::Repository::remove_from_index()).
+#![allow(clippy::empty_docs)]
+
+///
+pub mod add {
+    use crate::bstr::BString;
+
+    /// Options for [`Repository::add_to_index()`](crate::Repository::add_to_index()).
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct Options {
+        /// If `true`, only tracked files are updated with the worktree, which includes removing them if they were deleted,
+        /// while untracked files are left alone, like `git add --update`.
+        pub update: bool,
+        /// If `true`, untracked files are recorded with an empty blob to mark that they will be added later, like
+        /// `git add --intent-to-add`. Tracked files are left alone.
+        pub intent_to_add: bool,
+        /// If `true`, ignored files are added as well, like `git add --force`.
+        pub force: bool,
+    }
+
+    /// The outcome of [`Repository::add_to_index()`](crate::Repository::add_to_index()).
+    #[derive(Debug, Default, Clone)]
+    pub struct Outcome {
+        /// The paths of the entries that were newly added to the index.
+        pub added: Vec<BString>,
+        /// The paths of tracked entries whose content or mode changed.
+        pub modified: Vec<BString>,
+        /// The paths of tracked entries that were removed as they were deleted from the worktree.
+        pub removed: Vec<BString>,
+        /// The paths of ignored files or directories that were explicitly named by a pattern, but weren't added
+        /// as [`Options::force`] wasn't set.
+        pub ignored: Vec<BString>,
+    }
+
+    /// The error returned by [`Repository::add_to_index()`](crate::Repository::add_to_index()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no files to add", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error(transparent)]
+        DirwalkOptions(#[from] crate::config::boolean::Error),
+        #[error(transparent)]
+        Dirwalk(#[from] crate::dirwalk::Error),
+        #[error(transparent)]
+        Pathspec(#[from] crate::pathspec::init::Error),
+        #[error(transparent)]
+        StatOptions(#[from] crate::config::stat_options::Error),
+        #[error(transparent)]
+        FilterPipeline(#[from] crate::repository::filter::pipeline::Error),
+        #[error(transparent)]
+        WorktreeFileToObject(#[from] crate::filter::pipeline::worktree_file_to_object::Error),
+        #[error("Could not obtain metadata of '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+pub mod remove {
+    use crate::bstr::BString;
+
+    /// Options for [`Repository::remove_from_index()`](crate::Repository::remove_from_index()).
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct Options {
+        /// If `true`, only entries are removed from the index while their files are kept in the worktree, like `git rm --cached`.
+        pub cached: bool,
+        /// If `true`, entries are removed even if their files were modified, or if they have changes that are staged in the index,
+        /// like `git rm --force`.
+        pub force: bool,
+    }
+
+    /// The outcome of [`Repository::remove_from_index()`](crate::Repository::remove_from_index()).
+    #[derive(Debug, Default, Clone)]
+    pub struct Outcome {
+        /// The paths of the entries that were removed from the index.
+        pub removed: Vec<BString>,
+        /// The amount of files that were deleted from the worktree.
+        pub files_removed: usize,
+    }
+
+    /// The error returned by [`Repository::remove_from_index()`](crate::Repository::remove_from_index()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no files to remove", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error(transparent)]
+        Pathspec(#[from] crate::pathspec::init::Error),
+        #[error(transparent)]
+        StatOptions(#[from] crate::config::stat_options::Error),
+        #[error(transparent)]
+        HeadTreeId(#[from] crate::reference::head_tree_id::Error),
+        #[error(transparent)]
+        IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+        #[error(transparent)]
+        FilterPipeline(#[from] crate::repository::filter::pipeline::Error),
+        #[error(transparent)]
+        ConvertToGit(#[from] crate::filter::pipeline::convert_to_git::Error),
+        #[error(transparent)]
+        ComputeHash(#[from] gix_hash::hasher::Error),
+        #[error("The following files have local modifications or changes staged in the index: {paths:?}")]
+        LocalModifications { paths: Vec<BString> },
+        #[error("Could not read or remove '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
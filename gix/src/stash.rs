@@ -0,0 +1,255 @@
+//! Stashes are commits of the changes to the index and the worktree, kept in the reflog of [`REFERENCE`](crate::stash::REFERENCE) so they can be
+//! applied again later.
+//!
+//! Like with `git`, a stash commit has the commit of `HEAD` at the time of stashing as first parent and a commit
+//! of the index as second parent. If untracked files were stashed, a third parent holds them in its tree.
+#![allow(clippy::empty_docs)]
+use crate::bstr::BString;
+
+/// The name of the reference that points to the most recent stash, with all stashes in its reflog.
+pub const REFERENCE: &str = "refs/stash";
+
+/// A stash as listed by [`Repository::stash_list()`](crate::Repository::stash_list()).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The id of the stash commit.
+    pub id: gix_hash::ObjectId,
+    /// The message of the stash, like `WIP on main: 9b7f1a2 subject`.
+    pub message: BString,
+    /// The time at which the stash was created.
+    pub time: gix_date::Time,
+}
+
+///
+pub mod create {
+    use crate::bstr::BString;
+
+    /// Options for [`Repository::stash_create()`](crate::Repository::stash_create())
+    /// and [`Repository::stash_save()`](crate::Repository::stash_save()).
+    #[derive(Debug, Default, Clone)]
+    pub struct Options {
+        /// The message to use instead of the summary of the `HEAD` commit, like `git stash push -m <message>`.
+        pub message: Option<BString>,
+        /// If `true`, untracked files are stashed as well, like `git stash push --include-untracked`.
+        /// Ignored files are never stashed.
+        pub include_untracked: bool,
+    }
+
+    /// The error returned by [`Repository::stash_create()`](crate::Repository::stash_create()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no changes to stash", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error("Cannot stash changes without an initial commit")]
+        HeadCommit(#[from] crate::reference::head_commit::Error),
+        #[error(transparent)]
+        HeadName(#[from] crate::reference::find::existing::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        OpenIndex(#[from] crate::worktree::open_index::Error),
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error("Cannot stash changes while the index has conflicts")]
+        UnmergedIndex,
+        #[error(transparent)]
+        Status(#[from] crate::status::Error),
+        #[error(transparent)]
+        StatusIter(#[from] crate::status::into_iter::Error),
+        #[error(transparent)]
+        StatusItem(#[from] crate::status::index_worktree::Error),
+        #[error(transparent)]
+        FilterPipeline(#[from] crate::repository::filter::pipeline::Error),
+        #[error(transparent)]
+        WorktreeFileToObject(#[from] crate::filter::pipeline::worktree_file_to_object::Error),
+        #[error(transparent)]
+        EditTreeInit(#[from] crate::object::tree::editor::init::Error),
+        #[error(transparent)]
+        EditTree(#[from] gix_object::tree::editor::Error),
+        #[error(transparent)]
+        WriteTree(#[from] crate::object::tree::editor::write::Error),
+        #[error(transparent)]
+        WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+        #[error(transparent)]
+        NewCommit(#[from] crate::repository::new_commit::Error),
+    }
+}
+
+///
+pub mod save {
+    /// The error returned by [`Repository::stash_save()`](crate::Repository::stash_save()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Create(#[from] super::create::Error),
+        #[error(transparent)]
+        Store(#[from] super::store::Error),
+        #[error(transparent)]
+        Reset(#[from] super::reset::Error),
+    }
+}
+
+///
+pub mod store {
+    /// The error returned by [`Repository::stash_store()`](crate::Repository::stash_store()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error("{id} is not a stash commit as it has less than two parents")]
+        NotAStash { id: gix_hash::ObjectId },
+        #[error(transparent)]
+        EditReference(#[from] crate::reference::edit::Error),
+    }
+}
+
+///
+pub mod reset {
+    /// The error returned when the index or the worktree couldn't be changed after stashing or applying a stash.
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+        #[error(transparent)]
+        SparseCheckoutPatterns(#[from] crate::repository::sparse_checkout_patterns::Error),
+        #[error("Could not collapse the index into a sparse index")]
+        CollapseSparseIndex(#[from] crate::repository::collapse_sparse_index::Error),
+        #[error(transparent)]
+        UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+        #[error(transparent)]
+        CheckoutOptions(#[from] crate::config::checkout_options::Error),
+        #[error(transparent)]
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+        #[error(transparent)]
+        WriteIndex(#[from] gix_index::file::write::Error),
+        #[error("Could not remove '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+pub mod list {
+    /// The error returned by [`Repository::stash_list()`](crate::Repository::stash_list()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        FindReference(#[from] crate::reference::find::Error),
+        #[error("Could not read the reflog of the stash reference")]
+        Io(#[from] std::io::Error),
+        #[error(transparent)]
+        ReadReflog(#[from] gix_ref::file::log::iter::reverse::Error),
+    }
+}
+
+///
+pub mod apply {
+    use crate::bstr::BString;
+
+    /// Options for [`Repository::stash_apply()`](crate::Repository::stash_apply())
+    /// and [`Repository::stash_pop()`](crate::Repository::stash_pop()).
+    #[derive(Debug, Default, Copy, Clone)]
+    pub struct Options {
+        /// If `true`, the changes to the index are restored as well instead of only the changes to the worktree,
+        /// like `git stash apply --index`.
+        pub index: bool,
+    }
+
+    /// The outcome of [`Repository::stash_apply()`](crate::Repository::stash_apply())
+    /// and [`Repository::stash_pop()`](crate::Repository::stash_pop()).
+    #[derive(Debug)]
+    pub struct Outcome {
+        /// The id of the applied stash commit.
+        pub id: gix_hash::ObjectId,
+        /// The unresolved conflicts of merging the stashed changes into the worktree. Their stages were written to the
+        /// index, and conflict markers to the worktree.
+        pub conflicts: Vec<crate::merge::tree::Conflict>,
+        /// The outcome of updating the worktree.
+        pub worktree: crate::repository::update_worktree::Outcome,
+    }
+
+    /// The error returned by [`Repository::stash_apply()`](crate::Repository::stash_apply()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Repository at \"{}\" is a bare repository and has no worktree to apply a stash to", git_dir.display())]
+        BareRepository { git_dir: std::path::PathBuf },
+        #[error(transparent)]
+        List(#[from] super::list::Error),
+        #[error("There is no stash at index {index}")]
+        NoSuchStash { index: usize },
+        #[error(transparent)]
+        FindCommit(#[from] crate::object::find::existing::with_conversion::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error("{id} is not a stash commit as it has less than two parents")]
+        NotAStash { id: gix_hash::ObjectId },
+        #[error(transparent)]
+        OpenIndex(#[from] crate::worktree::open_index::Error),
+        #[error("Could not expand the sparse index")]
+        ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
+        #[error("Cannot apply a stash while the index has conflicts")]
+        UnmergedIndex,
+        #[error(transparent)]
+        WriteTree(#[from] crate::object::tree::editor::write::Error),
+        #[error(transparent)]
+        WriteTreeFromIndex(#[from] gix_index::write_tree::Error),
+        #[error(transparent)]
+        TreeMergeOptions(#[from] crate::repository::tree_merge_options::Error),
+        #[error(transparent)]
+        MergeTrees(#[from] crate::repository::merge_trees::Error),
+        #[error("The changes to the index conflict with the current index, try again without restoring the index")]
+        IndexConflicts,
+        #[error("Untracked file '{}' already exists and would be overwritten by the stashed untracked files", path.display())]
+        UntrackedFileExists { path: std::path::PathBuf },
+        #[error("The local changes to these files would be overwritten: {paths:?}")]
+        WouldOverwriteLocalChanges { paths: Vec<BString> },
+        #[error(transparent)]
+        Status(#[from] crate::status::Error),
+        #[error(transparent)]
+        StatusIter(#[from] crate::status::into_iter::Error),
+        #[error(transparent)]
+        StatusItem(#[from] crate::status::index_worktree::Error),
+        #[error(transparent)]
+        Reset(#[from] super::reset::Error),
+    }
+}
+
+///
+pub mod pop {
+    /// The error returned by [`Repository::stash_pop()`](crate::Repository::stash_pop()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error(transparent)]
+        Apply(#[from] super::apply::Error),
+        #[error(transparent)]
+        Drop(#[from] super::drop::Error),
+    }
+}
+
+///
+pub mod drop {
+    /// The error returned by [`Repository::stash_drop()`](crate::Repository::stash_drop()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("There is no stash at index {index}")]
+        NoSuchStash { index: usize },
+        #[error(transparent)]
+        LockTimeout(#[from] crate::config::lock_timeout::Error),
+        #[error(transparent)]
+        RetainReflog(#[from] gix_ref::file::log::retain::Error),
+        #[error(transparent)]
+        List(#[from] super::list::Error),
+        #[error(transparent)]
+        EditReference(#[from] crate::reference::edit::Error),
+    }
+}
@@ -4,7 +4,6 @@ use gix_status::index_as_worktree::{Change, EntryStatus};
 
 use crate::{
     bstr::BString,
-    config::cache::util::ApplyLeniencyDefault,
     status::{index_worktree, index_worktree::BuiltinSubmoduleStatus, tree_index, Platform},
     worktree::IndexPersistedOrInMemory,
 };
@@ -54,15 +53,7 @@ where
             })
         };
 
-        let skip_hash = self
-            .repo
-            .config
-            .resolved
-            .boolean(crate::config::tree::Index::SKIP_HASH)
-            .map(|res| crate::config::tree::Index::SKIP_HASH.enrich_error(res))
-            .transpose()
-            .with_lenient_default(self.repo.config.lenient_config)?
-            .unwrap_or_default();
+        let write_options = self.repo.index_write_options(&index)?;
         let should_interrupt = self.should_interrupt.clone().unwrap_or_default();
         let submodule = BuiltinSubmoduleStatus::new(self.repo.clone().into_sync(), self.submodules)?;
         #[cfg(feature = "parallel")]
@@ -140,7 +131,7 @@ where
                             tree_index: None,
                             worktree_index: index,
                             changes: None,
-                            skip_hash,
+                            write_options,
                         })
                     }
                 })
@@ -208,7 +199,7 @@ where
                 worktree_index: index,
                 tree_index,
                 changes: None,
-                skip_hash,
+                write_options,
             };
             items.extend(
                 collect
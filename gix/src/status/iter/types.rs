@@ -69,7 +69,7 @@ pub struct Outcome {
     pub tree_index: Option<tree_index::Outcome>,
     /// The worktree index that was used for the operation.
     pub worktree_index: IndexPersistedOrInMemory,
-    pub(super) skip_hash: bool,
+    pub(super) write_options: crate::index::write::Options,
     pub(super) changes: Option<Vec<(usize, ApplyChange)>>,
 }
 
@@ -105,10 +105,7 @@ impl Outcome {
             }
         }
 
-        Some(index.write(crate::index::write::Options {
-            extensions: Default::default(),
-            skip_hash: self.skip_hash,
-        }))
+        Some(index.write(self.write_options))
     }
 }
 
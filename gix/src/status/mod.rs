@@ -216,7 +216,7 @@ pub mod into_iter {
         #[cfg(not(feature = "parallel"))]
         IndexWorktreeStatus(#[from] crate::status::index_worktree::Error),
         #[error(transparent)]
-        ConfigSkipHash(#[from] crate::config::boolean::Error),
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
         #[error(transparent)]
         PrepareSubmodules(#[from] crate::submodule::modules::Error),
         #[error("Could not create an index for the head tree to compare with the worktree index")]
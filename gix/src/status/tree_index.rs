@@ -12,6 +12,8 @@ pub enum Error {
     DiffResourceCache(#[from] crate::repository::diff_resource_cache::Error),
     #[error(transparent)]
     TreeIndexDiff(#[from] gix_diff::index::Error),
+    #[error("Could not expand the sparse index for diffing")]
+    ExpandSparseIndex(#[from] gix_index::sparse::expand::Error),
 }
 
 /// Specify how to perform rewrite tracking [Repository::tree_index_status()].
@@ -46,6 +48,7 @@ impl Repository {
     /// full access to both indices that contributed to the change.
     ///
     /// *(It's notable that internally, the `tree_id` is converted into an index before diffing these)*.
+    /// If `worktree_index` is [sparse](gix_index::State::is_sparse()), a copy of it with all sparse directories expanded is diffed instead.
     /// Set `pathspec` to `Some(_)` to further reduce the set of files to check.
     ///
     /// ### Notes
@@ -69,6 +72,15 @@ impl Repository {
     {
         let _span = gix_trace::coarse!("gix::tree_index_status");
         let tree_index: gix_index::State = self.index_from_tree(tree_id)?.into();
+        let expanded_worktree_index;
+        let worktree_index = if worktree_index.is_sparse() {
+            let mut index = worktree_index.clone();
+            index.expand_all_sparse_directories(self)?;
+            expanded_worktree_index = index;
+            &expanded_worktree_index
+        } else {
+            worktree_index
+        };
         let rewrites = match renames {
             TrackRenames::AsConfigured => {
                 let (mut rewrites, mut is_configured) = crate::diff::utils::new_rewrites_inner(
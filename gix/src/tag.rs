@@ -14,6 +14,15 @@ mod error {
         ReferenceEdit(#[from] crate::reference::edit::Error),
         #[error(transparent)]
         DateParseError(#[from] gix_date::Error),
+        #[cfg(feature = "command")]
+        #[error(transparent)]
+        ConfigBoolean(#[from] crate::config::boolean::Error),
+        #[cfg(feature = "command")]
+        #[error(transparent)]
+        Signer(#[from] crate::gpg::signer::Error),
+        #[cfg(feature = "command")]
+        #[error("Could not sign the tag")]
+        Sign(#[from] crate::gpg::sign::Error),
     }
 }
 pub use error::Error;
@@ -282,3 +282,148 @@ pub mod pathspec {
         }
     }
 }
+
+///
+#[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+pub mod add {
+    use crate::bstr::BString;
+
+    /// Options for [`Repository::worktree_add()`](crate::Repository::worktree_add()).
+    #[derive(Debug, Default, Clone)]
+    pub struct Options {
+        /// If set, create a branch of this name at the commit to check out and check it out in the new worktree,
+        /// like `git worktree add -b <branch>`.
+        pub new_branch: Option<BString>,
+        /// If `true`, check out the commit with a detached `HEAD`, even if it was named by a branch, like `git worktree add --detach`.
+        pub detach: bool,
+        /// If `true`, check out a branch even if it's already checked out in another worktree, and reset
+        /// [`new_branch`](Self::new_branch) if it already exists, like `git worktree add --force` and `-B` respectively.
+        pub force: bool,
+        /// If set, lock the new worktree with the given reason, like `git worktree add --lock --reason <reason>`.
+        pub lock_reason: Option<BString>,
+        /// If `true`, set up the new worktree without checking out any files or writing an index, like `git worktree add --no-checkout`.
+        pub no_checkout: bool,
+    }
+
+    /// The error returned by [`Repository::worktree_add()`](crate::Repository::worktree_add()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("'{}' already exists and is not an empty directory", path.display())]
+        PathExists { path: std::path::PathBuf },
+        #[error("A branch named '{name}' already exists")]
+        BranchExists { name: BString },
+        #[error("Branch '{name}' is already checked out at '{}'", path.display())]
+        BranchCheckedOut { name: BString, path: std::path::PathBuf },
+        #[error(transparent)]
+        ValidateBranchName(#[from] gix_validate::reference::name::Error),
+        #[error(transparent)]
+        RevParse(#[from] crate::revision::spec::parse::single::Error),
+        #[error(transparent)]
+        HeadCommit(#[from] crate::reference::head_commit::Error),
+        #[error(transparent)]
+        FindReference(#[from] crate::reference::find::Error),
+        #[error(transparent)]
+        PeelToCommit(#[from] crate::object::peel::to_kind::Error),
+        #[error(transparent)]
+        PeelReference(#[from] crate::reference::peel::to_kind::Error),
+        #[error(transparent)]
+        FindObject(#[from] crate::object::find::existing::Error),
+        #[error(transparent)]
+        DecodeCommit(#[from] gix_object::decode::Error),
+        #[error(transparent)]
+        EditReference(#[from] crate::reference::edit::Error),
+        #[error(transparent)]
+        MainRepo(#[from] crate::open::Error),
+        #[error(transparent)]
+        OpenWorktree(#[from] crate::worktree::proxy::into_repo::Error),
+        #[error(transparent)]
+        IndexFromTree(#[from] crate::repository::index_from_tree::Error),
+        #[error(transparent)]
+        UpdateWorktree(#[from] crate::repository::update_worktree::Error),
+        #[error(transparent)]
+        IndexWriteOptions(#[from] crate::repository::index_write_options::Error),
+        #[error(transparent)]
+        WriteIndex(#[from] gix_index::file::write::Error),
+        #[error(transparent)]
+        FindHook(#[from] crate::hook::find::Error),
+        #[error("The worktree at '{}' was added, but the post-checkout hook failed", path.display())]
+        PostCheckoutHook {
+            path: std::path::PathBuf,
+            source: crate::hook::run::Error,
+        },
+        #[error("Could not set up the worktree at '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+pub mod prune {
+    use crate::{bstr::BString, worktree::proxy::Prunable};
+
+    /// Options for [`Repository::worktrees_prune()`](crate::Repository::worktrees_prune()).
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Options {
+        /// If `true`, only report what would be pruned without removing anything, like `git worktree prune --dry-run`.
+        pub dry_run: bool,
+        /// If set, worktrees whose location doesn't exist anymore are only pruned if their `gitdir` file was last modified
+        /// before this time, like `git worktree prune --expire <time>`. Otherwise, they are pruned right away.
+        pub expire: Option<std::time::SystemTime>,
+    }
+
+    /// A worktree administrative directory that was pruned by [`Repository::worktrees_prune()`](crate::Repository::worktrees_prune()).
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Pruned {
+        /// The name of the administrative directory within `worktrees/`, which is the id of the worktree.
+        pub id: BString,
+        /// The reason for pruning it.
+        pub reason: Prunable,
+    }
+
+    /// The error returned by [`Repository::worktrees_prune()`](crate::Repository::worktrees_prune()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Could not prune the worktree administrative directory at '{}'", path.display())]
+        Io {
+            path: std::path::PathBuf,
+            source: std::io::Error,
+        },
+    }
+}
+
+///
+pub mod repair {
+    use std::path::PathBuf;
+
+    /// What was repaired by [`Repository::worktrees_repair()`](crate::Repository::worktrees_repair()).
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum Kind {
+        /// The `.git` file in the worktree was rewritten to point to the administrative directory of the worktree again.
+        DotGitFile,
+        /// The `gitdir` file in the administrative directory of the worktree was rewritten to point to the worktree again.
+        GitDirFile,
+    }
+
+    /// A repair made by [`Repository::worktrees_repair()`](crate::Repository::worktrees_repair()).
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct Repaired {
+        /// The location of the worktree.
+        pub path: PathBuf,
+        /// The file that was rewritten.
+        pub kind: Kind,
+    }
+
+    /// The error returned by [`Repository::worktrees_repair()`](crate::Repository::worktrees_repair()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("'{}' is not a linked worktree of this repository", path.display())]
+        NotALinkedWorktree { path: PathBuf },
+        #[error("Could not repair the worktree at '{}'", path.display())]
+        Io { path: PathBuf, source: std::io::Error },
+    }
+}
@@ -24,6 +24,135 @@ pub mod into_repo {
     }
 }
 
+///
+pub mod lock {
+    use crate::bstr::BString;
+
+    /// The error returned by [`Proxy::lock()`](super::Proxy::lock()) and [`Proxy::unlock()`](super::Proxy::unlock()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Worktree '{id}' is already locked")]
+        AlreadyLocked { id: BString },
+        #[error("Worktree '{id}' is not locked")]
+        NotLocked { id: BString },
+        #[error(transparent)]
+        Io(#[from] std::io::Error),
+    }
+}
+
+///
+pub mod move_to {
+    use std::path::PathBuf;
+
+    use crate::bstr::BString;
+
+    /// The error returned by [`Proxy::move_to()`](super::Proxy::move_to()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Worktree '{id}' is locked and can only be moved by force")]
+        Locked { id: BString },
+        #[error("Worktree at '{}' is inaccessible", .base.display())]
+        MissingWorktree { base: PathBuf },
+        #[error("'{}' already exists", path.display())]
+        DestinationExists { path: PathBuf },
+        #[error(transparent)]
+        MissingGitDirFile(#[from] std::io::Error),
+        #[error(transparent)]
+        RealPath(#[from] gix_path::realpath::Error),
+        #[error("Could not move the worktree to '{}'", path.display())]
+        Io { path: PathBuf, source: std::io::Error },
+    }
+}
+
+///
+#[cfg(feature = "status")]
+pub mod remove {
+    use std::path::PathBuf;
+
+    use crate::bstr::BString;
+
+    /// Options for [`Proxy::remove()`](super::Proxy::remove()).
+    #[derive(Debug, Default, Clone, Copy)]
+    pub struct Options {
+        /// If `true`, remove the worktree even if it has modified, staged or untracked files, like `git worktree remove --force`.
+        pub force: bool,
+        /// If `true`, remove the worktree even if it is locked, like `git worktree remove --force --force`.
+        pub force_locked: bool,
+    }
+
+    /// The error returned by [`Proxy::remove()`](super::Proxy::remove()).
+    #[derive(Debug, thiserror::Error)]
+    #[allow(missing_docs)]
+    pub enum Error {
+        #[error("Worktree '{id}' is locked and can only be removed by force")]
+        Locked { id: BString },
+        #[error("Worktree '{id}' contains modified or untracked files and can only be removed by force")]
+        Modified { id: BString },
+        #[error(transparent)]
+        OpenWorktree(#[from] super::into_repo::Error),
+        #[error(transparent)]
+        Status(#[from] crate::status::Error),
+        #[error(transparent)]
+        StatusIter(#[from] crate::status::into_iter::Error),
+        #[error(transparent)]
+        StatusItem(#[from] crate::status::iter::Error),
+        #[error("Could not remove '{}'", path.display())]
+        Io { path: PathBuf, source: std::io::Error },
+    }
+}
+
+/// The reason for a worktree to be prunable, as returned by [`Proxy::prunable()`].
+///
+/// Its [`Display`](std::fmt::Display) implementation matches the reasons given by `git worktree list --porcelain`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Prunable {
+    /// The administrative directory of the worktree isn't a directory.
+    NotADirectory,
+    /// The `gitdir` file pointing to the worktree doesn't exist.
+    MissingGitDirFile,
+    /// The `gitdir` file pointing to the worktree is empty or can't be read.
+    InvalidGitDirFile,
+    /// The `gitdir` file points to a location that doesn't exist, typically because the worktree was deleted.
+    GitDirFilePointsToNonExistentLocation,
+}
+
+impl std::fmt::Display for Prunable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Prunable::NotADirectory => "not a valid directory",
+            Prunable::MissingGitDirFile => "gitdir file does not exist",
+            Prunable::InvalidGitDirFile => "invalid gitdir file",
+            Prunable::GitDirFilePointsToNonExistentLocation => "gitdir file points to non-existent location",
+        })
+    }
+}
+
+/// Return the reason for the worktree with the administrative directory `git_dir` to be pruned, if any, like `git` does.
+/// If `expire` is set, worktrees whose location doesn't exist are only prunable if their `gitdir` file was modified before that.
+pub(crate) fn prune_reason(git_dir: &Path, expire: Option<std::time::SystemTime>) -> Option<Prunable> {
+    if !git_dir.is_dir() {
+        return Some(Prunable::NotADirectory);
+    }
+    if git_dir.join("locked").exists() {
+        return None;
+    }
+    let gitdir_file = git_dir.join("gitdir");
+    let Ok(meta) = std::fs::metadata(&gitdir_file) else {
+        return Some(Prunable::MissingGitDirFile);
+    };
+    let location = match std::fs::read(&gitdir_file) {
+        Ok(content) if !content.trim().is_empty() => gix_path::from_bstring(BString::from(content.trim())),
+        _ => return Some(Prunable::InvalidGitDirFile),
+    };
+    if git_dir.join(location).exists() {
+        return None;
+    }
+    let is_expired = expire.is_none_or(|expire| meta.modified().is_ok_and(|modified| modified <= expire));
+    is_expired.then_some(Prunable::GitDirFilePointsToNonExistentLocation)
+}
+
 impl<'repo> Proxy<'repo> {
     pub(crate) fn new(parent: &'repo Repository, git_dir: impl Into<PathBuf>) -> Self {
         Proxy {
@@ -83,6 +212,108 @@ impl Proxy<'_> {
             .map(|contents| contents.trim().into())
     }
 
+    /// Return the reason for this worktree to be removed by [`Repository::worktrees_prune()`], or `None` if it's still in use.
+    ///
+    /// Locked worktrees are never prunable.
+    pub fn prunable(&self) -> Option<Prunable> {
+        prune_reason(&self.git_dir, None)
+    }
+
+    /// Lock this worktree with `reason`, which may be empty, to prevent it from being pruned, moved or removed, like `git worktree lock`.
+    pub fn lock(&self, reason: &BStr) -> Result<(), lock::Error> {
+        if self.is_locked() {
+            return Err(lock::Error::AlreadyLocked { id: self.id().into() });
+        }
+        let mut content = BString::from(reason);
+        content.push(b'\n');
+        std::fs::write(self.git_dir.join("locked"), content)?;
+        Ok(())
+    }
+
+    /// Unlock this worktree so it can be pruned, moved or removed again, like `git worktree unlock`.
+    pub fn unlock(&self) -> Result<(), lock::Error> {
+        if !self.is_locked() {
+            return Err(lock::Error::NotLocked { id: self.id().into() });
+        }
+        std::fs::remove_file(self.git_dir.join("locked"))?;
+        Ok(())
+    }
+
+    /// Move the checkout of this worktree to `destination` and return its new location, like `git worktree move`.
+    ///
+    /// If `destination` is an existing directory, the worktree is moved into it, keeping its directory name.
+    /// Locked worktrees are only moved if `force` is `true`.
+    pub fn move_to(&self, destination: impl AsRef<Path>, force: bool) -> Result<PathBuf, move_to::Error> {
+        use move_to::Error;
+
+        let base = self.base()?;
+        if !base.is_dir() {
+            return Err(Error::MissingWorktree { base });
+        }
+        if self.is_locked() && !force {
+            return Err(Error::Locked { id: self.id().into() });
+        }
+        let mut destination = destination.as_ref().to_owned();
+        if destination.is_dir() {
+            destination.push(base.file_name().expect("worktrees are never the root"));
+        }
+        if destination.exists() {
+            return Err(Error::DestinationExists { path: destination });
+        }
+        if let Some(parent) = destination.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent).map_err(|source| Error::Io {
+                path: parent.to_owned(),
+                source,
+            })?;
+        }
+        std::fs::rename(&base, &destination).map_err(|source| Error::Io {
+            path: destination.clone(),
+            source,
+        })?;
+        let destination = gix_path::realpath(&destination)?;
+        write_gitdir_file(&self.git_dir, &destination).map_err(|source| Error::Io {
+            path: self.git_dir.join("gitdir"),
+            source,
+        })?;
+        Ok(destination)
+    }
+
+    /// Delete the checkout of this worktree along with its administrative directory, like `git worktree remove`.
+    ///
+    /// Worktrees with modified, staged or untracked files, or that are locked, are only removed by force as
+    /// configured in `options`. If the checkout doesn't exist anymore, only the administrative directory is removed.
+    #[cfg(feature = "status")]
+    pub fn remove(self, options: remove::Options) -> Result<(), remove::Error> {
+        use remove::Error;
+
+        if self.is_locked() && !options.force_locked {
+            return Err(Error::Locked { id: self.id().into() });
+        }
+        let base = self.base().ok().filter(|base| base.is_dir());
+        if let Some(base) = base {
+            if !options.force {
+                let repo = self.clone().into_repo()?;
+                let has_changes = repo
+                    .status(gix_features::progress::Discard)?
+                    .untracked_files(crate::status::UntrackedFiles::Files)
+                    .index_worktree_rewrites(None)
+                    .into_iter(None)?
+                    .next()
+                    .transpose()?
+                    .is_some();
+                if has_changes {
+                    return Err(Error::Modified { id: self.id().into() });
+                }
+            }
+            std::fs::remove_dir_all(&base).map_err(|source| Error::Io { path: base, source })?;
+        }
+        std::fs::remove_dir_all(&self.git_dir).map_err(|source| Error::Io {
+            path: self.git_dir.clone(),
+            source,
+        })?;
+        Ok(())
+    }
+
     /// Transform this proxy into a [`Repository`] while ignoring issues reading `base()` and ignoring that it might not exist.
     ///
     /// Most importantly, the `Repository` might be initialized with a non-existing work tree directory as the checkout
@@ -108,3 +339,10 @@ impl Proxy<'_> {
         Ok(repo.into())
     }
 }
+
+/// Write the `gitdir` file into the administrative directory `git_dir` of a worktree to point to the worktree at `base`.
+pub(crate) fn write_gitdir_file(git_dir: &Path, base: &Path) -> std::io::Result<()> {
+    let mut content = gix_path::into_bstr(base.join(gix_discover::DOT_GIT_DIR)).into_owned();
+    content.push(b'\n');
+    std::fs::write(git_dir.join("gitdir"), content)
+}
@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+printf '1\n2\n3\n' > lines
+echo base > file
+git add .
+git commit -q -m "base"
+
+git checkout -q -b feature
+echo new > new-file
+git add new-file
+GIT_AUTHOR_NAME="Feature Author" GIT_AUTHOR_EMAIL="feature@example.com" \
+  git commit -q -m "add new-file" -m "Signed-off-by: Feature Author <feature@example.com>"
+printf 'one\n2\n3\n' > lines
+git commit -q -am "change first line"
+
+git checkout -q -b topic main
+echo topic > topic-file
+git add topic-file
+git commit -q -m "add topic-file"
+git checkout -q -b merged main
+git merge -q --no-ff -m "merge topic" topic
+
+git checkout -q main
+printf 'ONE\n2\n3\n' > lines
+git commit -q -am "change first line on main"
+echo changed > file
+git commit -q -am "change file on main"
+
+git checkout -q -b expected-pick main
+git cherry-pick -x feature~1
+git checkout -q -b expected-merge-pick main
+git cherry-pick -m 1 merged
+git checkout -q -b expected-revert main
+git revert --no-edit main~1
+
+git checkout -q main
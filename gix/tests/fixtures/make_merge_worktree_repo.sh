@@ -0,0 +1,40 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+printf '1\n2\n3\n' > lines
+echo base > file
+git add .
+git commit -q -m "base"
+
+git checkout -q -b feature
+echo new > new-file
+git add new-file
+git commit -q -m "add new-file"
+printf 'one\n2\n3\n' > lines
+git commit -q -am "change first line"
+
+git checkout -q -b conflicting main
+echo conflicting > file
+git commit -q -am "change file"
+
+git checkout -q main
+echo changed > file
+git commit -q -am "change file on main"
+
+git checkout -q -b ahead main
+echo ahead > ahead-file
+git add ahead-file
+git commit -q -m "add ahead-file"
+
+git checkout -q main
+git merge -q --no-edit "$(git rev-parse feature)"
+git branch expected-merge
+git reset -q --hard HEAD~1
+
+git merge -q "$(git rev-parse conflicting)" >/dev/null || :
+cp .git/MERGE_MSG .git/expected-merge-msg
+cp file .git/expected-conflict-file
+git merge --abort
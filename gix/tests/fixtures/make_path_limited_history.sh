@@ -0,0 +1,114 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+git checkout -q -b main
+
+# Each commit is one second younger than the previous one so that the commit-time order is unambiguous.
+tick=0
+function tick() {
+  tick=$((tick + 1))
+  export GIT_COMMITTER_DATE="$((946684800 + tick)) +0000"
+  export GIT_AUTHOR_DATE="$GIT_COMMITTER_DATE"
+}
+
+function commit() {
+  tick
+  git add -A
+  git commit -q --allow-empty -m "$1"
+  git tag "$1"
+}
+
+# Merge `$2` and let `$3` produce the merged tree before committing it as `$1`.
+function merge() {
+  tick
+  git merge -q --no-ff --no-commit --allow-unrelated-histories "$2" >/dev/null 2>&1 || :
+  eval "$3"
+  git add -A
+  git commit -q -m "$1"
+  git tag "$1"
+}
+
+# The history used to explain history simplification in the documentation of `git log`, with `foo` as path of interest.
+mkdir -p dir/sub other
+echo asdf > foo
+echo i > bar
+echo a > dir/a
+echo b > dir/sub/b
+echo c > other/c
+commit I
+
+echo foo > foo
+commit A
+
+git checkout -q -b b-branch I
+echo foo > foo
+commit B
+
+git checkout -q -b c-branch I
+echo c > bar
+commit C
+
+git checkout -q -b d-branch I
+echo baz > foo
+commit D
+
+git checkout -q -b e-branch I
+echo e > other/c
+commit E
+
+git checkout -q --orphan x-branch
+git rm -q -rf .
+echo x > side
+commit X
+
+echo y > side
+commit Y
+
+git checkout -q main
+merge M b-branch :
+merge N c-branch :
+merge O d-branch 'echo foobarbaz > foo'
+merge P e-branch :
+merge Q x-branch :
+
+# Linear history below `dir/` with a rename at the end.
+echo a2 > dir/a
+commit R1
+echo c2 > other/c
+commit R2
+echo b2 > dir/sub/b
+commit R3
+echo readme > dir/readme.txt
+commit R4
+git mv dir/a dir/renamed
+commit R5
+echo a3 > dir/renamed
+commit R6
+
+git commit-graph write --no-progress --reachable --changed-paths
+
+function baseline() {
+  local name=${1:?first argument is the name of the baseline}
+  shift
+  git log --format=%H "$@" > ".git/baseline-$name"
+}
+
+baseline default -- foo
+baseline full-history --full-history -- foo
+baseline simplify-merges --simplify-merges --date-order -- foo
+baseline full-simplify-merges --full-history --simplify-merges --date-order -- foo
+baseline first-parent --first-parent -- foo
+baseline hidden B..main -- foo
+baseline ancestry-path --ancestry-path D..main
+baseline ancestry-path-foo --ancestry-path D..main -- foo
+baseline ancestry-path-simplify-merges --ancestry-path --simplify-merges --date-order D..main -- foo
+baseline dir -- dir
+baseline dir-slash -- dir/
+baseline glob -- 'dir/*.txt'
+baseline exclude -- dir ':!dir/sub'
+baseline multiple -- bar other
+baseline side -- side
+baseline follow --follow -- dir/renamed
+baseline follow-foo --follow -- foo
+baseline follow-first-parent --first-parent --follow -- foo
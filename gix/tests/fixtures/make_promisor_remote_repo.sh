@@ -0,0 +1,14 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  git config uploadpack.allowFilter true
+  git config uploadpack.allowAnySHA1InWant true
+
+  echo 1 > a
+  git add a && git commit -q -m c1
+  echo 2 > a
+  mkdir dir && echo b > dir/b
+  git add . && git commit -q -m c2
+)
@@ -0,0 +1,25 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q base
+(cd base
+  git checkout -b main
+  git commit -q --allow-empty -m c1
+  git branch other
+  git commit -q --allow-empty -m c2
+)
+
+git clone -q --bare base remote.git
+
+git clone -q base local
+(cd local
+  git commit -q --allow-empty -m c3
+  git checkout -q -b diverged main~2
+  git commit -q --allow-empty -m d1
+  git tag -a -m "annotated" v1
+  git checkout -q -b skewed main
+  GIT_COMMITTER_DATE="1990-01-01 00:00:00 +0000" git commit -q --allow-empty -m "older than its parents"
+  git checkout -q main
+  git remote remove origin
+  git repack -adq
+)
@@ -0,0 +1,93 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function setup() {
+  git init -q
+  git checkout -q -b main
+  echo base > file
+  git add file
+  git commit -q -m "base"
+
+  git checkout -q -b topic
+  echo a > a
+  git add a
+  git commit -q -m "add a"
+  git branch part
+  git tag part-before
+  echo b > b
+  git add b
+  git commit -q -m "add b"
+  echo a2 >> a
+  git commit -q -am "fixup! add a"
+  echo b2 >> b
+  git commit -q -am "squash! add b
+
+more about b"
+
+  git checkout -q -b conflicting main
+  echo topic > file
+  git commit -q -am "change file"
+  echo c > c
+  git add c
+  git commit -q -m "add c"
+
+  git checkout -q -b feature main
+  echo f > f
+  git add f
+  git commit -q -m "add f"
+  git checkout -q -b merges main
+  echo x > x
+  git add x
+  git commit -q -m "add x"
+  git merge -q --no-ff -m "merge feature" feature
+  echo y > y
+  git add y
+  git commit -q -m "add y"
+
+  git checkout -q main
+  echo main > file
+  echo m > m
+  git add m
+  git commit -q -am "change file on main"
+
+  git checkout -q -b expected-linear topic
+  git rebase -q main
+
+  git tag topic-before topic
+  git checkout -q -b expected-autosquash topic
+  GIT_SEQUENCE_EDITOR=true git rebase -q -i --autosquash --update-refs main
+  git branch expected-autosquash-part part
+  git branch -f part part-before
+  git branch -f topic topic-before
+
+  git checkout -q -b expected-continue conflicting
+  git rebase -q main || :
+  echo resolved > file
+  git add file
+  GIT_EDITOR=true git rebase --continue
+
+  git checkout -q -b expected-skip conflicting
+  git rebase -q main || :
+  git rebase --skip
+
+  git checkout -q -b expected-merges merges
+  GIT_SEQUENCE_EDITOR='f() { cp "$1" .git/merges-todo; }; f' git rebase -q -i --rebase-merges main
+}
+
+mkdir topic
+(cd topic
+  setup
+  git checkout -q topic
+)
+
+mkdir conflicting
+(cd conflicting
+  setup
+  git checkout -q conflicting
+)
+
+mkdir merges
+(cd merges
+  setup
+  git checkout -q merges
+)
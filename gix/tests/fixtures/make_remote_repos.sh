@@ -381,3 +381,8 @@ git init empty-core-askpass
 (cd empty-core-askpass
   echo "    askpass =" >> .git/config
 )
+
+git clone --bare --no-local base base-with-bitmap
+(cd base-with-bitmap
+  git repack -q -adb
+)
@@ -0,0 +1,48 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+function setup() {
+  git init -q
+  git checkout -q -b main
+
+  echo base > file
+  git add file
+  git commit -q -m "base"
+
+  git checkout -q -b other
+  echo a > a
+  git add a
+  git commit -q -m "add a"
+  echo other > file
+  git commit -q -am "change file"
+  echo c > c
+  git add c
+  git commit -q -m "add c"
+
+  git checkout -q main
+  echo main > file
+  git commit -q -am "change file on main"
+
+  git checkout -q -b expected-continue main
+  git cherry-pick -x main..other || :
+  echo resolved > file
+  git add file
+  GIT_EDITOR=true git cherry-pick --continue
+
+  git checkout -q -b expected-skip main
+  git cherry-pick -x main..other || :
+  git cherry-pick --skip
+
+  git checkout -q main
+}
+
+mkdir fresh
+(cd fresh
+  setup
+)
+
+mkdir started-by-git
+(cd started-by-git
+  setup
+  git cherry-pick -x main..other || :
+)
@@ -0,0 +1,10 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+mkdir -p a/b d/e
+for file in root a/file a/b/file d/file d/e/file; do
+  echo "$file" > "$file"
+done
+git add .
+git commit -q -m "init"
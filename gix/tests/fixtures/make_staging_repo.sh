@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+mkdir dir
+echo base > file
+echo a > dir/a
+echo unchanged > unchanged
+echo removed > removed
+echo "*.ignored" > .gitignore
+git add .
+git commit -q -m "init"
+
+echo modified >> file
+rm removed
+echo new > dir/new
+echo untracked > untracked
+echo ignored > file.ignored
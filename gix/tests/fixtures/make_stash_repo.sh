@@ -0,0 +1,18 @@
+#!/usr/bin/env bash
+set -eu -o pipefail
+
+git init -q
+mkdir dir
+echo base > file
+echo a > dir/a
+echo staged > staged
+echo removed > removed
+git add .
+git commit -q -m "init"
+
+echo modified >> file
+echo staged-change > staged
+echo added > dir/added
+git add staged dir/added
+rm removed
+echo untracked > untracked
@@ -543,6 +543,38 @@ mod blocking_io {
         assure_index_entries_on_disk(&index, repo.workdir().expect("non-bare"));
         Ok(())
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn fetch_and_checkout_runs_post_checkout_hook() -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+
+        let hooks = gix_testtools::tempfile::TempDir::new()?;
+        let hook_path = hooks.path().join("post-checkout");
+        std::fs::write(&hook_path, "#!/bin/sh\necho \"$@\" > \"$GIT_DIR/post-checkout.args\"\n")?;
+        std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            remote::repo("base").path(),
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted()
+                .run_hooks(true)
+                .config_overrides([format!("core.hooksPath={}", hooks.path().display())]),
+        )?;
+        let (mut checkout, _out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+
+        assert_eq!(
+            std::fs::read_to_string(repo.git_dir().join("post-checkout.args"))?,
+            format!("{} {} 1\n", repo.object_hash().null(), repo.head_id()?),
+            "like `git clone`, there is no previous HEAD"
+        );
+        Ok(())
+    }
+
     #[test]
     fn fetch_and_checkout_specific_ref() -> crate::Result {
         let tmp = gix_testtools::tempfile::TempDir::new()?;
@@ -675,6 +707,68 @@ mod blocking_io {
         Ok(())
     }
 
+    #[test]
+    fn partial_clone_fetches_missing_objects_on_demand() -> crate::Result {
+        let remote_dir = gix_testtools::scripted_fixture_read_only("make_promisor_remote_repo.sh")?.join("base");
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let mut prepare = gix::clone::PrepareFetch::new(
+            remote_dir.as_path(),
+            tmp.path(),
+            gix::create::Kind::WithWorktree,
+            Default::default(),
+            restricted(),
+        )?
+        .with_filter("blob:none");
+        let (mut checkout, out) = prepare.fetch_then_checkout(gix::progress::Discard, &AtomicBool::default())?;
+
+        let gix::remote::fetch::Status::Change { write_pack_bundle, .. } = out.status else {
+            unreachable!("a pack was received")
+        };
+        let pack_path = write_pack_bundle.data_path.expect("pack written");
+        assert!(
+            pack_path.with_extension("promisor").is_file(),
+            "packs from promisor remotes are marked as such"
+        );
+        assert_eq!(
+            write_pack_bundle.index.num_objects, 5,
+            "only the two commits and their three trees were received"
+        );
+
+        let repo = checkout.repo();
+        let blob_id = repo.rev_parse_single("HEAD:a").expect("the tree is present").detach();
+        assert!(!repo.has_object(blob_id), "blobs are missing in a blob:none clone");
+
+        let (repo, _) = checkout.main_worktree(gix::progress::Discard, &AtomicBool::default())?;
+        let workdir = repo.workdir().expect("non-bare");
+        assert_eq!(std::fs::read(workdir.join("a"))?, b"2\n");
+        assert_eq!(std::fs::read(workdir.join("dir/b"))?, b"b\n");
+        assert!(repo.has_object(blob_id), "checkout fetched all blobs in one go");
+
+        let old_blob_id = repo.rev_parse_single("HEAD~1:a").expect("the tree is present").detach();
+        assert!(!repo.has_object(old_blob_id), "older blobs are still missing");
+        assert_eq!(repo.find_blob(old_blob_id)?.data, b"1\n", "they are fetched on access");
+
+        let num_promisor_packs = std::fs::read_dir(repo.objects.store_ref().path().join("pack"))?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "promisor"))
+            .count();
+        assert_eq!(num_promisor_packs, 3, "the clone, the checkout and the single blob");
+
+        for repo in [repo.clone(), gix::open_opts(repo.git_dir(), restricted())?] {
+            let config = repo.config_snapshot();
+            assert_eq!(
+                config.string("extensions.partialClone").expect("set").as_ref(),
+                "origin"
+            );
+            assert_eq!(config.boolean("remote.origin.promisor"), Some(true));
+            assert_eq!(
+                config.string("remote.origin.partialCloneFilter").expect("set").as_ref(),
+                "blob:none"
+            );
+        }
+        Ok(())
+    }
+
     fn assure_index_entries_on_disk(index: &gix::worktree::Index, work_dir: &Path) {
         for entry in index.entries() {
             let entry_path = work_dir.join(gix_path::from_bstr(entry.path(index)));
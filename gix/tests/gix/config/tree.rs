@@ -203,6 +203,89 @@ mod push {
     }
 }
 
+mod receive {
+    use gix::config::tree::{receive::DenyAction, Key, Receive};
+
+    use crate::config::tree::bcow;
+
+    #[test]
+    fn deny_current_branch() -> crate::Result {
+        for (actual, expected) in [
+            ("ignore", DenyAction::Ignore),
+            ("false", DenyAction::Ignore),
+            ("warn", DenyAction::Warn),
+            ("refuse", DenyAction::Refuse),
+            ("true", DenyAction::Refuse),
+            ("updateInstead", DenyAction::Refuse),
+        ] {
+            assert_eq!(Receive::DENY_CURRENT_BRANCH.try_into_action(bcow(actual))?, expected);
+        }
+
+        assert_eq!(
+            Receive::DENY_CURRENT_BRANCH
+                .try_into_action(bcow("something"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"receive.denyCurrentBranch=something\" was invalid"
+        );
+        assert!(Receive::DENY_DELETE_CURRENT.validate("warn".into()).is_ok());
+        Ok(())
+    }
+}
+
+mod gpg {
+    use gix::config::tree::{
+        gpg::{SignatureFormat, TrustLevel},
+        Gpg, Key,
+    };
+
+    use crate::config::tree::bcow;
+
+    #[test]
+    fn format() -> crate::Result {
+        for (actual, expected) in [
+            ("openpgp", SignatureFormat::OpenPgp),
+            ("x509", SignatureFormat::X509),
+            ("ssh", SignatureFormat::Ssh),
+        ] {
+            assert_eq!(Gpg::FORMAT.try_into_format(bcow(actual))?, expected);
+            assert_eq!(expected.as_str(), actual);
+        }
+
+        assert_eq!(
+            Gpg::FORMAT.try_into_format(bcow("gpg")).unwrap_err().to_string(),
+            "The key \"gpg.format=gpg\" was invalid"
+        );
+        assert!(Gpg::FORMAT.validate("ssh".into()).is_ok());
+        assert_eq!(gix::config::tree::gpg::Ssh::PROGRAM.logical_name(), "gpg.ssh.program");
+        Ok(())
+    }
+
+    #[test]
+    fn min_trust_level() -> crate::Result {
+        for (actual, expected) in [
+            ("undefined", TrustLevel::Undefined),
+            ("never", TrustLevel::Never),
+            ("Marginal", TrustLevel::Marginal),
+            ("FULLY", TrustLevel::Fully),
+            ("ultimate", TrustLevel::Ultimate),
+        ] {
+            assert_eq!(Gpg::MIN_TRUST_LEVEL.try_into_trust_level(bcow(actual))?, expected);
+        }
+        assert!(TrustLevel::Never < TrustLevel::Marginal, "levels are ordered by trust");
+
+        assert_eq!(
+            Gpg::MIN_TRUST_LEVEL
+                .try_into_trust_level(bcow("full"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"gpg.minTrustLevel=full\" was invalid"
+        );
+        assert!(Gpg::MIN_TRUST_LEVEL.validate("marginal".into()).is_ok());
+        Ok(())
+    }
+}
+
 mod fetch {
 
     #[test]
@@ -677,6 +760,61 @@ mod index {
             "The key \"index.threads=nothing\" was invalid"
         );
     }
+
+    #[test]
+    fn version() -> crate::Result {
+        for (value, expected) in [
+            (2, gix::index::Version::V2),
+            (3, gix::index::Version::V3),
+            (4, gix::index::Version::V4),
+        ] {
+            assert_eq!(Index::VERSION.try_into_index_version(Ok(value))?, expected);
+            assert!(Index::VERSION.validate(value.to_string().as_str().into()).is_ok());
+        }
+        assert_eq!(
+            Index::VERSION.try_into_index_version(Ok(1)).unwrap_err().to_string(),
+            "The value of key \"index.version\" was invalid"
+        );
+        assert!(Index::VERSION.validate("1".into()).is_err());
+        assert!(Index::VERSION.validate("5".into()).is_err());
+        Ok(())
+    }
+}
+
+mod split_index {
+    use std::time::{Duration, SystemTime};
+
+    use gix::config::tree::{Key, SplitIndex};
+
+    use crate::config::tree::bcow;
+
+    #[test]
+    fn shared_index_expire() -> crate::Result {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(100 * 24 * 60 * 60);
+        let two_weeks_ago = now - Duration::from_secs(14 * 24 * 60 * 60);
+        for (value, expected) in [
+            ("never", None),
+            ("now", Some(now)),
+            ("2.weeks.ago", Some(two_weeks_ago)),
+            ("2 weeks ago", Some(two_weeks_ago)),
+            ("1000", Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1000))),
+        ] {
+            assert_eq!(
+                SplitIndex::SHARED_INDEX_EXPIRE.try_into_expire(bcow(value), now)?,
+                expected,
+                "{value}"
+            );
+            assert!(SplitIndex::SHARED_INDEX_EXPIRE.validate(value.into()).is_ok());
+        }
+        assert_eq!(
+            SplitIndex::SHARED_INDEX_EXPIRE
+                .try_into_expire(bcow("whenever"), now)
+                .unwrap_err()
+                .to_string(),
+            "The date format at key \"splitIndex.sharedIndexExpire=whenever\" was invalid"
+        );
+        Ok(())
+    }
 }
 
 mod extensions {
@@ -706,6 +844,28 @@ mod extensions {
         assert!(Extensions::OBJECT_FORMAT.validate("invalid".into()).is_err());
         Ok(())
     }
+
+    #[test]
+    fn ref_storage() -> crate::Result {
+        assert_eq!(
+            Extensions::REF_STORAGE.try_into_ref_storage(bcow("files"))?,
+            gix_ref::store::Format::Files
+        );
+        assert_eq!(
+            Extensions::REF_STORAGE.try_into_ref_storage(bcow("reftable"))?,
+            gix_ref::store::Format::Reftable
+        );
+        assert_eq!(
+            Extensions::REF_STORAGE
+                .try_into_ref_storage(bcow("invalid"))
+                .unwrap_err()
+                .to_string(),
+            "The key \"extensions.refStorage=invalid\" was invalid"
+        );
+        assert!(Extensions::REF_STORAGE.validate("reftable".into()).is_ok());
+        assert!(Extensions::REF_STORAGE.validate("invalid".into()).is_err());
+        Ok(())
+    }
 }
 
 mod checkout {
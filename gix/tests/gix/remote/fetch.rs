@@ -576,6 +576,77 @@ mod blocking_and_async_io {
         Ok(())
     }
 
+    #[maybe_async::test(
+        feature = "blocking-network-client",
+        async(feature = "async-network-client-async-std", async_std::test)
+    )]
+    async fn fetch_writes_commit_graph_if_configured() -> crate::Result {
+        let daemon = spawn_git_daemon_if_async(repo_path("clone-as-base-with-changes"))?;
+        let (mut repo, _tmp) = repo_rw("two-origins");
+        repo.config_snapshot_mut()
+            .set_raw_value(&gix::config::tree::Fetch::WRITE_COMMIT_GRAPH, "true")?;
+        let remote = into_daemon_remote_if_async(
+            repo.find_remote("changes-on-top-of-origin")?
+                .with_fetch_tags(gix::remote::fetch::Tags::None),
+            daemon.as_ref(),
+            None,
+        );
+        let outcome = remote
+            .connect(Fetch)
+            .await?
+            .prepare_fetch(progress::Discard, Default::default())
+            .await?
+            .receive(progress::Discard, &AtomicBool::default())
+            .await?;
+        assert!(matches!(outcome.status, Status::Change { .. }));
+
+        let graph = repo.commit_graph()?;
+        let tip = repo
+            .find_reference("refs/remotes/changes-on-top-of-origin/main")?
+            .peel_to_id()?;
+        assert!(
+            graph.lookup(tip).is_some(),
+            "the fetched commit was added to the commit-graph"
+        );
+        assert!(
+            graph.lookup(repo.head_id()?).is_some(),
+            "local commits are added as well"
+        );
+        Ok(())
+    }
+
+    #[maybe_async::test(
+        feature = "blocking-network-client",
+        async(feature = "async-network-client-async-std", async_std::test)
+    )]
+    async fn fetch_does_not_write_commit_graph_if_commit_graphs_are_disabled() -> crate::Result {
+        let daemon = spawn_git_daemon_if_async(repo_path("clone-as-base-with-changes"))?;
+        let (mut repo, _tmp) = repo_rw("two-origins");
+        let mut config = repo.config_snapshot_mut();
+        config.set_raw_value(&gix::config::tree::Fetch::WRITE_COMMIT_GRAPH, "true")?;
+        config.set_raw_value(&gix::config::tree::Core::COMMIT_GRAPH, "false")?;
+        config.commit()?;
+        let remote = into_daemon_remote_if_async(
+            repo.find_remote("changes-on-top-of-origin")?
+                .with_fetch_tags(gix::remote::fetch::Tags::None),
+            daemon.as_ref(),
+            None,
+        );
+        let outcome = remote
+            .connect(Fetch)
+            .await?
+            .prepare_fetch(progress::Discard, Default::default())
+            .await?
+            .receive(progress::Discard, &AtomicBool::default())
+            .await?;
+        assert!(matches!(outcome.status, Status::Change { .. }));
+        assert!(
+            repo.commit_graph().is_err(),
+            "core.commitGraph = false prevents the commit-graph from being written"
+        );
+        Ok(())
+    }
+
     #[maybe_async::test(
         feature = "blocking-network-client",
         async(feature = "async-network-client-async-std", async_std::test)
@@ -0,0 +1,199 @@
+#[cfg(feature = "blocking-network-client")]
+mod blocking_io {
+    use std::sync::atomic::AtomicBool;
+
+    use gix::remote::{
+        push::{Lease, Status},
+        Direction,
+    };
+    use gix_testtools::tempfile::TempDir;
+
+    type Prepare<'a, 'repo> =
+        gix::remote::push::Prepare<'a, 'repo, Box<dyn gix::protocol::transport::client::blocking_io::Transport + Send>>;
+
+    fn repos() -> crate::Result<(gix::Repository, std::path::PathBuf, TempDir)> {
+        let dir = gix_testtools::scripted_fixture_writable("make_push_repos.sh")?;
+        let local = gix::open_opts(dir.path().join("local"), crate::restricted())?;
+        let remote = dir.path().join("remote.git");
+        Ok((local, remote, dir))
+    }
+
+    fn push(
+        repo: &gix::Repository,
+        remote_path: &std::path::Path,
+        specs: &[&str],
+        adjust: impl for<'a, 'repo> FnOnce(Prepare<'a, 'repo>) -> Prepare<'a, 'repo>,
+    ) -> crate::Result<gix::remote::push::Outcome> {
+        let remote = repo
+            .remote_at(remote_path)?
+            .with_refspecs(Some("+refs/heads/*:refs/remotes/origin/*"), Direction::Fetch)?
+            .with_refspecs(specs.iter().copied(), Direction::Push)?;
+        let prepare = remote
+            .connect(Direction::Push)?
+            .prepare_push(gix::progress::Discard, Default::default())?;
+        Ok(adjust(prepare).transmit(gix::progress::Discard, &AtomicBool::default())?)
+    }
+
+    fn remote_ref(remote_path: &std::path::Path, name: &str) -> crate::Result<Option<gix::ObjectId>> {
+        let remote = gix::open_opts(remote_path, crate::restricted())?;
+        Ok(remote
+            .try_find_reference(name)?
+            .map(|mut r| r.peel_to_id().expect("peelable").detach()))
+    }
+
+    #[test]
+    fn fast_forward_with_objects_and_tracking_ref_update() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let outcome = push(&repo, &remote_path, &["refs/heads/main"], |p| p)?;
+        let main = repo.find_reference("refs/heads/main")?.id().detach();
+        assert_eq!(outcome.updates.len(), 1);
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert!(outcome
+            .report
+            .as_ref()
+            .is_some_and(gix_protocol::push::response::Report::is_ok));
+        assert_eq!(remote_ref(&remote_path, "refs/heads/main")?, Some(main));
+        assert_eq!(
+            outcome.tracking_ref_edits.len(),
+            1,
+            "the remote-tracking branch was updated as well"
+        );
+        assert_eq!(repo.find_reference("refs/remotes/origin/main")?.id(), main);
+
+        let outcome = push(&repo, &remote_path, &["refs/heads/main"], |p| p)?;
+        assert_eq!(outcome.updates[0].1, Status::UpToDate, "nothing to do the second time");
+        assert!(outcome.report.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn new_branch_and_annotated_tag() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let outcome = push(&repo, &remote_path, &["diverged:new", "refs/tags/v1"], |p| p)?;
+        assert!(outcome.updates.iter().all(|(_, status)| *status == Status::Ok));
+        assert_eq!(
+            remote_ref(&remote_path, "refs/heads/new")?,
+            Some(repo.find_reference("diverged")?.id().detach())
+        );
+        let remote = gix::open_opts(&remote_path, crate::restricted())?;
+        let tag = remote.find_reference("refs/tags/v1")?.id().detach();
+        assert_eq!(
+            remote.find_object(tag)?.kind,
+            gix::object::Kind::Tag,
+            "the tag object was sent"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn dangling_symbolic_refs_are_ignored() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        std::fs::write(
+            repo.git_dir().join("refs/heads/dangling"),
+            "ref: refs/heads/does-not-exist\n",
+        )?;
+        let outcome = push(&repo, &remote_path, &["refs/heads/*:refs/heads/*"], |p| p)?;
+        assert!(outcome.updates.iter().all(|(_, status)| *status == Status::Ok));
+        assert!(
+            outcome
+                .updates
+                .iter()
+                .all(|(update, _)| update.remote.as_bstr() != "refs/heads/dangling"),
+            "the dangling ref can't be pushed, but doesn't prevent the other refs from being pushed"
+        );
+        assert_eq!(
+            remote_ref(&remote_path, "refs/heads/diverged")?,
+            Some(repo.find_reference("diverged")?.id().detach())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn non_fast_forward_is_rejected_unless_forced() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let before = remote_ref(&remote_path, "refs/heads/main")?;
+        let outcome = push(&repo, &remote_path, &["diverged:main"], |p| p)?;
+        assert_eq!(outcome.updates[0].1, Status::RejectedNonFastForward);
+        assert_eq!(remote_ref(&remote_path, "refs/heads/main")?, before, "nothing changed");
+
+        let outcome = push(&repo, &remote_path, &["+diverged:main"], |p| p)?;
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert_eq!(
+            remote_ref(&remote_path, "refs/heads/main")?,
+            Some(repo.find_reference("diverged")?.id().detach())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn fast_forward_is_detected_despite_clock_skew() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let outcome = push(&repo, &remote_path, &["skewed:main"], |p| p)?;
+        assert_eq!(
+            outcome.updates[0].1,
+            Status::Ok,
+            "the new commit is older than the one on the remote, but descends from it"
+        );
+        assert_eq!(
+            remote_ref(&remote_path, "refs/heads/main")?,
+            Some(repo.find_reference("skewed")?.id().detach())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lease_mismatch_is_rejected_and_match_forces() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let actual = remote_ref(&remote_path, "refs/heads/main")?;
+        let wrong = repo.find_reference("diverged")?.id().detach();
+        let main: &gix::refs::FullNameRef = "refs/heads/main".try_into()?;
+        let outcome = push(&repo, &remote_path, &["diverged:main"], |p| {
+            p.with_lease(main, Some(wrong))
+        })?;
+        assert_eq!(outcome.updates[0].1, Status::RejectedStale { actual });
+
+        let outcome = push(&repo, &remote_path, &["diverged:main"], |p| p.with_lease(main, actual))?;
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert_eq!(outcome.updates[0].0.lease, Lease::Expect(actual));
+        Ok(())
+    }
+
+    #[test]
+    fn deletion_and_dry_run() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let outcome = push(&repo, &remote_path, &[":other"], |p| p.with_dry_run(true))?;
+        assert!(outcome.dry_run);
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert!(
+            remote_ref(&remote_path, "refs/heads/other")?.is_some(),
+            "dry-run changes nothing"
+        );
+
+        let outcome = push(&repo, &remote_path, &[":other"], |p| p)?;
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert_eq!(remote_ref(&remote_path, "refs/heads/other")?, None);
+
+        let outcome = push(&repo, &remote_path, &[":other"], |p| p)?;
+        assert_eq!(outcome.updates[0].1, Status::RejectedRemoteRefMissing);
+        Ok(())
+    }
+
+    #[test]
+    fn atomic_push_sends_nothing_if_one_update_is_rejected() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let outcome = push(&repo, &remote_path, &["diverged:main", "main:new"], |p| {
+            p.with_atomic(true)
+        })?;
+        let status_of = |name: &str| {
+            outcome
+                .updates
+                .iter()
+                .find(|(update, _)| update.remote.as_bstr() == name)
+                .map(|(_, status)| status.clone())
+        };
+        assert_eq!(status_of("refs/heads/main"), Some(Status::RejectedNonFastForward));
+        assert_eq!(status_of("refs/heads/new"), Some(Status::AtomicPushFailed));
+        assert_eq!(remote_ref(&remote_path, "refs/heads/new")?, None);
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use std::sync::atomic::AtomicBool;
+
+use crate::util::repo_rw;
+
+#[test]
+#[cfg(unix)]
+fn update_worktree_does_not_remove_files_behind_symlinked_directories() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_sparse_checkout_repo.sh")?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+    let outside = gix_testtools::tempfile::TempDir::new()?;
+    std::fs::write(outside.path().join("file"), "outside")?;
+    std::fs::remove_dir_all(workdir.join("d/e"))?;
+    std::os::unix::fs::symlink(outside.path(), workdir.join("d/e"))?;
+
+    let from = repo.open_index()?;
+    let mut to = from.clone();
+    to.remove_entries(|_, path, _| path == "d/e/file" || path == "a/b/file");
+    let outcome = repo.update_worktree(&from, &mut to, &AtomicBool::default())?;
+
+    assert_eq!(outcome.files_removed, 1, "only a/b/file could be removed");
+    assert!(!workdir.join("a/b").exists(), "empty directories are removed");
+    assert!(
+        outside.path().join("file").is_file(),
+        "files are never removed through a symlink in the leading path"
+    );
+    assert!(
+        workdir.join("d/e").symlink_metadata()?.file_type().is_symlink(),
+        "the symlink itself is left alone"
+    );
+    Ok(())
+}
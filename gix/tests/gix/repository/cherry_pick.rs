@@ -0,0 +1,221 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::ByteSlice,
+    cherry_pick::{Error, Options},
+    index::entry::Stage,
+};
+
+use crate::util::{head_reflog_message, read, repo_rw};
+
+fn commit_of<'repo>(repo: &'repo gix::Repository, spec: &str) -> crate::Result<gix::Commit<'repo>> {
+    Ok(repo.rev_parse_single(spec)?.object()?.into_commit())
+}
+
+#[test]
+fn cherry_pick_matches_git() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_cherry_pick_and_revert_repo.sh")?;
+    let picked = commit_of(&repo, "feature~1")?;
+    let head_before = repo.head_id()?.detach();
+
+    let outcome = repo.cherry_pick(
+        picked.id,
+        Options {
+            record_origin: true,
+            ..Default::default()
+        },
+        &AtomicBool::default(),
+    )?;
+    assert!(outcome.conflicts.is_empty());
+    let id = outcome.commit.expect("committed");
+    assert_eq!(repo.head_id()?, id, "HEAD follows the new commit");
+
+    let commit = repo.find_commit(id)?;
+    let expected = commit_of(&repo, "expected-pick")?;
+    assert_eq!(commit.tree_id()?, expected.tree_id()?);
+    assert_eq!(
+        commit.message_raw()?,
+        expected.message_raw()?,
+        "the origin is added to the existing trailers"
+    );
+    assert_eq!(
+        commit.author()?.name,
+        "Feature Author",
+        "the author of the picked commit is kept"
+    );
+    assert_eq!(commit.parent_ids().collect::<Vec<_>>(), [head_before]);
+    assert_eq!(head_reflog_message(&repo)?, "cherry-pick: add new-file");
+
+    assert_eq!(read(&repo, "new-file")?, "new\n");
+    assert!(repo.open_index()?.entry_by_path("new-file".into()).is_some());
+    assert_eq!(repo.state(), None);
+    Ok(())
+}
+
+#[test]
+fn cherry_pick_with_conflicts() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_cherry_pick_and_revert_repo.sh")?;
+    let picked = commit_of(&repo, "feature")?;
+    let head_before = repo.head_id()?.detach();
+
+    let outcome = repo.cherry_pick(picked.id, Options::default(), &AtomicBool::default())?;
+    assert_eq!(outcome.commit, None);
+    assert_eq!(outcome.conflicts.len(), 1);
+    assert_eq!(repo.head_id()?, head_before, "nothing was committed");
+    assert_eq!(repo.state(), Some(gix::state::InProgress::CherryPick));
+
+    let git_dir = repo.git_dir();
+    assert_eq!(
+        std::fs::read(git_dir.join("CHERRY_PICK_HEAD"))?.as_bstr(),
+        format!("{}\n", picked.id)
+    );
+    assert_eq!(
+        std::fs::read(git_dir.join("MERGE_MSG"))?.as_bstr(),
+        "change first line\n\n# Conflicts:\n#\tlines\n"
+    );
+    let lines = read(&repo, "lines")?;
+    let short_id = picked.short_id()?;
+    assert_eq!(
+        lines,
+        format!("<<<<<<< HEAD\nONE\n=======\none\n>>>>>>> {short_id} (change first line)\n2\n3\n")
+    );
+
+    let index = repo.open_index()?;
+    let stages: Vec<_> = index
+        .entry_range("lines".into())
+        .map(|range| index.entries()[range].iter().map(gix::index::Entry::stage).collect())
+        .unwrap_or_default();
+    assert_eq!(stages, [Stage::Base, Stage::Ours, Stage::Theirs]);
+
+    let err = repo
+        .cherry_pick(picked.id, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::InProgress { .. }), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn revert_matches_git() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_cherry_pick_and_revert_repo.sh")?;
+    let reverted = commit_of(&repo, "main~1")?;
+
+    let outcome = repo.revert(reverted.id, Options::default(), &AtomicBool::default())?;
+    assert!(outcome.conflicts.is_empty());
+    let commit = repo.find_commit(outcome.commit.expect("committed"))?;
+    let expected = commit_of(&repo, "expected-revert")?;
+    assert_eq!(commit.tree_id()?, expected.tree_id()?);
+    assert_eq!(commit.message_raw()?, expected.message_raw()?);
+    assert_eq!(
+        commit.message_raw()?,
+        format!(
+            "Revert \"change first line on main\"\n\nThis reverts commit {}.\n",
+            reverted.id
+        )
+    );
+    assert_eq!(
+        head_reflog_message(&repo)?,
+        "revert: Revert \"change first line on main\""
+    );
+    assert_eq!(read(&repo, "lines")?, "1\n2\n3\n");
+    Ok(())
+}
+
+#[test]
+fn merge_commits_need_a_mainline() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_cherry_pick_and_revert_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let merge = commit_of(&repo, "merged")?;
+
+    let err = repo
+        .cherry_pick(merge.id, Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(matches!(err, Error::MissingMainline { .. }), "{err:?}");
+    let err = repo
+        .cherry_pick(
+            merge.id,
+            Options {
+                mainline: Some(3),
+                ..Default::default()
+            },
+            &should_interrupt,
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::InvalidMainline { mainline: 3, .. }), "{err:?}");
+    let err = repo
+        .cherry_pick(
+            commit_of(&repo, "feature~1")?.id,
+            Options {
+                mainline: Some(1),
+                ..Default::default()
+            },
+            &should_interrupt,
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::UnexpectedMainline { .. }), "{err:?}");
+
+    let outcome = repo.cherry_pick(
+        merge.id,
+        Options {
+            mainline: Some(1),
+            ..Default::default()
+        },
+        &should_interrupt,
+    )?;
+    let commit = repo.find_commit(outcome.commit.expect("committed"))?;
+    assert_eq!(commit.tree_id()?, commit_of(&repo, "expected-merge-pick")?.tree_id()?);
+    assert_eq!(commit.message_raw()?, "merge topic\n");
+    Ok(())
+}
+
+#[test]
+fn no_commit_and_preconditions() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_cherry_pick_and_revert_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let head_before = repo.head_id()?.detach();
+
+    let err = repo
+        .cherry_pick(commit_of(&repo, "main~1")?.id, Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::Empty { .. }),
+        "the changes are already present: {err:?}"
+    );
+
+    std::fs::write(repo.workdir().expect("non-bare").join("new-file"), "untracked")?;
+    let picked = commit_of(&repo, "feature~1")?;
+    let err = repo
+        .cherry_pick(picked.id, Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::WouldOverwriteLocalChanges { paths } if paths == &["new-file"]),
+        "{err:?}"
+    );
+    std::fs::remove_file(repo.workdir().expect("non-bare").join("new-file"))?;
+
+    let outcome = repo.cherry_pick(
+        picked.id,
+        Options {
+            no_commit: true,
+            ..Default::default()
+        },
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.commit, None);
+    assert_eq!(repo.head_id()?, head_before);
+    assert_eq!(repo.state(), None, "no operation is in progress without conflicts");
+    assert_eq!(read(&repo, "new-file")?, "new\n");
+    assert!(repo.open_index()?.entry_by_path("new-file".into()).is_some());
+    assert_eq!(
+        std::fs::read(repo.git_dir().join("MERGE_MSG"))?.as_bstr(),
+        picked.message_raw()?
+    );
+
+    let err = repo
+        .revert(commit_of(&repo, "main~1")?.id, Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::DirtyIndex),
+        "the index must match HEAD unless changes aren't committed: {err:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,58 @@
+use gix::commitgraph::write::Split;
+
+#[test]
+fn write_and_update_commit_graph() -> crate::Result {
+    let (repo, _tmp) = crate::repo_rw("make_rev_parse_repo.sh")?;
+    let info_dir = repo.objects.store_ref().path().join("info");
+    let expected_num_commits = repo
+        .rev_walk(
+            repo.references()?
+                .all()?
+                .filter_map(Result::ok)
+                .filter_map(|mut r| r.peel_to_commit().ok().map(|c| c.id)),
+        )
+        .all()?
+        .count();
+
+    let outcome = repo.write_commit_graph(None)?;
+    assert_eq!(outcome.num_commits as usize, expected_num_commits);
+    assert_eq!(outcome.num_files, 1);
+    assert!(info_dir.join("commit-graph").is_file());
+    assert_eq!(repo.commit_graph()?.num_commits() as usize, expected_num_commits);
+    assert_eq!(
+        repo.write_commit_graph(None)?.checksum,
+        None,
+        "nothing changed, nothing to write"
+    );
+
+    let head = repo.head_commit()?;
+    let new_commit = repo.commit("HEAD", "new", head.tree_id()?, [head.id])?.detach();
+    let outcome = repo.write_commit_graph(Some(Split::NoMerge))?;
+    assert_eq!(
+        outcome.num_commits,
+        expected_num_commits as u32 + 1,
+        "the single file is turned into a chain"
+    );
+    assert_eq!(outcome.num_files, 1);
+    assert!(!info_dir.join("commit-graph").exists());
+
+    repo.commit("HEAD", "another", head.tree_id()?, [new_commit])?;
+    let outcome = repo.write_commit_graph(Some(Split::NoMerge))?;
+    assert_eq!(
+        (outcome.num_commits, outcome.num_files),
+        (1, 2),
+        "only new commits are added"
+    );
+
+    let graph = repo.commit_graph()?;
+    assert_eq!(graph.num_commits() as usize, expected_num_commits + 2);
+    graph
+        .verify_integrity(|_| Ok::<_, gix::error::Message>(()))
+        .map_err(gix::Error::from)?;
+    let status = std::process::Command::new(gix::path::env::exe_invocation())
+        .args(["commit-graph", "verify"])
+        .current_dir(repo.git_dir())
+        .status()?;
+    assert!(status.success(), "git accepts the chain as well");
+    Ok(())
+}
@@ -0,0 +1,464 @@
+use gix::{
+    config::tree::{Commit, Gpg, Tag, User},
+    gpg::SignatureFormat,
+};
+
+#[test]
+fn signer_defaults_to_gpg_with_the_committer_identity_as_key() -> crate::Result {
+    let repo = crate::named_repo("make_basic_repo.sh")?;
+    let signer = repo.signer()?;
+    assert_eq!(signer.format(), SignatureFormat::OpenPgp);
+    assert_eq!(signer.program(), "gpg");
+    Ok(())
+}
+
+#[test]
+fn signer_for_ssh_needs_a_signing_key() -> crate::Result {
+    let mut repo = crate::named_repo("make_basic_repo.sh")?;
+    repo.config_snapshot_mut().set_value(&Gpg::FORMAT, "ssh")?;
+    assert!(matches!(
+        repo.signer().unwrap_err(),
+        gix::gpg::signer::Error::SshKeyMissing
+    ));
+
+    repo.config_snapshot_mut()
+        .set_value(&User::SIGNING_KEY, "key::ssh-ed25519 AAAA")?;
+    let signer = repo.signer()?;
+    assert_eq!(signer.format(), SignatureFormat::Ssh);
+    assert_eq!(signer.program(), "ssh-keygen");
+    Ok(())
+}
+
+#[cfg(unix)]
+mod sign {
+    use gix::bstr::ByteSlice;
+
+    use super::{Commit, Gpg, Tag, User};
+
+    const SIGNATURE: &str = "-----BEGIN PGP SIGNATURE-----\n\nfake\n-----END PGP SIGNATURE-----\n";
+
+    /// Write a program that records its arguments and input, and prints a fake signature like `gpg` would.
+    fn fake_gpg(repo: &gix::Repository) -> crate::Result<std::path::PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+        let path = repo.git_dir().join("fake-gpg");
+        let log_dir = repo.git_dir().display();
+        std::fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\necho \"$@\" > {log_dir}/gpg.args\ncat > {log_dir}/gpg.input\n\
+                 printf '%b' '{}'\necho '[GNUPG:] SIG_CREATED D 1 8 00 1 X' >&2\n",
+                SIGNATURE.replace('\n', "\\n")
+            ),
+        )?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(path)
+    }
+
+    fn read(repo: &gix::Repository, name: &str) -> crate::Result<Vec<u8>> {
+        Ok(std::fs::read(repo.git_dir().join(name))?)
+    }
+
+    /// Generate an ssh key in `dir` and return its path, or `None` if `ssh-keygen` isn't available.
+    pub(super) fn ssh_key(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+        let key = dir.join("key");
+        let status = std::process::Command::new("ssh-keygen")
+            .args(["-q", "-t", "ed25519", "-N", "", "-C", "test", "-f"])
+            .arg(&key)
+            .status();
+        if !status.is_ok_and(|status| status.success()) {
+            eprintln!("Skipping test as ssh-keygen isn't available");
+            return None;
+        }
+        Some(key)
+    }
+
+    #[test]
+    fn commit_with_gpg_sign_is_signed_with_the_committer_identity() -> crate::Result {
+        let (mut repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let program = fake_gpg(&repo)?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_value(&Gpg::PROGRAM, program.to_str().expect("valid UTF-8"))?;
+            config.set_value(&Commit::GPG_SIGN, "true")?;
+        }
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        let id = repo.commit("HEAD", "signed\n", tree, [parent])?;
+
+        let commit = id.object()?.into_commit();
+        let (signature, signed_data) = commit.signature()?.expect("signed");
+        assert_eq!(signature.as_ref(), SIGNATURE);
+        assert_eq!(
+            signed_data.to_bstring(),
+            read(&repo, "gpg.input")?.as_bstr(),
+            "the signature covers the commit without its signature"
+        );
+        assert_eq!(
+            read(&repo, "gpg.args")?.as_bstr(),
+            "--status-fd=2 -bsau gitoxide <gitoxide@localhost>\n"
+        );
+        assert_eq!(commit.message_raw()?, "signed\n");
+        Ok(())
+    }
+
+    #[test]
+    fn commit_with_failing_signing_program_fails() -> crate::Result {
+        let (mut repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_value(&Gpg::PROGRAM, "false")?;
+            config.set_value(&Commit::GPG_SIGN, "true")?;
+        }
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        let err = repo.commit("HEAD", "signed", tree, [parent]).unwrap_err();
+        assert!(matches!(
+            err,
+            gix::commit::Error::Sign(gix::gpg::sign::Error::Failed { .. })
+        ));
+        assert_eq!(repo.head_id()?, parent, "HEAD wasn't changed");
+        Ok(())
+    }
+
+    #[test]
+    fn tag_with_gpg_sign_is_signed_with_the_signing_key() -> crate::Result {
+        let (mut repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let program = fake_gpg(&repo)?;
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_value(&Gpg::PROGRAM, program.to_str().expect("valid UTF-8"))?;
+            config.set_value(&Tag::GPG_SIGN, "true")?;
+            config.set_value(&User::SIGNING_KEY, "ABCD")?;
+        }
+
+        let head = repo.head_id()?;
+        let tag_ref = repo.tag(
+            "signed",
+            head,
+            gix::objs::Kind::Commit,
+            repo.committer().transpose()?,
+            "message",
+            gix::refs::transaction::PreviousValue::MustNotExist,
+        )?;
+        let tag = tag_ref.id().object()?;
+        let payload = read(&repo, "gpg.input")?;
+        assert_eq!(
+            tag.data.as_bstr(),
+            [payload.as_slice(), SIGNATURE.as_bytes()].concat().as_bstr(),
+            "the signature is appended to the tag, which ends with a newline now"
+        );
+        assert!(payload.ends_with(b"message\n"));
+        assert_eq!(read(&repo, "gpg.args")?.as_bstr(), "--status-fd=2 -bsau ABCD\n");
+
+        let tag = tag.into_tag();
+        let tag = tag.decode()?;
+        assert_eq!(tag.message, "message");
+        assert_eq!(tag.pgp_signature.expect("signed"), SIGNATURE);
+        Ok(())
+    }
+
+    #[test]
+    fn commit_signed_with_ssh_key_can_be_checked_by_ssh_keygen() -> crate::Result {
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let Some(key) = ssh_key(tmp.path()) else {
+            return Ok(());
+        };
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_value(&Gpg::FORMAT, "ssh")?;
+            config.set_value(&User::SIGNING_KEY, key.to_str().expect("valid UTF-8"))?;
+            config.set_value(&Commit::GPG_SIGN, "true")?;
+        }
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        let id = repo.commit("HEAD", "signed with ssh", tree, [parent])?;
+
+        let commit = id.object()?.into_commit();
+        let (signature, signed_data) = commit.signature()?.expect("signed");
+        assert!(signature.starts_with(b"-----BEGIN SSH SIGNATURE-----"));
+        let signature_path = tmp.path().join("signature");
+        std::fs::write(&signature_path, signature.as_ref())?;
+
+        let mut child = std::process::Command::new("ssh-keygen")
+            .args(["-Y", "check-novalidate", "-n", "git", "-s"])
+            .arg(&signature_path)
+            .stdin(std::process::Stdio::piped())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .spawn()?;
+        {
+            use std::io::Write;
+            child
+                .stdin
+                .take()
+                .expect("configured")
+                .write_all(&signed_data.to_bstring())?;
+        }
+        assert!(child.wait()?.success(), "the signature is valid for the signed data");
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+mod verify {
+    use gix::{
+        bstr::ByteSlice,
+        config::tree::{gpg, Commit, Gpg, Tag, User},
+        gpg::{verify::Status, TrustLevel},
+    };
+
+    use super::sign::ssh_key;
+
+    /// Configure `repo` to sign with a new ssh key, trusted for `principal` if set, and return the path to the key.
+    fn sign_with_ssh(
+        repo: &mut gix::Repository,
+        dir: &std::path::Path,
+        principal: Option<&str>,
+    ) -> crate::Result<Option<std::path::PathBuf>> {
+        let Some(key) = ssh_key(dir) else {
+            return Ok(None);
+        };
+        let allowed_signers = dir.join("allowed_signers");
+        let public_key = std::fs::read_to_string(key.with_extension("pub"))?;
+        std::fs::write(
+            &allowed_signers,
+            principal.map_or_else(String::new, |principal| format!("{principal} {public_key}")),
+        )?;
+
+        let mut config = repo.config_snapshot_mut();
+        config.set_value(&Gpg::FORMAT, "ssh")?;
+        config.set_value(&User::SIGNING_KEY, key.to_str().expect("valid UTF-8"))?;
+        config.set_value(&Commit::GPG_SIGN, "true")?;
+        config.set_value(&Tag::GPG_SIGN, "true")?;
+        config.set_value(
+            &gpg::Ssh::ALLOWED_SIGNERS_FILE,
+            allowed_signers.to_str().expect("valid UTF-8"),
+        )?;
+        Ok(Some(key))
+    }
+
+    fn commit(repo: &gix::Repository) -> crate::Result<gix::Commit<'_>> {
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        Ok(repo.commit("HEAD", "signed", tree, [parent])?.object()?.into_commit())
+    }
+
+    #[test]
+    fn unsigned_commits_have_no_signature_to_verify() -> crate::Result {
+        let repo = crate::named_repo("make_basic_repo.sh")?;
+        assert!(repo.head_commit()?.verify_signature()?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_signature_with_allowed_key_is_good() -> crate::Result {
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        if sign_with_ssh(&mut repo, tmp.path(), Some("test@example.com"))?.is_none() {
+            return Ok(());
+        }
+
+        let outcome = commit(&repo)?.verify_signature()?.expect("signed");
+        assert_eq!(outcome.format, gix::gpg::SignatureFormat::Ssh);
+        assert_eq!(outcome.status, Status::Good);
+        assert!(outcome.is_good());
+        assert_eq!(outcome.trust, TrustLevel::Fully, "allowed keys are trusted");
+        assert_eq!(outcome.signer.expect("known"), "test@example.com");
+        assert!(outcome.key.expect("known").starts_with(b"SHA256:"));
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_signed_tag_is_good() -> crate::Result {
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        if sign_with_ssh(&mut repo, tmp.path(), Some("test@example.com"))?.is_none() {
+            return Ok(());
+        }
+
+        let tag = repo.tag(
+            "signed",
+            repo.head_id()?,
+            gix::objs::Kind::Commit,
+            repo.committer().transpose()?,
+            "message",
+            gix::refs::transaction::PreviousValue::MustNotExist,
+        )?;
+        let tag = tag.id().object()?.into_tag();
+        assert_eq!(
+            tag.decode()?.message,
+            "message",
+            "the signature isn't part of the message"
+        );
+        let outcome = tag.verify_signature()?.expect("signed");
+        assert_eq!(outcome.status, Status::Good);
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_signature_with_unknown_key_or_for_other_data() -> crate::Result {
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        if sign_with_ssh(&mut repo, tmp.path(), None)?.is_none() {
+            return Ok(());
+        }
+
+        let commit = commit(&repo)?;
+        let outcome = commit.verify_signature()?.expect("signed");
+        assert_eq!(
+            outcome.status,
+            Status::UnknownKey,
+            "the signature is valid, but its key isn't in the allowed signers file"
+        );
+        assert_eq!(outcome.signer, None);
+        assert_eq!(outcome.trust, TrustLevel::Undefined);
+        assert!(outcome.key.is_some());
+
+        let (signature, signed_data) = commit.signature()?.expect("signed");
+        let mut payload = signed_data.to_bstring();
+        payload.extend_from_slice(b"tampered");
+        let outcome = repo.signature_verifier()?.verify(&signature, &payload)?;
+        assert_eq!(outcome.status, Status::Bad);
+        Ok(())
+    }
+
+    #[test]
+    fn ssh_signature_needs_allowed_signers_file() -> crate::Result {
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let Some(key) = ssh_key(tmp.path()) else {
+            return Ok(());
+        };
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_value(&Gpg::FORMAT, "ssh")?;
+            config.set_value(&User::SIGNING_KEY, key.to_str().expect("valid UTF-8"))?;
+            config.set_value(&Commit::GPG_SIGN, "true")?;
+        }
+
+        assert!(matches!(
+            commit(&repo)?.verify_signature().unwrap_err(),
+            gix::gpg::verify::object::Error::Verify(gix::gpg::verify::Error::AllowedSignersFileMissing)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn gpg_status_is_interpreted() -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let program = tmp.path().join("fake-gpg");
+        repo.config_snapshot_mut()
+            .set_value(&Gpg::PROGRAM, program.to_str().expect("valid UTF-8"))?;
+        let verifier = repo.signature_verifier()?;
+        let signature = b"-----BEGIN PGP SIGNATURE-----\n\nfake\n-----END PGP SIGNATURE-----\n";
+
+        for (status_line, expected_status, expected_signer) in [
+            (
+                "GOODSIG 0123456789ABCDEF A U Thor <a@example.com>",
+                Status::Good,
+                Some("A U Thor <a@example.com>"),
+            ),
+            (
+                "BADSIG 0123456789ABCDEF A U Thor <a@example.com>",
+                Status::Bad,
+                Some("A U Thor <a@example.com>"),
+            ),
+            (
+                "ERRSIG 0123456789ABCDEF 22 10 00 1700000000 9 -",
+                Status::UnknownKey,
+                None,
+            ),
+            (
+                "EXPSIG 0123456789ABCDEF A U Thor <a@example.com>",
+                Status::ExpiredSignature,
+                Some("A U Thor <a@example.com>"),
+            ),
+            (
+                "EXPKEYSIG 0123456789ABCDEF A U Thor <a@example.com>",
+                Status::ExpiredKey,
+                Some("A U Thor <a@example.com>"),
+            ),
+            (
+                "REVKEYSIG 0123456789ABCDEF A U Thor <a@example.com>",
+                Status::RevokedKey,
+                Some("A U Thor <a@example.com>"),
+            ),
+        ] {
+            std::fs::write(
+                &program,
+                format!("#!/bin/sh\necho '[GNUPG:] NEWSIG'\necho '[GNUPG:] {status_line}'\nexit 1\n"),
+            )?;
+            std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))?;
+
+            let outcome = verifier.verify(signature, b"payload")?;
+            assert_eq!(outcome.format, gix::gpg::SignatureFormat::OpenPgp);
+            assert_eq!(outcome.status, expected_status, "{status_line}");
+            assert_eq!(outcome.key.expect("always set"), "0123456789ABCDEF");
+            assert_eq!(
+                outcome.signer.as_ref().map(|s| s.to_str_lossy()),
+                expected_signer.map(Into::into)
+            );
+        }
+
+        std::fs::write(
+            &program,
+            "#!/bin/sh\necho '[GNUPG:] GOODSIG 0123456789ABCDEF A'\necho '[GNUPG:] BADSIG 0123456789ABCDEF B'\n",
+        )?;
+        assert!(
+            matches!(
+                verifier.verify(signature, b"payload").unwrap_err(),
+                gix::gpg::verify::Error::MultipleSignatures { .. }
+            ),
+            "like `git`, multiple signatures are refused"
+        );
+
+        std::fs::write(&program, "#!/bin/sh\nexit 2\n")?;
+        assert!(matches!(
+            verifier.verify(signature, b"payload").unwrap_err(),
+            gix::gpg::verify::Error::Failed { .. }
+        ));
+        assert!(matches!(
+            verifier.verify(b"not a signature", b"payload").unwrap_err(),
+            gix::gpg::verify::Error::UnknownFormat
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn gpg_trust_is_reported_and_checked_against_the_minimal_trust_level() -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+        let (mut repo, tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        let program = tmp.path().join("fake-gpg");
+        std::fs::write(
+            &program,
+            "#!/bin/sh\n\
+             echo '[GNUPG:] NEWSIG'\n\
+             echo '[GNUPG:] GOODSIG 0123456789ABCDEF A U Thor <a@example.com>'\n\
+             echo '[GNUPG:] VALIDSIG FINGERPRINT 2024-01-01 1700000000 0 4 0 22 10 00 PRIMARY'\n\
+             echo '[GNUPG:] TRUST_MARGINAL 0 pgp'\n",
+        )?;
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755))?;
+        let signature = b"-----BEGIN PGP SIGNATURE-----\n\nfake\n-----END PGP SIGNATURE-----\n";
+        repo.config_snapshot_mut()
+            .set_value(&Gpg::PROGRAM, program.to_str().expect("valid UTF-8"))?;
+
+        let outcome = repo.signature_verifier()?.verify(signature, b"payload")?;
+        assert_eq!(outcome.status, Status::Good);
+        assert_eq!(outcome.trust, TrustLevel::Marginal);
+        assert_eq!(outcome.min_trust_level, TrustLevel::Undefined);
+        assert_eq!(outcome.fingerprint.as_ref().expect("valid signature"), "FINGERPRINT");
+        assert!(outcome.is_good(), "by default, the trust level doesn't matter");
+
+        repo.config_snapshot_mut().set_value(&Gpg::MIN_TRUST_LEVEL, "fully")?;
+        let verifier = repo.signature_verifier()?;
+        assert_eq!(verifier.min_trust_level(), TrustLevel::Fully);
+        let outcome = verifier.verify(signature, b"payload")?;
+        assert_eq!(outcome.status, Status::Good);
+        assert!(!outcome.is_good(), "the key isn't trusted enough");
+
+        repo.config_snapshot_mut()
+            .set_value(&Gpg::MIN_TRUST_LEVEL, "marginal")?;
+        assert!(repo.signature_verifier()?.verify(signature, b"payload")?.is_good());
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+use gix::config::tree::Core;
+
+use crate::util::named_subrepo_opts;
+
+#[test]
+fn hooks_dir_is_in_common_dir_or_relative_to_worktree_if_configured() -> crate::Result {
+    let mut repo = crate::named_repo("make_basic_repo.sh")?;
+    let git_dir = gix::path::realpath(repo.git_dir())?;
+    assert_eq!(repo.hooks_dir()?, git_dir.join("hooks"));
+    assert!(
+        repo.hook("pre-commit")?.is_none(),
+        "sample hooks aren't picked up, and neither are hooks that don't exist"
+    );
+
+    repo.config_snapshot_mut()
+        .set_value(&Core::HOOKS_PATH, "custom/hooks")?;
+    assert_eq!(
+        repo.hooks_dir()?,
+        gix::path::realpath(repo.workdir().expect("non-bare"))?.join("custom/hooks")
+    );
+
+    let mut bare = named_subrepo_opts("make_basic_repo.sh", "bare.git", crate::restricted())?;
+    bare.config_snapshot_mut()
+        .set_value(&Core::HOOKS_PATH, "custom/hooks")?;
+    assert_eq!(
+        bare.hooks_dir()?,
+        gix::path::realpath(bare.git_dir())?.join("custom/hooks"),
+        "bare repositories run hooks in the git directory"
+    );
+    Ok(())
+}
+
+#[cfg(unix)]
+mod run {
+    use gix::bstr::ByteSlice;
+
+    fn write_hook(repo: &gix::Repository, name: &str, script: &str) -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+        let path = repo.hooks_dir()?.join(name);
+        std::fs::create_dir_all(path.parent().expect("parent"))?;
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    fn hooks_log(repo: &gix::Repository) -> crate::Result<String> {
+        Ok(std::fs::read_to_string(repo.git_dir().join("hooks.log"))?)
+    }
+
+    fn repo_with_hooks() -> crate::Result<(gix::Repository, gix_testtools::tempfile::TempDir)> {
+        crate::util::repo_rw_opts("make_basic_repo.sh", crate::restricted().run_hooks(true))
+    }
+
+    #[test]
+    fn hooks_only_run_if_enabled_and_if_the_repository_is_fully_trusted() -> crate::Result {
+        let (repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        write_hook(&repo, "pre-commit", "exit 1")?;
+        assert!(repo.hook("pre-commit")?.is_some(), "the hook can be obtained…");
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        repo.commit("HEAD", "message", tree, [parent])?;
+        assert_ne!(repo.head_id()?, parent, "…but isn't run by default");
+
+        let repo = gix::open_opts(
+            repo.path(),
+            crate::restricted().run_hooks(true).with(gix::sec::Trust::Reduced),
+        )?;
+        assert!(
+            repo.hook("pre-commit")?.is_none(),
+            "hooks of repositories that aren't fully trusted are ignored"
+        );
+        let parent = repo.head_id()?.detach();
+        repo.commit("HEAD", "message", tree, [parent])?;
+        assert_ne!(repo.head_id()?, parent);
+        Ok(())
+    }
+
+    #[test]
+    fn output_captures_what_the_hook_prints_along_with_its_status() -> crate::Result {
+        let (repo, _tmp) = crate::repo_rw("make_basic_repo.sh")?;
+        write_hook(&repo, "custom", r#"echo "$1 $MY_VAR"; cat >&2; exit 3"#)?;
+        let output = repo
+            .hook("custom")?
+            .expect("present and executable")
+            .arg("arg")
+            .env("MY_VAR", "value")
+            .stdin("input")
+            .output()?;
+        assert_eq!(output.stdout.as_bstr(), "arg value\n");
+        assert_eq!(output.stderr.as_bstr(), "input");
+        assert_eq!(output.status.code(), Some(3));
+
+        let err = repo.hook("custom")?.expect("present").run().unwrap_err();
+        assert!(matches!(err, gix::hook::run::Error::Failed { ref name, .. } if name == "custom"));
+        Ok(())
+    }
+
+    #[test]
+    fn commit_runs_commit_hooks_and_the_reference_transaction_hook() -> crate::Result {
+        let (repo, _tmp) = repo_with_hooks()?;
+        write_hook(
+            &repo,
+            "pre-commit",
+            r#"test -n "$GIT_INDEX_FILE" && echo pre-commit >> "$GIT_DIR/hooks.log""#,
+        )?;
+        write_hook(
+            &repo,
+            "commit-msg",
+            r#"echo commit-msg >> "$GIT_DIR/hooks.log"; echo "Signed-off-by: hook" >> "$1""#,
+        )?;
+        write_hook(&repo, "post-commit", r#"echo post-commit >> "$GIT_DIR/hooks.log""#)?;
+        write_hook(
+            &repo,
+            "reference-transaction",
+            r#"{ echo "reference-transaction $1"; cat; } >> "$GIT_DIR/hooks.log""#,
+        )?;
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        let id = repo.commit("HEAD", "message\n", tree, [parent])?;
+
+        assert_eq!(
+            id.object()?.into_commit().message_raw()?,
+            "message\nSigned-off-by: hook\n",
+            "the commit-msg hook can edit the message"
+        );
+        assert_eq!(
+            hooks_log(&repo)?,
+            format!(
+                "pre-commit\ncommit-msg\n\
+                 reference-transaction prepared\n{parent} {id} refs/heads/main\n\
+                 reference-transaction committed\n{parent} {id} refs/heads/main\n\
+                 post-commit\n"
+            ),
+            "the split-off update of HEAD isn't passed to the reference-transaction hook"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn failing_pre_commit_hook_aborts_the_commit() -> crate::Result {
+        let (repo, _tmp) = repo_with_hooks()?;
+        write_hook(&repo, "pre-commit", "exit 1")?;
+
+        let parent = repo.head_id()?.detach();
+        let tree = repo.head_tree_id()?.detach();
+        let err = repo.commit("HEAD", "message", tree, [parent]).unwrap_err();
+        assert!(matches!(
+            err,
+            gix::commit::Error::Hook(gix::hook::run::Error::Failed { ref name, .. }) if name == "pre-commit"
+        ));
+        assert_eq!(repo.head_id()?, parent, "HEAD wasn't changed");
+        Ok(())
+    }
+
+    #[test]
+    fn failing_reference_transaction_hook_aborts_the_transaction() -> crate::Result {
+        let (repo, _tmp) = repo_with_hooks()?;
+        write_hook(
+            &repo,
+            "reference-transaction",
+            r#"{ echo "$1"; cat; } >> "$GIT_DIR/hooks.log"; test "$1" != prepared"#,
+        )?;
+
+        let head = repo.head_id()?.detach();
+        let err = repo
+            .reference(
+                "refs/heads/new",
+                head,
+                gix_ref::transaction::PreviousValue::MustNotExist,
+                "create",
+            )
+            .unwrap_err();
+        assert!(matches!(err, gix::reference::edit::Error::Hook(_)));
+        assert!(
+            repo.try_find_reference("refs/heads/new")?.is_none(),
+            "the reference wasn't created"
+        );
+        let null = repo.object_hash().null();
+        assert_eq!(
+            hooks_log(&repo)?,
+            format!("prepared\n{null} {head} refs/heads/new\naborted\n{null} {head} refs/heads/new\n")
+        );
+        Ok(())
+    }
+}
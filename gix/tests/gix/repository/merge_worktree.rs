@@ -0,0 +1,254 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::ByteSlice,
+    index::entry::Stage,
+    merge::worktree::{Error, FastForward, Options, Outcome},
+};
+
+use crate::util::{head_reflog_message, id_of, read, repo_rw};
+
+#[test]
+fn fast_forward() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let ahead = id_of(&repo, "ahead")?;
+
+    let outcome = repo.merge(ahead, Options::default(), &AtomicBool::default())?;
+    assert!(matches!(outcome, Outcome::FastForward { commit, .. } if commit == ahead));
+    assert_eq!(repo.head_id()?, ahead);
+    assert_eq!(
+        repo.head_name()?.expect("not detached").as_bstr(),
+        "refs/heads/main",
+        "the branch is moved"
+    );
+    assert_eq!(head_reflog_message(&repo)?, format!("merge {ahead}: Fast-forward"));
+    assert_eq!(read(&repo, "ahead-file")?, "ahead\n");
+    assert!(repo.open_index()?.entry_by_path("ahead-file".into()).is_some());
+    assert_eq!(repo.state(), None);
+
+    let outcome = repo.merge(id_of(&repo, "main~1")?, Options::default(), &AtomicBool::default())?;
+    assert!(matches!(outcome, Outcome::UpToDate));
+    assert_eq!(repo.head_id()?, ahead, "nothing changes");
+    Ok(())
+}
+
+#[test]
+fn no_fast_forward_and_fast_forward_only() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let head = repo.head_id()?.detach();
+
+    let err = repo
+        .merge(
+            id_of(&repo, "feature")?,
+            Options {
+                fast_forward: FastForward::Only,
+                ..Default::default()
+            },
+            &AtomicBool::default(),
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::NotPossibleToFastForward { .. }), "{err:?}");
+    assert_eq!(repo.head_id()?, head);
+
+    let ahead = id_of(&repo, "ahead")?;
+    let outcome = repo.merge(
+        ahead,
+        Options {
+            fast_forward: FastForward::Never,
+            ..Default::default()
+        },
+        &AtomicBool::default(),
+    )?;
+    let Outcome::Merge {
+        commit: Some(commit), ..
+    } = outcome
+    else {
+        panic!("a merge commit is created: {outcome:?}")
+    };
+    let commit = repo.find_commit(commit)?;
+    assert_eq!(commit.parent_ids().collect::<Vec<_>>(), [head, ahead]);
+    assert_eq!(commit.tree_id()?, repo.find_commit(ahead)?.tree_id()?);
+    assert_eq!(commit.message_raw()?, format!("Merge commit '{ahead}'\n"));
+    Ok(())
+}
+
+#[test]
+fn merge_matches_git() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let head = repo.head_id()?.detach();
+    let feature = id_of(&repo, "feature")?;
+
+    let outcome = repo.merge(feature, Options::default(), &AtomicBool::default())?;
+    let Outcome::Merge {
+        commit: Some(commit),
+        conflicts,
+        ..
+    } = outcome
+    else {
+        panic!("a merge commit is created: {outcome:?}")
+    };
+    assert!(conflicts.is_empty());
+    assert_eq!(repo.head_id()?, commit, "HEAD follows the merge commit");
+
+    let commit = repo.find_commit(commit)?;
+    let expected = repo.find_commit(id_of(&repo, "expected-merge")?)?;
+    assert_eq!(commit.tree_id()?, expected.tree_id()?);
+    assert_eq!(commit.message_raw()?, expected.message_raw()?);
+    assert_eq!(commit.parent_ids().collect::<Vec<_>>(), [head, feature]);
+    assert_eq!(
+        head_reflog_message(&repo)?,
+        format!("merge {feature}: Merge made by the 'ort' strategy.")
+    );
+
+    assert_eq!(read(&repo, "lines")?, "one\n2\n3\n");
+    assert_eq!(read(&repo, "new-file")?, "new\n");
+    assert_eq!(read(&repo, "file")?, "changed\n");
+    assert_eq!(repo.state(), None);
+    assert!(!repo.git_dir().join("MERGE_HEAD").exists());
+    Ok(())
+}
+
+#[test]
+fn merge_with_conflicts() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let head = repo.head_id()?.detach();
+    let conflicting = id_of(&repo, "conflicting")?;
+
+    let outcome = repo.merge(conflicting, Options::default(), &AtomicBool::default())?;
+    let Outcome::Merge {
+        commit: None,
+        conflicts,
+        ..
+    } = outcome
+    else {
+        panic!("nothing is committed: {outcome:?}")
+    };
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(repo.head_id()?, head);
+    assert_eq!(repo.state(), Some(gix::state::InProgress::Merge));
+
+    let git_dir = repo.git_dir();
+    assert_eq!(
+        std::fs::read(git_dir.join("MERGE_HEAD"))?.as_bstr(),
+        format!("{conflicting}\n")
+    );
+    assert_eq!(
+        std::fs::read(git_dir.join("MERGE_MSG"))?.as_bstr(),
+        std::fs::read(git_dir.join("expected-merge-msg"))?.as_bstr(),
+        "the conflicts are listed like git does"
+    );
+    assert_eq!(std::fs::read(git_dir.join("MERGE_MODE"))?, b"");
+    assert_eq!(
+        read(&repo, "file")?,
+        std::fs::read(git_dir.join("expected-conflict-file"))?.as_bstr(),
+        "conflict markers are written"
+    );
+
+    let index = repo.open_index()?;
+    let stages: Vec<_> = index
+        .entries()
+        .iter()
+        .filter(|entry| entry.path(&index) == "file")
+        .map(gix::index::Entry::stage)
+        .collect();
+    assert_eq!(stages, [Stage::Base, Stage::Ours, Stage::Theirs]);
+
+    let err = repo
+        .merge(id_of(&repo, "feature")?, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::InProgress { .. }), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn merge_without_commit() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let head = repo.head_id()?.detach();
+    let feature = id_of(&repo, "feature")?;
+
+    let outcome = repo.merge(
+        feature,
+        Options {
+            fast_forward: FastForward::Never,
+            no_commit: true,
+            message: Some("custom\n".into()),
+        },
+        &AtomicBool::default(),
+    )?;
+    assert!(matches!(outcome, Outcome::Merge { commit: None, ref conflicts, .. } if conflicts.is_empty()));
+    assert_eq!(repo.head_id()?, head);
+    assert_eq!(repo.state(), Some(gix::state::InProgress::Merge));
+
+    let git_dir = repo.git_dir();
+    assert_eq!(std::fs::read(git_dir.join("MERGE_MSG"))?.as_bstr(), "custom\n");
+    assert_eq!(std::fs::read(git_dir.join("MERGE_MODE"))?.as_bstr(), "no-ff");
+    let mut index = repo.open_index()?;
+    assert_eq!(
+        repo.write_tree_from_index(&mut index)?,
+        repo.find_commit(id_of(&repo, "expected-merge")?)?.tree_id()?,
+        "the merged tree is in the index"
+    );
+    assert_eq!(read(&repo, "new-file")?, "new\n");
+    Ok(())
+}
+
+#[test]
+fn local_changes_prevent_merging() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_merge_worktree_repo.sh")?;
+    let feature = id_of(&repo, "feature")?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+
+    std::fs::write(workdir.join("lines"), "modified\n")?;
+    std::fs::write(workdir.join("new-file"), "untracked\n")?;
+    let err = repo
+        .merge(feature, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(
+        matches!(&err, Error::WouldOverwriteLocalChanges { paths } if paths == &["lines", "new-file"]),
+        "{err:?}"
+    );
+    assert_eq!(read(&repo, "lines")?, "modified\n", "nothing was changed");
+    assert_eq!(repo.state(), None);
+
+    std::fs::remove_file(workdir.join("new-file"))?;
+    std::fs::write(workdir.join("lines"), "1\n2\n3\n")?;
+    std::fs::write(workdir.join("file"), "staged\n")?;
+    let mut index = repo.open_index()?;
+    repo.add_to_index(
+        &mut index,
+        ["file"],
+        gix::staging::add::Options::default(),
+        &AtomicBool::default(),
+    )?;
+    index.write(Default::default())?;
+    let err = repo
+        .merge(feature, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::DirtyIndex), "{err:?}");
+    Ok(())
+}
+
+#[test]
+#[cfg(all(unix, feature = "command"))]
+fn post_merge_hook_runs_after_changing_head() -> crate::Result {
+    use std::os::unix::fs::PermissionsExt;
+
+    let (repo, _tmp) = crate::util::repo_rw_opts("make_merge_worktree_repo.sh", crate::restricted().run_hooks(true))?;
+    let hook_path = repo.hooks_dir()?.join("post-merge");
+    std::fs::create_dir_all(hook_path.parent().expect("parent"))?;
+    std::fs::write(
+        &hook_path,
+        "#!/bin/sh\necho \"post-merge $1\" >> \"$GIT_DIR/hooks.log\"\nexit 1\n",
+    )?;
+    std::fs::set_permissions(&hook_path, std::fs::Permissions::from_mode(0o755))?;
+
+    repo.merge(id_of(&repo, "ahead")?, Options::default(), &AtomicBool::default())?;
+    repo.merge(id_of(&repo, "feature")?, Options::default(), &AtomicBool::default())?;
+    repo.merge(id_of(&repo, "conflicting")?, Options::default(), &AtomicBool::default())?;
+    assert_eq!(
+        std::fs::read_to_string(repo.git_dir().join("hooks.log"))?,
+        "post-merge 0\npost-merge 0\n",
+        "the hook doesn't run on conflicts, and its failure is ignored"
+    );
+    Ok(())
+}
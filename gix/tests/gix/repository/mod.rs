@@ -2,20 +2,43 @@ use gix::Repository;
 
 #[cfg(feature = "blame")]
 mod blame;
+#[cfg(feature = "worktree-mutation")]
+mod checkout;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod cherry_pick;
+mod commit_graph;
 mod config;
 #[cfg(feature = "excludes")]
 mod excludes;
 #[cfg(feature = "attributes")]
 mod filter;
+#[cfg(feature = "command")]
+mod gpg;
+#[cfg(feature = "command")]
+mod hook;
 #[cfg(feature = "merge")]
 mod merge;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod merge_worktree;
 mod object;
 mod open;
+#[cfg(all(feature = "attributes", feature = "blocking-network-client"))]
+mod pack;
 #[cfg(feature = "attributes")]
 mod pathspec;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod rebase;
 mod reference;
 mod remote;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod sequencer;
 mod shallow;
+#[cfg(feature = "worktree-mutation")]
+mod sparse;
+#[cfg(all(feature = "dirwalk", feature = "worktree-mutation"))]
+mod staging;
+#[cfg(all(feature = "status", feature = "merge", feature = "worktree-mutation"))]
+mod stash;
 mod state;
 #[cfg(feature = "attributes")]
 mod submodule;
@@ -70,6 +93,70 @@ mod index {
         );
         Ok(())
     }
+
+    #[test]
+    fn write_tree_from_index() -> crate::Result {
+        let repo = crate::named_repo("make_basic_repo.sh")?.with_object_memory();
+        let mut index = repo.open_index()?;
+        let head_tree = repo.head_tree_id()?;
+        assert_eq!(repo.write_tree_from_index(&mut index)?, head_tree);
+
+        index.remove_tree();
+        assert_eq!(
+            repo.write_tree_from_index(&mut index)?,
+            head_tree,
+            "without the TREE extension the same tree is written"
+        );
+        assert_eq!(
+            index.tree().map(|tree| tree.id),
+            Some(head_tree.detach()),
+            "the TREE extension is recreated"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn write_options() -> crate::Result {
+        use gix::config::tree::{Core, Index, SplitIndex};
+        let mut repo = crate::named_repo("make_basic_repo.sh")?;
+        let index = gix::index::State::new(repo.object_hash());
+        let options = repo.index_write_options(&index)?;
+        assert!(!options.skip_hash);
+        assert!(
+            options.split_index.is_none(),
+            "indices that aren't split stay that way by default"
+        );
+
+        {
+            let mut config = repo.config_snapshot_mut();
+            config.set_raw_value(&Index::SKIP_HASH, "true")?;
+            config.set_raw_value(&Core::SPLIT_INDEX, "true")?;
+            config.set_raw_value(&SplitIndex::MAX_PERCENT_CHANGE, "50")?;
+            config.set_raw_value(&SplitIndex::SHARED_INDEX_EXPIRE, "never")?;
+        }
+        let options = repo.index_write_options(&index)?;
+        assert!(options.skip_hash);
+        let split_index = options.split_index.expect("configured");
+        assert_eq!(split_index.max_percent_change, 50);
+        assert_eq!(split_index.shared_index_expire, None);
+        Ok(())
+    }
+
+    #[test]
+    fn index_from_tree_uses_configured_version() -> crate::Result {
+        let mut repo = crate::named_repo("make_basic_repo.sh")?;
+        let head_tree = repo.head_tree_id()?.detach();
+        assert_eq!(repo.index_from_tree(&head_tree)?.version(), gix::index::Version::V2);
+
+        repo.config_snapshot_mut()
+            .set_raw_value(&gix::config::tree::Index::VERSION, "4")?;
+        let index = repo.index_from_tree(&head_tree)?;
+        assert_eq!(index.version(), gix::index::Version::V4);
+        let mut buf = Vec::new();
+        let (version, _) = index.write_to(&mut buf, Default::default())?;
+        assert_eq!(version, gix::index::Version::V4, "the version is used when writing");
+        Ok(())
+    }
 }
 
 #[cfg(feature = "dirwalk")]
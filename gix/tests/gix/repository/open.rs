@@ -70,17 +70,41 @@ fn non_bare_reftable() -> crate::Result {
         }
         Err(err) => panic!("{err}"),
     };
-    assert!(
-        repo.head_id().is_err(),
-        "Trying to do anything with head will fail as we don't support reftables yet"
+    assert_eq!(repo.refs.format(), gix_ref::store::Format::Reftable);
+    let head_id = repo.head_id()?;
+    assert_eq!(
+        repo.head_name()?.expect("not detached").as_bstr(),
+        "refs/heads/main",
+        "HEAD is a symbolic ref stored in the table"
+    );
+    let names = repo
+        .references()?
+        .all()?
+        .map(|r| r.map(|r| r.name().as_bstr().to_string()))
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(
+        names,
+        [
+            "refs/heads/main",
+            "refs/remotes/origin/HEAD",
+            "refs/remotes/origin/main"
+        ]
+    );
+    let head = repo.head()?;
+    let mut log = head.log_iter();
+    let entries = log
+        .all()?
+        .expect("clone writes a reflog")
+        .collect::<Result<Vec<_>, _>>()?;
+    assert_eq!(entries.len(), 1);
+    assert_eq!(
+        entries[0].new_oid(),
+        head_id.detach(),
+        "the log leads to the current value"
     );
     assert!(!repo.is_bare());
     assert_eq!(repo.kind(), gix::repository::Kind::Common);
-    assert_ne!(
-        repo.workdir(),
-        None,
-        "Otherwise it can be used, but it's hard to do without refs"
-    );
+    assert_ne!(repo.workdir(), None);
     Ok(())
 }
 
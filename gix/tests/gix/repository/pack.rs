@@ -0,0 +1,40 @@
+use gix::objs::tree::EntryKind;
+use gix::odb::pack::data::output;
+
+use crate::util::{freeze_time, restricted_and_git};
+
+#[test]
+#[serial_test::serial]
+fn mark_no_delta_uses_the_delta_attribute_of_the_first_path() -> crate::Result {
+    let _env = freeze_time();
+    let tmp = gix_testtools::tempfile::tempdir()?;
+    let repo =
+        gix::ThreadSafeRepository::init_opts(&tmp, gix::create::Kind::Bare, Default::default(), restricted_and_git())?
+            .to_thread_local();
+    let attributes = repo.write_blob("*.bin -delta\nexplicit.bin delta\n")?.detach();
+    let binary = repo.write_blob("binary")?.detach();
+    let explicit = repo.write_blob("explicitly allowed")?.detach();
+    let text = repo.write_blob("text")?.detach();
+    let tree = repo
+        .edit_tree(repo.object_hash().empty_tree())?
+        .upsert(".gitattributes", EntryKind::Blob, attributes)?
+        .upsert("dir/a.bin", EntryKind::Blob, binary)?
+        .upsert("dir/explicit.bin", EntryKind::Blob, explicit)?
+        .upsert("text.txt", EntryKind::Blob, text)?
+        .write()?;
+    let commit = repo
+        .commit("HEAD", "initial", tree, gix::commit::NO_PARENT_IDS)?
+        .detach();
+
+    let mut counts: Vec<_> = [binary, explicit, text]
+        .into_iter()
+        .map(|id| output::Count::from_data(id, None))
+        .collect();
+    repo.mark_no_delta(&[commit], &mut counts)?;
+    assert_eq!(
+        counts.iter().map(|c| c.no_delta).collect::<Vec<_>>(),
+        [true, false, false],
+        "only blobs whose path has the attribute unset are marked"
+    );
+    Ok(())
+}
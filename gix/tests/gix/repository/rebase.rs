@@ -0,0 +1,332 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::ByteSlice,
+    rebase::{todo, Error, Options, State, Stop},
+    staging::add,
+    ObjectId,
+};
+
+use crate::util::{head_reflog_messages, history, id_of, named_subrepo_rw, read};
+
+fn write_and_add(repo: &gix::Repository, rela_path: &str, content: &str) -> crate::Result {
+    std::fs::write(repo.workdir().expect("non-bare").join(rela_path), content)?;
+    let mut index = repo.open_index()?;
+    repo.add_to_index(&mut index, [rela_path], add::Options::default(), &AtomicBool::default())?;
+    index.write(Default::default())?;
+    Ok(())
+}
+
+fn todo_lines(list: &todo::List) -> crate::Result<Vec<String>> {
+    let mut out = Vec::new();
+    list.write_to(&mut out)?;
+    Ok(out
+        .lines()
+        .map(|line| {
+            let line = line.to_str_lossy();
+            match line.split_once(' ') {
+                Some((command @ ("pick" | "fixup" | "squash"), rest)) => {
+                    format!(
+                        "{command} {}",
+                        rest.split_once(' ').map_or(rest, |(_name, summary)| summary)
+                    )
+                }
+                _ => line.into_owned(),
+            }
+        })
+        .collect())
+}
+
+fn assert_is_on_branch(repo: &gix::Repository, name: &str) -> crate::Result {
+    assert_eq!(
+        repo.head_name()?.map(|name| name.as_bstr().to_string()),
+        Some(name.to_string()),
+        "HEAD refers to the branch again"
+    );
+    assert_eq!(repo.state(), None);
+    assert!(!State::exists(repo.git_dir()), "the state is removed once done");
+    Ok(())
+}
+
+fn start_and_stop_at_conflict(repo: &gix::Repository) -> crate::Result<ObjectId> {
+    let main = id_of(repo, "main")?;
+    let todo = repo.rebase_todo(main, repo.head_id()?, &Options::default())?;
+    let outcome = repo.rebase_start(main, todo, Options::default(), &AtomicBool::default())?;
+    let change_file = id_of(repo, "conflicting~1")?;
+    assert!(
+        matches!(outcome.stop, Some(Stop::Conflicts { commit, ref conflicts }) if commit == change_file && conflicts.len() == 1),
+        "{:?}",
+        outcome.stop
+    );
+    assert_eq!(outcome.head, main, "nothing was picked yet");
+    assert_eq!(repo.state(), Some(gix::state::InProgress::Rebase));
+    assert_eq!(
+        repo.rebase_status()?
+            .map(|status| (status.head_name, status.step, status.total_steps)),
+        Some((Some("refs/heads/conflicting".into()), Some(1), Some(2)))
+    );
+    assert_eq!(id_of(repo, "REBASE_HEAD")?, change_file);
+    Ok(change_file)
+}
+
+#[test]
+fn todo_lists() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "topic")?;
+    let main = id_of(&repo, "main")?;
+    let head = repo.head_id()?;
+
+    let todo = repo.rebase_todo(main, head, &Options::default())?;
+    assert_eq!(
+        todo_lines(&todo)?,
+        ["pick add a", "pick add b", "pick fixup! add a", "pick squash! add b"]
+    );
+
+    let todo = repo.rebase_todo(
+        main,
+        head,
+        &Options {
+            autosquash: true,
+            update_refs: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(
+        todo_lines(&todo)?,
+        [
+            "pick add a",
+            "fixup fixup! add a",
+            "update-ref refs/heads/part",
+            "pick add b",
+            "squash squash! add b"
+        ]
+    );
+
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "merges")?;
+    let todo = repo.rebase_todo(id_of(&repo, "main")?, repo.head_id()?, &Options::default())?;
+    assert_eq!(
+        todo_lines(&todo)?,
+        ["pick add x", "pick add f", "pick add y"],
+        "merges are left out"
+    );
+    Ok(())
+}
+
+#[test]
+fn linear_rebase_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "topic")?;
+    let main = id_of(&repo, "main")?;
+    let orig_head = repo.head_id()?.detach();
+    let todo = repo.rebase_todo(main, orig_head, &Options::default())?;
+
+    let outcome = repo.rebase_start(main, todo, Options::default(), &AtomicBool::default())?;
+    assert!(outcome.stop.is_none());
+    assert_eq!(repo.head_id()?, outcome.head);
+    assert_is_on_branch(&repo, "refs/heads/topic")?;
+    assert_eq!(history(&repo, "topic", 5)?, history(&repo, "expected-linear", 5)?);
+    assert_eq!(id_of(&repo, "ORIG_HEAD")?, orig_head);
+    assert_eq!(read(&repo, "m")?, "m\n", "the worktree was updated");
+    assert_eq!(
+        head_reflog_messages(&repo, 5)?,
+        [
+            "rebase (pick): squash! add b".to_string(),
+            "rebase (pick): fixup! add a".into(),
+            "rebase (pick): add b".into(),
+            "rebase (pick): add a".into(),
+            format!("rebase (start): checkout {main}"),
+        ],
+        "returning to the branch doesn't change the id of HEAD, so it's not logged"
+    );
+    Ok(())
+}
+
+#[test]
+fn autosquash_and_update_refs_match_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "topic")?;
+    let main = id_of(&repo, "main")?;
+    let options = Options {
+        autosquash: true,
+        update_refs: true,
+        ..Default::default()
+    };
+    let todo = repo.rebase_todo(main, repo.head_id()?, &options)?;
+
+    let outcome = repo.rebase_start(main, todo, options, &AtomicBool::default())?;
+    assert!(outcome.stop.is_none());
+    assert_is_on_branch(&repo, "refs/heads/topic")?;
+    assert_eq!(history(&repo, "topic", 3)?, history(&repo, "expected-autosquash", 3)?);
+    assert_eq!(
+        history(&repo, "part", 2)?,
+        history(&repo, "expected-autosquash-part", 2)?,
+        "the branch was updated to the commit with the fixup"
+    );
+    Ok(())
+}
+
+#[test]
+fn continue_after_conflict_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "conflicting")?;
+    start_and_stop_at_conflict(&repo)?;
+
+    let err = repo.rebase_continue(None, &AtomicBool::default()).unwrap_err();
+    assert!(matches!(err, Error::UnmergedIndex), "{err:?}");
+
+    write_and_add(&repo, "file", "resolved\n")?;
+    let outcome = repo.rebase_continue(None, &AtomicBool::default())?;
+    assert!(outcome.stop.is_none());
+    assert_is_on_branch(&repo, "refs/heads/conflicting")?;
+    assert!(!repo.git_dir().join("REBASE_HEAD").exists());
+    assert_eq!(
+        history(&repo, "conflicting", 3)?,
+        history(&repo, "expected-continue", 3)?
+    );
+    assert_eq!(
+        head_reflog_messages(&repo, 2)?,
+        ["rebase (pick): add c", "rebase (continue): change file"]
+    );
+    Ok(())
+}
+
+#[test]
+fn skip_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "conflicting")?;
+    start_and_stop_at_conflict(&repo)?;
+
+    let outcome = repo.rebase_skip(&AtomicBool::default())?;
+    assert!(outcome.stop.is_none());
+    assert_is_on_branch(&repo, "refs/heads/conflicting")?;
+    assert_eq!(read(&repo, "file")?, "main\n", "the conflict was reset");
+    assert_eq!(history(&repo, "conflicting", 2)?, history(&repo, "expected-skip", 2)?);
+    Ok(())
+}
+
+#[test]
+fn abort_and_quit() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "conflicting")?;
+    let orig_head = repo.head_id()?.detach();
+    start_and_stop_at_conflict(&repo)?;
+
+    repo.rebase_abort(&AtomicBool::default())?;
+    assert_is_on_branch(&repo, "refs/heads/conflicting")?;
+    assert_eq!(repo.head_id()?, orig_head);
+    assert_eq!(read(&repo, "file")?, "topic\n");
+    assert!(!repo.workdir().expect("non-bare").join("m").exists());
+    assert_eq!(
+        head_reflog_messages(&repo, 1)?,
+        ["rebase (abort): returning to refs/heads/conflicting"]
+    );
+
+    start_and_stop_at_conflict(&repo)?;
+    let head = repo.head_id()?;
+    repo.rebase_quit()?;
+    assert!(!State::exists(repo.git_dir()));
+    assert_eq!(repo.head_id()?, head, "HEAD stays detached");
+    assert!(repo.head_name()?.is_none());
+
+    let err = repo.rebase_quit().unwrap_err();
+    assert!(matches!(err, Error::NothingInProgress), "{err:?}");
+    Ok(())
+}
+
+#[test]
+fn rebase_merges_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "merges")?;
+    let main = id_of(&repo, "main")?;
+    let todo = todo::List::from_bytes(&std::fs::read(repo.git_dir().join("merges-todo"))?)?;
+
+    let outcome = repo.rebase_start(main, todo, Options::default(), &AtomicBool::default())?;
+    assert!(outcome.stop.is_none());
+    assert_is_on_branch(&repo, "refs/heads/merges")?;
+    assert_eq!(history(&repo, "merges", 4)?, history(&repo, "expected-merges", 4)?);
+    assert_eq!(
+        history(&repo, "merges~1^2", 2)?,
+        history(&repo, "expected-merges~1^2", 2)?,
+        "the merged branch was rebased as well"
+    );
+    assert_eq!(
+        repo.find_commit(id_of(&repo, "merges~1")?)?.author()?,
+        repo.find_commit(id_of(&repo, "expected-merges~1")?)?.author()?,
+        "the author of the merge is kept"
+    );
+    assert!(
+        repo.references()?.prefixed("refs/rewritten/")?.next().is_none(),
+        "labels are removed"
+    );
+    Ok(())
+}
+
+#[test]
+fn edit_reword_break_and_exec() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "topic")?;
+    let main = id_of(&repo, "main")?;
+    let [add_a, add_b, fixup, squash] =
+        ["topic~3", "topic~2", "topic~1", "topic"].map(|spec| id_of(&repo, spec).expect("valid"));
+    let todo = todo::List::from_bytes(
+        format!("edit {add_a}\nreword {add_b}\nbreak\nexec exit 3\ndrop {fixup}\npick {squash}\n").as_bytes(),
+    )?;
+
+    let outcome = repo.rebase_start(main, todo, Options::default(), &AtomicBool::default())?;
+    assert!(matches!(outcome.stop, Some(Stop::Edit { commit }) if commit == add_a));
+    assert_eq!(
+        State::read(repo.git_dir())?.expect("in progress").amend,
+        Some(outcome.head)
+    );
+
+    write_and_add(&repo, "a", "edited\n")?;
+    let outcome = repo.rebase_continue(None, &AtomicBool::default())?;
+    assert!(matches!(outcome.stop, Some(Stop::Reword { commit }) if commit == add_b));
+
+    let outcome = repo.rebase_continue(Some("reworded\n".into()), &AtomicBool::default())?;
+    assert!(matches!(outcome.stop, Some(Stop::Break)));
+
+    let outcome = repo.rebase_continue(None, &AtomicBool::default())?;
+    if cfg!(feature = "command") {
+        assert!(
+            matches!(outcome.stop, Some(Stop::ExecFailed { ref command, status: Some(status) }) if command == "exit 3" && status.code() == Some(3)),
+            "{:?}",
+            outcome.stop
+        );
+        let outcome = repo.rebase_continue(None, &AtomicBool::default())?;
+        assert!(outcome.stop.is_none());
+    }
+    assert_is_on_branch(&repo, "refs/heads/topic")?;
+    assert_eq!(
+        history(&repo, "topic", 4)?
+            .into_iter()
+            .map(|(message, _tree)| message)
+            .collect::<Vec<_>>(),
+        [
+            "squash! add b\n\nmore about b\n",
+            "reworded\n",
+            "add a\n",
+            "change file on main\n"
+        ]
+    );
+    assert_eq!(read(&repo, "a")?, "edited\n");
+    assert_eq!(read(&repo, "b")?, "b\nb2\n");
+    Ok(())
+}
+
+#[test]
+fn preconditions() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_rebase_repos.sh", "topic")?;
+    let main = id_of(&repo, "main")?;
+
+    let err = repo.rebase_continue(None, &AtomicBool::default()).unwrap_err();
+    assert!(matches!(err, Error::NothingInProgress), "{err:?}");
+
+    std::fs::write(repo.workdir().expect("non-bare").join("a"), "changed\n")?;
+    let todo = repo.rebase_todo(main, repo.head_id()?, &Options::default())?;
+    let err = repo
+        .rebase_start(main, todo, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::DirtyWorktree), "{err:?}");
+    std::fs::write(repo.workdir().expect("non-bare").join("a"), "a\na2\n")?;
+
+    let head = repo.head_id()?;
+    let todo = todo::List::from_bytes(format!("fixup {head}\n").as_bytes())?;
+    let err = repo
+        .rebase_start(main, todo, Options::default(), &AtomicBool::default())
+        .unwrap_err();
+    assert!(matches!(err, Error::NoPreviousCommit { command: "fixup" }), "{err:?}");
+    Ok(())
+}
@@ -0,0 +1,211 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::ByteSlice,
+    sequencer::{Error, Operation, Options, State},
+    staging::add,
+    ObjectId,
+};
+
+use crate::util::{head_reflog_messages, history, id_of, named_subrepo_rw, read};
+
+fn other_commits(repo: &gix::Repository) -> crate::Result<Vec<ObjectId>> {
+    ["other~2", "other~1", "other"]
+        .into_iter()
+        .map(|spec| id_of(repo, spec))
+        .collect()
+}
+
+fn resolve_with(repo: &gix::Repository, content: &str) -> crate::Result {
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), content)?;
+    let mut index = repo.open_index()?;
+    repo.add_to_index(&mut index, ["file"], add::Options::default(), &AtomicBool::default())?;
+    index.write(Default::default())?;
+    Ok(())
+}
+
+fn start_and_stop_at_conflict(repo: &gix::Repository) -> crate::Result<Vec<ObjectId>> {
+    let commits = other_commits(repo)?;
+    let outcome = repo.sequence_start(
+        Operation::CherryPick,
+        commits.iter().copied(),
+        Options {
+            record_origin: true,
+            ..Default::default()
+        },
+        &AtomicBool::default(),
+    )?;
+    assert_eq!(outcome.commits.len(), 1, "the first commit applies cleanly");
+    assert_eq!(outcome.stopped_at, Some(commits[1]));
+    assert_eq!(outcome.conflicts.len(), 1);
+    Ok(outcome.commits)
+}
+
+#[test]
+fn start_and_continue_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "fresh")?;
+    let head_before = repo.head_id()?.detach();
+    let created = start_and_stop_at_conflict(&repo)?;
+    assert_eq!(repo.state(), Some(gix::state::InProgress::CherryPickSequence));
+
+    let state = State::read(repo.git_dir())?.expect("sequence in progress");
+    assert_eq!(
+        state.todo.instructions.len(),
+        2,
+        "the commit with conflicts is still on the todo list"
+    );
+    assert_eq!(state.head, head_before);
+    assert_eq!(state.abort_safety, Some(created[0]));
+    assert_eq!(
+        std::fs::read(State::dir(repo.git_dir()).join("opts"))?.as_bstr(),
+        "[options]\n\trecord-origin = true\n"
+    );
+
+    let err = repo.sequence_continue(&AtomicBool::default()).unwrap_err();
+    assert!(matches!(err, Error::UnmergedIndex), "{err:?}");
+
+    resolve_with(&repo, "resolved\n")?;
+    let outcome = repo.sequence_continue(&AtomicBool::default())?;
+    assert_eq!(outcome.commits.len(), 2);
+    assert_eq!(outcome.stopped_at, None);
+    assert_eq!(repo.head_id()?, outcome.commits[1]);
+    assert_eq!(repo.state(), None);
+    assert!(!State::exists(repo.git_dir()), "the state is removed once done");
+
+    assert_eq!(history(&repo, "HEAD", 4)?, history(&repo, "expected-continue", 4)?);
+    assert_eq!(
+        head_reflog_messages(&repo, 3)?,
+        [
+            "cherry-pick: add c",
+            "commit (cherry-pick): change file",
+            "cherry-pick: add a"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn continue_a_sequence_started_by_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "started-by-git")?;
+    assert_eq!(repo.state(), Some(gix::state::InProgress::CherryPickSequence));
+
+    resolve_with(&repo, "resolved\n")?;
+    let outcome = repo.sequence_continue(&AtomicBool::default())?;
+    assert_eq!(outcome.commits.len(), 2);
+    assert_eq!(repo.state(), None);
+    assert_eq!(history(&repo, "HEAD", 4)?, history(&repo, "expected-continue", 4)?);
+    Ok(())
+}
+
+#[test]
+fn skip_matches_git() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "fresh")?;
+    start_and_stop_at_conflict(&repo)?;
+
+    let outcome = repo.sequence_skip(&AtomicBool::default())?;
+    assert_eq!(outcome.commits.len(), 1);
+    assert_eq!(outcome.stopped_at, None);
+    assert_eq!(repo.state(), None);
+    assert!(!repo.git_dir().join("CHERRY_PICK_HEAD").exists());
+    assert_eq!(read(&repo, "file")?, "main\n", "the conflict was reset");
+    assert_eq!(history(&repo, "HEAD", 3)?, history(&repo, "expected-skip", 3)?);
+    Ok(())
+}
+
+#[test]
+fn abort_rewinds_unless_head_was_moved() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "fresh")?;
+    let head_before = repo.head_id()?.detach();
+    start_and_stop_at_conflict(&repo)?;
+
+    assert!(repo.sequence_abort(&AtomicBool::default())?);
+    assert_eq!(repo.head_id()?, head_before);
+    assert_eq!(repo.state(), None);
+    assert!(!State::exists(repo.git_dir()));
+    assert_eq!(read(&repo, "file")?, "main\n");
+    assert!(!repo.workdir().expect("non-bare").join("a").exists());
+    assert!(repo.open_index()?.entry_by_path("a".into()).is_none());
+    assert_eq!(
+        head_reflog_messages(&repo, 1)?,
+        [format!("reset: moving to {head_before}")]
+    );
+
+    let created = start_and_stop_at_conflict(&repo)?;
+    let mut state = State::read(repo.git_dir())?.expect("sequence in progress");
+    state.abort_safety = Some(head_before);
+    state.write(repo.git_dir())?;
+    assert!(
+        !repo.sequence_abort(&AtomicBool::default())?,
+        "HEAD doesn't point to the last commit of the sequence anymore"
+    );
+    assert_eq!(repo.head_id()?, created[0], "HEAD wasn't moved");
+    assert!(!State::exists(repo.git_dir()), "the state is removed nonetheless");
+    Ok(())
+}
+
+#[test]
+fn quit_keeps_everything_as_is() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "fresh")?;
+    let created = start_and_stop_at_conflict(&repo)?;
+    let conflicted = read(&repo, "file")?;
+
+    repo.sequence_quit()?;
+    assert_eq!(repo.state(), None);
+    assert!(!State::exists(repo.git_dir()));
+    assert!(!repo.git_dir().join("MERGE_MSG").exists());
+    assert_eq!(repo.head_id()?, created[0]);
+    assert_eq!(read(&repo, "file")?, conflicted);
+    Ok(())
+}
+
+#[test]
+fn revert_and_preconditions() -> crate::Result {
+    let (repo, _tmp) = named_subrepo_rw("make_sequencer_repos.sh", "fresh")?;
+    let should_interrupt = AtomicBool::default();
+    for err in [
+        repo.sequence_continue(&should_interrupt).unwrap_err(),
+        repo.sequence_skip(&should_interrupt).unwrap_err(),
+    ] {
+        assert!(matches!(err, Error::NothingInProgress), "{err:?}");
+    }
+    let err = repo.sequence_abort(&should_interrupt).unwrap_err();
+    assert!(matches!(err, Error::NothingInProgress), "{err:?}");
+
+    let err = repo
+        .sequence_start(
+            Operation::CherryPick,
+            other_commits(&repo)?,
+            Options {
+                signoff: true,
+                ..Default::default()
+            },
+            &should_interrupt,
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::UnsupportedOption { name: "signoff" }), "{err:?}");
+    assert!(!State::exists(repo.git_dir()), "nothing was written");
+
+    start_and_stop_at_conflict(&repo)?;
+    let err = repo
+        .sequence_start(
+            Operation::CherryPick,
+            other_commits(&repo)?,
+            Options::default(),
+            &should_interrupt,
+        )
+        .unwrap_err();
+    assert!(matches!(err, Error::InProgress { .. }), "{err:?}");
+    assert!(repo.sequence_abort(&should_interrupt)?);
+
+    let outcome = repo.sequence_start(
+        Operation::Revert,
+        [id_of(&repo, "main")?],
+        Options::default(),
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.commits.len(), 1);
+    assert_eq!(read(&repo, "file")?, "base\n");
+    assert!(!State::exists(repo.git_dir()));
+
+    Ok(())
+}
@@ -0,0 +1,139 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::worktree::sparse::Patterns;
+use gix::{bstr::ByteSlice, config::tree::Core};
+
+use crate::util::repo_rw;
+
+fn skipped_paths(repo: &gix::Repository) -> crate::Result<Vec<String>> {
+    let index = repo.open_index()?;
+    Ok(index
+        .entries()
+        .iter()
+        .filter(|e| e.flags.contains(gix::index::entry::Flags::SKIP_WORKTREE))
+        .map(|e| e.path(&index).to_str_lossy().into_owned())
+        .collect())
+}
+
+#[test]
+fn set_add_and_remove_cone_directories() -> crate::Result {
+    let (mut repo, _tmp) = repo_rw("make_sparse_checkout_repo.sh")?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+    let should_interrupt = AtomicBool::default();
+    assert!(repo.sparse_checkout_patterns()?.is_none());
+    assert!(matches!(
+        repo.sparse_checkout_add_directories(["a"], &should_interrupt),
+        Err(gix::repository::sparse_checkout_set::Error::NotEnabled)
+    ));
+
+    std::fs::write(workdir.join("d/file"), "modified")?;
+    let outcome = repo.sparse_checkout_set(Patterns::from_cone_directories(["a"]), &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 1, "only d/e/file was unmodified");
+    assert_eq!(outcome.files_kept, ["d/file"], "modified files are kept");
+    assert_eq!(outcome.checkout.files_updated, 0, "nothing was excluded before");
+    assert_eq!(skipped_paths(&repo)?, ["d/e/file"]);
+    assert!(!workdir.join("d/e").exists(), "empty directories are removed");
+    assert!(workdir.join("d/file").is_file());
+
+    let patterns = repo.sparse_checkout_patterns()?.expect("enabled");
+    assert_eq!(
+        patterns.cone_directories().expect("cone mode").collect::<Vec<_>>(),
+        ["a"]
+    );
+    for reopened in [&repo, &gix::open_opts(repo.path(), crate::restricted())?] {
+        let config = reopened.config_snapshot();
+        assert_eq!(config.boolean(Core::SPARSE_CHECKOUT), Some(true));
+        assert_eq!(config.boolean(Core::SPARSE_CHECKOUT_CONE), Some(true));
+    }
+    let local_config =
+        gix::config::File::from_path_no_includes(repo.path().join("config"), gix::config::Source::Local)?;
+    assert_eq!(
+        local_config.boolean(gix::config::tree::Extensions::WORKTREE_CONFIG),
+        Some(Ok(true)),
+        "the sparse checkout only affects the current worktree"
+    );
+    assert_eq!(local_config.integer(Core::REPOSITORY_FORMAT_VERSION), Some(Ok(1)));
+    assert!(local_config.boolean(Core::SPARSE_CHECKOUT).is_none());
+    let worktree_config =
+        gix::config::File::from_path_no_includes(repo.path().join("config.worktree"), gix::config::Source::Worktree)?;
+    assert_eq!(worktree_config.boolean(Core::SPARSE_CHECKOUT), Some(Ok(true)));
+    assert_eq!(worktree_config.boolean(Core::SPARSE_CHECKOUT_CONE), Some(Ok(true)));
+
+    std::fs::write(workdir.join("d/file"), "d/file\n")?;
+    let outcome = repo.sparse_checkout_add_directories(["d/e"], &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 0);
+    assert_eq!(outcome.checkout.files_updated, 1);
+    assert_eq!(std::fs::read(workdir.join("d/e/file"))?.as_bstr(), "d/e/file\n");
+    assert!(
+        skipped_paths(&repo)?.is_empty(),
+        "files directly in the leading directories of cone directories are included"
+    );
+
+    let outcome = repo.sparse_checkout_remove_directories(["/a/"], &should_interrupt)?;
+    assert_eq!(outcome.files_removed, 2);
+    assert!(!workdir.join("a").exists());
+    assert_eq!(skipped_paths(&repo)?, ["a/b/file", "a/file"]);
+    assert_eq!(
+        repo.sparse_checkout_patterns()?
+            .expect("enabled")
+            .cone_directories()
+            .expect("cone mode")
+            .collect::<Vec<_>>(),
+        ["d/e"]
+    );
+    assert_eq!(std::fs::read(workdir.join("root"))?.as_bstr(), "root\n");
+
+    let outcome = repo.sparse_checkout_set(Patterns::from_bytes(b"/*\n", false), &should_interrupt)?;
+    assert_eq!(
+        outcome.checkout.files_updated, 2,
+        "everything is checked out in non-cone mode"
+    );
+    assert!(skipped_paths(&repo)?.is_empty());
+    assert!(matches!(
+        repo.sparse_checkout_add_directories(["a"], &should_interrupt),
+        Err(gix::repository::sparse_checkout_set::Error::NotConeMode)
+    ));
+    Ok(())
+}
+
+fn sparse_directories(repo: &gix::Repository) -> crate::Result<Vec<String>> {
+    let index = repo.open_index()?;
+    Ok(index
+        .entries()
+        .iter()
+        .filter(|e| e.mode.is_sparse())
+        .map(|e| e.path(&index).to_str_lossy().into_owned())
+        .collect())
+}
+
+#[test]
+#[cfg(feature = "status")]
+fn sparse_index_is_collapsed_when_written_and_expanded_when_changed() -> crate::Result {
+    let (mut repo, _tmp) = repo_rw("make_sparse_checkout_repo.sh")?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+    repo.config_snapshot_mut()
+        .set_value(&gix::config::tree::Index::SPARSE, "true")?;
+    let should_interrupt = AtomicBool::default();
+
+    repo.sparse_checkout_set(Patterns::from_cone_directories(["a"]), &should_interrupt)?;
+    assert!(repo.open_index()?.is_sparse());
+    assert_eq!(sparse_directories(&repo)?, ["d/"]);
+    assert!(!workdir.join("d").exists());
+    assert!(
+        !repo.is_dirty()?,
+        "the sparse index is expanded to compare it with HEAD"
+    );
+
+    repo.sparse_checkout_add_directories(["d/e"], &should_interrupt)?;
+    assert!(
+        sparse_directories(&repo)?.is_empty(),
+        "the sparse directory was expanded to check out d/e"
+    );
+    assert_eq!(std::fs::read(workdir.join("d/e/file"))?.as_bstr(), "d/e/file\n");
+
+    repo.sparse_checkout_set(Patterns::from_cone_directories(["d"]), &should_interrupt)?;
+    assert_eq!(sparse_directories(&repo)?, ["a/"]);
+    assert!(!workdir.join("a").exists());
+    assert!(!repo.is_dirty()?);
+    Ok(())
+}
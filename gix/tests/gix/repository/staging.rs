@@ -0,0 +1,237 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::BString,
+    index::entry::{Flags, Stage},
+    staging::{add, remove},
+};
+
+use crate::util::repo_rw;
+
+fn paths(index: &gix::index::State) -> Vec<BString> {
+    index.entries().iter().map(|e| e.path(index).to_owned()).collect()
+}
+
+fn blob(repo: &gix::Repository, data: &str) -> crate::Result<gix::ObjectId> {
+    Ok(gix::objs::compute_hash(
+        repo.object_hash(),
+        gix::objs::Kind::Blob,
+        data.as_bytes(),
+    )?)
+}
+
+#[test]
+fn add_all() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let mut index = repo.open_index()?;
+
+    let outcome = repo.add_to_index(&mut index, None::<&str>, add::Options::default(), &should_interrupt)?;
+    assert_eq!(outcome.added, ["dir/new", "untracked"]);
+    assert_eq!(outcome.modified, ["file"]);
+    assert_eq!(outcome.removed, ["removed"]);
+    assert!(
+        outcome.ignored.is_empty(),
+        "ignored files are only reported if named explicitly"
+    );
+    assert_eq!(
+        paths(&index),
+        [".gitignore", "dir/a", "dir/new", "file", "unchanged", "untracked"]
+    );
+    assert!(index.verify_entries().is_ok());
+    assert_eq!(
+        index.entry_by_path("file".into()).expect("present").id,
+        blob(&repo, "base\nmodified\n")?
+    );
+    assert!(
+        repo.find_object(blob(&repo, "new\n")?).is_ok(),
+        "blobs are written to the object database"
+    );
+
+    index.write(repo.index_write_options(&index)?)?;
+    let mut index = repo.open_index()?;
+    let outcome = repo.add_to_index(&mut index, None::<&str>, add::Options::default(), &should_interrupt)?;
+    assert!(
+        outcome.added.is_empty() && outcome.modified.is_empty() && outcome.removed.is_empty(),
+        "there is nothing left to add"
+    );
+    Ok(())
+}
+
+#[test]
+fn add_ignored_files() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let mut index = repo.open_index()?;
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        ["file.ignored", "untracked"],
+        add::Options::default(),
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.added, ["untracked"]);
+    assert_eq!(
+        outcome.ignored,
+        ["file.ignored"],
+        "explicitly named ignored files are reported"
+    );
+    assert!(outcome.modified.is_empty(), "only matching tracked files are updated");
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        Some("file.ignored"),
+        add::Options {
+            force: true,
+            ..Default::default()
+        },
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.added, ["file.ignored"]);
+    assert!(outcome.ignored.is_empty());
+    Ok(())
+}
+
+#[test]
+fn add_update_only_changes_tracked_files() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let mut index = repo.open_index()?;
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        None::<&str>,
+        add::Options {
+            update: true,
+            ..Default::default()
+        },
+        &should_interrupt,
+    )?;
+    assert!(outcome.added.is_empty());
+    assert_eq!(outcome.modified, ["file"]);
+    assert_eq!(outcome.removed, ["removed"]);
+    assert_eq!(paths(&index), [".gitignore", "dir/a", "file", "unchanged"]);
+    Ok(())
+}
+
+#[test]
+fn add_intent_to_add() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let mut index = repo.open_index()?;
+    let file_id = index.entry_by_path("file".into()).expect("present").id;
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        None::<&str>,
+        add::Options {
+            intent_to_add: true,
+            ..Default::default()
+        },
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.added, ["dir/new", "untracked"]);
+    assert!(outcome.modified.is_empty() && outcome.removed.is_empty());
+    assert_eq!(
+        index.entry_by_path("file".into()).expect("present").id,
+        file_id,
+        "tracked files are left alone"
+    );
+    let entry = index.entry_by_path("untracked".into()).expect("present");
+    assert!(entry.flags.contains(Flags::INTENT_TO_ADD));
+    assert_eq!(entry.id, repo.object_hash().empty_blob());
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        Some("untracked"),
+        add::Options::default(),
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.modified, ["untracked"], "intended files are added for real");
+    let entry = index.entry_by_path("untracked".into()).expect("present");
+    assert!(!entry.flags.contains(Flags::INTENT_TO_ADD));
+    assert_eq!(entry.id, blob(&repo, "untracked\n")?);
+    Ok(())
+}
+
+#[test]
+fn add_resolves_conflicts() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let mut index = repo.open_index()?;
+    let entry = index.entry_by_path("unchanged".into()).expect("present").clone();
+    for stage in [Stage::Base, Stage::Ours, Stage::Theirs] {
+        index.upsert_entry(
+            entry.stat,
+            entry.id,
+            Flags::from_stage(stage),
+            entry.mode,
+            "unchanged".into(),
+        );
+    }
+    assert_eq!(index.entry_range("unchanged".into()).map(|r| r.len()), Some(4));
+
+    let outcome = repo.add_to_index(
+        &mut index,
+        Some("unchanged"),
+        add::Options::default(),
+        &should_interrupt,
+    )?;
+    assert_eq!(outcome.modified, ["unchanged"]);
+    assert_eq!(index.entry_range("unchanged".into()).map(|r| r.len()), Some(1));
+    assert_eq!(
+        index.entry_by_path("unchanged".into()).expect("present").stage(),
+        Stage::Unconflicted
+    );
+    Ok(())
+}
+
+#[test]
+fn remove() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_staging_repo.sh")?;
+    let workdir = repo.workdir().expect("non-bare").to_owned();
+    let mut index = repo.open_index()?;
+
+    let outcome = repo.remove_from_index(&mut index, None::<&str>, remove::Options::default())?;
+    assert!(outcome.removed.is_empty(), "nothing is removed without patterns");
+
+    let err = repo
+        .remove_from_index(&mut index, Some("file"), remove::Options::default())
+        .unwrap_err();
+    assert!(
+        matches!(&err, remove::Error::LocalModifications { paths } if paths == &["file"]),
+        "{err:?}"
+    );
+
+    let outcome = repo.remove_from_index(
+        &mut index,
+        Some("file"),
+        remove::Options {
+            cached: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.removed, ["file"]);
+    assert_eq!(outcome.files_removed, 0);
+    assert!(workdir.join("file").is_file(), "the file is kept");
+
+    let outcome = repo.remove_from_index(&mut index, ["dir", "removed"], remove::Options::default())?;
+    assert_eq!(outcome.removed, ["dir/a", "removed"]);
+    assert_eq!(outcome.files_removed, 1, "'removed' was already deleted");
+    assert!(!workdir.join("dir/a").exists());
+    assert!(workdir.join("dir/new").is_file(), "untracked files are kept");
+    assert_eq!(paths(&index), [".gitignore", "unchanged"]);
+
+    std::fs::write(workdir.join("unchanged"), "changed")?;
+    let outcome = repo.remove_from_index(
+        &mut index,
+        Some("unchanged"),
+        remove::Options {
+            force: true,
+            ..Default::default()
+        },
+    )?;
+    assert_eq!(outcome.files_removed, 1, "modified files can be removed by force");
+    assert!(!workdir.join("unchanged").exists());
+    Ok(())
+}
@@ -0,0 +1,253 @@
+use std::sync::atomic::AtomicBool;
+
+use gix::{
+    bstr::{BString, ByteSlice},
+    stash::{apply, create},
+};
+
+use crate::util::repo_rw;
+
+fn index_ids(repo: &gix::Repository) -> crate::Result<Vec<(BString, gix::ObjectId)>> {
+    let index = repo.open_index()?;
+    Ok(index
+        .entries()
+        .iter()
+        .map(|e| (e.path(&index).to_owned(), e.id))
+        .collect())
+}
+
+fn read(repo: &gix::Repository, rela_path: &str) -> crate::Result<Option<BString>> {
+    let path = repo.workdir().expect("non-bare").join(rela_path);
+    Ok(match std::fs::read(path) {
+        Ok(data) => Some(data.into()),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    })
+}
+
+#[test]
+fn save_and_pop_with_index() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    assert!(repo.stash_list()?.is_empty());
+    let index_before = index_ids(&repo)?;
+
+    let id = repo
+        .stash_save(create::Options::default(), &should_interrupt)?
+        .expect("there are changes");
+    let list = repo.stash_list()?;
+    assert_eq!(list.len(), 1);
+    assert_eq!(list[0].id, id);
+    assert!(
+        list[0].message.starts_with(b"WIP on main: ") && list[0].message.ends_with(b" init"),
+        "{}",
+        list[0].message
+    );
+    let stash = id.object()?.into_commit();
+    assert_eq!(stash.parent_ids().count(), 2, "untracked files weren't stashed");
+    assert_eq!(stash.message_raw()?, list[0].message);
+
+    assert_eq!(read(&repo, "file")?.expect("present"), "base\n");
+    assert_eq!(read(&repo, "staged")?.expect("present"), "staged\n");
+    assert_eq!(read(&repo, "removed")?.expect("restored"), "removed\n");
+    assert_eq!(read(&repo, "dir/added")?, None, "added files are removed");
+    assert_eq!(read(&repo, "untracked")?.expect("kept"), "untracked\n");
+    let head_index = repo.index_from_tree(&repo.head_tree_id()?)?;
+    assert_eq!(
+        index_ids(&repo)?,
+        head_index
+            .entries()
+            .iter()
+            .map(|e| (e.path(&head_index).to_owned(), e.id))
+            .collect::<Vec<_>>(),
+        "the index matches HEAD"
+    );
+    assert!(
+        repo.stash_create(create::Options::default())?.is_none(),
+        "only untracked files are left, which aren't stashed by default"
+    );
+
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), "local change")?;
+    let err = repo
+        .stash_pop(0, apply::Options { index: true }, &should_interrupt)
+        .unwrap_err();
+    assert!(
+        matches!(
+            &err,
+            gix::stash::pop::Error::Apply(gix::stash::apply::Error::WouldOverwriteLocalChanges { paths }) if paths == &["file"]
+        ),
+        "{err:?}"
+    );
+    assert_eq!(repo.stash_list()?.len(), 1, "nothing was dropped");
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), "base\n")?;
+
+    let outcome = repo.stash_pop(0, apply::Options { index: true }, &should_interrupt)?;
+    assert_eq!(outcome.id, id);
+    assert!(outcome.conflicts.is_empty());
+    assert!(repo.stash_list()?.is_empty(), "the stash was dropped");
+    assert!(repo.try_find_reference(gix::stash::REFERENCE)?.is_none());
+
+    assert_eq!(read(&repo, "file")?.expect("present"), "base\nmodified\n");
+    assert_eq!(read(&repo, "staged")?.expect("present"), "staged-change\n");
+    assert_eq!(read(&repo, "removed")?, None);
+    assert_eq!(read(&repo, "dir/added")?.expect("present"), "added\n");
+    assert_eq!(index_ids(&repo)?, index_before, "the index was restored as well");
+    Ok(())
+}
+
+#[test]
+fn apply_keeps_index_but_adds_new_files() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let id = repo
+        .stash_save(create::Options::default(), &should_interrupt)?
+        .expect("there are changes");
+    let head_index = index_ids(&repo)?;
+
+    let outcome = repo.stash_apply(0, apply::Options::default(), &should_interrupt)?;
+    assert!(outcome.conflicts.is_empty());
+    assert_eq!(repo.stash_list()?.len(), 1, "the stash is kept");
+    assert_eq!(read(&repo, "staged")?.expect("present"), "staged-change\n");
+    assert_eq!(read(&repo, "dir/added")?.expect("present"), "added\n");
+
+    let index = index_ids(&repo)?;
+    let mut expected = head_index.clone();
+    expected.push((
+        "dir/added".into(),
+        gix::objs::compute_hash(repo.object_hash(), gix::objs::Kind::Blob, b"added\n")?,
+    ));
+    expected.sort();
+    assert_eq!(index, expected, "only the new file was added to the index");
+
+    let err = repo
+        .stash_apply(1, apply::Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(matches!(err, gix::stash::apply::Error::NoSuchStash { index: 1 }));
+    assert_eq!(repo.stash_drop(0)?, id);
+    Ok(())
+}
+
+#[test]
+fn untracked_files_with_message() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let id = repo
+        .stash_save(
+            create::Options {
+                message: Some("custom".into()),
+                include_untracked: true,
+            },
+            &should_interrupt,
+        )?
+        .expect("there are changes");
+    assert_eq!(repo.stash_list()?[0].message, "On main: custom");
+    assert_eq!(id.object()?.into_commit().parent_ids().count(), 3);
+    assert_eq!(read(&repo, "untracked")?, None, "untracked files are removed");
+    assert!(
+        repo.stash_create(create::Options {
+            message: None,
+            include_untracked: true,
+        })?
+        .is_none(),
+        "there is nothing left to stash"
+    );
+
+    repo.stash_apply(0, apply::Options::default(), &should_interrupt)?;
+    assert_eq!(read(&repo, "untracked")?.expect("restored"), "untracked\n");
+    assert_eq!(read(&repo, "file")?.expect("present"), "base\nmodified\n");
+
+    let err = repo
+        .stash_apply(0, apply::Options::default(), &should_interrupt)
+        .unwrap_err();
+    assert!(
+        matches!(err, gix::stash::apply::Error::UntrackedFileExists { .. }),
+        "untracked files are never overwritten"
+    );
+    Ok(())
+}
+
+#[test]
+fn drop_updates_reference() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    let first = repo
+        .stash_save(create::Options::default(), &should_interrupt)?
+        .expect("there are changes")
+        .detach();
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), "second")?;
+    let second = repo
+        .stash_save(create::Options::default(), &should_interrupt)?
+        .expect("there are changes")
+        .detach();
+    assert_eq!(
+        repo.stash_list()?.iter().map(|e| e.id).collect::<Vec<_>>(),
+        [second, first],
+        "the most recent stash comes first"
+    );
+
+    assert_eq!(repo.stash_drop(0)?, second);
+    assert_eq!(repo.stash_list()?.iter().map(|e| e.id).collect::<Vec<_>>(), [first]);
+    assert_eq!(
+        repo.find_reference(gix::stash::REFERENCE)?.id(),
+        first,
+        "the reference points to the most recent remaining stash"
+    );
+
+    assert_eq!(repo.stash_drop(0)?, first);
+    assert!(repo.stash_list()?.is_empty());
+    assert!(repo.try_find_reference(gix::stash::REFERENCE)?.is_none());
+    assert!(matches!(
+        repo.stash_drop(0),
+        Err(gix::stash::drop::Error::NoSuchStash { index: 0 })
+    ));
+    assert!(matches!(
+        repo.stash_store(repo.head_id()?, "not a stash"),
+        Err(gix::stash::store::Error::NotAStash { .. })
+    ));
+    Ok(())
+}
+
+#[test]
+fn apply_with_conflicts() -> crate::Result {
+    let (repo, _tmp) = repo_rw("make_stash_repo.sh")?;
+    let should_interrupt = AtomicBool::default();
+    repo.stash_save(create::Options::default(), &should_interrupt)?
+        .expect("there are changes");
+
+    let head = repo.head_commit()?;
+    let mut editor = repo.edit_tree(head.tree_id()?)?;
+    editor.upsert(
+        "file",
+        gix::object::tree::EntryKind::Blob,
+        repo.write_blob("base\nupstream\n")?,
+    )?;
+    let tree = editor.write()?;
+    repo.commit("HEAD", "upstream change", tree, [head.id])?;
+    let mut index = repo.index_from_tree(&tree)?;
+    index.write(Default::default())?;
+    std::fs::write(repo.workdir().expect("non-bare").join("file"), "base\nupstream\n")?;
+
+    let outcome = repo.stash_pop(0, apply::Options::default(), &should_interrupt)?;
+    assert_eq!(outcome.conflicts.len(), 1);
+    assert_eq!(repo.stash_list()?.len(), 1, "stashes with conflicts aren't dropped");
+    let content = read(&repo, "file")?.expect("present");
+    assert!(
+        content.contains_str("<<<<<<< Updated upstream") && content.contains_str(">>>>>>> Stashed changes"),
+        "{content}"
+    );
+    let index = repo.open_index()?;
+    assert_eq!(
+        index
+            .entries()
+            .iter()
+            .filter(|e| e.path(&index) == "file")
+            .map(gix::index::Entry::stage_raw)
+            .collect::<Vec<_>>(),
+        [1, 2, 3]
+    );
+    assert!(matches!(
+        repo.stash_create(create::Options::default()),
+        Err(gix::stash::create::Error::UnmergedIndex)
+    ));
+    Ok(())
+}
@@ -350,6 +350,11 @@ fn run_assertions(main_repo: gix::Repository, should_be_bare: bool) {
             expected.prunable.is_none(),
             "in our case prunable repos have no worktree base"
         );
+        assert_eq!(
+            actual.prunable().map(|reason| reason.to_string().into()),
+            expected.prunable,
+            "the reason for being prunable is the same as the one given by git"
+        );
 
         assert_eq!(
             main_repo.worktree_proxy_by_id(actual.id()).expect("exists").git_dir(),
@@ -401,3 +406,348 @@ fn run_assertions(main_repo: gix::Repository, should_be_bare: bool) {
         );
     }
 }
+
+#[cfg(all(feature = "worktree-mutation", feature = "revision"))]
+mod management {
+    use std::sync::atomic::AtomicBool;
+
+    use gix::worktree::{add, proxy::Prunable, repair};
+
+    use crate::util::repo_rw;
+
+    #[test]
+    fn add_checks_out_a_new_branch_named_after_the_worktree() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let should_interrupt = AtomicBool::default();
+
+        let path = worktrees.path().join("wt");
+        let proxy = repo.worktree_add(&path, None, add::Options::default(), &should_interrupt)?;
+        assert_eq!(proxy.id(), "wt");
+        assert_eq!(proxy.base()?, gix_path::realpath(&path)?);
+        assert_eq!(proxy.prunable(), None);
+        assert!(!proxy.is_locked());
+
+        let wt_repo = proxy.into_repo()?;
+        assert_eq!(
+            wt_repo.head_name()?.expect("not detached").as_bstr(),
+            "refs/heads/wt",
+            "a new branch is created"
+        );
+        assert_eq!(wt_repo.head_id()?, repo.head_id()?, "…at the current HEAD");
+        assert_eq!(std::fs::read(path.join("this"))?, b"hello\n", "files are checked out");
+        assert_eq!(wt_repo.open_index()?.entries().len(), 1, "the index is written");
+        assert_eq!(wt_repo.main_repo()?, repo);
+        assert_eq!(repo.worktrees()?.len(), 1);
+
+        let err = repo
+            .worktree_add(
+                worktrees.path().join("other"),
+                Some("wt".into()),
+                add::Options::default(),
+                &should_interrupt,
+            )
+            .unwrap_err();
+        assert!(
+            matches!(&err, add::Error::BranchCheckedOut { name, .. } if name == "wt"),
+            "branches can only be checked out once: {err:?}"
+        );
+        assert!(
+            !worktrees.path().join("other").exists(),
+            "nothing is left behind on error"
+        );
+        assert_eq!(repo.worktrees()?.len(), 1);
+
+        let err = repo
+            .worktree_add(&path, None, add::Options::default(), &should_interrupt)
+            .unwrap_err();
+        assert!(matches!(err, add::Error::PathExists { .. }), "{err:?}");
+        Ok(())
+    }
+
+    #[test]
+    fn add_detached_or_with_new_branch() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let should_interrupt = AtomicBool::default();
+        let parent_id = repo.rev_parse_single("HEAD~1")?;
+
+        let proxy = repo.worktree_add(
+            worktrees.path().join("a").join("wt"),
+            Some("main".into()),
+            add::Options {
+                detach: true,
+                ..Default::default()
+            },
+            &should_interrupt,
+        )?;
+        let wt_repo = proxy.into_repo()?;
+        assert_eq!(wt_repo.head_name()?, None, "HEAD is detached");
+        assert_eq!(wt_repo.head_id()?, repo.head_id()?);
+
+        let proxy = repo.worktree_add(
+            worktrees.path().join("b").join("wt"),
+            Some("HEAD~1".into()),
+            add::Options {
+                new_branch: Some("feature".into()),
+                no_checkout: true,
+                lock_reason: Some("on a portable drive".into()),
+                ..Default::default()
+            },
+            &should_interrupt,
+        )?;
+        assert_eq!(proxy.id(), "wt1", "ids are unique");
+        assert_eq!(proxy.lock_reason().expect("locked"), "on a portable drive");
+        assert_eq!(repo.find_reference("feature")?.id(), parent_id);
+        let wt_repo = proxy.into_repo()?;
+        assert_eq!(wt_repo.head_name()?.expect("on branch").as_bstr(), "refs/heads/feature");
+        assert!(!worktrees.path().join("b/wt/this").exists(), "nothing was checked out");
+
+        let err = repo
+            .worktree_add(
+                worktrees.path().join("c"),
+                None,
+                add::Options {
+                    new_branch: Some("feature".into()),
+                    ..Default::default()
+                },
+                &should_interrupt,
+            )
+            .unwrap_err();
+        assert!(matches!(err, add::Error::BranchExists { .. }), "{err:?}");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn add_keeps_the_worktree_if_the_post_checkout_hook_fails() -> crate::Result {
+        let (repo, _tmp) = crate::util::repo_rw_opts("make_basic_repo.sh", crate::restricted().run_hooks(true))?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let hooks_dir = repo.git_dir().join("hooks");
+        std::fs::create_dir_all(&hooks_dir)?;
+        std::fs::write(hooks_dir.join("post-checkout"), "#!/bin/sh\nexit 1\n")?;
+        std::fs::set_permissions(
+            hooks_dir.join("post-checkout"),
+            std::os::unix::fs::PermissionsExt::from_mode(0o755),
+        )?;
+
+        let path = worktrees.path().join("wt");
+        let err = repo
+            .worktree_add(&path, None, add::Options::default(), &AtomicBool::default())
+            .unwrap_err();
+        assert!(matches!(err, add::Error::PostCheckoutHook { .. }), "{err:?}");
+        assert!(path.join("this").is_file(), "like `git`, the worktree is kept…");
+        assert_eq!(repo.worktrees()?.len(), 1);
+        assert!(repo.try_find_reference("wt")?.is_some(), "…along with its branch");
+        Ok(())
+    }
+
+    #[test]
+    fn add_removes_or_resets_the_branch_it_changed_on_error() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let should_interrupt = AtomicBool::default();
+        let actor = gix::actor::Signature {
+            name: "name".into(),
+            email: "name@example.com".into(),
+            time: Default::default(),
+        };
+        let commit_without_tree = repo
+            .write_object(gix::objs::Commit {
+                tree: gix::objs::compute_hash(repo.object_hash(), gix::objs::Kind::Tree, b"missing")?,
+                author: actor.clone(),
+                committer: actor,
+                parents: Default::default(),
+                encoding: None,
+                message: "tree is missing".into(),
+                extra_headers: vec![],
+            })?
+            .detach()
+            .to_string();
+
+        let path = worktrees.path().join("wt");
+        repo.worktree_add(
+            &path,
+            Some(commit_without_tree.as_str().into()),
+            add::Options {
+                new_branch: Some("new".into()),
+                ..Default::default()
+            },
+            &should_interrupt,
+        )
+        .unwrap_err();
+        assert!(!path.exists(), "nothing is left behind on error…");
+        assert_eq!(repo.worktrees()?.len(), 0);
+        assert!(
+            repo.try_find_reference("new")?.is_none(),
+            "…including the branch that was created for the worktree"
+        );
+
+        let head = repo.head_id()?.detach();
+        repo.reference(
+            "refs/heads/existing",
+            head,
+            gix::refs::transaction::PreviousValue::MustNotExist,
+            "",
+        )?;
+        repo.worktree_add(
+            worktrees.path().join("existing"),
+            Some(commit_without_tree.as_str().into()),
+            add::Options {
+                new_branch: Some("existing".into()),
+                force: true,
+                ..Default::default()
+            },
+            &should_interrupt,
+        )
+        .unwrap_err();
+        assert_eq!(
+            repo.find_reference("existing")?.id(),
+            head,
+            "branches that existed before are reset to where they were"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lock_move_and_prune() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let proxy = repo.worktree_add(
+            worktrees.path().join("wt"),
+            None,
+            add::Options::default(),
+            &AtomicBool::default(),
+        )?;
+
+        proxy.lock("reason".into())?;
+        assert_eq!(proxy.lock_reason().expect("locked"), "reason");
+        assert!(matches!(
+            proxy.lock("".into()),
+            Err(gix::worktree::proxy::lock::Error::AlreadyLocked { .. })
+        ));
+        assert!(matches!(
+            proxy.move_to(worktrees.path().join("moved"), false),
+            Err(gix::worktree::proxy::move_to::Error::Locked { .. })
+        ));
+        proxy.unlock()?;
+        assert!(!proxy.is_locked());
+
+        let new_location = proxy.move_to(worktrees.path().join("moved"), false)?;
+        assert_eq!(new_location, gix_path::realpath(worktrees.path().join("moved"))?);
+        assert_eq!(proxy.base()?, new_location, "the gitdir file is updated");
+        assert!(!worktrees.path().join("wt").exists());
+        assert_eq!(
+            gix::open(&new_location)?.head_name()?.expect("on branch").as_bstr(),
+            "refs/heads/wt",
+            "the worktree can be opened at its new location"
+        );
+
+        let outcome = repo.worktrees_prune(Default::default())?;
+        assert!(outcome.is_empty(), "nothing to prune");
+
+        std::fs::remove_dir_all(&new_location)?;
+        assert_eq!(proxy.prunable(), Some(Prunable::GitDirFilePointsToNonExistentLocation));
+        let outcome = repo.worktrees_prune(gix::worktree::prune::Options {
+            dry_run: true,
+            ..Default::default()
+        })?;
+        assert_eq!(outcome.len(), 1);
+        assert_eq!(outcome[0].id, "wt");
+        assert_eq!(outcome[0].reason, Prunable::GitDirFilePointsToNonExistentLocation);
+        assert_eq!(repo.worktrees()?.len(), 1, "dry-runs don't change anything");
+
+        let outcome = repo.worktrees_prune(gix::worktree::prune::Options {
+            expire: Some(std::time::SystemTime::UNIX_EPOCH),
+            ..Default::default()
+        })?;
+        assert!(outcome.is_empty(), "the worktree isn't old enough to be pruned");
+
+        let outcome = repo.worktrees_prune(Default::default())?;
+        assert_eq!(outcome.len(), 1);
+        assert!(repo.worktrees()?.is_empty());
+        assert!(!repo.common_dir().join("worktrees").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn repair_after_manual_move() -> crate::Result {
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let proxy = repo.worktree_add(
+            worktrees.path().join("wt"),
+            None,
+            add::Options::default(),
+            &AtomicBool::default(),
+        )?;
+        assert!(repo.worktrees_repair(None::<&str>)?.is_empty(), "nothing to repair");
+
+        let moved = worktrees.path().join("moved");
+        std::fs::rename(worktrees.path().join("wt"), &moved)?;
+        assert!(proxy.prunable().is_some(), "the worktree seems to be gone");
+
+        let outcome = repo.worktrees_repair(Some(&moved))?;
+        let moved = gix_path::realpath(&moved)?;
+        assert_eq!(
+            outcome,
+            [repair::Repaired {
+                path: moved.clone(),
+                kind: repair::Kind::GitDirFile,
+            }]
+        );
+        assert_eq!(proxy.base()?, moved);
+        assert_eq!(proxy.prunable(), None);
+
+        std::fs::write(moved.join(".git"), "gitdir: /does/not/exist/worktrees/wt\n")?;
+        let outcome = repo.worktrees_repair(None::<&str>)?;
+        assert_eq!(
+            outcome,
+            [repair::Repaired {
+                path: moved.clone(),
+                kind: repair::Kind::DotGitFile,
+            }]
+        );
+        assert!(gix::open(&moved).is_ok(), "the worktree can be opened again");
+
+        let err = repo
+            .worktrees_repair(Some(repo.workdir().expect("non-bare")))
+            .unwrap_err();
+        assert!(matches!(err, repair::Error::NotALinkedWorktree { .. }), "{err:?}");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "status")]
+    fn remove() -> crate::Result {
+        use gix::worktree::proxy::remove;
+
+        let (repo, _tmp) = repo_rw("make_basic_repo.sh")?;
+        let worktrees = gix_testtools::tempfile::TempDir::new()?;
+        let path = worktrees.path().join("wt");
+        let proxy = repo.worktree_add(&path, None, add::Options::default(), &AtomicBool::default())?;
+        let git_dir = proxy.git_dir().to_owned();
+
+        std::fs::write(path.join("untracked"), "")?;
+        let err = proxy.clone().remove(remove::Options::default()).unwrap_err();
+        assert!(matches!(err, remove::Error::Modified { .. }), "{err:?}");
+
+        proxy.lock("".into())?;
+        let err = proxy
+            .clone()
+            .remove(remove::Options {
+                force: true,
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert!(matches!(err, remove::Error::Locked { .. }), "{err:?}");
+
+        proxy.remove(remove::Options {
+            force: true,
+            force_locked: true,
+        })?;
+        assert!(!path.exists(), "the checkout is removed");
+        assert!(!git_dir.exists(), "the administrative directory is removed");
+        assert!(repo.worktrees()?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,221 @@
+use gix::{
+    bstr::ByteSlice,
+    revision::walk::{Platform, Simplification, Sorting},
+    ObjectId,
+};
+use gix_worktree::stack::state::attributes::Source;
+
+use crate::util::named_repo;
+
+fn baseline(repo: &gix::Repository, name: &str) -> crate::Result<Vec<ObjectId>> {
+    let ids = std::fs::read(repo.git_dir().join(format!("baseline-{name}")))?;
+    Ok(ids.lines().map(ObjectId::from_hex).collect::<Result<_, _>>()?)
+}
+
+fn id(repo: &gix::Repository, spec: &str) -> crate::Result<ObjectId> {
+    Ok(repo.rev_parse_single(spec)?.detach())
+}
+
+fn pathspec<'repo>(repo: &'repo gix::Repository, patterns: &[&str]) -> crate::Result<gix::Pathspec<'repo>> {
+    Ok(repo.pathspec(
+        true,
+        patterns,
+        false,
+        &gix::index::State::new(repo.object_hash()),
+        Source::IdMapping,
+    )?)
+}
+
+fn ids(walk: Platform<'_>) -> crate::Result<Vec<ObjectId>> {
+    Ok(walk
+        .sorting(Sorting::ByCommitTime(Default::default()))
+        .all()?
+        .map(|info| info.map(|info| info.id))
+        .collect::<Result<_, _>>()?)
+}
+
+/// Return a walk from `main` for each way of accessing commits.
+fn walks(repo: &gix::Repository) -> crate::Result<Vec<Platform<'_>>> {
+    let main = id(repo, "main")?;
+    Ok([false, true]
+        .into_iter()
+        .map(|use_commit_graph| repo.rev_walk(Some(main)).use_commit_graph(use_commit_graph))
+        .collect())
+}
+
+#[test]
+fn simplifications_match_git() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    for (simplification, expected) in [
+        (Simplification::Default, "default"),
+        (Simplification::FullHistory, "full-history"),
+        (Simplification::SimplifyMerges, "simplify-merges"),
+    ] {
+        for walk in walks(&repo)? {
+            assert_eq!(
+                ids(walk
+                    .with_pathspec(pathspec(&repo, &["foo"])?)
+                    .simplification(simplification))?,
+                baseline(&repo, expected)?,
+                "{simplification:?}"
+            );
+        }
+    }
+    assert_eq!(
+        baseline(&repo, "simplify-merges")?,
+        baseline(&repo, "full-simplify-merges")?,
+        "git doesn't distinguish these"
+    );
+    Ok(())
+}
+
+#[test]
+fn default_simplification_streams_commits() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    let main = id(&repo, "main")?;
+    let num_commits = ids(repo.rev_walk(Some(main)))?.len();
+    for (simplification, streams) in [(Simplification::Default, true), (Simplification::FullHistory, false)] {
+        let seen = std::rc::Rc::new(std::cell::Cell::new(0));
+        let mut walk = repo
+            .rev_walk(Some(main))
+            .with_pathspec(pathspec(&repo, &["dir"])?)
+            .simplification(simplification)
+            .selected({
+                let seen = seen.clone();
+                move |_| {
+                    seen.set(seen.get() + 1);
+                    true
+                }
+            })?;
+        assert!(walk.next().transpose()?.is_some());
+        assert_eq!(
+            seen.get() < num_commits,
+            streams,
+            "{simplification:?}: the first commit is returned before all commits are traversed"
+        );
+    }
+
+    let mut actual = repo
+        .rev_walk(Some(main))
+        .sorting(Sorting::BreadthFirst)
+        .with_pathspec(pathspec(&repo, &["foo"])?)
+        .all()?
+        .map(|info| info.map(|info| info.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut expected = baseline(&repo, "default")?;
+    actual.sort();
+    expected.sort();
+    assert_eq!(
+        actual, expected,
+        "commits seen before the child that follows them are still returned"
+    );
+    Ok(())
+}
+
+#[test]
+fn first_parent_and_hidden_tips_match_git() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk.first_parent_only().with_pathspec(pathspec(&repo, &["foo"])?))?,
+            baseline(&repo, "first-parent")?
+        );
+    }
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk
+                .with_hidden(Some(id(&repo, "B")?))
+                .with_pathspec(pathspec(&repo, &["foo"])?))?,
+            baseline(&repo, "hidden")?,
+            "a commit that is TREESAME to a hidden parent isn't simplified away"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn ancestry_path_matches_git() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    let hidden = id(&repo, "D")?;
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk.with_hidden(Some(hidden)).ancestry_path(true))?,
+            baseline(&repo, "ancestry-path")?
+        );
+    }
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk
+                .with_hidden(Some(hidden))
+                .ancestry_path(true)
+                .with_pathspec(pathspec(&repo, &["foo"])?))?,
+            baseline(&repo, "ancestry-path-foo")?
+        );
+    }
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk
+                .with_hidden(Some(hidden))
+                .ancestry_path(true)
+                .simplification(Simplification::SimplifyMerges)
+                .with_pathspec(pathspec(&repo, &["foo"])?))?,
+            baseline(&repo, "ancestry-path-simplify-merges")?
+        );
+    }
+
+    let err = repo.rev_walk(Some(hidden)).ancestry_path(true).all().err();
+    assert!(
+        matches!(err, Some(gix::revision::walk::Error::AncestryPathWithoutHiddenTips)),
+        "{err:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn pathspecs_match_git() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    for (patterns, expected) in [
+        (&["dir"][..], "dir"),
+        (&["dir/"], "dir-slash"),
+        (&["dir/*.txt"], "glob"),
+        (&["dir", ":!dir/sub"], "exclude"),
+        (&["bar", "other"], "multiple"),
+        (&["side"], "side"),
+    ] {
+        for walk in walks(&repo)? {
+            assert_eq!(
+                ids(walk.with_pathspec(pathspec(&repo, patterns)?))?,
+                baseline(&repo, expected)?,
+                "{patterns:?}"
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn follow_matches_git() -> crate::Result {
+    let repo = named_repo("make_path_limited_history.sh")?;
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk.follow("dir/renamed"))?,
+            baseline(&repo, "follow")?,
+            "the path changes to `dir/a` at the rename"
+        );
+    }
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk.follow("foo"))?,
+            baseline(&repo, "follow-foo")?,
+            "merges are skipped"
+        );
+    }
+    for walk in walks(&repo)? {
+        assert_eq!(
+            ids(walk.first_parent_only().follow("foo"))?,
+            baseline(&repo, "follow-first-parent")?,
+            "merges are compared to their first parent"
+        );
+    }
+    Ok(())
+}
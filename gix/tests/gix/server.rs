@@ -0,0 +1,404 @@
+#[cfg(feature = "blocking-network-client")]
+mod upload_pack {
+    use std::{io::Read, net::TcpListener, sync::atomic::AtomicBool};
+
+    use gix::{bstr::ByteSlice, protocol::transport::Protocol};
+
+    use crate::{remote, util::restricted};
+
+    /// Accept a single `git://` connection and serve `repo` to it, with the protocol version requested by the client.
+    fn serve_once(
+        repo: gix::ThreadSafeRepository,
+    ) -> crate::Result<(
+        u16,
+        std::thread::JoinHandle<crate::Result<gix::server::upload_pack::Outcome>>,
+    )> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let handle = std::thread::spawn(move || -> crate::Result<_> {
+            let (mut stream, _) = listener.accept()?;
+            let mut len = [0; 4];
+            stream.read_exact(&mut len)?;
+            let mut request = vec![0; usize::from_str_radix(std::str::from_utf8(&len)?, 16)? - 4];
+            stream.read_exact(&mut request)?;
+            assert!(request.starts_with(b"git-upload-pack "));
+            let version = if request.contains_str("\0version=2\0") {
+                Protocol::V2
+            } else {
+                Protocol::V0
+            };
+            Ok(repo.to_thread_local().serve_upload_pack(
+                &stream,
+                &stream,
+                gix::server::upload_pack::Options {
+                    version,
+                    ..Default::default()
+                },
+                &AtomicBool::default(),
+            )?)
+        });
+        Ok((port, handle))
+    }
+
+    fn clone_from_server(name: &str, version: &str) -> crate::Result {
+        let source = remote::repo(name);
+        let (port, server) = serve_once(source.clone().into_sync())?;
+
+        let tmp = gix_testtools::tempfile::TempDir::new()?;
+        let (repo, _outcome) = gix::clone::PrepareFetch::new(
+            format!("git://127.0.0.1:{port}/{name}").as_str(),
+            tmp.path(),
+            gix::create::Kind::Bare,
+            Default::default(),
+            restricted().config_overrides([format!("protocol.version={version}")]),
+        )?
+        .fetch_only(gix::progress::Discard, &AtomicBool::default())?;
+        let outcome = server.join().expect("no panic")?;
+        assert_eq!(outcome.packs.len(), 1, "a single pack was sent");
+        assert!(
+            outcome.packs[0].common.is_empty(),
+            "nothing in common with an empty repository"
+        );
+
+        assert_eq!(repo.head_id()?, source.head_id()?);
+        for reference in source.references()?.local_branches()? {
+            let mut reference = reference?;
+            let name = reference.name().shorten().to_str_lossy().into_owned();
+            let id = reference.peel_to_id()?.detach();
+            assert_eq!(
+                repo.find_reference(format!("refs/remotes/origin/{name}").as_str())?
+                    .id(),
+                id,
+                "all branches are present"
+            );
+            let count = |repo: &gix::Repository| -> crate::Result<usize> { Ok(repo.rev_walk(Some(id)).all()?.count()) };
+            assert_eq!(count(&repo)?, count(&source)?, "all commits were received");
+            for commit in repo.rev_walk(Some(id)).all()? {
+                let mut recorder = gix::traverse::tree::Recorder::default();
+                repo.find_object(commit?.id)?
+                    .peel_to_tree()?
+                    .traverse()
+                    .breadthfirst(&mut recorder)?;
+                for entry in recorder.records {
+                    assert!(
+                        repo.has_object(entry.oid),
+                        "trees and blobs of all commits were received"
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn clone_v2() -> crate::Result {
+        clone_from_server("base", "2")
+    }
+
+    #[test]
+    fn clone_v1() -> crate::Result {
+        clone_from_server("base", "1")
+    }
+
+    #[test]
+    fn clone_with_objects_counted_by_bitmap() -> crate::Result {
+        let pack_dir = remote::repo_path("base-with-bitmap").join("objects/pack");
+        assert!(
+            std::fs::read_dir(pack_dir)?
+                .any(|e| e.is_ok_and(|e| e.path().extension().is_some_and(|ext| ext == "bitmap"))),
+            "git wrote a bitmap"
+        );
+        clone_from_server("base-with-bitmap", "2")
+    }
+}
+
+#[cfg(feature = "blocking-network-client")]
+mod receive_pack {
+    use std::{io::Read, net::TcpListener, path::Path, sync::atomic::AtomicBool};
+
+    use gix::{
+        bstr::ByteSlice,
+        remote::{push::Status, Direction},
+    };
+
+    use crate::util::restricted;
+
+    /// Accept a single `git://` connection and let it push into the repository at `path`.
+    fn serve_once(
+        path: &Path,
+        hooks: bool,
+    ) -> crate::Result<(
+        u16,
+        std::thread::JoinHandle<crate::Result<gix::server::receive_pack::Outcome>>,
+    )> {
+        let repo = gix::open_opts(path, restricted())?.into_sync();
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let port = listener.local_addr()?.port();
+        let handle = std::thread::spawn(move || -> crate::Result<_> {
+            let (mut stream, _) = listener.accept()?;
+            let mut len = [0; 4];
+            stream.read_exact(&mut len)?;
+            let mut request = vec![0; usize::from_str_radix(std::str::from_utf8(&len)?, 16)? - 4];
+            stream.read_exact(&mut request)?;
+            assert!(request.starts_with(b"git-receive-pack "));
+            Ok(repo.to_thread_local().serve_receive_pack(
+                &stream,
+                &stream,
+                gix::server::receive_pack::Options {
+                    hooks,
+                    ..Default::default()
+                },
+                &AtomicBool::default(),
+            )?)
+        });
+        Ok((port, handle))
+    }
+
+    fn repos() -> crate::Result<(gix::Repository, std::path::PathBuf, gix_testtools::tempfile::TempDir)> {
+        let dir = gix_testtools::scripted_fixture_writable("make_push_repos.sh")?;
+        let local = gix::open_opts(dir.path().join("local"), restricted())?;
+        let remote = dir.path().join("remote.git");
+        Ok((local, remote, dir))
+    }
+
+    fn push(
+        repo: &gix::Repository,
+        remote_path: &Path,
+        hooks: bool,
+        specs: &[&str],
+    ) -> crate::Result<(gix::remote::push::Outcome, gix::server::receive_pack::Outcome)> {
+        let (port, server) = serve_once(remote_path, hooks)?;
+        let outcome = repo
+            .remote_at(format!("git://127.0.0.1:{port}/remote.git").as_str())?
+            .with_refspecs(specs.iter().copied(), Direction::Push)?
+            .connect(Direction::Push)?
+            .prepare_push(gix::progress::Discard, Default::default())?
+            .transmit(gix::progress::Discard, &AtomicBool::default())?;
+        let served = server.join().expect("no panic")?;
+        Ok((outcome, served))
+    }
+
+    fn remote_ref(remote_path: &Path, name: &str) -> crate::Result<Option<gix::ObjectId>> {
+        let remote = gix::open_opts(remote_path, restricted())?;
+        Ok(remote.try_find_reference(name)?.map(|r| r.id().detach()))
+    }
+
+    fn quarantine_dirs(remote_path: &Path) -> crate::Result<usize> {
+        Ok(std::fs::read_dir(remote_path.join("objects"))?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().starts_with("incoming-"))
+            .count())
+    }
+
+    #[test]
+    fn fast_forward_new_branch_and_annotated_tag() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let (outcome, served) = push(
+            &repo,
+            &remote_path,
+            true,
+            &["refs/heads/main", "diverged:new", "refs/tags/v1"],
+        )?;
+        assert!(outcome.updates.iter().all(|(_, status)| *status == Status::Ok));
+        assert_eq!(served.commands.len(), 3);
+        assert!(served.report.expect("commands were sent").is_ok());
+
+        for (local, remote) in [
+            ("refs/heads/main", "refs/heads/main"),
+            ("refs/heads/diverged", "refs/heads/new"),
+            ("refs/tags/v1", "refs/tags/v1"),
+        ] {
+            assert_eq!(
+                remote_ref(&remote_path, remote)?,
+                Some(repo.find_reference(local)?.id().detach())
+            );
+        }
+        let remote = gix::open_opts(&remote_path, restricted())?;
+        let tag = remote.find_reference("refs/tags/v1")?.id();
+        assert_eq!(
+            tag.object()?.peel_tags_to_end()?.id,
+            repo.find_reference("diverged")?.id(),
+            "the tag and its target were received"
+        );
+        assert_eq!(quarantine_dirs(&remote_path)?, 0, "the quarantine is removed");
+        Ok(())
+    }
+
+    #[test]
+    fn deny_configuration_rejects_deletions_and_non_fast_forwards() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        let mut config = std::fs::read(remote_path.join("config"))?;
+        config.extend_from_slice(b"[receive]\n\tdenyDeletes = true\n\tdenyNonFastForwards = true\n");
+        std::fs::write(remote_path.join("config"), config)?;
+
+        let other = remote_ref(&remote_path, "refs/heads/other")?;
+        let main = remote_ref(&remote_path, "refs/heads/main")?;
+        let (outcome, served) = push(&repo, &remote_path, true, &[":refs/heads/other", "+diverged:main"])?;
+        let status_of = |name: &str| {
+            outcome
+                .updates
+                .iter()
+                .find(|(update, _)| update.remote.as_bstr() == name)
+                .map(|(_, status)| status.clone())
+        };
+        assert_eq!(
+            status_of("refs/heads/other"),
+            Some(Status::RejectedByRemote {
+                reason: "deletion prohibited".into()
+            })
+        );
+        assert_eq!(
+            status_of("refs/heads/main"),
+            Some(Status::RejectedByRemote {
+                reason: "non-fast-forward".into()
+            })
+        );
+        assert!(!served.report.expect("commands were sent").is_ok());
+        assert_eq!(remote_ref(&remote_path, "refs/heads/other")?, other);
+        assert_eq!(remote_ref(&remote_path, "refs/heads/main")?, main);
+        Ok(())
+    }
+
+    #[test]
+    fn commands_sharing_a_missing_object_are_all_rejected() -> crate::Result {
+        let (_local, remote_path, _tmp) = repos()?;
+        let remote = gix::open_opts(&remote_path, restricted())?;
+        let missing = gix::objs::compute_hash(remote.object_hash(), gix::objs::Kind::Blob, b"never written")?;
+        let tree = remote.write_object(gix::objs::Tree {
+            entries: vec![gix::objs::tree::Entry {
+                mode: gix::objs::tree::EntryKind::Blob.into(),
+                filename: "file".into(),
+                oid: missing,
+            }],
+        })?;
+        let actor = gix::actor::Signature {
+            name: "name".into(),
+            email: "name@example.com".into(),
+            time: Default::default(),
+        };
+        let mut commits = Vec::new();
+        for message in ["first", "second"] {
+            commits.push(
+                remote
+                    .write_object(gix::objs::Commit {
+                        tree: tree.detach(),
+                        author: actor.clone(),
+                        committer: actor.clone(),
+                        parents: Default::default(),
+                        encoding: None,
+                        message: message.into(),
+                        extra_headers: vec![],
+                    })?
+                    .detach(),
+            );
+        }
+
+        let null = remote.object_hash().null();
+        let mut request = Vec::new();
+        for line in [
+            format!("{null} {} refs/heads/first\0report-status", commits[0]),
+            format!("{null} {} refs/heads/second", commits[1]),
+        ] {
+            request.extend_from_slice(format!("{:04x}{line}", line.len() + 4).as_bytes());
+        }
+        request.extend_from_slice(b"0000");
+        let mut pack = b"PACK\0\0\0\x02\0\0\0\0".to_vec();
+        let mut hasher = gix::hash::hasher(remote.object_hash());
+        hasher.update(&pack);
+        pack.extend_from_slice(hasher.try_finalize()?.as_slice());
+        request.extend_from_slice(&pack);
+
+        let outcome = remote.serve_receive_pack(
+            request.as_slice(),
+            Vec::new(),
+            gix::server::receive_pack::Options {
+                hooks: false,
+                ..Default::default()
+            },
+            &AtomicBool::default(),
+        )?;
+        let report = outcome.report.expect("commands were sent");
+        assert_eq!(report.statuses.len(), 2);
+        assert!(
+            report.statuses.iter().all(|status| matches!(
+                status,
+                gix::protocol::push::response::Status::Rejected { reason, .. } if reason == "missing necessary objects"
+            )),
+            "an object that was found missing for one command is still missing for the next: {:?}",
+            report.statuses
+        );
+        for name in ["refs/heads/first", "refs/heads/second"] {
+            assert_eq!(remote_ref(&remote_path, name)?, None);
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn write_hook(remote_path: &Path, name: &str, script: &str) -> crate::Result {
+        use std::os::unix::fs::PermissionsExt;
+        let path = remote_path.join("hooks").join(name);
+        std::fs::create_dir_all(path.parent().expect("parent"))?;
+        std::fs::write(&path, format!("#!/bin/sh\n{script}\n"))?;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn declining_pre_receive_hook_discards_the_quarantine() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        write_hook(
+            &remote_path,
+            "pre-receive",
+            r#"test -n "$GIT_QUARANTINE_PATH" || exit 2
+read old new name
+git cat-file -e "$new" || exit 3
+echo "declined $name"
+exit 1"#,
+        )?;
+        let packs_before = std::fs::read_dir(remote_path.join("objects").join("pack"))?.count();
+
+        let (outcome, _) = push(&repo, &remote_path, true, &["refs/heads/main"])?;
+        assert_eq!(
+            outcome.updates[0].1,
+            Status::RejectedByRemote {
+                reason: "pre-receive hook declined".into()
+            }
+        );
+        assert_eq!(
+            std::fs::read_dir(remote_path.join("objects").join("pack"))?.count(),
+            packs_before,
+            "no objects were added"
+        );
+        assert_eq!(quarantine_dirs(&remote_path)?, 0);
+
+        let (outcome, _) = push(&repo, &remote_path, false, &["refs/heads/main"])?;
+        assert_eq!(outcome.updates[0].1, Status::Ok, "hooks can be disabled");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn update_hook_declines_single_references_and_post_receive_sees_updates() -> crate::Result {
+        let (repo, remote_path, _tmp) = repos()?;
+        write_hook(&remote_path, "update", r#"test "$1" != refs/heads/new"#)?;
+        write_hook(&remote_path, "post-receive", "cat > post-receive.out")?;
+
+        let (outcome, _) = push(&repo, &remote_path, true, &["refs/heads/main", "diverged:new"])?;
+        assert_eq!(outcome.updates[0].1, Status::Ok);
+        assert_eq!(
+            outcome.updates[1].1,
+            Status::RejectedByRemote {
+                reason: "hook declined".into()
+            }
+        );
+        let main = repo.find_reference("refs/heads/main")?.id().detach();
+        assert_eq!(remote_ref(&remote_path, "refs/heads/main")?, Some(main));
+        assert_eq!(remote_ref(&remote_path, "refs/heads/new")?, None);
+
+        let post_receive = std::fs::read(remote_path.join("post-receive.out"))?;
+        assert_eq!(post_receive.lines().count(), 1, "only updated references are passed");
+        assert!(post_receive.ends_with(format!("{main} refs/heads/main\n").as_bytes()));
+        Ok(())
+    }
+}
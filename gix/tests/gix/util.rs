@@ -1,5 +1,5 @@
 #![allow(clippy::result_large_err)]
-use gix::{open, Repository, ThreadSafeRepository};
+use gix::{bstr::BString, open, ObjectId, Repository, ThreadSafeRepository};
 use gix_testtools::tempfile;
 pub use gix_testtools::Result;
 
@@ -75,6 +75,12 @@ pub fn repo_rw_opts(name: &str, opts: gix::open::Options) -> Result<(Repository,
     ))
 }
 
+pub fn named_subrepo_rw(fixture: &str, name: &str) -> Result<(Repository, tempfile::TempDir)> {
+    let tmp = gix_testtools::scripted_fixture_writable(fixture)?;
+    let repo = gix::open_opts(tmp.path().join(name), restricted())?;
+    Ok((repo, tmp))
+}
+
 pub fn basic_repo() -> Result<Repository> {
     repo("make_basic_repo.sh").map(|r| r.to_thread_local())
 }
@@ -82,3 +88,41 @@ pub fn basic_repo() -> Result<Repository> {
 pub fn basic_rw_repo() -> Result<(Repository, tempfile::TempDir)> {
     repo_rw("make_basic_repo.sh")
 }
+
+pub fn id_of(repo: &Repository, spec: &str) -> Result<ObjectId> {
+    Ok(repo.rev_parse_single(spec)?.detach())
+}
+
+/// Read the file at `rela_path` from the worktree of `repo`.
+pub fn read(repo: &Repository, rela_path: &str) -> Result<BString> {
+    Ok(std::fs::read(repo.workdir().expect("non-bare").join(rela_path))?.into())
+}
+
+/// Return the message and tree of the last `count` commits reachable from `spec`, following the first parent.
+pub fn history(repo: &Repository, spec: &str, count: usize) -> Result<Vec<(BString, ObjectId)>> {
+    let mut id = id_of(repo, spec)?;
+    let mut out = Vec::new();
+    for _ in 0..count {
+        let commit = repo.find_commit(id)?;
+        out.push((commit.message_raw()?.to_owned(), commit.tree_id()?.detach()));
+        id = commit.parent_ids().next().expect("enough parents").detach();
+    }
+    Ok(out)
+}
+
+/// Return the messages of the last `count` entries of the `HEAD` reflog, newest first.
+pub fn head_reflog_messages(repo: &Repository, count: usize) -> Result<Vec<BString>> {
+    let head = repo.find_reference("HEAD")?;
+    let mut platform = head.log_iter();
+    let messages = platform
+        .rev()?
+        .expect("reflog exists")
+        .take(count)
+        .map(|line| line.map(|line| line.message))
+        .collect::<std::result::Result<_, _>>()?;
+    Ok(messages)
+}
+
+pub fn head_reflog_message(repo: &Repository) -> Result<BString> {
+    Ok(head_reflog_messages(repo, 1)?.pop().expect("one line"))
+}
@@ -322,6 +322,108 @@ pub fn main() -> Result<()> {
                 None,
                 move |_progress, out, _err| core::repository::worktree::list(repository(Mode::Lenient)?, out, format),
             ),
+            crate::plumbing::options::worktree::SubCommands::Add {
+                branch,
+                detach,
+                force,
+                lock,
+                reason,
+                no_checkout,
+                path,
+                commitish,
+            } => prepare_and_run(
+                "worktree-add",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, out, _err| {
+                    core::repository::worktree::add(
+                        repository(Mode::Strict)?,
+                        out,
+                        &path,
+                        commitish.as_ref().map(AsRef::as_ref),
+                        &should_interrupt,
+                        core::repository::worktree::add::Options {
+                            branch,
+                            detach,
+                            force,
+                            lock,
+                            reason,
+                            no_checkout,
+                        },
+                    )
+                },
+            ),
+            crate::plumbing::options::worktree::SubCommands::Lock { reason, worktree } => prepare_and_run(
+                "worktree-lock",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, _out, _err| {
+                    core::repository::worktree::lock(
+                        repository(Mode::Lenient)?,
+                        &worktree,
+                        reason.as_ref().map(AsRef::as_ref),
+                    )
+                },
+            ),
+            crate::plumbing::options::worktree::SubCommands::Unlock { worktree } => prepare_and_run(
+                "worktree-unlock",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, _out, _err| core::repository::worktree::unlock(repository(Mode::Lenient)?, &worktree),
+            ),
+            crate::plumbing::options::worktree::SubCommands::Move {
+                force,
+                worktree,
+                new_path,
+            } => prepare_and_run(
+                "worktree-move",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, _out, _err| {
+                    core::repository::worktree::move_to(repository(Mode::Lenient)?, &worktree, &new_path, force)
+                },
+            ),
+            crate::plumbing::options::worktree::SubCommands::Remove { force, worktree } => prepare_and_run(
+                "worktree-remove",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, _out, _err| {
+                    core::repository::worktree::remove(repository(Mode::Lenient)?, &worktree, force)
+                },
+            ),
+            crate::plumbing::options::worktree::SubCommands::Prune { dry_run } => prepare_and_run(
+                "worktree-prune",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, out, _err| core::repository::worktree::prune(repository(Mode::Lenient)?, out, dry_run),
+            ),
+            crate::plumbing::options::worktree::SubCommands::Repair { paths } => prepare_and_run(
+                "worktree-repair",
+                trace,
+                verbose,
+                progress,
+                progress_keep_open,
+                None,
+                move |_progress, out, _err| core::repository::worktree::repair(repository(Mode::Lenient)?, out, &paths),
+            ),
         },
         Subcommands::IsClean | Subcommands::IsChanged => {
             let mode = if matches!(cmd, Subcommands::IsClean) {
@@ -885,6 +987,8 @@ pub fn main() -> Result<()> {
                     pack_cache_size_mb,
                     counting_threads,
                     object_cache_size_mb,
+                    window,
+                    depth,
                     output_directory,
                 } => {
                     let has_tips = !tips.is_empty();
@@ -901,6 +1005,7 @@ pub fn main() -> Result<()> {
                             let context = core::pack::create::Context {
                                 thread_limit,
                                 thin,
+                                delta_window_and_depth: window.map(|window| (window, depth)),
                                 nondeterministic_thread_count: nondeterministic_count.then_some(counting_threads),
                                 pack_cache_size_in_bytes: pack_cache_size_mb.unwrap_or(0) * 1_000_000,
                                 object_cache_size_in_bytes: object_cache_size_mb.unwrap_or(0) * 1_000_000,
@@ -1075,7 +1180,7 @@ pub fn main() -> Result<()> {
                                 core::pack::multi_index::verify(multi_index_path, progress, &should_interrupt)
                             },
                         ),
-                        free::pack::multi_index::Subcommands::Create { index_paths } => prepare_and_run(
+                        free::pack::multi_index::Subcommands::Create { index_paths, bitmap } => prepare_and_run(
                             "pack-multi-index-create",
                             trace,
                             verbose,
@@ -1089,6 +1194,7 @@ pub fn main() -> Result<()> {
                                     progress,
                                     &should_interrupt,
                                     object_hash,
+                                    bitmap,
                                 )
                             },
                         ),
@@ -1099,6 +1205,7 @@ pub fn main() -> Result<()> {
                         iteration_mode,
                         pack_path,
                         directory,
+                        bitmap,
                     } => prepare_and_run(
                         "pack-index-create",
                         trace,
@@ -1130,6 +1237,7 @@ pub fn main() -> Result<()> {
                                     out,
                                     object_hash,
                                     should_interrupt: &gix::interrupt::IS_INTERRUPTED,
+                                    write_bitmap: bitmap,
                                 },
                             )
                         },
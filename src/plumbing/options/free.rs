@@ -167,6 +167,16 @@ pub mod pack {
             /// Packs produced with this option enabled are only valid in transit, but not at rest.
             thin: bool,
 
+            #[clap(long)]
+            /// If set, objects which aren't yet in a pack are delta-compressed against this amount of similar objects.
+            ///
+            /// This takes more time but can produce much smaller packs. If unset, these objects are stored as they are.
+            window: Option<usize>,
+
+            #[clap(long, default_value_t = 50, requires = "window")]
+            /// The maximum length of delta chains created when `--window` is set.
+            depth: usize,
+
             /// The directory into which to write the pack file.
             #[clap(long, short = 'o')]
             output_directory: Option<PathBuf>,
@@ -316,6 +326,10 @@ pub mod pack {
                 /// Note for the multi-index to be useful, it should be side-by-side with the supplied `.idx` files.
                 #[clap(required = true)]
                 index_paths: Vec<PathBuf>,
+                /// Also write a reachability bitmap for all objects in the multi-pack index, with a bitmap for each
+                /// commit that isn't the parent of another one.
+                #[clap(long)]
+                bitmap: bool,
             },
         }
     }
@@ -358,6 +372,12 @@ pub mod pack {
                 ///
                 /// If unset, only informational output will be provided to standard output.
                 directory: Option<PathBuf>,
+                /// Also write a reachability bitmap for all objects in the pack, with a bitmap for each commit that isn't
+                /// the parent of another one.
+                ///
+                /// This requires an output directory, and all objects reachable from these commits to be in the pack.
+                #[clap(long)]
+                bitmap: bool,
             },
         }
     }
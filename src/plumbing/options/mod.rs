@@ -337,6 +337,12 @@ pub mod merge_base {
 }
 
 pub mod worktree {
+    use std::path::PathBuf;
+
+    use gix::bstr::BString;
+
+    use crate::shared::AsBString;
+
     #[derive(Debug, clap::Parser)]
     #[command(about = "Commands for handling worktrees")]
     pub struct Platform {
@@ -348,6 +354,74 @@ pub mod worktree {
     pub enum SubCommands {
         /// List all worktrees, along with some accompanying information.
         List,
+        /// Create a linked worktree at the given path and check out a commit into it.
+        Add {
+            /// Create a new branch with the given name and check it out.
+            #[clap(long, short = 'b', value_parser = AsBString)]
+            branch: Option<BString>,
+            /// Detach `HEAD` at the commit, even if it was named by a branch.
+            #[clap(long, short = 'd', conflicts_with = "branch")]
+            detach: bool,
+            /// Check out a branch even if it's checked out elsewhere, or reset the new branch if it exists.
+            #[clap(long, short = 'f')]
+            force: bool,
+            /// Lock the new worktree right away.
+            #[clap(long)]
+            lock: bool,
+            /// The reason for locking the new worktree.
+            #[clap(long, requires = "lock", value_parser = AsBString)]
+            reason: Option<BString>,
+            /// Don't check out any files.
+            #[clap(long)]
+            no_checkout: bool,
+            /// The location of the new worktree.
+            path: PathBuf,
+            /// The commit to check out, or a branch name. Defaults to a branch named after the last component of the path.
+            #[clap(value_parser = AsBString)]
+            commitish: Option<BString>,
+        },
+        /// Lock a linked worktree to prevent it from being pruned, moved or removed.
+        Lock {
+            /// The reason for locking the worktree.
+            #[clap(long, value_parser = AsBString)]
+            reason: Option<BString>,
+            /// The location or the id of the worktree.
+            worktree: PathBuf,
+        },
+        /// Unlock a linked worktree.
+        Unlock {
+            /// The location or the id of the worktree.
+            worktree: PathBuf,
+        },
+        /// Move a linked worktree to a new location.
+        Move {
+            /// Move the worktree even if it's locked.
+            #[clap(long, short = 'f')]
+            force: bool,
+            /// The location or the id of the worktree.
+            worktree: PathBuf,
+            /// The new location, or an existing directory to move the worktree into.
+            new_path: PathBuf,
+        },
+        /// Remove a linked worktree along with its checkout.
+        Remove {
+            /// Remove the worktree even if it has changes or untracked files. Pass it twice to remove locked worktrees as well.
+            #[clap(long, short = 'f', action = clap::ArgAction::Count)]
+            force: u8,
+            /// The location or the id of the worktree.
+            worktree: PathBuf,
+        },
+        /// Remove the administrative files of worktrees that don't exist anymore.
+        Prune {
+            /// Only print what would be pruned.
+            #[clap(long, short = 'n')]
+            dry_run: bool,
+        },
+        /// Repair the links between worktrees and the repository after they were moved manually.
+        Repair {
+            /// The new locations of worktrees that were moved.
+            paths: Vec<PathBuf>,
+        },
     }
 }
 
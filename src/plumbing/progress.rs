@@ -100,14 +100,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "core.looseCompression",
         usage: Planned("")
     },
-    Record {
-        config: "core.sparseCheckout",
-        usage: Planned("We want to support huge repos and be the fastest in doing so")
-    },
-    Record {
-        config: "core.sparseCheckoutCone",
-        usage: Planned("This is a nice improvement over 'sparseCheckout' alone and should one day be available too")
-    },
     Record {
         config: "core.gitProxy",
         usage: NotPlanned("The transport mechanism works differently enough to not support it for now, but of course it's possible to add support if there is demand")
@@ -136,10 +128,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "core.preloadIndex",
         usage: Planned("It's enabled by default and allows parallel stat checks - it's using a lot of CPU for just minor performance boosts though")
     },
-    Record {
-        config: "commitGraph.generationVersion",
-        usage: NotPlanned("Couldn't find a test that would require corrected generation numbers, even 'git' has no test for this.")
-    },
     Record {
         config: "commitGraph.maxNewFilters",
         usage: NotPlanned("Can be considered when the underlying feature is actually used or needed")
@@ -148,10 +136,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "commitGraph.readChangedPaths",
         usage: NotPlanned("Can be considered when the underlying feature is actually used or needed")
     },
-    Record {
-        config: "index.sparse",
-        usage: Planned("We can read sparse indices and support for it will be added early on")
-    },
     Record {
         config: "sparse.expectFilesOutsideOfPatterns",
         usage: Planned("A feature definitely worth having")
@@ -247,10 +231,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "fetch.pruneTags",
         usage: Planned("")
     },
-    Record {
-        config: "fetch.writeCommitGraph",
-        usage: Planned("")
-    },
     Record {
         config: "fetch.parallel",
         usage: Planned("")
@@ -272,34 +252,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "advice.updateSparsePath",
         usage: NotApplicable("gitoxide does not yet have an 'advice' system")
     },
-    Record {
-        config: "core.sparseCheckout",
-        usage: Planned("Together with 'index.sparse' and 'core.sparseCheckoutCone', configures if the index should be written sparse or not")
-    },
-    Record {
-        config: "core.sparseCheckoutCone",
-        usage: Planned("Non-cone mode is deprecated but should still fail gracefully if encountered")
-    },
-    Record {
-        config: "core.splitIndex",
-        usage: NotPlanned("We don't want to be able to create split indices, but we will read them. It's (somewhat) superseded by sparse indices")
-    },
-    Record {
-        config: "splitIndex.maxPercentageChange",
-        usage: NotPlanned("Seems like it's superseded by sparse indices")
-    },
-    Record {
-        config: "splitIndex.sharedIndexExpire",
-        usage: NotPlanned("Seems like it's superseded by sparse indices")
-    },
-    Record {
-        config: "index.sparse",
-        usage: Planned("Together with 'core.sparseCheckout' and 'core.sparseCheckoutCone', configures if the index should be written sparse or not")
-    },
-    Record {
-        config: "index.version",
-        usage: Planned("Once V4 indices can be written, we need to be able to set a desired version. For now we write the smallest possible index version only.")
-    },
     Record {
         config: "http.<url>.*",
         usage: Planned("Definitely needed for correctness, testing against baseline is a must")
@@ -408,14 +360,6 @@ static GIT_CONFIG: &[Record] = &[
         config: "sparse.expectFilesOutsideOfPatterns",
         usage: NotPlanned("TODO")
     },
-    Record {
-        config: "remote.<name>.promisor",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices")
-    },
-    Record {
-        config: "remote.<name>.partialCloneFilter",
-        usage: Planned("Required for big monorepos, and typically used in conjunction with sparse indices")
-    },
     Record {
         config: "merge.directoryRenames",
         usage: NotPlanned("On demand")
@@ -457,6 +457,17 @@ title "gix commit-graph"
             )
           )
         )
+        (with "the --bitmap flag"
+          (small-repo-in-sandbox
+            git repack -adq
+            PACK_FILE_WITH_ALL_OBJECTS="$(echo .git/objects/pack/*.pack)"
+            rm -f .git/objects/pack/*.idx .git/objects/pack/*.bitmap
+            it "writes a bitmap alongside the index which git can use" && {
+              expect_run $SUCCESSFULLY "$exe_plumbing" free pack index create --bitmap -p "$PACK_FILE_WITH_ALL_OBJECTS" .git/objects/pack
+              expect_run $SUCCESSFULLY git rev-list --test-bitmap main
+            }
+          )
+        )
         (with "'restore' iteration mode"
           (sandbox
             cp "${PACK_FILE}" .